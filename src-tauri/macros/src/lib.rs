@@ -0,0 +1,57 @@
+//! Attribute macros for the peppi backend
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse::Parser;
+use syn::{parse_macro_input, parse_quote, ItemFn, LitStr, ReturnType, Type};
+
+/// Time a function's body with crate::metrics. On a command, `#[timed]`
+/// records it under the function's name; on background work,
+/// `#[timed(job = "name")]` records a job. A run fails when the function
+/// returns a `Result` that's an `Err`; anything else counts as a success.
+///
+/// Goes under `#[tauri::command]`, which keeps the signature it sees
+#[proc_macro_attribute]
+pub fn timed(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut function = parse_macro_input!(item as ItemFn);
+
+    let mut job: Option<LitStr> = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("job") {
+            job = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("expected `job = \"name\"`"))
+        }
+    });
+    if let Err(e) = parser.parse(args) {
+        return e.to_compile_error().into();
+    }
+    let (kind, name) = match job {
+        Some(name) => (quote!(Job), name),
+        None => (quote!(Command), LitStr::new(&function.sig.ident.to_string(), function.sig.ident.span())),
+    };
+
+    let output: Type = match &function.sig.output {
+        ReturnType::Default => parse_quote!(()),
+        ReturnType::Type(_, ty) => (**ty).clone(),
+    };
+    let is_result = matches!(&output, Type::Path(ty) if ty.path.segments.last().is_some_and(|s| s.ident == "Result"));
+    let succeeded = if is_result { quote!(output.is_ok()) } else { quote!(true) };
+
+    let body = &function.block;
+    // Typed, so `?` in the body knows what it returns
+    let run = if function.sig.asyncness.is_some() {
+        quote!(crate::metrics::returning::<#output, _>(async move #body).await)
+    } else {
+        quote!((|| -> #output #body)())
+    };
+    function.block = parse_quote!({
+        let started = ::std::time::Instant::now();
+        #[allow(clippy::redundant_closure_call)]
+        let output = #run;
+        crate::metrics::report(crate::metrics::MetricKind::#kind, #name, started.elapsed(), #succeeded);
+        output
+    });
+    quote!(#function).into()
+}
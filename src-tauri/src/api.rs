@@ -0,0 +1,129 @@
+//! The versioned subset of the command API
+//!
+//! The frontend is built alongside the backend, but outside tools (the cloud
+//! dashboard, community overlays) call commands too. The payloads of the
+//! recordings, stats and clips commands are kept stable for them: any change
+//! to their shape bumps API_VERSION, and get_api_info describes them with a
+//! JSON schema so a tool can check what it's talking to.
+
+use crate::commands::clips::{ClipDeleted, ClipResult, ClipUpdated};
+use crate::commands::library::PaginatedRecordings;
+use crate::database::{AggregatedPlayerStats, PlayerStatsRow, StatsFilter};
+use crate::slippi::RecordingSession;
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Version of the stable payloads, bumped whenever one of them changes shape
+pub const API_VERSION: u32 = 1;
+
+/// What get_api_info returns
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiInfo {
+    pub api_version: u32,
+    /// JSON schema of each stable payload, by type name
+    pub schemas: BTreeMap<&'static str, RootSchema>,
+}
+
+pub fn api_info() -> ApiInfo {
+    ApiInfo { api_version: API_VERSION, schemas: stable_schemas() }
+}
+
+/// Inputs and outputs of the stable commands
+fn stable_schemas() -> BTreeMap<&'static str, RootSchema> {
+    BTreeMap::from([
+        // Recordings: get_recordings, get_recording, get_clips
+        ("PaginatedRecordings", schema_for!(PaginatedRecordings)),
+        ("RecordingSession", schema_for!(RecordingSession)),
+        // Stats: get_total_player_stats, get_player_stats
+        ("StatsFilter", schema_for!(StatsFilter)),
+        ("AggregatedPlayerStats", schema_for!(AggregatedPlayerStats)),
+        ("PlayerStatsRow", schema_for!(PlayerStatsRow)),
+        // Clips: process_clip_markers, rename_clip, set_clip_favorite and
+        // the clip events
+        ("ClipResult", schema_for!(ClipResult)),
+        ("ClipUpdated", schema_for!(ClipUpdated)),
+        ("ClipDeleted", schema_for!(ClipDeleted)),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    /// The shape of the stable payloads at each API version. Descriptions
+    /// aren't part of it, so rewording a doc comment doesn't need a bump
+    const SHAPES: &[(u32, &str)] = &[(1, include_str!("api/v1.shape"))];
+
+    /// One line per field (or per enum), "?" marking fields that may be left out
+    fn shape(schemas: &BTreeMap<&'static str, RootSchema>) -> String {
+        let mut types = BTreeMap::new();
+        for (name, root) in schemas {
+            let root = serde_json::to_value(root).unwrap();
+            if let Some(definitions) = root["definitions"].as_object() {
+                types.extend(definitions.iter().map(|(name, schema)| (name.clone(), schema.clone())));
+            }
+            types.insert(name.to_string(), root);
+        }
+
+        let mut lines = Vec::new();
+        for (name, schema) in &types {
+            let Some(properties) = schema["properties"].as_object() else {
+                lines.push(format!("{} = {}", name, summary(schema)));
+                continue;
+            };
+            let required: Vec<&str> = schema["required"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+            let mut fields: Vec<_> = properties.iter().collect();
+            fields.sort_by_key(|(field, _)| field.as_str());
+            for (field, schema) in fields {
+                let optional = if required.contains(&field.as_str()) { "" } else { "?" };
+                lines.push(format!("{}.{}{}: {}", name, field, optional, summary(schema)));
+            }
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// A type as written in a shape line, e.g. "string | null" or "array<PlayerInfo>"
+    fn summary(schema: &Value) -> String {
+        if let Some(reference) = schema["$ref"].as_str() {
+            return reference.rsplit('/').next().unwrap_or(reference).to_string();
+        }
+        if let Some(values) = schema["enum"].as_array() {
+            return values.iter().map(Value::to_string).collect::<Vec<_>>().join(" | ");
+        }
+        for key in ["allOf", "anyOf", "oneOf"] {
+            if let Some(alternatives) = schema[key].as_array() {
+                return alternatives.iter().map(summary).collect::<Vec<_>>().join(" | ");
+            }
+        }
+        let types: Vec<&str> = match &schema["type"] {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => vec!["any"],
+        };
+        types
+            .iter()
+            .map(|t| if *t == "array" { format!("array<{}>", summary(&schema["items"])) } else { t.to_string() })
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+
+    #[test]
+    fn test_stable_payloads_change_only_with_a_version_bump() {
+        let &(version, recorded) = SHAPES.last().unwrap();
+        assert_eq!(version, API_VERSION, "add api/v{}.shape for the new API version", API_VERSION);
+
+        // A checkout may have turned the snapshot's line endings into CRLF
+        let recorded = recorded.replace("\r\n", "\n");
+        let current = shape(&api_info().schemas);
+        assert!(
+            current == recorded,
+            "The stable payloads changed shape: bump API_VERSION and add api/v{}.shape with\n{}",
+            API_VERSION + 1,
+            current
+        );
+    }
+}
@@ -1,8 +1,26 @@
-use crate::database::Database;
+use crate::database::{Database, RecordingGame, StatsCache};
+use crate::deep_link::NavigateTarget;
+use crate::event_batch::EventBatcher;
+use crate::discord_presence::{DiscordPresence, PresenceSettings};
+use crate::game_detector::suspension::WatchSuspension;
 use crate::game_detector::GameDetector;
-use crate::recorder::Recorder;
+use crate::game_session::{GameSession, RecordingClaim};
+use crate::health::Health;
+use crate::instance_lock::InstanceLock;
+use crate::metrics::Metrics;
+use crate::overlay::{OverlayHub, OverlayServer};
+use crate::play_session::PlaySession;
+use crate::playback_review::PlaybackReview;
+use crate::pre_roll::PreRoll;
+use crate::recorder::{CaptureSettings, Recorder, RecordingQuality};
+use crate::recovery::RecoveryReport;
+use crate::scheduler::Scheduler;
+use crate::viewport::ViewportCrop;
+use crate::warm_start::WarmStart;
+use crate::work_gate::WorkGate;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
@@ -12,17 +30,81 @@ pub struct ClipMarker {
     pub timestamp_seconds: f64,
 }
 
+/// A recording that spans a set, while it's being recorded
+#[derive(Debug, Clone)]
+pub struct RecordingSet {
+    pub started_at: Instant,
+    /// Shared by the set's games (None until read, and for local games)
+    pub match_id: Option<String>,
+    pub games: Vec<RecordingGame>,
+}
+
 /// Global application state managed by Tauri
 pub struct AppState {
     pub game_detector: Mutex<Option<GameDetector>>,
+    /// Held by bulk library changes so the watcher doesn't take them for games
+    pub watch_suspension: WatchSuspension,
     pub recorder: Mutex<Option<Box<dyn Recorder + Send>>>,
+    /// What the current recording is captured with
+    pub capture_settings: Mutex<Option<CaptureSettings>>,
     pub settings: Mutex<HashMap<String, serde_json::Value>>,
     pub last_replay_path: Mutex<Option<String>>,
     pub current_recording_file: Mutex<Option<String>>,
-    pub last_file_modification: Mutex<Option<Instant>>,
+    /// Channel into the task following games, and the listeners feeding it
+    pub game_session: Mutex<GameSession>,
+    /// Session holding the auto-recording, so racing triggers start only one
+    pub recording_claim: RecordingClaim,
+    /// Games recorded so far, while recording a whole set
+    pub recording_set: Mutex<Option<RecordingSet>>,
+    /// Recording started before its game, see pre_roll
+    pub pre_roll: Mutex<Option<PreRoll>>,
+    /// Quality a recording rule picked for the new game's replay, until it's recorded
+    pub rule_quality: Mutex<Option<(String, RecordingQuality)>>,
     pub clip_markers: Mutex<Vec<ClipMarker>>,
+    /// Recordings to rename by the name template once their stats are saved
+    pub pending_renames: Mutex<HashSet<String>>,
+    /// Cancel flag for the dropped-frame monitor of the current recording
+    pub performance_monitor: Mutex<Option<Arc<AtomicBool>>>,
+    /// Where the game's picture is in the current recording, with autoCropGameViewport on
+    pub viewport_crop: Mutex<Option<ViewportCrop>>,
+    /// Live session summary for stream overlays (tracked even while the server is off)
+    pub overlay: Arc<OverlayHub>,
+    /// Local overlay HTTP server, if running
+    pub overlay_server: Mutex<Option<OverlayServer>>,
+    /// Games since watching began, for the live dashboard (None while not watching)
+    pub play_session: Mutex<Option<PlaySession>>,
+    /// Playback Dolphin the recording is being reviewed alongside, if any
+    pub playback_review: Mutex<Option<PlaybackReview>>,
+    /// Discord Rich Presence worker (disabled until settings are applied)
+    pub discord_presence: DiscordPresence,
+    /// Last buckwheat:// link target, until the frontend picks it up
+    pub pending_deep_link: Mutex<Option<NavigateTarget>>,
+    /// What recovery found after an unclean shutdown, until the frontend picks it up
+    pub pending_recovery: Mutex<Option<RecoveryReport>>,
+    /// Health of background subsystems (updated by the panic hook)
+    pub health: Arc<Health>,
+    /// Timings of commands and background jobs
+    pub metrics: Arc<Metrics>,
+    /// Whether the game window was open at the last check (to spot it appearing)
+    pub game_window_open: AtomicBool,
+    /// Recorder prepared ahead of the next recording
+    pub warm_start: WarmStart,
+    /// Name of the last applied settings profile (saved with each recording)
+    pub active_profile: Mutex<Option<String>>,
+    /// Whether retention or archiving is rewriting recordings (runs must not overlap)
+    pub rewriting_recordings: AtomicBool,
+    /// Lock held while this process watches for games or records
+    pub instance_lock: InstanceLock,
+    /// Holds background FFmpeg and stats work back while recording
+    pub work_gate: Arc<WorkGate>,
+    /// Maintenance jobs run in the background (see scheduler)
+    pub scheduler: Scheduler,
     /// SQLite database for persistent metadata cache
     pub database: Arc<Database>,
+    /// Aggregated stats recently computed (invalidate after writing stats rows)
+    pub stats_cache: StatsCache,
+    /// Events waiting to be emitted together (see event_batch)
+    pub event_batcher: EventBatcher,
 }
 
 impl AppState {
@@ -30,13 +112,40 @@ impl AppState {
     pub fn with_database(db: Database) -> Self {
         Self {
             game_detector: Mutex::new(None),
+            watch_suspension: WatchSuspension::default(),
             recorder: Mutex::new(None),
+            capture_settings: Mutex::new(None),
             settings: Mutex::new(HashMap::new()),
             last_replay_path: Mutex::new(None),
             current_recording_file: Mutex::new(None),
-            last_file_modification: Mutex::new(None),
+            game_session: Mutex::new(GameSession::default()),
+            recording_claim: RecordingClaim::default(),
+            recording_set: Mutex::new(None),
+            pre_roll: Mutex::new(None),
+            rule_quality: Mutex::new(None),
             clip_markers: Mutex::new(Vec::new()),
+            pending_renames: Mutex::new(HashSet::new()),
+            performance_monitor: Mutex::new(None),
+            viewport_crop: Mutex::new(None),
+            overlay: Arc::new(OverlayHub::new()),
+            overlay_server: Mutex::new(None),
+            play_session: Mutex::new(None),
+            playback_review: Mutex::new(None),
+            discord_presence: DiscordPresence::start(PresenceSettings::default()),
+            pending_deep_link: Mutex::new(None),
+            pending_recovery: Mutex::new(None),
+            health: Arc::new(Health::default()),
+            metrics: Arc::new(Metrics::default()),
+            game_window_open: AtomicBool::new(false),
+            warm_start: WarmStart::default(),
+            active_profile: Mutex::new(None),
+            rewriting_recordings: AtomicBool::new(false),
+            instance_lock: InstanceLock::default(),
+            work_gate: Arc::new(WorkGate::default()),
+            scheduler: Scheduler::default(),
             database: Arc::new(db),
+            stats_cache: StatsCache::default(),
+            event_batcher: EventBatcher::default(),
         }
     }
 }
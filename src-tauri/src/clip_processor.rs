@@ -1,8 +1,21 @@
 use crate::commands::errors::Error;
+use crate::metrics;
+use crate::paths;
+use crate::recorder::RecordingQuality;
+use crate::scheduler::{Cadence, Constraints, Job};
+use crate::timestamps;
+use crate::work_gate::ffmpeg_thread_args;
 use ffmpeg_sidecar::command::FfmpegCommand;
 use ffmpeg_sidecar::download::auto_download;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+
+/// Leave startup (the sync) alone before setting up FFmpeg
+const FFMPEG_JOB_DELAY: Duration = Duration::from_secs(10);
 
 /// Represents a crop region with position and dimensions
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,318 +29,1270 @@ pub struct CropRegion {
 /// Ensures FFmpeg is available, downloading if necessary
 pub fn ensure_ffmpeg() -> Result<(), Error> {
     auto_download()
-        .map_err(|e| Error::RecordingFailed(format!("Failed to download FFmpeg: {}", e)))?;
+        .map_err(|e| Error::ffmpeg(format!("Failed to download FFmpeg: {}", e), &[]))?;
     Ok(())
 }
 
+/// Download FFmpeg if it's missing and find the GPU decoder, once after
+/// startup (not while recording, the download competes with the capture)
+pub fn ffmpeg_job() -> Job {
+    Job {
+        name: "ffmpeg",
+        cadence: Cadence::Startup { delay: FFMPEG_JOB_DELAY },
+        constraints: Constraints { not_while_recording: true, ..Default::default() },
+        due: |_| true,
+        run: |_| {
+            Box::pin(async {
+                tauri::async_runtime::spawn_blocking(|| {
+                    ensure_ffmpeg()?;
+                    detect_hwaccel();
+                    Ok(())
+                })
+                .await
+                .map_err(|e| Error::ffmpeg(format!("FFmpeg setup stopped: {}", e), &[]))?
+            })
+        },
+    }
+}
+
+/// Hardware decoders to use, best first, if FFmpeg was built with them
+#[cfg(target_os = "windows")]
+const HWACCEL_CANDIDATES: &[&str] = &["d3d11va", "dxva2"];
+#[cfg(target_os = "macos")]
+const HWACCEL_CANDIDATES: &[&str] = &["videotoolbox"];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const HWACCEL_CANDIDATES: &[&str] = &["vaapi", "cuda"];
+
+/// Hardware decoder found at startup (None for software decoding)
+static HWACCEL: OnceLock<Option<&'static str>> = OnceLock::new();
+/// The hardwareDecode setting (on by default)
+static HWACCEL_ENABLED: AtomicBool = AtomicBool::new(true);
+/// Set once the hardware decoder failed to start where software decoding worked
+static HWACCEL_FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Whether thumbnails are decoded on the GPU, for diagnostics
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HwAccelStatus {
+    /// The hardwareDecode setting
+    pub enabled: bool,
+    /// Hardware decoder found at startup, None if there is none or it hasn't run yet
+    pub detected: Option<String>,
+    /// Whether it failed to start and software decoding is used instead
+    pub failed: bool,
+    /// What FFmpeg decodes with now ("software" or the decoder)
+    pub active: String,
+}
+
+/// Find the hardware decoder to use, once (later calls return the first result)
+pub fn detect_hwaccel() -> Option<&'static str> {
+    *HWACCEL.get_or_init(|| {
+        let mut command = std::process::Command::new(ffmpeg_sidecar::paths::ffmpeg_path());
+        command.args(["-hide_banner", "-hwaccels"]);
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+        let found = match command.output() {
+            Ok(output) if output.status.success() => pick_hwaccel(&String::from_utf8_lossy(&output.stdout)),
+            Ok(output) => {
+                log::warn!("FFmpeg couldn't list hardware decoders: {}", String::from_utf8_lossy(&output.stderr).trim());
+                None
+            }
+            Err(e) => {
+                log::warn!("Failed to run FFmpeg to list hardware decoders: {}", e);
+                None
+            }
+        };
+        log::info!("🎞️ Hardware decoding: {}", found.unwrap_or("none available, using software"));
+        found
+    })
+}
+
+/// The first candidate in FFmpeg's `-hwaccels` list
+fn pick_hwaccel(hwaccels: &str) -> Option<&'static str> {
+    let listed: Vec<&str> = hwaccels.lines().skip(1).map(str::trim).collect();
+    HWACCEL_CANDIDATES.iter().copied().find(|candidate| listed.contains(candidate))
+}
+
+/// Follow the hardwareDecode setting
+pub fn set_hardware_decode(enabled: bool) {
+    HWACCEL_ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+/// What decodes thumbnails and decode checks, without detecting anything
+pub fn hwaccel_status() -> HwAccelStatus {
+    let detected = HWACCEL.get().copied().flatten();
+    let enabled = HWACCEL_ENABLED.load(Ordering::SeqCst);
+    let failed = HWACCEL_FAILED.load(Ordering::SeqCst);
+    HwAccelStatus {
+        enabled,
+        detected: detected.map(str::to_string),
+        failed,
+        active: detected.filter(|_| enabled && !failed).unwrap_or("software").to_string(),
+    }
+}
+
+/// Hardware decoder to decode with now: enabled, detected and not failed
+/// (detected here if FFmpeg wasn't installed yet at startup)
+fn active_hwaccel() -> Option<&'static str> {
+    if !HWACCEL_ENABLED.load(Ordering::SeqCst) || HWACCEL_FAILED.load(Ordering::SeqCst) {
+        return None;
+    }
+    detect_hwaccel()
+}
+
+/// Run an FFmpeg decode with the hardware decoder, again in software if it
+/// exits with an error. `run` gets the arguments to give before the input
+fn with_hwaccel_fallback(what: &str, run: impl Fn(&[&str]) -> Result<(), Error>) -> Result<(), Error> {
+    let Some(hwaccel) = active_hwaccel() else {
+        return run(&[]);
+    };
+    match run(&["-hwaccel", hwaccel]) {
+        Ok(()) => Ok(()),
+        Err(hw_error) => {
+            log::warn!("{} failed with hardware decoding ({}), retrying in software: {}", what, hwaccel, hw_error);
+            run(&[])?;
+            // Software worked, so it's the decoder: stop using it this session
+            if !HWACCEL_FAILED.swap(true, Ordering::SeqCst) {
+                log::warn!("⚠️ Hardware decoding ({}) doesn't work here, using software decoding", hwaccel);
+            }
+            Ok(())
+        }
+    }
+}
+
 /// Extract a clip from a video file
 pub fn extract_clip(
-    input_path: &str,
-    output_path: &str,
+    input_path: impl AsRef<Path>,
+    output_path: impl AsRef<Path>,
     start_time: f64,
     duration: f64,
 ) -> Result<(), Error> {
-    log::info!(
-        "🎬 Extracting clip: input={}, output={}, start={}s, duration={}s",
-        input_path,
-        output_path,
-        start_time,
-        duration
-    );
-
-    // Ensure input file exists
-    if !Path::new(input_path).exists() {
-        return Err(Error::InvalidPath(format!(
-            "Input file does not exist: {}",
-            input_path
-        )));
-    }
+    let (input_path, output_path) = (input_path.as_ref(), output_path.as_ref());
+    metrics::job_sync("ffmpeg.extract_clip", || {
+        log::info!(
+            "🎬 Extracting clip: input={}, output={}, start={}s, duration={}s",
+            input_path.display(),
+            output_path.display(),
+            start_time,
+            duration
+        );
 
-    // Ensure output directory exists
-    if let Some(parent) = Path::new(output_path).parent() {
-        std::fs::create_dir_all(parent).map_err(|e| {
-            Error::RecordingFailed(format!("Failed to create output directory: {}", e))
-        })?;
-    }
+        // Ensure input file exists
+        if !input_path.exists() {
+            return Err(Error::InvalidPath(format!(
+                "Input file does not exist: {}",
+                input_path.display()
+            )));
+        }
 
-    // Build FFmpeg command
-    let result = FfmpegCommand::new()
-        .arg("-ss")
-        .arg(start_time.to_string())
-        .arg("-i")
-        .arg(input_path)
-        .arg("-t")
-        .arg(duration.to_string())
-        .arg("-c")
-        .arg("copy")
-        .arg("-avoid_negative_ts")
-        .arg("1")
-        .arg("-y") // Overwrite output file
-        .arg(output_path)
-        .spawn();
-
-    match result {
-        Ok(mut child) => {
-            let status = child
-                .wait()
-                .map_err(|e| Error::RecordingFailed(format!("FFmpeg process error: {}", e)))?;
+        // Ensure output directory exists
+        if let Some(parent) = output_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
 
-            if status.success() {
-                log::info!("✅ Clip extracted successfully: {}", output_path);
-                Ok(())
-            } else {
-                Err(Error::RecordingFailed(format!(
-                    "FFmpeg failed with status: {:?}",
-                    status
-                )))
+        // Build FFmpeg command
+        let result = FfmpegCommand::new()
+            .arg("-ss")
+            .arg(start_time.to_string())
+            .args(ffmpeg_thread_args())
+            .arg("-i")
+            .arg(paths::process_arg(input_path))
+            .args(ffmpeg_thread_args())
+            .arg("-t")
+            .arg(duration.to_string())
+            .arg("-c")
+            .arg("copy")
+            .arg("-avoid_negative_ts")
+            .arg("1")
+            .arg("-y") // Overwrite output file
+            .arg(paths::process_arg(output_path))
+            .spawn();
+
+        match result {
+            Ok(mut child) => {
+                let status = child
+                    .wait()
+                    .map_err(|e| Error::ffmpeg(format!("FFmpeg process error: {}", e), &[]))?;
+
+                if status.success() {
+                    log::info!("✅ Clip extracted successfully: {}", output_path.display());
+                    Ok(())
+                } else {
+                    Err(Error::ffmpeg(format!("FFmpeg failed with status: {:?}", status), &[]))
+                }
             }
+            Err(e) => Err(Error::ffmpeg(format!("Failed to spawn FFmpeg: {}", e), &[])),
         }
-        Err(e) => Err(Error::RecordingFailed(format!(
-            "Failed to spawn FFmpeg: {}",
-            e
-        ))),
-    }
+    })
 }
 
 /// Generate a thumbnail image from a video file
 /// Extracts a frame at the specified time (default: 1 second) and saves as JPEG
 pub fn generate_thumbnail(
-    video_path: &str,
-    thumbnail_path: &str,
+    video_path: impl AsRef<Path>,
+    thumbnail_path: impl AsRef<Path>,
     time_offset: Option<f64>,
 ) -> Result<(), Error> {
-    let offset = time_offset.unwrap_or(1.0); // Default to 1 second into video
-    
-    log::debug!(
-        "🖼️  Generating thumbnail: video={}, output={}, offset={}s",
-        video_path,
-        thumbnail_path,
-        offset
-    );
-
-    // Ensure input file exists
-    if !Path::new(video_path).exists() {
-        return Err(Error::InvalidPath(format!(
-            "Video file does not exist: {}",
-            video_path
-        )));
+    let (video_path, thumbnail_path) = (video_path.as_ref(), thumbnail_path.as_ref());
+    metrics::job_sync("ffmpeg.generate_thumbnail", || {
+        let offset = time_offset.unwrap_or(1.0); // Default to 1 second into video
+
+        log::debug!(
+            "🖼️  Generating thumbnail: video={}, output={}, offset={}s",
+            video_path.display(),
+            thumbnail_path.display(),
+            offset
+        );
+
+        // Ensure input file exists
+        if !video_path.exists() {
+            return Err(Error::InvalidPath(format!(
+                "Video file does not exist: {}",
+                video_path.display()
+            )));
+        }
+
+        // Ensure output directory exists
+        if let Some(parent) = thumbnail_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Build FFmpeg command to extract frame as JPEG
+        // -ss: seek to time offset
+        // -i: input file
+        // -vframes 1: extract only 1 frame
+        // -vf scale=320:-1: scale to 320px width, maintain aspect ratio
+        // -q:v 2: high quality JPEG (lower = better quality, 2-5 is good)
+        // -hwaccel: decode on the GPU when it can (4K software decoding is slow)
+        with_hwaccel_fallback("Thumbnail", |hwaccel| {
+            let result = FfmpegCommand::new()
+                .args(hwaccel)
+                .arg("-ss")
+                .arg(offset.to_string())
+                .args(ffmpeg_thread_args())
+                .arg("-i")
+                .arg(paths::process_arg(video_path))
+                .args(ffmpeg_thread_args())
+                .arg("-vframes")
+                .arg("1")
+                .arg("-vf")
+                .arg("scale=320:-1")
+                .arg("-q:v")
+                .arg("2")
+                .arg("-y") // Overwrite output file
+                .arg(paths::process_arg(thumbnail_path))
+                .spawn();
+
+            match result {
+                Ok(mut child) => {
+                    let status = child
+                        .wait()
+                        .map_err(|e| Error::ffmpeg(format!("FFmpeg process error: {}", e), &[]))?;
+
+                    if status.success() {
+                        log::debug!("✅ Thumbnail generated successfully: {}", thumbnail_path.display());
+                        Ok(())
+                    } else {
+                        Err(Error::ffmpeg(format!("FFmpeg failed with status: {:?}", status), &[]))
+                    }
+                }
+                Err(e) => Err(Error::ffmpeg(format!("Failed to spawn FFmpeg: {}", e), &[])),
+            }
+        })
+    })
+}
+
+/// What ffprobe reports about a media file (see media_info)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaInfo {
+    /// ffprobe's format name, e.g. "mov,mp4,m4a,3gp,3g2,mj2" or "matroska,webm"
+    pub container: String,
+    /// Container duration (0 if unknown)
+    pub duration_secs: f64,
+    /// Of the first video stream, None without one
+    pub video_codec: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Average frame rate, None if unknown
+    pub fps: Option<f64>,
+    /// Overall bitrate in bits per second, None if unknown
+    pub bitrate: Option<u64>,
+    /// Of the first audio stream, None without one
+    pub audio_codec: Option<String>,
+    pub audio_channels: Option<u32>,
+    pub has_audio: bool,
+}
+
+/// ffprobe's `-show_format -show_streams` JSON, as far as MediaInfo needs it
+#[derive(Debug, Deserialize)]
+struct ProbeOutput {
+    #[serde(default)]
+    streams: Vec<ProbeStream>,
+    format: Option<ProbeFormat>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeStream {
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    avg_frame_rate: Option<String>,
+    channels: Option<u32>,
+}
+
+/// ffprobe gives numbers in the format section as strings
+#[derive(Debug, Deserialize)]
+struct ProbeFormat {
+    format_name: Option<String>,
+    duration: Option<String>,
+    bit_rate: Option<String>,
+}
+
+/// Most files kept in the media info cache before it's emptied
+const MEDIA_INFO_CACHE_SIZE: usize = 4096;
+
+/// Media info by path, with the modification time and size it was read at
+type MediaInfoCache = HashMap<PathBuf, (SystemTime, u64, MediaInfo)>;
+static MEDIA_INFO_CACHE: OnceLock<Mutex<MediaInfoCache>> = OnceLock::new();
+
+/// Read a media file's container, streams, length and bitrate with ffprobe.
+/// Results are cached until the file's modification time or size changes.
+/// Files ffprobe can't read (corrupt, or not media) are Error::MediaUnreadable
+pub fn media_info(path: impl AsRef<Path>) -> Result<MediaInfo, Error> {
+    let path = path.as_ref();
+    let metadata = std::fs::metadata(path)?;
+    let stamp = (metadata.modified()?, metadata.len());
+    let cache = MEDIA_INFO_CACHE.get_or_init(Default::default);
+    if let Some((modified, len, info)) = cache.lock().ok().as_ref().and_then(|cache| cache.get(path)) {
+        if (*modified, *len) == stamp {
+            return Ok(info.clone());
+        }
     }
 
-    // Ensure output directory exists
-    if let Some(parent) = Path::new(thumbnail_path).parent() {
-        std::fs::create_dir_all(parent).map_err(|e| {
-            Error::RecordingFailed(format!("Failed to create thumbnail directory: {}", e))
-        })?;
+    let info = metrics::job_sync("ffmpeg.media_info", || {
+        let mut command = std::process::Command::new(ffmpeg_sidecar::ffprobe::ffprobe_path());
+        command.args(["-v", "error", "-show_format", "-show_streams", "-of", "json"]);
+        command.arg(paths::process_arg(path));
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        let output = command
+            .output()
+            .map_err(|e| Error::ffmpeg(format!("Failed to run ffprobe: {}", e), &[]))?;
+        let unreadable = |reason: String| Error::MediaUnreadable { path: path.display().to_string(), reason };
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("ffprobe failed");
+            return Err(unreadable(reason.trim().to_string()));
+        }
+        parse_media_info(&output.stdout).map_err(unreadable)
+    })?;
+
+    if let Ok(mut cache) = cache.lock() {
+        if cache.len() >= MEDIA_INFO_CACHE_SIZE {
+            cache.clear();
+        }
+        cache.insert(path.to_path_buf(), (stamp.0, stamp.1, info.clone()));
     }
+    Ok(info)
+}
 
-    // Build FFmpeg command to extract frame as JPEG
-    // -ss: seek to time offset
-    // -i: input file
-    // -vframes 1: extract only 1 frame
-    // -vf scale=320:-1: scale to 320px width, maintain aspect ratio
-    // -q:v 2: high quality JPEG (lower = better quality, 2-5 is good)
-    let result = FfmpegCommand::new()
-        .arg("-ss")
-        .arg(offset.to_string())
-        .arg("-i")
-        .arg(video_path)
-        .arg("-vframes")
-        .arg("1")
-        .arg("-vf")
-        .arg("scale=320:-1")
-        .arg("-q:v")
-        .arg("2")
-        .arg("-y") // Overwrite output file
-        .arg(thumbnail_path)
-        .spawn();
-
-    match result {
-        Ok(mut child) => {
-            let status = child
-                .wait()
-                .map_err(|e| Error::RecordingFailed(format!("FFmpeg process error: {}", e)))?;
+fn parse_media_info(json: &[u8]) -> Result<MediaInfo, String> {
+    let probe: ProbeOutput =
+        serde_json::from_slice(json).map_err(|e| format!("ffprobe's output couldn't be read: {}", e))?;
+    let format = probe.format.ok_or("No container was recognized")?;
+    let stream = |kind: &str| probe.streams.iter().find(|s| s.codec_type.as_deref() == Some(kind));
+    let (video, audio) = (stream("video"), stream("audio"));
+    if video.is_none() && audio.is_none() {
+        return Err("The file has no video or audio".to_string());
+    }
+    Ok(MediaInfo {
+        container: format.format_name.unwrap_or_default(),
+        duration_secs: format.duration.and_then(|d| d.parse().ok()).unwrap_or(0.0),
+        video_codec: video.and_then(|v| v.codec_name.clone()),
+        width: video.and_then(|v| v.width),
+        height: video.and_then(|v| v.height),
+        fps: video.and_then(|v| v.avg_frame_rate.as_deref()).and_then(parse_frame_rate),
+        bitrate: format.bit_rate.and_then(|b| b.parse().ok()),
+        audio_codec: audio.and_then(|a| a.codec_name.clone()),
+        audio_channels: audio.and_then(|a| a.channels),
+        has_audio: audio.is_some(),
+    })
+}
 
-            if status.success() {
-                log::debug!("✅ Thumbnail generated successfully: {}", thumbnail_path);
-                Ok(())
-            } else {
-                Err(Error::RecordingFailed(format!(
-                    "FFmpeg failed with status: {:?}",
-                    status
-                )))
-            }
+/// "60000/1001" as frames per second; "0/0" (unknown) as None
+fn parse_frame_rate(rate: &str) -> Option<f64> {
+    let (num, den) = rate.split_once('/')?;
+    let (num, den): (f64, f64) = (num.parse().ok()?, den.parse().ok()?);
+    (num > 0.0 && den > 0.0).then(|| num / den)
+}
+
+/// Dimensions and length of a video, as reported by ffprobe
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VideoProbe {
+    pub width: u32,
+    pub height: u32,
+    pub duration_secs: f64,
+}
+
+/// The first video stream's dimensions and the container duration (see media_info)
+pub fn probe_video(video_path: impl AsRef<Path>) -> Result<VideoProbe, Error> {
+    let info = media_info(video_path)?;
+    match (info.width, info.height) {
+        (Some(width), Some(height)) => Ok(VideoProbe { width, height, duration_secs: info.duration_secs }),
+        _ => Err(Error::RecordingFailed("The recording has no video stream".to_string())),
+    }
+}
+
+/// Whether a video has sound, from a short sample of its first audio stream
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioProbe {
+    pub has_audio: bool,
+    /// Mean volume of the sample in dB (about -91 for digital silence)
+    pub mean_volume_db: Option<f64>,
+}
+
+/// Measure the volume of `sample_secs` from the middle of a video with
+/// FFmpeg's volumedetect filter
+pub fn probe_audio(video_path: impl AsRef<Path>, duration_secs: f64, sample_secs: f64) -> Result<AudioProbe, Error> {
+    let video_path = video_path.as_ref();
+    metrics::job_sync("ffmpeg.probe_audio", || {
+        if !media_info(video_path)?.has_audio {
+            return Ok(AudioProbe { has_audio: false, mean_volume_db: None });
+        }
+
+        let start = ((duration_secs - sample_secs) / 2.0).max(0.0);
+        let mut command = std::process::Command::new(ffmpeg_sidecar::paths::ffmpeg_path());
+        command.args(["-hide_banner", "-nostats", "-ss", &format!("{:.3}", start), "-t", &format!("{:.3}", sample_secs)]);
+        command.arg("-i").arg(paths::process_arg(video_path));
+        command.args(["-map", "0:a:0", "-af", "volumedetect", "-f", "null", "-"]);
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+        let output = command
+            .output()
+            .map_err(|e| Error::ffmpeg(format!("Failed to run FFmpeg: {}", e), &[]))?;
+        // volumedetect reports on stderr: "[Parsed_volumedetect_0 @ ...] mean_volume: -23.4 dB"
+        let mean_volume_db = String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .find_map(|line| line.split("mean_volume:").nth(1)?.trim().trim_end_matches("dB").trim().parse::<f64>().ok())
+            // Pure silence can be reported as -inf
+            .map(|db| db.max(-91.0));
+        if !output.status.success() || mean_volume_db.is_none() {
+            return Err(Error::ffmpeg("FFmpeg couldn't measure the volume", &output.stderr));
         }
-        Err(e) => Err(Error::RecordingFailed(format!(
-            "Failed to spawn FFmpeg: {}",
-            e
-        ))),
+        Ok(AudioProbe { has_audio: true, mean_volume_db })
+    })
+}
+
+/// How far a crop may be moved or shrunk to fit the video before it's refused
+const CROP_TOLERANCE_PX: u32 = 4;
+
+/// Fit a crop into a `video_width`x`video_height` frame: clamped to its edges,
+/// with even dimensions (odd ones break some encoders). A crop that would
+/// change by more than CROP_TOLERANCE_PX to fit is refused instead
+fn fit_crop(crop: &CropRegion, video_width: u32, video_height: u32) -> Result<CropRegion, Error> {
+    // (offset, size) along one axis, or None if it can't fit closely enough
+    let fit_axis = |offset: u32, size: u32, frame: u32| {
+        let end = (u64::from(offset) + u64::from(size)).min(u64::from(frame)) as u32;
+        let fitted_offset = offset.min(end);
+        let fitted_size = (end - fitted_offset) & !1;
+        let close = offset - fitted_offset <= CROP_TOLERANCE_PX && size - fitted_size <= CROP_TOLERANCE_PX;
+        (close && fitted_size > 0).then_some((fitted_offset, fitted_size))
+    };
+    match (fit_axis(crop.x, crop.width, video_width), fit_axis(crop.y, crop.height, video_height)) {
+        (Some((x, width)), Some((y, height))) => Ok(CropRegion { x, y, width, height }),
+        _ => Err(Error::CropOutOfBounds {
+            x: crop.x,
+            y: crop.y,
+            width: crop.width,
+            height: crop.height,
+            video_width,
+            video_height,
+        }),
+    }
+}
+
+/// Fit a crop to the video at `input_path`, probing its dimensions
+pub fn fit_crop_to_video(input_path: &Path, crop: &CropRegion) -> Result<CropRegion, Error> {
+    let info = media_info(input_path)?;
+    let Some((video_width, video_height)) = info.width.zip(info.height) else {
+        return Err(Error::MediaUnreadable {
+            path: input_path.display().to_string(),
+            reason: "No video stream".to_string(),
+        });
+    };
+    let fitted = fit_crop(crop, video_width, video_height)?;
+    if (fitted.x, fitted.y, fitted.width, fitted.height) != (crop.x, crop.y, crop.width, crop.height) {
+        log::info!(
+            "Crop {}x{}+{}+{} fitted to {}x{}+{}+{} for the {}x{} video",
+            crop.width, crop.height, crop.x, crop.y,
+            fitted.width, fitted.height, fitted.x, fitted.y,
+            video_width, video_height
+        );
     }
+    Ok(fitted)
 }
 
 /// Crop a video to a specified region
 /// Uses FFmpeg's crop filter: crop=width:height:x:y
+/// The region is fitted to the video first (see fit_crop)
 pub fn crop_video(
-    input_path: &str,
-    output_path: &str,
+    input_path: impl AsRef<Path>,
+    output_path: impl AsRef<Path>,
     crop: &CropRegion,
 ) -> Result<(), Error> {
-    log::info!(
-        "✂️ Cropping video: input={}, output={}, crop={}x{}+{}+{}",
-        input_path,
-        output_path,
-        crop.width,
-        crop.height,
-        crop.x,
-        crop.y
-    );
-
-    // Ensure input file exists
-    if !Path::new(input_path).exists() {
-        return Err(Error::InvalidPath(format!(
-            "Input file does not exist: {}",
-            input_path
-        )));
-    }
+    let (input_path, output_path) = (input_path.as_ref(), output_path.as_ref());
+    metrics::job_sync("ffmpeg.crop_video", || {
+        log::info!(
+            "✂️ Cropping video: input={}, output={}, crop={}x{}+{}+{}",
+            input_path.display(),
+            output_path.display(),
+            crop.width,
+            crop.height,
+            crop.x,
+            crop.y
+        );
 
-    // Ensure output directory exists
-    if let Some(parent) = Path::new(output_path).parent() {
-        std::fs::create_dir_all(parent).map_err(|e| {
-            Error::RecordingFailed(format!("Failed to create output directory: {}", e))
-        })?;
-    }
+        // Ensure input file exists
+        if !input_path.exists() {
+            return Err(Error::InvalidPath(format!(
+                "Input file does not exist: {}",
+                input_path.display()
+            )));
+        }
 
-    // Build crop filter string: crop=width:height:x:y
-    let crop_filter = format!("crop={}:{}:{}:{}", crop.width, crop.height, crop.x, crop.y);
-
-    // Build FFmpeg command with crop filter
-    let result = FfmpegCommand::new()
-        .arg("-i")
-        .arg(input_path)
-        .arg("-vf")
-        .arg(&crop_filter)
-        .arg("-c:a")
-        .arg("copy") // Copy audio without re-encoding
-        .arg("-y") // Overwrite output file
-        .arg(output_path)
-        .spawn();
-
-    match result {
-        Ok(mut child) => {
-            let status = child
-                .wait()
-                .map_err(|e| Error::RecordingFailed(format!("FFmpeg process error: {}", e)))?;
+        // Ensure output directory exists
+        if let Some(parent) = output_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
 
-            if status.success() {
-                log::info!("✅ Video cropped successfully: {}", output_path);
-                Ok(())
-            } else {
-                Err(Error::RecordingFailed(format!(
-                    "FFmpeg crop failed with status: {:?}",
-                    status
-                )))
+        let crop = &fit_crop_to_video(input_path, crop)?;
+
+        // Build crop filter string: crop=width:height:x:y
+        let crop_filter = format!("crop={}:{}:{}:{}", crop.width, crop.height, crop.x, crop.y);
+
+        // Build FFmpeg command with crop filter
+        let result = FfmpegCommand::new()
+            .arg("-i")
+            .arg(paths::process_arg(input_path))
+            .arg("-vf")
+            .arg(&crop_filter)
+            .arg("-c:a")
+            .arg("copy") // Copy audio without re-encoding
+            .arg("-y") // Overwrite output file
+            .arg(paths::process_arg(output_path))
+            .spawn();
+
+        match result {
+            Ok(mut child) => {
+                let status = child
+                    .wait()
+                    .map_err(|e| Error::ffmpeg(format!("FFmpeg process error: {}", e), &[]))?;
+
+                if status.success() {
+                    log::info!("✅ Video cropped successfully: {}", output_path.display());
+                    Ok(())
+                } else {
+                    Err(Error::ffmpeg(format!("FFmpeg crop failed with status: {:?}", status), &[]))
+                }
             }
+            Err(e) => Err(Error::ffmpeg(format!("Failed to spawn FFmpeg for crop: {}", e), &[])),
         }
-        Err(e) => Err(Error::RecordingFailed(format!(
-            "Failed to spawn FFmpeg for crop: {}",
-            e
-        ))),
-    }
+    })
+}
+
+/// Frames drawn over an edit: raw RGBA, `width` x `height`, `fps` a second
+/// from the edit's start (see library::input_overlay)
+pub struct OverlayStream {
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    pub frames: Box<dyn Iterator<Item = Vec<u8>> + Send>,
 }
 
 /// Process video with combined trim and/or crop operations in a single FFmpeg pass
-/// This is more efficient than running separate trim and crop operations
+/// This is more efficient than running separate trim and crop operations.
+/// `overlay` is drawn into the bottom right corner (the video is re-encoded).
+/// `on_progress` gets how many seconds of the output are written so far
 pub fn process_video_edit(
-    input_path: &str,
-    output_path: &str,
+    input_path: impl AsRef<Path>,
+    output_path: impl AsRef<Path>,
     trim_start: Option<f64>,
     trim_end: Option<f64>,
     crop: Option<CropRegion>,
+    overlay: Option<OverlayStream>,
+    mut on_progress: impl FnMut(f64),
 ) -> Result<(), Error> {
-    log::info!(
-        "🎬 Processing video edit: input={}, output={}, trim_start={:?}, trim_end={:?}, crop={:?}",
-        input_path,
-        output_path,
-        trim_start,
-        trim_end,
-        crop
-    );
-
-    // Ensure input file exists
-    if !Path::new(input_path).exists() {
+    let (input_path, output_path) = (input_path.as_ref(), output_path.as_ref());
+    metrics::job_sync("ffmpeg.process_video_edit", || {
+        log::info!(
+            "🎬 Processing video edit: input={}, output={}, trim_start={:?}, trim_end={:?}, crop={:?}",
+            input_path.display(),
+            output_path.display(),
+            trim_start,
+            trim_end,
+            crop
+        );
+
+        // Ensure input file exists
+        if !input_path.exists() {
+            return Err(Error::InvalidPath(format!(
+                "Input file does not exist: {}",
+                input_path.display()
+            )));
+        }
+
+        // Ensure output directory exists
+        if let Some(parent) = output_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut cmd = std::process::Command::new(ffmpeg_sidecar::paths::ffmpeg_path());
+        // Progress as key=value lines on stdout, only errors on stderr
+        cmd.args(["-v", "error", "-nostats", "-progress", "pipe:1"]);
+
+        // Add trim start if specified (seeking before input is faster)
+        if let Some(start) = trim_start {
+            cmd.arg("-ss").arg(start.to_string());
+        }
+
+        // Input file (one thread each way while recording)
+        cmd.args(ffmpeg_thread_args()).arg("-i").arg(paths::process_arg(input_path)).args(ffmpeg_thread_args());
+
+        // The overlay's frames come in through stdin
+        if let Some(overlay) = &overlay {
+            cmd.args(["-f", "rawvideo", "-pix_fmt", "rgba"])
+                .args(["-s", &format!("{}x{}", overlay.width, overlay.height)])
+                .args(["-framerate", &overlay.fps.to_string()])
+                .args(["-i", "pipe:0"]);
+        }
+
+        // Add trim end if specified
+        if let Some(end) = trim_end {
+            let duration = if let Some(start) = trim_start {
+                end - start
+            } else {
+                end
+            };
+            cmd.arg("-t").arg(duration.to_string());
+        }
+
+        let crop_filter = crop.as_ref().map(|crop_region| {
+            format!("crop={}:{}:{}:{}", crop_region.width, crop_region.height, crop_region.x, crop_region.y)
+        });
+        if let Some(overlay) = &overlay {
+            // Drawn in the bottom right corner, over the cropped video
+            let base = crop_filter.map(|crop| format!("[0:v]{}[base];[base]", crop)).unwrap_or_else(|| "[0:v]".to_string());
+            let margin = overlay.height / 8;
+            cmd.arg("-filter_complex")
+                .arg(format!("{}[1:v]overlay=W-w-{}:H-h-{}:eof_action=pass[v]", base, margin, margin))
+                .args(["-map", "[v]", "-map", "0:a?", "-c:a", "copy"]);
+        } else if let Some(crop_filter) = crop_filter {
+            cmd.arg("-vf").arg(&crop_filter);
+            // When using video filter, we need to re-encode video
+            cmd.arg("-c:a").arg("copy"); // But copy audio
+        } else {
+            // No crop, can use stream copy for both video and audio (fastest)
+            cmd.arg("-c").arg("copy");
+        }
+
+        // Avoid negative timestamps issue
+        cmd.arg("-avoid_negative_ts").arg("1");
+
+        // Overwrite output file
+        cmd.arg("-y").arg(paths::process_arg(output_path));
+        cmd.stdout(std::process::Stdio::piped()).stderr(std::process::Stdio::piped());
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+            cmd.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        if overlay.is_some() {
+            cmd.stdin(std::process::Stdio::piped());
+        }
+
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| Error::ffmpeg(format!("Failed to spawn FFmpeg for edit: {}", e), &[]))?;
+        // Fed from another thread, so FFmpeg's progress is read meanwhile
+        let feeder = overlay.zip(child.stdin.take()).map(|(overlay, mut stdin)| {
+            std::thread::spawn(move || {
+                for frame in overlay.frames {
+                    // FFmpeg stops reading once the clip is long enough
+                    if std::io::Write::write_all(&mut stdin, &frame).is_err() {
+                        break;
+                    }
+                }
+            })
+        });
+        if let Some(stdout) = child.stdout.take() {
+            for line in std::io::BufRead::lines(std::io::BufReader::new(stdout)).map_while(Result::ok) {
+                if let Some(secs) = progress_secs(&line) {
+                    on_progress(secs);
+                }
+            }
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|e| Error::ffmpeg(format!("FFmpeg process error: {}", e), &[]))?;
+        if let Some(feeder) = feeder {
+            let _ = feeder.join();
+        }
+
+        if output.status.success() {
+            log::info!("✅ Video edit processed successfully: {}", output_path.display());
+            Ok(())
+        } else {
+            Err(Error::ffmpeg(format!("FFmpeg edit failed with status: {:?}", output.status), &output.stderr))
+        }
+    })
+}
+
+/// Seconds of output written, from one line of FFmpeg's `-progress` output
+/// (out_time_ms is in microseconds too, despite its name)
+fn progress_secs(line: &str) -> Option<f64> {
+    let (key, value) = line.split_once('=')?;
+    match key {
+        "out_time_us" | "out_time_ms" => value.trim().parse::<i64>().ok().map(|us| us.max(0) as f64 / 1_000_000.0),
+        _ => None,
+    }
+}
+
+/// Check a trim and crop against the probed video: the range must be inside
+/// it and the crop region within its frame
+pub fn validate_edit(
+    probe: &VideoProbe,
+    trim_start: Option<f64>,
+    trim_end: Option<f64>,
+    crop: Option<&CropRegion>,
+) -> Result<(), Error> {
+    let start = trim_start.unwrap_or(0.0);
+    let end = trim_end.unwrap_or(probe.duration_secs);
+    if start < 0.0 || end <= start {
+        return Err(Error::InvalidPath(format!("Invalid trim range {:.2}s to {:.2}s", start, end)));
+    }
+    // Containers round differently
+    if probe.duration_secs > 0.0 && start >= probe.duration_secs {
         return Err(Error::InvalidPath(format!(
-            "Input file does not exist: {}",
-            input_path
+            "Trim starts at {:.2}s but the video is {:.2}s long",
+            start, probe.duration_secs
         )));
     }
-
-    // Ensure output directory exists
-    if let Some(parent) = Path::new(output_path).parent() {
-        std::fs::create_dir_all(parent).map_err(|e| {
-            Error::RecordingFailed(format!("Failed to create output directory: {}", e))
-        })?;
+    if let Some(crop) = crop {
+        let fits = crop.width > 0
+            && crop.height > 0
+            && crop.x.checked_add(crop.width).is_some_and(|right| right <= probe.width)
+            && crop.y.checked_add(crop.height).is_some_and(|bottom| bottom <= probe.height);
+        if !fits {
+            return Err(Error::CropOutOfBounds {
+                x: crop.x,
+                y: crop.y,
+                width: crop.width,
+                height: crop.height,
+                video_width: probe.width,
+                video_height: probe.height,
+            });
+        }
     }
+    Ok(())
+}
+
+/// Codec for re-encoded videos
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoCodec {
+    H264,
+    /// Smaller at the same quality, but slower to encode and decode
+    Hevc,
+}
+
+/// Re-encode a video to a target bitrate with a two-pass encode, so the
+/// output lands close to `video_bitrate * duration` bytes whatever the content.
+/// `dimensions` scales the video (None keeps the source size)
+pub fn compress_video(
+    input_path: impl AsRef<Path>,
+    output_path: impl AsRef<Path>,
+    codec: VideoCodec,
+    video_bitrate: u32,
+    audio_bitrate: u32,
+    dimensions: Option<(u32, u32)>,
+) -> Result<(), Error> {
+    let (input_path, output_path) = (input_path.as_ref(), output_path.as_ref());
+    metrics::job_sync("ffmpeg.compress_video", || {
+        log::info!(
+            "🗜️ Compressing video: input={}, output={}, codec={:?}, bitrate={}bps, size={:?}",
+            input_path.display(),
+            output_path.display(),
+            codec,
+            video_bitrate,
+            dimensions
+        );
+
+        if !input_path.exists() {
+            return Err(Error::InvalidPath(format!(
+                "Input file does not exist: {}",
+                input_path.display()
+            )));
+        }
+
+        // Pass logs go to the temp dir; unique per run so concurrent encodes don't collide
+        let passlog = std::env::temp_dir().join(format!("peppi-2pass-{}", uuid::Uuid::new_v4()));
+        let passlog = passlog.to_string_lossy().to_string();
+        let null_output = if cfg!(target_os = "windows") { "NUL" } else { "/dev/null" };
+
+        let result = (1..=2).try_for_each(|pass| {
+            let mut cmd = FfmpegCommand::new();
+            // Keep output short: the pipes aren't read, and a full pipe stalls FFmpeg
+            cmd.args(["-loglevel", "error", "-nostats"]);
+            cmd.args(ffmpeg_thread_args()).arg("-i").arg(paths::process_arg(input_path)).args(ffmpeg_thread_args());
+            if let Some((width, height)) = dimensions {
+                cmd.arg("-vf").arg(format!("scale={}:{}", width, height));
+            }
+            match codec {
+                VideoCodec::H264 => {
+                    cmd.args(["-c:v", "libx264", "-preset", "medium"])
+                        .args(["-pass", &pass.to_string(), "-passlogfile", &passlog]);
+                }
+                VideoCodec::Hevc => {
+                    // x265 takes its pass settings itself; hvc1 lets Windows and macOS players open MP4s
+                    cmd.args(["-c:v", "libx265", "-preset", "medium"]);
+                    if is_mp4(output_path) {
+                        cmd.args(["-tag:v", "hvc1"]);
+                    }
+                    cmd.arg("-x265-params")
+                        .arg(format!("pass={}:stats={}.x265:log-level=error", pass, passlog));
+                }
+            }
+            cmd.arg("-b:v").arg(video_bitrate.to_string());
+            if pass == 1 {
+                cmd.args(["-an", "-f", "null", "-y", null_output]);
+            } else {
+                cmd.args(["-c:a", "aac", "-b:a", &audio_bitrate.to_string()]);
+                if is_mp4(output_path) {
+                    cmd.args(["-movflags", "+faststart"]);
+                }
+                cmd.arg("-y").arg(paths::process_arg(output_path));
+            }
+
+            let status = cmd
+                .spawn()
+                .map_err(|e| Error::ffmpeg(format!("Failed to spawn FFmpeg for compression: {}", e), &[]))?
+                .wait()
+                .map_err(|e| Error::ffmpeg(format!("FFmpeg process error: {}", e), &[]))?;
+            if status.success() {
+                Ok(())
+            } else {
+                Err(Error::ffmpeg(format!("FFmpeg compression pass {} failed with status: {:?}", pass, status), &[]))
+            }
+        });
+
+        // x264 writes <passlog>-0.log(.mbtree), x265 <passlog>.x265(.cutree)
+        for suffix in ["-0.log", "-0.log.mbtree", "-0.log.temp", "-0.log.mbtree.temp", ".x265", ".x265.cutree", ".x265.temp", ".x265.cutree.temp"] {
+            let _ = std::fs::remove_file(format!("{}{}", passlog, suffix));
+        }
+
+        result?;
+        log::info!("✅ Video compressed successfully: {}", output_path.display());
+        Ok(())
+    })
+}
+
+/// A video re-encoded in place by shrink_in_place
+#[derive(Debug, Clone)]
+pub struct ShrunkVideo {
+    pub size: u64,
+    /// Its new modification time, as stored
+    pub modified_at: Option<String>,
+}
 
-    let mut cmd = FfmpegCommand::new();
+/// Re-encode a video smaller (see compress_video), scaled to fit `quality`'s
+/// resolution, and swap it in (see replace_video) once it plays to the end
+/// and takes less space. The video is left as it was if any step fails
+pub fn shrink_in_place(
+    video: &Path,
+    purpose: &str,
+    codec: VideoCodec,
+    quality: RecordingQuality,
+    video_bitrate: u32,
+    audio_bitrate: u32,
+) -> Result<ShrunkVideo, Error> {
+    ensure_ffmpeg()?;
+    let original_size = std::fs::metadata(video)?.len();
+    let probe = probe_video(video)?;
+    let dimensions = quality.scale_dimensions(probe.width, probe.height);
 
-    // Add trim start if specified (seeking before input is faster)
-    if let Some(start) = trim_start {
-        cmd.arg("-ss").arg(start.to_string());
+    replace_video(video, purpose, |temp| {
+        compress_video(video, temp, codec, video_bitrate, audio_bitrate, Some(dimensions))?;
+        verify_playable(temp, probe.duration_secs)?;
+        if std::fs::metadata(temp)?.len() >= original_size {
+            return Err(Error::RecordingFailed("Re-encoding wouldn't make the video smaller".to_string()));
+        }
+        Ok(())
+    })?;
+
+    let metadata = std::fs::metadata(video)?;
+    Ok(ShrunkVideo { size: metadata.len(), modified_at: metadata.modified().ok().map(timestamps::from_system_time) })
+}
+
+/// Write a new version of a video with `encode`, which gets the temp file to
+/// write (`<name>.<purpose>.<extension>` next to the video), then move it over
+/// the video (see replace_with). The temp file is removed if either fails,
+/// leaving the video as it was
+pub fn replace_video(
+    video: &Path,
+    purpose: &str,
+    encode: impl FnOnce(&Path) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let extension = video.extension().and_then(|e| e.to_str()).unwrap_or("mp4");
+    let temp = video.with_extension(format!("{}.{}", purpose, extension));
+    let result = encode(&temp).and_then(|()| replace_with(video, &temp));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
     }
+    result
+}
 
-    // Input file
-    cmd.arg("-i").arg(input_path);
+/// Move a new version of a video, written in the same folder, over it. Same
+/// folder, so this is an atomic replace: nothing ever opens half a video
+pub fn replace_with(video: &Path, new_version: &Path) -> Result<(), Error> {
+    std::fs::rename(new_version, video).map_err(Error::from)
+}
 
-    // Add trim end if specified
-    if let Some(end) = trim_end {
-        let duration = if let Some(start) = trim_start {
-            end - start
-        } else {
-            end
+/// Copy a video's streams into a new file without re-encoding, skipping
+/// damaged packets. The container follows the output's extension (MP4s get
+/// their index in front). Rebuilds the index of a file whose writer was
+/// interrupted, as long as FFmpeg can still find its streams
+pub fn remux_video(input_path: impl AsRef<Path>, output_path: impl AsRef<Path>) -> Result<(), Error> {
+    let (input_path, output_path) = (input_path.as_ref(), output_path.as_ref());
+    metrics::job_sync("ffmpeg.remux_video", || {
+        let mut cmd = FfmpegCommand::new();
+        cmd.args(["-loglevel", "error", "-nostats"])
+            .args(["-err_detect", "ignore_err", "-fflags", "+genpts+discardcorrupt"])
+            .arg("-i")
+            .arg(paths::process_arg(input_path))
+            .args(["-map", "0", "-c", "copy"]);
+        if is_mp4(output_path) {
+            cmd.args(["-movflags", "+faststart"]);
+        }
+        cmd.arg("-y").arg(paths::process_arg(output_path));
+
+        let status = cmd
+            .spawn()
+            .map_err(|e| Error::ffmpeg(format!("Failed to spawn FFmpeg for remux: {}", e), &[]))?
+            .wait()
+            .map_err(|e| Error::ffmpeg(format!("FFmpeg process error: {}", e), &[]))?;
+        if !status.success() {
+            return Err(Error::ffmpeg(format!("FFmpeg remux failed with status: {:?}", status), &[]));
+        }
+        Ok(())
+    })
+}
+
+/// Whether FFmpeg writes an MP4 to this path (MP4-only options go by it)
+fn is_mp4(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("mp4"))
+}
+
+/// Copy a video without its first `start_secs`, without re-encoding. The copy
+/// starts on the keyframe before `start_secs`, so slightly less may be cut
+pub fn trim_start(input_path: impl AsRef<Path>, output_path: impl AsRef<Path>, start_secs: f64) -> Result<(), Error> {
+    let (input_path, output_path) = (input_path.as_ref(), output_path.as_ref());
+    metrics::job_sync("ffmpeg.trim_start", || {
+        let mut cmd = FfmpegCommand::new();
+        cmd.args(["-loglevel", "error", "-nostats"])
+            .args(["-ss", &format!("{:.3}", start_secs)])
+            .arg("-i")
+            .arg(paths::process_arg(input_path))
+            .args(["-map", "0", "-c", "copy", "-avoid_negative_ts", "make_zero"])
+            .args(["-movflags", "+faststart", "-y"])
+            .arg(paths::process_arg(output_path));
+
+        let status = cmd
+            .spawn()
+            .map_err(|e| Error::ffmpeg(format!("Failed to spawn FFmpeg for trim: {}", e), &[]))?
+            .wait()
+            .map_err(|e| Error::ffmpeg(format!("FFmpeg process error: {}", e), &[]))?;
+        if !status.success() {
+            return Err(Error::ffmpeg(format!("FFmpeg trim failed with status: {:?}", status), &[]));
+        }
+        Ok(())
+    })
+}
+
+/// Formats audio can be exported in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    Mp3,
+    /// AAC in an M4A file
+    Aac,
+    /// 16-bit PCM
+    Wav,
+}
+
+impl AudioFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Mp3 => "mp3",
+            Self::Aac => "m4a",
+            Self::Wav => "wav",
+        }
+    }
+
+    /// Whether a source's audio is already in this format, so it can be copied
+    fn copies(self, info: &MediaInfo) -> bool {
+        let codec = match self {
+            Self::Mp3 => "mp3",
+            Self::Aac => "aac",
+            Self::Wav => "pcm_s16le",
         };
-        cmd.arg("-t").arg(duration.to_string());
+        info.audio_codec.as_deref() == Some(codec)
     }
 
-    // Add crop filter if specified
-    if let Some(ref crop_region) = crop {
-        let crop_filter = format!(
-            "crop={}:{}:{}:{}",
-            crop_region.width, crop_region.height, crop_region.x, crop_region.y
-        );
-        cmd.arg("-vf").arg(&crop_filter);
-        // When using video filter, we need to re-encode video
-        cmd.arg("-c:a").arg("copy"); // But copy audio
-    } else {
-        // No crop, can use stream copy for both video and audio (fastest)
-        cmd.arg("-c").arg("copy");
+    fn encoder_args(self) -> &'static [&'static str] {
+        match self {
+            Self::Mp3 => &["-c:a", "libmp3lame", "-q:a", "2"],
+            Self::Aac => &["-c:a", "aac", "-b:a", "192k"],
+            Self::Wav => &["-c:a", "pcm_s16le"],
+        }
     }
+}
 
-    // Avoid negative timestamps issue
-    cmd.arg("-avoid_negative_ts").arg("1");
-    
-    // Overwrite output file
-    cmd.arg("-y").arg(output_path);
+/// The part of a `duration_secs` long file between `start` and `end`, as an
+/// offset and a length (None: to the end)
+fn audio_range(duration_secs: f64, start: Option<f64>, end: Option<f64>) -> Result<(f64, Option<f64>), Error> {
+    let from = start.unwrap_or(0.0);
+    if from < 0.0 || end.is_some_and(|end| end <= from) {
+        return Err(Error::InvalidPath(format!(
+            "Invalid time range {:.2}s to {:.2}s",
+            from,
+            end.unwrap_or(duration_secs)
+        )));
+    }
+    // Containers round differently
+    if duration_secs > 0.0 && from >= duration_secs {
+        return Err(Error::InvalidPath(format!(
+            "Range starts at {:.2}s but the file is {:.2}s long",
+            from, duration_secs
+        )));
+    }
+    Ok((from, end.map(|end| end - from)))
+}
 
-    let result = cmd.spawn();
+/// Write a video's first audio stream to a file of its own, from `start` to
+/// `end` seconds (default the whole length). The stream is copied as is when
+/// it's already in `format`, and re-encoded otherwise.
+/// Error::NoAudio if the video has no audio stream
+pub fn extract_audio(
+    input_path: impl AsRef<Path>,
+    output_path: impl AsRef<Path>,
+    format: AudioFormat,
+    start: Option<f64>,
+    end: Option<f64>,
+) -> Result<(), Error> {
+    let (input_path, output_path) = (input_path.as_ref(), output_path.as_ref());
+    let info = media_info(input_path)?;
+    if !info.has_audio {
+        return Err(Error::NoAudio { path: input_path.display().to_string() });
+    }
+    let (offset, length) = audio_range(info.duration_secs, start, end)?;
+    let copy = format.copies(&info);
 
-    match result {
-        Ok(mut child) => {
-            let status = child
-                .wait()
-                .map_err(|e| Error::RecordingFailed(format!("FFmpeg process error: {}", e)))?;
+    metrics::job_sync("ffmpeg.extract_audio", || {
+        log::info!(
+            "🔊 Extracting audio: input={}, output={}, format={:?}, copy={}",
+            input_path.display(),
+            output_path.display(),
+            format,
+            copy
+        );
 
-            if status.success() {
-                log::info!("✅ Video edit processed successfully: {}", output_path);
-                Ok(())
-            } else {
-                Err(Error::RecordingFailed(format!(
-                    "FFmpeg edit failed with status: {:?}",
-                    status
-                )))
+        let mut cmd = FfmpegCommand::new();
+        cmd.args(["-loglevel", "error", "-nostats"]);
+        if offset > 0.0 {
+            cmd.args(["-ss", &format!("{:.3}", offset)]);
+        }
+        cmd.args(ffmpeg_thread_args()).arg("-i").arg(paths::process_arg(input_path)).args(ffmpeg_thread_args());
+        if let Some(length) = length {
+            cmd.args(["-t", &format!("{:.3}", length)]);
+        }
+        cmd.args(["-map", "0:a:0", "-vn"]);
+        if copy {
+            cmd.args(["-c:a", "copy"]);
+        } else {
+            cmd.args(format.encoder_args());
+        }
+        cmd.arg("-y").arg(paths::process_arg(output_path));
+
+        let status = cmd
+            .spawn()
+            .map_err(|e| Error::ffmpeg(format!("Failed to spawn FFmpeg for audio export: {}", e), &[]))?
+            .wait()
+            .map_err(|e| Error::ffmpeg(format!("FFmpeg process error: {}", e), &[]))?;
+        if !status.success() {
+            let _ = std::fs::remove_file(output_path);
+            return Err(Error::ffmpeg(format!("FFmpeg audio export failed with status: {:?}", status), &[]));
+        }
+        log::info!("✅ Audio extracted: {}", output_path.display());
+        Ok(())
+    })
+}
+
+/// Check that a re-encoded video is complete and decodes: it must be about as
+/// long as the original, and its last seconds must decode without errors
+pub fn verify_playable(video_path: impl AsRef<Path>, expected_duration_secs: f64) -> Result<(), Error> {
+    let video_path = video_path.as_ref();
+    metrics::job_sync("ffmpeg.verify_playable", || {
+        let probe = probe_video(video_path)?;
+        // Containers round differently; anything past a second means frames are missing
+        if (probe.duration_secs - expected_duration_secs).abs() > 1.0 {
+            return Err(Error::RecordingFailed(format!(
+                "Re-encoded video is {:.1}s long, expected {:.1}s",
+                probe.duration_secs, expected_duration_secs
+            )));
+        }
+
+        with_hwaccel_fallback("Decode check", |hwaccel| {
+            let mut command = std::process::Command::new(ffmpeg_sidecar::paths::ffmpeg_path());
+            command.args(["-v", "error"]).args(hwaccel).args(["-sseof", "-5", "-i"]);
+            command.arg(paths::process_arg(video_path)).args(["-f", "null", "-"]);
+            #[cfg(target_os = "windows")]
+            {
+                use std::os::windows::process::CommandExt;
+                const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+                command.creation_flags(CREATE_NO_WINDOW);
+            }
+
+            let output = command
+                .output()
+                .map_err(|e| Error::ffmpeg(format!("Failed to run FFmpeg: {}", e), &[]))?;
+            let errors = String::from_utf8_lossy(&output.stderr);
+            if !output.status.success() || !errors.trim().is_empty() {
+                return Err(Error::ffmpeg("Re-encoded video doesn't decode", &output.stderr));
             }
+            Ok(())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clips_and_thumbnails_under_long_non_ascii_paths() {
+        let ffmpeg = ffmpeg_sidecar::paths::ffmpeg_path();
+        if std::process::Command::new(&ffmpeg).arg("-version").output().is_err() {
+            eprintln!("FFmpeg isn't installed, skipping");
+            return;
+        }
+        let (root, dir) = paths::long_test_dir("ffmpeg");
+        let video = dir.join("Game_20240301T180211.mp4");
+        let made = std::process::Command::new(&ffmpeg)
+            .args(["-loglevel", "error", "-f", "lavfi", "-i", "testsrc=duration=3:size=320x240:rate=30", "-y"])
+            .arg(paths::process_arg(&video))
+            .status()
+            .unwrap();
+        assert!(made.success());
+
+        let clip = dir.join("Clips").join("Clip_20240301T180211.mp4");
+        extract_clip(&video, &clip, 1.0, 1.0).unwrap();
+        assert!(probe_video(&clip).unwrap().duration_secs > 0.0);
+        let thumbnail = dir.join("Thumbnails").join("Game_20240301T180211.jpg");
+        generate_thumbnail(&video, &thumbnail, None).unwrap();
+        assert!(std::fs::metadata(&thumbnail).unwrap().len() > 0);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_edits_must_fit_the_probed_video() {
+        let probe = VideoProbe { width: 1920, height: 1080, duration_secs: 60.0 };
+        let crop = |x, y, width, height| CropRegion { x, y, width, height };
+        assert!(validate_edit(&probe, Some(5.0), Some(20.0), Some(&crop(0, 0, 1920, 1080))).is_ok());
+        assert!(validate_edit(&probe, None, None, Some(&crop(960, 540, 960, 540))).is_ok());
+        assert!(validate_edit(&probe, None, None, Some(&crop(961, 0, 960, 540))).is_err());
+        assert!(validate_edit(&probe, None, None, Some(&crop(0, 0, 1920, 1081))).is_err());
+        assert!(validate_edit(&probe, None, None, Some(&crop(0, 0, 0, 540))).is_err());
+        assert!(validate_edit(&probe, None, None, Some(&crop(u32::MAX, 0, 2, 2))).is_err());
+        assert!(validate_edit(&probe, Some(20.0), Some(5.0), None).is_err());
+        assert!(validate_edit(&probe, Some(61.0), None, None).is_err());
+
+        assert_eq!(progress_secs("out_time_us=2500000"), Some(2.5));
+        assert_eq!(progress_secs("out_time_ms=-1000"), Some(0.0));
+        assert_eq!(progress_secs("progress=continue"), None);
+    }
+
+    #[test]
+    fn test_crops_are_fitted_to_the_video() {
+        let crop = |x, y, width, height| CropRegion { x, y, width, height };
+        let fit = |c: CropRegion| fit_crop(&c, 1920, 1080).map(|c| (c.x, c.y, c.width, c.height));
+        assert_eq!(fit(crop(100, 50, 640, 360)).unwrap(), (100, 50, 640, 360));
+
+        // One pixel past the edge, and odd
+        assert_eq!(fit(crop(960, 0, 961, 1080)).unwrap(), (960, 0, 960, 1080));
+        assert_eq!(fit(crop(0, 0, 641, 361)).unwrap(), (0, 0, 640, 360));
+
+        // Larger than the video
+        let err = fit(crop(0, 0, 2560, 1440)).unwrap_err();
+        assert!(matches!(err, Error::CropOutOfBounds { width: 2560, video_width: 1920, .. }));
+        assert!(fit(crop(1910, 0, 640, 360)).is_err());
+        assert!(fit(crop(1930, 0, 2, 2)).is_err());
+        assert!(fit(crop(0, 0, 1, 360)).is_err());
+        assert!(fit(crop(u32::MAX, 0, u32::MAX, 2)).is_err());
+    }
+
+    #[test]
+    fn test_media_info_from_ffprobe_json() {
+        let json = br#"{
+            "streams": [
+                { "codec_type": "audio", "codec_name": "aac", "channels": 2 },
+                { "codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080, "avg_frame_rate": "60000/1001" }
+            ],
+            "format": { "format_name": "mov,mp4,m4a,3gp,3g2,mj2", "duration": "12.500000", "bit_rate": "8000000" }
+        }"#;
+        let info = parse_media_info(json).unwrap();
+        assert_eq!((info.width, info.height, info.bitrate), (Some(1920), Some(1080), Some(8_000_000)));
+        assert_eq!((info.video_codec.as_deref(), info.audio_codec.as_deref()), (Some("h264"), Some("aac")));
+        assert!((info.fps.unwrap() - 59.94).abs() < 0.01);
+        assert_eq!((info.duration_secs, info.audio_channels, info.has_audio), (12.5, Some(2), true));
+
+        // AAC audio is copied into an M4A, re-encoded into anything else
+        assert!(AudioFormat::Aac.copies(&info));
+        assert!(!AudioFormat::Mp3.copies(&info));
+        assert_eq!(audio_range(info.duration_secs, Some(2.0), Some(5.5)).unwrap(), (2.0, Some(3.5)));
+        assert!(audio_range(info.duration_secs, Some(13.0), None).is_err());
+
+        // Truncated output, and files without streams, are errors
+        assert!(parse_media_info(&json[..40]).is_err());
+        assert!(parse_media_info(br#"{ "streams": [], "format": { "format_name": "mp3" } }"#).is_err());
+        assert_eq!(parse_frame_rate("0/0"), None);
+    }
+
+    #[test]
+    fn test_pick_hwaccel_from_ffmpeg_list() {
+        let listed = format!("Hardware acceleration methods:\n{}\nopencl\n", HWACCEL_CANDIDATES.join("\n"));
+        assert_eq!(pick_hwaccel(&listed), Some(HWACCEL_CANDIDATES[0]));
+        // Only the second candidate
+        if let Some(second) = HWACCEL_CANDIDATES.get(1) {
+            assert_eq!(pick_hwaccel(&format!("Hardware acceleration methods:\n{}\n", second)), Some(*second));
         }
-        Err(e) => Err(Error::RecordingFailed(format!(
-            "Failed to spawn FFmpeg for edit: {}",
-            e
-        ))),
+        assert_eq!(pick_hwaccel("Hardware acceleration methods:\nopencl\n"), None);
+        assert_eq!(pick_hwaccel(""), None);
     }
 }
@@ -1,9 +1,13 @@
 pub mod clips;
 pub mod cloud;
 pub mod default;
+pub mod diagnostics;
 pub mod errors;
 pub mod library;
+pub mod overlay;
 pub mod recording;
+pub mod recording_rules;
+pub mod retention;
 pub mod settings;
 pub mod slippi;
 pub mod window;
@@ -5,16 +5,17 @@
 use crate::app_state::AppState;
 use crate::commands::errors::Error;
 use crate::database::{self, RecordingRow};
+use crate::event_batch;
 use crate::events::clips as clip_events;
 use crate::library;
+use crate::metrics;
 use std::path::Path;
-use std::time::SystemTime;
 use tauri::{Emitter, Manager, State};
 use tauri_plugin_store::StoreExt;
-use uuid::Uuid;
 
 /// Mark a timestamp for clip creation
 #[tauri::command]
+#[metrics::timed]
 pub fn mark_clip_timestamp(
     recording_file: String,
     timestamp: f64,
@@ -24,23 +25,41 @@ pub fn mark_clip_timestamp(
         .clip_markers
         .lock()
         .map_err(|e| Error::InitializationError(format!("Failed to lock clip markers: {}", e)))?;
-    
+
     markers.push(crate::app_state::ClipMarker {
         recording_file,
         timestamp_seconds: timestamp,
     });
-    
+
     log::info!("📍 Clip marker added at {}s", timestamp);
     Ok(())
 }
 
-/// Process all clip markers for a recording file
+/// A clip cut from a video, as returned by the clip commands and sent with
+/// clips-created
+#[derive(Debug, Clone, serde::Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipResult {
+    pub path: String,
+    /// Video the clip was cut from
+    pub source_recording: String,
+    /// Clip marker it was made for (seconds into the game), if any
+    pub marker_timestamp: Option<f64>,
+    /// Where the clip starts in its source, in seconds
+    pub start_offset: f64,
+    pub duration: f64,
+    pub thumbnail_path: Option<String>,
+}
+
+/// Process all clip markers for a recording file: each becomes a clip with a
+/// thumbnail, added to the library
 #[tauri::command]
+#[metrics::timed]
 pub async fn process_clip_markers(
     recording_file: String,
     app: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<Vec<String>, Error> {
+) -> Result<Vec<ClipResult>, Error> {
     // Ensure FFmpeg is available
     log::info!("Ensuring FFmpeg is available...");
     match crate::clip_processor::ensure_ffmpeg() {
@@ -50,34 +69,34 @@ pub async fn process_clip_markers(
             return Err(e);
         }
     }
-    
+
     // Get clip duration from settings
     let clip_duration = {
         let store = app.store("settings.json").map_err(|e| {
             Error::InitializationError(format!("Failed to open settings store: {}", e))
         })?;
-        
+
         store
             .get("clipDuration")
             .and_then(|v| v.as_f64())
             .unwrap_or(30.0)
     };
-    
+
     log::info!("⏱ Clip duration: {}s", clip_duration);
-    
+
     // Get markers for this recording (match by base filename)
     let recording_base = Path::new(&recording_file)
         .file_stem()
         .and_then(|s| s.to_str())
         .unwrap_or(&recording_file);
-    
+
     log::debug!("Looking for clip markers matching base: {}", recording_base);
-    
+
     let markers = {
         let mut markers_lock = state.clip_markers.lock().map_err(|e| {
             Error::InitializationError(format!("Failed to lock clip markers: {}", e))
         })?;
-        
+
         // Match by base filename
         let recording_markers: Vec<_> = markers_lock
             .iter()
@@ -90,7 +109,7 @@ pub async fn process_clip_markers(
             })
             .cloned()
             .collect();
-        
+
         // Remove processed markers
         markers_lock.retain(|m| {
             let marker_base = Path::new(&m.recording_file)
@@ -99,72 +118,83 @@ pub async fn process_clip_markers(
                 .unwrap_or(&m.recording_file);
             marker_base != recording_base
         });
-        
+
         recording_markers
     };
-    
+
     if markers.is_empty() {
         log::info!("ℹ No clip markers found for this recording");
         return Ok(Vec::new());
     }
-    
+
     log::info!("Found {} clip marker(s) to process", markers.len());
-    
+
     // Get recording directory
     let recording_dir = library::get_recording_directory(&app).await?;
-    
+
     // Determine video path
-    let video_path = if recording_file.ends_with(".mp4") {
+    let video_path = if library::is_video_file(Path::new(&recording_file)) {
         recording_file.clone()
     } else {
         format!("{}.mp4", recording_file.trim_end_matches(".slp"))
     };
-    
-    let input_path = if Path::new(&video_path).is_absolute() {
+
+    let mut input_path = if Path::new(&video_path).is_absolute() {
         video_path.clone()
     } else {
         format!("{}/{}", recording_dir, video_path)
     };
-    
+    // Finalized into another container since the markers were placed
+    if !Path::new(&input_path).exists() {
+        if let Some(existing) = library::VIDEO_EXTENSIONS
+            .iter()
+            .map(|ext| Path::new(&input_path).with_extension(ext))
+            .find(|path| path.exists())
+        {
+            input_path = existing.to_string_lossy().to_string();
+        }
+    }
+
     // Verify input file exists
     if !Path::new(&input_path).exists() {
         log::error!("Recording file not found: {}", input_path);
         return Err(Error::InvalidPath(format!("Recording file not found: {}", input_path)));
     }
-    
+
     // Create clips directory
     let recording_dir_path = Path::new(&recording_dir);
     let clips_parent_dir = recording_dir_path.parent().unwrap_or(recording_dir_path);
     let clips_dir_path = clips_parent_dir.join("Clips");
-    
-    std::fs::create_dir_all(&clips_dir_path).map_err(|e| {
-        log::error!("Failed to create clips directory: {}", e);
-        Error::RecordingFailed(format!("Failed to create clips directory: {}", e))
-    })?;
-    
+
+    std::fs::create_dir_all(&clips_dir_path)?;
+
+    // Markers are timed from when the game started, which the video may trail
+    let video_offset_secs = database::get_recording_by_video_path(&state.database.connection(), &input_path)
+        .ok()
+        .flatten()
+        .and_then(|row| row.video_offset_ms)
+        .map_or(0.0, |ms| ms as f64 / 1000.0);
+
     let mut created_clips = Vec::new();
-    
+
     // Process each marker
     for (idx, marker) in markers.iter().enumerate() {
-        let start_time = (marker.timestamp_seconds - clip_duration).max(0.0);
-        
+        let marker_time = (marker.timestamp_seconds - video_offset_secs).max(0.0);
+        let start_time = (marker_time - clip_duration).max(0.0);
+
         // Generate clip filename
         let timestamp = Path::new(&recording_file)
             .file_stem()
             .and_then(|s| s.to_str())
             .map(|s| s.strip_prefix("Game_").unwrap_or(s))
             .unwrap_or("unknown");
-        
-        let clip_filename = format!("Clip_{}_{:03}.mp4", timestamp, idx + 1);
+
+        let clip_filename = format!("Clip_{}_{:03}.{}", timestamp, idx + 1, library::clip_extension(Path::new(&input_path)));
         let output_path = clips_dir_path.join(&clip_filename);
-        let output_path_str = output_path
-            .to_str()
-            .ok_or_else(|| Error::InvalidPath("Failed to build clip output path".to_string()))?
-            .to_string();
-        
-        // Extract clip
-        match crate::clip_processor::extract_clip(&input_path, &output_path_str, start_time, clip_duration) {
-            Ok(_) => {
+
+        // Extract clip, with its thumbnail and library row
+        match save_clip_to(&app, &input_path, &output_path, start_time, clip_duration) {
+            Ok(clip) => {
                 log::info!(
                     "✅ Clip created ({}/{}): {} (start {}s, duration {}s)",
                     idx + 1,
@@ -173,7 +203,7 @@ pub async fn process_clip_markers(
                     start_time,
                     clip_duration
                 );
-                created_clips.push(output_path_str);
+                created_clips.push(ClipResult { marker_timestamp: Some(marker.timestamp_seconds), ..clip });
             }
             Err(e) => {
                 log::error!("Failed to create clip: {:?}", e);
@@ -181,76 +211,136 @@ pub async fn process_clip_markers(
             }
         }
     }
-    
+
     log::info!("✅ Created {} clip(s)", created_clips.len());
-    
+
     // Emit event to frontend
     if !created_clips.is_empty() {
         if let Err(e) = app.emit(clip_events::CREATED, created_clips.clone()) {
             log::error!("Failed to emit {} event: {:?}", clip_events::CREATED, e);
         }
+        library::clips_added(&app);
     }
-    
+
     Ok(created_clips)
 }
 
 /// Compress video for cloud upload
 #[tauri::command]
+#[metrics::timed]
 pub async fn compress_video_for_upload(input_path: String) -> Result<String, Error> {
     log::info!("Compressing video for upload: {}", input_path);
-    
+
     crate::clip_processor::ensure_ffmpeg()?;
-    
+
     // Generate output path in temp directory
     let input_file = Path::new(&input_path);
     let file_stem = input_file
         .file_stem()
         .and_then(|s| s.to_str())
         .ok_or_else(|| Error::InvalidPath("Invalid input path".into()))?;
-    
+
     let temp_dir = std::env::temp_dir();
     let output_path = temp_dir.join(format!("{}_compressed.mp4", file_stem));
     let output_path_str = output_path
         .to_str()
         .ok_or_else(|| Error::InvalidPath("Invalid output path".into()))?
         .to_string();
-    
+
+    // Scaled down to 720p, never up; audio only if there is some
+    let info = crate::clip_processor::media_info(&input_path)?;
+    let mut args = vec!["-c:v", "libx264", "-preset", "fast", "-crf", "28"];
+    if info.height.is_some_and(|height| height > 720) {
+        args.extend(["-vf", "scale=-2:720"]);
+    }
+    if info.has_audio {
+        args.extend(["-c:a", "aac", "-b:a", "128k"]);
+    } else {
+        args.push("-an");
+    }
+
     // Compress video
     use ffmpeg_sidecar::command::FfmpegCommand;
-    
+
     let mut command = FfmpegCommand::new();
     command
         .input(&input_path)
-        .args([
-            "-c:v", "libx264",
-            "-preset", "fast",
-            "-crf", "28",
-            "-vf", "scale=-2:720",
-            "-c:a", "aac",
-            "-b:a", "128k",
-        ])
+        .args(args)
         .output(&output_path_str)
         .overwrite();
-    
+
     let output = command
         .spawn()
-        .map_err(|e| Error::RecordingFailed(format!("Failed to start FFmpeg: {}", e)))?
+        .map_err(|e| Error::ffmpeg(format!("Failed to start FFmpeg: {}", e), &[]))?
         .wait()
-        .map_err(|e| Error::RecordingFailed(format!("FFmpeg failed: {}", e)))?;
-    
+        .map_err(|e| Error::ffmpeg(format!("FFmpeg failed: {}", e), &[]))?;
+
     if !output.success() {
-        return Err(Error::RecordingFailed(format!("FFmpeg exited with error: {:?}", output)));
+        return Err(Error::ffmpeg(format!("FFmpeg exited with error: {:?}", output), &[]));
     }
-    
+
     log::info!("✅ Video compressed successfully");
     Ok(output_path_str)
 }
 
+/// Container, codecs, size, frame rate, length and bitrate of a video or
+/// audio file, read with ffprobe (cached until the file changes)
+#[tauri::command]
+#[metrics::timed]
+pub async fn get_media_info(path: String) -> Result<crate::clip_processor::MediaInfo, Error> {
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::clip_processor::ensure_ffmpeg()?;
+        crate::clip_processor::media_info(&path)
+    })
+    .await
+    .map_err(|e| Error::RecordingFailed(format!("Probe task failed: {}", e)))?
+}
+
+/// An audio file written by export_audio
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedAudio {
+    pub path: String,
+    pub duration_secs: f64,
+}
+
+/// Export the audio of a clip or recording next to it, between `start_time`
+/// and `end_time` seconds (default the whole length)
+#[tauri::command]
+#[metrics::timed]
+pub async fn export_audio(
+    input_path: String,
+    format: crate::clip_processor::AudioFormat,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+    state: State<'_, AppState>,
+) -> Result<ExportedAudio, Error> {
+    crate::clip_processor::ensure_ffmpeg()?;
+    if !Path::new(&input_path).exists() {
+        return Err(Error::InvalidPath(format!("Input file does not exist: {}", input_path)));
+    }
+    let output_path = next_audio_path(Path::new(&input_path), format.extension());
+    let output_str = output_path
+        .to_str()
+        .ok_or_else(|| Error::InvalidPath("Invalid output path".into()))?
+        .to_string();
+
+    let _permit = state.work_gate.acquire(crate::work_gate::JobKind::Clips).await;
+    let duration_secs = tauri::async_runtime::spawn_blocking(move || {
+        crate::clip_processor::extract_audio(&input_path, &output_path, format, start_time, end_time)?;
+        crate::clip_processor::media_info(&output_path).map(|info| info.duration_secs)
+    })
+    .await
+    .map_err(|e| Error::RecordingFailed(format!("Audio export task failed: {}", e)))??;
+
+    Ok(ExportedAudio { path: output_str, duration_secs })
+}
+
 /// Delete a temporary file
 #[tauri::command]
+#[metrics::timed]
 pub async fn delete_temp_file(path: String) -> Result<(), Error> {
-    std::fs::remove_file(&path)
-        .map_err(|e| Error::RecordingFailed(format!("Failed to delete temp file: {}", e)))?;
+    std::fs::remove_file(&path)?;
     log::debug!("🗑️ Deleted temp file: {}", path);
     Ok(())
 }
@@ -258,6 +348,7 @@ pub async fn delete_temp_file(path: String) -> Result<(), Error> {
 /// Apply video edits (trim and/or crop) to a video file
 /// Creates a new clip in the clips directory instead of modifying the original
 #[tauri::command]
+#[metrics::timed]
 pub async fn apply_video_edit(
     input_path: String,
     trim_start: Option<f64>,
@@ -311,6 +402,9 @@ pub async fn apply_video_edit(
         log::warn!("No edits specified, returning original path");
         return Ok(input_path);
     }
+    let crop = crop
+        .map(|crop| crate::clip_processor::fit_crop_to_video(Path::new(&input_path), &crop))
+        .transpose()?;
 
     // Determine clips directory
     let recording_dir = library::get_recording_directory(&app).await?;
@@ -319,34 +413,9 @@ pub async fn apply_video_edit(
     let clips_dir = clips_parent_dir.join("Clips");
 
     // Ensure clips directory exists
-    std::fs::create_dir_all(&clips_dir).map_err(|e| {
-        Error::RecordingFailed(format!("Failed to create clips directory: {}", e))
-    })?;
+    std::fs::create_dir_all(&clips_dir)?;
 
-    // Generate clip filename: Clip01_<original_timestamp>.mp4
-    let input_file = Path::new(&input_path);
-    let source_stem = input_file
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("video");
-    
-    // Extract timestamp from original filename (e.g., "Game_20251110T194918" -> "20251110T194918")
-    let original_timestamp = source_stem
-        .strip_prefix("Game_")
-        .unwrap_or(source_stem);
-    
-    // Find next available clip number for this source
-    let mut clip_number = 1;
-    loop {
-        let candidate = clips_dir.join(format!("Clip{:02}_{}.mp4", clip_number, original_timestamp));
-        if !candidate.exists() {
-            break;
-        }
-        clip_number += 1;
-    }
-    
-    let clip_filename = format!("Clip{:02}_{}.mp4", clip_number, original_timestamp);
-    let output_path = clips_dir.join(&clip_filename);
+    let output_path = next_clip_path(&clips_dir, Path::new(&input_path));
     let output_str = output_path
         .to_str()
         .ok_or_else(|| Error::InvalidPath("Invalid output path".into()))?
@@ -359,6 +428,8 @@ pub async fn apply_video_edit(
         trim_start,
         trim_end,
         crop,
+        None,
+        |_| {},
     )?;
 
     // Generate thumbnail for the clip
@@ -371,36 +442,40 @@ pub async fn apply_video_edit(
     }
 
     // Get clip file metadata
-    let clip_meta = std::fs::metadata(&output_str).ok();
-    let file_size = clip_meta.as_ref().map(|m| m.len() as i64);
-    let file_modified = clip_meta
-        .as_ref()
-        .and_then(|m| m.modified().ok())
-        .and_then(|t| {
-            t.duration_since(SystemTime::UNIX_EPOCH)
-                .ok()
-                .map(|d| chrono::DateTime::from_timestamp(d.as_secs() as i64, 0))
-        })
-        .flatten()
-        .map(|dt| dt.to_rfc3339());
+    let (file_size, file_modified) = file_size_and_modified(Path::new(&output_str));
 
     // Add clip to database for immediate visibility
     let state = app.state::<AppState>();
     let db = state.database.clone();
+    // Before taking the connection, which choosing the id needs too
+    let id = library::choose_recording_id(&db, Path::new(&output_str), None);
     let conn = db.connection();
-    
+
     let clip_row = RecordingRow {
-        id: Uuid::new_v4().to_string(),
-        video_path: output_str.clone(),
+        id,
+        video_path: Some(output_str.clone()),
         slp_path: None,
         thumbnail_path: thumbnail_str,
-        start_time: Some(chrono::Utc::now().to_rfc3339()),
+        start_time: Some(crate::timestamps::now()),
         file_size,
         file_modified_at: file_modified,
         cached_at: chrono::Utc::now().to_rfc3339(),
         needs_reparse: false,
+        dropped_frame_estimate: None,
+        longest_stall_ms: None,
+        profile: None,
+        is_archived: false,
+        parse_error: None,
+        replay_id: None,
+        video_width: None,
+        video_height: None,
+        video_duration_secs: None,
+        has_audio: None,
+        audio_mean_volume_db: None,
+        video_offset_ms: None,
+        source_recording_id: None,
     };
-    
+
     if let Err(e) = database::upsert_recording(&conn, &clip_row) {
         log::warn!("Failed to add clip to database: {:?}", e);
     } else {
@@ -410,16 +485,230 @@ pub async fn apply_video_edit(
     log::info!("✅ Clip created: {}", output_str);
 
     // Emit clip created event so clips tab updates
-    if let Err(e) = app.emit(clip_events::CREATED, vec![output_str.clone()]) {
+    let clip = ClipResult {
+        path: output_str.clone(),
+        source_recording: input_path.clone(),
+        marker_timestamp: None,
+        start_offset: trim_start.unwrap_or(0.0),
+        duration: crate::clip_processor::media_info(&output_str).map_or(0.0, |info| info.duration_secs),
+        thumbnail_path: clip_row.thumbnail_path.clone(),
+    };
+    if let Err(e) = app.emit(clip_events::CREATED, vec![clip]) {
         log::error!("Failed to emit {} event: {:?}", clip_events::CREATED, e);
     }
+    library::clips_added(&app);
 
     Ok(output_str)
 }
 
+/// Where an edited recording goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EditOutput {
+    /// A new clip in the Clips folder
+    Clip,
+    /// A new recording next to the source, keeping its replay
+    Recording,
+}
+
+/// Payload of clip_events::EDIT_PROGRESS
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditProgress {
+    pub source_recording_id: String,
+    pub output_path: String,
+    /// 0 to 1
+    pub progress: f64,
+}
+
+/// An edit made by edit_recording
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditedRecording {
+    pub id: String,
+    pub video_path: String,
+}
+
+/// Trim and/or crop a library recording (by id or video path) into a new
+/// file, either a clip or a recording of the same game, linked back to it.
+/// `render_input_overlay` draws a player's controller inputs from the replay
+/// into the corner (`overlay_port`, by default the user's); recordings
+/// without a replay are edited without it.
+/// The edit waits its turn with the other FFmpeg jobs and reports progress
+#[tauri::command]
+#[metrics::timed]
+#[allow(clippy::too_many_arguments)]
+pub async fn edit_recording(
+    source: String,
+    trim_start: Option<f64>,
+    trim_end: Option<f64>,
+    crop: Option<crate::clip_processor::CropRegion>,
+    output: EditOutput,
+    render_input_overlay: Option<bool>,
+    overlay_port: Option<u8>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<EditedRecording, Error> {
+    let render_input_overlay = render_input_overlay.unwrap_or(false);
+    log::info!(
+        "🎬 Editing recording {} into a {:?}: trim={:?}-{:?}, crop={:?}, input overlay={}",
+        source,
+        output,
+        trim_start,
+        trim_end,
+        crop,
+        render_input_overlay
+    );
+    if trim_start.is_none() && trim_end.is_none() && crop.is_none() && !render_input_overlay {
+        return Err(Error::InvalidPath("No trim, crop or overlay to apply".into()));
+    }
+
+    let db = state.database.clone();
+    let source_row = {
+        let conn = db.connection();
+        match database::get_recording_by_id(&conn, &source)? {
+            Some(row) => Some(row),
+            None => database::get_recording_by_video_path(&conn, &source)?,
+        }
+    };
+    let Some(source_row) = source_row else {
+        return Err(Error::InvalidPath(format!("No recording in the library for {}", source)));
+    };
+    let Some(input_path) = source_row.video_path.clone() else {
+        return Err(Error::InvalidPath(format!("Recording {} has no video", source_row.id)));
+    };
+
+    crate::clip_processor::ensure_ffmpeg()?;
+    let probe = crate::clip_processor::probe_video(&input_path)?;
+    crate::clip_processor::validate_edit(&probe, trim_start, trim_end, crop.as_ref())?;
+
+    let output_path = match output {
+        EditOutput::Clip => {
+            let clips_dir = default_clips_directory(&app).await?;
+            std::fs::create_dir_all(&clips_dir)?;
+            next_clip_path(&clips_dir, Path::new(&input_path))
+        }
+        EditOutput::Recording => next_edit_path(Path::new(&input_path)),
+    };
+    let output_str = output_path
+        .to_str()
+        .ok_or_else(|| Error::InvalidPath("Invalid output path".into()))?
+        .to_string();
+
+    let _permit = state.work_gate.acquire(crate::work_gate::JobKind::Clips).await;
+    let length = trim_end.unwrap_or(probe.duration_secs) - trim_start.unwrap_or(0.0);
+    let overlay_source = if render_input_overlay {
+        let source = library::overlay_source(&app, &db.connection(), &source_row, overlay_port, trim_start.unwrap_or(0.0))?;
+        if source.is_none() {
+            log::info!("Recording {} has no replay, editing without the input overlay", source_row.id);
+        }
+        source
+    } else {
+        None
+    };
+    let video_height = crop.as_ref().map_or(probe.height, |crop| crop.height);
+    let progress_app = app.clone();
+    let mut progress = EditProgress {
+        source_recording_id: source_row.id.clone(),
+        output_path: output_str.clone(),
+        progress: 0.0,
+    };
+    let edit_input = input_path.clone();
+    let edit_output = output_str.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let overlay = overlay_source
+            .map(|source| library::overlay_stream(&source, trim_start.unwrap_or(0.0), length, video_height))
+            .transpose()?;
+        crate::clip_processor::process_video_edit(&edit_input, &edit_output, trim_start, trim_end, crop, overlay, |secs| {
+            progress.progress = if length > 0.0 { (secs / length).clamp(0.0, 1.0) } else { 0.0 };
+            event_batch::emit_batched(&progress_app, clip_events::EDIT_PROGRESS, &progress);
+        })?;
+        progress.progress = 1.0;
+        event_batch::emit_batched(&progress_app, clip_events::EDIT_PROGRESS, &progress);
+        Ok::<_, Error>(())
+    })
+    .await
+    .map_err(|e| Error::RecordingFailed(format!("Edit task failed: {}", e)))??;
+
+    let thumbnail_path = output_path.with_extension("jpg");
+    let thumbnail_str = thumbnail_path.to_str().map(|s| s.to_string());
+    if let Some(ref thumb_str) = thumbnail_str {
+        if let Err(e) = crate::clip_processor::generate_thumbnail(&output_str, thumb_str, None) {
+            log::warn!("Failed to generate thumbnail: {:?}", e);
+        }
+    }
+    let (file_size, file_modified) = file_size_and_modified(&output_path);
+
+    // A new recording still plays the source's game, shifted by the trim
+    let (slp_path, replay_id, video_offset_ms, start_time) = match output {
+        EditOutput::Clip => (None, None, None, Some(crate::timestamps::now())),
+        EditOutput::Recording => (
+            source_row.slp_path.clone(),
+            source_row.replay_id.clone(),
+            Some(
+                source_row.video_offset_ms.unwrap_or(0)
+                    - (trim_start.unwrap_or(0.0) * 1000.0).round() as i64,
+            ),
+            source_row.start_time.clone(),
+        ),
+    };
+    // Before taking the connection, which choosing the id needs too
+    let id = library::choose_recording_id(&db, &output_path, replay_id.as_deref());
+    let row = RecordingRow {
+        id,
+        video_path: Some(output_str.clone()),
+        slp_path,
+        thumbnail_path: thumbnail_str,
+        start_time,
+        file_size,
+        file_modified_at: file_modified,
+        cached_at: chrono::Utc::now().to_rfc3339(),
+        needs_reparse: false,
+        dropped_frame_estimate: None,
+        longest_stall_ms: None,
+        profile: source_row.profile.clone(),
+        is_archived: false,
+        parse_error: None,
+        replay_id,
+        video_width: None,
+        video_height: None,
+        video_duration_secs: None,
+        has_audio: None,
+        audio_mean_volume_db: None,
+        video_offset_ms,
+        source_recording_id: Some(source_row.id.clone()),
+    };
+    {
+        let conn = db.connection();
+        database::upsert_recording(&conn, &row)?;
+        database::set_recording_replay_id(&conn, &row.id, row.replay_id.as_deref())?;
+        database::set_recording_video_offset(&conn, &row.id, row.video_offset_ms)?;
+        database::set_recording_source(&conn, &row.id, &source_row.id)?;
+    }
+    log::info!("✅ Edited {} into {}", source_row.id, output_str);
+
+    if output == EditOutput::Clip {
+        let clip = ClipResult {
+            path: output_str.clone(),
+            source_recording: input_path.clone(),
+            marker_timestamp: None,
+            start_offset: trim_start.unwrap_or(0.0),
+            duration: length,
+            thumbnail_path: row.thumbnail_path.clone(),
+        };
+        if let Err(e) = app.emit(clip_events::CREATED, vec![clip]) {
+            log::error!("Failed to emit {} event: {:?}", clip_events::CREATED, e);
+        }
+        library::clips_added(&app);
+    }
+
+    Ok(EditedRecording { id: row.id, video_path: output_str })
+}
+
 /// Create a clip from a video with specified start and end times
 /// This is used by the clip editor to create a new clip from a selection
 #[tauri::command]
+#[metrics::timed]
 pub async fn create_clip_from_range(
     input_path: String,
     start_time: f64,
@@ -452,94 +741,381 @@ pub async fn create_clip_from_range(
         ));
     }
 
-    let duration = end_time - start_time;
-
     // Determine output directory
-    let clips_dir = if let Some(dir) = output_dir {
-        std::path::PathBuf::from(dir)
-    } else {
-        // Use default clips directory
-        let recording_dir = library::get_recording_directory(&app).await?;
-        let recording_dir_path = Path::new(&recording_dir);
-        let clips_parent_dir = recording_dir_path.parent().unwrap_or(recording_dir_path);
-        clips_parent_dir.join("Clips")
+    let clips_dir = match output_dir {
+        Some(dir) => std::path::PathBuf::from(dir),
+        None => default_clips_directory(&app).await?,
+    };
+    let clip = save_clip(&app, &input_path, start_time, end_time, &clips_dir)?;
+
+    log::info!("✅ Clip created: {}", clip.path);
+
+    // Emit clip created event
+    let output_str = clip.path.clone();
+    if let Err(e) = app.emit(clip_events::CREATED, vec![clip]) {
+        log::error!("Failed to emit {} event: {:?}", clip_events::CREATED, e);
+    }
+    library::clips_added(&app);
+
+    Ok(output_str)
+}
+
+/// A renamed or starred clip, as returned by rename_clip and set_clip_favorite
+/// and sent with clip-updated
+#[derive(Debug, Clone, serde::Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipUpdated {
+    pub id: String,
+    pub path: String,
+    /// Where the clip was before a rename (the same as path otherwise)
+    pub previous_path: String,
+    pub thumbnail_path: Option<String>,
+    pub is_favorite: bool,
+}
+
+/// A clip removed by delete_clip, as sent with clip-deleted
+#[derive(Debug, Clone, serde::Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipDeleted {
+    pub id: String,
+    pub path: String,
+}
+
+/// Delete a clip: its video, thumbnail, sidecar and library row. The frontend
+/// cancels uploads of it still waiting when it hears clip-deleted
+#[tauri::command]
+#[metrics::timed]
+pub async fn delete_clip(app: tauri::AppHandle, id: String) -> Result<(), Error> {
+    let clips_dir = default_clips_directory(&app).await?;
+    let (_, video_path) = {
+        let state = app.state::<AppState>();
+        let conn = state.database.connection();
+        clip_row(&conn, &id, &clips_dir)?
+    };
+    crate::commands::library::delete_video(&app, &video_path)?;
+
+    log::info!("🗑️ Deleted clip {}", id);
+    let deleted = ClipDeleted { id, path: video_path };
+    if let Err(e) = app.emit(clip_events::DELETED, &deleted) {
+        log::error!("Failed to emit {} event: {:?}", clip_events::DELETED, e);
+    }
+    Ok(())
+}
+
+/// Rename a clip's video (keeping its extension) and the thumbnail made with
+/// it. The name must be usable as is on every platform and not taken; the
+/// row and any highlights clipped into it follow the file
+#[tauri::command]
+#[metrics::timed]
+pub async fn rename_clip(app: tauri::AppHandle, id: String, new_name: String) -> Result<ClipUpdated, Error> {
+    let new_name = new_name.trim();
+    if !library::is_safe_name(new_name) {
+        return Err(Error::InvalidPath(format!("Not a valid file name: {:?}", new_name)));
+    }
+
+    let clips_dir = default_clips_directory(&app).await?;
+    let state = app.state::<AppState>();
+    let conn = state.database.connection();
+    let (row, old_str) = clip_row(&conn, &id, &clips_dir)?;
+    let old_path = Path::new(&old_str);
+    let extension = old_path.extension().and_then(|e| e.to_str()).unwrap_or("mp4");
+    let new_path = old_path.with_file_name(format!("{}.{}", new_name, extension));
+    let new_str = new_path.to_string_lossy().to_string();
+
+    let mut thumbnail_str = row.thumbnail_path.clone();
+    if new_path != old_path {
+        // Changing only the case finds the clip itself there on Windows and
+        // macOS; on Linux that name is another file
+        if new_path.exists() && !crate::paths::same_file(&new_path, old_path) {
+            return Err(Error::InvalidPath(format!("A file named {}.{} already exists", new_name, extension)));
+        }
+        std::fs::rename(old_path, &new_path)?;
+
+        // Clip thumbnails are saved next to the clip under its name;
+        // library thumbnails are named by id and stay put
+        let old_thumbnail = old_path.with_extension("jpg");
+        let new_thumbnail = new_path.with_extension("jpg");
+        let own_thumbnail = row
+            .thumbnail_path
+            .as_deref()
+            .is_some_and(|thumb| crate::paths::normalize(thumb) == crate::paths::normalize(&old_thumbnail));
+        let mut thumbnail_moved = false;
+        if own_thumbnail && !new_thumbnail.exists() {
+            match std::fs::rename(&old_thumbnail, &new_thumbnail) {
+                Ok(()) => {
+                    thumbnail_moved = true;
+                    thumbnail_str = Some(new_thumbnail.to_string_lossy().to_string());
+                }
+                Err(e) => log::warn!("Failed to rename thumbnail {}: {}", old_thumbnail.display(), e),
+            }
+        }
+
+        match database::rename_clip(&conn, &id, &old_str, &new_str, thumbnail_str.as_deref()) {
+            Ok(highlights) if highlights > 0 => log::debug!("Moved {} highlight(s) to {}", highlights, new_str),
+            Ok(_) => {}
+            Err(e) => {
+                if let Err(undo) = std::fs::rename(&new_path, old_path) {
+                    log::error!("Failed to move {} back to {}: {}", new_str, old_str, undo);
+                }
+                if thumbnail_moved {
+                    let _ = std::fs::rename(&new_thumbnail, &old_thumbnail);
+                }
+                return Err(Error::database("Failed to save the renamed clip", e));
+            }
+        }
+
+        let old_sidecar = library::sidecar_path(old_path);
+        if old_sidecar.exists() {
+            if let Err(e) = std::fs::rename(&old_sidecar, library::sidecar_path(&new_path)) {
+                log::warn!("Failed to rename sidecar {}: {}", old_sidecar.display(), e);
+            }
+        }
+        log::info!("📝 Renamed clip {} to {}", old_str, new_str);
+    }
+
+    let clip = ClipUpdated {
+        is_favorite: database::is_clip_favorite(&conn, &id).map_err(Error::from)?,
+        id,
+        path: new_str,
+        previous_path: old_str,
+        thumbnail_path: thumbnail_str,
     };
+    if let Err(e) = app.emit(clip_events::UPDATED, &clip) {
+        log::error!("Failed to emit {} event: {:?}", clip_events::UPDATED, e);
+    }
+    Ok(clip)
+}
+
+/// Star or unstar a clip. Starred clips are never rotated out by the Clips
+/// folder cap
+#[tauri::command]
+#[metrics::timed]
+pub async fn set_clip_favorite(app: tauri::AppHandle, id: String, favorite: bool) -> Result<ClipUpdated, Error> {
+    let clips_dir = default_clips_directory(&app).await?;
+    let clip = {
+        let state = app.state::<AppState>();
+        let conn = state.database.connection();
+        let (row, video_path) = clip_row(&conn, &id, &clips_dir)?;
+        database::set_clip_favorite(&conn, &id, favorite).map_err(Error::from)?;
+        ClipUpdated {
+            id,
+            previous_path: video_path.clone(),
+            path: video_path,
+            thumbnail_path: row.thumbnail_path,
+            is_favorite: favorite,
+        }
+    };
+    if let Err(e) = app.emit(clip_events::UPDATED, &clip) {
+        log::error!("Failed to emit {} event: {:?}", clip_events::UPDATED, e);
+    }
+    Ok(clip)
+}
 
+/// The row of clip `id` and its video path. Recordings outside the Clips
+/// folder aren't clips, so the clip commands can't touch them
+fn clip_row(conn: &rusqlite::Connection, id: &str, clips_dir: &Path) -> Result<(RecordingRow, String), Error> {
+    let row = database::get_recording_by_id(conn, id)
+        .map_err(Error::from)?
+        .filter(|row| row.video_path.is_some())
+        .ok_or_else(|| Error::InvalidPath(format!("Clip not found: {}", id)))?;
+    let video_path = row.video_path.clone().unwrap_or_default();
+    if !crate::paths::is_within(&video_path, clips_dir) {
+        return Err(Error::InvalidPath(format!("Not a clip: {}", id)));
+    }
+    Ok((row, video_path))
+}
+
+/// Default clips folder ("Clips" next to the recordings folder)
+pub(crate) async fn default_clips_directory(app: &tauri::AppHandle) -> Result<std::path::PathBuf, Error> {
+    let recording_dir = library::get_recording_directory(app).await?;
+    let recording_dir_path = Path::new(&recording_dir);
+    let clips_parent_dir = recording_dir_path.parent().unwrap_or(recording_dir_path);
+    Ok(clips_parent_dir.join("Clips"))
+}
+
+/// Next free Clip01_<original timestamp> path in clips_dir for an input video
+fn next_clip_path(clips_dir: &Path, input_file: &Path) -> std::path::PathBuf {
+    let extension = library::clip_extension(input_file);
+    let source_stem = input_file
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("video");
+
+    // Extract timestamp from original filename (e.g., "Game_20251110T194918" -> "20251110T194918")
+    let original_timestamp = source_stem
+        .strip_prefix("Game_")
+        .unwrap_or(source_stem);
+
+    // Find next available clip number for this source
+    let mut clip_number = 1;
+    loop {
+        let candidate = clips_dir.join(format!("Clip{:02}_{}.{}", clip_number, original_timestamp, extension));
+        if !candidate.exists() {
+            return candidate;
+        }
+        clip_number += 1;
+    }
+}
+
+/// Next free <stem>_edit<N> path next to an input video
+fn next_edit_path(input_file: &Path) -> std::path::PathBuf {
+    let extension = library::clip_extension(input_file);
+    let source_stem = input_file
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("video");
+    let mut edit_number = 1;
+    loop {
+        let candidate = input_file.with_file_name(format!("{}_edit{}.{}", source_stem, edit_number, extension));
+        if !candidate.exists() {
+            return candidate;
+        }
+        edit_number += 1;
+    }
+}
+
+/// Next free <stem>_audio<n>.<extension> path next to an input video
+fn next_audio_path(input_file: &Path, extension: &str) -> std::path::PathBuf {
+    let source_stem = input_file
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("video");
+    let mut audio_number = 1;
+    loop {
+        let candidate = input_file.with_file_name(format!("{}_audio{}.{}", source_stem, audio_number, extension));
+        if !candidate.exists() {
+            return candidate;
+        }
+        audio_number += 1;
+    }
+}
+
+/// A new file's size and modified time (RFC 3339) for its library row
+fn file_size_and_modified(path: &Path) -> (Option<i64>, Option<String>) {
+    let meta = std::fs::metadata(path).ok();
+    let file_size = meta.as_ref().map(|m| m.len() as i64);
+    let file_modified = meta
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .map(crate::timestamps::from_system_time);
+    (file_size, file_modified)
+}
+
+/// Cut start_time..end_time (seconds) out of a video into clips_dir, with a
+/// thumbnail, and add it to the library
+pub(crate) fn save_clip(
+    app: &tauri::AppHandle,
+    input_path: &str,
+    start_time: f64,
+    end_time: f64,
+    clips_dir: &Path,
+) -> Result<ClipResult, Error> {
     // Ensure clips directory exists
-    std::fs::create_dir_all(&clips_dir).map_err(|e| {
-        Error::RecordingFailed(format!("Failed to create clips directory: {}", e))
-    })?;
+    std::fs::create_dir_all(clips_dir)?;
 
     // Generate clip filename with timestamp
-    let input_file = Path::new(&input_path);
+    let input_file = Path::new(input_path);
     let source_stem = input_file
         .file_stem()
         .and_then(|s| s.to_str())
         .unwrap_or("video");
+    let extension = library::clip_extension(input_file);
 
     let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
-    let clip_filename = format!("Clip_{}_{}.mp4", source_stem, timestamp);
-    let output_path = clips_dir.join(&clip_filename);
+    // Several clips of the same video can be cut within a second
+    let mut output_path = clips_dir.join(format!("Clip_{}_{}.{}", source_stem, timestamp, extension));
+    let mut suffix = 2;
+    while output_path.exists() {
+        output_path = clips_dir.join(format!("Clip_{}_{}_{}.{}", source_stem, timestamp, suffix, extension));
+        suffix += 1;
+    }
+    save_clip_to(app, input_path, &output_path, start_time, end_time - start_time)
+}
+
+/// Cut `duration` seconds from `start_time` out of a video into `output_path`,
+/// with a thumbnail, and add it to the library as a clip of its source
+fn save_clip_to(
+    app: &tauri::AppHandle,
+    input_path: &str,
+    output_path: &Path,
+    start_time: f64,
+    duration: f64,
+) -> Result<ClipResult, Error> {
     let output_str = output_path
         .to_str()
         .ok_or_else(|| Error::InvalidPath("Invalid output path".into()))?
         .to_string();
 
     // Extract clip using existing function
-    crate::clip_processor::extract_clip(&input_path, &output_str, start_time, duration)?;
+    crate::clip_processor::extract_clip(input_path, &output_str, start_time, duration)?;
 
     // Generate thumbnail
     let thumbnail_path = output_path.with_extension("jpg");
-    let thumbnail_str = thumbnail_path.to_str().map(|s| s.to_string());
-    if let Some(ref thumb_str) = thumbnail_str {
-        if let Err(e) = crate::clip_processor::generate_thumbnail(&output_str, thumb_str, None) {
-            log::warn!("Failed to generate thumbnail: {:?}", e);
+    let thumbnail_str = thumbnail_path.to_str().map(|s| s.to_string()).filter(|thumb_str| {
+        match crate::clip_processor::generate_thumbnail(&output_str, thumb_str, None) {
+            Ok(_) => true,
+            Err(e) => {
+                log::warn!("Failed to generate thumbnail: {:?}", e);
+                false
+            }
         }
-    }
+    });
 
     // Get clip file metadata
-    let clip_meta = std::fs::metadata(&output_str).ok();
-    let file_size = clip_meta.as_ref().map(|m| m.len() as i64);
-    let file_modified = clip_meta
-        .as_ref()
-        .and_then(|m| m.modified().ok())
-        .and_then(|t| {
-            t.duration_since(SystemTime::UNIX_EPOCH)
-                .ok()
-                .map(|d| chrono::DateTime::from_timestamp(d.as_secs() as i64, 0))
-        })
-        .flatten()
-        .map(|dt| dt.to_rfc3339());
+    let (file_size, file_modified) = file_size_and_modified(Path::new(&output_str));
 
     // Add clip to database for immediate visibility
     let state = app.state::<AppState>();
     let db = state.database.clone();
+    // Before taking the connection, which choosing the id needs too
+    let id = library::choose_recording_id(&db, Path::new(&output_str), None);
     let conn = db.connection();
+    let source_recording_id = database::get_recording_by_video_path(&conn, input_path)
+        .ok()
+        .flatten()
+        .map(|source| source.id);
     
     let clip_row = RecordingRow {
-        id: Uuid::new_v4().to_string(),
-        video_path: output_str.clone(),
+        id,
+        video_path: Some(output_str.clone()),
         slp_path: None,
-        thumbnail_path: thumbnail_str,
-        start_time: Some(chrono::Utc::now().to_rfc3339()),
+        thumbnail_path: thumbnail_str.clone(),
+        start_time: Some(crate::timestamps::now()),
         file_size,
         file_modified_at: file_modified,
         cached_at: chrono::Utc::now().to_rfc3339(),
         needs_reparse: false,
+        dropped_frame_estimate: None,
+        longest_stall_ms: None,
+        profile: None,
+        is_archived: false,
+        parse_error: None,
+        replay_id: None,
+        video_width: None,
+        video_height: None,
+        video_duration_secs: None,
+        has_audio: None,
+        audio_mean_volume_db: None,
+        video_offset_ms: None,
+        source_recording_id: source_recording_id.clone(),
     };
     
-    if let Err(e) = database::upsert_recording(&conn, &clip_row) {
+    let saved = database::upsert_recording(&conn, &clip_row).and_then(|()| match &source_recording_id {
+        Some(source_id) => database::set_recording_source(&conn, &clip_row.id, source_id),
+        None => Ok(()),
+    });
+    if let Err(e) = saved {
         log::warn!("Failed to add clip to database: {:?}", e);
     } else {
         log::debug!("📝 Added clip to database: {}", clip_row.id);
     }
 
-    log::info!("✅ Clip created: {}", output_str);
-
-    // Emit clip created event
-    if let Err(e) = app.emit(clip_events::CREATED, vec![output_str.clone()]) {
-        log::error!("Failed to emit {} event: {:?}", clip_events::CREATED, e);
-    }
-
-    Ok(output_str)
+    Ok(ClipResult {
+        path: output_str,
+        source_recording: input_path.to_string(),
+        marker_timestamp: None,
+        start_offset: start_time,
+        duration,
+        thumbnail_path: thumbnail_str,
+    })
 }
@@ -1,14 +1,18 @@
+use crate::commands::errors::Error;
+use crate::commands::settings::save_error;
+use crate::metrics;
 use tauri::AppHandle;
 use uuid::Uuid;
 
 /// Get or create device ID for anonymous clip identification
 #[tauri::command]
-pub async fn get_device_id(app: AppHandle) -> Result<String, String> {
+#[metrics::timed]
+pub async fn get_device_id(app: AppHandle) -> Result<String, Error> {
     use tauri_plugin_store::StoreExt;
 
     let store = app
         .store("settings.json")
-        .map_err(|e| format!("Failed to open store: {}", e))?;
+        .map_err(|e| Error::InitializationError(format!("Failed to open settings store: {}", e)))?;
 
     // Check if device_id already exists
     if let Some(value) = store.get("device_id") {
@@ -21,9 +25,7 @@ pub async fn get_device_id(app: AppHandle) -> Result<String, String> {
     let device_id = Uuid::new_v4().to_string();
     store.set("device_id", serde_json::json!(device_id));
 
-    store
-        .save()
-        .map_err(|e| format!("Failed to save store: {}", e))?;
+    store.save().map_err(save_error)?;
 
     log::info!("📱 Generated new device ID: {}", device_id);
     Ok(device_id)
@@ -1,7 +1,9 @@
+use crate::metrics;
 use super::errors::Error;
 use std::fs;
 
 #[tauri::command]
+#[metrics::timed]
 pub fn read(path: String) -> Result<String, Error> {
     let data = fs::read(path)?;
     let string = String::from_utf8(data)?;
@@ -9,6 +11,7 @@ pub fn read(path: String) -> Result<String, Error> {
 }
 
 #[tauri::command]
+#[metrics::timed]
 pub fn write(path: String, contents: String) -> Result<(), Error> {
     fs::write(path, contents)?;
     Ok(())
@@ -0,0 +1,84 @@
+//! Log, diagnostics, health, metrics, decoding, readiness, scheduled job and API info commands
+//!
+//! Thin command handlers that delegate to the diagnostics, health, metrics,
+//! readiness, scheduler and api modules.
+
+use crate::api::{self, ApiInfo};
+use crate::app_state::AppState;
+use crate::clip_processor::{self, HwAccelStatus};
+use crate::commands::errors::Error;
+use crate::diagnostics;
+use crate::health::SubsystemHealth;
+use crate::metrics::{self, MetricSummary};
+use crate::readiness::{self, CheckResult};
+use crate::scheduler::JobStatus;
+use std::path::PathBuf;
+use tauri::State;
+
+/// Get the last lines of the app log (redacted), oldest first
+#[tauri::command]
+#[metrics::timed]
+pub async fn get_recent_logs(app: tauri::AppHandle, lines: Option<usize>) -> Result<Vec<String>, Error> {
+    let lines = lines.unwrap_or(200).clamp(1, diagnostics::MAX_RECENT_LOG_LINES);
+    let log_dir = diagnostics::log_directory(&app)?;
+    tauri::async_runtime::spawn_blocking(move || diagnostics::recent_logs(&log_dir, lines))
+        .await
+        .map_err(|e| Error::RecordingFailed(format!("Failed to read logs: {}", e)))
+}
+
+/// Zip the logs, redacted settings and system info for a bug report
+#[tauri::command]
+#[metrics::timed]
+pub async fn export_diagnostics(app: tauri::AppHandle, dest_path: String) -> Result<(), Error> {
+    diagnostics::export_diagnostics(&app, &PathBuf::from(dest_path)).await
+}
+
+/// Get the health of the watcher, recorder, sync and stats subsystems
+#[tauri::command]
+#[metrics::timed]
+pub fn get_health(state: State<'_, AppState>) -> Vec<SubsystemHealth> {
+    state.health.snapshot()
+}
+
+/// Get the timings of commands and background jobs since the app started
+#[tauri::command]
+#[metrics::timed]
+pub fn get_command_metrics(state: State<'_, AppState>) -> Vec<MetricSummary> {
+    state.metrics.snapshot()
+}
+
+/// Get the version of the stable command payloads (recordings, stats, clips)
+/// and their JSON schemas, for tools outside the app
+#[tauri::command]
+#[metrics::timed]
+pub fn get_api_info() -> ApiInfo {
+    api::api_info()
+}
+
+/// Get whether FFmpeg decodes with the GPU, and with which decoder
+#[tauri::command]
+#[metrics::timed]
+pub fn get_hwaccel_status() -> HwAccelStatus {
+    clip_processor::hwaccel_status()
+}
+
+/// Check everything auto-recording needs before a session
+#[tauri::command]
+#[metrics::timed]
+pub async fn run_readiness_check(app: tauri::AppHandle) -> Vec<CheckResult> {
+    readiness::run(&app).await
+}
+
+/// Get the background jobs, their conditions and how their last runs went
+#[tauri::command]
+#[metrics::timed]
+pub fn list_scheduled_jobs(state: State<'_, AppState>) -> Vec<JobStatus> {
+    state.scheduler.list()
+}
+
+/// Start a background job now, whether or not it's due or its conditions hold
+#[tauri::command]
+#[metrics::timed]
+pub fn run_job_now(app: tauri::AppHandle, state: State<'_, AppState>, name: String) -> Result<JobStatus, Error> {
+    state.scheduler.run_now(&app, &name)
+}
@@ -1,3 +1,18 @@
+//! Errors returned by commands
+//!
+//! Errors reach the frontend as `{ name, message, code, details? }`. `name`
+//! and `message` are what they have always been; `code` is a stable,
+//! machine-readable identifier the frontend maps to a localized message and
+//! a suggested fix, and `details` carries variant data such as FFmpeg's last
+//! output lines or the disk space needed.
+
+use crate::library::PathProblem;
+use serde::Serialize;
+use serde_json::json;
+
+/// Lines of FFmpeg's stderr kept in Error::Ffmpeg
+const STDERR_TAIL_LINES: usize = 5;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
@@ -16,38 +31,325 @@ pub enum Error {
     WindowNotFound,
     #[error("Recording failed: {0}")]
     RecordingFailed(String),
+    #[error("OBS: {0}")]
+    Obs(String),
+    #[error(transparent)]
+    Migration(#[from] crate::database::MigrationError),
+    /// FFmpeg or ffprobe failed (or couldn't be run)
+    #[error("FFmpeg: {message}")]
+    Ffmpeg { message: String, stderr_tail: Option<String> },
+    #[error("Database error: {message}")]
+    Database { kind: DatabaseErrorKind, message: String },
+    /// A replay that can't be read
+    #[error("Couldn't read the replay: {reason}")]
+    SlpParse { reason: String },
+    /// A video or audio file ffprobe can't read (corrupt, or not media)
+    #[error("Couldn't read {path}: {reason}")]
+    MediaUnreadable { path: String, reason: String },
+    /// Audio was asked of a video without an audio stream
+    #[error("{path} has no audio")]
+    NoAudio { path: String },
+    /// A crop that doesn't fit in the video (by more than rounding)
+    #[error("Crop {width}x{height} at ({x}, {y}) doesn't fit in the {video_width}x{video_height} video")]
+    CropOutOfBounds { x: u32, y: u32, width: u32, height: u32, video_width: u32, video_height: u32 },
+    /// Capturing the game window or screen failed
+    #[error("Window capture failed: {0}")]
+    WindowCapture(String),
+    /// The window closed before it could be captured
+    #[error("Window is gone: {identifier}")]
+    WindowGone { identifier: String },
+    /// Not enough free space for the operation (in bytes)
+    #[error("Not enough disk space: {} MB needed, {} MB free", needed / (1024 * 1024), available / (1024 * 1024))]
+    DiskFull { needed: u64, available: u64 },
+    /// A folder from settings that can't be used (see library::check_dir)
+    #[error("{problem}: {path}")]
+    PathUnavailable { setting: &'static str, path: String, problem: PathProblem },
+    /// run_job_now was given a name no job is registered under
+    #[error("No scheduled job named {name}")]
+    UnknownJob { name: String },
 }
 
-#[derive(serde::Serialize)]
-#[serde(tag = "name", content = "message")]
-#[serde(rename_all = "camelCase")]
-enum ErrorName {
-    Io(String),
-    FromUtf8Error(String),
-    WatchError(String),
-    InvalidPath(String),
-    UnsupportedPlatform(String),
-    InitializationError(String),
-    WindowNotFound(String),
-    RecordingFailed(String),
+/// What kind of database failure, for choosing a suggested fix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DatabaseErrorKind {
+    /// Another process (or a long query) holds the database
+    Busy,
+    /// The database file is damaged
+    Corrupt,
+    /// The disk is full
+    Full,
+    /// A write conflicted with existing data
+    Constraint,
+    /// An expected row wasn't there
+    NotFound,
+    Other,
+}
+
+impl Error {
+    /// A failed FFmpeg run, keeping the end of its stderr (which says why)
+    pub fn ffmpeg(message: impl Into<String>, stderr: &[u8]) -> Self {
+        let stderr = String::from_utf8_lossy(stderr);
+        let lines: Vec<&str> = stderr.lines().filter(|line| !line.trim().is_empty()).collect();
+        let tail = lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..].join("\n");
+        Self::Ffmpeg { message: message.into(), stderr_tail: Some(tail).filter(|t| !t.is_empty()) }
+    }
+
+    /// A database failure, with what was being done
+    pub fn database(context: &str, e: rusqlite::Error) -> Self {
+        match Self::from(e) {
+            Self::Database { kind, message } => Self::Database { kind, message: format!("{}: {}", context, message) },
+            other => other,
+        }
+    }
+
+    /// Stable identifier the frontend maps to a message and suggested fix
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io(e) => match e.kind() {
+                std::io::ErrorKind::NotFound => "file_not_found",
+                std::io::ErrorKind::PermissionDenied => "permission_denied",
+                std::io::ErrorKind::StorageFull => "disk_full",
+                _ => "io_failed",
+            },
+            Self::Utf8(_) => "invalid_text",
+            Self::WatchError(_) => "watch_failed",
+            Self::InvalidPath(_) => "invalid_path",
+            Self::UnsupportedPlatform => "unsupported_platform",
+            Self::InitializationError(_) => "initialization_failed",
+            Self::WindowNotFound => "window_not_found",
+            Self::RecordingFailed(_) => "recording_failed",
+            Self::Obs(_) => "obs_failed",
+            Self::Migration(_) => "database_migration_failed",
+            Self::Ffmpeg { .. } => "ffmpeg_failed",
+            Self::Database { kind, .. } => match kind {
+                DatabaseErrorKind::Busy => "database_busy",
+                DatabaseErrorKind::Corrupt => "database_corrupt",
+                DatabaseErrorKind::Full => "disk_full",
+                DatabaseErrorKind::Constraint => "database_conflict",
+                DatabaseErrorKind::NotFound => "not_found",
+                DatabaseErrorKind::Other => "database_failed",
+            },
+            Self::SlpParse { .. } => "replay_unreadable",
+            Self::MediaUnreadable { .. } => "media_unreadable",
+            Self::NoAudio { .. } => "no_audio",
+            Self::CropOutOfBounds { .. } => "crop_out_of_bounds",
+            Self::WindowCapture(_) => "window_capture_failed",
+            Self::WindowGone { .. } => "window_gone",
+            Self::DiskFull { .. } => "disk_full",
+            Self::PathUnavailable { problem, .. } => match problem {
+                PathProblem::VolumeNotMounted => "volume_not_mounted",
+                PathProblem::NotWritable => "path_not_writable",
+                PathProblem::NotADirectory => "not_a_directory",
+            },
+            Self::UnknownJob { .. } => "unknown_job",
+        }
+    }
+
+    /// Serialized name, unchanged from before codes were added
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Io(_) => "io",
+            Self::Utf8(_) => "fromUtf8Error",
+            Self::WatchError(_) => "watchError",
+            Self::InvalidPath(_) => "invalidPath",
+            Self::UnsupportedPlatform => "unsupportedPlatform",
+            Self::InitializationError(_) => "initializationError",
+            Self::WindowNotFound => "windowNotFound",
+            Self::RecordingFailed(_) => "recordingFailed",
+            Self::Obs(_) => "obsError",
+            Self::Migration(_) => "migrationError",
+            Self::Ffmpeg { .. } => "ffmpegError",
+            Self::Database { .. } => "databaseError",
+            Self::SlpParse { .. } => "slpParseError",
+            Self::MediaUnreadable { .. } => "mediaUnreadable",
+            Self::NoAudio { .. } => "noAudio",
+            Self::CropOutOfBounds { .. } => "cropOutOfBounds",
+            Self::WindowCapture(_) => "windowCaptureError",
+            Self::WindowGone { .. } => "windowGone",
+            Self::DiskFull { .. } => "diskFull",
+            Self::PathUnavailable { .. } => "pathUnavailable",
+            Self::UnknownJob { .. } => "unknownJob",
+        }
+    }
+
+    fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            Self::Ffmpeg { stderr_tail, .. } => Some(json!({ "stderrTail": stderr_tail })),
+            Self::Database { kind, .. } => Some(json!({ "kind": kind })),
+            Self::SlpParse { reason } => Some(json!({ "reason": reason })),
+            Self::MediaUnreadable { path, reason } => Some(json!({ "path": path, "reason": reason })),
+            Self::NoAudio { path } => Some(json!({ "path": path })),
+            Self::CropOutOfBounds { x, y, width, height, video_width, video_height } => Some(json!({
+                "crop": { "x": x, "y": y, "width": width, "height": height },
+                "videoWidth": video_width,
+                "videoHeight": video_height,
+            })),
+            Self::WindowGone { identifier } => Some(json!({ "identifier": identifier })),
+            Self::DiskFull { needed, available } => Some(json!({ "needed": needed, "available": available })),
+            Self::PathUnavailable { setting, path, problem } => {
+                Some(json!({ "setting": setting, "path": path, "problem": problem }))
+            }
+            Self::UnknownJob { name } => Some(json!({ "name": name })),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        use rusqlite::ErrorCode;
+        let kind = match &e {
+            rusqlite::Error::QueryReturnedNoRows => DatabaseErrorKind::NotFound,
+            rusqlite::Error::SqliteFailure(failure, _) => match failure.code {
+                ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked => DatabaseErrorKind::Busy,
+                ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase => DatabaseErrorKind::Corrupt,
+                ErrorCode::DiskFull => DatabaseErrorKind::Full,
+                ErrorCode::ConstraintViolation => DatabaseErrorKind::Constraint,
+                _ => DatabaseErrorKind::Other,
+            },
+            _ => DatabaseErrorKind::Other,
+        };
+        Self::Database { kind, message: e.to_string() }
+    }
 }
 
-impl serde::Serialize for Error {
+impl From<crate::slippi::integrity::ReplayProblem> for Error {
+    fn from(problem: crate::slippi::integrity::ReplayProblem) -> Self {
+        Self::SlpParse { reason: problem.detail }
+    }
+}
+
+#[derive(Serialize)]
+struct SerializedError {
+    name: &'static str,
+    message: String,
+    code: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<serde_json::Value>,
+}
+
+impl Serialize for Error {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::ser::Serializer,
     {
-        let message = self.to_string();
-        let name = match self {
-            Self::Io(_) => ErrorName::Io(message),
-            Self::Utf8(_) => ErrorName::FromUtf8Error(message),
-            Self::WatchError(_) => ErrorName::WatchError(message),
-            Self::InvalidPath(_) => ErrorName::InvalidPath(message),
-            Self::UnsupportedPlatform => ErrorName::UnsupportedPlatform(message),
-            Self::InitializationError(_) => ErrorName::InitializationError(message),
-            Self::WindowNotFound => ErrorName::WindowNotFound(message),
-            Self::RecordingFailed(_) => ErrorName::RecordingFailed(message),
+        SerializedError {
+            name: self.name(),
+            message: self.to_string(),
+            code: self.code(),
+            details: self.details(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn serialized(error: Error) -> Value {
+        serde_json::to_value(&error).unwrap()
+    }
+
+    #[test]
+    fn test_existing_variants_keep_their_shape() {
+        let cases = [
+            (Error::Io(std::io::Error::new(std::io::ErrorKind::NotFound, "gone")), "io", "file_not_found"),
+            (Error::Utf8(String::from_utf8(vec![0xff]).unwrap_err()), "fromUtf8Error", "invalid_text"),
+            (Error::WatchError("x".into()), "watchError", "watch_failed"),
+            (Error::InvalidPath("x".into()), "invalidPath", "invalid_path"),
+            (Error::UnsupportedPlatform, "unsupportedPlatform", "unsupported_platform"),
+            (Error::InitializationError("x".into()), "initializationError", "initialization_failed"),
+            (Error::WindowNotFound, "windowNotFound", "window_not_found"),
+            (Error::RecordingFailed("x".into()), "recordingFailed", "recording_failed"),
+            (Error::Obs("x".into()), "obsError", "obs_failed"),
+            (
+                Error::Migration(crate::database::MigrationError::Backup("read-only".into())),
+                "migrationError",
+                "database_migration_failed",
+            ),
+        ];
+        for (error, name, code) in cases {
+            let message = error.to_string();
+            assert_eq!(serialized(error), json!({ "name": name, "message": message, "code": code }));
+        }
+    }
+
+    #[test]
+    fn test_new_variants_carry_details() {
+        let ffmpeg = Error::ffmpeg("Clip extraction failed", b"frame=1\n\n[mp4] moov atom not found\nInvalid data\n");
+        assert_eq!(
+            serialized(ffmpeg),
+            json!({
+                "name": "ffmpegError",
+                "message": "FFmpeg: Clip extraction failed",
+                "code": "ffmpeg_failed",
+                "details": { "stderrTail": "frame=1\n[mp4] moov atom not found\nInvalid data" },
+            })
+        );
+
+        let busy = rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(5), Some("database is locked".into()));
+        let database = serialized(Error::database("Failed to save combos", busy));
+        assert_eq!(database["name"], "databaseError");
+        assert_eq!(database["code"], "database_busy");
+        assert_eq!(database["details"], json!({ "kind": "busy" }));
+        assert_eq!(database["message"], "Database error: Failed to save combos: database is locked");
+        assert_eq!(serialized(Error::from(rusqlite::Error::QueryReturnedNoRows))["code"], "not_found");
+
+        let replay = serialized(Error::SlpParse { reason: "The game never ended".into() });
+        assert_eq!(replay["code"], "replay_unreadable");
+        assert_eq!(replay["details"], json!({ "reason": "The game never ended" }));
+
+        let media = serialized(Error::MediaUnreadable { path: "a.mp4".into(), reason: "moov atom not found".into() });
+        assert_eq!(media["code"], "media_unreadable");
+        assert_eq!(media["details"], json!({ "path": "a.mp4", "reason": "moov atom not found" }));
+
+        let silent = serialized(Error::NoAudio { path: "a.mp4".into() });
+        assert_eq!(silent["code"], "no_audio");
+        assert_eq!(silent["details"], json!({ "path": "a.mp4" }));
+
+        let crop = serialized(Error::CropOutOfBounds { x: 0, y: 0, width: 2000, height: 1080, video_width: 1920, video_height: 1080 });
+        assert_eq!(crop["code"], "crop_out_of_bounds");
+        assert_eq!(crop["message"], "Crop 2000x1080 at (0, 0) doesn't fit in the 1920x1080 video");
+        assert_eq!(
+            crop["details"],
+            json!({ "crop": { "x": 0, "y": 0, "width": 2000, "height": 1080 }, "videoWidth": 1920, "videoHeight": 1080 })
+        );
+
+        let capture = serialized(Error::WindowCapture("Access denied".into()));
+        assert_eq!(
+            capture,
+            json!({ "name": "windowCaptureError", "message": "Window capture failed: Access denied", "code": "window_capture_failed" })
+        );
+
+        let gone = serialized(Error::WindowGone { identifier: "Slippi Dolphin (PID: 42)".into() });
+        assert_eq!(gone["code"], "window_gone");
+        assert_eq!(gone["details"], json!({ "identifier": "Slippi Dolphin (PID: 42)" }));
+
+        let disk = serialized(Error::DiskFull { needed: 2048 * 1024 * 1024, available: 512 * 1024 * 1024 });
+        assert_eq!(disk["name"], "diskFull");
+        assert_eq!(disk["code"], "disk_full");
+        assert_eq!(disk["message"], "Not enough disk space: 2048 MB needed, 512 MB free");
+        assert_eq!(disk["details"], json!({ "needed": 2048u64 * 1024 * 1024, "available": 512u64 * 1024 * 1024 }));
+
+        let unplugged = Error::PathUnavailable {
+            setting: "recordingPath",
+            path: "E:\\Recordings".into(),
+            problem: PathProblem::VolumeNotMounted,
         };
-        name.serialize(serializer)
+        let unplugged = serialized(unplugged);
+        assert_eq!(unplugged["code"], "volume_not_mounted");
+        assert_eq!(unplugged["message"], "The drive this folder is on isn't connected: E:\\Recordings");
+        assert_eq!(
+            unplugged["details"],
+            json!({ "setting": "recordingPath", "path": "E:\\Recordings", "problem": "volume_not_mounted" })
+        );
+
+        let job = serialized(Error::UnknownJob { name: "backup".into() });
+        assert_eq!(job["code"], "unknown_job");
+        assert_eq!(job["message"], "No scheduled job named backup");
+        assert_eq!(job["details"], json!({ "name": "backup" }));
     }
 }
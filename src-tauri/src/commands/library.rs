@@ -4,14 +4,35 @@
 
 use crate::app_state::AppState;
 use crate::commands::errors::Error;
-use crate::database::{self, AggregatedPlayerStats, StatsFilter, AvailableFilterOptions};
-use crate::slippi::{PlayerInfo, RecordingSession, SlippiMetadata};
+use crate::commands::settings::{my_identity, stats_exclusion, warmup_settings};
+use crate::deep_link::NavigateTarget;
+use crate::event_batch;
+use crate::events::library as library_events;
+use crate::game_detector::suspension;
+use crate::database::{self, AggregatedPlayerStats, InputQuality, StatsFilter, AvailableFilterOptions, PlayerScope};
+use crate::health::{self, Subsystem};
+use crate::highlights::{self, Highlight};
+use crate::inputs;
+use crate::library;
+use crate::metrics;
+use crate::overlay::{OverlayGameResult, OverlayPlayer};
+use crate::play_session::{self, SessionGame, SessionSummary};
+use crate::slippi::characters;
+use crate::slippi::melee_ids;
+use crate::slippi::game_start::{read_game_start, GameStart};
+use crate::slippi::frames::FrameSelection;
+use crate::slippi::integrity::{self, ParseErrorKind};
+use crate::slippi::{GameResult, PlayerInfo, RecordingSession, ReplayVersion, SlippiMetadata};
+use crate::weekly_report;
+use crate::work_gate::JobKind;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
-use tauri::State;
+use std::time::Instant;
+use tauri::{Manager, State};
 
 /// Response for paginated recordings
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct PaginatedRecordings {
     pub recordings: Vec<RecordingSession>,
     pub total: i32,
@@ -23,6 +44,7 @@ pub struct PaginatedRecordings {
 /// Get list of recorded sessions with pagination
 /// Returns cached data from SQLite for instant response
 #[tauri::command]
+#[metrics::timed]
 pub async fn get_recordings(
     page: Option<i32>,
     per_page: Option<i32>,
@@ -31,25 +53,25 @@ pub async fn get_recordings(
     let page = page.unwrap_or(1).max(1);
     let per_page = per_page.unwrap_or(20).clamp(1, 100);
     let offset = (page - 1) * per_page;
-    
+
     log::debug!("📂 Loading recordings from cache (page {}, {} per page)", page, per_page);
-    
+
     let db = state.database.clone();
     let conn = db.connection();
-    
+
     let (rows, total) = database::get_recordings_paginated(&conn, per_page, offset)
-        .map_err(|e| Error::InitializationError(format!("Database error: {}", e)))?;
-    
+        .map_err(Error::from)?;
+
     // Convert database rows to RecordingSession
     let recordings: Vec<RecordingSession> = rows
         .into_iter()
         .map(recording_with_stats_to_session)
         .collect();
-    
+
     let total_pages = (total as f64 / per_page as f64).ceil() as i32;
-    
+
     log::info!("✅ Loaded {} recording(s) from cache (page {}/{})", recordings.len(), page, total_pages);
-    
+
     Ok(PaginatedRecordings {
         recordings,
         total,
@@ -59,64 +81,370 @@ pub async fn get_recordings(
     })
 }
 
+/// Get a single recording by id (for views opened outside the paginated list)
+#[tauri::command]
+#[metrics::timed]
+pub async fn get_recording(
+    recording_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<RecordingSession>, Error> {
+    let db = state.database.clone();
+    let conn = db.connection();
+
+    let Some(row) = database::get_recording_by_id(&conn, &recording_id).map_err(Error::from)? else {
+        return Ok(None);
+    };
+    let game_stats = database::get_game_stats(&conn, &recording_id).map_err(Error::from)?;
+    let player_stats = database::get_player_stats_by_recording(&conn, &recording_id).map_err(Error::from)?;
+    Ok(Some(recording_row_to_session(row, game_stats, player_stats)))
+}
+
+/// Games in a recording and where each starts in the video: one per game of a
+/// per-set recording, or just its replay at 0 for a single game. Offsets
+/// include the recording's video offset, so replay times map onto the video
+#[tauri::command]
+#[metrics::timed]
+pub fn get_recording_games(
+    recording_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<database::RecordingGame>, Error> {
+    let conn = state.database.connection();
+    let video_offset_secs = database::get_recording_by_id(&conn, &recording_id)
+        .map_err(Error::from)?
+        .and_then(|row| row.video_offset_ms)
+        .map_or(0.0, |ms| ms as f64 / 1000.0);
+    let mut games = database::get_recording_games(&conn, &recording_id).map_err(Error::from)?;
+    for game in &mut games {
+        game.offset_secs -= video_offset_secs;
+    }
+    Ok(games)
+}
+
+/// Correct a recording's video offset by hand, for when the clocks it was
+/// measured with were skewed: how many milliseconds the video trails its
+/// replay (negative if it leads)
+#[tauri::command]
+#[metrics::timed]
+pub fn calibrate_offset(
+    recording_id: String,
+    offset_ms: i64,
+    state: State<'_, AppState>,
+) -> Result<(), Error> {
+    let conn = state.database.connection();
+    if database::get_recording_by_id(&conn, &recording_id).map_err(Error::from)?.is_none() {
+        return Err(Error::InvalidPath(format!("Recording not found: {}", recording_id)));
+    }
+    database::set_recording_video_offset(&conn, &recording_id, Some(offset_ms)).map_err(Error::from)?;
+    log::info!("⏱ Video offset of {} set to {}ms", recording_id, offset_ms);
+    Ok(())
+}
+
 /// Get list of all clips (clips don't use pagination yet, they're usually fewer)
 #[tauri::command]
+#[metrics::timed]
 pub async fn get_clips(
     state: State<'_, AppState>,
 ) -> Result<Vec<RecordingSession>, Error> {
     log::debug!("📂 Loading clips from cache...");
-    
+
     let db = state.database.clone();
     let conn = db.connection();
-    
+
     // Get all recordings and filter to clips (those in Clips folder)
     let all = database::get_all_recordings(&conn)
-        .map_err(|e| Error::InitializationError(format!("Database error: {}", e)))?;
-    
+        .map_err(Error::from)?;
+    let favorites = database::get_favorite_clip_ids(&conn).map_err(Error::from)?;
+
     let clips: Vec<RecordingSession> = all
         .into_iter()
-        .filter(|row| row.video_path.contains("Clips"))
-        .map(|row| recording_row_to_session(row, None, Vec::new()))
+        .filter(|row| row.video_path.as_deref().is_some_and(|path| path.contains("Clips")))
+        .map(|row| {
+            let is_favorite = favorites.contains(&row.id);
+            RecordingSession { is_favorite, ..recording_row_to_session(row, None, Vec::new()) }
+        })
         .collect();
-    
+
     log::info!("✅ Found {} clip(s)", clips.len());
     Ok(clips)
 }
 
 /// Delete a recording (video file and cache entry)
 #[tauri::command]
+#[metrics::timed]
 pub async fn delete_recording(
+    app: tauri::AppHandle,
     video_path: Option<String>,
     _slp_path: String,
-    state: State<'_, AppState>,
 ) -> Result<(), Error> {
-    if let Some(ref video) = video_path {
-        if !video.is_empty() {
-            let db = state.database.clone();
-            let conn = db.connection();
-            
-            // Look up by video path and delete from cache
-            if let Ok(Some(recording)) = database::get_recording_by_video_path(&conn, video) {
-                let _ = database::delete_recording(&conn, &recording.id);
-                log::debug!("🗑️ Removed {} from cache", recording.id);
+    match video_path {
+        Some(video) if !video.is_empty() => delete_video(&app, &video),
+        _ => Ok(()),
+    }
+}
+
+/// Delete a video's file, then remove it from the cache (with its cached
+/// analysis and thumbnail). Deleting a clip, by hand or over the Clips folder
+/// cap, goes through here too
+pub(crate) fn delete_video(app: &tauri::AppHandle, video: &str) -> Result<(), Error> {
+    // The file goes first: if it can't be deleted, the row still tracks it
+    if std::path::Path::new(video).exists() {
+        std::fs::remove_file(video)?;
+        log::info!("✅ Deleted video: {}", video);
+    }
+    let _ = std::fs::remove_file(library::sidecar_path(std::path::Path::new(video)));
+
+    {
+        let state = app.state::<AppState>();
+        let conn = state.database.connection();
+
+        // Look up by video path and delete from cache
+        if let Ok(Some(recording)) = database::get_recording_by_video_path(&conn, video) {
+            let _ = database::delete_recording(&conn, &recording.id);
+            // Its session may have lost its first game
+            if let Err(e) = database::recompute_warmups(&conn, &warmup_settings(app)) {
+                log::warn!("Failed to flag warm-up games: {}", e);
             }
-            
-            // Delete the actual file
-            if std::path::Path::new(video).exists() {
-                std::fs::remove_file(video)
-                    .map_err(|e| Error::RecordingFailed(format!("Failed to delete video: {}", e)))?;
-                log::info!("✅ Deleted video: {}", video);
+            let _ = library::invalidate_analysis(
+                &library::get_analysis_cache_directory(app),
+                Some(&recording.id),
+            );
+            if let Some(thumbnail) = &recording.thumbnail_path {
+                let _ = std::fs::remove_file(thumbnail);
             }
+            log::debug!("🗑️ Removed {} from cache", recording.id);
         }
     }
     Ok(())
 }
 
+/// Disk usage of recordings and clips, with the Clips folder cap
+#[tauri::command]
+#[metrics::timed]
+pub async fn get_storage_report(app: tauri::AppHandle) -> Result<library::StorageReport, Error> {
+    library::storage_report(&app).await
+}
+
+/// Find recordings of the same game (from the database, nothing is read from disk)
+#[tauri::command]
+#[metrics::timed]
+pub async fn find_duplicate_recordings(app: tauri::AppHandle) -> Result<Vec<library::DuplicateSet>, Error> {
+    library::find_duplicates(&app)
+}
+
+/// Result of resolve_duplicates
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateCleanup {
+    pub deleted: usize,
+    pub freed_bytes: u64,
+}
+
+/// Delete the copies the user chose not to keep. Duplicates are found again
+/// first, and nothing is deleted if any resolution names a recording that
+/// isn't a copy of the one it keeps, or one another resolution keeps
+#[tauri::command]
+#[metrics::timed]
+pub async fn resolve_duplicates(
+    app: tauri::AppHandle,
+    resolutions: Vec<library::DuplicateResolution>,
+) -> Result<DuplicateCleanup, Error> {
+    let _paused = suspension::suspend(&app, "deleting duplicates");
+    let sets = library::find_duplicates(&app)?;
+    let copies = library::copies_to_delete(&sets, &resolutions)?;
+
+    let mut cleanup = DuplicateCleanup::default();
+    for copy in copies {
+        let size = std::fs::metadata(&copy.video_path).map(|m| m.len()).unwrap_or(0);
+        delete_video(&app, &copy.video_path)?;
+        cleanup.deleted += 1;
+        cleanup.freed_bytes += size;
+    }
+    log::info!("🧹 Deleted {} duplicate recording(s)", cleanup.deleted);
+    Ok(cleanup)
+}
+
 /// Manually trigger a cache refresh
 #[tauri::command]
+#[metrics::timed]
 pub async fn refresh_recordings_cache(app: tauri::AppHandle) -> Result<(), Error> {
     log::info!("🔄 Manual cache refresh triggered");
-    crate::library::sync_recordings_cache(&app).await
+    crate::library::sync_recordings_cache(&app).await?;
+    library::spawn_media_probe(&app);
+    Ok(())
+}
+
+/// Re-read one recording from disk after its video was replaced outside the
+/// app, without a full sync. With reparse its replay's stats are extracted again
+#[tauri::command]
+#[metrics::timed]
+pub async fn refresh_recording(
+    app: tauri::AppHandle,
+    recording_id: String,
+    reparse: Option<bool>,
+) -> Result<database::RecordingWithStats, Error> {
+    library::refresh_recording(&app, &recording_id, reparse.unwrap_or(false)).await
+}
+
+/// Export a recording with its replay, stats and clip list as a zip bundle
+/// Clips cut from the game are included unless include_clips is false
+#[tauri::command]
+#[metrics::timed]
+pub async fn export_recording_bundle(
+    app: tauri::AppHandle,
+    recording_id: String,
+    dest_path: String,
+    include_clips: Option<bool>,
+) -> Result<(), Error> {
+    library::export_recording_bundle(&app, &recording_id, Path::new(&dest_path), include_clips.unwrap_or(true)).await
+}
+
+/// Export a recording's per-frame player data next to its video, one row per
+/// (frame, port). Only `ports` (0-indexed, all by default) and every
+/// `every_nth` frame (every frame by default) are kept
+#[tauri::command]
+#[metrics::timed]
+pub async fn export_frames(
+    app: tauri::AppHandle,
+    recording_id: String,
+    format: library::FrameExportFormat,
+    ports: Option<Vec<u8>>,
+    every_nth: Option<u32>,
+) -> Result<library::FrameExport, Error> {
+    let selection = FrameSelection { ports: ports.unwrap_or_default(), every_nth: every_nth.unwrap_or(1) };
+    library::export_frames(&app, &recording_id, format, selection).await
+}
+
+/// Write a copy of a recording's replay with the players' names and connect
+/// codes replaced by placeholders, to the temp folder, for sharing. Frame
+/// data is untouched
+#[tauri::command]
+#[metrics::timed]
+pub async fn export_anonymized_replay(
+    app: tauri::AppHandle,
+    recording_id: String,
+) -> Result<library::AnonymizedReplay, Error> {
+    library::export_anonymized_replay(&app, &recording_id).await
+}
+
+/// Import a bundle made by export_recording_bundle into the library
+/// Returns the id of the imported recording
+#[tauri::command]
+#[metrics::timed]
+pub async fn import_recording_bundle(app: tauri::AppHandle, bundle_path: String) -> Result<String, Error> {
+    let recording = library::import_recording_bundle(&app, Path::new(&bundle_path)).await?;
+    app.state::<AppState>().stats_cache.invalidate();
+    Ok(recording.id)
+}
+
+/// Import stats computed by another tool: Slippi Launcher's replay database
+/// or a folder of slippi-js getStats() dumps. Games that already have stats
+/// are left alone
+#[tauri::command]
+#[metrics::timed]
+pub async fn import_external_stats(
+    app: tauri::AppHandle,
+    source: library::ExternalStatsSource,
+    path: String,
+) -> Result<library::ExternalImportReport, Error> {
+    library::import_external_stats(&app, source, Path::new(&path)).await
+}
+
+/// List recordings at least older_than_days old that can be archived
+#[tauri::command]
+#[metrics::timed]
+pub async fn list_archive_candidates(
+    app: tauri::AppHandle,
+    older_than_days: u32,
+) -> Result<Vec<library::ArchiveCandidate>, Error> {
+    // Recordings on an unplugged drive would be left out without a word
+    library::get_recording_directory(&app).await?;
+    library::archive_candidates(&app, older_than_days)
+}
+
+/// Archive recordings to 720p HEVC, replacing the originals once the new files
+/// are verified. Reports the space freed per file and in total
+#[tauri::command]
+#[metrics::timed]
+pub async fn archive_recordings(
+    app: tauri::AppHandle,
+    recording_ids: Vec<String>,
+) -> Result<library::ArchiveReport, Error> {
+    tauri::async_runtime::spawn_blocking(move || library::archive_recordings(&app, &recording_ids))
+        .await
+        .map_err(|e| Error::RecordingFailed(format!("Archive task failed: {}", e)))?
+}
+
+/// Get the cached analysis for a recording, if it was computed from the same
+/// .slp contents by the same analysis version
+#[tauri::command]
+#[metrics::timed]
+pub async fn get_cached_analysis(
+    app: tauri::AppHandle,
+    recording_id: String,
+    slp_path: String,
+    analysis_version: u32,
+) -> Result<Option<serde_json::Value>, Error> {
+    library::read_cached_analysis(
+        &library::get_analysis_cache_directory(&app),
+        &recording_id,
+        Path::new(&slp_path),
+        analysis_version,
+    )
+}
+
+/// Cache the analysis computed by the frontend for a recording
+#[tauri::command]
+#[metrics::timed]
+pub async fn save_cached_analysis(
+    app: tauri::AppHandle,
+    recording_id: String,
+    slp_path: String,
+    analysis_version: u32,
+    analysis: serde_json::Value,
+) -> Result<(), Error> {
+    library::write_cached_analysis(
+        &library::get_analysis_cache_directory(&app),
+        &recording_id,
+        Path::new(&slp_path),
+        analysis_version,
+        analysis,
+    )
+}
+
+/// Delete the cached analysis for one recording, or every recording if no ID is given
+/// Returns the number of entries removed
+#[tauri::command]
+#[metrics::timed]
+pub async fn invalidate_analysis_cache(
+    app: tauri::AppHandle,
+    recording_id: Option<String>,
+) -> Result<usize, Error> {
+    let removed = library::invalidate_analysis(
+        &library::get_analysis_cache_directory(&app),
+        recording_id.as_deref(),
+    )?;
+    log::info!("🗑️ Invalidated {} analysis cache entries", removed);
+    Ok(removed)
+}
+
+/// Work out every saved game's winner and loser again from its players'
+/// stats, keeping them by connect code. Fixes results attributed to the wrong
+/// player when ports changed between games of a set. Returns how many changed
+#[tauri::command]
+#[metrics::timed]
+pub async fn repair_game_results(state: State<'_, AppState>) -> Result<usize, Error> {
+    let repaired = database::repair_game_results(&state.database.connection())
+        .map_err(|e| Error::database("Failed to repair game results", e))?;
+    state.stats_cache.invalidate();
+    log::info!("🔧 Repaired the results of {} game(s)", repaired);
+    Ok(repaired)
+}
+
+/// Report pending database migrations and their risk without applying them
+#[tauri::command]
+#[metrics::timed]
+pub fn check_migrations(state: State<'_, AppState>) -> database::MigrationStatus {
+    state.database.check_migrations()
 }
 
 // ============================================================================
@@ -134,6 +462,9 @@ pub struct ComputedGameStats {
     pub stage: i32,
     pub game_duration: i32,
     pub total_frames: i32,
+    /// Frames the game was paused for (frozen frames slippi-js still wrote)
+    #[serde(default)]
+    pub total_paused_frames: Option<i32>,
     pub is_pal: bool,
     pub played_on: Option<String>,
     pub match_id: Option<String>,
@@ -146,9 +477,39 @@ pub struct ComputedGameStats {
     pub winner_index: Option<i32>,
     pub loser_index: Option<i32>,
     pub game_end_method: Option<String>,
+    /// Whether the replay reaches Game End; crashes and quit-outs don't
+    pub completed: bool,
+    
+    /// Version of the frontend stats calculation (STATS_VERSION in slippi-stats.ts)
+    #[serde(default)]
+    pub stats_version: i32,
+    
+    /// Replay format version slippi-js read from Game Start (e.g. "3.16.0")
+    #[serde(default)]
+    pub replay_version: Option<String>,
     
     // Player stats
     pub players: Vec<ComputedPlayerStats>,
+    
+    /// Combos (slippi-js conversions), scored as highlights when saved
+    #[serde(default)]
+    pub combos: Vec<ComputedCombo>,
+}
+
+/// Combo computed by the frontend (slippi-js conversion)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComputedCombo {
+    /// Player who performed the combo
+    pub player_index: i32,
+    pub opponent_index: i32,
+    pub start_frame: i32,
+    pub end_frame: i32,
+    pub start_percent: f64,
+    pub end_percent: f64,
+    pub move_count: i32,
+    pub distinct_moves: i32,
+    pub did_kill: bool,
 }
 
 /// Computed player stats from the frontend (slippi-js)
@@ -190,51 +551,81 @@ pub struct ComputedPlayerStats {
     pub throw_count: i32,
     pub ground_tech_count: i32,
     pub wall_tech_count: i32,
-    pub wall_jump_tech_count: i32,
+    /// None when not computed (slippi-js doesn't count them)
+    pub wall_jump_tech_count: Option<i32>,
     
-    // L-Cancel stats
-    pub l_cancel_success_count: i32,
-    pub l_cancel_fail_count: i32,
+    // L-Cancel stats, None for replays older than ReplayVersion::L_CANCEL_STATUS
+    pub l_cancel_success_count: Option<i32>,
+    pub l_cancel_fail_count: Option<i32>,
     
     // Final state
     pub stocks_remaining: i32,
     pub final_percent: Option<f64>,
 }
 
+/// Wait until a background job of this kind may run (the frontend's stats
+/// pass asks before each replay). Returns whether it runs alongside a recording
+#[tauri::command]
+#[metrics::timed]
+pub async fn wait_for_job_slot(kind: JobKind, state: State<'_, AppState>) -> Result<bool, Error> {
+    Ok(state.work_gate.acquire(kind).await.is_throttled())
+}
+
 /// Save computed stats from slippi-js to the database.
 /// This is the SINGLE ENTRY POINT for saving game statistics.
 /// Creates/updates both game_stats and player_stats tables.
 #[tauri::command]
+#[metrics::timed]
 pub async fn save_computed_stats(
+    app: tauri::AppHandle,
     stats: ComputedGameStats,
     state: State<'_, AppState>,
 ) -> Result<(), Error> {
+    // Stats jobs run back to back from the frontend; a panic here should show up in get_health
+    let _subsystem = health::enter(Subsystem::Stats);
     log::info!("[SlippiStats] Saving computed stats for recording: {}", stats.recording_id);
-    
+
+    let identity = my_identity(&app);
+
+    // Zelda and Sheik can transform; they count as whichever was played
+    // most. Read before locking the database, as it reads every frame
+    let transforming: Vec<u8> = stats
+        .players
+        .iter()
+        .filter(|p| u8::try_from(p.character_id).is_ok_and(characters::can_transform))
+        .filter_map(|p| u8::try_from(p.player_index).ok())
+        .collect();
+    let played_characters = characters::dominant_characters(Path::new(&stats.slp_path), &transforming)
+        .unwrap_or_else(|e| {
+            log::warn!("[SlippiStats] Failed to read which character was played in {}: {}", stats.slp_path, e);
+            Default::default()
+        });
+
     let db = state.database.clone();
     let conn = db.connection();
-    
+
     // Get player info for game_stats
     let p1 = stats.players.get(0);
     let p2 = stats.players.get(1);
-    
+
     // Determine winner by stocks remaining:
     // 1. If one player has 0 stocks, the other wins
     // 2. If both have stocks, the one with MORE stocks wins
     // 3. If tied stocks, no winner (LRAS quit or timeout)
-    let (winner_port, loser_port) = if stats.players.len() == 2 {
+    // Kept by connect code as well as port: ports can change between games of a set
+    let (winner, loser) = if stats.players.len() == 2 {
         let player_a = &stats.players[0];
         let player_b = &stats.players[1];
-        
+
         let a_stocks = player_a.stocks_remaining;
         let b_stocks = player_b.stocks_remaining;
-        
+
         if a_stocks > b_stocks {
             // Player A has more stocks = winner
-            (Some(player_a.port), Some(player_b.port))
+            (Some(player_a), Some(player_b))
         } else if b_stocks > a_stocks {
             // Player B has more stocks = winner
-            (Some(player_b.port), Some(player_a.port))
+            (Some(player_b), Some(player_a))
         } else {
             // Tied stocks - no winner (probably LRAS quit with same stocks)
             log::warn!("[SlippiStats] No winner: tied stocks ({}) for {}", a_stocks, stats.recording_id);
@@ -244,7 +635,7 @@ pub async fn save_computed_stats(
         log::error!("[SlippiStats] Expected 2 players for {}, got {}", stats.recording_id, stats.players.len());
         (None, None)
     };
-    
+
     // Build and upsert game_stats (creates if missing, updates if exists)
     let game_stats = database::GameStatsRow {
         id: stats.recording_id.clone(),
@@ -256,8 +647,8 @@ pub async fn save_computed_stats(
         player2_character: p2.map(|p| p.character_id),
         player1_color: p1.map(|p| p.character_color),
         player2_color: p2.map(|p| p.character_color),
-        winner_port,
-        loser_port,
+        winner_port: winner.map(|p| p.port),
+        loser_port: loser.map(|p| p.port),
         stage: Some(stats.stage),
         game_duration: Some(stats.game_duration),
         total_frames: Some(stats.total_frames),
@@ -265,14 +656,33 @@ pub async fn save_computed_stats(
         played_on: stats.played_on.clone(),
         created_at: stats.created_at.clone(),
         slp_path: Some(stats.slp_path.clone()),
+        replay_version: stats.replay_version.clone(),
+        // Flagged once the players are saved
+        excluded_reason: None,
+        total_paused_frames: stats.total_paused_frames,
+        winner_code: winner.and_then(|p| p.connect_code.clone()),
+        loser_code: loser.and_then(|p| p.connect_code.clone()),
+        // Set once is_me is recomputed
+        my_result: database::MyResult::Unknown,
+        is_warmup: false,
+        stats_source: database::StatsSource::Buckwheat,
+        completed: stats.completed,
     };
-    
+
     database::upsert_game_stats(&conn, &game_stats)
-        .map_err(|e| Error::RecordingFailed(format!("Failed to save game stats: {}", e)))?;
-    
+        .map_err(|e| Error::database("Failed to save game stats", e))?;
+
     log::info!("[SlippiStats] Saved game_stats: stage={}, winner_port={:?}", 
-        stats.stage, winner_port);
-    
+        stats.stage, game_stats.winner_port);
+
+    // Player types aren't in slippi-js's stats; Game Start has them (by player index)
+    let cpu_indexes: Vec<i32> = read_game_start(Path::new(&stats.slp_path))
+        .map(|game| game.players.iter().filter(|p| p.is_cpu).map(|p| i32::from(p.port)).collect())
+        .unwrap_or_default();
+
+    // When the players were seen with these names, for their name history
+    let seen_at = stats.created_at.as_deref().and_then(crate::timestamps::normalize).unwrap_or_else(crate::timestamps::now);
+
     // Save player stats
     for player in &stats.players {
         let player_stats = database::PlayerStatsRow {
@@ -313,83 +723,525 @@ pub async fn save_computed_stats(
             stocks_remaining: player.stocks_remaining,
             final_percent: player.final_percent,
             slp_path: Some(stats.slp_path.clone()),
+            // Attributed below once all players are saved
+            is_me: false,
+            stats_version: stats.stats_version,
+            is_cpu: cpu_indexes.contains(&player.player_index),
         };
-        
+
         database::upsert_player_stats(&conn, &player_stats)
-            .map_err(|e| Error::RecordingFailed(format!("Failed to save player stats: {}", e)))?;
-        
+            .map_err(|e| Error::database("Failed to save player stats", e))?;
+        let played = u8::try_from(player.player_index).ok().and_then(|port| played_characters.get(&port));
+        if let Some(&character_id) = played.filter(|&&c| i32::from(c) != player.character_id) {
+            database::set_played_character(&conn, &stats.recording_id, player.player_index, i32::from(character_id))
+                .map_err(|e| Error::database("Failed to save player stats", e))?;
+        }
+
+        if let Some(code) = &player.connect_code {
+            if let Err(e) = database::record_player_identity(&conn, code, player.display_name.as_deref(), &seen_at) {
+                log::warn!("[SlippiStats] Failed to record {}'s display name: {}", code, e);
+            }
+        }
+
         log::debug!(
-            "Saved stats for player {} ({:?}) - {} kills, L-cancel: {}/{}",
+            "Saved stats for player {} ({:?}) - {} kills, L-cancel: {:?}/{:?}",
             player.player_index,
             player.connect_code,
             player.kill_count,
             player.l_cancel_success_count,
-            player.l_cancel_success_count + player.l_cancel_fail_count
+            player.l_cancel_success_count.zip(player.l_cancel_fail_count).map(|(s, f)| s + f)
         );
     }
-    
-    log::info!("[SlippiStats] Saved computed stats for {} players", stats.players.len());
+
+    let combos: Vec<database::ComboRow> = stats
+        .combos
+        .iter()
+        .map(|combo| {
+            let mut row = database::ComboRow {
+                recording_id: stats.recording_id.clone(),
+                player_index: combo.player_index,
+                opponent_index: combo.opponent_index,
+                start_frame: combo.start_frame,
+                end_frame: combo.end_frame,
+                start_percent: combo.start_percent,
+                end_percent: combo.end_percent,
+                move_count: combo.move_count,
+                distinct_moves: combo.distinct_moves,
+                did_kill: combo.did_kill,
+                highlight_score: 0.0,
+            };
+            row.highlight_score = highlights::highlight_score(&row);
+            row
+        })
+        .collect();
+    database::replace_combos(&conn, &stats.recording_id, &combos)
+        .map_err(|e| Error::database("Failed to save combos", e))?;
+
+    // Work out which player is me now that the whole game is saved
+    let me = database::recompute_is_me_for_recording(&conn, &stats.recording_id, &identity)
+        .map_err(|e| Error::database("Failed to attribute player", e))?;
+
+    // Flagged rather than skipped, so changing the settings brings it back
+    let excluded = database::update_excluded_reasons(&conn, &stats_exclusion(&app), Some(&stats.recording_id))
+        .map_err(|e| Error::database("Failed to flag excluded game", e))?;
+    if excluded > 0 {
+        log::info!("[SlippiStats] {} is left out of stats (CPU match or too short)", stats.recording_id);
+    }
+    // A new game can start or join a session, or be played before others
+    database::recompute_warmups(&conn, &warmup_settings(&app))
+        .map_err(|e| Error::database("Failed to flag warm-up games", e))?;
+
+    log::info!("[SlippiStats] Saved computed stats for {} players (me: {:?})", stats.players.len(), me);
+    state.stats_cache.invalidate();
+
+    // Open detail views of a reparsed recording refresh
+    let reparsed = database::finish_reparse(&conn, &stats.recording_id)
+        .map_err(|e| Error::database("Failed to clear reparse flag", e))?;
+    if reparsed {
+        // A reparse of outdated stats saves games back to back
+        event_batch::emit_batched(&app, library_events::REPARSED, &stats.recording_id);
+    }
+
+    let winner = winner.map(|p| p.player_index);
+
+    // A new recording takes its templated name now its players are known
+    if let Some(template) = library::recording_name_template(&app) {
+        let fields = |original: &str| rename_fields(&stats, &played_characters, me, winner, original);
+        match library::rename_deferred(&state, &conn, &stats.recording_id, &template, fields) {
+            Ok(Some(video_path)) => {
+                let payload = serde_json::json!({ "recordingId": stats.recording_id, "videoPath": video_path });
+                event_batch::emit_batched(&app, library_events::RENAMED, payload);
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("[SlippiStats] Failed to rename {}: {}", stats.recording_id, e),
+        }
+    }
+
+    state.overlay.stats_calculated(&stats.slp_path, overlay_result(&stats, me, winner));
+    if let Some(game) = session_game(&stats, me, winner).filter(|_| excluded == 0) {
+        play_session::stats_calculated(&app, &conn, &stats.slp_path, game);
+    }
+    inputs::spawn_analysis(&app);
     Ok(())
 }
 
+/// Name template values for a recording of this game, from my point of view
+/// (`me` and `winner` are player indexes; without me, the first player)
+fn rename_fields(
+    stats: &ComputedGameStats,
+    played_characters: &std::collections::BTreeMap<u8, u8>,
+    me: Option<i32>,
+    winner: Option<i32>,
+    original: &str,
+) -> library::NameFields {
+    let mine = me
+        .and_then(|me| stats.players.iter().find(|p| p.player_index == me))
+        .or(stats.players.first());
+    let opponent = mine.and_then(|mine| stats.players.iter().find(|p| p.player_index != mine.player_index));
+    let character = |player: &ComputedPlayerStats| {
+        let played = u8::try_from(player.player_index).ok().and_then(|port| played_characters.get(&port).copied());
+        played
+            .or_else(|| u8::try_from(player.character_id).ok())
+            .map(|id| melee_ids::character_name(id).to_string())
+    };
+    let played_at = stats
+        .created_at
+        .as_deref()
+        .and_then(|at| chrono::DateTime::parse_from_rfc3339(at).ok())
+        .map(|at| at.with_timezone(&chrono::Local))
+        .unwrap_or_else(chrono::Local::now);
+    library::NameFields {
+        original: original.to_string(),
+        date: Some(played_at.format("%Y-%m-%d").to_string()),
+        time: Some(played_at.format("%H%M%S").to_string()),
+        my_char: mine.and_then(character),
+        opp_char: opponent.and_then(character),
+        my_code: mine.and_then(|p| p.connect_code.clone()),
+        opp_code: opponent.and_then(|p| p.connect_code.clone()),
+        stage: u16::try_from(stats.stage).ok().map(|id| melee_ids::stage_name(id).to_string()),
+        result: mine
+            .zip(winner)
+            .map(|(mine, winner)| if mine.player_index == winner { "win" } else { "loss" }.to_string()),
+    }
+}
+
+/// Summarize a saved game for the stream overlay, from my point of view
+/// (`me` and `winner` are player indexes)
+fn overlay_result(stats: &ComputedGameStats, me: Option<i32>, winner: Option<i32>) -> OverlayGameResult {
+    let my_player = me.and_then(|index| stats.players.iter().find(|p| p.player_index == index));
+    let opponent = my_player.and_then(|mine| stats.players.iter().find(|p| p.player_index != mine.player_index));
+    
+    OverlayGameResult {
+        recording_id: stats.recording_id.clone(),
+        won: my_player.zip(winner).map(|(mine, winner)| mine.player_index == winner),
+        openings_per_kill: my_player.and_then(|p| p.openings_per_kill),
+        opponent: opponent
+            .map(|p| OverlayPlayer::new(p.connect_code.clone(), p.display_name.clone(), p.character_id)),
+    }
+}
+
+/// My side of a saved game for the play session, if I'm in it
+fn session_game(stats: &ComputedGameStats, me: Option<i32>, winner: Option<i32>) -> Option<SessionGame> {
+    let mine = stats.players.iter().find(|p| Some(p.player_index) == me)?;
+    let opponent = stats.players.iter().find(|p| p.player_index != mine.player_index);
+
+    Some(SessionGame {
+        recording_id: stats.recording_id.clone(),
+        won: winner.map(|winner| winner == mine.player_index),
+        my_character_id: mine.character_id,
+        opponent_character_id: opponent.map(|p| p.character_id),
+        l_cancel_success_count: mine.l_cancel_success_count,
+        l_cancel_fail_count: mine.l_cancel_fail_count,
+        openings_per_kill: mine.openings_per_kill,
+        damage_per_opening: mine.damage_per_opening,
+        neutral_win_ratio: mine.neutral_win_ratio,
+        inputs_per_minute: mine.inputs_per_minute,
+    })
+}
+
 /// Get player stats for a recording
 #[tauri::command]
+#[metrics::timed]
 pub async fn get_player_stats(
     recording_id: String,
     state: State<'_, AppState>,
 ) -> Result<Vec<database::PlayerStatsRow>, Error> {
     let db = state.database.clone();
     let conn = db.connection();
-    
+
     database::get_player_stats_by_recording(&conn, &recording_id)
-        .map_err(|e| Error::RecordingFailed(format!("Failed to get player stats: {}", e)))
+        .map_err(|e| Error::database("Failed to get player stats", e))
 }
 
 /// Get aggregated stats for a player across all recordings
+/// Without a connect code, aggregates over all of "my" connect codes
 #[tauri::command]
+#[metrics::timed]
 pub async fn get_total_player_stats(
-    connect_code: String,
+    connect_code: Option<String>,
     filter: Option<StatsFilter>,
     state: State<'_, AppState>,
 ) -> Result<AggregatedPlayerStats, Error> {
     log::debug!(
-        "Getting total stats for {} with filter: {:?}", 
+        "Getting total stats for {:?} with filter: {:?}", 
         connect_code, 
         filter
     );
-    
-    let db = state.database.clone();
-    let conn = db.connection();
-    
-    database::get_aggregated_player_stats(&conn, &connect_code, filter)
-        .map_err(|e| Error::RecordingFailed(format!("Failed to get aggregated stats: {}", e)))
+    total_player_stats(&state, connect_code.as_deref(), filter.as_ref())
+}
+
+/// Aggregated stats of a connect code, or of "my" codes, through the stats cache
+fn total_player_stats(
+    state: &AppState,
+    connect_code: Option<&str>,
+    filter: Option<&StatsFilter>,
+) -> Result<AggregatedPlayerStats, Error> {
+    let scope = match connect_code {
+        Some(code) => PlayerScope::Code(code),
+        None => PlayerScope::Me,
+    };
+
+    let started = Instant::now();
+    let (stats, cached) = state.stats_cache.get_or_compute(scope, filter, started, || {
+        let conn = state.database.connection();
+        database::get_aggregated_player_stats(&conn, scope, filter.cloned())
+            .map_err(|e| Error::database("Failed to get aggregated stats", e))
+    })?;
+    log::debug!(
+        "[TotalStats] {:?}: {} game(s) {} in {:.1} ms",
+        scope,
+        stats.total_games,
+        if cached { "from cache" } else { "computed" },
+        started.elapsed().as_secs_f64() * 1000.0
+    );
+    Ok(stats)
+}
+
+/// Two players' stats side by side, or one player's over two periods, with
+/// how each metric changed from the first to the second
+#[tauri::command]
+#[metrics::timed]
+pub async fn compare_stats(
+    first: database::StatsSubject,
+    second: database::StatsSubject,
+    state: State<'_, AppState>,
+) -> Result<database::StatsComparison, Error> {
+    let stats = |subject: &database::StatsSubject| {
+        total_player_stats(&state, subject.connect_code.as_deref(), subject.filter.as_ref())
+    };
+    Ok(database::compare_stats(stats(&first)?, stats(&second)?))
+}
+
+/// The win or loss streak the most recent games are on. Without a connect
+/// code, mine
+#[tauri::command]
+#[metrics::timed]
+pub async fn get_current_streak(
+    connect_code: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<database::CurrentStreak, Error> {
+    let conn = state.database.connection();
+    let scope = match connect_code.as_deref() {
+        Some(code) => PlayerScope::Code(code),
+        None => PlayerScope::Me,
+    };
+    database::get_current_streak(&conn, scope).map_err(|e| Error::database("Failed to get the current streak", e))
+}
+
+/// Games per character played in each day, week or month (default month),
+/// oldest first. Without a connect code, over all of "my" connect codes.
+/// Periods are in the client's time zone, utc_offset_minutes east of UTC
+#[tauri::command]
+#[metrics::timed]
+pub async fn get_character_usage(
+    connect_code: Option<String>,
+    bucket: Option<database::UsageBucket>,
+    utc_offset_minutes: Option<i32>,
+    state: State<'_, AppState>,
+) -> Result<Vec<database::CharacterUsagePeriod>, Error> {
+    let conn = state.database.connection();
+    let scope = match connect_code.as_deref() {
+        Some(code) => PlayerScope::Code(code),
+        None => PlayerScope::Me,
+    };
+    database::get_character_usage(&conn, scope, bucket.unwrap_or_default(), utc_offset_minutes)
+        .map_err(|e| Error::database("Failed to get character usage", e))
+}
+
+/// When a character was first and last played, with its record. None if it
+/// never was. Without a connect code, over all of "my" connect codes
+#[tauri::command]
+#[metrics::timed]
+pub async fn get_first_and_last_played(
+    character_id: i32,
+    connect_code: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Option<database::CharacterPlayedRange>, Error> {
+    let conn = state.database.connection();
+    let scope = match connect_code.as_deref() {
+        Some(code) => PlayerScope::Code(code),
+        None => PlayerScope::Me,
+    };
+    database::get_first_and_last_played(&conn, scope, character_id)
+        .map_err(|e| Error::database("Failed to get when the character was played", e))
+}
+
+/// Players seen in stats, most recently seen first, for autocomplete.
+/// `search` matches any of a player's connect codes or display names
+#[tauri::command]
+#[metrics::timed]
+pub fn get_known_players(
+    search: Option<String>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<database::KnownPlayer>, Error> {
+    database::get_known_players(&state.database.connection(), search.as_deref(), limit.unwrap_or(20))
+        .map_err(|e| Error::database("Failed to get known players", e))
+}
+
+/// Declare that `connect_code` belongs to the same person as `into_code`;
+/// stats for `into_code` then include its games
+#[tauri::command]
+#[metrics::timed]
+pub fn merge_players(connect_code: String, into_code: String, state: State<'_, AppState>) -> Result<(), Error> {
+    log::info!("Merging player {} into {}", connect_code, into_code);
+    database::merge_players(&state.database.connection(), &connect_code, &into_code)
+        .map_err(|e| Error::database("Failed to merge players", e))?;
+    state.stats_cache.invalidate();
+    Ok(())
+}
+
+/// Get controller input quality (dashbacks, pivots, snapbacks) for a player,
+/// with a daily trend over the filter's date range
+/// Without a connect code, over all of "my" connect codes
+#[tauri::command]
+#[metrics::timed]
+pub async fn get_input_quality(
+    connect_code: Option<String>,
+    filter: Option<StatsFilter>,
+    state: State<'_, AppState>,
+) -> Result<InputQuality, Error> {
+    let conn = state.database.connection();
+    let scope = match connect_code.as_deref() {
+        Some(code) => PlayerScope::Code(code),
+        None => PlayerScope::Me,
+    };
+    database::get_input_quality(&conn, scope, filter)
+        .map_err(|e| Error::database("Failed to get input quality", e))
+}
+
+/// Get the play session (games since watching began): record, matchups and
+/// my stats against my 30-day averages. None while not watching
+#[tauri::command]
+#[metrics::timed]
+pub async fn get_current_session(state: State<'_, AppState>) -> Result<Option<SessionSummary>, Error> {
+    let conn = state.database.connection();
+    play_session::summary(&state, &conn)
+        .map_err(|e| Error::database("Failed to get the session", e))
+}
+
+/// Get the best highlights (scored combos) across the library
+/// Without a connect code, only combos I performed
+#[tauri::command]
+#[metrics::timed]
+pub async fn get_top_highlights(
+    app: tauri::AppHandle,
+    connect_code: Option<String>,
+    filter: Option<StatsFilter>,
+    limit: Option<u32>,
+) -> Result<Vec<Highlight>, Error> {
+    let scope = match connect_code.as_deref() {
+        Some(code) => PlayerScope::Code(code),
+        None => PlayerScope::Me,
+    };
+    highlights::top_highlights(&app, scope, filter, false, limit.unwrap_or(10).clamp(1, 100))
+}
+
+/// Clip the day's best highlights now, like the daily job
+/// Returns the new clips' paths
+#[tauri::command]
+#[metrics::timed]
+pub async fn generate_highlights(app: tauri::AppHandle, count: Option<u32>) -> Result<Vec<String>, Error> {
+    highlights::generate_highlights(&app, count.unwrap_or(3).clamp(1, 10)).await
+}
+
+/// Summarize a week: games, record, biggest improvements and clip candidates.
+/// `week_offset` 0 (the default) is the last completed week, 1 the one before.
+/// With `render_card`, also writes a shareable HTML card. Replaces the week's
+/// saved report
+#[tauri::command]
+#[metrics::timed]
+pub async fn generate_weekly_report(
+    app: tauri::AppHandle,
+    week_offset: Option<u32>,
+    render_card: Option<bool>,
+) -> Result<database::ReportRow, Error> {
+    weekly_report::generate(&app, week_offset.unwrap_or(0), render_card.unwrap_or(false))
+}
+
+/// Saved weekly reports, latest week first
+#[tauri::command]
+#[metrics::timed]
+pub async fn list_weekly_reports(limit: Option<i64>, state: State<'_, AppState>) -> Result<Vec<database::ReportRow>, Error> {
+    let conn = state.database.connection();
+    database::get_reports(&conn, weekly_report::REPORT_KIND, limit.unwrap_or(52).max(1))
+        .map_err(|e| Error::database("Failed to list weekly reports", e))
 }
 
 /// Get available filter options (connect codes, characters, stages) from the database
+/// Without a connect code, options are scoped to "my" games (or everything if nothing identifies me)
 #[tauri::command]
+#[metrics::timed]
 pub async fn get_available_filter_options(
+    app: tauri::AppHandle,
     connect_code: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<AvailableFilterOptions, Error> {
+    let has_identity = my_identity(&app).is_configured();
+
     let db = state.database.clone();
     let conn = db.connection();
-    
-    database::get_available_filter_options(&conn, connect_code.as_deref())
-        .map_err(|e| Error::RecordingFailed(format!("Failed to get filter options: {}", e)))
+
+    let scope = match connect_code.as_deref() {
+        Some(code) => PlayerScope::Code(code),
+        None if has_identity => PlayerScope::Me,
+        None => PlayerScope::All,
+    };
+
+    database::get_available_filter_options(&conn, scope)
+        .map_err(|e| Error::database("Failed to get filter options", e))
+}
+
+/// Manually set which port was me in a recording (None clears the override)
+/// Updates the is_me flag so "my stats" aggregates pick up the change
+#[tauri::command]
+#[metrics::timed]
+pub async fn set_my_port(
+    app: tauri::AppHandle,
+    recording_id: String,
+    port: Option<i32>,
+    state: State<'_, AppState>,
+) -> Result<(), Error> {
+    let identity = my_identity(&app);
+
+    let db = state.database.clone();
+    let conn = db.connection();
+
+    database::set_my_port_override(&conn, &recording_id, port)
+        .map_err(|e| Error::database("Failed to save port override", e))?;
+
+    let me = database::recompute_is_me_for_recording(&conn, &recording_id, &identity)
+        .map_err(|e| Error::database("Failed to attribute player", e))?;
+    state.stats_cache.invalidate();
+
+    log::info!("🙋 Port override for {} set to {:?} (me: {:?})", recording_id, port, me);
+    Ok(())
+}
+
+/// Manually mark a game as a warm-up or not (None goes back to the one worked
+/// out from its session)
+#[tauri::command]
+#[metrics::timed]
+pub async fn set_warmup(
+    app: tauri::AppHandle,
+    recording_id: String,
+    is_warmup: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), Error> {
+    let conn = state.database.connection();
+
+    let found = database::set_warmup_override(&conn, &recording_id, is_warmup)
+        .map_err(|e| Error::database("Failed to save warm-up override", e))?;
+    if !found {
+        return Err(Error::database(&format!("No stats for {}", recording_id), rusqlite::Error::QueryReturnedNoRows));
+    }
+
+    database::recompute_warmups(&conn, &warmup_settings(&app))
+        .map_err(|e| Error::database("Failed to flag warm-up games", e))?;
+    state.stats_cache.invalidate();
+
+    log::info!("🔥 Warm-up override for {} set to {:?}", recording_id, is_warmup);
+    Ok(())
+}
+
+/// Count games whose stats were computed by an older stats version
+#[tauri::command]
+#[metrics::timed]
+pub async fn get_outdated_stats_count(
+    current_version: i32,
+    state: State<'_, AppState>,
+) -> Result<i64, Error> {
+    let conn = state.database.connection();
+    database::count_outdated_stats(&conn, current_version)
+        .map_err(|e| Error::database("Failed to count outdated stats", e))
+}
+
+/// List games whose stats were computed by an older stats version, oldest first,
+/// so the frontend can recompute just those
+#[tauri::command]
+#[metrics::timed]
+pub async fn get_outdated_stats_games(
+    current_version: i32,
+    state: State<'_, AppState>,
+) -> Result<Vec<database::OutdatedStatsGame>, Error> {
+    let conn = state.database.connection();
+    database::get_outdated_stats_games(&conn, current_version)
+        .map_err(|e| Error::database("Failed to get outdated stats", e))
 }
 
 /// List all .slp files in a directory (recursive, up to 5 levels deep)
 #[tauri::command]
+#[metrics::timed]
 pub async fn list_slp_files(directory: String) -> Result<Vec<String>, Error> {
     use walkdir::WalkDir;
-    
+
     let dir_path = std::path::Path::new(&directory);
     if !dir_path.exists() {
         return Err(Error::InvalidPath(format!("Directory does not exist: {}", directory)));
     }
-    
+
     let mut slp_files = Vec::new();
-    
+
     for entry in WalkDir::new(&directory)
         .max_depth(5)
         .into_iter()
@@ -400,26 +1252,185 @@ pub async fn list_slp_files(directory: String) -> Result<Vec<String>, Error> {
             slp_files.push(path.to_string_lossy().to_string());
         }
     }
-    
+
     log::info!("Found {} .slp files in {}", slp_files.len(), directory);
     Ok(slp_files)
 }
 
 /// Check if a game with the given slp_path already exists in the database
 #[tauri::command]
+#[metrics::timed]
 pub async fn check_slp_synced(
     slp_path: String,
     state: State<'_, AppState>,
 ) -> Result<bool, Error> {
     let db = state.database.clone();
     let conn = db.connection();
-    
-    database::game_stats_exists_by_slp_path(&conn, &slp_path)
-        .map_err(|e| Error::RecordingFailed(format!("Failed to check slp sync status: {}", e)))
+
+    // Replays known to be unparseable count as synced so they aren't retried every run
+    let synced = database::game_stats_exists_by_slp_path(&conn, &slp_path)
+        .and_then(|exists| Ok(exists || database::has_parse_error_for_slp(&conn, &slp_path)?))
+        .map_err(|e| Error::database("Failed to check slp sync status", e))?;
+    Ok(synced)
+}
+
+/// A recording whose replay couldn't be parsed
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnparseableReplay {
+    pub recording_id: String,
+    pub slp_path: String,
+    /// None for replay-only entries
+    pub video_path: Option<String>,
+    /// 'truncated', 'unsupported_version' or 'corrupt'
+    pub parse_error: String,
+    /// Stage and players salvaged from the replay's Game Start, if it has one
+    pub game_start: Option<GameStart>,
+}
+
+/// Record that a recording's replay couldn't be parsed, so stats passes skip it
+/// The file is checked to classify the failure; returns the classification
+#[tauri::command]
+#[metrics::timed]
+pub async fn record_replay_parse_failure(
+    recording_id: String,
+    slp_path: String,
+    error: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, Error> {
+    let path = std::path::PathBuf::from(&slp_path);
+    // A missing file is a sync problem, not a broken replay
+    if !path.exists() {
+        return Err(Error::InvalidPath(format!("Replay not found: {}", slp_path)));
+    }
+    let checked = tauri::async_runtime::spawn_blocking(move || integrity::check_replay(&path))
+        .await
+        .map_err(|e| Error::RecordingFailed(format!("Replay check failed: {}", e)))?;
+    // A replay that looks intact but still fails in slippi-js is treated as corrupt
+    let (kind, detail) = match checked {
+        Ok(()) => (ParseErrorKind::Corrupt, error.unwrap_or_else(|| "slippi-js couldn't parse it".to_string())),
+        Err(problem) => (problem.kind, problem.detail),
+    };
+    log::warn!("[SlippiStats] Skipping unparseable replay {} ({}): {}", slp_path, kind.as_str(), detail);
+
+    database::set_recording_parse_error(&state.database.connection(), &recording_id, Some(kind.as_str()))
+        .map_err(Error::from)?;
+    Ok(kind.as_str().to_string())
+}
+
+/// List recordings whose replays couldn't be parsed
+#[tauri::command]
+#[metrics::timed]
+pub async fn list_unparseable_replays(state: State<'_, AppState>) -> Result<Vec<UnparseableReplay>, Error> {
+    let rows = database::get_all_recordings(&state.database.connection())
+        .map_err(Error::from)?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            let parse_error = row.parse_error?;
+            let slp_path = row.slp_path?;
+            Some(UnparseableReplay {
+                game_start: read_game_start(Path::new(&slp_path)),
+                recording_id: row.id,
+                video_path: row.video_path,
+                slp_path,
+                parse_error,
+            })
+        })
+        .collect())
+}
+
+/// Clear the parse failures of the given recordings (all if None) so the next
+/// stats pass tries them again. Returns how many were cleared
+#[tauri::command]
+#[metrics::timed]
+pub async fn retry_unparseable_replays(
+    recording_ids: Option<Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<usize, Error> {
+    let conn = state.database.connection();
+    let ids = match recording_ids {
+        Some(ids) => ids,
+        None => database::get_all_recordings(&conn)
+            .map_err(Error::from)?
+            .into_iter()
+            .filter(|row| row.parse_error.is_some())
+            .map(|row| row.id)
+            .collect(),
+    };
+    for id in &ids {
+        database::set_recording_parse_error(&conn, id, None)
+            .map_err(Error::from)?;
+    }
+    Ok(ids.len())
+}
+
+/// Flag a recording to have its replay's metadata and stats extracted again,
+/// on the next sync and stats pass
+#[tauri::command]
+#[metrics::timed]
+pub async fn set_needs_reparse(
+    recording_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), Error> {
+    let conn = state.database.connection();
+    match database::set_needs_reparse(&conn, &recording_id).map_err(Error::from)? {
+        true => Ok(()),
+        false => Err(Error::InvalidPath(format!("Recording {} has no replay to reparse", recording_id))),
+    }
+}
+
+/// Flag games whose stats were computed by an older stats version to be
+/// reparsed. Returns how many were newly flagged
+#[tauri::command]
+#[metrics::timed]
+pub async fn flag_outdated_stats(
+    current_version: i32,
+    state: State<'_, AppState>,
+) -> Result<usize, Error> {
+    let conn = state.database.connection();
+    database::flag_outdated_stats(&conn, current_version)
+        .map_err(|e| Error::database("Failed to flag outdated stats", e))
+}
+
+/// A recording the media probe found no audible sound in
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SilentRecording {
+    pub recording_id: String,
+    pub video_path: String,
+    pub start_time: Option<String>,
+    /// false if the video has no audio stream at all
+    pub has_audio: bool,
+    pub mean_volume_db: Option<f64>,
+}
+
+/// List recordings without audible sound (e.g. captured from the wrong audio
+/// device), newest first. Videos not probed yet aren't listed
+#[tauri::command]
+#[metrics::timed]
+pub async fn list_silent_recordings(state: State<'_, AppState>) -> Result<Vec<SilentRecording>, Error> {
+    let rows = database::get_all_recordings(&state.database.connection())
+        .map_err(Error::from)?;
+    Ok(rows
+        .into_iter()
+        .filter(|row| row.is_silent())
+        .filter_map(|row| {
+            Some(SilentRecording {
+                recording_id: row.id,
+                video_path: row.video_path?,
+                start_time: row.start_time,
+                has_audio: row.has_audio.unwrap_or(false),
+                mean_volume_db: row.audio_mean_volume_db,
+            })
+        })
+        .collect())
 }
 
 /// Open a video file in the default player
 #[tauri::command]
+#[metrics::timed]
 pub async fn open_video(video_path: String) -> Result<(), Error> {
     #[cfg(target_os = "windows")]
     {
@@ -428,7 +1439,7 @@ pub async fn open_video(video_path: String) -> Result<(), Error> {
             .spawn()
             .map_err(|e| Error::RecordingFailed(format!("Failed to open video: {}", e)))?;
     }
-    
+
     #[cfg(target_os = "macos")]
     {
         std::process::Command::new("open")
@@ -436,7 +1447,7 @@ pub async fn open_video(video_path: String) -> Result<(), Error> {
             .spawn()
             .map_err(|e| Error::RecordingFailed(format!("Failed to open video: {}", e)))?;
     }
-    
+
     #[cfg(target_os = "linux")]
     {
         std::process::Command::new("xdg-open")
@@ -444,23 +1455,25 @@ pub async fn open_video(video_path: String) -> Result<(), Error> {
             .spawn()
             .map_err(|e| Error::RecordingFailed(format!("Failed to open video: {}", e)))?;
     }
-    
+
     Ok(())
 }
 
 /// Open the folder containing a video file
 #[tauri::command]
+#[metrics::timed]
 pub async fn open_recording_folder(video_path: String) -> Result<(), Error> {
     let path = std::path::Path::new(&video_path);
     let folder = path
         .parent()
         .ok_or_else(|| Error::InvalidPath("Failed to get parent directory".to_string()))?;
-    
+
     open_folder(folder)
 }
 
 /// Open a file's location in the system file explorer
 #[tauri::command]
+#[metrics::timed]
 pub fn open_file_location(path: String) -> Result<(), Error> {
     let file_path = Path::new(&path);
     let dir_path = if file_path.is_file() {
@@ -470,7 +1483,7 @@ pub fn open_file_location(path: String) -> Result<(), Error> {
     } else {
         file_path
     };
-    
+
     open_folder(dir_path)
 }
 
@@ -498,23 +1511,28 @@ fn recording_row_to_session(
     // Build SlippiMetadata - players come from player_stats now
     let slippi_metadata = if !player_stats.is_empty() || game_stats.is_some() {
         // Build players array from player_stats (includes kill_count for winner detection)
-        let players: Vec<PlayerInfo> = player_stats
-            .iter()
-            .map(|ps| PlayerInfo {
-                character_id: ps.character_id as u8,
-                character_color: ps.character_color as u8,
-                player_tag: ps.connect_code.clone().unwrap_or_else(|| 
-                    ps.display_name.clone().unwrap_or_else(|| format!("P{}", ps.port + 1))
-                ),
-                port: ps.port as u8,
-                kill_count: Some(ps.kill_count),
-            })
-            .collect();
+        let players: Vec<PlayerInfo> = if player_stats.is_empty() {
+            // A game that just started only has its provisional row so far
+            game_stats.as_ref().map(provisional_players).unwrap_or_default()
+        } else {
+            player_stats
+                .iter()
+                .map(|ps| PlayerInfo {
+                    character_id: ps.character_id as u8,
+                    character_color: ps.character_color as u8,
+                    player_tag: ps.connect_code.clone().unwrap_or_else(|| 
+                        ps.display_name.clone().unwrap_or_else(|| format!("P{}", ps.port + 1))
+                    ),
+                    port: ps.port as u8,
+                    kill_count: Some(ps.kill_count),
+                })
+                .collect()
+        };
         
         let characters: Vec<u8> = players.iter().map(|p| p.character_id).collect();
         
         // Get game-level metadata from game_stats
-        let (stage, game_duration, total_frames, is_pal, played_on, winner_port) = 
+        let (stage, game_duration, total_frames, is_pal, played_on, winner_port, replay_version, paused_frames) = 
             if let Some(ref gs) = game_stats {
                 (
                     gs.stage.unwrap_or(0) as u16,
@@ -523,10 +1541,16 @@ fn recording_row_to_session(
                     gs.is_pal.unwrap_or(false),
                     gs.played_on.clone(),
                     gs.winner_port.map(|p| p as u8),
+                    gs.replay_version.clone(),
+                    gs.total_paused_frames,
                 )
             } else {
-                (0, 0, 0, false, None, None)
+                (0, 0, 0, false, None, None, None, None)
             };
+        let capabilities = replay_version
+            .as_deref()
+            .and_then(|v| v.parse::<ReplayVersion>().ok())
+            .map(ReplayVersion::capabilities);
         
         Some(SlippiMetadata {
             characters,
@@ -538,53 +1562,110 @@ fn recording_row_to_session(
             winner_port,
             played_on,
             total_frames,
+            paused_frames,
+            replay_version,
+            capabilities,
         })
     } else {
         None
     };
     
-    // Calculate duration from stats if available
-    let duration = game_stats
+    // Calculate duration from stats if available, with and without pauses
+    let frames_to_seconds = |frames: i32| (frames.max(0) as f64 / 60.0) as u64;
+    let duration = game_stats.as_ref().and_then(|s| s.game_duration).map(frames_to_seconds);
+    let unpaused_duration = game_stats
         .as_ref()
-        .and_then(|s| s.game_duration)
-        .map(|d| (d as f64 / 60.0) as u64);
+        .and_then(|s| Some(frames_to_seconds(s.game_duration? - s.total_paused_frames?)));
+    
+    // "Me" annotations derived from the is_me flag
+    let me = player_stats.iter().find(|ps| ps.is_me);
+    let opponent = me.and_then(|me| player_stats.iter().find(|ps| ps.player_index != me.player_index));
+    // Kept with the stats as is_me and the winner change
+    let my_result = game_stats.as_ref().and_then(|gs| match gs.my_result {
+        database::MyResult::Win => Some(GameResult::Win),
+        database::MyResult::Loss => Some(GameResult::Loss),
+        database::MyResult::Unknown => None,
+    });
+    let is_silent = row.is_silent();
+    // Stats always come with player rows; a game_stats row alone is provisional
+    let stats_pending = game_stats.is_some() && player_stats.is_empty();
     
     RecordingSession {
         id: row.id,
         start_time: row.start_time.unwrap_or_default(),
         end_time: None,
         slp_path: row.slp_path.unwrap_or_default(),
-        video_path: Some(row.video_path),
+        video_path: row.video_path,
         thumbnail_path: row.thumbnail_path,
         duration,
+        unpaused_duration,
         file_size: row.file_size.map(|s| s as u64),
         slippi_metadata,
+        my_character: me.map(|ps| ps.character_id as u8),
+        my_result,
+        is_warmup: game_stats.as_ref().is_some_and(|gs| gs.is_warmup),
+        opponent_code: opponent.and_then(|ps| ps.connect_code.clone()),
+        dropped_frame_estimate: row.dropped_frame_estimate.map(|d| d as u64),
+        longest_stall_ms: row.longest_stall_ms.map(|s| s as u64),
+        profile: row.profile,
+        is_silent,
+        is_archived: row.is_archived,
+        parse_error: row.parse_error,
+        needs_reparse: row.needs_reparse,
+        source_recording_id: row.source_recording_id,
+        is_favorite: false,
+        stats_pending,
     }
 }
 
+/// The two players kept on a game_stats row, for a game without player stats
+/// yet (see database::insert_provisional_game_stats)
+fn provisional_players(gs: &database::GameStatsRow) -> Vec<PlayerInfo> {
+    [
+        (gs.player1_port, gs.player1_character, &gs.player1_id, gs.player1_color),
+        (gs.player2_port, gs.player2_character, &gs.player2_id, gs.player2_color),
+    ]
+    .into_iter()
+    .filter_map(|(port, character, code, color)| {
+        let port = port?;
+        Some(PlayerInfo {
+            character_id: character? as u8,
+            character_color: color.unwrap_or(0) as u8,
+            player_tag: code.clone().unwrap_or_else(|| format!("P{}", port + 1)),
+            port: port as u8,
+            kill_count: None,
+        })
+    })
+    .collect()
+}
+
+/// Take the target of the last buckwheat:// link, if the frontend hasn't yet
+#[tauri::command]
+#[metrics::timed]
+pub fn take_pending_deep_link(state: State<'_, AppState>) -> Option<NavigateTarget> {
+    state.pending_deep_link.lock().ok().and_then(|mut pending| pending.take())
+}
+
 fn open_folder(folder: &Path) -> Result<(), Error> {
     #[cfg(target_os = "windows")]
     {
         std::process::Command::new("explorer")
             .arg(folder)
-            .spawn()
-            .map_err(|e| Error::RecordingFailed(format!("Failed to open folder: {}", e)))?;
+            .spawn()?;
     }
     
     #[cfg(target_os = "macos")]
     {
         std::process::Command::new("open")
             .arg(folder)
-            .spawn()
-            .map_err(|e| Error::RecordingFailed(format!("Failed to open folder: {}", e)))?;
+            .spawn()?;
     }
     
     #[cfg(target_os = "linux")]
     {
         std::process::Command::new("xdg-open")
             .arg(folder)
-            .spawn()
-            .map_err(|e| Error::RecordingFailed(format!("Failed to open folder: {}", e)))?;
+            .spawn()?;
     }
     
     Ok(())
@@ -0,0 +1,123 @@
+use crate::app_state::AppState;
+use crate::commands::errors::Error;
+use crate::metrics;
+use crate::overlay::{OverlayServer, DEFAULT_PORT};
+use serde::Serialize;
+use tauri::{AppHandle, State};
+use tauri_plugin_store::StoreExt;
+
+/// Settings key for starting the overlay server with the app
+const OVERLAY_SERVER_KEY: &str = "overlayServer";
+/// Settings key for the overlay server port
+const OVERLAY_SERVER_PORT_KEY: &str = "overlayServerPort";
+
+/// Whether the overlay server is running, and where
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverlayServerStatus {
+    pub running: bool,
+    pub port: Option<u16>,
+    pub url: Option<String>,
+}
+
+/// Start the local overlay server (restarts it if running on a different port)
+/// Without a port, uses the overlayServerPort setting
+#[tauri::command]
+#[metrics::timed]
+pub async fn start_overlay_server(
+    app: AppHandle,
+    port: Option<u16>,
+    state: State<'_, AppState>,
+) -> Result<OverlayServerStatus, Error> {
+    let port = port.unwrap_or_else(|| overlay_port_setting(&app));
+    start_server(&state, port).await?;
+    get_overlay_server_status(state).await
+}
+
+/// Stop the local overlay server if it's running
+#[tauri::command]
+#[metrics::timed]
+pub async fn stop_overlay_server(state: State<'_, AppState>) -> Result<(), Error> {
+    stop_server(&state);
+    Ok(())
+}
+
+/// Get the overlay server status
+#[tauri::command]
+#[metrics::timed]
+pub async fn get_overlay_server_status(state: State<'_, AppState>) -> Result<OverlayServerStatus, Error> {
+    let server = state
+        .overlay_server
+        .lock()
+        .map_err(|e| Error::InitializationError(format!("Failed to lock overlay server: {}", e)))?;
+    let port = server.as_ref().map(|s| s.port());
+
+    Ok(OverlayServerStatus {
+        running: port.is_some(),
+        port,
+        url: port.map(|p| format!("http://127.0.0.1:{}", p)),
+    })
+}
+
+// ============================================================================
+// HELPER FUNCTIONS
+// ============================================================================
+
+/// Start the server on launch if the overlayServer setting is on
+pub async fn start_overlay_server_if_enabled(app: &AppHandle, state: &AppState) {
+    let enabled = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get(OVERLAY_SERVER_KEY))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if !enabled {
+        return;
+    }
+
+    if let Err(e) = start_server(state, overlay_port_setting(app)).await {
+        log::error!("Failed to start overlay server: {}", e);
+    }
+}
+
+/// Stop the server (used on app exit too)
+pub fn stop_server(state: &AppState) {
+    let server = state.overlay_server.lock().ok().and_then(|mut s| s.take());
+    if let Some(server) = server {
+        server.stop();
+    }
+}
+
+async fn start_server(state: &AppState, port: u16) -> Result<(), Error> {
+    let running_port = state
+        .overlay_server
+        .lock()
+        .ok()
+        .and_then(|s| s.as_ref().map(|s| s.port()));
+    if running_port == Some(port) {
+        return Ok(());
+    }
+
+    // Release the old port before binding the new one
+    stop_server(state);
+    let server = OverlayServer::start(state.overlay.clone(), port).await?;
+
+    let mut slot = state
+        .overlay_server
+        .lock()
+        .map_err(|e| Error::InitializationError(format!("Failed to lock overlay server: {}", e)))?;
+    if let Some(previous) = slot.replace(server) {
+        previous.stop();
+    }
+    Ok(())
+}
+
+fn overlay_port_setting(app: &AppHandle) -> u16 {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(OVERLAY_SERVER_PORT_KEY))
+        .and_then(|v| v.as_u64())
+        .and_then(|p| u16::try_from(p).ok())
+        .filter(|p| *p != 0)
+        .unwrap_or(DEFAULT_PORT)
+}
@@ -4,62 +4,122 @@
 
 use crate::app_state::AppState;
 use crate::commands::errors::Error;
+use crate::commands::settings::replay_only_mode;
 use crate::events::recording as recording_events;
+use crate::game_detector::suspension;
+use crate::game_session::{self, SessionEvent};
+use crate::health::{self, HealthStatus, Subsystem};
 use crate::library;
-use crate::recorder::{self, RecordingQuality};
+use crate::database::{self, RecordingGame};
+use crate::metrics;
+use crate::pre_roll;
+use crate::recorder::performance::{
+    self, BenchmarkResult, CaptureStats, PerformanceWarning, PresetBenchmark, RecordingResult,
+    DROPPED_FRAME_WARNING_RATIO,
+};
+use crate::recorder::obs::{self, ObsConfig, ObsRecorder};
+use crate::recorder::{self, CaptureSettings, Recorder, RecordingQuality};
+use crate::settings::{RecorderBackend, RecordingContainer};
+use crate::readiness;
+use crate::recovery::{self, RecoveryReport};
+use crate::slippi;
+use crate::viewport;
+use crate::warm_start;
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use std::path::Path;
-use tauri::{Emitter, State};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager, State};
+use tauri_plugin_store::StoreExt;
+
+/// How long each preset is recorded during the benchmark
+const BENCHMARK_DURATION: Duration = Duration::from_secs(10);
+
+/// How long the test capture records
+const TEST_CAPTURE_DURATION: Duration = Duration::from_secs(2);
+
+/// How often the dropped-frame monitor samples capture stats
+const PERFORMANCE_SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Largest video offset taken as measured; beyond it the clocks were off or
+/// the replay isn't the video's, and calibrate_offset is left to set it
+const MAX_MEASURED_VIDEO_OFFSET_MS: i64 = 30_000;
+
+/// Settings key selecting the recorder: "native" (built-in capture) or "obs"
+const RECORDER_BACKEND_KEY: &str = "recorderBackend";
+/// Settings keys for reaching obs-websocket
+const OBS_HOST_KEY: &str = "obsHost";
+const OBS_PORT_KEY: &str = "obsPort";
+const OBS_PASSWORD_KEY: &str = "obsPassword";
+/// Settings key for the file type of finished recordings ("mp4" or "mkv")
+const RECORDING_CONTAINER_KEY: &str = "recordingContainer";
 
 /// Start recording with a specific output path
 #[tauri::command]
-pub async fn start_recording(output_path: String, state: State<'_, AppState>) -> Result<(), Error> {
+#[metrics::timed]
+pub async fn start_recording(
+    app: tauri::AppHandle,
+    output_path: String,
+    state: State<'_, AppState>,
+) -> Result<(), Error> {
     let quality = resolve_recording_quality(&state)?;
     log_quality_info(&quality);
-    
+
     configure_target_window(&state);
-    start_recording_with_quality(&state, &output_path, quality)?;
+    start_recording_with_quality(&app, &state, &output_path, quality)?;
     Ok(())
 }
 
 /// Start a generic/manual recording with an auto-generated filename
 #[tauri::command]
+#[metrics::timed]
 pub async fn start_generic_recording(
     app: tauri::AppHandle,
     state: State<'_, AppState>,
 ) -> Result<String, Error> {
     let recording_dir = library::get_recording_directory(&app).await?;
     let output_path = generate_generic_recording_path(&recording_dir);
-    
+
     let quality = resolve_recording_quality(&state)?;
     log_quality_info(&quality);
-    
+
     configure_target_window(&state);
-    start_recording_with_quality(&state, &output_path, quality)?;
-    
+    start_recording_with_quality(&app, &state, &output_path, quality)?;
+
     if let Ok(mut current_file) = state.current_recording_file.lock() {
         *current_file = Some(output_path.clone());
     }
-    
+
     Ok(output_path)
 }
 
 /// Stop the current recording
 #[tauri::command]
+#[metrics::timed]
 pub async fn stop_recording(
     app: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<String, Error> {
+) -> Result<RecordingResult, Error> {
     let mut recorder_lock = state
         .recorder
         .lock()
         .map_err(|e| Error::RecordingFailed(format!("Failed to lock recorder: {}", e)))?;
-    
+
     if let Some(recorder) = recorder_lock.as_mut() {
-        let output_path = recorder.stop_recording()?;
-        
+        let result = stop_and_save_capture_stats(&app, &state, recorder.as_mut())?;
+        let output_path = result.output_path.clone();
+
         // Clean up recorder
         *recorder_lock = None;
-        
+        state.recording_claim.clear();
+        state.work_gate.set_recording(false);
+        spawn_post_recording_hook(&app, &result);
+        // The game may have ended just before; don't stop a later recording
+        game_session::send(&app, SessionEvent::RecordingStopped);
+
         // Log any clip markers
         let marker_snapshot = {
             let markers = state.clip_markers.lock().map_err(|e| {
@@ -71,33 +131,237 @@ pub async fn stop_recording(
                 .map(|m| m.timestamp_seconds)
                 .collect::<Vec<_>>()
         };
-        
+
         if marker_snapshot.is_empty() {
             log::info!("No clip markers queued for {}", output_path);
         } else {
             log::info!("Clip markers for {}: {:?}", output_path, marker_snapshot);
         }
-        
+
         if let Err(e) = app.emit(recording_events::STOPPED, output_path.clone()) {
             log::error!("Failed to emit {} event: {:?}", recording_events::STOPPED, e);
         }
-        
+
         if let Ok(mut current_file) = state.current_recording_file.lock() {
             if current_file.as_ref().map(|s| s == &output_path).unwrap_or(false) {
                 *current_file = None;
             }
         }
-        
-        Ok(output_path)
+
+        Ok(result)
     } else {
         Err(Error::RecordingFailed("No active recording to stop".to_string()))
     }
 }
 
+/// Take what recovery found after the last session ended while recording
+/// (cleared once taken). Check on startup and on `recording-recovery-finished`
+#[tauri::command]
+#[metrics::timed]
+pub fn take_recovery_report(state: State<'_, AppState>) -> Option<RecoveryReport> {
+    state.pending_recovery.lock().ok().and_then(|mut pending| pending.take())
+}
+
+/// Record the selected capture source for a few seconds at each quality preset
+/// and recommend the highest one that doesn't drop frames.
+/// Temporary output files are deleted afterwards. No game is auto-recorded
+/// until it's done (one started meanwhile is picked up then).
+#[tauri::command]
+#[metrics::timed]
+pub async fn run_recording_benchmark(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<BenchmarkResult, Error> {
+    let _paused = suspension::suspend(&app, "running the recording benchmark");
+    {
+        let recorder_lock = state
+            .recorder
+            .lock()
+            .map_err(|e| Error::RecordingFailed(format!("Failed to lock recorder: {}", e)))?;
+        if recorder_lock.as_ref().is_some_and(|r| r.is_recording()) {
+            return Err(Error::RecordingFailed("Can't run the benchmark while recording".to_string()));
+        }
+    }
+
+    configure_target_window(&state);
+    log::info!("⏱️ Starting recording benchmark ({}s per preset)", BENCHMARK_DURATION.as_secs());
+
+    let mut presets = Vec::new();
+    for quality in RecordingQuality::ALL {
+        let output_path = std::env::temp_dir()
+            .join(format!("peppi_benchmark_{:?}.mp4", quality).to_lowercase());
+
+        let result = benchmark_preset(quality, &output_path).await;
+        remove_benchmark_file(&output_path).await;
+
+        log::info!(
+            "⏱️ {:?}: {:.1} fps, {} dropped ({:.1}%)",
+            quality,
+            result.achieved_fps,
+            result.dropped_frames,
+            result.dropped_ratio * 100.0
+        );
+        presets.push(result);
+    }
+
+    let recommended = performance::recommend_quality(&presets);
+    log::info!("✅ Benchmark complete, recommended quality: {:?}", recommended);
+
+    Ok(BenchmarkResult { presets, recommended })
+}
+
+/// What a test capture recorded
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestCaptureResult {
+    /// The window or monitor that was captured
+    pub target: String,
+    pub width: u32,
+    pub height: u32,
+    pub duration_secs: f64,
+    /// Base64 JPEG of the first frame
+    pub thumbnail: Option<String>,
+}
+
+/// Record a couple of seconds exactly the way auto-recording would (same
+/// target resolution and quality) so users can check their setup before playing.
+/// The temporary recording is deleted afterwards, and no game is
+/// auto-recorded until it's done.
+#[tauri::command]
+#[metrics::timed]
+pub async fn test_capture(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<TestCaptureResult, Error> {
+    let _paused = suspension::suspend(&app, "making a test recording");
+    {
+        let recorder_lock = state
+            .recorder
+            .lock()
+            .map_err(|e| Error::RecordingFailed(format!("Failed to lock recorder: {}", e)))?;
+        if recorder_lock.as_ref().is_some_and(|r| r.is_recording()) {
+            return Err(Error::RecordingFailed("Can't run a test recording while recording".to_string()));
+        }
+    }
+
+    let quality = resolve_recording_quality(&state)?;
+    configure_target_window(&state);
+
+    let output_path = std::env::temp_dir().join(format!("peppi_test_capture_{}.mp4", uuid::Uuid::new_v4()));
+    let thumbnail_path = output_path.with_extension("jpg");
+    log::info!("🧪 Test capture ({:?} quality) to {:?}", quality, output_path);
+
+    let mut recorder = resolve_recorder(&app);
+    recorder.start_recording(&output_path.to_string_lossy(), quality)?;
+    let target = recorder
+        .target_description()
+        .unwrap_or_else(|| "Capture source configured in OBS".to_string());
+    tokio::time::sleep(TEST_CAPTURE_DURATION).await;
+    let saved_path = recorder.stop_recording();
+
+    // OBS decides where the file actually goes
+    let video_path = match &saved_path {
+        Ok(path) if !path.is_empty() => Path::new(path).to_path_buf(),
+        _ => output_path.clone(),
+    };
+    let result = match saved_path {
+        Ok(_) => inspect_test_capture(video_path.clone(), thumbnail_path.clone(), target).await,
+        Err(e) => Err(e),
+    };
+
+    remove_benchmark_file(&video_path).await;
+    remove_benchmark_file(&output_path).await;
+    remove_benchmark_file(&thumbnail_path).await;
+
+    if let Ok(result) = &result {
+        log::info!("🧪 Test capture of {}: {}x{}", result.target, result.width, result.height);
+    }
+    result
+}
+
+/// Probe a test capture and grab its first frame
+async fn inspect_test_capture(
+    video_path: std::path::PathBuf,
+    thumbnail_path: std::path::PathBuf,
+    target: String,
+) -> Result<TestCaptureResult, Error> {
+    tokio::task::spawn_blocking(move || {
+        if !video_path.exists() {
+            return Err(Error::RecordingFailed("The test recording produced no video file".to_string()));
+        }
+        crate::clip_processor::ensure_ffmpeg()?;
+        
+        let probe = crate::clip_processor::probe_video(&video_path)?;
+        let thumbnail = match crate::clip_processor::generate_thumbnail(&video_path, &thumbnail_path, Some(0.0)) {
+            Ok(()) => std::fs::read(&thumbnail_path)
+                .ok()
+                .map(|bytes| base64::engine::general_purpose::STANDARD.encode(bytes)),
+            Err(e) => {
+                log::warn!("Failed to grab the test capture's first frame: {}", e);
+                None
+            }
+        };
+        
+        Ok(TestCaptureResult {
+            target,
+            width: probe.width,
+            height: probe.height,
+            duration_secs: probe.duration_secs,
+            thumbnail,
+        })
+    })
+    .await
+    .map_err(|e| Error::RecordingFailed(format!("Test capture failed: {}", e)))?
+}
+
+/// Connect to OBS with the saved settings and return its version
+#[tauri::command]
+#[metrics::timed]
+pub async fn test_obs_connection(app: tauri::AppHandle) -> Result<String, Error> {
+    let config = obs_config(&app);
+    tokio::task::spawn_blocking(move || ObsRecorder::test_connection(&config))
+        .await
+        .map_err(|e| Error::Obs(format!("Connection test failed: {}", e)))?
+}
+
 // ============================================================================
 // HELPER FUNCTIONS
 // ============================================================================
 
+/// Pick the recorder backend from the recorderBackend setting
+/// OBS connection problems surface when recording starts - there's no silent fallback to native capture
+pub(crate) fn resolve_recorder(app: &tauri::AppHandle) -> Box<dyn Recorder + Send> {
+    if uses_obs(app) {
+        let config = obs_config(app);
+        log::info!("🎬 Using OBS recorder ({}:{})", config.host, config.port);
+        Box::new(ObsRecorder::new(config))
+    } else {
+        recorder::get_recorder()
+    }
+}
+
+/// Whether the recorderBackend setting selects OBS
+pub(crate) fn uses_obs(app: &tauri::AppHandle) -> bool {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(RECORDER_BACKEND_KEY))
+        .is_some_and(|v| v.as_str() == Some("obs"))
+}
+
+/// Read the obs-websocket connection settings
+pub(crate) fn obs_config(app: &tauri::AppHandle) -> ObsConfig {
+    let store = app.store("settings.json").ok();
+    let get = |key: &str| store.as_ref().and_then(|s| s.get(key));
+
+    ObsConfig {
+        host: get(OBS_HOST_KEY)
+            .and_then(|v| v.as_str().map(|h| h.trim().to_string()))
+            .filter(|h| !h.is_empty())
+            .unwrap_or_else(|| "127.0.0.1".to_string()),
+        port: get(OBS_PORT_KEY)
+            .and_then(|v| v.as_u64())
+            .and_then(|p| u16::try_from(p).ok())
+            .unwrap_or(obs::DEFAULT_PORT),
+        password: get(OBS_PASSWORD_KEY)
+            .and_then(|v| v.as_str().map(str::to_string))
+            .filter(|p| !p.is_empty()),
+    }
+}
+
 pub(crate) fn resolve_recording_quality(state: &State<'_, AppState>) -> Result<RecordingQuality, Error> {
     let settings = state
         .settings
@@ -134,27 +398,390 @@ fn log_quality_info(quality: &RecordingQuality) {
 }
 
 pub(crate) fn start_recording_with_quality(
+    app: &tauri::AppHandle,
     state: &State<'_, AppState>,
     output_path: &str,
     quality: RecordingQuality,
 ) -> Result<(), Error> {
+    if replay_only_mode(app) {
+        return Err(Error::RecordingFailed("Recording is off in replay-only mode".to_string()));
+    }
+    // Don't record alongside a stray process that still holds the recorder
+    state.instance_lock.ensure_held(app)?;
+    // A recording that fills the disk is cut off without its index
+    if let Some(dir) = Path::new(output_path).parent() {
+        if let Some(available) = readiness::free_space_at(dir).filter(|free| *free < readiness::MIN_FREE_SPACE) {
+            return Err(Error::DiskFull { needed: readiness::MIN_FREE_SPACE, available });
+        }
+    }
+
     let mut recorder_lock = state
         .recorder
         .lock()
         .map_err(|e| Error::InitializationError(format!("Failed to lock recorder: {}", e)))?;
     
+    let mut warm = false;
     if recorder_lock.is_none() {
-        *recorder_lock = Some(recorder::get_recorder());
+        let prewarmed = warm_start::take(state, uses_obs(app));
+        warm = prewarmed.is_some();
+        *recorder_lock = Some(prewarmed.unwrap_or_else(|| resolve_recorder(app)));
     }
     
     if let Some(recorder) = recorder_lock.as_mut() {
-        recorder.start_recording(output_path, quality)?;
+        let started = Instant::now();
+        let job = if warm { "recorder.start_warm" } else { "recorder.start_cold" };
+        metrics::job_sync(job, || recorder.start_recording(output_path, quality))?;
+        warm_start::log_start(state, warm, started.elapsed());
+        drop(recorder_lock);
+        state.work_gate.set_recording(true);
+        if let Ok(mut capture) = state.capture_settings.lock() {
+            *capture = Some(CaptureSettings {
+                quality,
+                backend: if uses_obs(app) { RecorderBackend::Obs } else { RecorderBackend::Native },
+                profile: state.active_profile.lock().ok().and_then(|p| p.clone()),
+            });
+        }
+        recovery::journal_started(app, output_path);
+        spawn_performance_monitor(app, state, output_path, quality);
+        viewport::start(app);
+        state.discord_presence.recording_started();
+        state.health.set_status(Subsystem::Recorder, HealthStatus::Running);
         Ok(())
     } else {
         Err(Error::InitializationError("Failed to initialize recorder".to_string()))
     }
 }
 
+/// Link a finished recording to its replay and write its sidecar, then run
+/// the user's post-recording hook in the background
+pub(crate) fn spawn_post_recording_hook(app: &tauri::AppHandle, result: &RecordingResult) {
+    let app = app.clone();
+    let video_path = result.output_path.clone();
+    let duration_secs = result.duration_secs;
+    let capture = result.capture.clone();
+    // Processing the markers removes them, so take them now
+    let clip_markers = clip_markers_for(&app.state::<AppState>(), &video_path);
+    
+    tauri::async_runtime::spawn(health::scope(Subsystem::Recorder, async move {
+        match library::index_recording(&app, Path::new(&video_path)).await {
+            Ok(recording) => {
+                if library::sidecar_enabled(&app) {
+                    let db = &app.state::<AppState>().database;
+                    if let Err(e) = library::write_sidecar(db, Path::new(&video_path), capture, Some(clip_markers)) {
+                        log::warn!("Failed to write the sidecar of {}: {}", video_path, e);
+                    }
+                }
+                library::run_post_recording_hook(&app, library::HookPayload::new(&recording, duration_secs)).await;
+            }
+            Err(e) => log::warn!("Failed to index {} for the post-recording hook: {}", video_path, e),
+        }
+    }));
+}
+
+/// Clip markers set on a recording (matched by file name, like process_clip_markers)
+fn clip_markers_for(state: &AppState, video_path: &str) -> Vec<f64> {
+    let stem = |path: &str| Path::new(path).file_stem().map(|s| s.to_os_string());
+    let Ok(markers) = state.clip_markers.lock() else { return Vec::new() };
+    markers
+        .iter()
+        .filter(|m| stem(&m.recording_file) == stem(video_path))
+        .map(|m| m.timestamp_seconds)
+        .collect()
+}
+
+/// Stop a recorder and store its capture stats (dropped frames, longest stall)
+/// and the active settings profile in the recordings table
+pub(crate) fn stop_and_save_capture_stats(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    recorder: &mut (dyn Recorder + Send),
+) -> Result<RecordingResult, Error> {
+    let mut result = stop_capture(app, state, recorder)?;
+    
+    let set = state.recording_set.lock().ok().and_then(|mut set| set.take());
+    let mut games = set.map(|set| set.games).unwrap_or_default();
+    let mut trimmed = false;
+    // A recording started during character select is cut down and renamed
+    // after its game, which moves where the games start
+    let pre_roll = state.pre_roll.lock().ok().and_then(|mut pre_roll| pre_roll.take());
+    if let Some((slp_path, offset)) = pre_roll.as_ref().and_then(|p| p.game.clone()) {
+        let (final_path, cut) = pre_roll::finish(app, &result.output_path, &slp_path, offset);
+        result.output_path = final_path;
+        trimmed = cut > 0.0;
+        result.duration_secs = result.duration_secs.map(|d| (d - cut).max(0.0));
+        result.first_frame_at = result
+            .first_frame_at
+            .map(|at| at + chrono::Duration::milliseconds((cut * 1000.0).round() as i64));
+        if games.is_empty() {
+            games.push(RecordingGame { game_index: 0, slp_path, offset_secs: offset });
+        }
+        for game in &mut games {
+            game.offset_secs = (game.offset_secs - cut).max(0.0);
+        }
+    }
+    // The crop's output hasn't had its index moved to the front yet
+    let cropped = viewport::finish(state, &result.output_path);
+    result.output_path = finalize_container(app, &result.output_path, trimmed && !cropped);
+    // How late the video started against its first game's replay
+    let video_offset_ms = result.first_frame_at.and_then(|first_frame_at| {
+        let first_game = games.first().cloned().or_else(|| {
+            let slp_path = state.last_replay_path.lock().ok()?.clone()?;
+            let stem = |path: &str| Path::new(path).file_stem().map(|s| s.to_os_string());
+            (stem(&slp_path) == stem(&result.output_path))
+                .then_some(RecordingGame { game_index: 0, slp_path, offset_secs: 0.0 })
+        })?;
+        measure_video_offset(first_frame_at, &first_game)
+    });
+    // A single game starting with the video needs no offsets
+    if games.len() == 1 && games[0].offset_secs == 0.0 {
+        games.clear();
+    }
+    
+    let profile = state.active_profile.lock().ok().and_then(|p| p.clone());
+    if result.dropped_frame_estimate.is_some() || profile.is_some() || !games.is_empty() || video_offset_ms.is_some() {
+        let conn = state.database.connection();
+        let id = database::get_recording_by_video_path(&conn, &result.output_path)
+            .ok()
+            .flatten()
+            .map(|row| row.id)
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        if let Err(e) = database::set_recording_capture_info(
+            &conn,
+            &id,
+            &result.output_path,
+            result.dropped_frame_estimate.map(|d| d as i64),
+            result.longest_stall_ms.map(|s| s as i64),
+            profile.as_deref(),
+            video_offset_ms,
+        ) {
+            log::warn!("Failed to save recording metadata: {}", e);
+        }
+        if !games.is_empty() {
+            match database::set_recording_games(&conn, &id, &games) {
+                Ok(()) => log::info!("🎬 Saved where the {} game(s) start in {}", games.len(), result.output_path),
+                Err(e) => log::warn!("Failed to save where the games start: {}", e),
+            }
+        }
+    }
+    // Named by the template once its game's players are known
+    if library::recording_name_template(app).is_some() {
+        library::defer_rename(state, &result.output_path);
+    }
+    
+    Ok(result)
+}
+
+/// The recordingContainer setting
+fn recording_container(app: &tauri::AppHandle) -> RecordingContainer {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(RECORDING_CONTAINER_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or(RecordingContainer::Mp4)
+}
+
+/// Copy a stopped MP4 recording into the recordingContainer file type: an
+/// MKV, or an MP4 with its index in front so uploads stream. A trimmed
+/// pre-roll already has it. Returns where the recording ended up (where it
+/// was if FFmpeg fails)
+fn finalize_container(app: &tauri::AppHandle, output_path: &str, trimmed: bool) -> String {
+    let path = Path::new(output_path);
+    let container = recording_container(app);
+    let is_mp4 = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("mp4"));
+    if !is_mp4 || !path.exists() || (container == RecordingContainer::Mp4 && trimmed) {
+        return output_path.to_string();
+    }
+    if let Err(e) = crate::clip_processor::ensure_ffmpeg() {
+        log::warn!("Can't finalize {} without FFmpeg: {}", output_path, e);
+        return output_path.to_string();
+    }
+
+    let target = match container {
+        RecordingContainer::Mp4 => path.with_extension("finalizing.mp4"),
+        RecordingContainer::Mkv => path.with_extension(container.extension()),
+    };
+    let finalized = crate::clip_processor::remux_video(path, &target).and_then(|()| match container {
+        RecordingContainer::Mp4 => crate::clip_processor::replace_with(path, &target),
+        RecordingContainer::Mkv => std::fs::remove_file(path).map_err(Error::from),
+    });
+    match (finalized, container) {
+        (Ok(()), RecordingContainer::Mp4) => output_path.to_string(),
+        (Ok(()), RecordingContainer::Mkv) => {
+            log::info!("📦 Remuxed {} to MKV", output_path);
+            target.to_string_lossy().to_string()
+        }
+        (Err(e), _) => {
+            let _ = std::fs::remove_file(&target);
+            log::warn!("Failed to finalize {}, keeping it as it was: {}", output_path, e);
+            output_path.to_string()
+        }
+    }
+}
+
+/// How far the video's first frame is behind where its first game should be
+/// (the replay's startAt, less how far into the video the game starts), in
+/// milliseconds. None without a finished replay or when the clocks disagree
+/// by more than MAX_MEASURED_VIDEO_OFFSET_MS
+fn measure_video_offset(first_frame_at: DateTime<Utc>, game: &RecordingGame) -> Option<i64> {
+    let start_at = slippi::metadata::read_start_at(Path::new(&game.slp_path))?;
+    let game_at_video_start = start_at - chrono::Duration::milliseconds((game.offset_secs * 1000.0).round() as i64);
+    let offset_ms = (first_frame_at - game_at_video_start).num_milliseconds();
+    if offset_ms.abs() > MAX_MEASURED_VIDEO_OFFSET_MS {
+        log::warn!("Ignoring a {}ms video offset for {} - clocks skewed?", offset_ms, game.slp_path);
+        return None;
+    }
+    Some(offset_ms)
+}
+
+/// Stop the recorder and wrap up the recording's bookkeeping, without
+/// touching the database
+pub(crate) fn stop_capture(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    recorder: &mut (dyn Recorder + Send),
+) -> Result<RecordingResult, Error> {
+    // Read the counters first - backends reset them on stop
+    let stats = recorder.capture_stats();
+    let first_frame_at = recorder.first_frame_at();
+    let output_path = recorder.stop_recording()?;
+    recovery::journal_stopped(app);
+    let mut result = RecordingResult::new(output_path, stats);
+    result.first_frame_at = first_frame_at;
+    result.capture = state.capture_settings.lock().ok().and_then(|mut c| c.take());
+    state.discord_presence.recording_stopped();
+    state.health.set_status(Subsystem::Recorder, HealthStatus::Idle);
+    
+    if let (Some(dropped), Some(stall)) = (result.dropped_frame_estimate, result.longest_stall_ms) {
+        log::info!(
+            "📉 Capture stats for {}: ~{} dropped frames, longest stall {}ms",
+            result.output_path,
+            dropped,
+            stall
+        );
+    }
+    Ok(result)
+}
+
+/// Watch the dropped-frame ratio of the current recording and emit a
+/// performance warning (once) if it goes over the threshold
+fn spawn_performance_monitor(
+    app: &tauri::AppHandle,
+    state: &State<'_, AppState>,
+    output_path: &str,
+    quality: RecordingQuality,
+) {
+    // Cancel the monitor of any previous recording
+    let cancel = Arc::new(AtomicBool::new(false));
+    if let Ok(mut monitor) = state.performance_monitor.lock() {
+        if let Some(previous) = monitor.replace(cancel.clone()) {
+            previous.store(true, Ordering::Relaxed);
+        }
+    }
+    
+    let app = app.clone();
+    let output_path = output_path.to_string();
+    tauri::async_runtime::spawn(health::scope(Subsystem::Recorder, async move {
+        let mut previous: Option<CaptureStats> = None;
+        
+        loop {
+            tokio::time::sleep(PERFORMANCE_SAMPLE_INTERVAL).await;
+            if cancel.load(Ordering::Relaxed) {
+                break;
+            }
+            
+            let state = app.state::<AppState>();
+            let stats = {
+                let Ok(recorder_lock) = state.recorder.lock() else {
+                    break;
+                };
+                match recorder_lock.as_ref() {
+                    Some(recorder) if recorder.is_recording() => recorder.capture_stats(),
+                    _ => break,
+                }
+            };
+            
+            // No first frame yet (or backend doesn't track frames)
+            let Some(stats) = stats else {
+                continue;
+            };
+            
+            let window = previous.map(|p| stats.since(&p)).unwrap_or(stats);
+            previous = Some(stats);
+            
+            let dropped_ratio = window.dropped_ratio();
+            if dropped_ratio > DROPPED_FRAME_WARNING_RATIO {
+                let warning = PerformanceWarning {
+                    output_path: output_path.clone(),
+                    quality,
+                    dropped_ratio,
+                    suggested_quality: quality.lower(),
+                };
+                log::warn!(
+                    "⚠️ Recording is dropping {:.1}% of frames at {:?} quality (suggesting {:?})",
+                    dropped_ratio * 100.0,
+                    quality,
+                    warning.suggested_quality
+                );
+                if let Err(e) = app.emit(recording_events::PERFORMANCE_WARNING, warning) {
+                    log::error!("Failed to emit {} event: {:?}", recording_events::PERFORMANCE_WARNING, e);
+                }
+                break;
+            }
+        }
+    }));
+}
+
+/// Record one preset for BENCHMARK_DURATION and measure dropped frames
+async fn benchmark_preset(quality: RecordingQuality, output_path: &Path) -> PresetBenchmark {
+    let mut recorder = recorder::get_recorder();
+    let output = output_path.to_string_lossy().to_string();
+    
+    if let Err(e) = recorder.start_recording(&output, quality) {
+        return PresetBenchmark {
+            quality,
+            achieved_fps: 0.0,
+            dropped_frames: 0,
+            dropped_ratio: 1.0,
+            error: Some(e.to_string()),
+        };
+    }
+    
+    tokio::time::sleep(BENCHMARK_DURATION).await;
+    let stats = recorder.capture_stats();
+    
+    if let Err(e) = recorder.stop_recording() {
+        log::warn!("Failed to stop benchmark recording for {:?}: {}", quality, e);
+    }
+    
+    match stats {
+        Some(stats) => PresetBenchmark {
+            quality,
+            achieved_fps: stats.achieved_fps(),
+            dropped_frames: stats.dropped_frames(),
+            dropped_ratio: stats.dropped_ratio(),
+            error: None,
+        },
+        None => PresetBenchmark {
+            quality,
+            achieved_fps: 0.0,
+            dropped_frames: 0,
+            dropped_ratio: 1.0,
+            error: Some("No frames were captured".to_string()),
+        },
+    }
+}
+
+/// Delete a benchmark or test capture file, retrying briefly while the encoder releases it
+async fn remove_benchmark_file(path: &Path) {
+    for _ in 0..5 {
+        if !path.exists() || std::fs::remove_file(path).is_ok() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    log::warn!("Failed to delete benchmark file: {:?}", path);
+}
+
 #[cfg(target_os = "windows")]
 pub(crate) fn configure_target_window(state: &State<'_, AppState>) {
     let identifier = match state.settings.lock() {
@@ -0,0 +1,60 @@
+//! Commands for the recording rules
+
+use crate::app_state::AppState;
+use crate::commands::errors::Error;
+use crate::database::{self, RecordingRuleLogEntry, RecordingRuleRow};
+use crate::metrics;
+use crate::recording_rules;
+use tauri::State;
+
+/// Log entries returned when no limit is given
+const DEFAULT_LOG_LIMIT: i64 = 100;
+
+#[tauri::command]
+#[metrics::timed]
+pub async fn list_recording_rules(state: State<'_, AppState>) -> Result<Vec<RecordingRuleRow>, Error> {
+    let conn = state.database.connection();
+    database::get_recording_rules(&conn).map_err(|e| Error::database("Failed to get recording rules", e))
+}
+
+/// Create a rule, or update it if it has an id. Returns the saved rule
+#[tauri::command]
+#[metrics::timed]
+pub async fn save_recording_rule(
+    mut rule: RecordingRuleRow,
+    state: State<'_, AppState>,
+) -> Result<RecordingRuleRow, Error> {
+    recording_rules::validate_rule(&rule).map_err(Error::InvalidPath)?;
+    // A blank code means "any", and codes are stored as Slippi shows them
+    rule.opponent_code = rule.opponent_code.take().map(|c| c.trim().to_uppercase()).filter(|c| !c.is_empty());
+    rule.name = rule.name.trim().to_string();
+    if rule.action != database::RecordingRuleAction::RecordAtQuality {
+        rule.quality = None;
+    }
+
+    let conn = state.database.connection();
+    let id =
+        database::save_recording_rule(&conn, &rule).map_err(|e| Error::database("Failed to save recording rule", e))?;
+    log::info!("📋 Saved recording rule '{}' ({:?})", rule.name, rule.action);
+    Ok(RecordingRuleRow { id: Some(id), ..rule })
+}
+
+#[tauri::command]
+#[metrics::timed]
+pub async fn delete_recording_rule(id: i64, state: State<'_, AppState>) -> Result<(), Error> {
+    let conn = state.database.connection();
+    database::delete_recording_rule(&conn, id).map_err(|e| Error::database("Failed to delete recording rule", e))?;
+    Ok(())
+}
+
+/// Which rule decided each recent game, newest first
+#[tauri::command]
+#[metrics::timed]
+pub async fn get_recording_rule_log(
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<RecordingRuleLogEntry>, Error> {
+    let conn = state.database.connection();
+    database::get_recording_rule_log(&conn, limit.unwrap_or(DEFAULT_LOG_LIMIT).max(1))
+        .map_err(|e| Error::database("Failed to get the recording rule log", e))
+}
@@ -0,0 +1,56 @@
+//! Commands for the recording retention rules
+
+use crate::app_state::AppState;
+use crate::commands::clips::default_clips_directory;
+use crate::commands::errors::Error;
+use crate::database::{self, RetentionRuleRow};
+use crate::metrics;
+use crate::retention::{self, RetentionReport};
+use tauri::State;
+
+#[tauri::command]
+#[metrics::timed]
+pub async fn list_retention_rules(state: State<'_, AppState>) -> Result<Vec<RetentionRuleRow>, Error> {
+    let conn = state.database.connection();
+    database::get_retention_rules(&conn).map_err(|e| Error::database("Failed to get retention rules", e))
+}
+
+/// Create a rule, or update it if it has an id. Returns the saved rule
+#[tauri::command]
+#[metrics::timed]
+pub async fn save_retention_rule(
+    mut rule: RetentionRuleRow,
+    state: State<'_, AppState>,
+) -> Result<RetentionRuleRow, Error> {
+    retention::validate_rule(&rule).map_err(Error::InvalidPath)?;
+    // Blank criteria mean "any"
+    for criterion in [&mut rule.tag, &mut rule.opponent] {
+        *criterion = criterion.take().map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+    }
+    rule.name = rule.name.trim().to_string();
+
+    let conn = state.database.connection();
+    let id =
+        database::save_retention_rule(&conn, &rule).map_err(|e| Error::database("Failed to save retention rule", e))?;
+    log::info!("🧹 Saved retention rule '{}' ({:?})", rule.name, rule.action);
+    Ok(RetentionRuleRow { id: Some(id), ..rule })
+}
+
+#[tauri::command]
+#[metrics::timed]
+pub async fn delete_retention_rule(id: i64, state: State<'_, AppState>) -> Result<(), Error> {
+    let conn = state.database.connection();
+    database::delete_retention_rule(&conn, id).map_err(|e| Error::database("Failed to delete retention rule", e))?;
+    Ok(())
+}
+
+/// Apply the retention rules now. With `dry_run`, only report what would happen
+#[tauri::command]
+#[metrics::timed]
+pub async fn apply_retention(app: tauri::AppHandle, dry_run: bool) -> Result<RetentionReport, Error> {
+    let clips_dir = default_clips_directory(&app).await?;
+    // Deleting is quick, but compressing runs FFmpeg for every matching video
+    tauri::async_runtime::spawn_blocking(move || retention::apply(&app, &clips_dir, dry_run))
+        .await
+        .map_err(|e| Error::Io(std::io::Error::other(format!("Retention task failed: {}", e))))?
+}
@@ -1,45 +1,98 @@
+use crate::app_state::AppState;
+use crate::clip_processor;
+use crate::commands::errors::Error;
+use crate::database;
+use crate::discord_presence::PresenceSettings;
+use crate::events;
+use crate::library;
+use crate::metrics;
+use crate::settings::{self, ImportReport, Profile, Settings};
+use crate::watch_filters::{WatchFilters, WATCH_FILTERS_KEY};
+use crate::work_gate::JobKind;
+use serde::Serialize;
 use std::path::PathBuf;
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_autostart::ManagerExt;
+use tauri_plugin_store::StoreExt;
+
+/// Settings key for the list of the user's own connect codes
+const MY_CONNECT_CODES_KEY: &str = "myConnectCodes";
+/// Settings key for the user's usual in-game tag (for local games without connect codes)
+const MY_IN_GAME_TAG_KEY: &str = "myInGameTag";
+/// Settings key for the character IDs the user usually plays (for local games)
+const MY_PREFERRED_CHARACTERS_KEY: &str = "myPreferredCharacters";
+/// Settings key for Discord Rich Presence
+const ENABLE_DISCORD_PRESENCE_KEY: &str = "enableDiscordPresence";
+/// Settings key for showing the opponent's connect code in Discord Rich Presence
+const DISCORD_PRESENCE_SHOW_OPPONENT_KEY: &str = "discordPresenceShowOpponent";
+/// Settings key for launching on system startup (mirrors the OS autostart entry)
+const LAUNCH_ON_STARTUP_KEY: &str = "launchOnStartup";
+/// Settings key for starting minimized when launched on startup
+const START_MINIMIZED_KEY: &str = "startMinimized";
+/// Settings key for picking up watching and the play session where the last run left them
+const RESTORE_STATE_ON_LAUNCH_KEY: &str = "restoreStateOnLaunch";
+/// Settings key for the shortest game counted in stats, in seconds
+const MIN_GAME_SECONDS_FOR_STATS_KEY: &str = "minGameSecondsForStats";
+/// Settings key for the games at the start of each session flagged as warm-ups
+const WARMUP_GAMES_KEY: &str = "warmupGames";
+/// Settings key for the break between games that starts a new session, in minutes
+const SESSION_GAP_MINUTES_KEY: &str = "sessionGapMinutes";
+/// Settings key for recording (and counting) games against CPUs
+const RECORD_CPU_MATCHES_KEY: &str = "recordCpuMatches";
+/// Settings key for counting games whose replay never reached Game End
+const COUNT_UNFINISHED_GAMES_KEY: &str = "countUnfinishedGames";
+/// Settings key for indexing replays without recording videos
+const REPLAY_ONLY_MODE_KEY: &str = "replayOnlyMode";
+/// Settings key for the background jobs that keep running (throttled) while recording
+const JOBS_WHILE_RECORDING_KEY: &str = "jobsWhileRecording";
+/// Settings key for decoding video with the GPU when FFmpeg can
+const HARDWARE_DECODE_KEY: &str = "hardwareDecode";
+/// Settings key for the saved settings profiles
+const PROFILES_KEY: &str = "profiles";
+/// Settings key for the name of the last applied profile
+const ACTIVE_PROFILE_KEY: &str = "activeProfile";
+
+/// Argument the autostart entry launches the app with
+pub const MINIMIZED_ARG: &str = "--minimized";
 
 #[tauri::command]
-pub fn get_settings_path(app: AppHandle) -> Result<String, String> {
+#[metrics::timed]
+pub fn get_settings_path(app: AppHandle) -> Result<String, Error> {
     let app_data_dir = app
         .path()
         .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        .map_err(|e| Error::InitializationError(format!("Failed to get app data directory: {}", e)))?;
 
     let settings_path = app_data_dir.join("settings.json");
 
     Ok(settings_path
         .to_str()
-        .ok_or("Invalid path encoding")?
+        .ok_or_else(|| Error::InvalidPath("Invalid path encoding".to_string()))?
         .to_string())
 }
 
 #[tauri::command]
-pub fn open_settings_folder(app: AppHandle) -> Result<(), String> {
+#[metrics::timed]
+pub fn open_settings_folder(app: AppHandle) -> Result<(), Error> {
     let app_data_dir = app
         .path()
         .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        .map_err(|e| Error::InitializationError(format!("Failed to get app data directory: {}", e)))?;
 
     #[cfg(target_os = "macos")]
     std::process::Command::new("open")
         .arg(&app_data_dir)
-        .spawn()
-        .map_err(|e| format!("Failed to open folder: {}", e))?;
+        .spawn()?;
 
     #[cfg(target_os = "windows")]
     std::process::Command::new("explorer")
         .arg(&app_data_dir)
-        .spawn()
-        .map_err(|e| format!("Failed to open folder: {}", e))?;
+        .spawn()?;
 
     #[cfg(target_os = "linux")]
     std::process::Command::new("xdg-open")
         .arg(&app_data_dir)
-        .spawn()
-        .map_err(|e| format!("Failed to open folder: {}", e))?;
+        .spawn()?;
 
     Ok(())
 }
@@ -47,11 +100,12 @@ pub fn open_settings_folder(app: AppHandle) -> Result<(), String> {
 /// Get a setting value from the settings store
 /// Returns the value as a string, or None if the setting doesn't exist
 #[tauri::command]
-pub async fn get_setting(app: AppHandle, key: String) -> Result<Option<String>, String> {
+#[metrics::timed]
+pub async fn get_setting(app: AppHandle, key: String) -> Result<Option<String>, Error> {
     let path = app
         .path()
         .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        .map_err(|e| Error::InitializationError(format!("Failed to get app data directory: {}", e)))?;
     let store_path = path.join("settings.json");
 
     // Try to read setting from settings file
@@ -78,7 +132,8 @@ pub async fn get_setting(app: AppHandle, key: String) -> Result<Option<String>,
 /// Get the recording output directory, resolving defaults and ensuring it exists
 /// Returns the directory path (not a file path)
 #[tauri::command]
-pub async fn get_recording_directory(app: AppHandle) -> Result<String, String> {
+#[metrics::timed]
+pub async fn get_recording_directory(app: AppHandle) -> Result<String, Error> {
     // Get recordingPath from settings
     let recording_path = get_setting(app.clone(), "recordingPath".to_string()).await?;
 
@@ -91,7 +146,7 @@ pub async fn get_recording_directory(app: AppHandle) -> Result<String, String> {
             } else {
                 app.path()
                     .app_data_dir()
-                    .map_err(|e| format!("Failed to get app data directory: {}", e))?
+                    .map_err(|e| Error::InitializationError(format!("Failed to get app data directory: {}", e)))?
                     .join("Recordings")
             }
         } else {
@@ -100,7 +155,7 @@ pub async fn get_recording_directory(app: AppHandle) -> Result<String, String> {
                 if let Some(home) = std::env::var_os("HOME") {
                     PathBuf::from(home).join(&path[2..])
                 } else {
-                    return Err("HOME environment variable not set".to_string());
+                    return Err(Error::InvalidPath("HOME environment variable not set".to_string()));
                 }
             } else {
                 PathBuf::from(path)
@@ -114,18 +169,571 @@ pub async fn get_recording_directory(app: AppHandle) -> Result<String, String> {
         } else {
             app.path()
                 .app_data_dir()
-                .map_err(|e| format!("Failed to get app data directory: {}", e))?
+                .map_err(|e| Error::InitializationError(format!("Failed to get app data directory: {}", e)))?
                 .join("Recordings")
         }
     };
 
-    // Ensure the directory exists
-    if let Err(e) = std::fs::create_dir_all(&final_path) {
-        return Err(format!("Failed to create recording directory: {}", e));
-    }
+    // Ensure the directory exists (and its drive is connected)
+    library::ensure_writable(&app, "recordingPath", &final_path)?;
 
     Ok(final_path
         .to_str()
-        .ok_or("Invalid path encoding")?
+        .ok_or_else(|| Error::InvalidPath("Invalid path encoding".to_string()))?
         .to_string())
 }
+
+/// Get the user's own connect codes (normalized to uppercase)
+#[tauri::command]
+#[metrics::timed]
+pub fn get_my_connect_codes(app: AppHandle) -> Vec<String> {
+    my_connect_codes(&app)
+}
+
+/// Set the user's own connect codes and re-flag which players are "me" in stored stats
+/// Returns the normalized list that was saved
+#[tauri::command]
+#[metrics::timed]
+pub async fn set_my_connect_codes(
+    app: AppHandle,
+    codes: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, Error> {
+    let mut normalized: Vec<String> = Vec::new();
+    for code in codes.iter().map(|c| c.trim().to_uppercase()) {
+        if !code.is_empty() && !normalized.contains(&code) {
+            normalized.push(code);
+        }
+    }
+
+    save_store_value(&app, MY_CONNECT_CODES_KEY, serde_json::json!(normalized))?;
+
+    let attributed = reattribute_games(&app, &state)?;
+    log::info!("🙋 Saved {} connect code(s), {} game(s) attributed to me", normalized.len(), attributed);
+    Ok(normalized)
+}
+
+/// Set my in-game tag and preferred characters, used to find me in local games without connect codes
+#[tauri::command]
+#[metrics::timed]
+pub async fn set_my_local_identity(
+    app: AppHandle,
+    in_game_tag: Option<String>,
+    preferred_characters: Vec<i32>,
+    state: State<'_, AppState>,
+) -> Result<(), Error> {
+    let tag = in_game_tag
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty());
+
+    save_store_value(&app, MY_IN_GAME_TAG_KEY, serde_json::json!(tag))?;
+    save_store_value(&app, MY_PREFERRED_CHARACTERS_KEY, serde_json::json!(preferred_characters))?;
+
+    let attributed = reattribute_games(&app, &state)?;
+    log::info!("🙋 Saved local identity (tag={:?}), {} game(s) attributed to me", tag, attributed);
+    Ok(())
+}
+
+/// Set which new replays start an auto-recording. Applies from the next game.
+/// Returns the rules as saved (trimmed, without empty or repeated patterns)
+#[tauri::command]
+#[metrics::timed]
+pub fn set_watch_filters(app: AppHandle, filters: WatchFilters) -> Result<WatchFilters, Error> {
+    let filters = filters.normalized().map_err(Error::InvalidPath)?;
+    let value = serde_json::to_value(&filters).map_err(std::io::Error::from)?;
+    save_store_value(&app, WATCH_FILTERS_KEY, value)?;
+    log::info!(
+        "👀 Saved watch filters: {} ignore pattern(s), {}s between recordings, players {:?}-{:?}, skip teams {}, {:?} games",
+        filters.ignore_patterns.len(),
+        filters.min_trigger_interval_seconds,
+        filters.min_players,
+        filters.max_players,
+        filters.skip_teams,
+        filters.connection
+    );
+    Ok(filters)
+}
+
+/// Write the current settings to a JSON file, without secrets or identity
+#[tauri::command]
+#[metrics::timed]
+pub fn export_settings(app: AppHandle, dest_path: String) -> Result<(), Error> {
+    let store = app
+        .store("settings.json")
+        .map_err(|e| Error::InitializationError(format!("Failed to open settings store: {}", e)))?;
+    let exported = settings::export(&store.entries().into_iter().collect());
+    let json = serde_json::to_string_pretty(&exported).map_err(std::io::Error::from)?;
+    std::fs::write(&dest_path, json)?;
+
+    log::info!("📤 Exported settings to {}", dest_path);
+    Ok(())
+}
+
+/// Import settings exported from another machine
+/// Nothing is written if any value is invalid; the report lists every problem
+#[tauri::command]
+#[metrics::timed]
+pub fn import_settings(app: AppHandle, path: String, state: State<'_, AppState>) -> Result<ImportReport, Error> {
+    let contents = std::fs::read_to_string(&path)?;
+    let file: serde_json::Value = serde_json::from_str(&contents)
+        .map_err(|e| Error::InvalidPath(format!("Not a valid settings file: {}", e)))?;
+
+    let (entries, report) = settings::validate_import(&file);
+    if !report.applied {
+        log::warn!("Rejected settings import from {}: {:?}", path, report.errors);
+        return Ok(report);
+    }
+
+    save_store_values(&app, entries)?;
+    state.discord_presence.update_settings(discord_presence_settings(&app));
+    state.work_gate.set_overrides(jobs_while_recording(&app));
+    clip_processor::set_hardware_decode(hardware_decode(&app));
+    reexclude_games(&app, &state)?;
+    log::info!(
+        "📥 Imported settings from {} ({} path(s) reset, {} ignored)",
+        path,
+        report.replaced_paths.len(),
+        report.ignored.len()
+    );
+    Ok(report)
+}
+
+/// Reset every setting to its default, keeping the user's identity
+/// (connect codes, tag and characters) so stats stay attributed
+#[tauri::command]
+#[metrics::timed]
+pub fn reset_settings_to_defaults(app: AppHandle, state: State<'_, AppState>) -> Result<(), Error> {
+    let mut defaults = Settings::default().to_entries();
+    for key in [MY_CONNECT_CODES_KEY, MY_IN_GAME_TAG_KEY, MY_PREFERRED_CHARACTERS_KEY] {
+        defaults.remove(key);
+    }
+
+    // The settings no longer match any profile (the profiles themselves are kept)
+    defaults.insert(ACTIVE_PROFILE_KEY.to_string(), serde_json::Value::Null);
+
+    if let Err(e) = app.autolaunch().disable() {
+        log::warn!("Failed to remove launch on startup entry: {}", e);
+    }
+    save_store_values(&app, defaults)?;
+    set_active_profile_state(&state, None);
+    state.discord_presence.update_settings(discord_presence_settings(&app));
+    state.work_gate.set_overrides(jobs_while_recording(&app));
+    clip_processor::set_hardware_decode(hardware_decode(&app));
+    reexclude_games(&app, &state)?;
+    log::info!("↩️ Reset settings to defaults");
+    Ok(())
+}
+
+/// Saved settings profiles and the one applied last
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileList {
+    pub profiles: Vec<Profile>,
+    pub active: Option<String>,
+}
+
+#[tauri::command]
+#[metrics::timed]
+pub fn list_profiles(app: AppHandle) -> Result<ProfileList, Error> {
+    Ok(ProfileList {
+        profiles: read_profiles(&app)?,
+        active: active_profile(&app),
+    })
+}
+
+/// Save the current capture setup as a profile (replacing one with the same name)
+/// It becomes the active profile, since it matches the current settings
+#[tauri::command]
+#[metrics::timed]
+pub fn create_profile(app: AppHandle, name: String, state: State<'_, AppState>) -> Result<Profile, Error> {
+    let store = app
+        .store("settings.json")
+        .map_err(|e| Error::InitializationError(format!("Failed to open settings store: {}", e)))?;
+    let game_window = state
+        .settings
+        .lock()
+        .ok()
+        .and_then(|settings| settings.get("game_process_name").and_then(|v| v.as_str()).map(str::to_string));
+    let profile = settings::snapshot_profile(&name, &store.entries().into_iter().collect(), game_window)
+        .map_err(Error::InvalidPath)?;
+
+    let mut profiles = read_profiles(&app)?;
+    profiles.retain(|p| p.name != profile.name);
+    profiles.push(profile.clone());
+
+    let mut values = serde_json::Map::new();
+    values.insert(PROFILES_KEY.to_string(), serde_json::json!(profiles));
+    values.insert(ACTIVE_PROFILE_KEY.to_string(), serde_json::json!(profile.name));
+    save_store_values(&app, values)?;
+    set_active_profile_state(&state, Some(profile.name.clone()));
+
+    log::info!("🗂️ Saved settings profile '{}'", profile.name);
+    Ok(profile)
+}
+
+/// Apply a profile's settings and make it the active profile
+/// A recording in progress keeps its settings; the next one uses the profile's.
+/// The watcher moves to the profile's replay folder if it's running
+#[tauri::command]
+#[metrics::timed]
+pub fn apply_profile(app: AppHandle, name: String, state: State<'_, AppState>) -> Result<Profile, Error> {
+    let profile = read_profiles(&app)?
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| Error::InvalidPath(format!("No profile named '{}'", name)))?;
+    let mut values = settings::profile_entries(&profile).map_err(|errors| {
+        let fields: Vec<String> = errors.iter().map(|e| format!("{}: {}", e.field, e.message)).collect();
+        Error::InvalidPath(format!("Profile '{}' has invalid settings ({})", name, fields.join(", ")))
+    })?;
+    values.insert(ACTIVE_PROFILE_KEY.to_string(), serde_json::json!(profile.name));
+
+    let previous_slippi_dir = library::get_slippi_directory(&app).ok();
+    save_store_values(&app, values)?;
+    if let Some(game_window) = &profile.game_window {
+        if let Ok(mut settings) = state.settings.lock() {
+            settings.insert("game_process_name".to_string(), serde_json::json!(game_window));
+        }
+    }
+    set_active_profile_state(&state, Some(profile.name.clone()));
+
+    let slippi_dir = library::get_slippi_directory(&app).ok();
+    if slippi_dir != previous_slippi_dir {
+        if let Some(dir) = slippi_dir {
+            if let Err(e) = crate::commands::slippi::restart_watching_in(&app, Some(PathBuf::from(dir))) {
+                log::warn!("Failed to move the watcher to the profile's replay folder: {}", e);
+            }
+        }
+    }
+
+    if let Err(e) = app.emit(events::settings::CHANGED, serde_json::json!({ "profile": profile.name })) {
+        log::warn!("Failed to emit settings changed event: {}", e);
+    }
+    log::info!("🗂️ Applied settings profile '{}'", profile.name);
+    Ok(profile)
+}
+
+/// Delete a profile; the settings it applied stay as they are
+#[tauri::command]
+#[metrics::timed]
+pub fn delete_profile(app: AppHandle, name: String, state: State<'_, AppState>) -> Result<(), Error> {
+    let mut profiles = read_profiles(&app)?;
+    let count = profiles.len();
+    profiles.retain(|p| p.name != name);
+    if profiles.len() == count {
+        return Err(Error::InvalidPath(format!("No profile named '{}'", name)));
+    }
+
+    let mut values = serde_json::Map::new();
+    values.insert(PROFILES_KEY.to_string(), serde_json::json!(profiles));
+    let was_active = active_profile(&app).as_deref() == Some(name.as_str());
+    if was_active {
+        values.insert(ACTIVE_PROFILE_KEY.to_string(), serde_json::Value::Null);
+    }
+    save_store_values(&app, values)?;
+    if was_active {
+        set_active_profile_state(&state, None);
+    }
+
+    log::info!("🗂️ Deleted settings profile '{}'", name);
+    Ok(())
+}
+
+// ============================================================================
+// HELPER FUNCTIONS
+// ============================================================================
+
+/// Read the user's own connect codes from the settings store
+pub fn my_connect_codes(app: &AppHandle) -> Vec<String> {
+    let Ok(store) = app.store("settings.json") else {
+        return Vec::new();
+    };
+
+    store
+        .get(MY_CONNECT_CODES_KEY)
+        .and_then(|v| v.as_array().cloned())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|v| v.as_str())
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+/// Read everything we know about the local user (connect codes + local game hints)
+pub fn my_identity(app: &AppHandle) -> database::MyIdentity {
+    let Ok(store) = app.store("settings.json") else {
+        return database::MyIdentity::default();
+    };
+
+    let in_game_tag = store
+        .get(MY_IN_GAME_TAG_KEY)
+        .and_then(|v| v.as_str().map(|t| t.trim().to_string()))
+        .filter(|t| !t.is_empty());
+
+    let preferred_characters = store
+        .get(MY_PREFERRED_CHARACTERS_KEY)
+        .and_then(|v| v.as_array().cloned())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|v| v.as_i64().map(|id| id as i32))
+        .collect();
+
+    database::MyIdentity {
+        connect_codes: my_connect_codes(app),
+        in_game_tag,
+        preferred_characters,
+    }
+}
+
+/// Read the name of the last applied settings profile
+pub fn active_profile(app: &AppHandle) -> Option<String> {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(ACTIVE_PROFILE_KEY))
+        .and_then(|v| v.as_str().map(str::to_string))
+}
+
+/// Read the saved settings profiles
+fn read_profiles(app: &AppHandle) -> Result<Vec<Profile>, Error> {
+    let store = app
+        .store("settings.json")
+        .map_err(|e| Error::InitializationError(format!("Failed to open settings store: {}", e)))?;
+    match store.get(PROFILES_KEY) {
+        Some(value) => serde_json::from_value(value)
+            .map_err(|e| Error::InvalidPath(format!("Saved profiles are corrupted: {}", e))),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn set_active_profile_state(state: &AppState, name: Option<String>) {
+    if let Ok(mut active) = state.active_profile.lock() {
+        *active = name;
+    }
+}
+
+/// Read the Discord Rich Presence settings (both off by default)
+pub fn discord_presence_settings(app: &AppHandle) -> PresenceSettings {
+    let Ok(store) = app.store("settings.json") else {
+        return PresenceSettings::default();
+    };
+    let get = |key: &str| store.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+
+    PresenceSettings {
+        enabled: get(ENABLE_DISCORD_PRESENCE_KEY),
+        show_opponent: get(DISCORD_PRESENCE_SHOW_OPPONENT_KEY),
+    }
+}
+
+/// Apply the saved Discord Rich Presence settings (call after changing them)
+#[tauri::command]
+#[metrics::timed]
+pub fn apply_discord_presence_settings(app: AppHandle, state: State<'_, AppState>) {
+    state.discord_presence.update_settings(discord_presence_settings(&app));
+}
+
+/// Which games are left out of stats (only unfinished games are by default)
+pub fn stats_exclusion(app: &AppHandle) -> database::StatsExclusion {
+    let Ok(store) = app.store("settings.json") else {
+        return database::StatsExclusion { exclude_unfinished: true, ..Default::default() };
+    };
+    let min_seconds = store
+        .get(MIN_GAME_SECONDS_FOR_STATS_KEY)
+        .and_then(|v| v.as_u64())
+        .map(|v| v.min(u64::from(*settings::MIN_GAME_SECONDS_FOR_STATS.end())) as i32)
+        .unwrap_or(0);
+
+    database::StatsExclusion {
+        // Replays count frames at 60 per second
+        min_frames: min_seconds * 60,
+        exclude_cpu: !record_cpu_matches(app),
+        exclude_unfinished: !store.get(COUNT_UNFINISHED_GAMES_KEY).and_then(|v| v.as_bool()).unwrap_or(false),
+    }
+}
+
+/// How warm-up games are told apart (none are by default)
+pub fn warmup_settings(app: &AppHandle) -> database::WarmupSettings {
+    let Ok(store) = app.store("settings.json") else {
+        return database::WarmupSettings::default();
+    };
+    let get = |key: &str, range: &std::ops::RangeInclusive<u32>, default: u32| {
+        store
+            .get(key)
+            .and_then(|v| v.as_u64())
+            .map(|v| v.clamp(u64::from(*range.start()), u64::from(*range.end())) as u32)
+            .unwrap_or(default)
+    };
+
+    database::WarmupSettings {
+        games: get(WARMUP_GAMES_KEY, &settings::WARMUP_GAMES, 0),
+        session_gap_minutes: get(SESSION_GAP_MINUTES_KEY, &settings::SESSION_GAP_MINUTES, 60),
+    }
+}
+
+/// Whether games against CPUs are recorded and counted in stats (on by default)
+pub fn record_cpu_matches(app: &AppHandle) -> bool {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(RECORD_CPU_MATCHES_KEY))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true)
+}
+
+/// Whether the library is built from the Slippi folder's replays alone, with
+/// nothing recorded (off by default)
+pub fn replay_only_mode(app: &AppHandle) -> bool {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(REPLAY_ONLY_MODE_KEY))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// Background jobs that keep running while recording, one at a time with
+/// FFmpeg on a single thread (none by default: they wait for it to stop)
+pub fn jobs_while_recording(app: &AppHandle) -> Vec<JobKind> {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(JOBS_WHILE_RECORDING_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Apply the saved background job settings to the work gate (call after changing them)
+#[tauri::command]
+#[metrics::timed]
+pub fn apply_work_gate_settings(app: AppHandle, state: State<'_, AppState>) {
+    state.work_gate.set_overrides(jobs_while_recording(&app));
+}
+
+/// Whether FFmpeg decodes with the GPU's decoder when there is one (on by default)
+pub fn hardware_decode(app: &AppHandle) -> bool {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(HARDWARE_DECODE_KEY))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true)
+}
+
+/// Apply the saved hardware decoding setting (call after changing it)
+#[tauri::command]
+#[metrics::timed]
+pub fn apply_hardware_decode_setting(app: AppHandle) {
+    clip_processor::set_hardware_decode(hardware_decode(&app));
+}
+
+/// Apply the saved stats exclusion settings to every stored game (call after changing them)
+/// Returns how many games are left out of stats
+#[tauri::command]
+#[metrics::timed]
+pub fn apply_stats_exclusion_settings(app: AppHandle, state: State<'_, AppState>) -> Result<usize, Error> {
+    let excluded = reexclude_games(&app, &state)?;
+    log::info!("📊 {} game(s) left out of stats", excluded);
+    Ok(excluded)
+}
+
+/// Register the app to launch on system startup
+#[tauri::command]
+#[metrics::timed]
+pub fn enable_autostart(app: AppHandle) -> Result<(), Error> {
+    app.autolaunch()
+        .enable()
+        .map_err(|e| Error::InitializationError(format!("Failed to enable launch on startup: {}", e)))?;
+    save_store_value(&app, LAUNCH_ON_STARTUP_KEY, serde_json::Value::Bool(true))
+}
+
+/// Remove the app from system startup
+#[tauri::command]
+#[metrics::timed]
+pub fn disable_autostart(app: AppHandle) -> Result<(), Error> {
+    app.autolaunch()
+        .disable()
+        .map_err(|e| Error::InitializationError(format!("Failed to disable launch on startup: {}", e)))?;
+    save_store_value(&app, LAUNCH_ON_STARTUP_KEY, serde_json::Value::Bool(false))
+}
+
+/// Whether the OS will actually launch the app on startup (the entry can be
+/// removed outside the app, e.g. from Task Manager)
+#[tauri::command]
+#[metrics::timed]
+pub fn get_autostart_status(app: AppHandle) -> Result<bool, Error> {
+    app.autolaunch()
+        .is_enabled()
+        .map_err(|e| Error::InitializationError(format!("Failed to read launch on startup status: {}", e)))
+}
+
+/// Whether this launch should start minimized: launched by the autostart entry
+/// with the startMinimized setting on
+pub fn should_start_minimized(app: &AppHandle) -> bool {
+    std::env::args().any(|arg| arg == MINIMIZED_ARG)
+        && app
+            .store("settings.json")
+            .ok()
+            .and_then(|store| store.get(START_MINIMIZED_KEY))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+}
+
+/// Whether the watcher and play session of the last run are restored on launch (on by default)
+pub fn restore_state_on_launch(app: &AppHandle) -> bool {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(RESTORE_STATE_ON_LAUNCH_KEY))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true)
+}
+
+/// Recompute which player is me in every stored game. Returns the number of games attributed to me
+pub fn reattribute_games(app: &AppHandle, state: &AppState) -> Result<usize, Error> {
+    let identity = my_identity(app);
+    let conn = state.database.connection();
+    let attributed = database::recompute_is_me(&conn, &identity)
+        .map_err(|e| Error::database("Failed to update player stats", e))?;
+    state.stats_cache.invalidate();
+    Ok(attributed)
+}
+
+/// Work out which stored games are left out of stats, and which are warm-ups.
+/// Returns how many are left out
+pub fn reexclude_games(app: &AppHandle, state: &AppState) -> Result<usize, Error> {
+    let conn = state.database.connection();
+    let excluded = database::update_excluded_reasons(&conn, &stats_exclusion(app), None)
+        .map_err(|e| Error::database("Failed to update game stats", e))?;
+    database::recompute_warmups(&conn, &warmup_settings(app))
+        .map_err(|e| Error::database("Failed to flag warm-up games", e))?;
+    state.stats_cache.invalidate();
+    Ok(excluded)
+}
+
+/// Set a single value in the settings store and persist it
+fn save_store_value(app: &AppHandle, key: &str, value: serde_json::Value) -> Result<(), Error> {
+    let store = app
+        .store("settings.json")
+        .map_err(|e| Error::InitializationError(format!("Failed to open settings store: {}", e)))?;
+    store.set(key, value);
+    store.save().map_err(save_error)
+}
+
+/// Set several values and persist them together; on failure the store is
+/// reloaded from disk so none of them stick
+fn save_store_values(app: &AppHandle, values: serde_json::Map<String, serde_json::Value>) -> Result<(), Error> {
+    let store = app
+        .store("settings.json")
+        .map_err(|e| Error::InitializationError(format!("Failed to open settings store: {}", e)))?;
+    for (key, value) in values {
+        store.set(key, value);
+    }
+    store.save().map_err(|e| {
+        if let Err(reload) = store.reload() {
+            log::warn!("Failed to roll back settings: {}", reload);
+        }
+        save_error(e)
+    })
+}
+
+/// Writing the store is I/O; keep the underlying error's kind when there is one
+pub(crate) fn save_error(e: tauri_plugin_store::Error) -> Error {
+    match e {
+        tauri_plugin_store::Error::Io(e) => Error::Io(e),
+        other => Error::Io(std::io::Error::other(other.to_string())),
+    }
+}
@@ -2,20 +2,56 @@
 //!
 //! Commands for watching .slp files, parsing replays, and Slippi-related functionality.
 
-use crate::app_state::AppState;
+use crate::app_state::{AppState, RecordingSet};
 use crate::commands::errors::Error;
-use crate::commands::recording::{configure_target_window, resolve_recording_quality, start_recording_with_quality};
-use crate::events::{game as game_events, recording as recording_events};
-use crate::game_detector::{slippi_paths, GameDetector};
+use crate::commands::recording::{
+    configure_target_window, resolve_recording_quality, start_recording_with_quality,
+    spawn_post_recording_hook, stop_and_save_capture_stats,
+};
+use crate::events::recording as recording_events;
+use crate::game_detector::{slippi_paths, GameDetector, WatcherStatus};
+use crate::game_session;
+use crate::inputs;
+use crate::health::{HealthStatus, Subsystem};
+use crate::database::{self, InputQualityCounts, RecordingGame};
 use crate::library;
-use std::path::PathBuf;
-use tauri::{Emitter, Listener, Manager, State};
+use crate::metrics;
+use crate::play_session;
+use crate::playback_review;
+use crate::recording_rules;
+use crate::runtime_state;
+use crate::warm_start;
+use crate::settings::RecordingGranularity;
+use crate::slippi::game_start;
+use crate::slippi::integrity::{self, ParseErrorKind};
+use crate::slippi::timeline::{self, TimelineSample};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager, State};
+use tauri_plugin_store::StoreExt;
+
+/// Settings key for the playback Dolphin executable (auto-detected when unset)
+const PLAYBACK_DOLPHIN_PATH_KEY: &str = "playbackDolphinPath";
+/// Settings key for the Melee ISO (falls back to the one set in Slippi Launcher)
+const MELEE_ISO_PATH_KEY: &str = "meleeIsoPath";
+/// Settings key for recording whole sets
+const RECORDING_GRANULARITY_KEY: &str = "recordingGranularity";
+/// Game Start is written just after the replay is created
+const GAME_START_READ_ATTEMPTS: u32 = 10;
+const GAME_START_READ_INTERVAL: Duration = Duration::from_millis(200);
+/// Largest file analyze_replay reads (an 8 minute game is under 10 MB)
+const MAX_ANALYZED_REPLAY_BYTES: u64 = 32 * 1024 * 1024;
+/// How long analyze_replay may spend reading a replay
+const ANALYZE_REPLAY_TIMEOUT: Duration = Duration::from_secs(20);
 
 /// Get the default Slippi replay folder path for the current OS
 #[tauri::command]
+#[metrics::timed]
 pub fn get_default_slippi_path() -> Result<String, Error> {
     let path = slippi_paths::get_default_slippi_path();
-    
+
     path.to_str()
         .map(|s| s.to_string())
         .ok_or_else(|| Error::InvalidPath("Failed to convert path to string".to_string()))
@@ -23,6 +59,7 @@ pub fn get_default_slippi_path() -> Result<String, Error> {
 
 /// Get the last detected replay file path
 #[tauri::command]
+#[metrics::timed]
 pub fn get_last_replay_path(state: State<'_, AppState>) -> Option<String> {
     state
         .last_replay_path
@@ -31,174 +68,122 @@ pub fn get_last_replay_path(state: State<'_, AppState>) -> Option<String> {
         .and_then(|path| path.clone())
 }
 
+/// How the Slippi folder is being watched (native events or polling), for support
+#[tauri::command]
+#[metrics::timed]
+pub fn get_watcher_status(state: State<'_, AppState>) -> WatcherStatus {
+    state
+        .game_detector
+        .lock()
+        .ok()
+        .and_then(|detector| detector.as_ref().map(GameDetector::status))
+        .unwrap_or_default()
+}
+
 /// Start watching for new Slippi games
 #[tauri::command]
-pub async fn start_watching(
-    path: String,
-    app: tauri::AppHandle,
-    state: State<'_, AppState>,
-) -> Result<(), Error> {
-    let slippi_path = PathBuf::from(&path);
-    
+#[metrics::timed]
+pub async fn start_watching(path: String, app: tauri::AppHandle) -> Result<(), Error> {
+    start_watching_in(&app, PathBuf::from(path))
+}
+
+/// Start watching a folder, replacing any running watcher (also used to
+/// restore watching on launch)
+pub(crate) fn start_watching_in(app: &tauri::AppHandle, slippi_path: PathBuf) -> Result<(), Error> {
     // Check if path exists
     if !slippi_path.exists() {
-        log::error!("Path does not exist: {}", path);
-        return Err(Error::InvalidPath(format!("Slippi folder does not exist: {}", path)));
+        log::error!("Path does not exist: {:?}", slippi_path);
+        return Err(Error::InvalidPath(format!("Slippi folder does not exist: {}", slippi_path.display())));
     }
-    
+
+    let state = app.state::<AppState>();
+    // A stray process still watching would double every game event
+    state.instance_lock.ensure_held(app)?;
+
     // Create new GameDetector with app handle
-    let mut detector = GameDetector::new(slippi_path);
+    let mut detector = GameDetector::new(slippi_path.clone());
     detector.set_app_handle(app.clone());
     detector.start_watching()?;
-    
+
     // Store in app state
     let mut game_detector = state
         .game_detector
         .lock()
         .map_err(|e| Error::InitializationError(format!("Failed to lock game detector: {}", e)))?;
-    *game_detector = Some(detector);
-    
-    // Set up event listener for game start (auto-recording)
-    let app_clone = app.clone();
-    log::info!("Setting up event listener for '{}' events", game_events::FILE_CREATED);
-    
-    let app_clone2 = app.clone();
-    app.listen(game_events::FILE_CREATED, move |event| {
-        let slp_path: &str = event.payload();
-        log::info!("========================================");
-        log::info!("Received {} event!", game_events::FILE_CREATED);
-        log::info!("Payload: {}", slp_path);
-        log::info!("========================================");
-        
-        let app_handle = app_clone.clone();
-        let state_ref = app_handle.state::<AppState>();
-        
-        // Store the last replay path
-        if let Ok(mut last_replay) = state_ref.last_replay_path.lock() {
-            *last_replay = Some(slp_path.to_string());
-            log::info!("Last replay path stored: {}", slp_path);
-            
-            // Emit event to frontend
-            if let Err(e) = app_handle.emit(game_events::LAST_REPLAY_UPDATED, slp_path) {
-                log::error!("Failed to emit {} event: {:?}", game_events::LAST_REPLAY_UPDATED, e);
-            }
-        }
-        
-        // Check if auto-start recording is enabled
-        if let Ok(settings) = state_ref.settings.lock() {
-            let auto_start = settings
-                .get("autoStartRecording")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(true);
-            
-            if !auto_start {
-                log::info!("Auto-start recording is disabled");
-                return;
-            }
-        }
-        
-        // Check if already recording
-        if let Ok(recorder_lock) = state_ref.recorder.lock() {
-            if recorder_lock.is_some() {
-                log::info!("Already recording, skipping");
-                return;
-            }
-        }
-        
-        // Track the file for game end detection
-        let slp_path_clean = slp_path.trim_matches('"');
-        if let Ok(mut current_file) = state_ref.current_recording_file.lock() {
-            *current_file = Some(slp_path_clean.to_string());
-            log::info!("Tracking recording file for game end detection: {}", slp_path_clean);
-        }
-        
-        let slp_path_for_recording = slp_path_clean.to_string();
-        tauri::async_runtime::spawn(async move {
-            if let Err(e) = trigger_auto_recording(app_handle, slp_path_for_recording).await {
-                log::error!("Failed to trigger auto-recording: {:?}", e);
-            }
-        });
-    });
-    
-    // Set up event listener for game end (stop recording)
-    log::info!("Setting up event listener for '{}' events", game_events::FILE_MODIFIED);
-    let app_clone2_inner = app_clone2.clone();
-    app_clone2.listen(game_events::FILE_MODIFIED, move |event| {
-        let modified_path = event.payload();
-        log::info!("File modified - game likely ended: {}", modified_path);
-        
-        let state_ref = app_clone2_inner.state::<AppState>();
-        
-        // Check if this is the file we're currently recording
-        // Extract the info we need while holding the lock, then release it
-        let should_stop = {
-            let current_file = match state_ref.current_recording_file.lock() {
-                Ok(f) => f,
-                Err(_) => return,
-            };
-            
-            if let Some(recording_file) = current_file.as_ref() {
-                let modified_path_clean = modified_path.trim_matches('"');
-                
-                // Compare by base filename
-                let stored_base = std::path::Path::new(recording_file)
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("");
-                let modified_base = std::path::Path::new(modified_path_clean)
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("");
-                
-                log::info!("Comparing base filenames: stored='{}' modified='{}'", stored_base, modified_base);
-                
-                stored_base == modified_base && !stored_base.is_empty()
-            } else {
-                false
-            }
-        };
-        
-        if should_stop {
-            log::info!("Detected modification of recording file - game ended!");
-            
-            // Clear current_recording_file immediately to prevent duplicate stop attempts
-            // (file watcher can emit multiple events for a single file change)
-            if let Ok(mut current_file) = state_ref.current_recording_file.lock() {
-                *current_file = None;
-            }
-            
-            // Wait for file write to complete, then stop recording
-            let app_handle = app_clone2_inner.clone();
-            tauri::async_runtime::spawn(async move {
-                log::info!("Waiting 3 seconds for file write to complete...");
-                tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
-                
-                log::info!("Stopping recording after game end...");
-                if let Err(e) = stop_recording_internal(&app_handle).await {
-                    // This is expected if already stopped, only log at debug level
-                    log::debug!("Stop recording result: {:?}", e);
-                }
-            });
-        }
-    });
-    
+    if let Some(mut previous) = game_detector.replace(detector) {
+        previous.stop_watching();
+    }
+    drop(game_detector);
+    state.health.set_status(Subsystem::Watcher, HealthStatus::Running);
+
+    // Auto-recording follows the watcher's events (replacing the listeners
+    // of an earlier start, so games aren't handled twice)
+    game_session::attach(app);
+    play_session::start(&state);
+    warm_start::prewarm(app);
+    runtime_state::watching_started(app, &slippi_path);
+
     log::info!("Now watching for .slp files");
     Ok(())
 }
 
 /// Stop watching for new games
 #[tauri::command]
-pub async fn stop_watching(state: State<'_, AppState>) -> Result<(), Error> {
+#[metrics::timed]
+pub async fn stop_watching(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<(), Error> {
+    game_session::detach(&app);
+    let mut game_detector = state
+        .game_detector
+        .lock()
+        .map_err(|e| Error::InitializationError(format!("Failed to lock game detector: {}", e)))?;
+
+    if let Some(detector) = game_detector.as_mut() {
+        detector.stop_watching();
+    }
+
+    *game_detector = None;
+    state.health.set_status(Subsystem::Watcher, HealthStatus::Idle);
+    play_session::end(&state);
+    warm_start::release(&app);
+    runtime_state::watching_stopped(&app);
+    Ok(())
+}
+
+/// Replace the running watcher with a fresh one on the same folder
+/// (used by the supervisor after the watcher panicked; event listeners stay in place)
+pub(crate) fn restart_watching(app: &tauri::AppHandle) -> Result<(), Error> {
+    restart_watching_in(app, None)
+}
+
+/// Replace the running watcher with one on another folder (or the same one
+/// when `folder` is None). Does nothing if nothing is being watched
+pub(crate) fn restart_watching_in(app: &tauri::AppHandle, folder: Option<PathBuf>) -> Result<(), Error> {
+    let state = app.state::<AppState>();
     let mut game_detector = state
         .game_detector
         .lock()
         .map_err(|e| Error::InitializationError(format!("Failed to lock game detector: {}", e)))?;
     
+    // Stopped by the user in the meantime
+    let Some(current_path) = game_detector.as_ref().map(|d| d.slippi_path().clone()) else {
+        state.health.set_status(Subsystem::Watcher, HealthStatus::Idle);
+        return Ok(());
+    };
+    if let Some(folder) = &folder {
+        runtime_state::watching_started(app, folder);
+    }
+    let slippi_path = folder.unwrap_or(current_path);
     if let Some(detector) = game_detector.as_mut() {
         detector.stop_watching();
     }
     
-    *game_detector = None;
+    let mut detector = GameDetector::new(slippi_path);
+    detector.set_app_handle(app.clone());
+    detector.start_watching()?;
+    *game_detector = Some(detector);
+    
+    state.health.set_status(Subsystem::Watcher, HealthStatus::Running);
+    log::info!("✅ File watcher restarted");
     Ok(())
 }
 
@@ -206,7 +191,73 @@ pub async fn stop_watching(state: State<'_, AppState>) -> Result<(), Error> {
 // INTERNAL HELPERS
 // ============================================================================
 
-async fn stop_recording_internal(app: &tauri::AppHandle) -> Result<(), Error> {
+/// Whether recordings cover a game or a whole set (read when one starts)
+fn recording_granularity(app: &tauri::AppHandle) -> RecordingGranularity {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(RECORDING_GRANULARITY_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or(RecordingGranularity::PerGame)
+}
+
+/// Add a new game to the set being recorded, unless its match ID says it's
+/// another set. Returns whether it was added
+pub(crate) async fn join_recording_set(app: &tauri::AppHandle, slp_path: &str) -> bool {
+    let state = app.state::<AppState>();
+    let Some((first_game, set_match_id)) = state
+        .recording_set
+        .lock()
+        .ok()
+        .and_then(|set| set.as_ref().map(|set| (set.games.first().map(|g| g.slp_path.clone()), set.match_id.clone())))
+    else {
+        return false;
+    };
+
+    // The first game's replay is complete by now; the new one may still be empty
+    let set_match_id = set_match_id.or_else(|| {
+        first_game.and_then(|slp| game_start::read_game_start(Path::new(&slp))).and_then(|g| g.match_id)
+    });
+    let mut game_start = None;
+    for _ in 0..GAME_START_READ_ATTEMPTS {
+        game_start = game_start::read_game_start(Path::new(slp_path));
+        if game_start.is_some() {
+            break;
+        }
+        tokio::time::sleep(GAME_START_READ_INTERVAL).await;
+    }
+    let match_id = game_start.and_then(|g| g.match_id);
+
+    // Local games and older replays have no match ID; they count as the same set
+    if let (Some(set_match_id), Some(match_id)) = (&set_match_id, &match_id) {
+        if set_match_id != match_id {
+            log::info!("New match ({}) - the set is over", match_id);
+            return false;
+        }
+    }
+
+    let Ok(mut set) = state.recording_set.lock() else { return false };
+    let Some(set) = set.as_mut() else { return false };
+    set.match_id = set_match_id.or(match_id);
+    let game = RecordingGame {
+        game_index: set.games.len() as i64,
+        slp_path: slp_path.to_string(),
+        offset_secs: set.started_at.elapsed().as_secs_f64(),
+    };
+    log::info!("🎬 Game {} of the set starts at {:.1}s: {}", game.game_index + 1, game.offset_secs, slp_path);
+    set.games.push(game);
+
+    if let Ok(mut current_file) = state.current_recording_file.lock() {
+        *current_file = Some(slp_path.to_string());
+    }
+    true
+}
+
+/// A path's file name without extension (a replay and its video share it)
+fn file_stem(path: &str) -> &str {
+    Path::new(path).file_stem().and_then(|s| s.to_str()).unwrap_or(path)
+}
+
+pub(crate) async fn stop_recording_internal(app: &tauri::AppHandle) -> Result<(), Error> {
     let state = app.state::<AppState>();
     
     let mut recorder_lock = state
@@ -215,11 +266,15 @@ async fn stop_recording_internal(app: &tauri::AppHandle) -> Result<(), Error> {
         .map_err(|e| Error::RecordingFailed(format!("Failed to lock recorder: {}", e)))?;
     
     if let Some(recorder) = recorder_lock.as_mut() {
-        let output_path = recorder.stop_recording()?;
+        let result = stop_and_save_capture_stats(app, &state, recorder.as_mut())?;
+        let output_path = result.output_path.clone();
         log::info!("Auto-stopped recording: {}", output_path);
         
         *recorder_lock = None;
         drop(recorder_lock);
+        state.recording_claim.clear();
+        state.work_gate.set_recording(false);
+        spawn_post_recording_hook(app, &result);
         
         // Log clip markers
         let associated_recording = {
@@ -248,10 +303,6 @@ async fn stop_recording_internal(app: &tauri::AppHandle) -> Result<(), Error> {
             }
         }
         
-        if let Ok(mut last_mod) = state.last_file_modification.lock() {
-            *last_mod = None;
-        }
-        
         // Emit event to frontend
         log::info!("[SlippiStats] Emitting recording-stopped event with path: {}", output_path);
         if let Err(e) = app.emit(recording_events::STOPPED, &output_path) {
@@ -266,16 +317,52 @@ async fn stop_recording_internal(app: &tauri::AppHandle) -> Result<(), Error> {
     }
 }
 
-async fn trigger_auto_recording(app: tauri::AppHandle, slp_path: String) -> Result<(), Error> {
+pub(crate) async fn trigger_auto_recording(app: tauri::AppHandle, slp_path: String) -> Result<(), Error> {
     log::info!("Triggering auto-recording for: {}", slp_path);
     
     let state = app.state::<AppState>();
+    // Another game's trigger got here first
+    let Some(session) = state.recording_claim.claim() else {
+        return Err(Error::RecordingFailed("Already recording".to_string()));
+    };
+    let output_path = match start_auto_recording(&app, &state, &slp_path).await {
+        Ok(output_path) => output_path,
+        Err(e) => {
+            state.recording_claim.release(session);
+            return Err(e);
+        }
+    };
+
+    if recording_granularity(&app) == RecordingGranularity::PerSet {
+        if let Ok(mut set) = state.recording_set.lock() {
+            *set = Some(RecordingSet {
+                started_at: Instant::now(),
+                match_id: None,
+                games: vec![RecordingGame { game_index: 0, slp_path: slp_path.clone(), offset_secs: 0.0 }],
+            });
+        }
+    }
+    
+    // Track the video output path
+    if let Ok(mut current_file) = state.current_recording_file.lock() {
+        *current_file = Some(output_path.clone());
+    }
     
+    // Emit event to frontend
+    if let Err(e) = app.emit(recording_events::STARTED, output_path.clone()) {
+        log::error!("Failed to emit {} event: {:?}", recording_events::STARTED, e);
+    }
+    
+    Ok(())
+}
+
+/// Start recording a game, named after its replay. Returns the video's path
+async fn start_auto_recording(app: &tauri::AppHandle, state: &State<'_, AppState>, slp_path: &str) -> Result<String, Error> {
     // Get recording directory
-    let recording_dir = library::get_recording_directory(&app).await?;
+    let recording_dir = library::get_recording_directory(app).await?;
     
     // Generate output path matching the .slp filename
-    let slp_filename = std::path::Path::new(&slp_path)
+    let slp_filename = std::path::Path::new(slp_path)
         .file_stem()
         .and_then(|s| s.to_str())
         .unwrap_or("recording");
@@ -283,8 +370,11 @@ async fn trigger_auto_recording(app: tauri::AppHandle, slp_path: String) -> Resu
     let output_path = format!("{}/{}.mp4", recording_dir, slp_filename);
     log::info!("Output path: {}", output_path);
     
-    // Get recording quality
-    let quality = resolve_recording_quality(&state)?;
+    // Get recording quality (a recording rule may have picked one for this game)
+    let quality = match recording_rules::take_quality(state, slp_path) {
+        Some(quality) => quality,
+        None => resolve_recording_quality(state)?,
+    };
     let resolution_info = quality
         .target_resolution()
         .map(|(w, h)| format!("{}x{}", w, h))
@@ -296,19 +386,263 @@ async fn trigger_auto_recording(app: tauri::AppHandle, slp_path: String) -> Resu
         quality.bitrate() / 1_000_000
     );
     
-    configure_target_window(&state);
-    start_recording_with_quality(&state, &output_path, quality)?;
-    
-    // Track the video output path
+    configure_target_window(state);
+    start_recording_with_quality(app, state, &output_path, quality)?;
+    Ok(output_path)
+}
+
+/// Keep the pre-roll going as the recording of a game that starts `offset`
+/// seconds into it
+pub(crate) fn continue_pre_roll(app: &tauri::AppHandle, slp_path: String, started_at: Instant, offset: f64) {
+    let state = app.state::<AppState>();
+    let Ok(recording_dir) = state.pre_roll.lock().map(|pre_roll| {
+        pre_roll.as_ref().and_then(|p| Path::new(&p.output_path).parent().map(Path::to_path_buf))
+    }) else {
+        return;
+    };
+    // The name it gets once it stops
+    let output_path = recording_dir
+        .unwrap_or_default()
+        .join(format!("{}.mp4", file_stem(&slp_path)))
+        .to_string_lossy()
+        .to_string();
+
+    // The pre-roll's recording becomes this game's
+    if state.recording_claim.claim().is_none() {
+        log::warn!("Another game already holds the recording, not tracking {}", slp_path);
+        return;
+    }
+
+    if recording_granularity(app) == RecordingGranularity::PerSet {
+        if let Ok(mut set) = state.recording_set.lock() {
+            *set = Some(RecordingSet {
+                started_at,
+                match_id: None,
+                games: vec![RecordingGame { game_index: 0, slp_path, offset_secs: offset }],
+            });
+        }
+    }
     if let Ok(mut current_file) = state.current_recording_file.lock() {
         *current_file = Some(output_path.clone());
     }
-    
-    // Emit event to frontend
-    if let Err(e) = app.emit(recording_events::STARTED, output_path.clone()) {
+    if let Err(e) = app.emit(recording_events::STARTED, output_path) {
         log::error!("Failed to emit {} event: {:?}", recording_events::STARTED, e);
     }
-    
+}
+
+/// Playback instructions read by Slippi's playback Dolphin (`-i <file>`)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PlaybackConfig {
+    mode: &'static str,
+    replay: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_frame: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_frame: Option<i32>,
+    is_real_time_mode: bool,
+    command_id: String,
+}
+
+/// Open a recording's replay in Slippi's playback Dolphin
+/// Frames use Slippi numbering (the game starts on frame -123). `game_index`
+/// picks the game of a per-set recording (the first by default)
+#[tauri::command]
+#[metrics::timed]
+pub async fn launch_replay(
+    app: tauri::AppHandle,
+    recording_id: String,
+    game_index: Option<i64>,
+    start_frame: Option<i32>,
+    end_frame: Option<i32>,
+    state: State<'_, AppState>,
+) -> Result<(), Error> {
+    let mut launch = playback_launch(&app, &state, &recording_id, game_index, start_frame, end_frame)?;
+    launch
+        .command
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .map_err(|e| Error::InvalidPath(format!("Failed to launch {}: {}", launch.dolphin_path.display(), e)))?;
+
+    log::info!(
+        "🐬 Opened {} in playback Dolphin (start frame {:?})",
+        launch.game.slp_path,
+        start_frame
+    );
+    Ok(())
+}
+
+/// Open a recording's replay in playback Dolphin and follow it: the frames
+/// Dolphin shows are emitted as playback-frame events with where they are in
+/// the video, until Dolphin closes or stop_playback_review is called
+#[tauri::command]
+#[metrics::timed]
+pub async fn start_playback_review(
+    app: tauri::AppHandle,
+    recording_id: String,
+    game_index: Option<i64>,
+    start_frame: Option<i32>,
+    state: State<'_, AppState>,
+) -> Result<(), Error> {
+    let mut launch = playback_launch(&app, &state, &recording_id, game_index, start_frame, None)?;
+    let child = launch
+        .command
+        .arg("--cout")
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::InvalidPath(format!("Failed to launch {}: {}", launch.dolphin_path.display(), e)))?;
+
+    let video_start_secs = launch.game.offset_secs - launch.video_offset_ms.unwrap_or(0) as f64 / 1000.0;
+    playback_review::start(&app, child, recording_id, video_start_secs)?;
+    log::info!("🐬 Reviewing {} in playback Dolphin", launch.game.slp_path);
     Ok(())
 }
 
+/// Stop the playback review, closing its Dolphin
+#[tauri::command]
+#[metrics::timed]
+pub async fn stop_playback_review(state: State<'_, AppState>) -> Result<(), Error> {
+    playback_review::stop(&state);
+    Ok(())
+}
+
+/// Playback Dolphin set up to open a recording's replay (stdout left to the caller)
+struct PlaybackLaunch {
+    command: std::process::Command,
+    dolphin_path: PathBuf,
+    /// The game being played
+    game: RecordingGame,
+    /// How late the video starts compared with the replay (see RecordingRow)
+    video_offset_ms: Option<i64>,
+}
+
+fn playback_launch(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    recording_id: &str,
+    game_index: Option<i64>,
+    start_frame: Option<i32>,
+    end_frame: Option<i32>,
+) -> Result<PlaybackLaunch, Error> {
+    // The capture could pick up the playback window instead of the game
+    {
+        let recorder = state
+            .recorder
+            .lock()
+            .map_err(|e| Error::RecordingFailed(format!("Failed to lock recorder: {}", e)))?;
+        if recorder.as_ref().is_some_and(|r| r.is_recording()) {
+            return Err(Error::RecordingFailed(
+                "Can't open a replay in Dolphin while recording. Stop the recording first".to_string(),
+            ));
+        }
+    }
+
+    let (game, video_offset_ms) = {
+        let conn = state.database.connection();
+        let Some(recording) = database::get_recording_by_id(&conn, recording_id).map_err(Error::from)? else {
+            return Err(Error::InvalidPath(format!("Recording not found: {}", recording_id)));
+        };
+        let game = database::get_recording_games(&conn, recording_id)
+            .map_err(Error::from)?
+            .into_iter()
+            .find(|game| game.game_index == game_index.unwrap_or(0))
+            .filter(|game| Path::new(&game.slp_path).exists())
+            .ok_or_else(|| Error::InvalidPath("This recording has no replay file".to_string()))?;
+        (game, recording.video_offset_ms)
+    };
+    let slp_path = PathBuf::from(&game.slp_path);
+    // Dolphin plays a cut-off replay up to where it stops, but gives no reason for any other failure
+    if let Err(problem) = integrity::check_replay(&slp_path) {
+        if problem.kind != ParseErrorKind::Truncated {
+            return Err(problem.into());
+        }
+    }
+
+    let store = app.store("settings.json").ok();
+    let get_path = |key: &str| {
+        store
+            .as_ref()
+            .and_then(|s| s.get(key))
+            .and_then(|v| v.as_str().map(|p| p.trim().to_string()))
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+    };
+
+    let dolphin_path = get_path(PLAYBACK_DOLPHIN_PATH_KEY).unwrap_or_else(slippi_paths::get_default_playback_path);
+    if !dolphin_path.exists() {
+        return Err(Error::InvalidPath(format!(
+            "Slippi playback Dolphin not found at {}. Open Slippi Launcher once to install it, or set its location in Settings",
+            dolphin_path.display()
+        )));
+    }
+
+    let iso_path = get_path(MELEE_ISO_PATH_KEY)
+        .or_else(slippi_paths::get_launcher_iso_path)
+        .filter(|p| p.exists())
+        .ok_or_else(|| {
+            Error::InvalidPath("Melee ISO not found. Set it in Slippi Launcher or in Settings".to_string())
+        })?;
+
+    let command_id = uuid::Uuid::new_v4().to_string();
+    let config = PlaybackConfig {
+        mode: "normal",
+        replay: game.slp_path.clone(),
+        start_frame,
+        end_frame,
+        is_real_time_mode: false,
+        command_id: command_id.clone(),
+    };
+    let config_path = std::env::temp_dir().join(format!("buckwheat-playback-{}.json", command_id));
+    std::fs::write(&config_path, serde_json::to_vec(&config).unwrap_or_default())?;
+
+    let mut command = std::process::Command::new(&dolphin_path);
+    command
+        .arg("-i")
+        .arg(&config_path)
+        .arg("-e")
+        .arg(&iso_path)
+        .stdin(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+    Ok(PlaybackLaunch { command, dolphin_path, game, video_offset_ms })
+}
+
+/// What the backend reads from a replay analyzed outside the library
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayAnalysis {
+    pub game_start: game_start::GameStart,
+    pub timeline: Vec<TimelineSample>,
+    /// Input quality counts per port
+    pub inputs: BTreeMap<u8, InputQualityCounts>,
+}
+
+/// Check and read a .slp that isn't in the library (e.g. one a friend sent),
+/// without writing anything. The frontend computes the slippi-js stats of
+/// the same file once this succeeds, so the size limit and structure check
+/// also keep it from loading a file that isn't a replay
+#[tauri::command]
+#[metrics::timed]
+pub async fn analyze_replay(slp_path: String) -> Result<ReplayAnalysis, Error> {
+    let path = PathBuf::from(&slp_path);
+    let size = std::fs::metadata(&path)
+        .map_err(|_| Error::InvalidPath(format!("Replay not found: {}", slp_path)))?
+        .len();
+    if size > MAX_ANALYZED_REPLAY_BYTES {
+        return Err(Error::SlpParse {
+            reason: format!("The file is {} MB, too large to be a replay", size / (1024 * 1024)),
+        });
+    }
+
+    let task = tauri::async_runtime::spawn_blocking(move || read_replay_analysis(&path));
+    tokio::time::timeout(ANALYZE_REPLAY_TIMEOUT, task)
+        .await
+        .map_err(|_| Error::SlpParse { reason: "Reading the replay took too long".to_string() })?
+        .map_err(|e| Error::RecordingFailed(format!("Replay analysis task failed: {}", e)))?
+}
+
+fn read_replay_analysis(path: &Path) -> Result<ReplayAnalysis, Error> {
+    integrity::check_replay(path)?;
+    let game_start = game_start::read_game_start(path)
+        .ok_or_else(|| Error::SlpParse { reason: "The replay has no Game Start".to_string() })?;
+    Ok(ReplayAnalysis { game_start, timeline: timeline::read_timeline(path)?, inputs: inputs::analyze_replay(path)? })
+}
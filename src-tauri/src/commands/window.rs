@@ -4,19 +4,30 @@
 
 use crate::app_state::AppState;
 use crate::commands::errors::Error;
-use crate::window_detector::{self, GameWindow};
+use crate::metrics;
+use crate::readiness;
+use crate::window_detector::{self, GameWindow, PreviewError};
 use base64::Engine as _;
 use tauri::State;
+use tokio::sync::Semaphore;
+
+/// Picker previews captured at once
+static PREVIEW_SLOTS: Semaphore = Semaphore::const_new(2);
+
+/// Widest a picker preview is, in pixels
+const PREVIEW_MAX_WIDTH: u32 = 320;
 
 /// List all potential game windows (Slippi/Dolphin)
 #[tauri::command]
+#[metrics::timed]
 pub fn list_game_windows() -> Result<Vec<GameWindow>, Error> {
     Ok(window_detector::find_game_windows())
 }
 
 /// Check if the game window is currently open
 #[tauri::command]
-pub async fn check_game_window(state: State<'_, AppState>) -> Result<bool, Error> {
+#[metrics::timed]
+pub async fn check_game_window(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<bool, Error> {
     let stored_id = {
         let settings = state
             .settings
@@ -28,12 +39,15 @@ pub async fn check_game_window(state: State<'_, AppState>) -> Result<bool, Error
             .map(|s| s.trim().to_string())
             .filter(|s| !s.is_empty())
     };
-    
-    Ok(window_detector::check_game_window_open(stored_id.as_deref()))
+
+    let open = window_detector::check_game_window_open(stored_id.as_deref());
+    readiness::game_window_changed(&app, open);
+    Ok(open)
 }
 
 /// Capture a preview screenshot of the selected game window
 #[tauri::command]
+#[metrics::timed]
 pub async fn capture_window_preview(state: State<'_, AppState>) -> Result<Option<String>, Error> {
     let identifier = {
         let settings = state
@@ -46,11 +60,11 @@ pub async fn capture_window_preview(state: State<'_, AppState>) -> Result<Option
             .map(|s| s.trim().to_string())
             .filter(|s| !s.is_empty())
     };
-    
+
     let Some(target_id) = identifier else {
         return Ok(None);
     };
-    
+
     match window_detector::capture_window_preview(&target_id) {
         Ok(bytes) => {
             let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
@@ -63,14 +77,37 @@ pub async fn capture_window_preview(state: State<'_, AppState>) -> Result<Option
     }
 }
 
+/// Capture a small preview of any window, by the identifier list_game_windows
+/// gives it (title or "title (PID: n)"). Returns base64 PNG
+#[tauri::command]
+#[metrics::timed]
+pub async fn capture_window_preview_for(identifier: String) -> Result<String, Error> {
+    let _slot = PREVIEW_SLOTS
+        .acquire()
+        .await
+        .map_err(|e| Error::WindowCapture(format!("Preview slots closed: {}", e)))?;
+    let target = identifier.clone();
+    let bytes = tauri::async_runtime::spawn_blocking(move || {
+        window_detector::capture_window_thumbnail(&target, PREVIEW_MAX_WIDTH)
+    })
+    .await
+    .map_err(|e| Error::WindowCapture(format!("Preview task failed: {}", e)))?
+    .map_err(|err| match err {
+        PreviewError::Gone => Error::WindowGone { identifier: identifier.trim().to_string() },
+        PreviewError::Failed(message) => Error::WindowCapture(message),
+    })?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
 /// Get the stored game process name
 #[tauri::command]
+#[metrics::timed]
 pub async fn get_game_process_name(state: State<'_, AppState>) -> Result<Option<String>, Error> {
     let settings = state
         .settings
         .lock()
         .map_err(|e| Error::InitializationError(format!("Failed to lock settings: {}", e)))?;
-    
+
     Ok(settings
         .get("game_process_name")
         .and_then(|v| v.as_str())
@@ -79,22 +116,23 @@ pub async fn get_game_process_name(state: State<'_, AppState>) -> Result<Option<
 
 /// Set the game process name for detection and recording
 #[tauri::command]
+#[metrics::timed]
 pub async fn set_game_process_name(
     process_name: String,
     state: State<'_, AppState>,
 ) -> Result<(), Error> {
     log::info!("Setting game process name to: {}", process_name);
-    
+
     let mut settings = state
         .settings
         .lock()
         .map_err(|e| Error::InitializationError(format!("Failed to lock settings: {}", e)))?;
-    
+
     settings.insert(
         "game_process_name".to_string(),
         serde_json::Value::String(process_name),
     );
-    
+
     Ok(())
 }
 
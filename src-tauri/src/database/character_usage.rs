@@ -0,0 +1,150 @@
+//! Which characters a player played, over time
+//!
+//! Games count as the character played for most of them (played_character_id),
+//! so a Sheik player who picks Zelda and transforms counts as Sheik. Games left
+//! out of stats (see update_excluded_reasons) aren't counted.
+
+use super::recordings::{PlayerScope, WON_SQL};
+use crate::timestamps;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+/// How long each period of the usage timeline is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageBucket {
+    Day,
+    /// Monday to Sunday
+    Week,
+    #[default]
+    Month,
+}
+
+impl UsageBucket {
+    /// SQL for the first day (YYYY-MM-DD) of the period a timestamp `column` falls in
+    fn start_sql(self, column: &str) -> String {
+        match self {
+            UsageBucket::Day => format!("date({})", column),
+            // Back six days, then forward to the first Monday
+            UsageBucket::Week => format!("date({}, '-6 days', 'weekday 1')", column),
+            UsageBucket::Month => format!("strftime('%Y-%m-01', {})", column),
+        }
+    }
+}
+
+/// Games as one character
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CharacterUsage {
+    pub character_id: i32,
+    pub games: i64,
+    pub wins: i64,
+    /// None without games with a winner
+    pub win_rate: Option<f64>,
+}
+
+/// One period of the usage timeline
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CharacterUsagePeriod {
+    /// First day of the period (YYYY-MM-DD)
+    pub start: String,
+    pub games: i64,
+    /// Most played first
+    pub characters: Vec<CharacterUsage>,
+}
+
+/// When a character was first and last played, and how it went overall
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CharacterPlayedRange {
+    pub character_id: i32,
+    pub games: i64,
+    pub wins: i64,
+    pub first_played: Option<String>,
+    pub last_played: Option<String>,
+}
+
+/// Games per character in each period a scope played in, oldest first.
+/// Periods are in the client's time zone (`utc_offset_minutes` east of UTC)
+pub fn get_character_usage(
+    conn: &Connection,
+    scope: PlayerScope<'_>,
+    bucket: UsageBucket,
+    utc_offset_minutes: Option<i32>,
+) -> rusqlite::Result<Vec<CharacterUsagePeriod>> {
+    let (scope_clause, scope_param) = scope.condition(1);
+    let start = bucket.start_sql(&timestamps::local_sql("g.created_at", utc_offset_minutes));
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {0}, COALESCE(p.played_character_id, p.character_id), COUNT(*),
+                SUM(CASE WHEN {2} THEN 1 ELSE 0 END),
+                SUM(CASE WHEN g.winner_port IS NOT NULL THEN 1 ELSE 0 END)
+         FROM player_stats p
+         JOIN game_stats g ON g.id = p.recording_id
+         WHERE {1} AND g.excluded_reason IS NULL AND {0} IS NOT NULL
+         GROUP BY 1, 2
+         ORDER BY 1, 3 DESC, 2",
+        start, scope_clause, WON_SQL
+    ))?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(scope_param), |row| {
+            let decided: i64 = row.get(4)?;
+            let wins: i64 = row.get(3)?;
+            Ok((
+                row.get::<_, String>(0)?,
+                CharacterUsage {
+                    character_id: row.get(1)?,
+                    games: row.get(2)?,
+                    wins,
+                    win_rate: (decided > 0).then(|| wins as f64 / decided as f64),
+                },
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut periods: Vec<CharacterUsagePeriod> = Vec::new();
+    for (start, usage) in rows {
+        match periods.last_mut() {
+            Some(period) if period.start == start => {
+                period.games += usage.games;
+                period.characters.push(usage);
+            }
+            _ => periods.push(CharacterUsagePeriod { start, games: usage.games, characters: vec![usage] }),
+        }
+    }
+    Ok(periods)
+}
+
+/// A scope's games as one character. None if they never played it
+pub fn get_first_and_last_played(
+    conn: &Connection,
+    scope: PlayerScope<'_>,
+    character_id: i32,
+) -> rusqlite::Result<Option<CharacterPlayedRange>> {
+    let (scope_clause, scope_param) = scope.condition(2);
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(character_id)];
+    if let Some(code) = scope_param {
+        params.push(Box::new(code));
+    }
+    let range = conn.query_row(
+        &format!(
+            "SELECT COUNT(*), SUM(CASE WHEN {} THEN 1 ELSE 0 END),
+                    MIN(g.created_at), MAX(g.created_at)
+             FROM player_stats p
+             JOIN game_stats g ON g.id = p.recording_id
+             WHERE COALESCE(p.played_character_id, p.character_id) = ?1 AND {} AND g.excluded_reason IS NULL",
+            WON_SQL, scope_clause
+        ),
+        rusqlite::params_from_iter(params.iter().map(|p| p.as_ref())),
+        |row| {
+            Ok(CharacterPlayedRange {
+                character_id,
+                games: row.get(0)?,
+                wins: row.get::<_, Option<i64>>(1)?.unwrap_or(0),
+                first_played: row.get(2)?,
+                last_played: row.get(3)?,
+            })
+        },
+    )?;
+    Ok(Some(range).filter(|range| range.games > 0))
+}
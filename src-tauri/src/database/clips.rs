@@ -0,0 +1,92 @@
+//! Clips: recordings whose video is in the Clips folder
+//!
+//! Clips are rows of the recordings table like any other video; these
+//! queries pick them out by folder for the Clips cap and the storage report,
+//! and keep them (and the highlights clipped into them) up to date when a
+//! clip is starred or renamed.
+
+use crate::paths;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashSet;
+
+/// A clip's video, for the Clips folder cap
+#[derive(Debug, Clone)]
+pub struct ClipFileRow {
+    pub id: String,
+    pub video_path: String,
+    /// Bytes on disk (0 if unknown)
+    pub file_size: u64,
+    pub is_favorite: bool,
+}
+
+/// Clips under `clips_dir` (with a trailing separator), oldest first
+pub fn get_clips_by_age(conn: &Connection, clips_dir: &str) -> rusqlite::Result<Vec<ClipFileRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, video_path, COALESCE(file_size, 0), is_favorite FROM recordings
+         WHERE substr(video_path, 1, length(?1)) = ?1
+         ORDER BY COALESCE(file_modified_at, start_time, cached_at), id",
+    )?;
+    let rows = stmt.query_map(params![clips_dir], |row| {
+        Ok(ClipFileRow {
+            id: row.get(0)?,
+            video_path: row.get(1)?,
+            file_size: row.get::<_, i64>(2)?.max(0) as u64,
+            is_favorite: row.get::<_, i32>(3)? != 0,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Recordings outside `clips_dir` that have a video: (count, bytes)
+pub fn get_recordings_size(conn: &Connection, clips_dir: &str) -> rusqlite::Result<(usize, u64)> {
+    conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(file_size), 0) FROM recordings
+         WHERE video_path IS NOT NULL AND substr(video_path, 1, length(?1)) != ?1",
+        params![clips_dir],
+        |row| Ok((row.get::<_, i64>(0)? as usize, row.get::<_, i64>(1)?.max(0) as u64)),
+    )
+}
+
+/// Star or unstar a clip. Returns false if there's no such recording
+pub fn set_clip_favorite(conn: &Connection, id: &str, favorite: bool) -> rusqlite::Result<bool> {
+    let updated = conn.execute("UPDATE recordings SET is_favorite = ? WHERE id = ?", params![favorite as i32, id])?;
+    Ok(updated > 0)
+}
+
+/// Whether a clip is starred (false if there's no such recording)
+pub fn is_clip_favorite(conn: &Connection, id: &str) -> rusqlite::Result<bool> {
+    let favorite = conn
+        .query_row("SELECT is_favorite FROM recordings WHERE id = ?", params![id], |row| row.get::<_, i32>(0))
+        .optional()?;
+    Ok(favorite.is_some_and(|f| f != 0))
+}
+
+/// Ids of the starred recordings
+pub fn get_favorite_clip_ids(conn: &Connection) -> rusqlite::Result<HashSet<String>> {
+    let mut stmt = conn.prepare("SELECT id FROM recordings WHERE is_favorite != 0")?;
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Point a renamed clip's row and every highlight clipped into it at its new
+/// video and thumbnail. Returns how many highlights were updated
+pub fn rename_clip(
+    conn: &Connection,
+    id: &str,
+    old_video_path: &str,
+    video_path: &str,
+    thumbnail_path: Option<&str>,
+) -> rusqlite::Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE recordings SET video_path = ?2, thumbnail_path = ?3 WHERE id = ?1",
+        params![id, paths::normalize(video_path), thumbnail_path.map(paths::normalize)],
+    )?;
+    // Highlights clipped before their paths were normalized have the raw one
+    let highlights = tx.execute(
+        "UPDATE combos SET clip_path = ?3 WHERE clip_path IN (?1, ?2)",
+        params![old_video_path, paths::normalize(old_video_path), paths::normalize(video_path)],
+    )?;
+    tx.commit()?;
+    Ok(highlights)
+}
@@ -0,0 +1,203 @@
+//! Combos found by the stats pass, scored as highlights
+
+use super::recordings::{PlayerScope, StatsFilter};
+use crate::paths;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Row from the combos table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComboRow {
+    pub recording_id: String,
+    /// Player who performed the combo
+    pub player_index: i32,
+    pub opponent_index: i32,
+    pub start_frame: i32,
+    pub end_frame: i32,
+    /// Opponent's percent when the combo started
+    pub start_percent: f64,
+    pub end_percent: f64,
+    pub move_count: i32,
+    pub distinct_moves: i32,
+    pub did_kill: bool,
+    pub highlight_score: f64,
+}
+
+/// A combo with what's needed to show it and cut it out of the video
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HighlightRow {
+    pub combo_id: i64,
+    pub recording_id: String,
+    pub video_path: String,
+    pub slp_path: Option<String>,
+    pub player_index: i32,
+    pub connect_code: Option<String>,
+    pub character_id: i32,
+    pub opponent_character_id: Option<i32>,
+    pub stage: Option<i32>,
+    /// When the game was played (ISO 8601)
+    pub played_at: Option<String>,
+    pub start_frame: i32,
+    pub end_frame: i32,
+    pub start_percent: f64,
+    pub end_percent: f64,
+    pub move_count: i32,
+    pub did_kill: bool,
+    pub highlight_score: f64,
+    /// Clip already made from this highlight
+    pub clip_path: Option<String>,
+    /// Where the game starts in the video (later games of a per-set recording),
+    /// less the recording's measured or calibrated video offset
+    pub video_offset_secs: f64,
+}
+
+/// Replace a recording's combos (the stats pass recomputes all of them)
+/// Combos that were already clipped keep their clip path
+pub fn replace_combos(conn: &Connection, recording_id: &str, combos: &[ComboRow]) -> rusqlite::Result<()> {
+    let tx = conn.unchecked_transaction()?;
+
+    let clipped: HashMap<(i32, i32), String> = {
+        let mut stmt = tx.prepare(
+            "SELECT player_index, start_frame, clip_path FROM combos
+             WHERE recording_id = ? AND clip_path IS NOT NULL",
+        )?;
+        let rows = stmt.query_map(params![recording_id], |row| Ok(((row.get(0)?, row.get(1)?), row.get(2)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    tx.execute("DELETE FROM combos WHERE recording_id = ?", params![recording_id])?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO combos (
+                recording_id, player_index, opponent_index, start_frame, end_frame,
+                start_percent, end_percent, move_count, distinct_moves, did_kill,
+                highlight_score, clip_path
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        )?;
+        for combo in combos {
+            stmt.execute(params![
+                recording_id,
+                combo.player_index,
+                combo.opponent_index,
+                combo.start_frame,
+                combo.end_frame,
+                combo.start_percent,
+                combo.end_percent,
+                combo.move_count,
+                combo.distinct_moves,
+                combo.did_kill as i32,
+                combo.highlight_score,
+                clipped.get(&(combo.player_index, combo.start_frame)),
+            ])?;
+        }
+    }
+    tx.commit()
+}
+
+/// Best highlights across the library, highest score first
+/// The scope and the filter's character are about the player who performed the combo
+pub fn get_top_highlights(
+    conn: &Connection,
+    scope: PlayerScope<'_>,
+    filter: Option<StatsFilter>,
+    unclipped_only: bool,
+    limit: u32,
+) -> rusqlite::Result<Vec<HighlightRow>> {
+    let filter = filter.unwrap_or_default();
+
+    let (scope_clause, scope_param) = scope.condition(1);
+    // Replay-only entries have no video to clip from
+    let mut where_clauses = vec![scope_clause, "r.video_path IS NOT NULL".to_string()];
+    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    if let Some(code) = scope_param {
+        params_vec.push(Box::new(code));
+    }
+
+    if let Some(stage) = filter.stage_id {
+        params_vec.push(Box::new(stage));
+        where_clauses.push(format!("g.stage = ?{}", params_vec.len()));
+    }
+    let (start_time, end_time) = filter.time_range();
+    if let Some(start) = start_time {
+        params_vec.push(Box::new(start));
+        where_clauses.push(format!("g.created_at >= ?{}", params_vec.len()));
+    }
+    if let Some(end) = end_time {
+        params_vec.push(Box::new(end));
+        where_clauses.push(format!("g.created_at <= ?{}", params_vec.len()));
+    }
+    if let Some(player_char) = filter.player_character_id {
+        params_vec.push(Box::new(player_char));
+        where_clauses.push(format!("p.character_id = ?{}", params_vec.len()));
+    }
+    if let Some(opp_char) = filter.opponent_character_id {
+        params_vec.push(Box::new(opp_char));
+        where_clauses.push(format!("o.character_id = ?{}", params_vec.len()));
+    }
+    if let Some(min_version) = filter.min_stats_version {
+        params_vec.push(Box::new(min_version));
+        where_clauses.push(format!("p.stats_version >= ?{}", params_vec.len()));
+    }
+    if filter.exclude_warmups {
+        where_clauses.push("g.is_warmup = 0".to_string());
+    }
+    if unclipped_only {
+        where_clauses.push("c.clip_path IS NULL".to_string());
+    }
+    params_vec.push(Box::new(limit));
+
+    let query = format!(
+        "SELECT c.id, c.recording_id, r.video_path, r.slp_path, c.player_index, p.connect_code,
+                p.character_id, o.character_id, g.stage, g.created_at, c.start_frame, c.end_frame,
+                c.start_percent, c.end_percent, c.move_count, c.did_kill, c.highlight_score, c.clip_path,
+                COALESCE(rr.offset_secs, 0) - COALESCE(r.video_offset_ms, 0) / 1000.0
+         FROM combos c
+         JOIN recordings r ON r.id = c.recording_id
+         LEFT JOIN recording_replays rr ON rr.recording_id = c.recording_id AND rr.slp_path = r.slp_path
+         JOIN player_stats p ON p.recording_id = c.recording_id AND p.player_index = c.player_index
+         LEFT JOIN player_stats o ON o.recording_id = c.recording_id AND o.player_index = c.opponent_index
+         LEFT JOIN game_stats g ON g.id = c.recording_id
+         WHERE {}
+         ORDER BY c.highlight_score DESC
+         LIMIT ?{}",
+        where_clauses.join(" AND "),
+        params_vec.len()
+    );
+
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+    let mut stmt = conn.prepare(&query)?;
+    let rows = stmt.query_map(params_refs.as_slice(), |row| {
+        Ok(HighlightRow {
+            combo_id: row.get(0)?,
+            recording_id: row.get(1)?,
+            video_path: row.get(2)?,
+            slp_path: row.get(3)?,
+            player_index: row.get(4)?,
+            connect_code: row.get(5)?,
+            character_id: row.get(6)?,
+            opponent_character_id: row.get(7)?,
+            stage: row.get(8)?,
+            played_at: row.get(9)?,
+            start_frame: row.get(10)?,
+            end_frame: row.get(11)?,
+            start_percent: row.get(12)?,
+            end_percent: row.get(13)?,
+            move_count: row.get(14)?,
+            did_kill: row.get::<_, i32>(15)? != 0,
+            highlight_score: row.get(16)?,
+            clip_path: row.get(17)?,
+            video_offset_secs: row.get(18)?,
+        })
+    })?;
+
+    rows.collect()
+}
+
+/// Remember the clip made from a highlight
+pub fn set_combo_clip_path(conn: &Connection, combo_id: i64, clip_path: &str) -> rusqlite::Result<()> {
+    conn.execute("UPDATE combos SET clip_path = ?2 WHERE id = ?1", params![combo_id, paths::normalize(clip_path)])?;
+    Ok(())
+}
@@ -0,0 +1,178 @@
+-- Snapshot of a v14 database (sqlite3 .dump), used by the migration tests in schema.rs
+PRAGMA foreign_keys=OFF;
+BEGIN TRANSACTION;
+CREATE TABLE schema_version (
+            version INTEGER PRIMARY KEY
+        , applied_at TEXT);
+INSERT INTO schema_version VALUES(9,'2024-01-10T12:00:00+00:00');
+INSERT INTO schema_version VALUES(10,'2024-01-20T12:00:00+00:00');
+INSERT INTO schema_version VALUES(11,'2024-02-01T12:00:00+00:00');
+INSERT INTO schema_version VALUES(12,'2024-02-08T12:00:00+00:00');
+INSERT INTO schema_version VALUES(13,'2024-02-15T12:00:00+00:00');
+INSERT INTO schema_version VALUES(14,'2024-02-22T12:00:00+00:00');
+CREATE TABLE recordings (
+            id TEXT PRIMARY KEY,  -- UUID
+            video_path TEXT NOT NULL UNIQUE,
+            slp_path TEXT,
+            
+            -- File metadata
+            file_size INTEGER,
+            file_modified_at TEXT,
+            
+            -- Thumbnail
+            thumbnail_path TEXT,
+            
+            -- Timing
+            start_time TEXT,
+            
+            -- Cache metadata
+            cached_at TEXT NOT NULL,
+            needs_reparse INTEGER DEFAULT 0
+        , dropped_frame_estimate INTEGER, longest_stall_ms INTEGER, profile TEXT, is_archived INTEGER DEFAULT 0);
+INSERT INTO recordings VALUES('0b6d1f8e-6a51-4d7e-9f3c-2f1a3c7e5d01','C:\Users\me\Videos\Buckwheat\Game_20240115T193012.mp4','C:\Users\me\Documents\Slippi\Game_20240115T193012.slp',52428800,'2024-01-15T19:37:40+00:00','C:\Users\me\Videos\Buckwheat\Thumbnails\Game_20240115T193012.jpg','2024-01-15T19:30:12+00:00','2024-01-15T19:40:00+00:00',0,42,250,'Netplay',1);
+INSERT INTO recordings VALUES('5c2e9a41-0d3b-4c8a-b6e2-7a9d4f1e3b02','C:\Users\me\Videos\Buckwheat\Clips\Clip_20240115_193512_001.mp4',NULL,4194304,'2024-01-15T19:41:02+00:00',NULL,'2024-01-15T19:41:02+00:00','2024-01-15T19:41:05+00:00',0,NULL,NULL,NULL,0);
+CREATE TABLE game_stats (
+            id TEXT PRIMARY KEY,  -- UUID (same as recordings.id for recorded games)
+            
+            -- Player identifiers (connect codes)
+            player1_id TEXT,
+            player2_id TEXT,
+            
+            -- Port assignments
+            player1_port INTEGER,
+            player2_port INTEGER,
+            
+            -- Characters
+            player1_character INTEGER,
+            player2_character INTEGER,
+            player1_color INTEGER,
+            player2_color INTEGER,
+            
+            -- Game outcome
+            winner_port INTEGER,
+            loser_port INTEGER,
+            
+            -- Stage
+            stage INTEGER,
+            
+            -- Duration
+            game_duration INTEGER,
+            total_frames INTEGER,
+            
+            -- Game info
+            is_pal INTEGER DEFAULT 0,
+            played_on TEXT,
+            
+            -- Match info
+            match_id TEXT,
+            game_number INTEGER,
+            game_end_method TEXT,
+            
+            -- Timestamps
+            created_at TEXT,  -- ISO 8601 timestamp when game was played
+            
+            -- For deduplication of historical games
+            slp_path TEXT UNIQUE
+        );
+INSERT INTO game_stats VALUES('0b6d1f8e-6a51-4d7e-9f3c-2f1a3c7e5d01','HATS#982','ABCD#123',1,2,2,20,0,1,1,2,31,27440,27440,0,'dolphin',NULL,NULL,NULL,'2024-01-15T19:30:12+00:00','C:\Users\me\Documents\Slippi\Game_20240115T193012.slp');
+CREATE TABLE player_stats (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            recording_id TEXT NOT NULL,
+            player_index INTEGER NOT NULL,
+            
+            -- Player identification
+            connect_code TEXT,
+            display_name TEXT,
+            character_id INTEGER NOT NULL,
+            character_color INTEGER DEFAULT 0,
+            port INTEGER NOT NULL,
+            
+            -- Overall performance
+            total_damage REAL DEFAULT 0,
+            kill_count INTEGER DEFAULT 0,
+            conversion_count INTEGER DEFAULT 0,
+            successful_conversions INTEGER DEFAULT 0,
+            openings_per_kill REAL,
+            damage_per_opening REAL,
+            neutral_win_ratio REAL,
+            counter_hit_ratio REAL,
+            beneficial_trade_ratio REAL,
+            
+            -- Input stats
+            inputs_total INTEGER DEFAULT 0,
+            inputs_per_minute REAL,
+            avg_kill_percent REAL,
+            
+            -- Action counts
+            wavedash_count INTEGER DEFAULT 0,
+            waveland_count INTEGER DEFAULT 0,
+            air_dodge_count INTEGER DEFAULT 0,
+            dash_dance_count INTEGER DEFAULT 0,
+            spot_dodge_count INTEGER DEFAULT 0,
+            ledgegrab_count INTEGER DEFAULT 0,
+            roll_count INTEGER DEFAULT 0,
+            grab_count INTEGER DEFAULT 0,
+            throw_count INTEGER DEFAULT 0,
+            ground_tech_count INTEGER DEFAULT 0,
+            wall_tech_count INTEGER DEFAULT 0,
+            wall_jump_tech_count INTEGER DEFAULT 0,
+            
+            -- L-Cancel stats
+            l_cancel_success_count INTEGER DEFAULT 0,
+            l_cancel_fail_count INTEGER DEFAULT 0,
+            
+            -- Final game state
+            stocks_remaining INTEGER DEFAULT 0,
+            final_percent REAL,
+            
+            -- Whether this player is the local user (see database::identity)
+            is_me INTEGER DEFAULT 0,
+            
+            -- For historical games
+            slp_path TEXT, stats_version INTEGER DEFAULT 0,
+            
+            -- Constraints
+            UNIQUE(recording_id, player_index)
+        );
+INSERT INTO player_stats VALUES(1,'0b6d1f8e-6a51-4d7e-9f3c-2f1a3c7e5d01',0,'HATS#982','hats',2,0,1,312.5,4,21,4,5.25,14.90000000000000035,0.5500000000000000444,0.4199999999999999845,0.5,2210,290.1000000000000227,118.0,12,3,9,30,2,5,4,7,6,3,0,0,41,7,2,64.0,1,'C:\Users\me\Documents\Slippi\Game_20240115T193012.slp',1);
+INSERT INTO player_stats VALUES(2,'0b6d1f8e-6a51-4d7e-9f3c-2f1a3c7e5d01',1,'ABCD#123','abcd',20,0,2,245.0,2,19,2,9.5,12.90000000000000035,0.4500000000000000111,0.4000000000000000222,0.5,1980,259.8000000000000113,142.0,0,1,14,8,4,9,11,5,4,2,1,0,22,12,0,133.0,0,'C:\Users\me\Documents\Slippi\Game_20240115T193012.slp',1);
+CREATE TABLE my_port_overrides (
+            recording_id TEXT PRIMARY KEY,
+            port INTEGER NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+INSERT INTO my_port_overrides VALUES('0b6d1f8e-6a51-4d7e-9f3c-2f1a3c7e5d01',1,'2024-01-16T10:00:00+00:00');
+PRAGMA writable_schema=ON;
+CREATE TABLE retention_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            -- Match criteria; NULL matches everything
+            tag TEXT,
+            opponent TEXT,
+            result TEXT,  -- 'win' or 'loss'
+            min_age_days INTEGER,
+            action TEXT NOT NULL,  -- 'keep', 'delete_video', 'delete_video_and_replay' or 'compress'
+            created_at TEXT NOT NULL
+        );
+INSERT INTO retention_rules VALUES(1,'Old friendlies',1,NULL,NULL,NULL,30,'delete_video','2024-02-15T12:00:00+00:00');
+CREATE TABLE IF NOT EXISTS sqlite_sequence(name,seq);
+DELETE FROM sqlite_sequence;
+INSERT INTO sqlite_sequence VALUES('player_stats',2);
+INSERT INTO sqlite_sequence VALUES('retention_rules',1);
+CREATE INDEX idx_recordings_start_time ON recordings(start_time DESC);
+CREATE INDEX idx_recordings_video_path ON recordings(video_path);
+CREATE INDEX idx_game_stats_player1 ON game_stats(player1_id);
+CREATE INDEX idx_game_stats_player2 ON game_stats(player2_id);
+CREATE INDEX idx_game_stats_characters ON game_stats(player1_character, player2_character);
+CREATE INDEX idx_game_stats_stage ON game_stats(stage);
+CREATE INDEX idx_game_stats_slp_path ON game_stats(slp_path);
+CREATE INDEX idx_game_stats_created_at ON game_stats(created_at DESC);
+CREATE INDEX idx_player_stats_recording ON player_stats(recording_id);
+CREATE INDEX idx_player_stats_connect_code ON player_stats(connect_code);
+CREATE INDEX idx_player_stats_character ON player_stats(character_id);
+CREATE INDEX idx_player_stats_slp_path ON player_stats(slp_path);
+CREATE INDEX idx_player_stats_is_me ON player_stats(is_me);
+CREATE INDEX idx_player_stats_stats_version ON player_stats(stats_version);
+PRAGMA writable_schema=OFF;
+COMMIT;
@@ -0,0 +1,226 @@
+//! Attribution of "me" (the local user) to a player in each stored game
+//!
+//! Netplay games are matched by connect code. Local games have no connect codes,
+//! so they fall back to the user's in-game tag and preferred characters, unless
+//! the user has pinned their port for that recording with an override.
+//! Games that can't be attributed unambiguously are left without an is_me row.
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// Everything we know about the local user for attribution purposes
+#[derive(Debug, Clone, Default)]
+pub struct MyIdentity {
+    /// My connect codes (uppercase)
+    pub connect_codes: Vec<String>,
+    /// My usual in-game name tag for local games
+    pub in_game_tag: Option<String>,
+    /// Character IDs I usually play
+    pub preferred_characters: Vec<i32>,
+}
+
+impl MyIdentity {
+    /// Whether any attribution hint has been configured
+    pub fn is_configured(&self) -> bool {
+        !self.connect_codes.is_empty() || self.in_game_tag.is_some() || !self.preferred_characters.is_empty()
+    }
+}
+
+/// The subset of a player_stats row needed for attribution
+#[derive(Debug, Clone)]
+struct AttributionPlayer {
+    player_index: i32,
+    port: i32,
+    connect_code: Option<String>,
+    display_name: Option<String>,
+    character_id: i32,
+}
+
+/// Pick which player in a game is me, returning their player_index
+/// Returns None when attribution is unknown or ambiguous
+fn attribute_me(
+    players: &[AttributionPlayer],
+    identity: &MyIdentity,
+    port_override: Option<i32>,
+) -> Option<i32> {
+    // 1. Manual override always wins
+    if let Some(port) = port_override {
+        return players.iter().find(|p| p.port == port).map(|p| p.player_index);
+    }
+
+    // 2. Connect codes (netplay games)
+    let has_codes = players.iter().any(|p| p.connect_code.as_deref().is_some_and(|c| !c.is_empty()));
+    if has_codes {
+        return players
+            .iter()
+            .find(|p| {
+                p.connect_code
+                    .as_ref()
+                    .is_some_and(|c| identity.connect_codes.contains(&c.to_uppercase()))
+            })
+            .map(|p| p.player_index);
+    }
+
+    // 3. Local games - in-game tag, then preferred characters, only if exactly one player matches
+    if let Some(tag) = &identity.in_game_tag {
+        let matches: Vec<&AttributionPlayer> = players
+            .iter()
+            .filter(|p| p.display_name.as_deref().is_some_and(|n| n.trim().eq_ignore_ascii_case(tag)))
+            .collect();
+        if matches.len() == 1 {
+            return Some(matches[0].player_index);
+        }
+    }
+
+    let matches: Vec<&AttributionPlayer> = players
+        .iter()
+        .filter(|p| identity.preferred_characters.contains(&p.character_id))
+        .collect();
+    if matches.len() == 1 {
+        return Some(matches[0].player_index);
+    }
+
+    None
+}
+
+/// Recompute is_me for every game in the database
+/// Returns the number of games attributed to me
+pub fn recompute_is_me(conn: &Connection, identity: &MyIdentity) -> rusqlite::Result<usize> {
+    let mut stmt = conn.prepare("SELECT DISTINCT recording_id FROM player_stats")?;
+    let recording_ids: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let tx = conn.unchecked_transaction()?;
+    let mut attributed = 0;
+    for recording_id in &recording_ids {
+        if recompute_is_me_for_recording(&tx, recording_id, identity)?.is_some() {
+            attributed += 1;
+        }
+    }
+    tx.commit()?;
+
+    Ok(attributed)
+}
+
+/// Recompute is_me (and with it my_result) for a single game
+/// Returns the player_index attributed to me, if any
+pub fn recompute_is_me_for_recording(
+    conn: &Connection,
+    recording_id: &str,
+    identity: &MyIdentity,
+) -> rusqlite::Result<Option<i32>> {
+    let mut stmt = conn.prepare(
+        "SELECT player_index, port, connect_code, display_name, character_id
+         FROM player_stats WHERE recording_id = ? ORDER BY player_index",
+    )?;
+    let players: Vec<AttributionPlayer> = stmt
+        .query_map(params![recording_id], |row| {
+            Ok(AttributionPlayer {
+                player_index: row.get(0)?,
+                port: row.get(1)?,
+                connect_code: row.get(2)?,
+                display_name: row.get(3)?,
+                character_id: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let port_override = get_my_port_override(conn, recording_id)?;
+    let me = attribute_me(&players, identity, port_override);
+
+    conn.execute(
+        "UPDATE player_stats SET is_me = CASE WHEN player_index = ?2 THEN 1 ELSE 0 END
+         WHERE recording_id = ?1",
+        params![recording_id, me],
+    )?;
+    super::recordings::refresh_my_result(conn, recording_id)?;
+
+    Ok(me)
+}
+
+/// Get the manual "my port" override for a recording, if set
+pub fn get_my_port_override(conn: &Connection, recording_id: &str) -> rusqlite::Result<Option<i32>> {
+    conn.query_row(
+        "SELECT port FROM my_port_overrides WHERE recording_id = ?",
+        params![recording_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Set (or clear, with None) the manual "my port" override for a recording
+pub fn set_my_port_override(conn: &Connection, recording_id: &str, port: Option<i32>) -> rusqlite::Result<()> {
+    match port {
+        Some(port) => {
+            conn.execute(
+                "INSERT INTO my_port_overrides (recording_id, port, updated_at)
+                 VALUES (?1, ?2, ?3)
+                 ON CONFLICT(recording_id) DO UPDATE SET
+                    port = excluded.port,
+                    updated_at = excluded.updated_at",
+                params![recording_id, port, chrono::Utc::now().to_rfc3339()],
+            )?;
+        }
+        None => {
+            conn.execute("DELETE FROM my_port_overrides WHERE recording_id = ?", params![recording_id])?;
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOX: i32 = 2;
+    const MARTH: i32 = 9;
+
+    fn player(
+        player_index: i32,
+        connect_code: Option<&str>,
+        display_name: Option<&str>,
+        character_id: i32,
+    ) -> AttributionPlayer {
+        AttributionPlayer {
+            player_index,
+            port: player_index + 1,
+            connect_code: connect_code.map(str::to_string),
+            display_name: display_name.map(str::to_string),
+            character_id,
+        }
+    }
+
+    #[test]
+    fn test_attribute_me() {
+        let me = MyIdentity {
+            connect_codes: vec!["HATS#982".to_string()],
+            in_game_tag: Some("HATS".to_string()),
+            preferred_characters: vec![FOX],
+        };
+
+        // Netplay: my code, whatever port it's on and however it's cased
+        let netplay = [player(0, Some("HATS#982"), None, FOX), player(1, Some("ABCD#123"), None, MARTH)];
+        assert_eq!(attribute_me(&netplay, &me, None), Some(0));
+        let swapped = [player(0, Some("ABCD#123"), None, FOX), player(1, Some("hats#982"), None, MARTH)];
+        assert_eq!(attribute_me(&swapped, &me, None), Some(1));
+        // Someone else's netplay game isn't guessed from characters
+        let others = [player(0, Some("WXYZ#1"), None, FOX), player(1, Some("ABCD#123"), None, MARTH)];
+        assert_eq!(attribute_me(&others, &me, None), None);
+        // The override wins, and an empty port is no one
+        assert_eq!(attribute_me(&netplay, &me, Some(2)), Some(1));
+        assert_eq!(attribute_me(&netplay, &me, Some(4)), None);
+
+        // Local: the tag, then the one player on a preferred character
+        let tagged = [player(0, None, Some(" hats "), MARTH), player(1, None, None, FOX)];
+        assert_eq!(attribute_me(&tagged, &me, None), Some(0));
+        let untagged = [player(0, None, None, MARTH), player(1, None, None, FOX)];
+        assert_eq!(attribute_me(&untagged, &me, None), Some(1));
+        // Two players on the tag or on my characters is ambiguous
+        let ditto = [player(0, None, Some("HATS"), FOX), player(1, None, Some("HATS"), FOX)];
+        assert_eq!(attribute_me(&ditto, &me, None), None);
+        let strangers = [player(0, None, None, MARTH), player(1, None, None, MARTH)];
+        assert_eq!(attribute_me(&strangers, &me, None), None);
+        assert_eq!(attribute_me(&untagged, &MyIdentity::default(), None), None);
+    }
+}
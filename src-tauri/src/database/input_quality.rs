@@ -0,0 +1,226 @@
+//! Controller input quality counted by the input analysis pass (see inputs.rs)
+
+use super::recordings::{PlayerScope, StatsFilter};
+use crate::timestamps;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// What the input analysis counted for one player in one game
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputQualityCounts {
+    pub dashback_attempts: i64,
+    pub dashback_successes: i64,
+    pub pivot_attempts: i64,
+    pub pivot_successes: i64,
+    /// Pivots not followed by an attack or grab
+    pub empty_pivots: i64,
+    /// Suspected stick snapback
+    pub snapbacks: i64,
+}
+
+/// Input quality over a set of games
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputQuality {
+    /// Analyzed games
+    pub games: i64,
+    #[serde(flatten)]
+    pub counts: InputQualityCounts,
+    /// Successful share of attempts, None without attempts
+    pub dashback_success_rate: Option<f64>,
+    pub pivot_success_rate: Option<f64>,
+    pub snapbacks_per_game: Option<f64>,
+    /// One point per day played, oldest first
+    pub trend: Vec<InputQualityPoint>,
+}
+
+/// Input quality on one day
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputQualityPoint {
+    /// YYYY-MM-DD
+    pub date: String,
+    pub games: i64,
+    pub dashback_success_rate: Option<f64>,
+    pub pivot_success_rate: Option<f64>,
+    pub snapbacks_per_game: Option<f64>,
+}
+
+/// A game whose replay hasn't been through the input analysis
+#[derive(Debug, Clone)]
+pub struct PendingInputAnalysis {
+    pub recording_id: String,
+    pub slp_path: String,
+}
+
+/// Games with player rows the input analysis hasn't counted yet
+/// Replays known not to parse are left out
+pub fn get_pending_input_analysis(conn: &Connection) -> rusqlite::Result<Vec<PendingInputAnalysis>> {
+    let mut stmt = conn.prepare(
+        "SELECT p.recording_id, MAX(COALESCE(r.slp_path, p.slp_path))
+         FROM player_stats p
+         LEFT JOIN recordings r ON r.id = p.recording_id
+         WHERE p.dashback_attempts IS NULL AND r.parse_error IS NULL
+         GROUP BY p.recording_id
+         HAVING MAX(COALESCE(r.slp_path, p.slp_path)) IS NOT NULL",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(PendingInputAnalysis { recording_id: row.get(0)?, slp_path: row.get(1)? })
+    })?;
+    rows.collect()
+}
+
+/// Store a player's counts (players without stats rows are ignored)
+pub fn set_input_quality(
+    conn: &Connection,
+    recording_id: &str,
+    player_index: i32,
+    counts: &InputQualityCounts,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE player_stats SET
+            dashback_attempts = ?3, dashback_successes = ?4, pivot_attempts = ?5,
+            pivot_successes = ?6, empty_pivots = ?7, snapbacks = ?8
+         WHERE recording_id = ?1 AND player_index = ?2",
+        params![
+            recording_id,
+            player_index,
+            counts.dashback_attempts,
+            counts.dashback_successes,
+            counts.pivot_attempts,
+            counts.pivot_successes,
+            counts.empty_pivots,
+            counts.snapbacks,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Mark the players of a game the analysis found no frames for as analyzed,
+/// so the game isn't picked up again
+pub fn finish_input_analysis(conn: &Connection, recording_id: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE player_stats SET
+            dashback_attempts = 0, dashback_successes = 0, pivot_attempts = 0,
+            pivot_successes = 0, empty_pivots = 0, snapbacks = 0
+         WHERE recording_id = ?1 AND dashback_attempts IS NULL",
+        params![recording_id],
+    )?;
+    Ok(())
+}
+
+/// Input quality of a player scope over the filtered games, with a daily trend
+pub fn get_input_quality(
+    conn: &Connection,
+    scope: PlayerScope<'_>,
+    filter: Option<StatsFilter>,
+) -> rusqlite::Result<InputQuality> {
+    let filter = filter.unwrap_or_default();
+
+    let (scope_clause, scope_param) = scope.condition(1);
+    let mut where_clauses = vec![scope_clause, "p.dashback_attempts IS NOT NULL".to_string()];
+    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    if let Some(code) = scope_param {
+        params_vec.push(Box::new(code));
+    }
+
+    if !filter.include_excluded {
+        where_clauses.push("g.excluded_reason IS NULL".to_string());
+    }
+    if filter.exclude_warmups {
+        where_clauses.push("g.is_warmup = 0".to_string());
+    }
+    if let Some(stage) = filter.stage_id {
+        params_vec.push(Box::new(stage));
+        where_clauses.push(format!("g.stage = ?{}", params_vec.len()));
+    }
+    let (start_time, end_time) = filter.time_range();
+    if let Some(start) = start_time {
+        params_vec.push(Box::new(start));
+        where_clauses.push(format!("g.created_at >= ?{}", params_vec.len()));
+    }
+    if let Some(end) = end_time {
+        params_vec.push(Box::new(end));
+        where_clauses.push(format!("g.created_at <= ?{}", params_vec.len()));
+    }
+    if let Some(player_char) = filter.player_character_id {
+        params_vec.push(Box::new(player_char));
+        where_clauses.push(format!("p.character_id = ?{}", params_vec.len()));
+    }
+    if let Some(opp_char) = filter.opponent_character_id {
+        params_vec.push(Box::new(opp_char));
+        where_clauses.push(format!(
+            "EXISTS (SELECT 1 FROM player_stats o
+                     WHERE o.recording_id = p.recording_id AND o.player_index != p.player_index
+                       AND o.character_id = ?{})",
+            params_vec.len()
+        ));
+    }
+    if let Some(min_version) = filter.min_stats_version {
+        params_vec.push(Box::new(min_version));
+        where_clauses.push(format!("p.stats_version >= ?{}", params_vec.len()));
+    }
+
+    let day = format!("date({})", timestamps::local_sql("g.created_at", filter.utc_offset_minutes));
+    let query = format!(
+        "SELECT {1}, COUNT(*),
+                SUM(p.dashback_attempts), SUM(p.dashback_successes), SUM(p.pivot_attempts),
+                SUM(p.pivot_successes), SUM(p.empty_pivots), SUM(p.snapbacks)
+         FROM player_stats p
+         JOIN game_stats g ON g.id = p.recording_id
+         WHERE {0}
+         GROUP BY 1
+         ORDER BY 1",
+        where_clauses.join(" AND "),
+        day
+    );
+
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+    let mut stmt = conn.prepare(&query)?;
+    let days = stmt
+        .query_map(params_refs.as_slice(), |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get::<_, i64>(1)?,
+                InputQualityCounts {
+                    dashback_attempts: row.get(2)?,
+                    dashback_successes: row.get(3)?,
+                    pivot_attempts: row.get(4)?,
+                    pivot_successes: row.get(5)?,
+                    empty_pivots: row.get(6)?,
+                    snapbacks: row.get(7)?,
+                },
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut quality = InputQuality::default();
+    for (date, games, counts) in days {
+        quality.games += games;
+        quality.counts.dashback_attempts += counts.dashback_attempts;
+        quality.counts.dashback_successes += counts.dashback_successes;
+        quality.counts.pivot_attempts += counts.pivot_attempts;
+        quality.counts.pivot_successes += counts.pivot_successes;
+        quality.counts.empty_pivots += counts.empty_pivots;
+        quality.counts.snapbacks += counts.snapbacks;
+        // Games without a date count in the totals only
+        if let Some(date) = date {
+            quality.trend.push(InputQualityPoint {
+                date,
+                games,
+                dashback_success_rate: rate(counts.dashback_successes, counts.dashback_attempts),
+                pivot_success_rate: rate(counts.pivot_successes, counts.pivot_attempts),
+                snapbacks_per_game: rate(counts.snapbacks, games),
+            });
+        }
+    }
+    quality.dashback_success_rate = rate(quality.counts.dashback_successes, quality.counts.dashback_attempts);
+    quality.pivot_success_rate = rate(quality.counts.pivot_successes, quality.counts.pivot_attempts);
+    quality.snapbacks_per_game = rate(quality.counts.snapbacks, quality.games);
+    Ok(quality)
+}
+
+fn rate(count: i64, total: i64) -> Option<f64> {
+    (total > 0).then(|| count as f64 / total as f64)
+}
@@ -5,53 +5,166 @@
 
 mod schema;
 mod recordings;
+mod identity;
+mod retention;
+mod combos;
+mod input_quality;
+mod character_usage;
+mod players;
+mod stats_cache;
+mod stats_compare;
+mod streaks;
+mod recording_rules;
+mod reports;
+mod warmups;
+mod write_queue;
+mod clips;
 
 pub use recordings::{
     // Recording operations
-    get_all_recordings, get_recordings_paginated, get_recording_by_video_path, 
-    upsert_recording, delete_recording, get_cached_video_paths,
+    get_all_recordings, get_recordings_paginated, get_recording_by_video_path, get_recording_by_id, get_recording_with_stats,
+    upsert_recording, delete_recording, rekey_recording, get_cached_video_paths, get_linked_slp_paths,
+    get_replay_only_recordings, get_replay_only_recording_id, get_recording_id_by_slp_path, set_recording_capture_info,
+    set_recording_file_info, set_recording_archived, set_recording_parse_error, has_parse_error_for_slp,
+    set_recording_replay_id, set_recording_source, set_recording_thumbnail, set_recording_video_offset, set_recording_video_info,
+    set_recording_video_path, set_recording_audio_info, get_recording_games, set_recording_games, normalize_stored_paths,
+    set_needs_reparse, flag_outdated_stats, finish_reparse, get_replay_hashes, set_slp_hash,
     // Game stats operations
-    upsert_game_stats, game_stats_exists_by_slp_path,
+    upsert_game_stats, insert_provisional_game_stats, get_game_stats, game_stats_exists_by_slp_path, get_stats_source_by_slp_path, update_excluded_reasons,
+    repair_game_results,
     // Player stats operations
-    upsert_player_stats, get_player_stats_by_recording, get_aggregated_player_stats,
+    upsert_player_stats, set_played_character, get_player_stats_by_recording, get_aggregated_player_stats, get_my_stat_averages, get_my_games_since,
+    count_outdated_stats, get_outdated_stats_games,
     // Filter options
     get_available_filter_options,
     // Types
-    RecordingRow, GameStatsRow, RecordingWithStats, PlayerStatsRow,
-    AggregatedPlayerStats, StatsFilter, AvailableFilterOptions,
+    RecordingRow, RecordingGame, GameStatsRow, RecordingWithStats, PlayerStatsRow,
+    AggregatedPlayerStats, StatsFilter, AvailableFilterOptions, PlayerScope, OutdatedStatsGame, StatsExclusion, ReplayHash,
+    StatAverages, MyGameRow, MyResult, StatsSource,
+};
+pub use identity::{
+    recompute_is_me, recompute_is_me_for_recording, set_my_port_override, MyIdentity,
+};
+
+pub use retention::{
+    get_retention_rules, save_retention_rule, delete_retention_rule, has_enabled_retention_rules,
+    RetentionAction, RetentionRuleRow, ResultFilter,
+};
+pub use combos::{get_top_highlights, replace_combos, set_combo_clip_path, ComboRow, HighlightRow};
+pub use input_quality::{
+    finish_input_analysis, get_input_quality, get_pending_input_analysis, set_input_quality, InputQuality,
+    InputQualityCounts,
+};
+pub use character_usage::{
+    get_character_usage, get_first_and_last_played, CharacterPlayedRange, CharacterUsage, CharacterUsagePeriod, UsageBucket,
+};
+pub use players::{get_known_players, merge_players, record_player_identity, KnownPlayer};
+pub use schema::{MigrationError, MigrationStatus};
+pub use stats_cache::StatsCache;
+pub use stats_compare::{compare_stats, MetricComparison, StatsComparison, StatsSubject};
+pub use streaks::{get_current_streak, CurrentStreak};
+pub use recording_rules::{
+    delete_recording_rule, get_recording_rule_log, get_recording_rules, log_recording_rule, save_recording_rule,
+    RecordingRuleAction, RecordingRuleLogEntry, RecordingRuleRow,
+};
+pub use reports::{get_reports, has_report, save_report, ReportRow};
+pub use warmups::{recompute_warmups, set_warmup_override, WarmupSettings};
+pub use write_queue::spawn_write_flusher;
+pub use clips::{
+    get_clips_by_age, get_favorite_clip_ids, get_recordings_size, is_clip_favorite, rename_clip, set_clip_favorite,
+    ClipFileRow,
 };
 
 use rusqlite::Connection;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
 /// Database connection wrapper for thread-safe access
 pub struct Database {
     conn: Mutex<Connection>,
+    path: PathBuf,
+    /// Writes applied later, see write_behind
+    writes: write_queue::WriteQueue,
 }
 
 impl Database {
     /// Open or create a database at the specified path
-    pub fn open(path: &PathBuf) -> Result<Self, rusqlite::Error> {
+    pub fn open(path: &Path) -> Result<Self, rusqlite::Error> {
         // Ensure parent directory exists
         if let Some(parent) = path.parent() {
             let _ = std::fs::create_dir_all(parent);
         }
         
-        let conn = Connection::open(path)?;
-        
-        // Enable WAL mode for better concurrent access
-        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL;")?;
-        
         Ok(Self {
-            conn: Mutex::new(conn),
+            conn: Mutex::new(open_connection(path)?),
+            path: path.to_path_buf(),
+            writes: Default::default(),
         })
     }
     
+    /// A fully migrated database in memory (for tests)
+    pub fn open_in_memory() -> Result<Self, MigrationError> {
+        let db = Self {
+            conn: Mutex::new(Connection::open_in_memory()?),
+            path: PathBuf::from(":memory:"),
+            writes: Default::default(),
+        };
+        db.init()?;
+        Ok(db)
+    }
+    
     /// Initialize the database schema
-    pub fn init(&self) -> Result<(), rusqlite::Error> {
+    /// Takes a backup before migrating an existing database, and restores it if a migration fails
+    pub fn init(&self) -> Result<(), MigrationError> {
+        let mut conn = self.conn.lock().unwrap();
+        
+        // Version table may not exist yet on a brand new database
+        let needs_backup = schema::current_version(&conn) > 0 && schema::has_pending_migrations(&conn);
+        let backup_path = if needs_backup {
+            Some(self.backup(&conn)?)
+        } else {
+            None
+        };
+        
+        match schema::init_database(&conn) {
+            Ok(()) => {
+                if let Some(backup) = backup_path {
+                    let _ = std::fs::remove_file(backup);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                let Some(backup) = backup_path else {
+                    return Err(e);
+                };
+                log::error!("❌ {}, restoring backup from {:?}", e, backup);
+                
+                // Close the connection before replacing the file underneath it
+                *conn = Connection::open_in_memory()?;
+                restore_backup(&backup, &self.path)?;
+                *conn = open_connection(&self.path)?;
+                
+                Err(e.with_restored())
+            }
+        }
+    }
+    
+    /// Report pending migrations without applying them
+    pub fn check_migrations(&self) -> MigrationStatus {
         let conn = self.conn.lock().unwrap();
-        schema::init_database(&conn)
+        schema::check_migrations(&conn)
+    }
+    
+    /// Snapshot the database next to itself before migrating (e.g. peppi.db.pre-v9.bak)
+    fn backup(&self, conn: &Connection) -> Result<PathBuf, MigrationError> {
+        let backup = self.path.with_extension(format!("db.pre-v{}.bak", schema::current_version(conn)));
+        let _ = std::fs::remove_file(&backup);
+        
+        conn.execute("VACUUM INTO ?", [backup.to_string_lossy().to_string()])
+            .map_err(|e| MigrationError::Backup(e.to_string()))?;
+        
+        log::info!("💾 Backed up database to {:?} before migrating", backup);
+        Ok(backup)
     }
     
     /// Get a reference to the connection (for operations)
@@ -60,6 +173,29 @@ impl Database {
     }
 }
 
+/// Open a connection with the pragmas every connection should have
+fn open_connection(path: &Path) -> Result<Connection, rusqlite::Error> {
+    let conn = Connection::open(path)?;
+    
+    // Enable WAL mode for better concurrent access
+    conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL;")?;
+    
+    Ok(conn)
+}
+
+/// Replace the database file with a backup, discarding any WAL files left by the failed migration
+fn restore_backup(backup: &Path, path: &Path) -> Result<(), MigrationError> {
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        let _ = std::fs::remove_file(PathBuf::from(sidecar));
+    }
+    
+    std::fs::copy(backup, path).map_err(|e| MigrationError::Restore(e.to_string()))?;
+    log::info!("♻️ Restored database from {:?}", backup);
+    Ok(())
+}
+
 /// Get the default database path (in app data directory)
 pub fn get_database_path(app: &tauri::AppHandle) -> PathBuf {
     use tauri::Manager;
@@ -0,0 +1,147 @@
+//! Who the players are: the display names seen with each connect code, and
+//! codes merged into one person
+//!
+//! Stats group players by connect code, since display names change all the
+//! time. Each (code, name) pair a stats pass sees is kept with when it was
+//! first and last seen, for autocomplete and to show a code's past names. A
+//! code merged into another (an alt account) counts as that code in
+//! aggregate queries (see PlayerScope::Code).
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// SQL for the code a `column` holding a connect code counts as, after merges.
+/// Qualify the column (`p.connect_code`): bare, it names player_aliases' own
+pub(super) fn canonical_code_sql(column: &str) -> String {
+    format!("COALESCE((SELECT a.canonical_code FROM player_aliases a WHERE a.connect_code = {0}), {0})", column)
+}
+
+/// A person, by the code their merged codes count as
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownPlayer {
+    pub connect_code: String,
+    /// Every code of theirs (this one and those merged into it)
+    pub codes: Vec<String>,
+    /// Most recently seen first
+    pub display_names: Vec<String>,
+    pub first_seen: Option<String>,
+    pub last_seen: Option<String>,
+}
+
+/// Note a code and display name seen in a game played at `seen_at` (RFC 3339)
+pub fn record_player_identity(
+    conn: &Connection,
+    connect_code: &str,
+    display_name: Option<&str>,
+    seen_at: &str,
+) -> rusqlite::Result<()> {
+    let connect_code = connect_code.trim();
+    if connect_code.is_empty() {
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT INTO player_identities (connect_code, display_name, first_seen, last_seen)
+         VALUES (?1, ?2, ?3, ?3)
+         ON CONFLICT(connect_code, display_name) DO UPDATE SET
+            first_seen = MIN(first_seen, excluded.first_seen),
+            last_seen = MAX(last_seen, excluded.last_seen)",
+        params![connect_code, display_name.map(str::trim).unwrap_or_default(), seen_at],
+    )?;
+    Ok(())
+}
+
+/// The code a connect code counts as
+fn canonical_code(conn: &Connection, connect_code: &str) -> rusqlite::Result<String> {
+    let canonical: Option<String> = conn
+        .query_row(
+            "SELECT canonical_code FROM player_aliases WHERE connect_code = ?1",
+            [connect_code],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(canonical.unwrap_or_else(|| connect_code.to_string()))
+}
+
+/// Declare that `connect_code` belongs to the same person as `into_code`.
+/// Codes already merged into `connect_code` follow it
+pub fn merge_players(conn: &Connection, connect_code: &str, into_code: &str) -> rusqlite::Result<()> {
+    let connect_code = connect_code.trim().to_uppercase();
+    let canonical = canonical_code(conn, &into_code.trim().to_uppercase())?;
+    if canonical == connect_code {
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE player_aliases SET canonical_code = ?2 WHERE canonical_code = ?1",
+        params![connect_code, canonical],
+    )?;
+    tx.execute(
+        "INSERT OR REPLACE INTO player_aliases (connect_code, canonical_code) VALUES (?1, ?2)",
+        params![connect_code, canonical],
+    )?;
+    tx.commit()
+}
+
+/// Players seen in stats, most recently seen first. `search` matches any of
+/// their codes or display names (ignoring case)
+pub fn get_known_players(conn: &Connection, search: Option<&str>, limit: usize) -> rusqlite::Result<Vec<KnownPlayer>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, connect_code, display_name, first_seen, last_seen FROM player_identities
+         UNION ALL
+         SELECT canonical_code, connect_code, '', NULL, NULL FROM player_aliases
+         ORDER BY 5 DESC",
+        canonical_code_sql("player_identities.connect_code")
+    ))?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, Option<String>>(4)?,
+        ))
+    })?;
+
+    let mut players: Vec<KnownPlayer> = Vec::new();
+    let mut by_code: HashMap<String, usize> = HashMap::new();
+    for row in rows {
+        let (canonical, code, display_name, first_seen, last_seen) = row?;
+        let index = *by_code.entry(canonical.clone()).or_insert_with(|| {
+            players.push(KnownPlayer {
+                connect_code: canonical.clone(),
+                codes: vec![canonical],
+                display_names: Vec::new(),
+                first_seen: None,
+                last_seen: None,
+            });
+            players.len() - 1
+        });
+        let player = &mut players[index];
+        if !player.codes.contains(&code) {
+            player.codes.push(code);
+        }
+        if !display_name.is_empty() && !player.display_names.contains(&display_name) {
+            player.display_names.push(display_name);
+        }
+        // Rows come newest first, so the first last_seen is the latest
+        player.last_seen = player.last_seen.take().or(last_seen);
+        player.first_seen = match (player.first_seen.take(), first_seen) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    let search = search.map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty());
+    Ok(players
+        .into_iter()
+        .filter(|player| {
+            search.as_deref().is_none_or(|search| {
+                player.codes.iter().chain(&player.display_names).any(|s| s.to_lowercase().contains(search))
+            })
+        })
+        .take(limit)
+        .collect())
+}
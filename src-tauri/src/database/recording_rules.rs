@@ -0,0 +1,228 @@
+//! Recording rules: which new games to record, skip or record at another quality,
+//! and the log of which rule decided each game
+
+use crate::recorder::RecordingQuality;
+use crate::watch_filters::ConnectionFilter;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// Entries kept in recording_rule_log (older ones are dropped as new ones come in)
+const MAX_LOG_ENTRIES: i64 = 500;
+
+/// What a rule does with the games it matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordingRuleAction {
+    /// Record, even if the watch filters or the CPU setting would skip it
+    Record,
+    Skip,
+    /// Record at the rule's quality instead of the recording quality setting
+    RecordAtQuality,
+}
+
+impl RecordingRuleAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            RecordingRuleAction::Record => "record",
+            RecordingRuleAction::Skip => "skip",
+            RecordingRuleAction::RecordAtQuality => "record_at_quality",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [RecordingRuleAction::Record, RecordingRuleAction::Skip, RecordingRuleAction::RecordAtQuality]
+            .into_iter()
+            .find(|action| action.as_str() == value)
+    }
+}
+
+/// Row from the recording_rules table. Unset criteria match every game
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingRuleRow {
+    /// None for a rule that hasn't been saved yet
+    pub id: Option<i64>,
+    pub name: String,
+    pub enabled: bool,
+    /// Connect code of any opponent
+    pub opponent_code: Option<String>,
+    /// External character ID I play
+    pub my_character: Option<u8>,
+    /// External character ID of any opponent
+    pub opponent_character: Option<u8>,
+    #[serde(default)]
+    pub connection: ConnectionFilter,
+    pub action: RecordingRuleAction,
+    /// For RecordAtQuality
+    pub quality: Option<RecordingQuality>,
+}
+
+/// Which rule decided a new game
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingRuleLogEntry {
+    pub slp_path: String,
+    pub rule_id: i64,
+    /// Name and action at the time (the rule may have changed or gone since)
+    pub rule_name: String,
+    pub action: RecordingRuleAction,
+    pub quality: Option<RecordingQuality>,
+    pub created_at: String,
+}
+
+fn connection_as_str(connection: ConnectionFilter) -> &'static str {
+    match connection {
+        ConnectionFilter::Any => "any",
+        ConnectionFilter::Online => "online",
+        ConnectionFilter::Offline => "offline",
+    }
+}
+
+fn parse_connection(value: &str) -> ConnectionFilter {
+    match value {
+        "online" => ConnectionFilter::Online,
+        "offline" => ConnectionFilter::Offline,
+        _ => ConnectionFilter::Any,
+    }
+}
+
+fn quality_as_str(quality: RecordingQuality) -> &'static str {
+    match quality {
+        RecordingQuality::Low => "low",
+        RecordingQuality::Medium => "medium",
+        RecordingQuality::High => "high",
+        RecordingQuality::Ultra => "ultra",
+    }
+}
+
+fn parse_quality(value: &str) -> Option<RecordingQuality> {
+    RecordingQuality::ALL.into_iter().find(|quality| quality_as_str(*quality) == value)
+}
+
+/// Get every recording rule, oldest first
+pub fn get_recording_rules(conn: &Connection) -> rusqlite::Result<Vec<RecordingRuleRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, enabled, opponent_code, my_character, opponent_character, connection, action, quality
+         FROM recording_rules
+         ORDER BY id",
+    )?;
+
+    let rows = stmt.query_map([], |row| {
+        let connection: String = row.get(6)?;
+        let action: String = row.get(7)?;
+        let quality: Option<String> = row.get(8)?;
+        Ok(RecordingRuleRow {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            enabled: row.get::<_, i32>(2)? != 0,
+            opponent_code: row.get(3)?,
+            my_character: row.get(4)?,
+            opponent_character: row.get(5)?,
+            connection: parse_connection(&connection),
+            // Unknown actions (from a newer version) are treated as record, so no game is lost
+            action: RecordingRuleAction::parse(&action).unwrap_or(RecordingRuleAction::Record),
+            quality: quality.as_deref().and_then(parse_quality),
+        })
+    })?;
+
+    rows.collect()
+}
+
+/// Insert a rule, or update it if it has an id. Returns the rule's id
+pub fn save_recording_rule(conn: &Connection, rule: &RecordingRuleRow) -> rusqlite::Result<i64> {
+    match rule.id {
+        Some(id) => {
+            let updated = conn.execute(
+                "UPDATE recording_rules
+                 SET name = ?2, enabled = ?3, opponent_code = ?4, my_character = ?5, opponent_character = ?6,
+                     connection = ?7, action = ?8, quality = ?9
+                 WHERE id = ?1",
+                params![
+                    id,
+                    rule.name,
+                    rule.enabled as i32,
+                    rule.opponent_code,
+                    rule.my_character,
+                    rule.opponent_character,
+                    connection_as_str(rule.connection),
+                    rule.action.as_str(),
+                    rule.quality.map(quality_as_str),
+                ],
+            )?;
+            if updated == 0 {
+                return Err(rusqlite::Error::QueryReturnedNoRows);
+            }
+            Ok(id)
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO recording_rules
+                 (name, enabled, opponent_code, my_character, opponent_character, connection, action, quality, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    rule.name,
+                    rule.enabled as i32,
+                    rule.opponent_code,
+                    rule.my_character,
+                    rule.opponent_character,
+                    connection_as_str(rule.connection),
+                    rule.action.as_str(),
+                    rule.quality.map(quality_as_str),
+                    chrono::Utc::now().to_rfc3339(),
+                ],
+            )?;
+            Ok(conn.last_insert_rowid())
+        }
+    }
+}
+
+/// Delete a rule (its log entries stay). Returns whether it existed
+pub fn delete_recording_rule(conn: &Connection, id: i64) -> rusqlite::Result<bool> {
+    Ok(conn.execute("DELETE FROM recording_rules WHERE id = ?", params![id])? > 0)
+}
+
+/// Note that `rule` (a saved one) decided the game of `slp_path`
+pub fn log_recording_rule(conn: &Connection, slp_path: &str, rule: &RecordingRuleRow) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO recording_rule_log (slp_path, rule_id, rule_name, action, quality, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            slp_path,
+            rule.id,
+            rule.name,
+            rule.action.as_str(),
+            rule.quality.map(quality_as_str),
+            chrono::Utc::now().to_rfc3339(),
+        ],
+    )?;
+    conn.execute(
+        "DELETE FROM recording_rule_log WHERE id <= (SELECT MAX(id) FROM recording_rule_log) - ?",
+        params![MAX_LOG_ENTRIES],
+    )?;
+    Ok(())
+}
+
+/// The latest log entries, newest first
+pub fn get_recording_rule_log(conn: &Connection, limit: i64) -> rusqlite::Result<Vec<RecordingRuleLogEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT slp_path, rule_id, rule_name, action, quality, created_at
+         FROM recording_rule_log
+         ORDER BY id DESC
+         LIMIT ?",
+    )?;
+
+    let rows = stmt.query_map(params![limit], |row| {
+        let action: String = row.get(3)?;
+        let quality: Option<String> = row.get(4)?;
+        Ok(RecordingRuleLogEntry {
+            slp_path: row.get(0)?,
+            rule_id: row.get(1)?,
+            rule_name: row.get(2)?,
+            action: RecordingRuleAction::parse(&action).unwrap_or(RecordingRuleAction::Record),
+            quality: quality.as_deref().and_then(parse_quality),
+            created_at: row.get(5)?,
+        })
+    })?;
+
+    rows.collect()
+}
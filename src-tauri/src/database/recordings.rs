@@ -1,6 +1,12 @@
 //! Recording, game stats, and player stats database operations
+//!
+//! File paths are stored and looked up normalized (see crate::paths), so a
+//! file reached through two spellings is one row. Game and recording times are
+//! stored in UTC, one spelling (see crate::timestamps).
 
+use crate::{paths, timestamps};
 use rusqlite::{params, Connection, OptionalExtension};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 // ============================================================================
@@ -11,7 +17,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RecordingRow {
     pub id: String,
-    pub video_path: String,
+    /// None for replay-only entries (indexed from the Slippi folder in replay-only mode)
+    pub video_path: Option<String>,
     pub slp_path: Option<String>,
     pub file_size: Option<i64>,
     pub file_modified_at: Option<String>,
@@ -19,6 +26,62 @@ pub struct RecordingRow {
     pub start_time: Option<String>,
     pub cached_at: String,
     pub needs_reparse: bool,
+    /// Frames dropped during capture (None if not recorded by this app or not tracked)
+    pub dropped_frame_estimate: Option<i64>,
+    /// Longest gap between captured frames in milliseconds
+    pub longest_stall_ms: Option<i64>,
+    /// Settings profile active when it was recorded
+    pub profile: Option<String>,
+    /// Whether the video was re-encoded to the archive format
+    #[serde(default)]
+    pub is_archived: bool,
+    /// Why the .slp couldn't be parsed ('truncated', 'unsupported_version' or 'corrupt')
+    /// Stats passes skip the replay until it's retried
+    #[serde(default)]
+    pub parse_error: Option<String>,
+    /// Stable id of the linked replay's game (see library::stable_ids); a
+    /// video whose id came from the file still shares this with its duplicates
+    #[serde(default)]
+    pub replay_id: Option<String>,
+    /// Video dimensions and length from ffprobe, None until probed
+    #[serde(default)]
+    pub video_width: Option<i64>,
+    #[serde(default)]
+    pub video_height: Option<i64>,
+    #[serde(default)]
+    pub video_duration_secs: Option<f64>,
+    /// Whether the video has an audio stream, None until probed
+    #[serde(default)]
+    pub has_audio: Option<bool>,
+    /// Mean volume of a sample from the middle of the video, in dB
+    #[serde(default)]
+    pub audio_mean_volume_db: Option<f64>,
+    /// How late the video's first frame is compared with the replay's start
+    /// (None if unknown); video time = replay time - offset
+    #[serde(default)]
+    pub video_offset_ms: Option<i64>,
+    /// Recording this one was edited from (trimmed or cropped), if it was
+    #[serde(default)]
+    pub source_recording_id: Option<String>,
+}
+
+/// Below this mean volume a recording counts as silent (game audio is around -20 dB)
+pub const SILENT_MEAN_VOLUME_DB: f64 = -60.0;
+
+impl RecordingRow {
+    /// Whether the recording was probed and has no audible sound
+    pub fn is_silent(&self) -> bool {
+        self.has_audio == Some(false) || self.audio_mean_volume_db.is_some_and(|db| db < SILENT_MEAN_VOLUME_DB)
+    }
+}
+
+/// A game within a recording, and where it starts in the video
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingGame {
+    pub game_index: i64,
+    pub slp_path: String,
+    pub offset_secs: f64,
 }
 
 /// Game stats row from the game_stats table
@@ -44,6 +107,131 @@ pub struct GameStatsRow {
     pub created_at: Option<String>,
     /// Path to .slp file - used for deduplication of historical games
     pub slp_path: Option<String>,
+    /// Replay format version (e.g. "3.16.0"), None for games saved before it was tracked
+    #[serde(default)]
+    pub replay_version: Option<String>,
+    /// Why the game is left out of aggregates ("cpu", "short", "unfinished", "provisional"), None
+    /// if it counts. Set by update_excluded_reasons, not by upsert_game_stats
+    #[serde(default)]
+    pub excluded_reason: Option<String>,
+    /// Frames the game was paused for, None for stats saved before pauses were detected
+    #[serde(default)]
+    pub total_paused_frames: Option<i32>,
+    /// Connect codes of the winner and loser; None offline (see won_by)
+    #[serde(default)]
+    pub winner_code: Option<String>,
+    #[serde(default)]
+    pub loser_code: Option<String>,
+    /// My result in the game. Set by refresh_my_result, not by upsert_game_stats
+    #[serde(default)]
+    pub my_result: MyResult,
+    /// One of the first games of a play session. Set by recompute_warmups, not by upsert_game_stats
+    #[serde(default)]
+    pub is_warmup: bool,
+    /// Which tool computed the stats
+    #[serde(default)]
+    pub stats_source: StatsSource,
+    /// Whether the replay reaches Game End. Crashes and quit-outs don't, and
+    /// their stats stop wherever the replay does
+    #[serde(default = "completed_by_default")]
+    pub completed: bool,
+}
+
+fn completed_by_default() -> bool {
+    true
+}
+
+/// My result in a game, as stored in game_stats.my_result
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MyResult {
+    Win,
+    Loss,
+    /// Not attributed to me, or without a winner
+    #[default]
+    Unknown,
+}
+
+impl MyResult {
+    pub(super) fn from_column(value: &str) -> Self {
+        match value {
+            "win" => Self::Win,
+            "loss" => Self::Loss,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Which tool computed a game's stats, as stored in game_stats.stats_source
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsSource {
+    /// Our own stats pass
+    #[default]
+    Buckwheat,
+    /// Imported from Slippi Launcher's replay database: players, stage and
+    /// winner only, without any per-player stats
+    SlippiLauncher,
+    /// Imported from slippi-js getStats() dumps: inputs per minute include
+    /// paused time and characters are the starting ones
+    SlippiJs,
+}
+
+impl StatsSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Buckwheat => "buckwheat",
+            Self::SlippiLauncher => "slippi_launcher",
+            Self::SlippiJs => "slippi_js",
+        }
+    }
+
+    pub(super) fn from_column(value: &str) -> Self {
+        match value {
+            "slippi_launcher" => Self::SlippiLauncher,
+            "slippi_js" => Self::SlippiJs,
+            _ => Self::Buckwheat,
+        }
+    }
+}
+
+impl GameStatsRow {
+    /// Whether a player of this game won it: by connect code where the game
+    /// and player have one, since ports can change between games of a set,
+    /// else by port. None without a winner
+    pub fn won_by(&self, player: &PlayerStatsRow) -> Option<bool> {
+        match (&self.winner_code, &player.connect_code) {
+            (Some(winner), Some(code)) => Some(winner == code),
+            _ => self.winner_port.map(|winner| winner == player.port),
+        }
+    }
+}
+
+/// SQL for whether player `p` won game `g`, the way GameStatsRow::won_by decides
+pub(super) const WON_SQL: &str =
+    "(CASE WHEN g.winner_code IS NOT NULL AND p.connect_code IS NOT NULL THEN g.winner_code = p.connect_code ELSE g.winner_port = p.port END)";
+
+/// SQL for player `p`'s result in game `g` as a my_result value
+/// (WON_SQL is NULL where won_by is None)
+pub(super) fn result_sql() -> String {
+    format!("(CASE WHEN {WON_SQL} IS NULL THEN 'unknown' WHEN {WON_SQL} THEN 'win' ELSE 'loss' END)")
+}
+
+/// Store my result in a game (game_stats.my_result) from its is_me row and
+/// winner; call after either changes
+pub fn refresh_my_result(conn: &Connection, recording_id: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        &format!(
+            "UPDATE game_stats SET my_result = COALESCE(
+                (SELECT {} FROM player_stats p JOIN game_stats g ON g.id = p.recording_id
+                 WHERE g.id = ?1 AND p.is_me = 1),
+                'unknown')
+             WHERE id = ?1",
+            result_sql()
+        ),
+        params![recording_id],
+    )?;
+    Ok(())
 }
 
 /// Combined recording with its stats (for paginated queries)
@@ -55,7 +243,7 @@ pub struct RecordingWithStats {
 }
 
 /// Player stats row from the player_stats table
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PlayerStatsRow {
     pub id: Option<i64>,
@@ -89,13 +277,20 @@ pub struct PlayerStatsRow {
     pub throw_count: i32,
     pub ground_tech_count: i32,
     pub wall_tech_count: i32,
-    pub wall_jump_tech_count: i32,
-    pub l_cancel_success_count: i32,
-    pub l_cancel_fail_count: i32,
+    pub wall_jump_tech_count: Option<i32>,
+    pub l_cancel_success_count: Option<i32>,
+    pub l_cancel_fail_count: Option<i32>,
     pub stocks_remaining: i32,
     pub final_percent: Option<f64>,
     /// Path to .slp file - for historical games that don't have a recording
     pub slp_path: Option<String>,
+    /// Whether this player is the local user (see database::identity)
+    pub is_me: bool,
+    /// Version of the stats calculation that produced this row
+    pub stats_version: i32,
+    /// Whether the player is a CPU (from the replay's Game Start)
+    #[serde(default)]
+    pub is_cpu: bool,
 }
 
 // ============================================================================
@@ -106,7 +301,10 @@ pub struct PlayerStatsRow {
 pub fn get_all_recordings(conn: &Connection) -> rusqlite::Result<Vec<RecordingRow>> {
     let mut stmt = conn.prepare(
         "SELECT id, video_path, slp_path, file_size, file_modified_at, 
-                thumbnail_path, start_time, cached_at, needs_reparse
+                thumbnail_path, start_time, cached_at, needs_reparse,
+                dropped_frame_estimate, longest_stall_ms, profile, is_archived, parse_error,
+                replay_id, video_width, video_height, video_duration_secs, has_audio, audio_mean_volume_db,
+                video_offset_ms, source_recording_id
          FROM recordings 
          ORDER BY start_time DESC"
     )?;
@@ -122,6 +320,19 @@ pub fn get_all_recordings(conn: &Connection) -> rusqlite::Result<Vec<RecordingRo
             start_time: row.get(6)?,
             cached_at: row.get(7)?,
             needs_reparse: row.get::<_, i32>(8)? != 0,
+            dropped_frame_estimate: row.get(9)?,
+            longest_stall_ms: row.get(10)?,
+            profile: row.get(11)?,
+            is_archived: row.get::<_, Option<i32>>(12)?.unwrap_or(0) != 0,
+            parse_error: row.get(13)?,
+            replay_id: row.get(14)?,
+            video_width: row.get(15)?,
+            video_height: row.get(16)?,
+            video_duration_secs: row.get(17)?,
+            has_audio: row.get::<_, Option<i32>>(18)?.map(|v| v != 0),
+            audio_mean_volume_db: row.get(19)?,
+            video_offset_ms: row.get(20)?,
+            source_recording_id: row.get(21)?,
         })
     })?;
     
@@ -148,7 +359,12 @@ pub fn get_recordings_paginated(
                 g.player1_id, g.player2_id, g.player1_port, g.player2_port,
                 g.player1_character, g.player2_character, g.player1_color, g.player2_color,
                 g.winner_port, g.loser_port, g.stage, g.game_duration, g.total_frames,
-                g.is_pal, g.played_on, g.created_at, g.slp_path
+                g.is_pal, g.played_on, g.created_at, g.slp_path,
+                r.dropped_frame_estimate, r.longest_stall_ms, r.profile, r.is_archived, r.parse_error,
+                g.replay_version, r.replay_id, r.video_width, r.video_height, r.video_duration_secs,
+                r.has_audio, r.audio_mean_volume_db, g.excluded_reason, r.video_offset_ms,
+                g.total_paused_frames, g.winner_code, g.loser_code, r.source_recording_id, g.my_result, g.is_warmup, g.stats_source,
+                g.completed
          FROM recordings r
          LEFT JOIN game_stats g ON r.id = g.id
          ORDER BY r.start_time DESC
@@ -166,6 +382,19 @@ pub fn get_recordings_paginated(
             start_time: row.get(6)?,
             cached_at: row.get(7)?,
             needs_reparse: row.get::<_, i32>(8)? != 0,
+            dropped_frame_estimate: row.get(26)?,
+            longest_stall_ms: row.get(27)?,
+            profile: row.get(28)?,
+            is_archived: row.get::<_, Option<i32>>(29)?.unwrap_or(0) != 0,
+            parse_error: row.get(30)?,
+            replay_id: row.get(32)?,
+            video_width: row.get(33)?,
+            video_height: row.get(34)?,
+            video_duration_secs: row.get(35)?,
+            has_audio: row.get::<_, Option<i32>>(36)?.map(|v| v != 0),
+            audio_mean_volume_db: row.get(37)?,
+            video_offset_ms: row.get(39)?,
+            source_recording_id: row.get(43)?,
         };
         
         // Check if we have stats (by checking if player1_character is not null)
@@ -190,6 +419,15 @@ pub fn get_recordings_paginated(
                 played_on: row.get(23)?,
                 created_at: row.get(24)?,
                 slp_path: row.get(25)?,
+                replay_version: row.get(31)?,
+                excluded_reason: row.get(38)?,
+                total_paused_frames: row.get(40)?,
+                winner_code: row.get(41)?,
+                loser_code: row.get(42)?,
+                my_result: MyResult::from_column(&row.get::<_, String>(44)?),
+                is_warmup: row.get(45)?,
+                stats_source: StatsSource::from_column(&row.get::<_, Option<String>>(46)?.unwrap_or_default()),
+                completed: row.get::<_, Option<bool>>(47)?.unwrap_or(true),
             })
         } else {
             None
@@ -216,7 +454,7 @@ pub fn get_recordings_paginated(
                     spot_dodge_count, ledgegrab_count, roll_count, grab_count, throw_count,
                     ground_tech_count, wall_tech_count, wall_jump_tech_count,
                     l_cancel_success_count, l_cancel_fail_count, stocks_remaining, final_percent,
-                    slp_path
+                    slp_path, is_me, stats_version, is_cpu
              FROM player_stats 
              WHERE recording_id IN ({})
              ORDER BY recording_id, player_index",
@@ -265,6 +503,9 @@ pub fn get_recordings_paginated(
                 stocks_remaining: row.get(34)?,
                 final_percent: row.get(35)?,
                 slp_path: row.get(36)?,
+                is_me: row.get::<_, i32>(37)? != 0,
+                stats_version: row.get(38)?,
+                is_cpu: row.get::<_, i32>(39)? != 0,
             })
         })?;
         
@@ -287,9 +528,51 @@ pub fn get_recordings_paginated(
 pub fn get_recording_by_video_path(conn: &Connection, video_path: &str) -> rusqlite::Result<Option<RecordingRow>> {
     conn.query_row(
         "SELECT id, video_path, slp_path, file_size, file_modified_at, 
-                thumbnail_path, start_time, cached_at, needs_reparse
+                thumbnail_path, start_time, cached_at, needs_reparse,
+                dropped_frame_estimate, longest_stall_ms, profile, is_archived, parse_error,
+                replay_id, video_width, video_height, video_duration_secs, has_audio, audio_mean_volume_db,
+                video_offset_ms, source_recording_id
          FROM recordings WHERE video_path = ?",
-        params![video_path],
+        params![paths::normalize(video_path)],
+        |row| {
+            Ok(RecordingRow {
+                id: row.get(0)?,
+                video_path: row.get(1)?,
+                slp_path: row.get(2)?,
+                file_size: row.get(3)?,
+                file_modified_at: row.get(4)?,
+                thumbnail_path: row.get(5)?,
+                start_time: row.get(6)?,
+                cached_at: row.get(7)?,
+                needs_reparse: row.get::<_, i32>(8)? != 0,
+                dropped_frame_estimate: row.get(9)?,
+                longest_stall_ms: row.get(10)?,
+                profile: row.get(11)?,
+                is_archived: row.get::<_, Option<i32>>(12)?.unwrap_or(0) != 0,
+                parse_error: row.get(13)?,
+                replay_id: row.get(14)?,
+                video_width: row.get(15)?,
+                video_height: row.get(16)?,
+                video_duration_secs: row.get(17)?,
+                has_audio: row.get::<_, Option<i32>>(18)?.map(|v| v != 0),
+                audio_mean_volume_db: row.get(19)?,
+                video_offset_ms: row.get(20)?,
+                source_recording_id: row.get(21)?,
+            })
+        },
+    ).optional()
+}
+
+/// Get a recording by its id
+pub fn get_recording_by_id(conn: &Connection, id: &str) -> rusqlite::Result<Option<RecordingRow>> {
+    conn.query_row(
+        "SELECT id, video_path, slp_path, file_size, file_modified_at, 
+                thumbnail_path, start_time, cached_at, needs_reparse,
+                dropped_frame_estimate, longest_stall_ms, profile, is_archived, parse_error,
+                replay_id, video_width, video_height, video_duration_secs, has_audio, audio_mean_volume_db,
+                video_offset_ms, source_recording_id
+         FROM recordings WHERE id = ?",
+        params![id],
         |row| {
             Ok(RecordingRow {
                 id: row.get(0)?,
@@ -301,17 +584,33 @@ pub fn get_recording_by_video_path(conn: &Connection, video_path: &str) -> rusql
                 start_time: row.get(6)?,
                 cached_at: row.get(7)?,
                 needs_reparse: row.get::<_, i32>(8)? != 0,
+                dropped_frame_estimate: row.get(9)?,
+                longest_stall_ms: row.get(10)?,
+                profile: row.get(11)?,
+                is_archived: row.get::<_, Option<i32>>(12)?.unwrap_or(0) != 0,
+                parse_error: row.get(13)?,
+                replay_id: row.get(14)?,
+                video_width: row.get(15)?,
+                video_height: row.get(16)?,
+                video_duration_secs: row.get(17)?,
+                has_audio: row.get::<_, Option<i32>>(18)?.map(|v| v != 0),
+                audio_mean_volume_db: row.get(19)?,
+                video_offset_ms: row.get(20)?,
+                source_recording_id: row.get(21)?,
             })
         },
     ).optional()
 }
 
 /// Insert or update a recording
+/// Capture stats and the profile are kept if the new row doesn't have them (e.g. a cache re-sync),
+/// and so are the archived and reparse flags
 pub fn upsert_recording(conn: &Connection, row: &RecordingRow) -> rusqlite::Result<()> {
     conn.execute(
         "INSERT INTO recordings (id, video_path, slp_path, file_size, file_modified_at, 
-                                 thumbnail_path, start_time, cached_at, needs_reparse)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                                 thumbnail_path, start_time, cached_at, needs_reparse,
+                                 dropped_frame_estimate, longest_stall_ms, profile, is_archived, start_time_invalid)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
          ON CONFLICT(id) DO UPDATE SET
             video_path = excluded.video_path,
             slp_path = excluded.slp_path,
@@ -319,36 +618,470 @@ pub fn upsert_recording(conn: &Connection, row: &RecordingRow) -> rusqlite::Resu
             file_modified_at = excluded.file_modified_at,
             thumbnail_path = excluded.thumbnail_path,
             start_time = excluded.start_time,
+            start_time_invalid = excluded.start_time_invalid,
             cached_at = excluded.cached_at,
-            needs_reparse = excluded.needs_reparse",
+            needs_reparse = MAX(excluded.needs_reparse, COALESCE(recordings.needs_reparse, 0)),
+            dropped_frame_estimate = COALESCE(excluded.dropped_frame_estimate, recordings.dropped_frame_estimate),
+            longest_stall_ms = COALESCE(excluded.longest_stall_ms, recordings.longest_stall_ms),
+            profile = COALESCE(excluded.profile, recordings.profile),
+            is_archived = MAX(excluded.is_archived, COALESCE(recordings.is_archived, 0))",
         params![
             row.id,
-            row.video_path,
-            row.slp_path,
+            row.video_path.as_deref().map(paths::normalize),
+            row.slp_path.as_deref().map(paths::normalize),
             row.file_size,
-            row.file_modified_at,
-            row.thumbnail_path,
-            row.start_time,
+            timestamps::for_storage(row.file_modified_at.as_deref()),
+            row.thumbnail_path.as_deref().map(paths::normalize),
+            timestamps::for_storage(row.start_time.as_deref()),
             row.cached_at,
             row.needs_reparse as i32,
+            row.dropped_frame_estimate,
+            row.longest_stall_ms,
+            row.profile,
+            row.is_archived as i32,
+            row.start_time.as_deref().is_some_and(|time| timestamps::parse(time).is_none()) as i32,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Store capture stats, the active settings profile and the measured video
+/// offset for a recording that just finished. The recording usually isn't
+/// indexed yet, so this inserts a placeholder row (no file metadata, so the
+/// next cache sync fills in the rest).
+pub fn set_recording_capture_info(
+    conn: &Connection,
+    id: &str,
+    video_path: &str,
+    dropped_frame_estimate: Option<i64>,
+    longest_stall_ms: Option<i64>,
+    profile: Option<&str>,
+    video_offset_ms: Option<i64>,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO recordings (id, video_path, start_time, cached_at, dropped_frame_estimate, longest_stall_ms, profile,
+                                 video_offset_ms)
+         VALUES (?1, ?2, ?3, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(video_path) DO UPDATE SET
+            dropped_frame_estimate = COALESCE(excluded.dropped_frame_estimate, recordings.dropped_frame_estimate),
+            longest_stall_ms = COALESCE(excluded.longest_stall_ms, recordings.longest_stall_ms),
+            profile = COALESCE(excluded.profile, recordings.profile),
+            video_offset_ms = COALESCE(excluded.video_offset_ms, recordings.video_offset_ms)",
+        params![
+            id,
+            paths::normalize(video_path),
+            timestamps::now(),
+            dropped_frame_estimate,
+            longest_stall_ms,
+            profile,
+            video_offset_ms,
         ],
     )?;
     Ok(())
 }
 
+/// Update a recording's file size and modification time after its video was replaced
+pub fn set_recording_file_info(
+    conn: &Connection,
+    id: &str,
+    file_size: i64,
+    file_modified_at: Option<&str>,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        // The video was rewritten, so what ffprobe found out about it is stale
+        "UPDATE recordings SET file_size = ?2, file_modified_at = COALESCE(?3, file_modified_at),
+                video_width = NULL, video_height = NULL, video_duration_secs = NULL,
+                has_audio = NULL, audio_mean_volume_db = NULL
+         WHERE id = ?1",
+        params![id, file_size, timestamps::for_storage(file_modified_at)],
+    )?;
+    Ok(())
+}
+
+/// Flag a recording whose video was re-encoded to the archive format
+pub fn set_recording_archived(conn: &Connection, id: &str) -> rusqlite::Result<()> {
+    conn.execute("UPDATE recordings SET is_archived = 1 WHERE id = ?", params![id])?;
+    Ok(())
+}
+
+/// Record why a recording's .slp couldn't be parsed, or clear it (None) to retry
+pub fn set_recording_parse_error(conn: &Connection, id: &str, parse_error: Option<&str>) -> rusqlite::Result<()> {
+    conn.execute("UPDATE recordings SET parse_error = ?2 WHERE id = ?1", params![id, parse_error])?;
+    Ok(())
+}
+
+/// Flag a recording with a replay to have its metadata and stats extracted
+/// again, clearing any parse error. Returns whether it has a replay
+pub fn set_needs_reparse(conn: &Connection, id: &str) -> rusqlite::Result<bool> {
+    let changed = conn.execute(
+        "UPDATE recordings SET needs_reparse = 1, parse_error = NULL WHERE id = ? AND slp_path IS NOT NULL",
+        params![id],
+    )?;
+    Ok(changed > 0)
+}
+
+/// Flag games with player stats older than the given stats version to be
+/// reparsed. Returns how many were newly flagged
+pub fn flag_outdated_stats(conn: &Connection, current_version: i32) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE recordings SET needs_reparse = 1
+         WHERE COALESCE(needs_reparse, 0) = 0 AND slp_path IS NOT NULL
+           AND id IN (SELECT recording_id FROM player_stats WHERE stats_version < ?)
+           AND id NOT IN (SELECT id FROM game_stats WHERE stats_source != 'buckwheat')",
+        params![current_version],
+    )
+}
+
+/// Clear a recording's reparse flag once its stats are saved again.
+/// Returns whether it was flagged
+pub fn finish_reparse(conn: &Connection, id: &str) -> rusqlite::Result<bool> {
+    let changed = conn.execute(
+        "UPDATE recordings SET needs_reparse = 0 WHERE id = ? AND needs_reparse = 1",
+        params![id],
+    )?;
+    Ok(changed > 0)
+}
+
+/// A recording's replay and the hash it had when last indexed
+#[derive(Debug, Clone)]
+pub struct ReplayHash {
+    pub recording_id: String,
+    pub slp_path: String,
+    pub slp_hash: Option<String>,
+    pub cached_at: String,
+}
+
+/// Replays linked to recordings, with their stored hashes
+pub fn get_replay_hashes(conn: &Connection) -> rusqlite::Result<Vec<ReplayHash>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, slp_path, slp_hash, cached_at FROM recordings WHERE slp_path IS NOT NULL"
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(ReplayHash {
+            recording_id: row.get(0)?,
+            slp_path: row.get(1)?,
+            slp_hash: row.get(2)?,
+            cached_at: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Store the hash of a recording's replay; a replay whose hash changed
+/// (`changed`) is flagged to be reparsed
+pub fn set_slp_hash(conn: &Connection, id: &str, slp_hash: &str, changed: bool) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE recordings SET slp_hash = ?2, needs_reparse = MAX(COALESCE(needs_reparse, 0), ?3) WHERE id = ?1",
+        params![id, slp_hash, changed as i32],
+    )?;
+    Ok(())
+}
+
+/// Remember the stable id of a recording's replay
+pub fn set_recording_replay_id(conn: &Connection, id: &str, replay_id: Option<&str>) -> rusqlite::Result<()> {
+    conn.execute("UPDATE recordings SET replay_id = ?2 WHERE id = ?1", params![id, replay_id])?;
+    Ok(())
+}
+
+/// Link an edited recording to the one it was edited from
+pub fn set_recording_source(conn: &Connection, id: &str, source_recording_id: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE recordings SET source_recording_id = ?2 WHERE id = ?1",
+        params![id, source_recording_id],
+    )?;
+    Ok(())
+}
+
+/// Store the thumbnail of the recording with this video (made after it was cached)
+pub fn set_recording_thumbnail(conn: &Connection, video_path: &str, thumbnail_path: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE recordings SET thumbnail_path = ?2 WHERE video_path = ?1",
+        params![paths::normalize(video_path), paths::normalize(thumbnail_path)],
+    )?;
+    Ok(())
+}
+
+/// Point a recording at its video's new path (after the file was renamed)
+pub fn set_recording_video_path(conn: &Connection, id: &str, video_path: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE recordings SET video_path = ?2 WHERE id = ?1",
+        params![id, paths::normalize(video_path)],
+    )?;
+    Ok(())
+}
+
+/// Set how late a recording's video starts compared with its replay (None if unknown)
+pub fn set_recording_video_offset(conn: &Connection, id: &str, video_offset_ms: Option<i64>) -> rusqlite::Result<()> {
+    conn.execute("UPDATE recordings SET video_offset_ms = ?2 WHERE id = ?1", params![id, video_offset_ms])?;
+    Ok(())
+}
+
+/// Store what ffprobe found out about a recording's video (None to probe it again)
+pub fn set_recording_video_info(
+    conn: &Connection,
+    id: &str,
+    width: Option<i64>,
+    height: Option<i64>,
+    duration_secs: Option<f64>,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE recordings SET video_width = ?2, video_height = ?3, video_duration_secs = ?4 WHERE id = ?1",
+        params![id, width, height, duration_secs],
+    )?;
+    Ok(())
+}
+
+/// Store whether a recording has sound (None to probe it again)
+pub fn set_recording_audio_info(
+    conn: &Connection,
+    id: &str,
+    has_audio: Option<bool>,
+    mean_volume_db: Option<f64>,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE recordings SET has_audio = ?2, audio_mean_volume_db = ?3 WHERE id = ?1",
+        params![id, has_audio.map(|v| v as i32), mean_volume_db],
+    )?;
+    Ok(())
+}
+
+/// Games in a recording, in order. A single-game recording with nothing stored
+/// is its replay at offset 0
+pub fn get_recording_games(conn: &Connection, id: &str) -> rusqlite::Result<Vec<RecordingGame>> {
+    let mut stmt = conn.prepare(
+        "SELECT game_index, slp_path, offset_secs FROM recording_replays WHERE recording_id = ? ORDER BY game_index",
+    )?;
+    let games = stmt
+        .query_map(params![id], |row| {
+            Ok(RecordingGame { game_index: row.get(0)?, slp_path: row.get(1)?, offset_secs: row.get(2)? })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    if !games.is_empty() {
+        return Ok(games);
+    }
+
+    let slp_path: Option<String> = conn
+        .query_row("SELECT slp_path FROM recordings WHERE id = ?", params![id], |row| row.get(0))
+        .optional()?
+        .flatten();
+    Ok(slp_path
+        .map(|slp_path| RecordingGame { game_index: 0, slp_path, offset_secs: 0.0 })
+        .into_iter()
+        .collect())
+}
+
+/// Store where the games of a recording start (a set, or a game recorded from
+/// character select), replacing any already stored
+pub fn set_recording_games(conn: &Connection, id: &str, games: &[RecordingGame]) -> rusqlite::Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM recording_replays WHERE recording_id = ?", params![id])?;
+    for game in games {
+        tx.execute(
+            "INSERT INTO recording_replays (recording_id, game_index, slp_path, offset_secs) VALUES (?1, ?2, ?3, ?4)",
+            params![id, game.game_index, paths::normalize(&game.slp_path), game.offset_secs],
+        )?;
+    }
+    tx.commit()
+}
+
+/// Whether a recording with this .slp is known to be unparseable
+pub fn has_parse_error_for_slp(conn: &Connection, slp_path: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT 1 FROM recordings WHERE slp_path = ? AND parse_error IS NOT NULL LIMIT 1",
+        params![paths::normalize(slp_path)],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|row| row.is_some())
+}
+
+/// Change a recording's id, moving its stats, combos, port override and games along
+pub fn rekey_recording(conn: &Connection, old_id: &str, new_id: &str) -> rusqlite::Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("UPDATE recordings SET id = ?2 WHERE id = ?1", params![old_id, new_id])?;
+    tx.execute("UPDATE game_stats SET id = ?2 WHERE id = ?1", params![old_id, new_id])?;
+    tx.execute("UPDATE recordings SET source_recording_id = ?2 WHERE source_recording_id = ?1", params![old_id, new_id])?;
+    for table in ["player_stats", "combos", "my_port_overrides", "recording_replays"] {
+        tx.execute(
+            &format!("UPDATE {} SET recording_id = ?2 WHERE recording_id = ?1", table),
+            params![old_id, new_id],
+        )?;
+    }
+    tx.commit()
+}
+
 /// Delete a recording by ID
 pub fn delete_recording(conn: &Connection, id: &str) -> rusqlite::Result<()> {
     conn.execute("DELETE FROM recordings WHERE id = ?", params![id])?;
+    conn.execute("DELETE FROM recording_replays WHERE recording_id = ?", params![id])?;
+    Ok(())
+}
+
+/// Normalize paths stored before they were (see crate::paths), merging
+/// recordings that turn out to be one file indexed under two spellings: the
+/// one with stats is kept, otherwise the one already normalized, and the
+/// other goes with its stats. Returns how many recordings changed
+pub fn normalize_stored_paths(conn: &Connection) -> rusqlite::Result<usize> {
+    type StoredPaths = (String, Option<String>, Option<String>, Option<String>);
+    let rows: Vec<StoredPaths> = {
+        let mut stmt = conn.prepare("SELECT id, video_path, slp_path, thumbnail_path FROM recordings")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    let tx = conn.unchecked_transaction()?;
+    let has_stats = |id: &str| {
+        tx.query_row("SELECT 1 FROM game_stats WHERE id = ?", params![id], |_| Ok(()))
+            .optional()
+            .map(|row| row.is_some())
+    };
+    let mut changed = 0;
+    for (id, video_path, slp_path, thumbnail_path) in rows {
+        let normalized = |path: &Option<String>| path.as_deref().map(paths::normalize);
+        let (video, slp, thumbnail) = (normalized(&video_path), normalized(&slp_path), normalized(&thumbnail_path));
+        if (&video, &slp, &thumbnail) == (&video_path, &slp_path, &thumbnail_path) {
+            continue;
+        }
+        changed += 1;
+
+        let twin: Option<String> = match &video {
+            Some(video) if Some(video) != video_path.as_ref() => tx
+                .query_row(
+                    "SELECT id FROM recordings WHERE video_path = ?1 AND id != ?2",
+                    params![video, id],
+                    |row| row.get(0),
+                )
+                .optional()?,
+            _ => None,
+        };
+        if let Some(twin) = twin {
+            let keep_this = has_stats(&id)? && !has_stats(&twin)?;
+            if !keep_this {
+                delete_recording_and_stats(&tx, &id)?;
+                continue;
+            }
+            delete_recording_and_stats(&tx, &twin)?;
+        }
+        tx.execute(
+            "UPDATE recordings SET video_path = ?2, slp_path = ?3, thumbnail_path = ?4 WHERE id = ?1",
+            params![id, video, slp, thumbnail],
+        )?;
+    }
+
+    // The .slp paths kept with games and stats; a game_stats row whose
+    // normalized path is taken is a leftover copy and keeps its old one
+    let slp_paths: Vec<String> = {
+        let mut stmt = tx.prepare(
+            "SELECT slp_path FROM recording_replays
+             UNION SELECT slp_path FROM game_stats WHERE slp_path IS NOT NULL
+             UNION SELECT slp_path FROM player_stats WHERE slp_path IS NOT NULL",
+        )?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    for slp_path in slp_paths {
+        let normalized = paths::normalize(&slp_path);
+        if normalized == slp_path {
+            continue;
+        }
+        for table in ["recording_replays", "game_stats", "player_stats"] {
+            tx.execute(
+                &format!("UPDATE OR IGNORE {} SET slp_path = ?2 WHERE slp_path = ?1", table),
+                params![slp_path, normalized],
+            )?;
+        }
+    }
+    tx.commit()?;
+    Ok(changed)
+}
+
+/// Rewrite the game and recording times stored before they were normalized
+/// (see crate::timestamps). A start_time that can't be read is left as it is
+/// and flagged (start_time_invalid). Returns (times rewritten, start times flagged)
+pub fn normalize_stored_times(conn: &Connection) -> rusqlite::Result<(usize, usize)> {
+    let tx = conn.unchecked_transaction()?;
+    let mut changed = 0;
+    let mut invalid = 0;
+    for (table, column) in [("recordings", "start_time"), ("recordings", "file_modified_at"), ("game_stats", "created_at")] {
+        let stored: Vec<(String, String)> = {
+            let mut stmt = tx.prepare(&format!("SELECT id, {0} FROM {1} WHERE {0} IS NOT NULL", column, table))?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        for (id, value) in stored {
+            match timestamps::normalize(&value) {
+                Some(normalized) if normalized == value => {}
+                Some(normalized) => {
+                    tx.execute(&format!("UPDATE {} SET {} = ?2 WHERE id = ?1", table, column), params![id, normalized])?;
+                    changed += 1;
+                }
+                None if column == "start_time" => {
+                    tx.execute("UPDATE recordings SET start_time_invalid = 1 WHERE id = ?", params![id])?;
+                    invalid += 1;
+                }
+                None => log::warn!("Unreadable {}.{} of {}: {:?}", table, column, id, value),
+            }
+        }
+    }
+    tx.commit()?;
+    Ok((changed, invalid))
+}
+
+/// Delete a recording and everything stored for it
+fn delete_recording_and_stats(conn: &Connection, id: &str) -> rusqlite::Result<()> {
+    delete_recording(conn, id)?;
+    conn.execute("DELETE FROM game_stats WHERE id = ?", params![id])?;
+    for table in ["player_stats", "combos", "my_port_overrides"] {
+        conn.execute(&format!("DELETE FROM {} WHERE recording_id = ?", table), params![id])?;
+    }
     Ok(())
 }
 
 /// Get all cached video paths (for sync comparison)
 pub fn get_cached_video_paths(conn: &Connection) -> rusqlite::Result<Vec<String>> {
-    let mut stmt = conn.prepare("SELECT video_path FROM recordings")?;
+    let mut stmt = conn.prepare("SELECT video_path FROM recordings WHERE video_path IS NOT NULL")?;
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Every .slp linked to a recording, video or replay-only (for indexing replays)
+pub fn get_linked_slp_paths(conn: &Connection) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT slp_path FROM recordings WHERE slp_path IS NOT NULL
+         UNION SELECT slp_path FROM recording_replays",
+    )?;
     let rows = stmt.query_map([], |row| row.get(0))?;
     rows.collect()
 }
 
+/// Replay-only entries, as (id, slp_path)
+pub fn get_replay_only_recordings(conn: &Connection) -> rusqlite::Result<Vec<(String, String)>> {
+    let mut stmt =
+        conn.prepare("SELECT id, slp_path FROM recordings WHERE video_path IS NULL AND slp_path IS NOT NULL")?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
+
+/// The recording (with or without a video) of a .slp, if it has one
+pub fn get_recording_id_by_slp_path(conn: &Connection, slp_path: &str) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT id FROM recordings WHERE slp_path = ?",
+        params![paths::normalize(slp_path)],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// The replay-only entry of a .slp, if it has one (a video recorded later takes it over)
+pub fn get_replay_only_recording_id(conn: &Connection, slp_path: &str) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT id FROM recordings WHERE video_path IS NULL AND slp_path = ?",
+        params![paths::normalize(slp_path)],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
 // ============================================================================
 // GAME STATS OPERATIONS
 // ============================================================================
@@ -359,8 +1092,9 @@ pub fn upsert_game_stats(conn: &Connection, stats: &GameStatsRow) -> rusqlite::R
         "INSERT INTO game_stats (id, player1_id, player2_id, player1_port, player2_port,
                                   player1_character, player2_character, player1_color, player2_color,
                                   winner_port, loser_port, stage, game_duration, total_frames,
-                                  is_pal, played_on, created_at, slp_path)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)
+                                  is_pal, played_on, created_at, slp_path, replay_version, total_paused_frames,
+                                  winner_code, loser_code, stats_source, completed)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24)
          ON CONFLICT(id) DO UPDATE SET
             player1_id = excluded.player1_id,
             player2_id = excluded.player2_id,
@@ -378,7 +1112,16 @@ pub fn upsert_game_stats(conn: &Connection, stats: &GameStatsRow) -> rusqlite::R
             is_pal = excluded.is_pal,
             played_on = excluded.played_on,
             created_at = excluded.created_at,
-            slp_path = excluded.slp_path",
+            slp_path = excluded.slp_path,
+            replay_version = excluded.replay_version,
+            total_paused_frames = excluded.total_paused_frames,
+            winner_code = excluded.winner_code,
+            loser_code = excluded.loser_code,
+            stats_source = excluded.stats_source,
+            completed = excluded.completed,
+            -- Full stats replace a provisional row, which was only excluded for being one
+            excluded_reason = CASE WHEN game_stats.is_provisional = 1 THEN NULL ELSE game_stats.excluded_reason END,
+            is_provisional = 0",
         params![
             stats.id,
             stats.player1_id,
@@ -396,23 +1139,231 @@ pub fn upsert_game_stats(conn: &Connection, stats: &GameStatsRow) -> rusqlite::R
             stats.total_frames,
             stats.is_pal.map(|b| b as i32),
             stats.played_on,
-            stats.created_at,
-            stats.slp_path,
+            timestamps::for_storage(stats.created_at.as_deref()),
+            stats.slp_path.as_deref().map(paths::normalize),
+            stats.replay_version,
+            stats.total_paused_frames,
+            stats.winner_code,
+            stats.loser_code,
+            stats.stats_source.as_str(),
+            stats.completed,
         ],
     )?;
     Ok(())
 }
 
+/// Save what a new game's Game Start tells (players, ports, stage) before its
+/// stats are computed, excluded from aggregates as 'provisional'. Nothing is
+/// written if the game already has a row. Returns whether one was added
+pub fn insert_provisional_game_stats(conn: &Connection, stats: &GameStatsRow) -> rusqlite::Result<bool> {
+    let inserted = conn.execute(
+        "INSERT INTO game_stats (id, player1_id, player2_id, player1_port, player2_port,
+                                  player1_character, player2_character, stage, created_at, slp_path,
+                                  replay_version, excluded_reason, is_provisional)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, 'provisional', 1)
+         ON CONFLICT DO NOTHING",
+        params![
+            stats.id,
+            stats.player1_id,
+            stats.player2_id,
+            stats.player1_port,
+            stats.player2_port,
+            stats.player1_character,
+            stats.player2_character,
+            stats.stage,
+            timestamps::for_storage(stats.created_at.as_deref()),
+            stats.slp_path.as_deref().map(paths::normalize),
+            stats.replay_version,
+        ],
+    )?;
+    Ok(inserted > 0)
+}
+
+/// A recording with its game and player stats
+pub fn get_recording_with_stats(conn: &Connection, id: &str) -> rusqlite::Result<Option<RecordingWithStats>> {
+    let Some(recording) = get_recording_by_id(conn, id)? else {
+        return Ok(None);
+    };
+    Ok(Some(RecordingWithStats {
+        stats: get_game_stats(conn, id)?,
+        player_stats: get_player_stats_by_recording(conn, id)?,
+        recording,
+    }))
+}
+
+/// Get the game stats for a recording
+pub fn get_game_stats(conn: &Connection, recording_id: &str) -> rusqlite::Result<Option<GameStatsRow>> {
+    conn.query_row(
+        "SELECT id, player1_id, player2_id, player1_port, player2_port,
+                player1_character, player2_character, player1_color, player2_color,
+                winner_port, loser_port, stage, game_duration, total_frames,
+                is_pal, played_on, created_at, slp_path, replay_version, excluded_reason,
+                total_paused_frames, winner_code, loser_code, my_result, is_warmup, stats_source, completed
+         FROM game_stats WHERE id = ?",
+        params![recording_id],
+        |row| {
+            Ok(GameStatsRow {
+                id: row.get(0)?,
+                player1_id: row.get(1)?,
+                player2_id: row.get(2)?,
+                player1_port: row.get(3)?,
+                player2_port: row.get(4)?,
+                player1_character: row.get(5)?,
+                player2_character: row.get(6)?,
+                player1_color: row.get(7)?,
+                player2_color: row.get(8)?,
+                winner_port: row.get(9)?,
+                loser_port: row.get(10)?,
+                stage: row.get(11)?,
+                game_duration: row.get(12)?,
+                total_frames: row.get(13)?,
+                is_pal: row.get::<_, Option<i32>>(14)?.map(|v| v != 0),
+                played_on: row.get(15)?,
+                created_at: row.get(16)?,
+                slp_path: row.get(17)?,
+                replay_version: row.get(18)?,
+                excluded_reason: row.get(19)?,
+                total_paused_frames: row.get(20)?,
+                winner_code: row.get(21)?,
+                loser_code: row.get(22)?,
+                my_result: MyResult::from_column(&row.get::<_, String>(23)?),
+                is_warmup: row.get(24)?,
+                stats_source: StatsSource::from_column(&row.get::<_, String>(25)?),
+                completed: row.get(26)?,
+            })
+        },
+    ).optional()
+}
+
+/// A game's (winner_port, loser_port, winner_code, loser_code)
+type GameResultColumns = (Option<i32>, Option<i32>, Option<String>, Option<String>);
+/// A player's (port, connect_code, stocks_remaining)
+type ResultPlayer = (i32, Option<String>, i32);
+
+/// Work out each two-player game's winner and loser again from its
+/// player_stats rows, the way the stats pass does (more stocks left wins, a
+/// tie has no winner), and store their ports and connect codes. Fixes games
+/// saved before results were kept by connect code. Returns how many changed
+pub fn repair_game_results(conn: &Connection) -> rusqlite::Result<usize> {
+    let mut stmt = conn.prepare(
+        "SELECT p.recording_id, p.port, p.connect_code, p.stocks_remaining,
+                g.winner_port, g.loser_port, g.winner_code, g.loser_code
+         FROM player_stats p
+         JOIN game_stats g ON g.id = p.recording_id
+         -- Imported games don't have stocks left; their winner comes with them
+         WHERE g.stats_source = 'buckwheat'
+         ORDER BY p.recording_id, p.player_index",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                (row.get::<_, i32>(1)?, row.get::<_, Option<String>>(2)?, row.get::<_, i32>(3)?),
+                (row.get(4)?, row.get(5)?, row.get(6)?, row.get(7)?),
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<(String, ResultPlayer, GameResultColumns)>>>()?;
+    drop(stmt);
+
+    // Rows come grouped by game
+    let mut games: Vec<(String, GameResultColumns, Vec<ResultPlayer>)> = Vec::new();
+    for (id, player, stored) in rows {
+        match games.last_mut() {
+            Some((last_id, _, players)) if *last_id == id => players.push(player),
+            _ => games.push((id, stored, vec![player])),
+        }
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    let mut repaired = 0;
+    for (id, stored, players) in games {
+        let derived: GameResultColumns = match players.as_slice() {
+            [a, b] if a.2 != b.2 => {
+                let (winner, loser) = if a.2 > b.2 { (a, b) } else { (b, a) };
+                (Some(winner.0), Some(loser.0), winner.1.clone(), loser.1.clone())
+            }
+            [_, _] => (None, None, None, None),
+            // Not a singles game; the stats pass doesn't pick a winner either
+            _ => continue,
+        };
+        if derived != stored {
+            tx.execute(
+                "UPDATE game_stats SET winner_port = ?2, loser_port = ?3, winner_code = ?4, loser_code = ?5
+                 WHERE id = ?1",
+                params![id, derived.0, derived.1, derived.2, derived.3],
+            )?;
+            refresh_my_result(&tx, &id)?;
+            repaired += 1;
+        }
+    }
+    tx.commit()?;
+    Ok(repaired)
+}
+
 /// Check if a game_stats entry exists for the given slp_path
+/// (a provisional one doesn't count: the game still has to be parsed)
 pub fn game_stats_exists_by_slp_path(conn: &Connection, slp_path: &str) -> rusqlite::Result<bool> {
     let count: i32 = conn.query_row(
-        "SELECT COUNT(*) FROM game_stats WHERE slp_path = ?",
-        params![slp_path],
+        "SELECT COUNT(*) FROM game_stats WHERE slp_path = ? AND is_provisional = 0",
+        params![paths::normalize(slp_path)],
         |row| row.get(0),
     )?;
     Ok(count > 0)
 }
 
+/// Where the stats of the game of `slp_path` came from, if it has any
+pub fn get_stats_source_by_slp_path(conn: &Connection, slp_path: &str) -> rusqlite::Result<Option<StatsSource>> {
+    conn.query_row(
+        "SELECT stats_source FROM game_stats WHERE slp_path = ?",
+        params![paths::normalize(slp_path)],
+        |row| Ok(StatsSource::from_column(&row.get::<_, String>(0)?)),
+    )
+    .optional()
+}
+
+/// Which games are left out of aggregates (from minGameSecondsForStats,
+/// recordCpuMatches and countUnfinishedGames)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StatsExclusion {
+    /// Games shorter than this many frames are "short"
+    pub min_frames: i32,
+    /// Games with a CPU and at most one human are "cpu"
+    pub exclude_cpu: bool,
+    /// Games whose replay never reached Game End are "unfinished"
+    pub exclude_unfinished: bool,
+}
+
+/// Work out excluded_reason again for one game, or every game, from what's
+/// stored. Nothing is deleted, so loosening the settings brings games back.
+/// Returns how many of those games are excluded
+pub fn update_excluded_reasons(
+    conn: &Connection,
+    exclusion: &StatsExclusion,
+    recording_id: Option<&str>,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE game_stats SET excluded_reason = CASE
+            WHEN is_provisional = 1 THEN 'provisional'
+            WHEN ?2
+                AND EXISTS (SELECT 1 FROM player_stats p WHERE p.recording_id = game_stats.id AND p.is_cpu = 1)
+                AND (SELECT COUNT(*) FROM player_stats p WHERE p.recording_id = game_stats.id AND p.is_cpu = 0) <= 1
+                THEN 'cpu'
+            WHEN ?4 AND completed = 0 THEN 'unfinished'
+            -- Time spent paused doesn't make a game longer
+            WHEN total_frames - COALESCE(total_paused_frames, 0) < ?1 THEN 'short'
+            ELSE NULL
+         END
+         WHERE ?3 IS NULL OR id = ?3",
+        params![exclusion.min_frames, exclusion.exclude_cpu, recording_id, exclusion.exclude_unfinished],
+    )?;
+    conn.query_row(
+        "SELECT COUNT(*) FROM game_stats WHERE excluded_reason IS NOT NULL AND (?1 IS NULL OR id = ?1)",
+        params![recording_id],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count as usize)
+}
+
 // ============================================================================
 // PLAYER STATS OPERATIONS
 // ============================================================================
@@ -427,15 +1378,18 @@ pub fn upsert_player_stats(conn: &Connection, stats: &PlayerStatsRow) -> rusqlit
             inputs_total, inputs_per_minute, avg_kill_percent,
             wavedash_count, waveland_count, air_dodge_count, dash_dance_count, spot_dodge_count, ledgegrab_count,
             roll_count, grab_count, throw_count, ground_tech_count, wall_tech_count, wall_jump_tech_count,
-            l_cancel_success_count, l_cancel_fail_count, stocks_remaining, final_percent, slp_path
+            l_cancel_success_count, l_cancel_fail_count, stocks_remaining, final_percent, slp_path, is_me, stats_version,
+            is_cpu, played_character_id
         ) VALUES (
             ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16,
-            ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32, ?33, ?34, ?35, ?36
+            ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32, ?33, ?34, ?35, ?36, ?37, ?38,
+            ?39, ?5
         )
         ON CONFLICT(recording_id, player_index) DO UPDATE SET
             connect_code = excluded.connect_code,
             display_name = excluded.display_name,
             character_id = excluded.character_id,
+            played_character_id = excluded.character_id,
             character_color = excluded.character_color,
             port = excluded.port,
             total_damage = excluded.total_damage,
@@ -466,7 +1420,10 @@ pub fn upsert_player_stats(conn: &Connection, stats: &PlayerStatsRow) -> rusqlit
             l_cancel_fail_count = excluded.l_cancel_fail_count,
             stocks_remaining = excluded.stocks_remaining,
             final_percent = excluded.final_percent,
-            slp_path = excluded.slp_path",
+            slp_path = excluded.slp_path,
+            is_me = excluded.is_me,
+            stats_version = excluded.stats_version,
+            is_cpu = excluded.is_cpu",
         params![
             stats.recording_id,
             stats.player_index,
@@ -503,12 +1460,30 @@ pub fn upsert_player_stats(conn: &Connection, stats: &PlayerStatsRow) -> rusqlit
             stats.l_cancel_fail_count,
             stats.stocks_remaining,
             stats.final_percent,
-            stats.slp_path,
+            stats.slp_path.as_deref().map(paths::normalize),
+            stats.is_me as i32,
+            stats.stats_version,
+            stats.is_cpu as i32,
         ],
     )?;
     Ok(())
 }
 
+/// Set the character a player played most of a game as, when it isn't the
+/// one they picked (upsert_player_stats assumes it is)
+pub fn set_played_character(
+    conn: &Connection,
+    recording_id: &str,
+    player_index: i32,
+    character_id: i32,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE player_stats SET played_character_id = ?3 WHERE recording_id = ?1 AND player_index = ?2",
+        params![recording_id, player_index, character_id],
+    )?;
+    Ok(())
+}
+
 /// Get player stats for a recording
 pub fn get_player_stats_by_recording(conn: &Connection, recording_id: &str) -> rusqlite::Result<Vec<PlayerStatsRow>> {
     let mut stmt = conn.prepare(
@@ -518,7 +1493,8 @@ pub fn get_player_stats_by_recording(conn: &Connection, recording_id: &str) -> r
                 inputs_total, inputs_per_minute, avg_kill_percent,
                 wavedash_count, waveland_count, air_dodge_count, dash_dance_count, spot_dodge_count, ledgegrab_count,
                 roll_count, grab_count, throw_count, ground_tech_count, wall_tech_count, wall_jump_tech_count,
-                l_cancel_success_count, l_cancel_fail_count, stocks_remaining, final_percent, slp_path
+                l_cancel_success_count, l_cancel_fail_count, stocks_remaining, final_percent, slp_path, is_me, stats_version,
+                is_cpu
          FROM player_stats WHERE recording_id = ? ORDER BY player_index"
     )?;
     
@@ -561,6 +1537,53 @@ pub fn get_player_stats_by_recording(conn: &Connection, recording_id: &str) -> r
             stocks_remaining: row.get(34)?,
             final_percent: row.get(35)?,
             slp_path: row.get(36)?,
+            is_me: row.get::<_, i32>(37)? != 0,
+            stats_version: row.get(38)?,
+            is_cpu: row.get::<_, i32>(39)? != 0,
+        })
+    })?;
+    
+    rows.collect()
+}
+
+/// A game whose player stats were computed by an older stats version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutdatedStatsGame {
+    pub recording_id: String,
+    pub slp_path: Option<String>,
+    /// Oldest stats version among the game's player rows
+    pub stats_version: i32,
+}
+
+/// Count games with player stats older than the given stats version
+pub fn count_outdated_stats(conn: &Connection, current_version: i32) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "SELECT COUNT(DISTINCT p.recording_id)
+         FROM player_stats p
+         LEFT JOIN game_stats g ON p.recording_id = g.id
+         WHERE p.stats_version < ? AND COALESCE(g.stats_source, 'buckwheat') = 'buckwheat'",
+        params![current_version],
+        |row| row.get(0),
+    )
+}
+
+/// Get games with player stats older than the given stats version, so they can be recomputed
+pub fn get_outdated_stats_games(conn: &Connection, current_version: i32) -> rusqlite::Result<Vec<OutdatedStatsGame>> {
+    let mut stmt = conn.prepare(
+        "SELECT p.recording_id, COALESCE(g.slp_path, MAX(p.slp_path)), MIN(p.stats_version)
+         FROM player_stats p
+         LEFT JOIN game_stats g ON p.recording_id = g.id
+         WHERE p.stats_version < ? AND COALESCE(g.stats_source, 'buckwheat') = 'buckwheat'
+         GROUP BY p.recording_id
+         ORDER BY MIN(p.stats_version), p.recording_id"
+    )?;
+    
+    let rows = stmt.query_map(params![current_version], |row| {
+        Ok(OutdatedStatsGame {
+            recording_id: row.get(0)?,
+            slp_path: row.get(1)?,
+            stats_version: row.get(2)?,
         })
     })?;
     
@@ -571,8 +1594,38 @@ pub fn get_player_stats_by_recording(conn: &Connection, recording_id: &str) -> r
 // AGGREGATED STATS OPERATIONS
 // ============================================================================
 
+/// Which player rows an aggregate query is about
+#[derive(Debug, Clone, Copy)]
+pub enum PlayerScope<'a> {
+    /// A specific connect code, with the codes merged into it (or it into)
+    Code(&'a str),
+    /// Rows flagged as the local user (union of myConnectCodes)
+    Me,
+    /// Every player in the database
+    All,
+}
+
+impl PlayerScope<'_> {
+    /// SQL condition on the `p` (player_stats) alias, plus its bound parameter if any.
+    /// `param_idx` is the numbered placeholder to use for the parameter.
+    pub(super) fn condition(&self, param_idx: usize) -> (String, Option<String>) {
+        match self {
+            PlayerScope::Code(code) => (
+                format!(
+                    "{} = {}",
+                    super::players::canonical_code_sql("p.connect_code"),
+                    super::players::canonical_code_sql(&format!("?{}", param_idx))
+                ),
+                Some(code.to_string()),
+            ),
+            PlayerScope::Me => ("p.is_me = 1".to_string(), None),
+            PlayerScope::All => ("1 = 1".to_string(), None),
+        }
+    }
+}
+
 /// Filter options for aggregated stats
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct StatsFilter {
     /// Filter by opponent character ID (what you played AGAINST)
@@ -581,14 +1634,43 @@ pub struct StatsFilter {
     pub player_character_id: Option<i32>,
     /// Filter by stage ID
     pub stage_id: Option<i32>,
-    /// Filter by start time (ISO8601 format, games after this time)
+    /// Filter by start time (ISO8601 format, games after this time; a bare
+    /// date or a time without an offset is in the client's time zone)
     pub start_time: Option<String>,
-    /// Filter by end time (ISO8601 format, games before this time)
+    /// Filter by end time (ISO8601 format, games before this time; a bare
+    /// date includes that whole day)
     pub end_time: Option<String>,
+    /// The client's offset from UTC in minutes (east positive), for bare dates
+    /// and daily trends. None for UTC
+    #[serde(default)]
+    pub utc_offset_minutes: Option<i32>,
+    /// Exclude rows computed by a stats version older than this
+    /// (keeps numbers consistent while outdated rows are being recomputed)
+    pub min_stats_version: Option<i32>,
+    /// Count games left out of stats (CPU matches, short games) too
+    #[serde(default)]
+    pub include_excluded: bool,
+    /// Leave out the first games of each play session (see recompute_warmups)
+    #[serde(default)]
+    pub exclude_warmups: bool,
+    /// Only count games whose stats came from this tool
+    #[serde(default)]
+    pub stats_source: Option<StatsSource>,
+}
+
+impl StatsFilter {
+    /// start_time and end_time as stored times (see timestamps::filter_bound).
+    /// A bound that isn't a time is compared as it is
+    pub fn time_range(&self) -> (Option<String>, Option<String>) {
+        let bound = |value: &Option<String>, end: bool| {
+            value.as_deref().map(|v| timestamps::filter_bound(v, self.utc_offset_minutes, end).unwrap_or_else(|| v.to_string()))
+        };
+        (bound(&self.start_time, false), bound(&self.end_time, true))
+    }
 }
 
 /// Aggregated stats for a player
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AggregatedPlayerStats {
     pub total_games: i64,
@@ -603,7 +1685,7 @@ pub struct AggregatedPlayerStats {
     pub stage_stats: Vec<StageWinRate>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CharacterWinRate {
     pub character_id: i32,
@@ -611,7 +1693,7 @@ pub struct CharacterWinRate {
     pub wins: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct StageWinRate {
     pub stage_id: i32,
@@ -619,58 +1701,52 @@ pub struct StageWinRate {
     pub wins: i64,
 }
 
-/// Get aggregated stats for a specific connect code with optional filters
+/// Get aggregated stats for a player scope (a connect code, or "me") with optional filters
 pub fn get_aggregated_player_stats(
-    conn: &Connection, 
-    connect_code: &str,
+    conn: &Connection,
+    scope: PlayerScope<'_>,
     filter: Option<StatsFilter>,
 ) -> rusqlite::Result<AggregatedPlayerStats> {
     let filter = filter.unwrap_or_default();
-    
-    // Debug: count how many player_stats exist for this connect code
-    let total_player_stats: i32 = conn.query_row(
-        "SELECT COUNT(*) FROM player_stats WHERE connect_code = ?",
-        [connect_code],
-        |row| row.get(0),
-    ).unwrap_or(0);
-    
-    let total_game_stats: i32 = conn.query_row(
-        "SELECT COUNT(*) FROM game_stats",
-        [],
-        |row| row.get(0),
-    ).unwrap_or(0);
-    
-    let joined_count: i32 = conn.query_row(
-        "SELECT COUNT(*) FROM player_stats p JOIN game_stats g ON p.recording_id = g.id WHERE p.connect_code = ?",
-        [connect_code],
-        |row| row.get(0),
-    ).unwrap_or(0);
-    
-    log::info!("[TotalStats] connect_code={}, player_stats={}, game_stats={}, joined={}", 
-        connect_code, total_player_stats, total_game_stats, joined_count);
-    
-    // Build dynamic WHERE clause for filters
-    let mut where_clauses = vec!["p.connect_code = ?1".to_string()];
-    let mut param_idx = 2;
-    
-    // Build params vector - start with connect_code
-    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(connect_code.to_string())];
-    
+
+    // Build dynamic WHERE clause for filters - start with the player scope
+    let (scope_clause, scope_param) = scope.condition(1);
+    let mut where_clauses = vec![scope_clause];
+    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    if let Some(code) = scope_param {
+        params_vec.push(Box::new(code));
+    }
+    let mut param_idx = params_vec.len() + 1;
+
+    if !filter.include_excluded {
+        where_clauses.push("g.excluded_reason IS NULL".to_string());
+    }
+    if filter.exclude_warmups {
+        where_clauses.push("g.is_warmup = 0".to_string());
+    }
+
+    if let Some(source) = filter.stats_source {
+        where_clauses.push(format!("g.stats_source = ?{}", param_idx));
+        params_vec.push(Box::new(source.as_str()));
+        param_idx += 1;
+    }
+
     if let Some(stage) = filter.stage_id {
         where_clauses.push(format!("g.stage = ?{}", param_idx));
         params_vec.push(Box::new(stage));
         param_idx += 1;
     }
     
-    if let Some(start) = &filter.start_time {
+    let (start_time, end_time) = filter.time_range();
+    if let Some(start) = start_time {
         where_clauses.push(format!("g.created_at >= ?{}", param_idx));
-        params_vec.push(Box::new(start.clone()));
+        params_vec.push(Box::new(start));
         param_idx += 1;
     }
     
-    if let Some(end) = &filter.end_time {
+    if let Some(end) = end_time {
         where_clauses.push(format!("g.created_at <= ?{}", param_idx));
-        params_vec.push(Box::new(end.clone()));
+        params_vec.push(Box::new(end));
         param_idx += 1;
     }
     
@@ -680,6 +1756,12 @@ pub fn get_aggregated_player_stats(
         param_idx += 1;
     }
     
+    if let Some(min_version) = filter.min_stats_version {
+        where_clauses.push(format!("p.stats_version >= ?{}", param_idx));
+        params_vec.push(Box::new(min_version));
+        param_idx += 1;
+    }
+    
     // Opponent character filter requires join with opponent player_stats
     let opponent_join = if filter.opponent_character_id.is_some() {
         "JOIN player_stats opp_filter ON p.recording_id = opp_filter.recording_id AND opp_filter.player_index != p.player_index"
@@ -696,16 +1778,12 @@ pub fn get_aggregated_player_stats(
     let where_clause = where_clauses.join(" AND ");
     
     // 1. Overall stats
-    // Winner is determined by matching connect code to the winning player's ID in game_stats
-    // If winner_port=1 and player1_id=connect_code, player won. Same for port 2.
+    // Wins go by connect code, falling back to port for rows without one
+    // (e.g. is_me rows from local games); see WON_SQL
     let overall_query = format!(
         "SELECT 
             COUNT(*) as total_games,
-            SUM(CASE 
-                WHEN (g.winner_port = 1 AND g.player1_id = p.connect_code) THEN 1
-                WHEN (g.winner_port = 2 AND g.player2_id = p.connect_code) THEN 1
-                ELSE 0 
-            END) as total_wins,
+            SUM(CASE WHEN {won} THEN 1 ELSE 0 END) as total_wins,
             AVG(
                 CAST(p.l_cancel_success_count AS FLOAT) / 
                 NULLIF(p.l_cancel_success_count + p.l_cancel_fail_count, 0)
@@ -719,7 +1797,7 @@ pub fn get_aggregated_player_stats(
          JOIN game_stats g ON p.recording_id = g.id
          {}
          WHERE {}",
-        opponent_join, where_clause
+        opponent_join, where_clause, won = WON_SQL
     );
     
     log::debug!("[TotalStats] Query: {}", overall_query);
@@ -755,24 +1833,19 @@ pub fn get_aggregated_player_stats(
     )?;
 
     // 2. Character stats (opponents faced) - with filters applied
-    // Winner determined by matching connect code to winning player's ID
     // Note: This query already has 'opp' joined, so replace opp_filter reference with opp
     let character_where = where_clause.replace("opp_filter.character_id", "opp.character_id");
     let character_query = format!(
         "SELECT 
             opp.character_id,
             COUNT(*) as games,
-            SUM(CASE 
-                WHEN (g.winner_port = 1 AND g.player1_id = p.connect_code) THEN 1
-                WHEN (g.winner_port = 2 AND g.player2_id = p.connect_code) THEN 1
-                ELSE 0 
-            END) as wins
+            SUM(CASE WHEN {won} THEN 1 ELSE 0 END) as wins
          FROM player_stats p
          JOIN game_stats g ON p.recording_id = g.id
          JOIN player_stats opp ON p.recording_id = opp.recording_id AND opp.player_index != p.player_index
          WHERE {}
          GROUP BY opp.character_id",
-        character_where
+        character_where, won = WON_SQL
     );
     
     let mut stmt = conn.prepare(&character_query)?;
@@ -787,22 +1860,17 @@ pub fn get_aggregated_player_stats(
     })?.collect::<Result<Vec<_>, _>>()?;
 
     // 3. Stage stats - with filters applied
-    // Winner determined by matching connect code to winning player's ID
     let stage_query = format!(
         "SELECT 
             g.stage,
             COUNT(*) as games,
-            SUM(CASE 
-                WHEN (g.winner_port = 1 AND g.player1_id = p.connect_code) THEN 1
-                WHEN (g.winner_port = 2 AND g.player2_id = p.connect_code) THEN 1
-                ELSE 0 
-            END) as wins
+            SUM(CASE WHEN {won} THEN 1 ELSE 0 END) as wins
          FROM player_stats p
          JOIN game_stats g ON p.recording_id = g.id
          {}
          WHERE {} AND g.stage IS NOT NULL
          GROUP BY g.stage",
-        opponent_join, where_clause
+        opponent_join, where_clause, won = WON_SQL
     );
     
     let mut stmt = conn.prepare(&stage_query)?;
@@ -830,11 +1898,102 @@ pub fn get_aggregated_player_stats(
     })
 }
 
+/// My per-game averages of the headline stats (same definitions as
+/// get_aggregated_player_stats), None where no game had the stat
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatAverages {
+    pub games: i64,
+    pub l_cancel_percent: Option<f64>,
+    pub openings_per_kill: Option<f64>,
+    pub damage_per_opening: Option<f64>,
+    pub neutral_win_percent: Option<f64>,
+    pub inputs_per_minute: Option<f64>,
+}
+
+/// Averages over my games played since `since` (ISO8601), leaving out games
+/// excluded from stats
+pub fn get_my_stat_averages(conn: &Connection, since: &str) -> rusqlite::Result<StatAverages> {
+    conn.query_row(
+        "SELECT COUNT(*),
+            AVG(CAST(p.l_cancel_success_count AS FLOAT) /
+                NULLIF(p.l_cancel_success_count + p.l_cancel_fail_count, 0)) * 100,
+            AVG(p.openings_per_kill),
+            AVG(p.damage_per_opening),
+            AVG(p.neutral_win_ratio) * 100,
+            AVG(p.inputs_per_minute)
+         FROM player_stats p
+         JOIN game_stats g ON p.recording_id = g.id
+         WHERE p.is_me = 1 AND g.excluded_reason IS NULL AND g.created_at >= ?1",
+        params![since],
+        |row| {
+            Ok(StatAverages {
+                games: row.get(0)?,
+                l_cancel_percent: row.get(1)?,
+                openings_per_kill: row.get(2)?,
+                damage_per_opening: row.get(3)?,
+                neutral_win_percent: row.get(4)?,
+                inputs_per_minute: row.get(5)?,
+            })
+        },
+    )
+}
+
+/// My side of a stored game (see get_my_games_since)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MyGameRow {
+    pub recording_id: String,
+    pub my_result: MyResult,
+    pub my_character_id: i32,
+    pub opponent_character_id: Option<i32>,
+    pub l_cancel_success_count: Option<i32>,
+    pub l_cancel_fail_count: Option<i32>,
+    pub openings_per_kill: Option<f64>,
+    pub damage_per_opening: Option<f64>,
+    pub neutral_win_ratio: Option<f64>,
+    pub inputs_per_minute: Option<f64>,
+}
+
+/// My games played since `since` (RFC 3339) that count for stats, oldest first
+pub fn get_my_games_since(conn: &Connection, since: &str) -> rusqlite::Result<Vec<MyGameRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT g.id, g.my_result, p.character_id,
+            (SELECT o.character_id FROM player_stats o
+             WHERE o.recording_id = g.id AND o.player_index != p.player_index
+             ORDER BY o.player_index LIMIT 1),
+            p.l_cancel_success_count, p.l_cancel_fail_count, p.openings_per_kill,
+            p.damage_per_opening, p.neutral_win_ratio, p.inputs_per_minute
+         FROM player_stats p
+         JOIN game_stats g ON p.recording_id = g.id
+         WHERE p.is_me = 1 AND g.excluded_reason IS NULL AND g.created_at >= ?1
+         ORDER BY g.created_at",
+    )?;
+
+    let rows = stmt.query_map(params![since], |row| {
+        let my_result: String = row.get(1)?;
+        Ok(MyGameRow {
+            recording_id: row.get(0)?,
+            my_result: MyResult::from_column(&my_result),
+            my_character_id: row.get(2)?,
+            opponent_character_id: row.get(3)?,
+            l_cancel_success_count: row.get(4)?,
+            l_cancel_fail_count: row.get(5)?,
+            openings_per_kill: row.get(6)?,
+            damage_per_opening: row.get(7)?,
+            neutral_win_ratio: row.get(8)?,
+            inputs_per_minute: row.get(9)?,
+        })
+    })?;
+
+    rows.collect()
+}
+
 /// Available filter options for stats page (only values that exist in the database)
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AvailableFilterOptions {
-    /// All unique connect codes in the database
+    /// All unique connect codes in the database (merged codes as the code
+    /// they were merged into)
     pub connect_codes: Vec<String>,
     /// All character IDs that players have played as
     pub player_characters: Vec<i32>,
@@ -844,68 +2003,78 @@ pub struct AvailableFilterOptions {
     pub stages: Vec<i32>,
 }
 
-/// Get available filter options from the database, optionally filtered by a player's connect code
-pub fn get_available_filter_options(conn: &Connection, connect_code: Option<&str>) -> rusqlite::Result<AvailableFilterOptions> {
+/// Get available filter options from the database, scoped to a player (or everyone)
+pub fn get_available_filter_options(conn: &Connection, scope: PlayerScope<'_>) -> rusqlite::Result<AvailableFilterOptions> {
     // Get all unique connect codes
-    let mut stmt = conn.prepare(
-        "SELECT DISTINCT connect_code FROM player_stats WHERE connect_code IS NOT NULL ORDER BY connect_code"
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT DISTINCT {} AS code FROM player_stats WHERE connect_code IS NOT NULL ORDER BY code",
+        super::players::canonical_code_sql("player_stats.connect_code")
+    ))?;
     let connect_codes: Vec<String> = stmt.query_map([], |row| row.get(0))?
         .filter_map(|r| r.ok())
         .collect();
 
     // Get characters and stages based on whether we have a specific player
-    let (player_characters, opponent_characters, stages) = if let Some(code) = connect_code {
-        // Get characters this player has played as
+    let (player_characters, opponent_characters, stages) = if let PlayerScope::All = scope {
+        // No player filter - return all
         let mut stmt = conn.prepare(
-            "SELECT DISTINCT character_id FROM player_stats WHERE connect_code = ?1 ORDER BY character_id"
+            "SELECT DISTINCT character_id FROM player_stats ORDER BY character_id"
         )?;
-        let player_chars: Vec<i32> = stmt.query_map([code], |row| row.get(0))?
+        let characters: Vec<i32> = stmt.query_map([], |row| row.get(0))?
             .filter_map(|r| r.ok())
             .collect();
 
-        // Get characters this player has faced (opponent's characters in games where this player participated)
         let mut stmt = conn.prepare(
+            "SELECT DISTINCT stage FROM game_stats WHERE stage IS NOT NULL ORDER BY stage"
+        )?;
+        let all_stages: Vec<i32> = stmt.query_map([], |row| row.get(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        (characters.clone(), characters, all_stages)
+    } else {
+        let (scope_clause, scope_param) = scope.condition(1);
+        let scope_params: Vec<&dyn rusqlite::ToSql> = scope_param
+            .iter()
+            .map(|s| s as &dyn rusqlite::ToSql)
+            .collect();
+
+        // Get characters this player has played as
+        let mut stmt = conn.prepare(&format!(
+            "SELECT DISTINCT p.character_id FROM player_stats p WHERE {} ORDER BY p.character_id",
+            scope_clause
+        ))?;
+        let player_chars: Vec<i32> = stmt.query_map(scope_params.as_slice(), |row| row.get(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        // Get characters this player has faced (opponent's characters in games where this player participated)
+        let mut stmt = conn.prepare(&format!(
             "SELECT DISTINCT opp.character_id 
              FROM player_stats p
              JOIN player_stats opp ON p.recording_id = opp.recording_id AND opp.player_index != p.player_index
-             WHERE p.connect_code = ?1
-             ORDER BY opp.character_id"
-        )?;
-        let opp_chars: Vec<i32> = stmt.query_map([code], |row| row.get(0))?
+             WHERE {}
+             ORDER BY opp.character_id",
+            scope_clause
+        ))?;
+        let opp_chars: Vec<i32> = stmt.query_map(scope_params.as_slice(), |row| row.get(0))?
             .filter_map(|r| r.ok())
             .collect();
 
         // Get stages this player has played on
-        let mut stmt = conn.prepare(
+        let mut stmt = conn.prepare(&format!(
             "SELECT DISTINCT g.stage 
              FROM player_stats p
              JOIN game_stats g ON p.recording_id = g.id
-             WHERE p.connect_code = ?1 AND g.stage IS NOT NULL
-             ORDER BY g.stage"
-        )?;
-        let player_stages: Vec<i32> = stmt.query_map([code], |row| row.get(0))?
+             WHERE {} AND g.stage IS NOT NULL
+             ORDER BY g.stage",
+            scope_clause
+        ))?;
+        let player_stages: Vec<i32> = stmt.query_map(scope_params.as_slice(), |row| row.get(0))?
             .filter_map(|r| r.ok())
             .collect();
 
         (player_chars, opp_chars, player_stages)
-    } else {
-        // No player filter - return all
-        let mut stmt = conn.prepare(
-            "SELECT DISTINCT character_id FROM player_stats ORDER BY character_id"
-        )?;
-        let characters: Vec<i32> = stmt.query_map([], |row| row.get(0))?
-            .filter_map(|r| r.ok())
-            .collect();
-
-        let mut stmt = conn.prepare(
-            "SELECT DISTINCT stage FROM game_stats WHERE stage IS NOT NULL ORDER BY stage"
-        )?;
-        let all_stages: Vec<i32> = stmt.query_map([], |row| row.get(0))?
-            .filter_map(|r| r.ok())
-            .collect();
-
-        (characters.clone(), characters, all_stages)
     };
 
     Ok(AvailableFilterOptions {
@@ -0,0 +1,81 @@
+//! Generated reports (the weekly summary), one per kind and period
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+/// Row from the reports table
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportRow {
+    pub id: i64,
+    pub kind: String,
+    /// Local date the period starts on
+    pub period_start: String,
+    /// Local date after the period's last day
+    pub period_end: String,
+    /// The report itself
+    pub payload: serde_json::Value,
+    /// Shareable card, if one was rendered
+    pub card_path: Option<String>,
+    pub created_at: String,
+}
+
+/// Save a report, replacing the one for the same kind and period. Returns its id
+pub fn save_report(
+    conn: &Connection,
+    kind: &str,
+    period_start: &str,
+    period_end: &str,
+    payload: &serde_json::Value,
+    card_path: Option<&str>,
+) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "INSERT INTO reports (kind, period_start, period_end, payload, card_path, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(kind, period_start) DO UPDATE SET
+            period_end = excluded.period_end,
+            payload = excluded.payload,
+            card_path = excluded.card_path,
+            created_at = excluded.created_at
+         RETURNING id",
+        params![kind, period_start, period_end, payload.to_string(), card_path, chrono::Utc::now().to_rfc3339()],
+        |row| row.get(0),
+    )
+}
+
+/// Reports of a kind, latest period first
+pub fn get_reports(conn: &Connection, kind: &str, limit: i64) -> rusqlite::Result<Vec<ReportRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, kind, period_start, period_end, payload, card_path, created_at
+         FROM reports
+         WHERE kind = ?1
+         ORDER BY period_start DESC
+         LIMIT ?2",
+    )?;
+
+    let rows = stmt.query_map(params![kind, limit], |row| {
+        let payload: String = row.get(4)?;
+        Ok(ReportRow {
+            id: row.get(0)?,
+            kind: row.get(1)?,
+            period_start: row.get(2)?,
+            period_end: row.get(3)?,
+            payload: serde_json::from_str(&payload).unwrap_or(serde_json::Value::Null),
+            card_path: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    })?;
+
+    rows.collect()
+}
+
+/// Whether a report of this kind exists for the period starting on `period_start`
+pub fn has_report(conn: &Connection, kind: &str, period_start: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT 1 FROM reports WHERE kind = ?1 AND period_start = ?2",
+        params![kind, period_start],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|row| row.is_some())
+}
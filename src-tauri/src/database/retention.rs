@@ -0,0 +1,166 @@
+//! Retention rules: which recordings to delete or compress as they age
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// What a rule does to the recordings it matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RetentionAction {
+    /// Never touch matching recordings (takes precedence over every other rule)
+    Keep,
+    /// Delete the video, keeping the replay
+    DeleteVideo,
+    /// Delete the video and its replay
+    DeleteVideoAndReplay,
+    /// Re-encode the video at a low bitrate
+    Compress,
+}
+
+impl RetentionAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            RetentionAction::Keep => "keep",
+            RetentionAction::DeleteVideo => "delete_video",
+            RetentionAction::DeleteVideoAndReplay => "delete_video_and_replay",
+            RetentionAction::Compress => "compress",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [
+            RetentionAction::Keep,
+            RetentionAction::DeleteVideo,
+            RetentionAction::DeleteVideoAndReplay,
+            RetentionAction::Compress,
+        ]
+        .into_iter()
+        .find(|action| action.as_str() == value)
+    }
+}
+
+/// Game result a rule can match, from the local user's point of view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ResultFilter {
+    Win,
+    Loss,
+}
+
+impl ResultFilter {
+    fn as_str(self) -> &'static str {
+        match self {
+            ResultFilter::Win => "win",
+            ResultFilter::Loss => "loss",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "win" => Some(ResultFilter::Win),
+            "loss" => Some(ResultFilter::Loss),
+            _ => None,
+        }
+    }
+}
+
+/// Row from the retention_rules table. Unset criteria match everything
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionRuleRow {
+    /// None for a rule that hasn't been saved yet
+    pub id: Option<i64>,
+    pub name: String,
+    pub enabled: bool,
+    /// In-game tag of any player in the game
+    pub tag: Option<String>,
+    /// Opponent's connect code or in-game tag
+    pub opponent: Option<String>,
+    pub result: Option<ResultFilter>,
+    /// Only recordings at least this many days old
+    pub min_age_days: Option<i64>,
+    pub action: RetentionAction,
+}
+
+/// Get every retention rule, oldest first
+pub fn get_retention_rules(conn: &Connection) -> rusqlite::Result<Vec<RetentionRuleRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, enabled, tag, opponent, result, min_age_days, action
+         FROM retention_rules
+         ORDER BY id",
+    )?;
+
+    let rows = stmt.query_map([], |row| {
+        let result: Option<String> = row.get(5)?;
+        let action: String = row.get(7)?;
+        Ok(RetentionRuleRow {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            enabled: row.get::<_, i32>(2)? != 0,
+            tag: row.get(3)?,
+            opponent: row.get(4)?,
+            result: result.as_deref().and_then(ResultFilter::parse),
+            min_age_days: row.get(6)?,
+            // Unknown actions (from a newer version) are treated as keep, the safe choice
+            action: RetentionAction::parse(&action).unwrap_or(RetentionAction::Keep),
+        })
+    })?;
+
+    rows.collect()
+}
+
+/// Insert a rule, or update it if it has an id. Returns the rule's id
+pub fn save_retention_rule(conn: &Connection, rule: &RetentionRuleRow) -> rusqlite::Result<i64> {
+    match rule.id {
+        Some(id) => {
+            let updated = conn.execute(
+                "UPDATE retention_rules
+                 SET name = ?2, enabled = ?3, tag = ?4, opponent = ?5, result = ?6, min_age_days = ?7, action = ?8
+                 WHERE id = ?1",
+                params![
+                    id,
+                    rule.name,
+                    rule.enabled as i32,
+                    rule.tag,
+                    rule.opponent,
+                    rule.result.map(ResultFilter::as_str),
+                    rule.min_age_days,
+                    rule.action.as_str(),
+                ],
+            )?;
+            if updated == 0 {
+                return Err(rusqlite::Error::QueryReturnedNoRows);
+            }
+            Ok(id)
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO retention_rules (name, enabled, tag, opponent, result, min_age_days, action, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    rule.name,
+                    rule.enabled as i32,
+                    rule.tag,
+                    rule.opponent,
+                    rule.result.map(ResultFilter::as_str),
+                    rule.min_age_days,
+                    rule.action.as_str(),
+                    chrono::Utc::now().to_rfc3339(),
+                ],
+            )?;
+            Ok(conn.last_insert_rowid())
+        }
+    }
+}
+
+/// Delete a rule. Returns whether it existed
+pub fn delete_retention_rule(conn: &Connection, id: i64) -> rusqlite::Result<bool> {
+    Ok(conn.execute("DELETE FROM retention_rules WHERE id = ?", params![id])? > 0)
+}
+
+/// Whether any enabled rule exists (the scheduled run is skipped otherwise)
+pub fn has_enabled_retention_rules(conn: &Connection) -> rusqlite::Result<bool> {
+    conn.query_row("SELECT 1 FROM retention_rules WHERE enabled = 1 LIMIT 1", [], |_| Ok(()))
+        .optional()
+        .map(|row| row.is_some())
+}
@@ -1,63 +1,768 @@
 //! Database schema initialization
 //!
-//! Simple approach: drop and recreate tables if schema doesn't match.
+//! Versioned migrations: each migration moves the schema up by one version and
+//! is recorded in schema_version along with when it was applied. Each migration
+//! runs in its own transaction, so a failure leaves the database at the previous
+//! version. Statements SQLite can't run inside a transaction are run afterwards.
+//!
+//! Databases from before versioned migrations (older than the baseline) are
+//! dropped and recreated, since they only hold cached data that is rebuilt on
+//! the next sync.
 
 use rusqlite::Connection;
+use serde::Serialize;
+
+/// A single schema migration
+struct Migration {
+    /// Schema version after this migration is applied
+    version: i32,
+    /// Short description for logs
+    description: &'static str,
+    /// SQL to apply (may contain multiple statements)
+    sql: &'static str,
+}
+
+/// All migrations, in order. Never edit an existing migration - add a new one instead.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 7,
+        description: "baseline schema (recordings, game_stats, player_stats)",
+        sql: BASELINE_SQL,
+    },
+    Migration {
+        version: 8,
+        description: "player_stats.is_me flag",
+        sql: "
+        ALTER TABLE player_stats ADD COLUMN is_me INTEGER DEFAULT 0;
+        CREATE INDEX idx_player_stats_is_me ON player_stats(is_me);
+        ",
+    },
+    Migration {
+        version: 9,
+        description: "manual 'my port' overrides",
+        sql: "
+        -- Manual 'which port was me' overrides for games that can't be attributed automatically
+        CREATE TABLE my_port_overrides (
+            recording_id TEXT PRIMARY KEY,
+            port INTEGER NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        ",
+    },
+    Migration {
+        version: 10,
+        description: "recording capture stats (dropped frames, stalls)",
+        sql: "
+        ALTER TABLE recordings ADD COLUMN dropped_frame_estimate INTEGER;
+        ALTER TABLE recordings ADD COLUMN longest_stall_ms INTEGER;
+        ",
+    },
+    Migration {
+        version: 11,
+        description: "player_stats.stats_version",
+        sql: "
+        -- Rows from before stats versioning count as version 0 (outdated)
+        ALTER TABLE player_stats ADD COLUMN stats_version INTEGER DEFAULT 0;
+        CREATE INDEX idx_player_stats_stats_version ON player_stats(stats_version);
+        ",
+    },
+    Migration {
+        version: 12,
+        description: "recordings.profile",
+        sql: "
+        -- Name of the settings profile that was active when the game was recorded
+        ALTER TABLE recordings ADD COLUMN profile TEXT;
+        ",
+    },
+    Migration {
+        version: 13,
+        description: "retention_rules table",
+        sql: "
+        CREATE TABLE retention_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            -- Match criteria; NULL matches everything
+            tag TEXT,
+            opponent TEXT,
+            result TEXT,  -- 'win' or 'loss'
+            min_age_days INTEGER,
+            action TEXT NOT NULL,  -- 'keep', 'delete_video', 'delete_video_and_replay' or 'compress'
+            created_at TEXT NOT NULL
+        );
+        ",
+    },
+    Migration {
+        version: 14,
+        description: "recordings.is_archived",
+        sql: "
+        -- Set once the video has been re-encoded to the archive format
+        ALTER TABLE recordings ADD COLUMN is_archived INTEGER DEFAULT 0;
+        ",
+    },
+    Migration {
+        version: 15,
+        description: "combos table",
+        sql: "
+        -- Combos (slippi-js conversions) from the stats pass, scored as highlights
+        CREATE TABLE combos (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            recording_id TEXT NOT NULL,
+            player_index INTEGER NOT NULL,  -- Player who performed the combo
+            opponent_index INTEGER NOT NULL,
+            start_frame INTEGER NOT NULL,
+            end_frame INTEGER NOT NULL,
+            start_percent REAL NOT NULL,  -- Opponent's percent
+            end_percent REAL NOT NULL,
+            move_count INTEGER NOT NULL,
+            distinct_moves INTEGER NOT NULL,
+            did_kill INTEGER NOT NULL DEFAULT 0,
+            highlight_score REAL NOT NULL,
+            clip_path TEXT  -- Set once the highlight was extracted as a clip
+        );
+        CREATE INDEX idx_combos_recording ON combos(recording_id);
+        CREATE INDEX idx_combos_highlight_score ON combos(highlight_score DESC);
+        ",
+    },
+    Migration {
+        version: 16,
+        description: "recordings.parse_error",
+        sql: "
+        -- Why the .slp couldn't be parsed; stats passes skip it until it's retried
+        ALTER TABLE recordings ADD COLUMN parse_error TEXT;
+        ",
+    },
+    Migration {
+        version: 17,
+        description: "game_stats.replay_version",
+        sql: "
+        -- Replay format version from Game Start; older replays lack some stats
+        ALTER TABLE game_stats ADD COLUMN replay_version TEXT;
+        -- slippi-js never counted wall jump techs, so the stored zeros mean unknown
+        UPDATE player_stats SET wall_jump_tech_count = NULL;
+        ",
+    },
+    Migration {
+        version: 18,
+        description: "recordings.replay_id and video info",
+        sql: "
+        -- Stable id of the linked replay's game, for finding the same game recorded twice
+        ALTER TABLE recordings ADD COLUMN replay_id TEXT;
+        -- Filled in by the media probe after sync
+        ALTER TABLE recordings ADD COLUMN video_width INTEGER;
+        ALTER TABLE recordings ADD COLUMN video_height INTEGER;
+        ALTER TABLE recordings ADD COLUMN video_duration_secs REAL;
+        CREATE INDEX idx_recordings_replay_id ON recordings(replay_id);
+        ",
+    },
+    Migration {
+        version: 19,
+        description: "recordings audio info",
+        sql: "
+        -- Filled in by the media probe after sync; NULL until probed
+        ALTER TABLE recordings ADD COLUMN has_audio INTEGER;
+        ALTER TABLE recordings ADD COLUMN audio_mean_volume_db REAL;
+        ",
+    },
+    Migration {
+        version: 20,
+        description: "recording_replays table",
+        sql: "
+        -- Games of a recording that spans a whole set; single-game recordings
+        -- have no rows and use recordings.slp_path
+        CREATE TABLE recording_replays (
+            recording_id TEXT NOT NULL,
+            game_index INTEGER NOT NULL,  -- 0 for the set's first game
+            slp_path TEXT NOT NULL,
+            offset_secs REAL NOT NULL,  -- Where the game starts in the video
+            PRIMARY KEY (recording_id, game_index)
+        );
+        CREATE INDEX idx_recording_replays_slp_path ON recording_replays(slp_path);
+        ",
+    },
+    Migration {
+        version: 21,
+        description: "stats exclusion flags",
+        sql: "
+        -- From the replay's Game Start player type
+        ALTER TABLE player_stats ADD COLUMN is_cpu INTEGER NOT NULL DEFAULT 0;
+        -- Why the game is left out of aggregates ('cpu', 'short'), NULL if it counts
+        ALTER TABLE game_stats ADD COLUMN excluded_reason TEXT;
+        ",
+    },
+    Migration {
+        version: 22,
+        description: "player_stats input quality",
+        sql: "
+        -- Counted from the replay's frames by the input analysis pass, NULL until it has run
+        ALTER TABLE player_stats ADD COLUMN dashback_attempts INTEGER;
+        ALTER TABLE player_stats ADD COLUMN dashback_successes INTEGER;
+        ALTER TABLE player_stats ADD COLUMN pivot_attempts INTEGER;
+        ALTER TABLE player_stats ADD COLUMN pivot_successes INTEGER;
+        ALTER TABLE player_stats ADD COLUMN empty_pivots INTEGER;
+        ALTER TABLE player_stats ADD COLUMN snapbacks INTEGER;
+        ",
+    },
+    Migration {
+        version: 23,
+        description: "nullable recordings.video_path (replay-only entries)",
+        sql: "
+        -- Replay-only mode indexes .slp files without a video. SQLite can't drop
+        -- NOT NULL from a column, so the table is rebuilt
+        CREATE TABLE recordings_new (
+            id TEXT PRIMARY KEY,
+            video_path TEXT UNIQUE,
+            slp_path TEXT,
+            file_size INTEGER,
+            file_modified_at TEXT,
+            thumbnail_path TEXT,
+            start_time TEXT,
+            cached_at TEXT NOT NULL,
+            needs_reparse INTEGER DEFAULT 0,
+            dropped_frame_estimate INTEGER,
+            longest_stall_ms INTEGER,
+            profile TEXT,
+            is_archived INTEGER DEFAULT 0,
+            parse_error TEXT,
+            replay_id TEXT,
+            video_width INTEGER,
+            video_height INTEGER,
+            video_duration_secs REAL,
+            has_audio INTEGER,
+            audio_mean_volume_db REAL
+        );
+        INSERT INTO recordings_new (
+            id, video_path, slp_path, file_size, file_modified_at, thumbnail_path, start_time, cached_at,
+            needs_reparse, dropped_frame_estimate, longest_stall_ms, profile, is_archived, parse_error,
+            replay_id, video_width, video_height, video_duration_secs, has_audio, audio_mean_volume_db
+        )
+        SELECT
+            id, video_path, slp_path, file_size, file_modified_at, thumbnail_path, start_time, cached_at,
+            needs_reparse, dropped_frame_estimate, longest_stall_ms, profile, is_archived, parse_error,
+            replay_id, video_width, video_height, video_duration_secs, has_audio, audio_mean_volume_db
+        FROM recordings;
+        DROP TABLE recordings;
+        ALTER TABLE recordings_new RENAME TO recordings;
+        CREATE INDEX idx_recordings_start_time ON recordings(start_time DESC);
+        CREATE INDEX idx_recordings_video_path ON recordings(video_path);
+        CREATE INDEX idx_recordings_replay_id ON recordings(replay_id);
+        -- Replay-only entries are found by their .slp
+        CREATE INDEX idx_recordings_slp_path ON recordings(slp_path);
+        ",
+    },
+    Migration {
+        version: 24,
+        description: "recordings.video_offset_ms",
+        sql: "
+        -- How late the video's first frame is compared with its replay's start,
+        -- measured when recording stops (or set by hand). NULL if unknown
+        ALTER TABLE recordings ADD COLUMN video_offset_ms INTEGER;
+        ",
+    },
+    Migration {
+        version: 25,
+        description: "game_stats.total_paused_frames",
+        sql: "
+        -- Frames the game was paused for, left out of per-minute rates. NULL
+        -- for games whose stats were saved before pauses were detected
+        ALTER TABLE game_stats ADD COLUMN total_paused_frames INTEGER;
+        ",
+    },
+    Migration {
+        version: 26,
+        description: "player_identities, player_aliases",
+        sql: "
+        -- Every display name seen with a connect code, and when ('' when the
+        -- player had none)
+        CREATE TABLE player_identities (
+            connect_code TEXT NOT NULL,
+            display_name TEXT NOT NULL DEFAULT '',
+            first_seen TEXT NOT NULL,
+            last_seen TEXT NOT NULL,
+            PRIMARY KEY (connect_code, display_name)
+        );
+        CREATE INDEX idx_player_identities_last_seen ON player_identities(last_seen DESC);
+        INSERT INTO player_identities (connect_code, display_name, first_seen, last_seen)
+        SELECT p.connect_code, COALESCE(p.display_name, ''),
+               MIN(COALESCE(g.created_at, r.start_time, r.cached_at)),
+               MAX(COALESCE(g.created_at, r.start_time, r.cached_at))
+        FROM player_stats p
+        LEFT JOIN game_stats g ON g.id = p.recording_id
+        LEFT JOIN recordings r ON r.id = p.recording_id
+        WHERE p.connect_code IS NOT NULL AND p.connect_code != ''
+          AND COALESCE(g.created_at, r.start_time, r.cached_at) IS NOT NULL
+        GROUP BY p.connect_code, COALESCE(p.display_name, '');
+
+        -- Codes merged into another (the same person), counted as that code
+        -- in aggregate stats
+        CREATE TABLE player_aliases (
+            connect_code TEXT PRIMARY KEY,
+            canonical_code TEXT NOT NULL
+        );
+        CREATE INDEX idx_player_aliases_canonical ON player_aliases(canonical_code);
+        ",
+    },
+    Migration {
+        version: 27,
+        description: "player_stats.played_character_id",
+        sql: "
+        -- The character played for most of the game: character_id is the one
+        -- picked, which for Zelda and Sheik isn't always it. Games saved before
+        -- this count as the picked one until their stats are recomputed
+        ALTER TABLE player_stats ADD COLUMN played_character_id INTEGER;
+        UPDATE player_stats SET played_character_id = character_id;
+        ",
+    },
+    Migration {
+        version: 28,
+        description: "game_stats.winner_code, game_stats.loser_code",
+        sql: "
+        -- Connect codes of the winner and loser, which unlike ports stay the
+        -- same through a set. NULL offline, and until repair_game_results
+        -- fills them in for games saved before
+        ALTER TABLE game_stats ADD COLUMN winner_code TEXT;
+        ALTER TABLE game_stats ADD COLUMN loser_code TEXT;
+        ",
+    },
+    Migration {
+        version: 29,
+        description: "recordings.slp_hash",
+        sql: "
+        -- Sampled hash of the linked .slp when it was last indexed, so a
+        -- replaced replay is reparsed. NULL until the next sync stores it
+        ALTER TABLE recordings ADD COLUMN slp_hash TEXT;
+        ",
+    },
+    Migration {
+        version: 30,
+        description: "recordings.source_recording_id",
+        sql: "
+        -- Recording a trimmed or cropped copy was made from (edit_recording)
+        ALTER TABLE recordings ADD COLUMN source_recording_id TEXT;
+        ",
+    },
+    Migration {
+        version: 31,
+        description: "game_stats.my_result",
+        sql: "
+        -- 'win', 'loss' or 'unknown' (not attributed to me, or no winner),
+        -- kept up to date with is_me and the winner (see refresh_my_result)
+        ALTER TABLE game_stats ADD COLUMN my_result TEXT NOT NULL DEFAULT 'unknown';
+        UPDATE game_stats SET my_result = COALESCE(
+            (SELECT CASE
+                        WHEN (CASE WHEN g.winner_code IS NOT NULL AND p.connect_code IS NOT NULL
+                                   THEN g.winner_code = p.connect_code ELSE g.winner_port = p.port END) IS NULL THEN 'unknown'
+                        WHEN (CASE WHEN g.winner_code IS NOT NULL AND p.connect_code IS NOT NULL
+                                   THEN g.winner_code = p.connect_code ELSE g.winner_port = p.port END) THEN 'win'
+                        ELSE 'loss'
+                    END
+             FROM player_stats p JOIN game_stats g ON g.id = p.recording_id
+             WHERE g.id = game_stats.id AND p.is_me = 1),
+            'unknown');
+        ",
+    },
+    Migration {
+        version: 32,
+        description: "recording_rules and recording_rule_log tables",
+        sql: "
+        CREATE TABLE recording_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            -- Match criteria; NULL matches everything
+            opponent_code TEXT,
+            my_character INTEGER,
+            opponent_character INTEGER,
+            connection TEXT NOT NULL DEFAULT 'any',  -- 'any', 'online' or 'offline'
+            action TEXT NOT NULL,  -- 'record', 'skip' or 'record_at_quality'
+            quality TEXT,  -- the quality for record_at_quality
+            created_at TEXT NOT NULL
+        );
+        -- Which rule decided each new game (the rule's name and action are
+        -- copied, so entries outlive changes to it)
+        CREATE TABLE recording_rule_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            slp_path TEXT NOT NULL,
+            rule_id INTEGER NOT NULL,
+            rule_name TEXT NOT NULL,
+            action TEXT NOT NULL,
+            quality TEXT,
+            created_at TEXT NOT NULL
+        );
+        CREATE INDEX idx_recording_rule_log_created_at ON recording_rule_log(created_at DESC);
+        ",
+    },
+    Migration {
+        version: 33,
+        description: "reports table",
+        sql: "
+        CREATE TABLE reports (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,  -- 'weekly'
+            -- The period covered, as local dates (the end is exclusive)
+            period_start TEXT NOT NULL,
+            period_end TEXT NOT NULL,
+            payload TEXT NOT NULL,  -- the report as JSON
+            card_path TEXT,  -- shareable HTML card, if one was rendered
+            created_at TEXT NOT NULL,
+            UNIQUE(kind, period_start)
+        );
+        ",
+    },
+    Migration {
+        version: 34,
+        description: "game_stats.is_warmup",
+        sql: "
+        -- The first games of a play session (see warmups), left out of stats on request;
+        -- warmup_override is the user's choice for the game, NULL to go by the session
+        ALTER TABLE game_stats ADD COLUMN is_warmup INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE game_stats ADD COLUMN warmup_override INTEGER;
+        ",
+    },
+    Migration {
+        version: 35,
+        description: "game_stats.stats_source",
+        sql: "
+        -- Which tool computed the stats ('buckwheat', or an import: 'slippi_launcher',
+        -- 'slippi_js'); their definitions differ, so aggregates can be limited to one
+        ALTER TABLE game_stats ADD COLUMN stats_source TEXT NOT NULL DEFAULT 'buckwheat';
+        ",
+    },
+    Migration {
+        version: 36,
+        description: "recordings.start_time_invalid",
+        sql: "
+        -- Set when start_time isn't a time that can be read (see crate::timestamps);
+        -- such recordings sort and filter by whatever the text happens to be
+        ALTER TABLE recordings ADD COLUMN start_time_invalid INTEGER NOT NULL DEFAULT 0;
+        ",
+    },
+    Migration {
+        version: 37,
+        description: "recordings.is_favorite",
+        sql: "
+        -- Clips the user starred; the Clips folder cap (maxClipsSizeGb) never deletes them
+        ALTER TABLE recordings ADD COLUMN is_favorite INTEGER NOT NULL DEFAULT 0;
+        ",
+    },
+    Migration {
+        version: 38,
+        description: "game_stats.is_provisional",
+        sql: "
+        -- Set on rows saved from a new game's Game Start (players and stage only)
+        -- until its full stats replace them; such games are excluded ('provisional')
+        ALTER TABLE game_stats ADD COLUMN is_provisional INTEGER NOT NULL DEFAULT 0;
+        ",
+    },
+    Migration {
+        version: 39,
+        description: "game_stats.completed",
+        sql: "
+        -- 0 for replays without a Game End (crashes, disconnects, quit-outs), whose
+        -- stats stop early; such games are excluded ('unfinished') unless counted.
+        -- Games saved before this are taken to have finished
+        ALTER TABLE game_stats ADD COLUMN completed INTEGER NOT NULL DEFAULT 1;
+        ",
+    },
+];
+
+/// Migrations adding columns that only a reparse fills in; games with stats
+/// are flagged for one (needs_reparse) after any of them is applied
+const REPARSE_AFTER: &[i32] = &[27, 28];
+
+/// Stored times are rewritten in one spelling (see crate::timestamps) when
+/// migrating from before this version
+const NORMALIZE_TIMES_BEFORE: i32 = 36;
+
+/// Oldest version that can be migrated forward; anything older is recreated
+const BASELINE_VERSION: i32 = 7;
+
+/// Current schema version (the last migration)
+pub const SCHEMA_VERSION: i32 = 39;
+
+/// Statement prefixes SQLite refuses to run (or silently ignores) inside a transaction
+const NON_TRANSACTIONAL_PREFIXES: &[&str] = &[
+    "VACUUM",
+    "PRAGMA JOURNAL_MODE",
+    "PRAGMA FOREIGN_KEYS",
+    "PRAGMA PAGE_SIZE",
+    "PRAGMA AUTO_VACUUM",
+];
+
+/// Error applying schema migrations
+#[derive(Debug, thiserror::Error)]
+pub enum MigrationError {
+    #[error("Migration v{version} ({description}) failed: {source}{}", if *.restored { " - database restored from backup" } else { "" })]
+    Failed {
+        version: i32,
+        description: &'static str,
+        source: rusqlite::Error,
+        /// Whether the pre-migration backup was restored
+        restored: bool,
+    },
+    #[error("Failed to back up database before migrating: {0}")]
+    Backup(String),
+    #[error("Failed to restore database from backup: {0}")]
+    Restore(String),
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+}
 
-/// Current schema version - bump this to force a recreate
-const SCHEMA_VERSION: i32 = 7;
+impl MigrationError {
+    /// Mark a failed migration as having been rolled back to the pre-migration backup
+    pub fn with_restored(self) -> Self {
+        match self {
+            MigrationError::Failed { version, description, source, .. } => {
+                MigrationError::Failed { version, description, source, restored: true }
+            }
+            other => other,
+        }
+    }
+}
+
+/// How risky a pending migration is to apply
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationRisk {
+    /// Only creates new tables/indexes, fully transactional
+    Low,
+    /// Alters existing tables, but transactional
+    Medium,
+    /// Drops data or contains statements that can't be rolled back
+    High,
+}
+
+/// A migration that hasn't been applied yet
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingMigration {
+    pub version: i32,
+    pub description: String,
+    pub transactional: bool,
+    pub risk: MigrationRisk,
+}
+
+/// Report of pending migrations, without applying anything
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationStatus {
+    pub current_version: i32,
+    pub latest_version: i32,
+    /// Database predates versioned migrations and will be recreated (cache data is lost)
+    pub requires_recreate: bool,
+    pub pending: Vec<PendingMigration>,
+}
 
 /// Initialize the database schema
-/// Drops and recreates all tables if version doesn't match
-pub fn init_database(conn: &Connection) -> Result<(), rusqlite::Error> {
-    // Create schema version table
+/// Applies any pending migrations; pre-baseline databases are recreated from scratch
+pub fn init_database(conn: &Connection) -> Result<(), MigrationError> {
+    ensure_version_table(conn)?;
+    
+    // Get current version
+    let mut current_version = current_version(conn);
+    
+    if current_version > SCHEMA_VERSION {
+        log::warn!("⚠️ Database schema v{} is newer than this app (v{}), leaving it as is", current_version, SCHEMA_VERSION);
+        return Ok(());
+    }
+    
+    // Pre-migration databases can't be upgraded in place - start over at the baseline
+    if current_version != 0 && current_version < BASELINE_VERSION {
+        log::info!("📦 Schema v{} predates migrations, recreating database...", current_version);
+        drop_all_tables(conn)?;
+        current_version = 0;
+    }
+    
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current_version) {
+        log::info!("📦 Applying migration v{}: {}", migration.version, migration.description);
+        apply_migration(conn, migration).map_err(|source| MigrationError::Failed {
+            version: migration.version,
+            description: migration.description,
+            source,
+            restored: false,
+        })?;
+    }
+    
+    if REPARSE_AFTER.iter().any(|&version| version > current_version) {
+        let flagged = conn.execute(
+            "UPDATE recordings SET needs_reparse = 1 WHERE id IN (SELECT id FROM game_stats)",
+            [],
+        )?;
+        if flagged > 0 {
+            log::info!("📦 Flagged {} game(s) to reparse for the new columns", flagged);
+        }
+    }
+    
+    if current_version != 0 && current_version < NORMALIZE_TIMES_BEFORE {
+        let (changed, invalid) = super::recordings::normalize_stored_times(conn)?;
+        log::info!("📦 Rewrote {} stored time(s) in UTC, {} start time(s) couldn't be read", changed, invalid);
+    }
+    
+    log::info!("✅ Database schema at v{}", SCHEMA_VERSION);
+    Ok(())
+}
+
+/// Report which migrations would run, and how risky they are, without applying them
+pub fn check_migrations(conn: &Connection) -> MigrationStatus {
+    let current_version = current_version(conn);
+    let requires_recreate = current_version != 0 && current_version < BASELINE_VERSION;
+    let from_version = if requires_recreate { 0 } else { current_version };
+    
+    let pending = MIGRATIONS
+        .iter()
+        .filter(|m| m.version > from_version)
+        .map(|m| {
+            let (_, non_transactional) = split_transactional(m.sql);
+            PendingMigration {
+                version: m.version,
+                description: m.description.to_string(),
+                transactional: non_transactional.is_empty(),
+                risk: estimate_risk(m.sql),
+            }
+        })
+        .collect();
+    
+    MigrationStatus {
+        current_version,
+        latest_version: SCHEMA_VERSION,
+        requires_recreate,
+        pending,
+    }
+}
+
+/// Whether any migrations (or a recreate) would run against this database
+pub fn has_pending_migrations(conn: &Connection) -> bool {
+    current_version(conn) < SCHEMA_VERSION
+}
+
+/// Highest applied schema version (0 for a new database)
+pub fn current_version(conn: &Connection) -> i32 {
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )
+    .unwrap_or(0)
+}
+
+// ============================================================================
+// INTERNAL HELPERS
+// ============================================================================
+
+/// Create the version tracking table, adding applied_at to tables from before it existed
+fn ensure_version_table(conn: &Connection) -> Result<(), rusqlite::Error> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS schema_version (
-            version INTEGER PRIMARY KEY
+            version INTEGER PRIMARY KEY,
+            applied_at TEXT
         )",
         [],
     )?;
     
-    // Get current version
-    let current_version: i32 = conn
-        .query_row(
-            "SELECT COALESCE(MAX(version), 0) FROM schema_version",
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or(0);
+    let has_applied_at: bool = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('schema_version') WHERE name = 'applied_at'",
+        [],
+        |row| row.get::<_, i32>(0).map(|count| count > 0),
+    )?;
+    if !has_applied_at {
+        conn.execute("ALTER TABLE schema_version ADD COLUMN applied_at TEXT", [])?;
+    }
+    
+    Ok(())
+}
+
+/// Apply one migration: transactional statements and the version bump in one transaction,
+/// then any statements that can't run inside a transaction
+fn apply_migration(conn: &Connection, migration: &Migration) -> Result<(), rusqlite::Error> {
+    let (transactional, non_transactional) = split_transactional(migration.sql);
+    
+    let tx = conn.unchecked_transaction()?;
+    for statement in &transactional {
+        tx.execute_batch(statement)?;
+    }
+    tx.execute(
+        "INSERT INTO schema_version (version, applied_at) VALUES (?1, ?2)",
+        rusqlite::params![migration.version, chrono::Utc::now().to_rfc3339()],
+    )?;
+    tx.commit()?;
     
-    // If version doesn't match, drop everything and recreate
-    if current_version != SCHEMA_VERSION {
-        log::info!("📦 Schema version mismatch ({} != {}), recreating database...", current_version, SCHEMA_VERSION);
-        recreate_schema(conn)?;
+    // These must be idempotent: if one fails the version is already recorded
+    for statement in &non_transactional {
+        log::info!("📦 Running non-transactional statement for v{}: {}", migration.version, statement);
+        conn.execute_batch(statement)?;
     }
     
     Ok(())
 }
 
-/// Drop all tables and recreate with current schema
-fn recreate_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
+/// Split migration SQL into (transactional, non-transactional) statements
+/// Comments are stripped; statements are split on semicolons
+fn split_transactional(sql: &str) -> (Vec<String>, Vec<String>) {
+    let without_comments: String = sql
+        .lines()
+        .map(|line| match line.find("--") {
+            Some(idx) => &line[..idx],
+            None => line,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    
+    without_comments
+        .split(';')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .partition(|s| !is_non_transactional(s))
+}
+
+fn is_non_transactional(statement: &str) -> bool {
+    let normalized = statement
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_uppercase();
+    NON_TRANSACTIONAL_PREFIXES
+        .iter()
+        .any(|prefix| normalized.starts_with(prefix))
+}
+
+/// Rough risk estimate from the statements a migration contains
+fn estimate_risk(sql: &str) -> MigrationRisk {
+    let (transactional, non_transactional) = split_transactional(sql);
+    let drops_data = transactional
+        .iter()
+        .any(|s| s.to_uppercase().starts_with("DROP") || s.to_uppercase().contains("DROP COLUMN"));
+    
+    if !non_transactional.is_empty() || drops_data {
+        MigrationRisk::High
+    } else if transactional.iter().any(|s| s.to_uppercase().starts_with("ALTER")) {
+        MigrationRisk::Medium
+    } else {
+        MigrationRisk::Low
+    }
+}
+
+/// Drop all tables and reset version tracking
+fn drop_all_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
     log::info!("🗑️ Dropping existing tables...");
     
     conn.execute_batch(
         "
+        DROP TABLE IF EXISTS my_port_overrides;
         DROP TABLE IF EXISTS player_stats;
         DROP TABLE IF EXISTS game_stats;
         DROP TABLE IF EXISTS recordings;
-        DROP TABLE IF EXISTS schema_version;
+        DELETE FROM schema_version;
         "
-    )?;
-    
-    log::info!("📦 Creating fresh schema v{}...", SCHEMA_VERSION);
-    
-    conn.execute_batch(
-        "
-        -- Schema version tracking
-        CREATE TABLE schema_version (
-            version INTEGER PRIMARY KEY
-        );
-        
+    )
+}
+
+// ============================================================================
+// MIGRATION SQL
+// ============================================================================
+
+/// v7: the schema as it was when versioned migrations were introduced
+const BASELINE_SQL: &str = "
         -- Main recordings table with UUID primary key
         CREATE TABLE recordings (
             id TEXT PRIMARY KEY,  -- UUID
@@ -201,16 +906,231 @@ fn recreate_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
         CREATE INDEX idx_player_stats_connect_code ON player_stats(connect_code);
         CREATE INDEX idx_player_stats_character ON player_stats(character_id);
         CREATE INDEX idx_player_stats_slp_path ON player_stats(slp_path);
-        "
-    )?;
-    
-    // Set the version
-    conn.execute(
-        "INSERT INTO schema_version (version) VALUES (?)",
-        [SCHEMA_VERSION],
-    )?;
-    
-    log::info!("✅ Database schema v{} created", SCHEMA_VERSION);
-    Ok(())
-}
+        ";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Snapshots of real databases at each historical version
+    const FIXTURES: &[(i32, &str)] = &[
+        (7, include_str!("fixtures/schema_v7.sql")),
+        (8, include_str!("fixtures/schema_v8.sql")),
+        (9, include_str!("fixtures/schema_v9.sql")),
+        (10, include_str!("fixtures/schema_v10.sql")),
+        (11, include_str!("fixtures/schema_v11.sql")),
+        (12, include_str!("fixtures/schema_v12.sql")),
+        (13, include_str!("fixtures/schema_v13.sql")),
+        (14, include_str!("fixtures/schema_v14.sql")),
+        (15, include_str!("fixtures/schema_v15.sql")),
+        (16, include_str!("fixtures/schema_v16.sql")),
+        (17, include_str!("fixtures/schema_v17.sql")),
+        (18, include_str!("fixtures/schema_v18.sql")),
+        (19, include_str!("fixtures/schema_v19.sql")),
+        (20, include_str!("fixtures/schema_v20.sql")),
+        (21, include_str!("fixtures/schema_v21.sql")),
+        (22, include_str!("fixtures/schema_v22.sql")),
+        (23, include_str!("fixtures/schema_v23.sql")),
+        (24, include_str!("fixtures/schema_v24.sql")),
+        (25, include_str!("fixtures/schema_v25.sql")),
+        (26, include_str!("fixtures/schema_v26.sql")),
+        (27, include_str!("fixtures/schema_v27.sql")),
+        (28, include_str!("fixtures/schema_v28.sql")),
+        (29, include_str!("fixtures/schema_v29.sql")),
+        (30, include_str!("fixtures/schema_v30.sql")),
+        (31, include_str!("fixtures/schema_v31.sql")),
+        (32, include_str!("fixtures/schema_v32.sql")),
+        (33, include_str!("fixtures/schema_v33.sql")),
+        (34, include_str!("fixtures/schema_v34.sql")),
+        (35, include_str!("fixtures/schema_v35.sql")),
+        (36, include_str!("fixtures/schema_v36.sql")),
+        (37, include_str!("fixtures/schema_v37.sql")),
+        (38, include_str!("fixtures/schema_v38.sql")),
+        (39, include_str!("fixtures/schema_v39.sql")),
+    ];
 
+    const RECORDING_ID: &str = "0b6d1f8e-6a51-4d7e-9f3c-2f1a3c7e5d01";
+
+    /// Tables, columns (name + type) and indexes, sorted so column order doesn't matter
+    fn schema_shape(conn: &Connection) -> Vec<String> {
+        let mut shape = Vec::new();
+
+        let mut stmt = conn
+            .prepare("SELECT type, name FROM sqlite_master WHERE name NOT LIKE 'sqlite_%' ORDER BY name")
+            .unwrap();
+        let objects: Vec<(String, String)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        for (kind, name) in objects {
+            shape.push(format!("{} {}", kind, name));
+            if kind == "table" {
+                let mut stmt = conn
+                    .prepare(&format!("SELECT name, type FROM pragma_table_info('{}')", name))
+                    .unwrap();
+                let mut columns: Vec<String> = stmt
+                    .query_map([], |row| Ok(format!("  {}.{} {}", name, row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+                    .unwrap()
+                    .collect::<Result<_, _>>()
+                    .unwrap();
+                columns.sort();
+                shape.extend(columns);
+            }
+        }
+
+        shape
+    }
+
+    fn fresh_database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_database(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_fresh_database_is_current() {
+        let conn = fresh_database();
+        assert_eq!(current_version(&conn), SCHEMA_VERSION);
+        assert_eq!(MIGRATIONS.last().map(|m| m.version), Some(SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn test_fixtures_migrate_to_current_schema_with_data_intact() {
+        let expected = schema_shape(&fresh_database());
+
+        for (version, sql) in FIXTURES {
+            let conn = Connection::open_in_memory().unwrap();
+            conn.execute_batch(sql).unwrap();
+            assert_eq!(current_version(&conn), *version);
+
+            init_database(&conn).unwrap();
+
+            assert_eq!(current_version(&conn), SCHEMA_VERSION, "fixture v{}", version);
+            assert_eq!(schema_shape(&conn), expected, "fixture v{}", version);
+
+            let recordings: i64 = conn
+                .query_row("SELECT COUNT(*) FROM recordings", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(recordings, 2, "fixture v{}", version);
+
+            let (stage, winner_port): (i32, i32) = conn
+                .query_row(
+                    "SELECT stage, winner_port FROM game_stats WHERE id = ?",
+                    [RECORDING_ID],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap();
+            assert_eq!((stage, winner_port), (31, 1), "fixture v{}", version);
+
+            let (code, kills, is_me): (String, i32, i32) = conn
+                .query_row(
+                    "SELECT connect_code, kill_count, is_me FROM player_stats
+                     WHERE recording_id = ? AND player_index = 0",
+                    [RECORDING_ID],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .unwrap();
+            assert_eq!((code.as_str(), kills), ("HATS#982", 4), "fixture v{}", version);
+            // is_me only existed from v8; older rows get the column default
+            assert_eq!(is_me, if *version >= 8 { 1 } else { 0 }, "fixture v{}", version);
+
+            let my_result: String = conn
+                .query_row("SELECT my_result FROM game_stats WHERE id = ?", [RECORDING_ID], |row| row.get(0))
+                .unwrap();
+            assert_eq!(my_result, if *version >= 8 { "win" } else { "unknown" }, "fixture v{}", version);
+
+            let (start_time, created_at): (String, String) = conn
+                .query_row(
+                    "SELECT r.start_time, g.created_at FROM recordings r JOIN game_stats g ON g.id = r.id WHERE r.id = ?",
+                    [RECORDING_ID],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap();
+            assert_eq!((start_time.as_str(), created_at.as_str()), ("2024-01-15T19:30:12Z", "2024-01-15T19:30:12Z"), "fixture v{}", version);
+
+            let recording_rules: i64 = conn
+                .query_row("SELECT COUNT(*) FROM recording_rules", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(recording_rules, if *version >= 32 { 1 } else { 0 }, "fixture v{}", version);
+        }
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        let conn = fresh_database();
+        let broken = Migration {
+            version: SCHEMA_VERSION + 1,
+            description: "broken",
+            sql: "
+            CREATE TABLE half_applied (id INTEGER);
+            ALTER TABLE no_such_table ADD COLUMN x INTEGER;
+            ",
+        };
+
+        assert!(apply_migration(&conn, &broken).is_err());
+
+        assert_eq!(current_version(&conn), SCHEMA_VERSION);
+        let leftover: i64 = conn
+            .query_row("SELECT COUNT(*) FROM sqlite_master WHERE name = 'half_applied'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(leftover, 0);
+    }
+
+    #[test]
+    fn test_applied_at_recorded() {
+        let conn = fresh_database();
+        let missing: i64 = conn
+            .query_row("SELECT COUNT(*) FROM schema_version WHERE applied_at IS NULL", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(missing, 0);
+    }
+
+    #[test]
+    fn test_non_transactional_statements_split_out() {
+        let (transactional, non_transactional) = split_transactional(
+            "-- comment; with a semicolon
+            CREATE TABLE a (id INTEGER); -- trailing
+            VACUUM;
+            pragma  journal_mode = WAL;",
+        );
+        assert_eq!(transactional, vec!["CREATE TABLE a (id INTEGER)"]);
+        assert_eq!(non_transactional.len(), 2);
+        assert_eq!(estimate_risk("CREATE TABLE a (id INTEGER);"), MigrationRisk::Low);
+        assert_eq!(estimate_risk("ALTER TABLE a ADD COLUMN b TEXT;"), MigrationRisk::Medium);
+        assert_eq!(estimate_risk("DROP TABLE a;"), MigrationRisk::High);
+    }
+
+    #[test]
+    fn test_check_migrations_reports_pending() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(FIXTURES[0].1).unwrap();
+
+        let status = check_migrations(&conn);
+        assert_eq!(status.current_version, 7);
+        assert!(!status.requires_recreate);
+        assert_eq!(
+            status.pending.iter().map(|m| m.version).collect::<Vec<_>>(),
+            (8..=SCHEMA_VERSION).collect::<Vec<_>>()
+        );
+        // Dry run - nothing applied
+        assert_eq!(current_version(&conn), 7);
+    }
+
+    #[test]
+    fn test_pre_baseline_database_is_recreated() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE schema_version (version INTEGER PRIMARY KEY);
+             INSERT INTO schema_version VALUES (6);
+             CREATE TABLE recordings (id TEXT PRIMARY KEY, video_path TEXT);",
+        )
+        .unwrap();
+
+        init_database(&conn).unwrap();
+
+        assert_eq!(current_version(&conn), SCHEMA_VERSION);
+        assert_eq!(schema_shape(&conn), schema_shape(&fresh_database()));
+    }
+}
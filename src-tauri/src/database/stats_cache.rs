@@ -0,0 +1,150 @@
+//! Short-lived cache of aggregated stats
+//!
+//! The stats page asks for the same totals on every visit, and each ask scans
+//! every stats row of the player. Results are kept for a few minutes per
+//! player and filter, and dropped whenever stats rows are written (see
+//! `invalidate`). The time limit covers writes that don't invalidate.
+
+use super::recordings::{AggregatedPlayerStats, PlayerScope, StatsFilter};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a result is served before it's computed again
+const TTL: Duration = Duration::from_secs(5 * 60);
+/// Most results kept (the oldest goes first)
+const MAX_ENTRIES: usize = 64;
+
+/// Whose stats, and a hash of the filter they're filtered by
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    /// Uppercase connect code, None for "me" or everyone
+    code: Option<String>,
+    me: bool,
+    filter_hash: u64,
+}
+
+impl CacheKey {
+    fn new(scope: PlayerScope<'_>, filter: Option<&StatsFilter>) -> Self {
+        let mut hasher = DefaultHasher::new();
+        filter.cloned().unwrap_or_default().hash(&mut hasher);
+        let (code, me) = match scope {
+            PlayerScope::Code(code) => (Some(code.trim().to_uppercase()), false),
+            PlayerScope::Me => (None, true),
+            PlayerScope::All => (None, false),
+        };
+        Self { code, me, filter_hash: hasher.finish() }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    results: HashMap<CacheKey, (Instant, AggregatedPlayerStats)>,
+    /// Bumped by every invalidation, so a result computed before one isn't kept
+    generation: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct StatsCache {
+    entries: Mutex<Entries>,
+}
+
+impl StatsCache {
+    /// The cached stats for a player and filter, or `compute`'s (kept unless
+    /// stats were written meanwhile). The bool is whether they were cached
+    pub fn get_or_compute<E>(
+        &self,
+        scope: PlayerScope<'_>,
+        filter: Option<&StatsFilter>,
+        now: Instant,
+        compute: impl FnOnce() -> Result<AggregatedPlayerStats, E>,
+    ) -> Result<(AggregatedPlayerStats, bool), E> {
+        let key = CacheKey::new(scope, filter);
+        let generation = {
+            let Ok(entries) = self.entries.lock() else {
+                return compute().map(|stats| (stats, false));
+            };
+            if let Some((at, stats)) = entries.results.get(&key) {
+                if now.saturating_duration_since(*at) < TTL {
+                    return Ok((stats.clone(), true));
+                }
+            }
+            entries.generation
+        };
+
+        let stats = compute()?;
+        if let Ok(mut entries) = self.entries.lock() {
+            if entries.generation == generation {
+                entries.results.retain(|_, (at, _)| now.saturating_duration_since(*at) < TTL);
+                if entries.results.len() >= MAX_ENTRIES {
+                    let oldest = entries.results.iter().min_by_key(|(_, (at, _))| *at).map(|(key, _)| key.clone());
+                    if let Some(oldest) = oldest {
+                        entries.results.remove(&oldest);
+                    }
+                }
+                entries.results.insert(key, (now, stats.clone()));
+            }
+        }
+        Ok((stats, false))
+    }
+
+    /// Drop every result (call after writing stats rows)
+    pub fn invalidate(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.results.clear();
+            entries.generation += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(total_games: i64) -> AggregatedPlayerStats {
+        AggregatedPlayerStats {
+            total_games,
+            total_wins: 0,
+            avg_l_cancel_percent: 0.0,
+            avg_rolls_per_game: 0.0,
+            avg_openings_per_kill: 0.0,
+            avg_damage_per_opening: 0.0,
+            avg_neutral_wins: 0.0,
+            avg_inputs_per_minute: 0.0,
+            character_stats: Vec::new(),
+            stage_stats: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_results_are_kept_per_filter_until_invalidated_or_expired() {
+        let cache = StatsCache::default();
+        let start = Instant::now();
+        let get = |scope, filter: Option<&StatsFilter>, at, games| {
+            cache
+                .get_or_compute(scope, filter, at, || Ok::<_, ()>(stats(games)))
+                .map(|(stats, cached)| (stats.total_games, cached))
+                .unwrap()
+        };
+
+        assert_eq!(get(PlayerScope::Code("abc#1"), None, start, 10), (10, false));
+        assert_eq!(get(PlayerScope::Code("ABC#1"), None, start, 11), (10, true));
+        let on_stage = StatsFilter { stage_id: Some(31), ..Default::default() };
+        assert_eq!(get(PlayerScope::Code("ABC#1"), Some(&on_stage), start, 4), (4, false));
+        assert_eq!(get(PlayerScope::Me, None, start, 7), (7, false));
+
+        cache.invalidate();
+        assert_eq!(get(PlayerScope::Code("ABC#1"), None, start, 12), (12, false));
+        assert_eq!(get(PlayerScope::Code("ABC#1"), None, start + TTL, 13), (13, false));
+
+        // Computed across an invalidation: served, not kept
+        let cached = cache.get_or_compute(PlayerScope::All, None, start, || {
+            cache.invalidate();
+            Ok::<_, ()>(stats(1))
+        });
+        assert!(!cached.unwrap().1);
+        assert_eq!(get(PlayerScope::All, None, start, 2), (2, false));
+    }
+}
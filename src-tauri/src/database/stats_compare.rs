@@ -0,0 +1,115 @@
+//! Stats side by side: two players, or one player over two periods
+//!
+//! Each side is aggregated as for the stats page (get_aggregated_player_stats)
+//! and every metric is paired with how it changed from the first side to the
+//! second. A side without games has no averages to compare, so it's None
+//! rather than zeros, and so are the changes involving it; a percentage
+//! change from 0 is None too.
+
+use super::recordings::{AggregatedPlayerStats, StatsFilter};
+use serde::{Deserialize, Serialize};
+
+/// One side of a comparison
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsSubject {
+    /// None for "me" (every one of my connect codes)
+    pub connect_code: Option<String>,
+    pub filter: Option<StatsFilter>,
+}
+
+/// One metric on both sides
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricComparison {
+    /// camelCase name, as in AggregatedPlayerStats (plus winRate)
+    pub metric: &'static str,
+    pub first: Option<f64>,
+    pub second: Option<f64>,
+    /// second - first
+    pub delta: Option<f64>,
+    /// delta as a percentage of first
+    pub percent_change: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsComparison {
+    /// None when the subject has no games
+    pub first: Option<AggregatedPlayerStats>,
+    pub second: Option<AggregatedPlayerStats>,
+    pub metrics: Vec<MetricComparison>,
+}
+
+/// A metric's name and how to read it
+type Metric = (&'static str, fn(&AggregatedPlayerStats) -> f64);
+
+/// Every compared metric, in display order
+const METRICS: &[Metric] = &[
+    ("totalGames", |s| s.total_games as f64),
+    ("totalWins", |s| s.total_wins as f64),
+    ("winRate", |s| s.total_wins as f64 / s.total_games as f64 * 100.0),
+    ("avgLCancelPercent", |s| s.avg_l_cancel_percent),
+    ("avgRollsPerGame", |s| s.avg_rolls_per_game),
+    ("avgOpeningsPerKill", |s| s.avg_openings_per_kill),
+    ("avgDamagePerOpening", |s| s.avg_damage_per_opening),
+    ("avgNeutralWins", |s| s.avg_neutral_wins),
+    ("avgInputsPerMinute", |s| s.avg_inputs_per_minute),
+];
+
+/// Pair up two sides' stats
+pub fn compare_stats(first: AggregatedPlayerStats, second: AggregatedPlayerStats) -> StatsComparison {
+    let first = Some(first).filter(|s| s.total_games > 0);
+    let second = Some(second).filter(|s| s.total_games > 0);
+    let metrics = METRICS
+        .iter()
+        .map(|(metric, value)| {
+            let (a, b) = (first.as_ref().map(value), second.as_ref().map(value));
+            let delta = a.zip(b).map(|(a, b)| b - a);
+            let percent_change = a.zip(delta).filter(|(a, _)| *a != 0.0).map(|(a, delta)| delta / a.abs() * 100.0);
+            MetricComparison { metric, first: a, second: b, delta, percent_change }
+        })
+        .collect();
+    StatsComparison { first, second, metrics }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(total_games: i64, total_wins: i64, avg_rolls_per_game: f64) -> AggregatedPlayerStats {
+        AggregatedPlayerStats {
+            total_games,
+            total_wins,
+            avg_l_cancel_percent: 0.0,
+            avg_rolls_per_game,
+            avg_openings_per_kill: 0.0,
+            avg_damage_per_opening: 0.0,
+            avg_neutral_wins: 0.0,
+            avg_inputs_per_minute: 0.0,
+            character_stats: Vec::new(),
+            stage_stats: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_changes_and_empty_subjects() {
+        let metric = |comparison: &StatsComparison, name: &str| {
+            comparison.metrics.iter().find(|m| m.metric == name).cloned().unwrap()
+        };
+
+        let comparison = compare_stats(stats(10, 5, 2.0), stats(20, 15, 3.0));
+        let win_rate = metric(&comparison, "winRate");
+        assert_eq!((win_rate.first, win_rate.second, win_rate.delta), (Some(50.0), Some(75.0), Some(25.0)));
+        assert_eq!(win_rate.percent_change, Some(50.0));
+        assert_eq!(metric(&comparison, "avgRollsPerGame").percent_change, Some(50.0));
+        // From 0: a change, but no percentage
+        let l_cancels = metric(&comparison, "avgLCancelPercent");
+        assert_eq!((l_cancels.delta, l_cancels.percent_change), (Some(0.0), None));
+
+        let comparison = compare_stats(stats(0, 0, 0.0), stats(4, 1, 1.0));
+        assert!(comparison.first.is_none() && comparison.second.is_some());
+        assert!(comparison.metrics.iter().all(|m| m.first.is_none() && m.delta.is_none() && m.percent_change.is_none()));
+        assert_eq!(metric(&comparison, "winRate").second, Some(25.0));
+    }
+}
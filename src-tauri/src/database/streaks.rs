@@ -0,0 +1,108 @@
+//! The current win or loss streak
+//!
+//! My streak goes by game_stats.my_result over every game, newest first;
+//! another player's by their result in the games they played. A game without
+//! a known result (not attributed to me, or without a winner) ends the streak
+//! rather than being skipped, and is named as what ended it. Games left out
+//! of the stats (CPU, too short) are left out here too.
+
+use super::recordings::{result_sql, MyResult, PlayerScope};
+use rusqlite::{params_from_iter, Connection};
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentStreak {
+    /// Win or loss; unknown when there are no games or the latest has no result
+    pub result: MyResult,
+    /// Games in a row with that result
+    pub length: u32,
+    /// The game without a known result that ended the streak, if that's what did
+    pub unknown_game: Option<String>,
+}
+
+impl CurrentStreak {
+    fn new() -> Self {
+        Self { result: MyResult::Unknown, length: 0, unknown_game: None }
+    }
+
+    /// Add the next older game. Returns whether the streak goes on
+    fn push(&mut self, recording_id: String, result: MyResult) -> bool {
+        if result == MyResult::Unknown {
+            self.unknown_game = Some(recording_id);
+            return false;
+        }
+        if self.length > 0 && result != self.result {
+            return false;
+        }
+        self.result = result;
+        self.length += 1;
+        true
+    }
+}
+
+/// The streak of my games (PlayerScope::Me) or a connect code's
+pub fn get_current_streak(conn: &Connection, scope: PlayerScope) -> rusqlite::Result<CurrentStreak> {
+    let (sql, param) = match scope {
+        PlayerScope::Me => (
+            "SELECT g.id, g.my_result FROM game_stats g
+             WHERE g.excluded_reason IS NULL
+             ORDER BY g.created_at DESC"
+                .to_string(),
+            None,
+        ),
+        scope => {
+            let (condition, param) = scope.condition(1);
+            (
+                format!(
+                    "SELECT g.id, {} FROM game_stats g JOIN player_stats p ON p.recording_id = g.id
+                     WHERE {} AND g.excluded_reason IS NULL
+                     ORDER BY g.created_at DESC",
+                    result_sql(),
+                    condition
+                ),
+                param,
+            )
+        }
+    };
+
+    let mut stmt = conn.prepare(&sql)?;
+    let mut rows = stmt.query(params_from_iter(param))?;
+    let mut streak = CurrentStreak::new();
+    while let Some(row) = rows.next()? {
+        if !streak.push(row.get(0)?, MyResult::from_column(&row.get::<_, String>(1)?)) {
+            break;
+        }
+    }
+    Ok(streak)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn streak(results: &[MyResult]) -> CurrentStreak {
+        let mut streak = CurrentStreak::new();
+        for (i, result) in results.iter().enumerate() {
+            if !streak.push(format!("game-{}", i), *result) {
+                break;
+            }
+        }
+        streak
+    }
+
+    #[test]
+    fn test_streak_runs_until_the_result_changes_or_is_unknown() {
+        use MyResult::{Loss, Unknown, Win};
+        assert_eq!(streak(&[]), CurrentStreak { result: Unknown, length: 0, unknown_game: None });
+        assert_eq!(streak(&[Win, Win, Loss, Win]), CurrentStreak { result: Win, length: 2, unknown_game: None });
+        assert_eq!(streak(&[Loss, Loss, Loss]), CurrentStreak { result: Loss, length: 3, unknown_game: None });
+
+        // An unattributed game isn't skipped over
+        let broken = streak(&[Loss, Unknown, Loss]);
+        assert_eq!((broken.result, broken.length, broken.unknown_game.as_deref()), (Loss, 1, Some("game-1")));
+        let latest_unknown = streak(&[Unknown, Win]);
+        assert_eq!((latest_unknown.result, latest_unknown.length), (Unknown, 0));
+        assert_eq!(latest_unknown.unknown_game.as_deref(), Some("game-0"));
+    }
+}
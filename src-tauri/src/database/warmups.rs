@@ -0,0 +1,128 @@
+//! Warm-up games: the first games of each play session
+//!
+//! Stored games are split into play sessions wherever there's a gap of more
+//! than `session_gap_minutes` between one game and the next, and the first
+//! `games` games of each session are flagged with is_warmup. Games already
+//! left out of stats (excluded_reason) don't count towards them. A game the
+//! user marked by hand (warmup_override) keeps their choice.
+//!
+//! Sessions shift whenever games are added or removed, so the flags are
+//! worked out again for every game, and only changed rows are written.
+
+use rusqlite::{params, Connection};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WarmupSettings {
+    /// Games flagged at the start of each session (0: none)
+    pub games: u32,
+    /// Longest break between two games of the same session
+    pub session_gap_minutes: u32,
+}
+
+/// Whether each game is a warm-up, for games in play order given as their
+/// start (Unix seconds, None if unknown) and whether they're excluded
+fn warmup_flags(games: &[(Option<i64>, bool)], settings: &WarmupSettings) -> Vec<bool> {
+    let gap = i64::from(settings.session_gap_minutes) * 60;
+    let mut last_start: Option<i64> = None;
+    let mut counted = 0;
+    games
+        .iter()
+        .map(|&(start, excluded)| {
+            let Some(start) = start else { return false };
+            if last_start.is_none_or(|last| start - last > gap) {
+                counted = 0;
+            }
+            last_start = Some(start);
+            if excluded || counted >= settings.games {
+                return false;
+            }
+            counted += 1;
+            true
+        })
+        .collect()
+}
+
+/// A stored game as recompute_warmups sees it
+struct StoredGame {
+    id: String,
+    start: Option<i64>,
+    excluded: bool,
+    is_warmup: bool,
+    warmup_override: Option<bool>,
+}
+
+/// Flag the warm-ups of every stored game again. Returns how many games are warm-ups
+pub fn recompute_warmups(conn: &Connection, settings: &WarmupSettings) -> rusqlite::Result<usize> {
+    let mut stmt = conn.prepare(
+        "SELECT id, created_at, excluded_reason IS NOT NULL, is_warmup, warmup_override FROM game_stats",
+    )?;
+    let mut rows: Vec<StoredGame> = stmt
+        .query_map([], |row| {
+            let created_at: Option<String> = row.get(1)?;
+            Ok(StoredGame {
+                id: row.get(0)?,
+                // Timestamps can carry different offsets, so they're compared as instants
+                start: created_at
+                    .as_deref()
+                    .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                    .map(|t| t.timestamp()),
+                excluded: row.get(2)?,
+                is_warmup: row.get(3)?,
+                warmup_override: row.get(4)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    rows.sort_by_key(|game| game.start);
+
+    let games: Vec<(Option<i64>, bool)> = rows.iter().map(|game| (game.start, game.excluded)).collect();
+    let flags = warmup_flags(&games, settings);
+
+    let tx = conn.unchecked_transaction()?;
+    let mut warmups = 0;
+    for (game, by_session) in rows.iter().zip(flags) {
+        let is_warmup = game.warmup_override.unwrap_or(by_session);
+        if is_warmup != game.is_warmup {
+            tx.execute("UPDATE game_stats SET is_warmup = ?2 WHERE id = ?1", params![game.id, is_warmup])?;
+        }
+        warmups += usize::from(is_warmup);
+    }
+    tx.commit()?;
+    Ok(warmups)
+}
+
+/// Mark a game as a warm-up or not by hand, or (None) go back to its session.
+/// Returns whether the game exists
+pub fn set_warmup_override(conn: &Connection, recording_id: &str, is_warmup: Option<bool>) -> rusqlite::Result<bool> {
+    Ok(conn.execute(
+        "UPDATE game_stats SET warmup_override = ?2 WHERE id = ?1",
+        params![recording_id, is_warmup],
+    )? > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_games_of_each_session() {
+        let settings = WarmupSettings { games: 2, session_gap_minutes: 60 };
+        let minutes = |m: i64| Some(m * 60);
+        let games = [
+            (minutes(0), false),
+            // Too short to count, so the next game is the second warm-up
+            (minutes(5), true),
+            (minutes(10), false),
+            (minutes(20), false),
+            (None, false),
+            // A new session after more than an hour
+            (minutes(90), false),
+            (minutes(100), false),
+            (minutes(150), false),
+        ];
+        assert_eq!(
+            warmup_flags(&games, &settings),
+            vec![true, false, true, false, false, true, true, false]
+        );
+        assert!(warmup_flags(&games, &WarmupSettings { games: 0, ..settings }).iter().all(|w| !w));
+    }
+}
@@ -0,0 +1,195 @@
+//! Write-behind queue for bookkeeping writes
+//!
+//! While a game is recorded the watcher, the recording rules and the library
+//! sync all write to SQLite, and on a slow disk a write can hold the
+//! connection long enough to delay the stop trigger. Writes nothing waits on
+//! (rule log, replay hashes, replay ids) are queued instead and applied
+//! together in one transaction by a background thread, less often while
+//! recording. Writes a caller reads back right away (the recording row at
+//! finalize) still go straight to the connection.
+//!
+//! Each write runs in its own savepoint, so one failing halfway leaves none of
+//! its statements behind. When the transaction can't commit, its writes are
+//! queued again for the next flush, up to MAX_COMMIT_ATTEMPTS times.
+
+use super::Database;
+use rusqlite::Connection;
+use std::sync::{Mutex, Weak};
+use std::time::Duration;
+
+/// How often queued writes are applied
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+/// How often while recording
+const RECORDING_FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+/// Flushes a write is tried in before it's dropped, when its transaction
+/// keeps failing to commit
+const MAX_COMMIT_ATTEMPTS: u32 = 3;
+
+type Write = Box<dyn Fn(&Connection) -> Result<(), rusqlite::Error> + Send>;
+
+/// A write waiting to be applied, with what it's for (logged on failure)
+struct Queued {
+    label: &'static str,
+    write: Write,
+    commit_attempts: u32,
+}
+
+#[derive(Default)]
+pub(super) struct WriteQueue {
+    writes: Mutex<Vec<Queued>>,
+}
+
+impl WriteQueue {
+    pub(super) fn push(&self, label: &'static str, write: Write) {
+        if let Ok(mut writes) = self.writes.lock() {
+            writes.push(Queued { label, write, commit_attempts: 0 });
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.writes.lock().map(|writes| writes.len()).unwrap_or(0)
+    }
+
+    fn take(&self) -> Vec<Queued> {
+        self.writes.lock().map(|mut writes| std::mem::take(&mut *writes)).unwrap_or_default()
+    }
+
+    /// Put writes back ahead of the ones queued since they were taken
+    fn requeue(&self, mut writes: Vec<Queued>) {
+        if let Ok(mut queued) = self.writes.lock() {
+            writes.append(&mut queued);
+            *queued = writes;
+        }
+    }
+}
+
+impl Database {
+    /// Queue a write nothing reads back right away; it's applied within a few
+    /// seconds (or on exit). Failures are logged
+    pub fn write_behind(
+        &self,
+        label: &'static str,
+        write: impl Fn(&Connection) -> Result<(), rusqlite::Error> + Send + 'static,
+    ) {
+        self.writes.push(label, Box::new(write));
+    }
+
+    /// Writes queued and not applied yet
+    pub fn pending_writes(&self) -> usize {
+        self.writes.len()
+    }
+
+    /// Apply the queued writes in one transaction, each in its own savepoint.
+    /// Returns how many were applied
+    pub fn flush_writes(&self) -> usize {
+        let writes = self.writes.take();
+        if writes.is_empty() {
+            return 0;
+        }
+
+        let mut conn = self.connection();
+        let mut tx = match conn.transaction() {
+            Ok(tx) => tx,
+            Err(e) => {
+                log::error!("❌ Failed to apply {} queued write(s), retrying later: {}", writes.len(), e);
+                self.writes.requeue(writes);
+                return 0;
+            }
+        };
+        let mut applied = Vec::with_capacity(writes.len());
+        for queued in writes {
+            // Dropping the savepoint of a failed write rolls back what it did
+            let result = tx.savepoint().and_then(|savepoint| {
+                (queued.write)(&savepoint)?;
+                savepoint.commit()
+            });
+            match result {
+                Ok(()) => applied.push(queued),
+                Err(e) => log::warn!("Failed to write {}: {}", queued.label, e),
+            }
+        }
+        if let Err(e) = tx.commit() {
+            let (retry, dropped): (Vec<_>, Vec<_>) = applied
+                .into_iter()
+                .map(|queued| Queued { commit_attempts: queued.commit_attempts + 1, ..queued })
+                .partition(|queued| queued.commit_attempts < MAX_COMMIT_ATTEMPTS);
+            log::error!(
+                "❌ Failed to commit queued writes ({} to retry, {} dropped): {}",
+                retry.len(),
+                dropped.len(),
+                e
+            );
+            for queued in &dropped {
+                log::error!("Dropped queued write {} after {} failed commits", queued.label, queued.commit_attempts);
+            }
+            self.writes.requeue(retry);
+            return 0;
+        }
+        log::debug!("💾 Applied {} queued write(s)", applied.len());
+        applied.len()
+    }
+}
+
+/// Apply queued writes in the background until the database is dropped
+pub fn spawn_write_flusher(db: Weak<Database>) {
+    let spawned = std::thread::Builder::new().name("db-write-flusher".into()).spawn(move || loop {
+        let interval = if crate::work_gate::recording() { RECORDING_FLUSH_INTERVAL } else { FLUSH_INTERVAL };
+        std::thread::sleep(interval);
+        let Some(db) = db.upgrade() else { return };
+        db.flush_writes();
+    });
+    if let Err(e) = spawned {
+        log::error!("Failed to start the database write flusher: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queued_writes_apply_together_and_skip_failures() {
+        let db = Database::open_in_memory().unwrap();
+        db.connection().execute_batch("CREATE TABLE queued (value INTEGER NOT NULL)").unwrap();
+
+        for value in 0..3 {
+            db.write_behind("test row", move |conn| conn.execute("INSERT INTO queued VALUES (?)", [value]).map(|_| ()));
+        }
+        db.write_behind("bad row", |conn| conn.execute("INSERT INTO missing VALUES (1)", []).map(|_| ()));
+        let count = || db.connection().query_row("SELECT COUNT(*) FROM queued", [], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!((db.pending_writes(), count()), (4, 0));
+
+        // A failed write doesn't roll back the others
+        assert_eq!(db.flush_writes(), 3);
+        assert_eq!((db.pending_writes(), count()), (0, 3));
+        assert_eq!(db.flush_writes(), 0);
+
+        // Nor is a write that fails halfway partly applied
+        db.write_behind("half row", |conn| {
+            conn.execute("INSERT INTO queued VALUES (10)", [])?;
+            conn.execute("INSERT INTO missing VALUES (1)", []).map(|_| ())
+        });
+        assert_eq!(db.flush_writes(), 0);
+        assert_eq!(count(), 3);
+    }
+
+    #[test]
+    fn test_writes_are_queued_again_when_the_commit_fails() {
+        let db = Database::open_in_memory().unwrap();
+        db.connection()
+            .execute_batch(
+                "CREATE TABLE parent (id INTEGER PRIMARY KEY);
+                 CREATE TABLE child (parent_id INTEGER REFERENCES parent(id) DEFERRABLE INITIALLY DEFERRED);
+                 PRAGMA foreign_keys = ON;",
+            )
+            .unwrap();
+        // Valid on its own, but the deferred foreign key fails the commit
+        db.write_behind("orphan row", |conn| conn.execute("INSERT INTO child VALUES (1)", []).map(|_| ()));
+
+        assert_eq!(db.flush_writes(), 0);
+        assert_eq!(db.pending_writes(), 1);
+        db.connection().execute("INSERT INTO parent VALUES (1)", []).unwrap();
+        assert_eq!(db.flush_writes(), 1);
+        assert_eq!(db.pending_writes(), 0);
+    }
+}
@@ -0,0 +1,152 @@
+//! buckwheat:// links, used by the cloud dashboard's "Open in app" buttons
+//!
+//! - `buckwheat://recording/<id>` opens a recording's replay view
+//! - `buckwheat://clip/<hash>` opens a clip, where the hash is the hex SHA-256
+//!   of the clip's video path (clip ids aren't stable across re-indexing)
+//!
+//! Links come from outside the app, so anything that isn't exactly one of
+//! these shapes, or points at something not in the library, is logged and
+//! dropped. The resolved target is parked in app state and the frontend is
+//! told to pick it up; that also covers links that launched the app before
+//! the frontend was listening.
+
+use crate::app_state::AppState;
+use crate::database::{self, Database};
+use crate::events;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::{Emitter, Manager, Url};
+use uuid::Uuid;
+
+pub const SCHEME: &str = "buckwheat";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DeepLink {
+    Recording(String),
+    Clip(String),
+}
+
+/// Where the frontend should navigate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NavigateTarget {
+    pub recording_id: String,
+    pub is_clip: bool,
+}
+
+/// Handle URLs the app was opened with
+pub fn handle_urls(app: &tauri::AppHandle, urls: Vec<Url>) {
+    let state = app.state::<AppState>();
+    let Some(target) = urls.iter().rev().find_map(|url| resolve_url(&state.database, url)) else {
+        return;
+    };
+
+    log::info!("🔗 Opening {} {} from link", if target.is_clip { "clip" } else { "recording" }, target.recording_id);
+    if let Ok(mut pending) = state.pending_deep_link.lock() {
+        *pending = Some(target.clone());
+    }
+    if let Err(e) = app.emit(events::navigation::DEEP_LINK, &target) {
+        log::warn!("Failed to emit deep link event: {}", e);
+    }
+    crate::focus_main_window(app);
+}
+
+fn resolve_url(db: &Database, url: &Url) -> Option<NavigateTarget> {
+    let Some(link) = parse(url) else {
+        log::warn!("Ignoring unrecognized link: {}", url);
+        return None;
+    };
+
+    let conn = db.connection();
+    let target = match &link {
+        DeepLink::Recording(id) => database::get_recording_by_id(&conn, id)
+            .ok()
+            .flatten()
+            .map(|row| NavigateTarget { recording_id: row.id, is_clip: false }),
+        // Clips are recordings in the Clips folder (same rule as get_clips)
+        DeepLink::Clip(hash) => database::get_all_recordings(&conn)
+            .ok()?
+            .into_iter()
+            .find(|row| row.video_path.as_deref().is_some_and(|path| path.contains("Clips") && path_hash(path) == *hash))
+            .map(|row| NavigateTarget { recording_id: row.id, is_clip: true }),
+    };
+
+    if target.is_none() {
+        log::warn!("Ignoring link to something not in the library: {}", url);
+    }
+    target
+}
+
+/// Hash used in clip links
+pub fn path_hash(video_path: &str) -> String {
+    Sha256::digest(video_path.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn parse(url: &Url) -> Option<DeepLink> {
+    if url.scheme() != SCHEME
+        || !url.username().is_empty()
+        || url.password().is_some()
+        || url.port().is_some()
+        || url.query().is_some()
+        || url.fragment().is_some()
+    {
+        return None;
+    }
+
+    // Windows may append a trailing slash when launching the handler
+    let mut segments: Vec<&str> = url.path_segments()?.collect();
+    if segments.len() == 2 && segments[1].is_empty() {
+        segments.pop();
+    }
+    let [id] = segments.as_slice() else {
+        return None;
+    };
+
+    match url.host_str()? {
+        "recording" => Uuid::parse_str(id).ok().map(|id| DeepLink::Recording(id.to_string())),
+        "clip" if id.len() == 64 && id.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            Some(DeepLink::Clip(id.to_ascii_lowercase()))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(url: &str) -> Option<DeepLink> {
+        parse(&Url::parse(url).ok()?)
+    }
+
+    #[test]
+    fn test_parse_accepts_only_known_links() {
+        let id = "0b6f3c1e-6f0a-4d55-9a5e-2f1f4c7d8e90";
+        let hash = path_hash("C:\\Videos\\Clips\\Clip_1.mp4");
+
+        assert_eq!(parse_str(&format!("buckwheat://recording/{}", id)), Some(DeepLink::Recording(id.to_string())));
+        assert_eq!(parse_str(&format!("buckwheat://recording/{}/", id)), Some(DeepLink::Recording(id.to_string())));
+        assert_eq!(
+            parse_str(&format!("buckwheat://clip/{}", hash.to_uppercase())),
+            Some(DeepLink::Clip(hash.clone()))
+        );
+
+        for bad in [
+            format!("https://recording/{}", id),
+            format!("buckwheat://settings/{}", id),
+            format!("buckwheat://recording/{}?delete=1", id),
+            format!("buckwheat://recording/{}#x", id),
+            format!("buckwheat://user@recording/{}", id),
+            format!("buckwheat://recording/{}/extra", id),
+            "buckwheat://recording/not-a-uuid".to_string(),
+            "buckwheat://recording/".to_string(),
+            "buckwheat://clip/..%2F..%2Fetc".to_string(),
+            format!("buckwheat://clip/{}", &hash[..32]),
+        ] {
+            assert_eq!(parse_str(&bad), None, "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,381 @@
+//! Log files and diagnostics bundles for bug reports
+//!
+//! Logs go to rotating files under `<app data>/logs` in every build (stdout
+//! is only added in debug builds). `export_diagnostics` zips the latest log
+//! files, the settings, some system info and the command timings into one
+//! file a user can attach to a report.
+//!
+//! Everything that leaves the machine this way goes through the redaction
+//! pass first: passwords, tokens, webhook URLs and connect codes are replaced
+//! whether they show up as settings or inside log lines.
+
+use crate::app_state::AppState;
+use crate::clip_processor::{self, HwAccelStatus};
+use crate::commands::errors::Error;
+use crate::database::MigrationStatus;
+use crate::metrics::MetricSummary;
+use regex::Regex;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use tauri::plugin::TauriPlugin;
+use tauri::Manager;
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind, TimezoneStrategy};
+use tauri_plugin_store::StoreExt;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+const LOG_FILE_NAME: &str = "peppi";
+/// Rotate at 5 MB, keeping the current file and four older ones
+const MAX_LOG_FILE_SIZE: u128 = 5 * 1024 * 1024;
+const LOG_FILES_KEPT: usize = 5;
+
+/// Upper bound for get_recent_logs
+pub const MAX_RECENT_LOG_LINES: usize = 5000;
+
+const REDACTED: &str = "[redacted]";
+
+/// Settings whose values are secret or identify the player
+const SENSITIVE_KEY_PARTS: &[&str] = &[
+    "password", "token", "secret", "auth", "apikey", "api_key", "hook", "connectcode", "connect_code",
+    "slippicode", "ingametag",
+];
+
+static CONNECT_CODE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b[A-Z0-9]{1,7}(#|＃)[0-9]{1,4}\b").unwrap());
+static JWT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\beyJ[A-Za-z0-9_-]*\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*").unwrap());
+static BEARER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\b(bearer)\s+[^\s,;]+").unwrap());
+static SECRET_PARAM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\b([a-z_]*(?:token|password|secret|api_?key)[a-z_]*)(["']?\s*[=:]\s*["']?)[^\s&"',;]+"#).unwrap()
+});
+static URL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\bhttps?://[^\s\x22']+").unwrap());
+
+/// Directory the log files are written to
+pub fn log_directory(app: &tauri::AppHandle) -> Result<PathBuf, Error> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("logs"))
+        .map_err(|e| Error::InitializationError(format!("Failed to get app data directory: {}", e)))
+}
+
+/// Logger for all builds: our own logs at info, dependencies only when they warn
+pub fn log_plugin(app: &tauri::AppHandle) -> TauriPlugin<tauri::Wry> {
+    let file_target = match log_directory(app) {
+        Ok(path) => TargetKind::Folder { path, file_name: Some(LOG_FILE_NAME.to_string()) },
+        Err(_) => TargetKind::LogDir { file_name: Some(LOG_FILE_NAME.to_string()) },
+    };
+
+    let mut builder = tauri_plugin_log::Builder::default()
+        .clear_targets()
+        .target(Target::new(file_target))
+        .rotation_strategy(RotationStrategy::KeepSome(LOG_FILES_KEPT))
+        .max_file_size(MAX_LOG_FILE_SIZE)
+        .timezone_strategy(TimezoneStrategy::UseLocal)
+        .level(log::LevelFilter::Warn)
+        .level_for("peppi_lib", log::LevelFilter::Info);
+    if cfg!(debug_assertions) {
+        builder = builder.target(Target::new(TargetKind::Stdout));
+    }
+    builder.build()
+}
+
+/// The last `lines` log lines across the log files, oldest first, redacted
+pub fn recent_logs(log_dir: &Path, lines: usize) -> Vec<String> {
+    let mut recent: Vec<String> = Vec::new();
+    for file in log_files(log_dir).iter().rev() {
+        let Ok(contents) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        let mut older: Vec<String> = contents.lines().rev().take(lines - recent.len()).map(redact_text).collect();
+        older.reverse();
+        older.append(&mut recent);
+        recent = older;
+        if recent.len() >= lines {
+            break;
+        }
+    }
+    recent
+}
+
+/// Log files, oldest first (rotated files are named peppi_<date>.log)
+fn log_files(log_dir: &Path) -> Vec<PathBuf> {
+    let current = format!("{}.log", LOG_FILE_NAME);
+    let mut rotated: Vec<PathBuf> = std::fs::read_dir(log_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            name.starts_with(&format!("{}_", LOG_FILE_NAME)) && name.ends_with(".log")
+        })
+        .collect();
+    rotated.sort();
+
+    let current = log_dir.join(current);
+    if current.exists() {
+        rotated.push(current);
+    }
+    rotated
+}
+
+/// Contents of system.json
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SystemInfo {
+    app_version: String,
+    exported_at: String,
+    os: String,
+    kernel: Option<String>,
+    arch: String,
+    cpu: Option<String>,
+    memory_mb: u64,
+    gpus: Vec<String>,
+    ffmpeg: Option<String>,
+    hwaccel: HwAccelStatus,
+    database: MigrationStatus,
+    /// Database writes queued and not applied yet
+    pending_writes: usize,
+}
+
+/// Zip the log files, redacted settings and system info into `dest_path`
+pub async fn export_diagnostics(app: &tauri::AppHandle, dest_path: &Path) -> Result<(), Error> {
+    let log_dir = log_directory(app)?;
+
+    let mut settings = app
+        .store("settings.json")
+        .map(|store| serde_json::Value::Object(store.entries().into_iter().collect()))
+        .unwrap_or_default();
+    redact_settings(&mut settings);
+
+    let state = app.state::<AppState>();
+    let database = state.database.check_migrations();
+    let pending_writes = state.database.pending_writes();
+    let metrics = state.metrics.snapshot();
+    let app_version = app.package_info().version.to_string();
+
+    log::info!("🩺 Exporting diagnostics to {}", dest_path.display());
+
+    let dest_path = dest_path.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || {
+        let system = system_info(app_version, database, pending_writes);
+        write_diagnostics(&dest_path, &log_files(&log_dir), &settings, &system, &metrics)
+    })
+    .await
+    .map_err(|e| Error::RecordingFailed(format!("Diagnostics export task failed: {}", e)))?
+}
+
+fn write_diagnostics(
+    dest_path: &Path,
+    log_files: &[PathBuf],
+    settings: &serde_json::Value,
+    system: &SystemInfo,
+    metrics: &[MetricSummary],
+) -> Result<(), Error> {
+    let partial_path = dest_path.with_extension("zip.part");
+
+    let result = (|| -> Result<(), Error> {
+        let mut zip = ZipWriter::new(BufWriter::new(File::create(&partial_path)?));
+        let options = SimpleFileOptions::default();
+
+        zip.start_file("system.json", options).map_err(zip_error)?;
+        serde_json::to_writer_pretty(&mut zip, system).map_err(std::io::Error::from)?;
+
+        zip.start_file("settings.json", options).map_err(zip_error)?;
+        serde_json::to_writer_pretty(&mut zip, settings).map_err(std::io::Error::from)?;
+
+        zip.start_file("metrics.json", options).map_err(zip_error)?;
+        serde_json::to_writer_pretty(&mut zip, metrics).map_err(std::io::Error::from)?;
+
+        for path in log_files {
+            let Ok(contents) = std::fs::read_to_string(path) else {
+                continue;
+            };
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or(LOG_FILE_NAME);
+            zip.start_file(format!("logs/{}", name), options).map_err(zip_error)?;
+            zip.write_all(redact_text(&contents).as_bytes())?;
+        }
+
+        zip.finish().map_err(zip_error)?.flush()?;
+        std::fs::rename(&partial_path, dest_path)?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial_path);
+    }
+    result
+}
+
+fn zip_error(e: zip::result::ZipError) -> Error {
+    match e {
+        zip::result::ZipError::Io(e) => Error::Io(e),
+        e => Error::RecordingFailed(format!("Failed to write diagnostics: {}", e)),
+    }
+}
+
+fn system_info(app_version: String, database: MigrationStatus, pending_writes: usize) -> SystemInfo {
+    let mut sys = sysinfo::System::new();
+    sys.refresh_cpu_all();
+    sys.refresh_memory();
+
+    SystemInfo {
+        app_version,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        os: sysinfo::System::long_os_version().unwrap_or_else(|| std::env::consts::OS.to_string()),
+        kernel: sysinfo::System::kernel_version(),
+        arch: std::env::consts::ARCH.to_string(),
+        cpu: sys.cpus().first().map(|cpu| cpu.brand().trim().to_string()),
+        memory_mb: sys.total_memory() / (1024 * 1024),
+        gpus: gpu_names(),
+        ffmpeg: ffmpeg_sidecar::version::ffmpeg_version().ok(),
+        hwaccel: clip_processor::hwaccel_status(),
+        database,
+        pending_writes,
+    }
+}
+
+/// Command listing the display adapters
+#[cfg(target_os = "windows")]
+const GPU_QUERY: (&str, &[&str]) = (
+    "powershell",
+    &["-NoProfile", "-Command", "(Get-CimInstance Win32_VideoController).Name"],
+);
+#[cfg(target_os = "macos")]
+const GPU_QUERY: (&str, &[&str]) = ("system_profiler", &["SPDisplaysDataType"]);
+#[cfg(target_os = "linux")]
+const GPU_QUERY: (&str, &[&str]) = ("lspci", &[]);
+
+/// Display adapters as reported by the OS (empty if the query fails)
+fn gpu_names() -> Vec<String> {
+    let (program, args) = GPU_QUERY;
+    let mut command = std::process::Command::new(program);
+    command.args(args);
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let Ok(output) = command.output() else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| gpu_name(line.trim()))
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Pick the adapter name out of a line of GPU_QUERY output
+fn gpu_name(line: &str) -> Option<&str> {
+    #[cfg(target_os = "windows")]
+    {
+        Some(line)
+    }
+    #[cfg(target_os = "macos")]
+    {
+        line.strip_prefix("Chipset Model:")
+    }
+    #[cfg(target_os = "linux")]
+    {
+        // e.g. "01:00.0 VGA compatible controller: NVIDIA Corporation ..."
+        let (_, rest) = line.split_once(' ')?;
+        let (class, name) = rest.split_once(": ")?;
+        (class.contains("VGA") || class.contains("3D") || class.contains("Display")).then_some(name)
+    }
+}
+
+/// Replace secret and identifying settings values (recursively)
+pub fn redact_settings(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let key = key.to_lowercase();
+                let is_set = !(value.is_null() || value.as_str().is_some_and(str::is_empty));
+                if is_set && SENSITIVE_KEY_PARTS.iter().any(|part| key.contains(part)) {
+                    *value = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact_settings(value);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_settings),
+        serde_json::Value::String(text) => *text = redact_text(text),
+        _ => {}
+    }
+}
+
+/// Scrub connect codes, tokens, secrets and URLs from free text (log lines)
+pub fn redact_text(text: &str) -> String {
+    let text = JWT.replace_all(text, REDACTED);
+    let text = BEARER.replace_all(&text, format!("$1 {}", REDACTED));
+    let text = SECRET_PARAM.replace_all(&text, format!("$1$2{}", REDACTED));
+    // Webhook and signed URLs carry credentials in the path; keep only the host
+    let text = URL.replace_all(&text, |caps: &regex::Captures| match tauri::Url::parse(&caps[0]) {
+        Ok(url) if url.path() != "/" || url.query().is_some() => {
+            format!("{}://{}/{}", url.scheme(), url.host_str().unwrap_or_default(), REDACTED)
+        }
+        _ => caps[0].to_string(),
+    });
+    CONNECT_CODE.replace_all(&text, "[connect code]").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redaction_scrubs_secrets_and_connect_codes() {
+        let line = "🎮 Game started: ABCD#123 vs XY＃9 (token=abc123&page=2) Authorization: Bearer sk-live-42 \
+                    session eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiIxIn0.c2ln posted to https://discord.com/api/webhooks/1/secret";
+        let redacted = redact_text(line);
+        for secret in ["ABCD#123", "XY＃9", "abc123", "sk-live-42", "eyJhbGci", "webhooks/1/secret"] {
+            assert!(!redacted.contains(secret), "{} leaked: {}", secret, redacted);
+        }
+        assert!(redacted.contains("page=2"));
+        assert!(redacted.contains("https://discord.com/[redacted]"));
+        // Not connect codes
+        assert_eq!(redact_text("Game #3 at 1920x1080"), "Game #3 at 1920x1080");
+
+        let mut settings = json!({
+            "obsPassword": "hunter2",
+            "obsHost": "localhost",
+            "postRecordingHook": "curl https://example.com/hook?key=1",
+            "slippiCode": "HATS#982",
+            "myConnectCodes": ["HATS#982"],
+            "overlayServerPort": 4455,
+            "recordingPath": "C:\\Users\\me\\Videos",
+            "meleeIsoPath": "",
+            "nested": { "accessToken": "abc" },
+        });
+        redact_settings(&mut settings);
+        assert_eq!(settings["obsPassword"], REDACTED);
+        assert_eq!(settings["postRecordingHook"], REDACTED);
+        assert_eq!(settings["slippiCode"], REDACTED);
+        assert_eq!(settings["myConnectCodes"], REDACTED);
+        assert_eq!(settings["nested"]["accessToken"], REDACTED);
+        assert_eq!(settings["obsHost"], "localhost");
+        assert_eq!(settings["overlayServerPort"], 4455);
+        assert_eq!(settings["meleeIsoPath"], "");
+    }
+
+    #[test]
+    fn test_recent_logs_reads_across_rotated_files() {
+        let dir = std::env::temp_dir().join(format!("peppi-logs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("peppi_2026-01-01_10-00-00.log"), "one\ntwo\n").unwrap();
+        std::fs::write(dir.join("peppi.log"), "three\nfour ABCD#123\n").unwrap();
+        std::fs::write(dir.join("other.log"), "ignored\n").unwrap();
+
+        assert_eq!(recent_logs(&dir, 3), vec!["two", "three", "four [connect code]"]);
+        assert_eq!(recent_logs(&dir, 100).len(), 4);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
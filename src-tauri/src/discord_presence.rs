@@ -0,0 +1,313 @@
+//! Discord Rich Presence for the current session
+//!
+//! Shows "Recording — Fox vs Marth" / "Game 3" while a game or recording is
+//! active and clears it when idle. The Discord IPC socket is blocking, so all
+//! calls happen on a dedicated thread fed through a channel; event handlers
+//! only send a message. If Discord isn't running (or restarts) the worker keeps
+//! retrying in the background.
+//!
+//! The Discord application id is taken from DISCORD_CLIENT_ID at build time;
+//! builds without it never connect.
+
+use crate::database::MyIdentity;
+use crate::game_session::my_player;
+use crate::slippi::game_start::GameStart;
+use crate::slippi::melee_ids;
+use discord_rich_presence::activity::{Activity, Timestamps};
+use discord_rich_presence::{DiscordIpc, DiscordIpcClient};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+const DISCORD_CLIENT_ID: Option<&str> = option_env!("DISCORD_CLIENT_ID");
+
+/// How often to reconnect to Discord and refresh the activity
+const REFRESH_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Presence settings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PresenceSettings {
+    pub enabled: bool,
+    /// Include the opponent's connect code (off by default for privacy)
+    pub show_opponent: bool,
+}
+
+enum PresenceMessage {
+    Settings(PresenceSettings),
+    /// The new game's Game Start, None if it wasn't written in time
+    GameStarted { game: Option<GameStart>, identity: MyIdentity },
+    GameEnded,
+    RecordingStarted,
+    RecordingStopped,
+}
+
+/// What to show on Discord
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PresenceStatus {
+    details: String,
+    state: Option<String>,
+    /// Unix timestamp for Discord's elapsed timer
+    started_at: i64,
+}
+
+/// The live game, from my point of view
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LiveGame {
+    my_character: Option<u8>,
+    opponent_character: Option<u8>,
+    opponent_code: Option<String>,
+    /// Consecutive games against the same opponent
+    game_number: u32,
+    started_at: i64,
+}
+
+#[derive(Debug, Default)]
+struct Session {
+    settings: PresenceSettings,
+    recording_since: Option<i64>,
+    game: Option<LiveGame>,
+    /// Opponent and count of the previous game, for the game number
+    last_opponent: Option<(String, u32)>,
+}
+
+impl Session {
+    fn apply(&mut self, message: PresenceMessage) {
+        match message {
+            PresenceMessage::Settings(settings) => self.settings = settings,
+            PresenceMessage::GameStarted { game, identity } => {
+                self.game = Some(self.live_game(game.as_ref(), &identity));
+            }
+            PresenceMessage::GameEnded => self.game = None,
+            PresenceMessage::RecordingStarted => self.recording_since = Some(now()),
+            PresenceMessage::RecordingStopped => self.recording_since = None,
+        }
+    }
+
+    fn live_game(&mut self, game: Option<&GameStart>, identity: &MyIdentity) -> LiveGame {
+        let players = game.map(|g| g.players.as_slice()).unwrap_or_default();
+
+        // Me by connect code, then preferred character, else port order
+        let me = my_player(players, identity).unwrap_or(0);
+        let opponent = players.iter().enumerate().find(|(i, _)| *i != me).map(|(_, p)| p);
+
+        // Same opponent (by code, or by character locally) means the set continues
+        let opponent_key = opponent.map(|p| {
+            p.connect_code
+                .clone()
+                .unwrap_or_else(|| melee_ids::character_name(p.character_id).to_string())
+        });
+        let game_number = match (&opponent_key, &self.last_opponent) {
+            (Some(key), Some((last, count))) if key == last => count + 1,
+            _ => 1,
+        };
+        self.last_opponent = opponent_key.map(|key| (key, game_number));
+
+        LiveGame {
+            my_character: players.get(me).map(|p| p.character_id),
+            opponent_character: opponent.map(|p| p.character_id),
+            opponent_code: opponent.and_then(|p| p.connect_code.clone()),
+            game_number,
+            started_at: now(),
+        }
+    }
+
+    /// What should be shown right now, None when idle or disabled
+    fn status(&self) -> Option<PresenceStatus> {
+        if !self.settings.enabled {
+            return None;
+        }
+
+        let activity = if self.recording_since.is_some() { "Recording" } else { "Playing" };
+        match &self.game {
+            Some(game) => {
+                let matchup = match (game.my_character, game.opponent_character) {
+                    (Some(me), Some(opponent)) => {
+                        format!("{} vs {}", melee_ids::character_name(me), melee_ids::character_name(opponent))
+                    }
+                    (Some(me), None) => melee_ids::character_name(me).to_string(),
+                    _ => "In game".to_string(),
+                };
+                let opponent = game
+                    .opponent_code
+                    .as_ref()
+                    .filter(|_| self.settings.show_opponent)
+                    .map(|code| format!(" vs {}", code))
+                    .unwrap_or_default();
+
+                Some(PresenceStatus {
+                    details: format!("{} — {}", activity, matchup),
+                    state: Some(format!("Game {}{}", game.game_number, opponent)),
+                    started_at: game.started_at,
+                })
+            }
+            None => self.recording_since.map(|since| PresenceStatus {
+                details: activity.to_string(),
+                state: Some("Between games".to_string()),
+                started_at: since,
+            }),
+        }
+    }
+}
+
+/// Handle to the presence worker thread
+pub struct DiscordPresence {
+    sender: Sender<PresenceMessage>,
+}
+
+impl DiscordPresence {
+    /// Start the worker thread
+    pub fn start(settings: PresenceSettings) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let _ = sender.send(PresenceMessage::Settings(settings));
+
+        match DISCORD_CLIENT_ID {
+            Some(client_id) => {
+                std::thread::Builder::new()
+                    .name("discord-presence".to_string())
+                    .spawn(move || run(receiver, client_id))
+                    .map_err(|e| log::warn!("Failed to start Discord presence thread: {}", e))
+                    .ok();
+            }
+            None => log::debug!("Discord presence unavailable: built without DISCORD_CLIENT_ID"),
+        }
+
+        Self { sender }
+    }
+
+    pub fn update_settings(&self, settings: PresenceSettings) {
+        self.send(PresenceMessage::Settings(settings));
+    }
+
+    pub fn game_started(&self, game: Option<GameStart>, identity: MyIdentity) {
+        self.send(PresenceMessage::GameStarted { game, identity });
+    }
+
+    pub fn game_ended(&self) {
+        self.send(PresenceMessage::GameEnded);
+    }
+
+    pub fn recording_started(&self) {
+        self.send(PresenceMessage::RecordingStarted);
+    }
+
+    pub fn recording_stopped(&self) {
+        self.send(PresenceMessage::RecordingStopped);
+    }
+
+    fn send(&self, message: PresenceMessage) {
+        // Fails only if the worker isn't running (no client id)
+        let _ = self.sender.send(message);
+    }
+}
+
+fn run(receiver: Receiver<PresenceMessage>, client_id: &str) {
+    let mut session = Session::default();
+    let mut client: Option<DiscordIpcClient> = None;
+    let mut shown: Option<PresenceStatus> = None;
+
+    loop {
+        let refresh = match receiver.recv_timeout(REFRESH_INTERVAL) {
+            Ok(message) => {
+                session.apply(message);
+                false
+            }
+            Err(RecvTimeoutError::Timeout) => true,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        let status = session.status();
+        if status == shown && !refresh {
+            continue;
+        }
+
+        match &status {
+            // Closing the connection clears the presence
+            None => disconnect(&mut client),
+            Some(status) => {
+                if let Err(e) = show(&mut client, client_id, status) {
+                    log::debug!("Discord presence update failed (will retry): {}", e);
+                    disconnect(&mut client);
+                    shown = None;
+                    continue;
+                }
+            }
+        }
+        shown = status;
+    }
+
+    disconnect(&mut client);
+}
+
+fn show(client: &mut Option<DiscordIpcClient>, client_id: &str, status: &PresenceStatus) -> Result<(), String> {
+    if client.is_none() {
+        let mut new_client = DiscordIpcClient::new(client_id);
+        new_client.connect().map_err(|e| e.to_string())?;
+        log::info!("🎮 Connected to Discord");
+        *client = Some(new_client);
+    }
+    let Some(client) = client.as_mut() else {
+        return Ok(());
+    };
+
+    let mut activity = Activity::new()
+        .details(status.details.as_str())
+        .timestamps(Timestamps::new().start(status.started_at));
+    if let Some(state) = &status.state {
+        activity = activity.state(state.as_str());
+    }
+    client.set_activity(activity).map_err(|e| e.to_string())
+}
+
+fn disconnect(client: &mut Option<DiscordIpcClient>) {
+    if let Some(mut client) = client.take() {
+        let _ = client.close();
+    }
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slippi::game_start::GameStartPlayer;
+
+    fn game(opponent_code: &str) -> GameStart {
+        GameStart {
+            version: crate::slippi::ReplayVersion(3, 16, 0),
+            stage: 31,
+            is_teams: false,
+            players: vec![
+                GameStartPlayer { port: 0, character_id: 9, connect_code: Some(opponent_code.to_string()), is_cpu: false },
+                GameStartPlayer { port: 1, character_id: 2, connect_code: Some("ME#1".to_string()), is_cpu: false },
+            ],
+            match_id: None,
+        }
+    }
+
+    #[test]
+    fn test_status_counts_games_and_hides_opponent_by_default() {
+        let identity = MyIdentity { connect_codes: vec!["ME#1".to_string()], ..Default::default() };
+        let mut session = Session {
+            settings: PresenceSettings { enabled: true, show_opponent: false },
+            recording_since: Some(0),
+            ..Default::default()
+        };
+
+        for _ in 0..3 {
+            session.game = Some(session.live_game(Some(&game("OPP#2")), &identity));
+        }
+        let status = session.status().unwrap();
+        assert_eq!(status.details, "Recording — Fox vs Marth");
+        assert_eq!(status.state.as_deref(), Some("Game 3"));
+
+        session.settings.show_opponent = true;
+        session.game = Some(session.live_game(Some(&game("NEW#3")), &identity));
+        assert_eq!(session.status().unwrap().state.as_deref(), Some("Game 1 vs NEW#3"));
+
+        // Idle clears the presence
+        session.apply(PresenceMessage::GameEnded);
+        session.apply(PresenceMessage::RecordingStopped);
+        assert_eq!(session.status(), None);
+    }
+}
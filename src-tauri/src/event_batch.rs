@@ -0,0 +1,95 @@
+//! Coalescing bursts of events
+//!
+//! Some events fire many times a second: a stats pass reparses or renames
+//! recordings back to back, and an edit reports progress for every chunk
+//! FFmpeg writes. Each one costs the webview a message, so call sites opt in
+//! with `emit_batched`: payloads of the same event emitted within
+//! BATCH_WINDOW go out together as one array, in the order they were
+//! emitted. Everything else (recording started/stopped and other one-shot
+//! events) keeps using `app.emit` and is sent at once.
+
+use crate::app_state::AppState;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+/// How long a batch collects payloads after its first one
+pub const BATCH_WINDOW: Duration = Duration::from_millis(150);
+
+/// Payloads waiting to be sent, by event name
+#[derive(Debug, Default)]
+pub struct EventBatcher {
+    pending: Mutex<HashMap<&'static str, Vec<Value>>>,
+}
+
+impl EventBatcher {
+    /// Queue a payload. Returns whether it started a new batch, whose flush
+    /// the caller schedules
+    fn push(&self, event: &'static str, payload: Value) -> bool {
+        let Ok(mut pending) = self.pending.lock() else {
+            return false;
+        };
+        let batch = pending.entry(event).or_default();
+        batch.push(payload);
+        batch.len() == 1
+    }
+
+    /// Take the batch of an event, in emit order
+    fn take(&self, event: &'static str) -> Vec<Value> {
+        self.pending
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.remove(event))
+            .unwrap_or_default()
+    }
+}
+
+/// Emit `payload` as part of the next batch of `event`. Listeners get an
+/// array of payloads
+pub fn emit_batched<S: Serialize>(app: &tauri::AppHandle, event: &'static str, payload: S) {
+    let payload = match serde_json::to_value(payload) {
+        Ok(payload) => payload,
+        Err(e) => {
+            log::error!("Failed to serialize {} payload: {}", event, e);
+            return;
+        }
+    };
+    if !app.state::<AppState>().event_batcher.push(event, payload) {
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(BATCH_WINDOW).await;
+        let batch = app.state::<AppState>().event_batcher.take(event);
+        log::trace!("Emitting {} {} events together", batch.len(), event);
+        if let Err(e) = app.emit(event, &batch) {
+            log::error!("Failed to emit {} event: {:?}", event, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batches_keep_emit_order() {
+        let batcher = EventBatcher::default();
+        assert!(batcher.push("progress", Value::from(1)));
+        assert!(!batcher.push("progress", Value::from(2)));
+        // Other events batch on their own
+        assert!(batcher.push("renamed", Value::from("a")));
+        assert!(!batcher.push("progress", Value::from(3)));
+
+        assert_eq!(batcher.take("progress"), vec![Value::from(1), Value::from(2), Value::from(3)]);
+        assert_eq!(batcher.take("renamed"), vec![Value::from("a")]);
+        assert!(batcher.take("progress").is_empty());
+
+        // The next payload starts a new batch
+        assert!(batcher.push("progress", Value::from(4)));
+    }
+}
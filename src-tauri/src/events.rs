@@ -16,6 +16,10 @@ pub mod game {
 
     /// Emitted when the last replay path is updated
     pub const LAST_REPLAY_UPDATED: &str = "last-replay-updated";
+
+    /// Emitted once a new replay's Game Start is read (includes the players,
+    /// stage and matchup, see game_session::StartedGame)
+    pub const STARTED: &str = "game-started";
 }
 
 /// Events emitted during the recording lifecycle
@@ -25,12 +29,102 @@ pub mod recording {
 
     /// Emitted when recording stops (includes output path)
     pub const STOPPED: &str = "recording-stopped";
+
+    /// Emitted when a recording is dropping too many frames (includes a suggested lower preset)
+    pub const PERFORMANCE_WARNING: &str = "recording-performance-warning";
+
+    /// Emitted when the post-recording hook finishes (includes success and any error)
+    pub const HOOK_FINISHED: &str = "recording-hook-finished";
+
+    /// Emitted when recordings interrupted by an unclean shutdown were checked (includes what was recovered)
+    pub const RECOVERY_FINISHED: &str = "recording-recovery-finished";
+}
+
+/// Events about the recordings library
+pub mod library {
+    /// Emitted when recordings flagged to reparse have their stats saved again
+    /// (batched: includes their recording ids)
+    pub const REPARSED: &str = "recording-reparsed";
+
+    /// Emitted when new recordings were renamed by the name template
+    /// (batched: includes each one's id and new video path)
+    pub const RENAMED: &str = "recording-renamed";
 }
 
 /// Events emitted during clip processing
 pub mod clips {
-    /// Emitted when clips have been created (includes list of clip paths)
+    /// Emitted when clips have been created (includes the clips, see ClipResult)
     pub const CREATED: &str = "clips-created";
+
+    /// Emitted after the Clips folder cap deleted old clips (includes counts,
+    /// bytes freed and the folder's size against the cap)
+    pub const ROTATED: &str = "clips-rotated";
+
+    /// Emitted when a clip was renamed or starred (includes the clip, see ClipUpdated)
+    pub const UPDATED: &str = "clip-updated";
+
+    /// Emitted when delete_clip deleted a clip (includes its id and path)
+    pub const DELETED: &str = "clip-deleted";
+
+    /// Emitted while edit_recording writes its output
+    /// (batched: includes the source, output path and progress from 0 to 1 of each update)
+    pub const EDIT_PROGRESS: &str = "clip-edit-progress";
+}
+
+/// Events that move the frontend to another view
+pub mod navigation {
+    /// Emitted when a buckwheat:// link was opened (includes the target recording)
+    pub const DEEP_LINK: &str = "deep-link-navigate";
+}
+
+/// Events about the app's own background work
+pub mod health {
+    /// Emitted when a background task panics (includes the subsystem and whether it will be restarted)
+    pub const BACKGROUND_TASK_FAILED: &str = "background-task-failed";
+}
+
+/// Events from the pre-game readiness check
+pub mod readiness {
+    /// Emitted when the automatic check finds a problem (includes the failed checks)
+    pub const WARNING: &str = "readiness-warning";
+}
+
+/// Events about settings changed by the backend
+pub mod settings {
+    /// Emitted when settings were changed outside the settings page (includes the profile name, if any)
+    pub const CHANGED: &str = "settings-changed";
+
+    /// Emitted when a folder from settings can't be used, e.g. on an unplugged drive
+    /// (includes the settings key, the path and the problem)
+    pub const PATH_UNAVAILABLE: &str = "settings-path-unavailable";
+}
+
+/// Events about the play session (games since watching began)
+pub mod session {
+    /// Emitted after each game of the session is counted (includes the session summary)
+    pub const UPDATED: &str = "session-updated";
+}
+
+/// Events from the recording retention rules
+pub mod retention {
+    /// Emitted after the weekly run (includes counts and bytes freed)
+    pub const COMPLETED: &str = "retention-completed";
+}
+
+/// Events from reviewing a replay in playback Dolphin
+pub mod playback {
+    /// Emitted as Dolphin shows new frames
+    /// (batched: includes the recording id, frame and where it is in the video)
+    pub const FRAME: &str = "playback-frame";
+
+    /// Emitted when the review ends, because Dolphin closed or it was stopped (includes the recording id)
+    pub const ENDED: &str = "playback-review-ended";
+}
+
+/// Events about generated reports
+pub mod reports {
+    /// Emitted when the weekly report is generated on start (includes the report)
+    pub const GENERATED: &str = "weekly-report-generated";
 }
 
 /// Represents the current state of a Slippi game session
@@ -1,15 +1,32 @@
+pub mod polling;
 pub mod slippi_paths;
+pub mod suspension;
 
+use crate::app_state::AppState;
 use crate::commands::errors::Error;
 use crate::events::game as game_events;
-use notify::{Event, EventKind, RecursiveMode, Watcher};
-use std::path::PathBuf;
-use tauri::{AppHandle, Emitter};
+use crate::health::{self, Subsystem};
+use crate::settings::Settings;
+use notify::{Event, EventKind, PollWatcher, RecursiveMode, Watcher};
+use polling::{WatchLog, WatcherBackend};
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
 
 pub struct GameDetector {
     slippi_path: PathBuf,
     watcher: Option<Box<dyn Watcher + Send>>,
     app_handle: Option<AppHandle>,
+    backend: WatcherBackend,
+    /// Scan interval once polling
+    poll_interval: Duration,
+    /// Why polling replaced the native watcher
+    fallback_reason: Option<String>,
+    /// Cancels the native watcher's watchdog
+    watchdog: Option<Arc<AtomicBool>>,
 }
 
 impl GameDetector {
@@ -18,22 +35,130 @@ impl GameDetector {
             slippi_path,
             watcher: None,
             app_handle: None,
+            backend: WatcherBackend::Native,
+            poll_interval: Duration::from_millis(Settings::default().watcher_poll_interval_ms.into()),
+            fallback_reason: None,
+            watchdog: None,
         }
     }
 
+    pub fn slippi_path(&self) -> &PathBuf {
+        &self.slippi_path
+    }
+
     pub fn set_app_handle(&mut self, handle: AppHandle) {
         self.app_handle = Some(handle);
     }
 
     pub fn start_watching(&mut self) -> Result<(), Error> {
-        let app_handle = self.app_handle.clone();
         let watch_path = self.slippi_path.clone();
 
         log::info!("🔧 Setting up file watcher for path: {:?}", watch_path);
         log::info!("🔧 Path exists: {}", watch_path.exists());
         log::info!("🔧 Path is directory: {}", watch_path.is_dir());
 
-        let mut watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
+        if let Some(handle) = &self.app_handle {
+            self.poll_interval = polling::poll_interval(handle);
+        }
+
+        let watch_log = WatchLog::default();
+        let native = notify::recommended_watcher(event_handler(self.app_handle.clone(), watch_log.clone()))
+            .and_then(|mut watcher| {
+                log::info!("🔧 Calling watcher.watch() with RecursiveMode::Recursive");
+                watcher.watch(&self.slippi_path, RecursiveMode::Recursive)?;
+                Ok(watcher)
+            });
+        let watcher = match native {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                log::warn!("⚠️ Native file watcher unavailable ({}), polling instead", e);
+                return self.fall_back_to_polling(format!("native watcher failed: {}", e));
+            }
+        };
+
+        self.watcher = Some(Box::new(watcher));
+        self.backend = WatcherBackend::Native;
+        self.fallback_reason = None;
+        if let Some(handle) = &self.app_handle {
+            let cancelled = Arc::new(AtomicBool::new(false));
+            polling::spawn_watchdog(handle.clone(), watch_path, watch_log, cancelled.clone());
+            self.watchdog = Some(cancelled);
+        }
+        log::info!("👀 Started watching for .slp files: {:?}", self.slippi_path);
+        log::info!("✅ File watcher is now active and monitoring for changes");
+
+        Ok(())
+    }
+
+    /// Replace the native watcher with one scanning the folder on an interval
+    pub fn fall_back_to_polling(&mut self, reason: String) -> Result<(), Error> {
+        self.cancel_watchdog();
+        self.watcher = None;
+
+        let config = notify::Config::default().with_poll_interval(self.poll_interval);
+        let mut watcher = PollWatcher::new(event_handler(self.app_handle.clone(), WatchLog::default()), config)
+            .map_err(|e| Error::WatchError(e.to_string()))?;
+        watcher
+            .watch(&self.slippi_path, RecursiveMode::Recursive)
+            .map_err(|e| Error::WatchError(e.to_string()))?;
+
+        self.watcher = Some(Box::new(watcher));
+        self.backend = WatcherBackend::Polling;
+        log::info!(
+            "👀 Polling for .slp files every {} ms: {:?}",
+            self.poll_interval.as_millis(),
+            self.slippi_path
+        );
+        self.fallback_reason = Some(reason);
+        Ok(())
+    }
+
+    /// What's watching the folder, for get_watcher_status
+    pub fn status(&self) -> WatcherStatus {
+        WatcherStatus {
+            watching: self.watcher.is_some(),
+            path: Some(self.slippi_path.to_string_lossy().to_string()),
+            backend: self.watcher.as_ref().map(|_| self.backend),
+            poll_interval_ms: (self.backend == WatcherBackend::Polling)
+                .then(|| u64::try_from(self.poll_interval.as_millis()).unwrap_or(u64::MAX)),
+            fallback_reason: self.fallback_reason.clone(),
+        }
+    }
+
+    fn cancel_watchdog(&mut self) {
+        if let Some(cancelled) = self.watchdog.take() {
+            cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn stop_watching(&mut self) {
+        self.cancel_watchdog();
+        self.watcher = None;
+        log::info!("⏹️  Stopped watching for .slp files");
+    }
+}
+
+/// Watcher state reported to support (see get_watcher_status)
+#[derive(Debug, Clone, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatcherStatus {
+    pub watching: bool,
+    pub path: Option<String>,
+    pub backend: Option<WatcherBackend>,
+    /// Set while polling
+    pub poll_interval_ms: Option<u64>,
+    /// Why the native watcher was given up on
+    pub fallback_reason: Option<String>,
+}
+
+/// Handles the events of either watcher
+fn event_handler(app_handle: Option<AppHandle>, watch_log: WatchLog) -> impl FnMut(notify::Result<Event>) + Send + 'static {
+    move |res: Result<Event, notify::Error>| {
+        // A panic here would kill notify's thread and silently stop game detection;
+        // catch it so the watcher survives (the panic hook reports it and the
+        // supervisor restarts the watcher)
+        let _subsystem = health::enter(Subsystem::Watcher);
+        let _ = std::panic::catch_unwind(AssertUnwindSafe(|| {
             match res {
                 Ok(event) => {
                     log::debug!("📂 File system event received: {:?}", event.kind);
@@ -58,23 +183,11 @@ impl GameDetector {
 
                                 if ext == "slp" {
                                     log::info!("🎮 New Slippi replay detected: {:?}", path);
+                                    watch_log.report(path);
 
                                     // Emit event to trigger auto-recording
                                     if let Some(handle) = &app_handle {
-                                        let path_string = path.to_string_lossy().to_string();
-                                        log::info!(
-                                            "📤 Emitting {} event with path: {}",
-                                            game_events::FILE_CREATED,
-                                            path_string
-                                        );
-
-                                        match handle.emit(game_events::FILE_CREATED, path_string.clone()) {
-                                            Ok(_) => log::info!("✅ Event emitted successfully"),
-                                            Err(e) => log::error!(
-                                                "❌ Failed to emit slp-file-created event: {:?}",
-                                                e
-                                            ),
-                                        }
+                                        announce_created(handle, path);
                                     } else {
                                         log::error!("❌ App handle is None, cannot emit event");
                                     }
@@ -97,9 +210,7 @@ impl GameDetector {
                                         let path_string = path.to_string_lossy().to_string();
                                         log::debug!("📝 .slp file modified: {}", path_string);
 
-                                        if let Err(e) =
-                                            handle.emit(game_events::FILE_MODIFIED, path_string)
-                                        {
+                                        if let Err(e) = handle.emit(game_events::FILE_MODIFIED, path_string) {
                                             log::error!(
                                                 "❌ Failed to emit {} event: {:?}",
                                                 game_events::FILE_MODIFIED,
@@ -112,25 +223,27 @@ impl GameDetector {
                         }
                     }
                 }
-                Err(e) => log::error!("❌ Watch error: {:?}", e),
+                Err(e) => {
+                    log::error!("❌ Watch error: {:?}", e);
+                    watch_log.fail(e.to_string());
+                }
             }
-        })
-        .map_err(|e| Error::WatchError(e.to_string()))?;
-
-        log::info!("🔧 Calling watcher.watch() with RecursiveMode::Recursive");
-        watcher
-            .watch(&self.slippi_path, RecursiveMode::Recursive)
-            .map_err(|e| Error::WatchError(e.to_string()))?;
-
-        self.watcher = Some(Box::new(watcher));
-        log::info!("👀 Started watching for .slp files: {:?}", self.slippi_path);
-        log::info!("✅ File watcher is now active and monitoring for changes");
+        }));
+    }
+}
 
-        Ok(())
+/// Emit FILE_CREATED for a new replay, unless the watcher is paused
+fn announce_created(handle: &AppHandle, path: &Path) {
+    // Likely moved in by a bulk library change
+    if handle.state::<AppState>().watch_suspension.drop_creation() {
+        log::debug!("⏸️ Watcher paused, not announcing {:?}", path);
+        return;
     }
+    let path_string = path.to_string_lossy().to_string();
+    log::info!("📤 Emitting {} event with path: {}", game_events::FILE_CREATED, path_string);
 
-    pub fn stop_watching(&mut self) {
-        self.watcher = None;
-        log::info!("⏹️  Stopped watching for .slp files");
+    match handle.emit(game_events::FILE_CREATED, path_string) {
+        Ok(_) => log::info!("✅ Event emitted successfully"),
+        Err(e) => log::error!("❌ Failed to emit slp-file-created event: {:?}", e),
     }
 }
@@ -0,0 +1,181 @@
+//! Fallback for folders the platform watcher can't follow
+//!
+//! On network drives and some synced folders (OneDrive) the native watcher
+//! starts fine but never delivers an event. A watchdog lists the replays now
+//! and then; a new one the watcher never reported (or a watcher error) moves
+//! the detector over to polling, which reports the same events
+
+use crate::app_state::AppState;
+use crate::settings::{self, Settings};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+use walkdir::WalkDir;
+
+/// Settings key for how often the polling watcher scans the folder
+pub const POLL_INTERVAL_MS_KEY: &str = "watcherPollIntervalMs";
+/// How often the watchdog lists the folder
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(10);
+/// Time the native watcher gets to report a replay the listing found
+const EVENT_GRACE: Duration = Duration::from_secs(2);
+/// A missed replay written this recently is probably a game in progress, so
+/// it's announced once polling takes over
+const RECENT_REPLAY: Duration = Duration::from_secs(30);
+
+/// How changes in the Slippi folder are found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatcherBackend {
+    /// The platform's change notifications
+    Native,
+    /// Scanning the folder on an interval
+    Polling,
+}
+
+/// Interval of the polling watcher (read when watching starts)
+pub fn poll_interval(app: &AppHandle) -> Duration {
+    let range = settings::WATCHER_POLL_INTERVAL_MS;
+    let ms = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get(POLL_INTERVAL_MS_KEY))
+        .and_then(|v| v.as_u64())
+        .map(|v| u32::try_from(v).unwrap_or(u32::MAX).clamp(*range.start(), *range.end()))
+        .unwrap_or(Settings::default().watcher_poll_interval_ms);
+    Duration::from_millis(ms.into())
+}
+
+/// Every replay under `root`
+pub fn list_replays(root: &Path) -> HashSet<PathBuf> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && entry.path().extension().is_some_and(|ext| ext == "slp"))
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// Replays in `listing` that weren't in `previous` and weren't reported
+fn unreported(listing: &HashSet<PathBuf>, previous: &HashSet<PathBuf>, reported: &HashSet<PathBuf>) -> Vec<PathBuf> {
+    let mut missed: Vec<PathBuf> = listing
+        .iter()
+        .filter(|path| !previous.contains(*path) && !reported.contains(*path))
+        .cloned()
+        .collect();
+    missed.sort();
+    missed
+}
+
+/// What the native watcher has seen, shared with the watchdog
+#[derive(Clone, Default)]
+pub struct WatchLog {
+    /// Replays the watcher reported as created
+    pub reported: Arc<Mutex<HashSet<PathBuf>>>,
+    /// Last error the watcher reported
+    pub error: Arc<Mutex<Option<String>>>,
+}
+
+impl WatchLog {
+    pub fn report(&self, path: &Path) {
+        if let Ok(mut reported) = self.reported.lock() {
+            reported.insert(path.to_path_buf());
+        }
+    }
+
+    pub fn fail(&self, error: String) {
+        if let Ok(mut last) = self.error.lock() {
+            last.get_or_insert(error);
+        }
+    }
+
+    fn reported(&self) -> HashSet<PathBuf> {
+        self.reported.lock().map(|r| r.clone()).unwrap_or_default()
+    }
+
+    fn error(&self) -> Option<String> {
+        self.error.lock().ok().and_then(|e| e.clone())
+    }
+}
+
+/// Check on the native watcher until it's cancelled or polling takes over
+pub fn spawn_watchdog(app: AppHandle, root: PathBuf, watch_log: WatchLog, cancelled: Arc<AtomicBool>) {
+    let spawned = std::thread::Builder::new().name("watcher-watchdog".into()).spawn(move || {
+        let mut previous = list_replays(&root);
+        loop {
+            std::thread::sleep(WATCHDOG_INTERVAL);
+            if cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+
+            let (reason, missed) = if let Some(error) = watch_log.error() {
+                (format!("watcher error: {}", error), Vec::new())
+            } else {
+                let listing = list_replays(&root);
+                let mut missed = unreported(&listing, &previous, &watch_log.reported());
+                if !missed.is_empty() {
+                    std::thread::sleep(EVENT_GRACE);
+                    let reported = watch_log.reported();
+                    missed.retain(|path| !reported.contains(path));
+                }
+                previous = listing;
+                match missed.first() {
+                    Some(first) => (format!("no event for {}", first.display()), missed),
+                    None => continue,
+                }
+            };
+
+            let state = app.state::<AppState>();
+            let Ok(mut detector) = state.game_detector.lock() else { return };
+            // Stopped or replaced while listing
+            if cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+            let Some(detector) = detector.as_mut() else { return };
+            log::warn!("⚠️ File watcher isn't delivering events ({}), switching to polling", reason);
+            if let Err(e) = detector.fall_back_to_polling(reason) {
+                log::error!("❌ Failed to start polling watcher: {}", e);
+                return;
+            }
+            for path in missed.iter().filter(|path| is_recent(path)) {
+                super::announce_created(&app, path);
+            }
+            return;
+        }
+    });
+    if let Err(e) = spawned {
+        log::error!("Failed to start watcher watchdog: {}", e);
+    }
+}
+
+fn is_recent(path: &Path) -> bool {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < RECENT_REPLAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unreported_replays_are_new_and_unseen() {
+        let set = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<HashSet<_>>();
+        let previous = set(&["a.slp", "b.slp"]);
+        let listing = set(&["a.slp", "b.slp", "c.slp", "d.slp"]);
+
+        assert_eq!(unreported(&listing, &previous, &set(&["c.slp"])), vec![PathBuf::from("d.slp")]);
+        assert_eq!(
+            unreported(&listing, &previous, &HashSet::new()),
+            vec![PathBuf::from("c.slp"), PathBuf::from("d.slp")]
+        );
+        assert!(unreported(&listing, &listing, &HashSet::new()).is_empty());
+        // Deleted replays aren't missed events
+        assert!(unreported(&set(&["a.slp"]), &previous, &HashSet::new()).is_empty());
+    }
+}
@@ -1,13 +1,11 @@
 use std::path::PathBuf;
 
-/// Get the default Slippi replay folder path based on the operating system
-pub fn get_default_slippi_path() -> PathBuf {
+/// Slippi Launcher's data directory for the current OS
+fn get_launcher_directory() -> PathBuf {
     #[cfg(target_os = "windows")]
     {
         let appdata = std::env::var("APPDATA").unwrap_or_else(|_| String::from("C:\\"));
-        PathBuf::from(appdata)
-            .join("Slippi Launcher")
-            .join("netplay")
+        PathBuf::from(appdata).join("Slippi Launcher")
     }
 
     #[cfg(target_os = "macos")]
@@ -17,7 +15,6 @@ pub fn get_default_slippi_path() -> PathBuf {
             .join("Library")
             .join("Application Support")
             .join("Slippi Launcher")
-            .join("netplay")
     }
 
     #[cfg(target_os = "linux")]
@@ -26,10 +23,49 @@ pub fn get_default_slippi_path() -> PathBuf {
         PathBuf::from(home)
             .join(".config")
             .join("Slippi Launcher")
-            .join("netplay")
     }
 }
 
+/// Get the default Slippi replay folder path based on the operating system
+pub fn get_default_slippi_path() -> PathBuf {
+    get_launcher_directory().join("netplay")
+}
+
+/// Get the playback Dolphin executable installed by Slippi Launcher
+pub fn get_default_playback_path() -> PathBuf {
+    let playback_dir = get_launcher_directory().join("playback");
+
+    #[cfg(target_os = "windows")]
+    {
+        playback_dir.join("Slippi Dolphin.exe")
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        playback_dir
+            .join("Slippi Dolphin.app")
+            .join("Contents")
+            .join("MacOS")
+            .join("Slippi Dolphin")
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        playback_dir.join("Slippi_Playback-x86_64.AppImage")
+    }
+}
+
+/// Melee ISO configured in Slippi Launcher, if any
+pub fn get_launcher_iso_path() -> Option<PathBuf> {
+    let contents = std::fs::read_to_string(get_launcher_directory().join("Settings")).ok()?;
+    let settings: serde_json::Value = serde_json::from_str(&contents).ok()?;
+    settings
+        .pointer("/settings/isoPath")
+        .and_then(|v| v.as_str())
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
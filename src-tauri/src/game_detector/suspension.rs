@@ -0,0 +1,222 @@
+//! Pausing the watcher while the library is changed in bulk
+//!
+//! Deleting, moving or archiving many recordings (and their replays) makes the
+//! Slippi folder fire bursts of create and remove events, and a replay moved
+//! into it looks just like a game starting. Library changes that can take a
+//! while hold a SuspensionGuard, and so do test recordings (the benchmark and
+//! test capture), which a game's auto-recording would collide with: while any
+//! is held, replays appearing are
+//! dropped (counted for the log) instead of announced. Writes still go
+//! through, so a game already being recorded isn't taken for over.
+//!
+//! When the last guard goes the folder is reconciled: a replay created since
+//! the pause began and still being written is a game that started meanwhile,
+//! and is announced as if it had just appeared. Replays moved or copied in
+//! keep their old modification times and are left alone.
+
+use crate::app_state::AppState;
+use crate::events::game as game_events;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tauri::{Emitter, Manager};
+
+/// A replay written to this recently is a game in progress (the longest
+/// quiet period a game is given, see settings::REPLAY_QUIET_PERIOD_MS)
+const LIVE_WINDOW: Duration = Duration::from_secs(10);
+/// How deep the reconciliation looks into the Slippi folder (as list_slp_files)
+const MAX_DEPTH: usize = 5;
+
+#[derive(Debug, Default)]
+struct Paused {
+    /// Guards held
+    holders: usize,
+    /// When the first of them was taken
+    since: Option<SystemTime>,
+    /// New replays not announced since then
+    dropped: usize,
+}
+
+/// The end of a pause, once its last guard is gone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resumed {
+    pub since: SystemTime,
+    pub dropped: usize,
+}
+
+/// Whether the watcher is paused, shared by every guard
+#[derive(Debug, Default)]
+pub struct WatchSuspension {
+    paused: Mutex<Paused>,
+}
+
+impl WatchSuspension {
+    pub fn is_suspended(&self) -> bool {
+        self.paused.lock().map(|p| p.holders > 0).unwrap_or(false)
+    }
+
+    /// Count a new replay instead of announcing it, if paused.
+    /// Returns whether it was dropped
+    pub fn drop_creation(&self) -> bool {
+        let Ok(mut paused) = self.paused.lock() else {
+            return false;
+        };
+        if paused.holders == 0 {
+            return false;
+        }
+        paused.dropped += 1;
+        true
+    }
+
+    fn hold(&self, now: SystemTime) {
+        if let Ok(mut paused) = self.paused.lock() {
+            paused.holders += 1;
+            paused.since.get_or_insert(now);
+        }
+    }
+
+    /// Let go of one guard; Some when it was the last
+    fn release(&self) -> Option<Resumed> {
+        let mut paused = self.paused.lock().ok()?;
+        paused.holders = paused.holders.checked_sub(1)?;
+        if paused.holders > 0 {
+            return None;
+        }
+        let resumed = Resumed { since: paused.since.take()?, dropped: paused.dropped };
+        paused.dropped = 0;
+        Some(resumed)
+    }
+}
+
+/// Keeps the watcher paused until dropped
+#[must_use = "the watcher resumes as soon as the guard is dropped"]
+pub struct SuspensionGuard {
+    app: tauri::AppHandle,
+    reason: &'static str,
+}
+
+/// Pause the watcher for a library change (`reason` is for the log)
+pub fn suspend(app: &tauri::AppHandle, reason: &'static str) -> SuspensionGuard {
+    let state = app.state::<AppState>();
+    if !state.watch_suspension.is_suspended() {
+        log::info!("⏸️ Watcher paused while {}", reason);
+    }
+    state.watch_suspension.hold(SystemTime::now());
+    SuspensionGuard { app: app.clone(), reason }
+}
+
+impl Drop for SuspensionGuard {
+    fn drop(&mut self) {
+        let Some(resumed) = self.app.state::<AppState>().watch_suspension.release() else {
+            return;
+        };
+        log::info!(
+            "▶️ Watcher resumed after {} ({} new replay event(s) dropped)",
+            self.reason,
+            resumed.dropped
+        );
+        let app = self.app.clone();
+        tauri::async_runtime::spawn_blocking(move || reconcile(&app, resumed.since));
+    }
+}
+
+/// Announce a game that started while the watcher was paused
+fn reconcile(app: &tauri::AppHandle, since: SystemTime) {
+    let state = app.state::<AppState>();
+    let Some(folder) = state
+        .game_detector
+        .lock()
+        .ok()
+        .and_then(|detector| detector.as_ref().map(|d| d.slippi_path().clone()))
+    else {
+        return;
+    };
+
+    let replays: Vec<ReplayTimes> = walkdir::WalkDir::new(&folder)
+        .max_depth(MAX_DEPTH)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("slp"))
+        .filter_map(|e| {
+            let metadata = e.metadata().ok()?;
+            Some(ReplayTimes {
+                path: e.into_path(),
+                created: metadata.created().ok(),
+                modified: metadata.modified().ok()?,
+            })
+        })
+        .collect();
+
+    match live_replay(&replays, since, SystemTime::now()) {
+        Some(path) => {
+            let path = path.to_string_lossy().to_string();
+            log::info!("🎮 Replay created while the watcher was paused is still being written: {}", path);
+            if let Err(e) = app.emit(game_events::FILE_CREATED, path) {
+                log::error!("❌ Failed to emit {} event: {:?}", game_events::FILE_CREATED, e);
+            }
+        }
+        None => log::debug!("No game started while the watcher was paused ({} replays)", replays.len()),
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ReplayTimes {
+    path: PathBuf,
+    /// None where the file system doesn't keep it
+    created: Option<SystemTime>,
+    modified: SystemTime,
+}
+
+/// The newest replay created since `since` (or, without creation times,
+/// written to since) that is still being written
+fn live_replay(replays: &[ReplayTimes], since: SystemTime, now: SystemTime) -> Option<&Path> {
+    replays
+        .iter()
+        .filter(|r| r.created.unwrap_or(r.modified) >= since)
+        .filter(|r| now.duration_since(r.modified).map(|age| age <= LIVE_WINDOW).unwrap_or(true))
+        .max_by_key(|r| r.modified)
+        .map(|r| r.path.as_path())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_replays_are_dropped_until_the_last_guard_goes() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let suspension = WatchSuspension::default();
+        assert!(!suspension.drop_creation());
+        assert_eq!(suspension.release(), None);
+
+        // A bulk delete, then an archive run overlapping it
+        suspension.hold(at(0));
+        assert!(suspension.drop_creation());
+        suspension.hold(at(5));
+        assert!(suspension.drop_creation());
+        assert_eq!(suspension.release(), None);
+        assert!(suspension.is_suspended());
+        assert!(suspension.drop_creation());
+        assert_eq!(suspension.release(), Some(Resumed { since: at(0), dropped: 3 }));
+        assert!(!suspension.is_suspended());
+        assert!(!suspension.drop_creation());
+
+        let replay = |name: &str, created: Option<u64>, modified: u64| ReplayTimes {
+            path: PathBuf::from(name),
+            created: created.map(at),
+            modified: at(modified),
+        };
+        let replays = [
+            // Moved in by the bulk change: created now, last written long ago
+            replay("moved.slp", Some(20), 0),
+            // Started and finished while paused
+            replay("finished.slp", Some(10), 30),
+            replay("playing.slp", Some(40), 95),
+            replay("older.slp", None, 0),
+        ];
+        assert_eq!(live_replay(&replays, at(0), at(100)), Some(Path::new("playing.slp")));
+        assert_eq!(live_replay(&replays, at(50), at(100)), None);
+        assert_eq!(live_replay(&replays[..2], at(0), at(100)), None);
+    }
+}
@@ -0,0 +1,1027 @@
+//! Following games from the watcher's events to finished recordings
+//!
+//! Two listeners forward the watcher's FILE_CREATED and FILE_MODIFIED events
+//! into a channel. start_watching attaches them and stop_watching removes
+//! them; attaching again replaces the ones already attached, so toggling
+//! watching never doubles the events. A single task reads the channel and
+//! feeds GameSessionController, the state machine deciding what happens to
+//! the recording:
+//!
+//! Idle → GameStarted (replay created, recording starting) → Recording →
+//! GameEnded (the replay stopped changing; post-game delays, or waiting for
+//! the set's next game) → Finalizing (stopping) → Idle
+//!
+//! The controller only returns actions. The task carries them out (starting
+//! or stopping the recording, adding a game to the set) and feeds the outcome
+//! back as an event. Time is passed in, so tests drive the controller with
+//! synthetic events and instants.
+//!
+//! Every new replay is also announced as soon as its Game Start is written:
+//! Discord presence and the frontend (game-started) learn the matchup, and a
+//! provisional game_stats row lets the library show it before the full parse
+//! replaces it.
+//!
+//! The watch filters (see watch_filters) are applied by the controller too:
+//! a new replay is checked against them before it's handed to `handle`, and
+//! `handle` holds back recordings starting too soon after the last one. The
+//! recording rules (see recording_rules) come before them and can override
+//! them.
+
+use crate::app_state::AppState;
+use crate::commands::settings::{my_identity, record_cpu_matches, replay_only_mode};
+use crate::commands::slippi::{
+    continue_pre_roll, join_recording_set, stop_recording_internal, trigger_auto_recording,
+};
+use crate::events::game as game_events;
+use crate::health::{self, Subsystem};
+use crate::database::{self, GameStatsRow, MyIdentity, RecordingRuleAction};
+use crate::library;
+use crate::pre_roll;
+use crate::recording_rules;
+use crate::warm_start;
+use crate::settings::{self, Settings};
+use crate::slippi::game_start::{read_game_start, GameStart, GameStartPlayer};
+use crate::slippi::melee_ids;
+use crate::watch_filters::{watch_filters, WatchFilters};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, EventId, Listener, Manager};
+use tauri_plugin_store::StoreExt;
+use tokio::sync::mpsc;
+
+/// Settings keys for game end detection (see GameEndTiming)
+const REPLAY_QUIET_PERIOD_MS_KEY: &str = "replayQuietPeriodMs";
+const STOP_RECORDING_DELAY_SECONDS_KEY: &str = "stopRecordingDelaySeconds";
+const END_RECORDING_DELAY_SECONDS_KEY: &str = "endRecordingDelaySeconds";
+const SET_END_TIMEOUT_MINUTES_KEY: &str = "setEndTimeoutMinutes";
+
+/// Game Start follows the replay's creation closely; how long to wait for it
+const GAME_START_ATTEMPTS: u32 = 10;
+const GAME_START_RETRY: Duration = Duration::from_millis(100);
+/// Replays created this soon after the recorded game's are the same moment
+/// seen twice (a replay per console in doubles, or a spectated game next to
+/// one's own); the first one created keeps the recording
+const CONCURRENT_REPLAY_WINDOW: Duration = Duration::from_secs(2);
+
+/// What the controller is told about
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionEvent {
+    /// A replay was created: a game started
+    ReplayCreated(String),
+    /// A replay was written to, at the given time
+    ReplayModified(String, Instant),
+    /// The game's recording started (`per_set`: it will cover the whole set)
+    RecordingStarted { per_set: bool },
+    RecordingFailed,
+    /// The new game was added to the set being recorded
+    SetJoined(String),
+    /// The new game is from another match, so the set is over
+    SetEnded(String),
+    /// The recording stopped, after the game or by hand
+    RecordingStopped,
+    GameWindowClosed,
+}
+
+/// Where the session is in the current game
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionState {
+    Idle,
+    /// The game's replay appeared and its recording is starting
+    GameStarted { slp_path: String },
+    Recording { slp_path: String, per_set: bool },
+    /// The replay stopped changing at `at`
+    GameEnded { slp_path: String, per_set: bool, at: Instant },
+    /// The recording is being stopped
+    Finalizing,
+}
+
+/// What the controller wants done
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Record a new game (taking over the pre-roll, if there's one)
+    StartRecording(String),
+    /// Try adding a new game to the set being recorded
+    JoinSet(String),
+    StopRecording,
+    /// Record character select until the next game
+    StartPreRoll,
+}
+
+/// A game that just started, from its Game Start, as sent with game-started
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartedGame {
+    pub slp_path: String,
+    /// Id its recording and stats will have, if Game Start gives one
+    pub recording_id: Option<String>,
+    pub game: GameStart,
+    /// Index in game.players of me, if I'm found (see my_player)
+    pub me: Option<usize>,
+    /// "Fox vs Marth", mine first
+    pub matchup: String,
+}
+
+/// When a game counts as over, and how long recording goes on after it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GameEndTiming {
+    /// Time without writes to the replay
+    pub quiet_period: Duration,
+    /// For Slippi to finish the replay
+    pub stop_delay: Duration,
+    /// Post-game footage the player asked for
+    pub end_recording_delay: Duration,
+    /// Time without a new game before a per-set recording stops
+    pub set_end_timeout: Duration,
+}
+
+/// The state machine behind auto-recording
+#[derive(Debug)]
+pub struct GameSessionController {
+    state: SessionState,
+    timing: GameEndTiming,
+    /// Last write to the replay of the game being recorded
+    last_write: Option<Instant>,
+    filters: WatchFilters,
+    /// When the last auto-recording was started
+    last_trigger: Option<Instant>,
+    /// When the game being recorded claimed the recording
+    claimed_at: Option<Instant>,
+}
+
+impl GameSessionController {
+    pub fn new(timing: GameEndTiming) -> Self {
+        Self {
+            state: SessionState::Idle,
+            timing,
+            last_write: None,
+            filters: WatchFilters::default(),
+            last_trigger: None,
+            claimed_at: None,
+        }
+    }
+
+    pub fn state(&self) -> &SessionState {
+        &self.state
+    }
+
+    /// Settings are read again before each event, so changes apply to the game in progress
+    pub fn set_timing(&mut self, timing: GameEndTiming) {
+        self.timing = timing;
+    }
+
+    /// Read again before each new replay, like the timing
+    pub fn set_filters(&mut self, filters: WatchFilters) {
+        self.filters = filters;
+    }
+
+    /// Whether the filters look at the game, so its Game Start must be read first
+    pub fn needs_game_start(&self) -> bool {
+        self.filters.needs_game_start()
+    }
+
+    /// Whether a new replay passes the filters on its path and game (`game`:
+    /// its Game Start, None if it couldn't be read). A replay that doesn't is
+    /// left out entirely, as if it had never been created
+    pub fn admits(&self, slp_path: &str, game: Option<&GameStart>) -> bool {
+        if let Some(pattern) = self.filters.ignored_by(slp_path) {
+            log::info!("🚫 Not recording {}: matches ignore pattern {:?}", slp_path, pattern);
+            return false;
+        }
+        if let Some(reason) = game.and_then(|game| self.filters.rejects(game)) {
+            log::info!("🚫 Not recording {}: {}", slp_path, reason);
+            return false;
+        }
+        true
+    }
+
+    /// `recording`: whether something else is being recorded (by hand)
+    pub fn handle(&mut self, event: SessionEvent, now: Instant, recording: bool) -> Vec<Action> {
+        match (&self.state, event) {
+            (_, SessionEvent::ReplayCreated(slp_path)) => self.replay_created(slp_path, now, recording),
+            (
+                SessionState::GameStarted { slp_path }
+                | SessionState::Recording { slp_path, .. }
+                | SessionState::GameEnded { slp_path, .. },
+                SessionEvent::ReplayModified(path, at),
+            ) if same_game(slp_path, &path) => {
+                if self.last_write.is_none() && matches!(self.state, SessionState::Recording { .. }) {
+                    log::info!("Recording file modified - waiting for the replay to stop changing");
+                }
+                self.last_write = Some(at.min(now));
+                Vec::new()
+            }
+            (SessionState::GameStarted { slp_path }, SessionEvent::RecordingStarted { per_set }) => {
+                self.state = SessionState::Recording { slp_path: slp_path.clone(), per_set };
+                Vec::new()
+            }
+            (SessionState::GameStarted { .. }, SessionEvent::RecordingFailed) => {
+                self.state = SessionState::Idle;
+                Vec::new()
+            }
+            (SessionState::GameEnded { .. }, SessionEvent::SetJoined(slp_path)) => {
+                self.state = SessionState::Recording { slp_path, per_set: true };
+                self.last_write = None;
+                Vec::new()
+            }
+            (SessionState::GameEnded { .. }, SessionEvent::SetEnded(slp_path)) => self.restart(slp_path, now),
+            (SessionState::Finalizing, SessionEvent::RecordingStopped) => {
+                self.state = SessionState::Idle;
+                vec![Action::StartPreRoll]
+            }
+            // Stopped by hand
+            (SessionState::Recording { .. } | SessionState::GameEnded { .. }, SessionEvent::RecordingStopped) => {
+                self.state = SessionState::Idle;
+                Vec::new()
+            }
+            (SessionState::GameEnded { per_set: true, .. }, SessionEvent::GameWindowClosed) => {
+                log::info!("Game window closed - the set is over");
+                self.finalize()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// When `tick` has something to do next
+    pub fn deadline(&self) -> Option<Instant> {
+        match &self.state {
+            SessionState::Recording { .. } => self.last_write.map(|at| at + self.timing.quiet_period),
+            SessionState::GameEnded { per_set: false, at, .. } => {
+                Some(*at + self.timing.stop_delay + self.timing.end_recording_delay)
+            }
+            SessionState::GameEnded { per_set: true, at, .. } => Some(*at + self.timing.set_end_timeout),
+            _ => None,
+        }
+    }
+
+    /// Move on once the deadline has passed
+    pub fn tick(&mut self, now: Instant) -> Vec<Action> {
+        if self.deadline().is_none_or(|deadline| now < deadline) {
+            return Vec::new();
+        }
+        match &self.state {
+            SessionState::Recording { slp_path, per_set } => {
+                if *per_set {
+                    log::info!(
+                        "Game ended - recording until the set's next game (set ends after {} min without one)",
+                        self.timing.set_end_timeout.as_secs() / 60
+                    );
+                } else {
+                    let delay = self.timing.stop_delay + self.timing.end_recording_delay;
+                    log::info!("Game ended - stopping recording in {:.1}s", delay.as_secs_f64());
+                }
+                self.state = SessionState::GameEnded { slp_path: slp_path.clone(), per_set: *per_set, at: now };
+                Vec::new()
+            }
+            SessionState::GameEnded { per_set, .. } => {
+                if *per_set {
+                    log::info!("No new game - the set is over");
+                }
+                self.finalize()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn replay_created(&mut self, slp_path: String, now: Instant, recording: bool) -> Vec<Action> {
+        match &self.state {
+            SessionState::Idle if recording => {
+                log::info!("Already recording, skipping");
+                Vec::new()
+            }
+            SessionState::Idle => {
+                if self.too_soon(now) {
+                    return Vec::new();
+                }
+                self.state = SessionState::GameStarted { slp_path: slp_path.clone() };
+                self.last_write = None;
+                self.claimed_at = Some(now);
+                vec![Action::StartRecording(slp_path)]
+            }
+            SessionState::GameStarted { slp_path: current }
+            | SessionState::Recording { slp_path: current, .. }
+            | SessionState::GameEnded { slp_path: current, .. }
+                if !same_game(current, &slp_path) && self.concurrent(now) =>
+            {
+                log::info!("Replay {} created alongside {}, keeping the recording on the first", slp_path, current);
+                Vec::new()
+            }
+            // A new replay means the last game is over, even if the wait
+            // before stopping its recording hasn't finished
+            SessionState::Recording { slp_path: current, per_set }
+            | SessionState::GameEnded { slp_path: current, per_set, .. }
+                if !same_game(current, &slp_path) =>
+            {
+                // Per-set recordings keep going into the next game
+                if *per_set {
+                    vec![Action::JoinSet(slp_path)]
+                } else if self.too_soon(now) {
+                    // The last game's recording ends as usual
+                    Vec::new()
+                } else {
+                    self.restart(slp_path, now)
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether an auto-recording started less than minTriggerIntervalSeconds
+    /// ago. If not, one starting `now` is noted
+    fn too_soon(&mut self, now: Instant) -> bool {
+        let interval = Duration::from_secs(self.filters.min_trigger_interval_seconds.into());
+        let since = self.last_trigger.map(|at| now.saturating_duration_since(at));
+        let too_soon = since.is_some_and(|since| since < interval);
+        log::debug!(
+            "Watch filter: minTriggerIntervalSeconds {} ({:?} since the last recording started)",
+            if too_soon { "matched" } else { "passed" },
+            since
+        );
+        if too_soon {
+            log::info!(
+                "🚫 Not recording: the last recording started less than {}s ago",
+                interval.as_secs()
+            );
+        } else {
+            self.last_trigger = Some(now);
+        }
+        too_soon
+    }
+
+    /// Whether a replay created `now` comes with the recorded game's, see
+    /// CONCURRENT_REPLAY_WINDOW
+    fn concurrent(&self, now: Instant) -> bool {
+        self.claimed_at.is_some_and(|at| now.saturating_duration_since(at) < CONCURRENT_REPLAY_WINDOW)
+    }
+
+    /// Stop the current recording and start one for a new game
+    fn restart(&mut self, slp_path: String, now: Instant) -> Vec<Action> {
+        log::info!("New game started - stopping the last recording now");
+        self.state = SessionState::GameStarted { slp_path: slp_path.clone() };
+        self.last_write = None;
+        self.claimed_at = Some(now);
+        vec![Action::StopRecording, Action::StartRecording(slp_path)]
+    }
+
+    fn finalize(&mut self) -> Vec<Action> {
+        log::info!("Stopping recording after game end...");
+        self.state = SessionState::Finalizing;
+        vec![Action::StopRecording]
+    }
+}
+
+/// Which session holds the auto-recording. Starting one claims it with a
+/// compare-and-set on a session id, so two triggers racing can't both start a
+/// recording, and the one that lost can't take over the tracked file
+#[derive(Debug, Default)]
+pub struct RecordingClaim {
+    /// Session holding the recording (0: none)
+    holder: AtomicU64,
+    last_session: AtomicU64,
+}
+
+impl RecordingClaim {
+    /// Claim the recording for a new session; None if one already holds it
+    pub fn claim(&self) -> Option<u64> {
+        let session = self.last_session.fetch_add(1, Ordering::SeqCst) + 1;
+        self.holder
+            .compare_exchange(0, session, Ordering::SeqCst, Ordering::SeqCst)
+            .ok()
+            .map(|_| session)
+    }
+
+    /// Give the claim back (the recording didn't start), if `session` still holds it
+    pub fn release(&self, session: u64) {
+        let _ = self.holder.compare_exchange(session, 0, Ordering::SeqCst, Ordering::SeqCst);
+    }
+
+    /// The recording stopped, whichever session held it
+    pub fn clear(&self) {
+        self.holder.store(0, Ordering::SeqCst);
+    }
+
+    pub fn holder(&self) -> Option<u64> {
+        Some(self.holder.load(Ordering::SeqCst)).filter(|session| *session != 0)
+    }
+}
+
+/// Where the watcher's events come from: the app, or a stand-in in tests
+pub trait EventSource {
+    /// Send each `event` into `events`, as made by `wrap` from its path
+    fn forward(
+        &self,
+        event: &'static str,
+        events: mpsc::UnboundedSender<SessionEvent>,
+        wrap: fn(String) -> SessionEvent,
+    ) -> EventId;
+    fn unlisten(&self, id: EventId);
+}
+
+impl<R: tauri::Runtime> EventSource for tauri::AppHandle<R> {
+    fn forward(
+        &self,
+        event: &'static str,
+        events: mpsc::UnboundedSender<SessionEvent>,
+        wrap: fn(String) -> SessionEvent,
+    ) -> EventId {
+        self.listen(event, move |e| {
+            let _ = events.send(wrap(crate::event_path(e.payload())));
+        })
+    }
+
+    fn unlisten(&self, id: EventId) {
+        Listener::unlisten(self, id);
+    }
+}
+
+/// The channel into the session and the listeners feeding it
+#[derive(Debug)]
+pub struct GameSession {
+    events: mpsc::UnboundedSender<SessionEvent>,
+    /// Until the session task takes it at startup
+    receiver: Option<mpsc::UnboundedReceiver<SessionEvent>>,
+    listeners: Vec<EventId>,
+}
+
+impl Default for GameSession {
+    fn default() -> Self {
+        let (events, receiver) = mpsc::unbounded_channel();
+        Self { events, receiver: Some(receiver), listeners: Vec::new() }
+    }
+}
+
+impl GameSession {
+    /// Forward the watcher's events, replacing the listeners already attached
+    pub fn attach(&mut self, source: &impl EventSource) {
+        self.detach(source);
+        self.listeners = vec![
+            source.forward(game_events::FILE_CREATED, self.events.clone(), SessionEvent::ReplayCreated),
+            source.forward(game_events::FILE_MODIFIED, self.events.clone(), |path| {
+                SessionEvent::ReplayModified(path, Instant::now())
+            }),
+        ];
+    }
+
+    pub fn detach(&mut self, source: &impl EventSource) {
+        for id in self.listeners.drain(..) {
+            source.unlisten(id);
+        }
+    }
+
+    pub fn send(&self, event: SessionEvent) {
+        let _ = self.events.send(event);
+    }
+}
+
+/// Start the task following games (once, at startup)
+pub fn spawn(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let Some(events) = state.game_session.lock().ok().and_then(|mut session| session.receiver.take()) else {
+        return;
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(health::scope(Subsystem::Recorder, run(app, events)));
+}
+
+/// Start following the watcher's events
+pub fn attach(app: &tauri::AppHandle) {
+    if let Ok(mut session) = app.state::<AppState>().game_session.lock() {
+        session.attach(app);
+    }
+}
+
+/// Stop following the watcher's events (a game being recorded still finishes)
+pub fn detach(app: &tauri::AppHandle) {
+    if let Ok(mut session) = app.state::<AppState>().game_session.lock() {
+        session.detach(app);
+    }
+}
+
+/// Tell the session about something that didn't come from the watcher
+pub fn send(app: &tauri::AppHandle, event: SessionEvent) {
+    if let Ok(session) = app.state::<AppState>().game_session.lock() {
+        session.send(event);
+    }
+}
+
+/// Read from settings.json on every use, so changes apply to a running watcher
+pub fn game_end_timing(app: &tauri::AppHandle) -> GameEndTiming {
+    let store = app.store("settings.json").ok();
+    let get = |key: &str, range: RangeInclusive<u32>, default: u32| {
+        store
+            .as_ref()
+            .and_then(|s| s.get(key))
+            .and_then(|v| v.as_u64())
+            .map(|v| u32::try_from(v).unwrap_or(u32::MAX).clamp(*range.start(), *range.end()))
+            .unwrap_or(default)
+    };
+    let defaults = Settings::default();
+
+    GameEndTiming {
+        set_end_timeout: Duration::from_secs(
+            60 * u64::from(get(
+                SET_END_TIMEOUT_MINUTES_KEY,
+                settings::SET_END_TIMEOUT_MINUTES,
+                defaults.set_end_timeout_minutes,
+            )),
+        ),
+        quiet_period: Duration::from_millis(
+            get(REPLAY_QUIET_PERIOD_MS_KEY, settings::REPLAY_QUIET_PERIOD_MS, defaults.replay_quiet_period_ms).into(),
+        ),
+        stop_delay: Duration::from_secs(
+            get(
+                STOP_RECORDING_DELAY_SECONDS_KEY,
+                settings::STOP_RECORDING_DELAY_SECONDS,
+                defaults.stop_recording_delay_seconds,
+            )
+            .into(),
+        ),
+        end_recording_delay: Duration::from_secs(
+            get(
+                END_RECORDING_DELAY_SECONDS_KEY,
+                settings::END_RECORDING_DELAY_SECONDS,
+                defaults.end_recording_delay_seconds,
+            )
+            .into(),
+        ),
+    }
+}
+
+async fn run(app: tauri::AppHandle, mut events: mpsc::UnboundedReceiver<SessionEvent>) {
+    let mut controller = GameSessionController::new(game_end_timing(&app));
+    loop {
+        let deadline = controller.deadline();
+        let actions = tokio::select! {
+            event = events.recv() => {
+                let Some(event) = event else { return };
+                if let SessionEvent::ReplayCreated(slp_path) = &event {
+                    // Beside the recording, which doesn't wait for it
+                    tauri::async_runtime::spawn(announce(app.clone(), slp_path.clone()));
+                    if !replay_created(&app, slp_path) {
+                        continue;
+                    }
+                    controller.set_filters(watch_filters(&app));
+                    let rules = recording_rules::enabled_rules(&app);
+                    let skip_cpu = !record_cpu_matches(&app);
+                    let game = if skip_cpu || controller.needs_game_start() || !rules.is_empty() {
+                        game_start(slp_path).await
+                    } else {
+                        None
+                    };
+                    let rule = game.as_ref().and_then(|game| recording_rules::decide(&app, slp_path, &rules, game));
+                    match rule.map(|rule| rule.action) {
+                        Some(RecordingRuleAction::Skip) => {
+                            log::info!("🚫 Not recording {}: skipped by a recording rule", slp_path);
+                            continue;
+                        }
+                        // Only the rules on the path still apply
+                        Some(RecordingRuleAction::Record | RecordingRuleAction::RecordAtQuality) => {
+                            if !controller.admits(slp_path, None) {
+                                continue;
+                            }
+                        }
+                        None => {
+                            if skip_cpu && game.as_ref().is_some_and(GameStart::is_cpu_match) {
+                                log::info!("🤖 Not recording a match against CPUs: {}", slp_path);
+                                continue;
+                            }
+                            if !controller.admits(slp_path, game.as_ref()) {
+                                continue;
+                            }
+                        }
+                    }
+                }
+                controller.set_timing(game_end_timing(&app));
+                controller.handle(event, Instant::now(), recording(&app))
+            }
+            _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now).into()), if deadline.is_some() => {
+                controller.set_timing(game_end_timing(&app));
+                controller.tick(Instant::now())
+            }
+        };
+
+        let mut actions = VecDeque::from(actions);
+        while let Some(action) = actions.pop_front() {
+            if let Some(outcome) = perform(&app, action).await {
+                actions.extend(controller.handle(outcome, Instant::now(), recording(&app)));
+            }
+        }
+        log::debug!("Game session: {:?}", controller.state());
+    }
+}
+
+/// Note the new replay. Returns whether auto-recording is on
+fn replay_created(app: &tauri::AppHandle, slp_path: &str) -> bool {
+    log::info!("New replay: {}", slp_path);
+    let state = app.state::<AppState>();
+    if let Ok(mut last_replay) = state.last_replay_path.lock() {
+        *last_replay = Some(slp_path.to_string());
+        if let Err(e) = app.emit(game_events::LAST_REPLAY_UPDATED, slp_path) {
+            log::error!("Failed to emit {} event: {:?}", game_events::LAST_REPLAY_UPDATED, e);
+        }
+    }
+
+    let auto_start = state
+        .settings
+        .lock()
+        .map(|settings| settings.get("autoStartRecording").and_then(|v| v.as_bool()).unwrap_or(true))
+        .unwrap_or(true);
+    if !auto_start {
+        log::info!("Auto-start recording is disabled");
+        return false;
+    }
+    if replay_only_mode(app) {
+        log::info!("Replay-only mode, not recording");
+        return false;
+    }
+    true
+}
+
+/// The new game's Game Start, for the recording rules, the CPU match check, the watch filters
+/// and announcing the game. None if it isn't written in time, and then the game is recorded
+async fn game_start(slp_path: &str) -> Option<GameStart> {
+    for _ in 0..GAME_START_ATTEMPTS {
+        if let Some(game) = read_game_start(Path::new(slp_path)) {
+            return Some(game);
+        }
+        tokio::time::sleep(GAME_START_RETRY).await;
+    }
+    log::debug!("Game Start of {} not written in time", slp_path);
+    None
+}
+
+/// Tell who is playing as soon as a new replay's Game Start is written:
+/// Discord presence, a provisional stats row for the library (replaced by the
+/// full parse) and game-started for the frontend
+async fn announce(app: tauri::AppHandle, slp_path: String) {
+    let game = game_start(&slp_path).await;
+    let identity = my_identity(&app);
+    let state = app.state::<AppState>();
+    state.discord_presence.game_started(game.clone(), identity.clone());
+    let Some(game) = game else { return };
+
+    let recording_id = library::replay_id_for(Path::new(&slp_path));
+    if let Some(id) = &recording_id {
+        let row = provisional_stats(id, &slp_path, &game);
+        match database::insert_provisional_game_stats(&state.database.connection(), &row) {
+            Ok(true) => log::debug!("Saved provisional stats for {}", id),
+            Ok(false) => {}
+            Err(e) => log::warn!("Failed to save provisional stats for {}: {}", slp_path, e),
+        }
+    }
+
+    let me = my_player(&game.players, &identity);
+    let started = StartedGame { matchup: matchup(&game.players, me), slp_path, recording_id, game, me };
+    log::info!("🎮 Game started: {}", started.matchup);
+    if let Err(e) = app.emit(game_events::STARTED, &started) {
+        log::error!("Failed to emit {} event: {:?}", game_events::STARTED, e);
+    }
+}
+
+/// Which player is me: by connect code, else the only one on a preferred character
+pub fn my_player(players: &[GameStartPlayer], identity: &MyIdentity) -> Option<usize> {
+    players
+        .iter()
+        .position(|p| {
+            p.connect_code
+                .as_ref()
+                .is_some_and(|c| identity.connect_codes.contains(&c.to_uppercase()))
+        })
+        .or_else(|| {
+            let matches: Vec<usize> = players
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| identity.preferred_characters.contains(&(p.character_id as i32)))
+                .map(|(i, _)| i)
+                .collect();
+            (matches.len() == 1).then(|| matches[0])
+        })
+}
+
+/// The players' characters, "Fox vs Marth", with mine first
+fn matchup(players: &[GameStartPlayer], me: Option<usize>) -> String {
+    let mut players: Vec<&GameStartPlayer> = players.iter().collect();
+    if let Some(me) = me.filter(|&me| me < players.len()) {
+        let mine = players.remove(me);
+        players.insert(0, mine);
+    }
+    players
+        .iter()
+        .map(|p| melee_ids::character_name(p.character_id))
+        .collect::<Vec<_>>()
+        .join(" vs ")
+}
+
+/// The game_stats row saved for a game until its stats are computed: its
+/// first two players (ports 1-4, like the stats pass), stage and version
+fn provisional_stats(recording_id: &str, slp_path: &str, game: &GameStart) -> GameStatsRow {
+    let player = |index: usize| game.players.get(index);
+    GameStatsRow {
+        id: recording_id.to_string(),
+        player1_id: player(0).and_then(|p| p.connect_code.clone()),
+        player2_id: player(1).and_then(|p| p.connect_code.clone()),
+        player1_port: player(0).map(|p| i32::from(p.port) + 1),
+        player2_port: player(1).map(|p| i32::from(p.port) + 1),
+        player1_character: player(0).map(|p| i32::from(p.character_id)),
+        player2_character: player(1).map(|p| i32::from(p.character_id)),
+        player1_color: None,
+        player2_color: None,
+        winner_port: None,
+        loser_port: None,
+        stage: Some(i32::from(game.stage)),
+        game_duration: None,
+        total_frames: None,
+        is_pal: None,
+        played_on: None,
+        created_at: Some(crate::timestamps::now()),
+        slp_path: Some(slp_path.to_string()),
+        replay_version: Some(game.version.to_string()),
+        excluded_reason: Some("provisional".to_string()),
+        total_paused_frames: None,
+        winner_code: None,
+        loser_code: None,
+        my_result: database::MyResult::Unknown,
+        is_warmup: false,
+        stats_source: database::StatsSource::Buckwheat,
+        // Not over yet; the full stats say how it ended
+        completed: false,
+    }
+}
+
+/// Whether something other than a pre-roll waiting for its game is being recorded
+fn recording(app: &tauri::AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let busy = state.recorder.lock().map(|r| r.is_some()).unwrap_or(false);
+    busy && !pre_roll::waiting(&state)
+}
+
+/// Carry out an action. Returns what came of it, for the controller
+async fn perform(app: &tauri::AppHandle, action: Action) -> Option<SessionEvent> {
+    let state = app.state::<AppState>();
+    let per_set = || state.recording_set.lock().map(|set| set.is_some()).unwrap_or(false);
+    match action {
+        Action::StartRecording(slp_path) => {
+            if let Some((started_at, offset)) = pre_roll::adopt(&state, &slp_path) {
+                if let Some(quality) = recording_rules::take_quality(&state, &slp_path) {
+                    log::info!("The pre-roll is already recording, so not switching to {:?} quality", quality);
+                }
+                continue_pre_roll(app, slp_path, started_at, offset);
+                return Some(SessionEvent::RecordingStarted { per_set: per_set() });
+            }
+            match trigger_auto_recording(app.clone(), slp_path).await {
+                Ok(()) => Some(SessionEvent::RecordingStarted { per_set: per_set() }),
+                Err(e) => {
+                    log::error!("Failed to trigger auto-recording: {:?}", e);
+                    Some(SessionEvent::RecordingFailed)
+                }
+            }
+        }
+        Action::JoinSet(slp_path) => Some(if join_recording_set(app, &slp_path).await {
+            SessionEvent::SetJoined(slp_path)
+        } else {
+            SessionEvent::SetEnded(slp_path)
+        }),
+        Action::StopRecording => {
+            if let Err(e) = stop_recording_internal(app).await {
+                // This is expected if already stopped, only log at debug level
+                log::debug!("Stop recording result: {:?}", e);
+            }
+            Some(SessionEvent::RecordingStopped)
+        }
+        Action::StartPreRoll => {
+            // Back to character select, if the game is still open
+            warm_start::prewarm(app);
+            pre_roll::start(app);
+            None
+        }
+    }
+}
+
+/// A replay and the game's recording share a file name
+fn same_game(a: &str, b: &str) -> bool {
+    let stem = |path: &str| Path::new(path).file_stem().map(|s| s.to_os_string());
+    stem(a).is_some() && stem(a) == stem(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    const TIMING: GameEndTiming = GameEndTiming {
+        quiet_period: Duration::from_secs(1),
+        stop_delay: Duration::from_secs(3),
+        end_recording_delay: Duration::ZERO,
+        set_end_timeout: Duration::from_secs(300),
+    };
+    const GAME_1: &str = "C:/Slippi/Game_20240101T120000.slp";
+    const GAME_2: &str = "C:/Slippi/Game_20240101T120500.slp";
+
+    fn secs(start: Instant, s: u64) -> Instant {
+        start + Duration::from_secs(s)
+    }
+
+    /// Start recording GAME_1 and play it until its last write at `start` + 60s
+    fn play_game(controller: &mut GameSessionController, start: Instant, per_set: bool) {
+        let created = controller.handle(SessionEvent::ReplayCreated(GAME_1.into()), start, false);
+        assert_eq!(created, vec![Action::StartRecording(GAME_1.into())]);
+        controller.handle(SessionEvent::RecordingStarted { per_set }, start, false);
+        for s in [1, 30, 60] {
+            controller.handle(SessionEvent::ReplayModified(GAME_1.into(), secs(start, s)), secs(start, s), false);
+        }
+        assert_eq!(controller.deadline(), Some(secs(start, 61)));
+    }
+
+    #[test]
+    fn test_game_is_recorded_until_the_delays_after_it_end() {
+        let start = Instant::now();
+        let mut controller = GameSessionController::new(TIMING);
+        play_game(&mut controller, start, false);
+
+        // Other replays' writes and early ticks change nothing
+        controller.handle(SessionEvent::ReplayModified(GAME_2.into(), secs(start, 61)), secs(start, 61), false);
+        assert!(controller.tick(secs(start, 60)).is_empty());
+        assert!(matches!(controller.state(), SessionState::Recording { .. }));
+
+        assert!(controller.tick(secs(start, 61)).is_empty());
+        assert!(matches!(controller.state(), SessionState::GameEnded { per_set: false, .. }));
+        assert_eq!(controller.deadline(), Some(secs(start, 64)));
+        assert_eq!(controller.tick(secs(start, 64)), vec![Action::StopRecording]);
+        assert_eq!(controller.state(), &SessionState::Finalizing);
+
+        let stopped = controller.handle(SessionEvent::RecordingStopped, secs(start, 65), false);
+        assert_eq!(stopped, vec![Action::StartPreRoll]);
+        assert_eq!(controller.state(), &SessionState::Idle);
+        assert_eq!(controller.deadline(), None);
+    }
+
+    #[test]
+    fn test_new_game_cuts_the_wait_short() {
+        let start = Instant::now();
+        let mut controller = GameSessionController::new(TIMING);
+        play_game(&mut controller, start, false);
+        controller.tick(secs(start, 61));
+
+        let actions = controller.handle(SessionEvent::ReplayCreated(GAME_2.into()), secs(start, 62), false);
+        assert_eq!(actions, vec![Action::StopRecording, Action::StartRecording(GAME_2.into())]);
+        // The stop's outcome doesn't end the new game's session
+        assert!(controller.handle(SessionEvent::RecordingStopped, secs(start, 62), false).is_empty());
+        assert_eq!(controller.state(), &SessionState::GameStarted { slp_path: GAME_2.into() });
+        assert_eq!(controller.deadline(), None);
+
+        // Stopped by hand, and a game started next to a manual recording
+        controller.handle(SessionEvent::RecordingStarted { per_set: false }, secs(start, 63), false);
+        controller.handle(SessionEvent::RecordingStopped, secs(start, 70), false);
+        assert_eq!(controller.state(), &SessionState::Idle);
+        assert!(controller.handle(SessionEvent::ReplayCreated(GAME_1.into()), secs(start, 80), true).is_empty());
+    }
+
+    #[test]
+    fn test_set_recording_spans_games() {
+        let start = Instant::now();
+        let mut controller = GameSessionController::new(TIMING);
+        play_game(&mut controller, start, true);
+        controller.tick(secs(start, 61));
+        assert_eq!(controller.deadline(), Some(secs(start, 361)));
+
+        let actions = controller.handle(SessionEvent::ReplayCreated(GAME_2.into()), secs(start, 90), false);
+        assert_eq!(actions, vec![Action::JoinSet(GAME_2.into())]);
+        controller.handle(SessionEvent::SetJoined(GAME_2.into()), secs(start, 91), false);
+        assert_eq!(controller.state(), &SessionState::Recording { slp_path: GAME_2.into(), per_set: true });
+        assert_eq!(controller.deadline(), None);
+
+        controller.handle(SessionEvent::ReplayModified(GAME_2.into(), secs(start, 150)), secs(start, 150), false);
+        controller.tick(secs(start, 151));
+        let closed = controller.handle(SessionEvent::GameWindowClosed, secs(start, 160), false);
+        assert_eq!(closed, vec![Action::StopRecording]);
+
+        // A game from another match starts a new recording
+        let mut controller = GameSessionController::new(TIMING);
+        play_game(&mut controller, start, true);
+        controller.tick(secs(start, 61));
+        controller.handle(SessionEvent::ReplayCreated(GAME_2.into()), secs(start, 90), false);
+        let actions = controller.handle(SessionEvent::SetEnded(GAME_2.into()), secs(start, 91), false);
+        assert_eq!(actions, vec![Action::StopRecording, Action::StartRecording(GAME_2.into())]);
+    }
+
+    #[test]
+    fn test_watch_filters_hold_back_games() {
+        let start = Instant::now();
+        let mut controller = GameSessionController::new(TIMING);
+        controller.set_filters(WatchFilters {
+            ignore_patterns: vec!["*T1205??.slp".into()],
+            min_trigger_interval_seconds: 120,
+            ..Default::default()
+        });
+        assert!(!controller.needs_game_start());
+        assert!(controller.admits(GAME_1, None));
+        assert!(!controller.admits(GAME_2, None));
+
+        play_game(&mut controller, start, false);
+        controller.tick(secs(start, 61));
+        // Too soon after GAME_1's recording started: GAME_1's ends as usual
+        let third = "C:/Slippi/Game_20240101T120130.slp";
+        assert!(controller.handle(SessionEvent::ReplayCreated(third.into()), secs(start, 90), false).is_empty());
+        assert_eq!(controller.tick(secs(start, 64)), vec![Action::StopRecording]);
+        controller.handle(SessionEvent::RecordingStopped, secs(start, 65), false);
+
+        let actions = controller.handle(SessionEvent::ReplayCreated(third.into()), secs(start, 120), false);
+        assert_eq!(actions, vec![Action::StartRecording(third.into())]);
+    }
+
+    #[test]
+    fn test_replays_created_together_record_the_first() {
+        let start = Instant::now();
+        let mut controller = GameSessionController::new(TIMING);
+        let other_console = "C:/Slippi/Game_20240101T120000-2.slp";
+        let later = Duration::from_millis(300);
+
+        let first = controller.handle(SessionEvent::ReplayCreated(GAME_1.into()), start, false);
+        assert_eq!(first, vec![Action::StartRecording(GAME_1.into())]);
+        // Before and after the recording started, the second replay changes nothing
+        assert!(controller.handle(SessionEvent::ReplayCreated(other_console.into()), start + later, false).is_empty());
+        controller.handle(SessionEvent::RecordingStarted { per_set: false }, start + later, false);
+        assert!(controller.handle(SessionEvent::ReplayCreated(other_console.into()), start + later * 2, false).is_empty());
+        controller.handle(SessionEvent::ReplayModified(other_console.into(), secs(start, 1)), secs(start, 1), false);
+        assert_eq!(controller.state(), &SessionState::Recording { slp_path: GAME_1.into(), per_set: false });
+        assert_eq!(controller.deadline(), None);
+
+        // A game starting later still cuts the recording short
+        let actions = controller.handle(SessionEvent::ReplayCreated(GAME_2.into()), secs(start, 5), false);
+        assert_eq!(actions, vec![Action::StopRecording, Action::StartRecording(GAME_2.into())]);
+
+        // Only one trigger gets the recording, until it's stopped or given back
+        let claim = RecordingClaim::default();
+        let session = claim.claim().unwrap();
+        assert_eq!((claim.claim(), claim.holder()), (None, Some(session)));
+        claim.release(session + 1);
+        assert_eq!(claim.holder(), Some(session));
+        claim.release(session);
+        let next = claim.claim().unwrap();
+        assert_ne!(next, session);
+        claim.clear();
+        assert_eq!(claim.holder(), None);
+    }
+
+    /// Listeners kept by name, like the app's event system
+    #[derive(Default)]
+    struct FakeSource {
+        listeners: Mutex<Vec<Option<(&'static str, mpsc::UnboundedSender<SessionEvent>, fn(String) -> SessionEvent)>>>,
+    }
+
+    impl FakeSource {
+        fn emit(&self, event: &str, path: &str) {
+            for (name, events, wrap) in self.listeners.lock().unwrap().iter().flatten() {
+                if *name == event {
+                    events.send(wrap(path.to_string())).unwrap();
+                }
+            }
+        }
+
+        fn live(&self) -> usize {
+            self.listeners.lock().unwrap().iter().flatten().count()
+        }
+    }
+
+    impl EventSource for FakeSource {
+        fn forward(
+            &self,
+            event: &'static str,
+            events: mpsc::UnboundedSender<SessionEvent>,
+            wrap: fn(String) -> SessionEvent,
+        ) -> EventId {
+            let mut listeners = self.listeners.lock().unwrap();
+            listeners.push(Some((event, events, wrap)));
+            listeners.len() as EventId - 1
+        }
+
+        fn unlisten(&self, id: EventId) {
+            self.listeners.lock().unwrap()[id as usize] = None;
+        }
+    }
+
+    #[test]
+    fn test_watching_again_does_not_duplicate_events() {
+        let source = FakeSource::default();
+        let mut session = GameSession::default();
+        let mut events = session.receiver.take().unwrap();
+
+        session.attach(&source);
+        session.detach(&source);
+        session.attach(&source);
+        session.attach(&source);
+        assert_eq!(source.live(), 2);
+
+        source.emit(game_events::FILE_CREATED, GAME_1);
+        assert_eq!(events.try_recv(), Ok(SessionEvent::ReplayCreated(GAME_1.into())));
+        assert!(events.try_recv().is_err());
+
+        session.detach(&source);
+        assert_eq!(source.live(), 0);
+        source.emit(game_events::FILE_CREATED, GAME_2);
+        assert!(events.try_recv().is_err());
+    }
+}
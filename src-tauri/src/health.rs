@@ -0,0 +1,314 @@
+//! Health of background subsystems and recovery from panics
+//!
+//! Background work runs on spawned tasks and threads, where a panic used to
+//! disappear: the task died, nothing was logged in release builds and the
+//! feature stayed dead until the app was restarted. The panic hook installed
+//! here logs every panic with a backtrace. When the panicking code belongs to
+//! a subsystem (async work wrapped in `scope`, threads or synchronous work
+//! inside `enter`), the subsystem is marked failed and the frontend gets a
+//! BACKGROUND_TASK_FAILED event. The supervisor restarts the watcher and the
+//! sync worker with exponential backoff; the recorder and stats recover on
+//! their next successful run.
+
+use crate::events;
+use serde::Serialize;
+use std::cell::Cell;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+/// First restart delay, doubled on each consecutive failure
+const RESTART_BASE_DELAY: Duration = Duration::from_secs(2);
+const RESTART_MAX_DELAY: Duration = Duration::from_secs(60);
+/// Give up after this many failures without a stable period in between
+const MAX_CONSECUTIVE_RESTARTS: u32 = 5;
+/// Running this long after a restart resets the backoff
+const STABLE_PERIOD: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Subsystem {
+    Watcher,
+    Recorder,
+    Sync,
+    Stats,
+}
+
+impl Subsystem {
+    const ALL: [Subsystem; 4] = [Subsystem::Watcher, Subsystem::Recorder, Subsystem::Sync, Subsystem::Stats];
+
+    fn is_restartable(self) -> bool {
+        matches!(self, Subsystem::Watcher | Subsystem::Sync)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthStatus {
+    Idle,
+    Running,
+    Failed,
+    Restarting,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubsystemHealth {
+    pub subsystem: Subsystem,
+    pub status: HealthStatus,
+    /// Last panic message (kept after the subsystem recovers)
+    pub last_error: Option<String>,
+    pub failed_at: Option<String>,
+    /// Automatic restarts since the app started
+    pub restarts: u32,
+}
+
+/// Payload of BACKGROUND_TASK_FAILED
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackgroundTaskFailed {
+    pub subsystem: Subsystem,
+    pub message: String,
+    /// Whether the supervisor will restart it
+    pub restarting: bool,
+}
+
+/// Health of every subsystem, shared with the panic hook
+#[derive(Debug)]
+pub struct Health {
+    subsystems: Mutex<Vec<SubsystemHealth>>,
+    supervisor: Mutex<Option<UnboundedSender<Subsystem>>>,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            subsystems: Mutex::new(
+                Subsystem::ALL
+                    .iter()
+                    .map(|&subsystem| SubsystemHealth {
+                        subsystem,
+                        status: HealthStatus::Idle,
+                        last_error: None,
+                        failed_at: None,
+                        restarts: 0,
+                    })
+                    .collect(),
+            ),
+            supervisor: Mutex::new(None),
+        }
+    }
+}
+
+impl Health {
+    /// Current state of every subsystem
+    pub fn snapshot(&self) -> Vec<SubsystemHealth> {
+        self.subsystems.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    pub fn set_status(&self, subsystem: Subsystem, status: HealthStatus) {
+        self.update(subsystem, |health| health.status = status);
+    }
+
+    /// Mark a subsystem failed and hand it to the supervisor if it can be restarted
+    /// Returns whether it will be restarted
+    fn report_failure(&self, subsystem: Subsystem, message: &str) -> bool {
+        let restarting = subsystem.is_restartable()
+            && self
+                .supervisor
+                .lock()
+                .ok()
+                .and_then(|s| s.as_ref().map(|tx| tx.send(subsystem).is_ok()))
+                .unwrap_or(false);
+
+        self.update(subsystem, |health| {
+            health.status = if restarting { HealthStatus::Restarting } else { HealthStatus::Failed };
+            health.last_error = Some(message.to_string());
+            health.failed_at = Some(chrono::Utc::now().to_rfc3339());
+        });
+        restarting
+    }
+
+    fn update(&self, subsystem: Subsystem, f: impl FnOnce(&mut SubsystemHealth)) {
+        // Never panic here: this runs inside the panic hook
+        let Ok(mut subsystems) = self.subsystems.lock() else {
+            return;
+        };
+        if let Some(health) = subsystems.iter_mut().find(|h| h.subsystem == subsystem) {
+            f(health);
+        }
+    }
+}
+
+tokio::task_local! {
+    static TASK_SUBSYSTEM: Subsystem;
+}
+
+thread_local! {
+    static THREAD_SUBSYSTEM: Cell<Option<Subsystem>> = const { Cell::new(None) };
+}
+
+/// Attribute panics in an async task to a subsystem
+pub fn scope<F: Future>(subsystem: Subsystem, future: F) -> impl Future<Output = F::Output> {
+    TASK_SUBSYSTEM.scope(subsystem, future)
+}
+
+/// Attribute panics on this thread to a subsystem until the guard drops
+/// (only for threads and synchronous code; use `scope` across awaits)
+pub fn enter(subsystem: Subsystem) -> SubsystemGuard {
+    SubsystemGuard { previous: THREAD_SUBSYSTEM.with(|s| s.replace(Some(subsystem))) }
+}
+
+pub struct SubsystemGuard {
+    previous: Option<Subsystem>,
+}
+
+impl Drop for SubsystemGuard {
+    fn drop(&mut self) {
+        THREAD_SUBSYSTEM.with(|s| s.set(self.previous));
+    }
+}
+
+fn current_subsystem() -> Option<Subsystem> {
+    TASK_SUBSYSTEM.try_with(|s| *s).ok().or_else(|| THREAD_SUBSYSTEM.with(|s| s.get()))
+}
+
+/// Install the panic hook and start the supervisor
+pub fn install(app: &tauri::AppHandle, health: Arc<Health>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    if let Ok(mut supervisor) = health.supervisor.lock() {
+        *supervisor = Some(tx);
+    }
+    tauri::async_runtime::spawn(supervise(app.clone(), health.clone(), rx));
+
+    let app = app.clone();
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = panic_message(info);
+        let subsystem = current_subsystem();
+        let thread = std::thread::current();
+        log::error!(
+            "💥 Panic in {} (thread '{}'): {}\n{}",
+            subsystem.map(|s| format!("{:?}", s).to_lowercase()).unwrap_or_else(|| "app".to_string()),
+            thread.name().unwrap_or("unnamed"),
+            message,
+            std::backtrace::Backtrace::force_capture()
+        );
+
+        if let Some(subsystem) = subsystem {
+            let restarting = health.report_failure(subsystem, &message);
+            let _ = app.emit(
+                events::health::BACKGROUND_TASK_FAILED,
+                BackgroundTaskFailed { subsystem, message, restarting },
+            );
+        }
+
+        if cfg!(debug_assertions) {
+            previous_hook(info);
+        }
+    }));
+}
+
+fn panic_message(info: &std::panic::PanicHookInfo) -> String {
+    let payload = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    match info.location() {
+        Some(location) => format!("{} at {}:{}", payload, location.file(), location.line()),
+        None => payload,
+    }
+}
+
+/// Delay before the nth consecutive restart, None once it's time to give up
+fn restart_delay(consecutive: u32) -> Option<Duration> {
+    if consecutive == 0 || consecutive > MAX_CONSECUTIVE_RESTARTS {
+        return None;
+    }
+    Some(RESTART_BASE_DELAY.saturating_mul(1 << (consecutive - 1)).min(RESTART_MAX_DELAY))
+}
+
+async fn supervise(app: tauri::AppHandle, health: Arc<Health>, mut failures: UnboundedReceiver<Subsystem>) {
+    let mut history: Vec<(Subsystem, u32, Instant)> = Vec::new();
+
+    while let Some(subsystem) = failures.recv().await {
+        let consecutive = match history.iter_mut().find(|(s, _, _)| *s == subsystem) {
+            Some((_, count, last)) => {
+                *count = if last.elapsed() > STABLE_PERIOD { 1 } else { *count + 1 };
+                *last = Instant::now();
+                *count
+            }
+            None => {
+                history.push((subsystem, 1, Instant::now()));
+                1
+            }
+        };
+
+        let Some(delay) = restart_delay(consecutive) else {
+            log::error!("{:?} failed {} times in a row, not restarting it again", subsystem, consecutive - 1);
+            health.set_status(subsystem, HealthStatus::Failed);
+            continue;
+        };
+
+        log::warn!("🔁 Restarting {:?} in {:?} (attempt {})", subsystem, delay, consecutive);
+        let app = app.clone();
+        let health = health.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(delay).await;
+            health.update(subsystem, |h| h.restarts += 1);
+            let restarted = match subsystem {
+                Subsystem::Watcher => crate::commands::slippi::restart_watching(&app),
+                Subsystem::Sync => app
+                    .state::<crate::app_state::AppState>()
+                    .scheduler
+                    .run_now(&app, crate::library::SYNC_JOB)
+                    .map(|_| ()),
+                Subsystem::Recorder | Subsystem::Stats => Ok(()),
+            };
+            if let Err(e) = restarted {
+                log::error!("Failed to restart {:?}: {}", subsystem, e);
+                health.update(subsystem, |h| {
+                    h.status = HealthStatus::Failed;
+                    h.last_error = Some(e.to_string());
+                });
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restart_backoff_grows_then_gives_up() {
+        assert_eq!(restart_delay(1), Some(Duration::from_secs(2)));
+        assert_eq!(restart_delay(3), Some(Duration::from_secs(8)));
+        assert_eq!(restart_delay(MAX_CONSECUTIVE_RESTARTS), Some(Duration::from_secs(32)));
+        assert_eq!(restart_delay(MAX_CONSECUTIVE_RESTARTS + 1), None);
+    }
+
+    #[test]
+    fn test_panics_are_attributed_to_the_current_subsystem() {
+        assert_eq!(current_subsystem(), None);
+        {
+            let _outer = enter(Subsystem::Recorder);
+            {
+                let _inner = enter(Subsystem::Stats);
+                assert_eq!(current_subsystem(), Some(Subsystem::Stats));
+            }
+            assert_eq!(current_subsystem(), Some(Subsystem::Recorder));
+        }
+        assert_eq!(current_subsystem(), None);
+
+        let health = Health::default();
+        assert!(!health.report_failure(Subsystem::Sync, "boom"));
+        let sync = health.snapshot().into_iter().find(|h| h.subsystem == Subsystem::Sync).unwrap();
+        assert_eq!(sync.status, HealthStatus::Failed);
+        assert_eq!(sync.last_error.as_deref(), Some("boom"));
+    }
+}
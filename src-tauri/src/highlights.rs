@@ -0,0 +1,222 @@
+//! Highlights: the best combos across the library
+//!
+//! Every combo (a slippi-js conversion) saved by the stats pass gets a
+//! highlight score from its damage, how many different moves it used, and
+//! whether it killed, with early kills (low opponent percent at the start,
+//! e.g. zero-to-deaths) scoring highest.
+//!
+//! When enabled, a daily job clips the day's best highlights of my games
+//! into the Clips folder and announces them with the clips CREATED event.
+//! Clipping waits at the work gate while a game is being recorded.
+
+use crate::app_state::AppState;
+use crate::commands::clips::{default_clips_directory, save_clip};
+use crate::commands::errors::Error;
+use crate::commands::settings::my_identity;
+use crate::database::{self, ComboRow, HighlightRow, PlayerScope, StatsFilter};
+use crate::events;
+use crate::scheduler::{Cadence, Constraints, Job};
+use crate::work_gate::JobKind;
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+
+/// The video starts with the game, on Slippi frame -123
+const FIRST_FRAME: i32 = -123;
+const FRAMES_PER_SECOND: f64 = 60.0;
+/// Time kept before the first hit and after the last one in a clip
+const CLIP_LEAD_IN_SECS: f64 = 2.0;
+const CLIP_LEAD_OUT_SECS: f64 = 1.5;
+
+/// Settings keys for the daily job
+const ENABLED_KEY: &str = "autoHighlights";
+const COUNT_KEY: &str = "autoHighlightsCount";
+const LAST_RUN_KEY: &str = "highlightsLastRun";
+const DEFAULT_COUNT: u32 = 3;
+/// How often the scheduler checks whether a run is due
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Leave startup (cache sync, stats) alone before the first check
+const SCHEDULE_STARTUP_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// A highlight and the part of the video to clip
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Highlight {
+    #[serde(flatten)]
+    pub combo: HighlightRow,
+    pub clip_start_secs: f64,
+    pub clip_end_secs: f64,
+}
+
+impl From<HighlightRow> for Highlight {
+    fn from(combo: HighlightRow) -> Self {
+        // A paused game still writes (frozen) frames, one per video frame, so
+        // frames map straight onto the video through pauses
+        let to_secs = |frame: i32| combo.video_offset_secs + (frame - FIRST_FRAME) as f64 / FRAMES_PER_SECOND;
+        Self {
+            clip_start_secs: (to_secs(combo.start_frame) - CLIP_LEAD_IN_SECS).max(0.0),
+            clip_end_secs: to_secs(combo.end_frame) + CLIP_LEAD_OUT_SECS,
+            combo,
+        }
+    }
+}
+
+/// Score a combo as a highlight
+pub fn highlight_score(combo: &ComboRow) -> f64 {
+    let damage = (combo.end_percent - combo.start_percent).clamp(0.0, 200.0);
+    // Different moves count for much more than repeating one (e.g. uthrow uair chains)
+    let variety = combo.distinct_moves.clamp(0, 8) as f64 * 8.0;
+    let length = (combo.move_count - combo.distinct_moves).clamp(0, 10) as f64 * 2.0;
+    let kill = if combo.did_kill {
+        40.0 + (120.0 - combo.start_percent).clamp(0.0, 120.0) * 0.5
+    } else {
+        0.0
+    };
+    damage + variety + length + kill
+}
+
+/// Best highlights across the library, highest score first
+pub fn top_highlights(
+    app: &tauri::AppHandle,
+    scope: PlayerScope<'_>,
+    filter: Option<StatsFilter>,
+    unclipped_only: bool,
+    limit: u32,
+) -> Result<Vec<Highlight>, Error> {
+    let state = app.state::<AppState>();
+    let rows = database::get_top_highlights(&state.database.connection(), scope, filter, unclipped_only, limit)
+        .map_err(Error::from)?;
+    Ok(rows.into_iter().map(Highlight::from).collect())
+}
+
+/// Clip the best unclipped highlights of my games from the last day
+/// Returns the new clips' paths
+pub async fn generate_highlights(app: &tauri::AppHandle, count: u32) -> Result<Vec<String>, Error> {
+    let filter = StatsFilter {
+        start_time: Some(crate::timestamps::format(chrono::Utc::now() - chrono::Duration::days(1))),
+        ..Default::default()
+    };
+    // Everyone's combos are fair game until the user has told us who they are
+    let scope = if my_identity(app).is_configured() { PlayerScope::Me } else { PlayerScope::All };
+    let highlights: Vec<Highlight> = top_highlights(app, scope, Some(filter), true, count)?
+        .into_iter()
+        .filter(|h| Path::new(&h.combo.video_path).exists())
+        .collect();
+    if highlights.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    crate::clip_processor::ensure_ffmpeg()?;
+    let clips_dir = default_clips_directory(app).await?;
+    let app = app.clone();
+    let created = tauri::async_runtime::spawn_blocking(move || {
+        let mut created = Vec::new();
+        for highlight in highlights {
+            // Encoding competes with the capture for CPU
+            let gate = app.state::<AppState>().work_gate.clone();
+            let _permit = tauri::async_runtime::block_on(gate.acquire(JobKind::Clips));
+            match save_clip(&app, &highlight.combo.video_path, highlight.clip_start_secs, highlight.clip_end_secs, &clips_dir) {
+                Ok(clip) => {
+                    let state = app.state::<AppState>();
+                    if let Err(e) = database::set_combo_clip_path(&state.database.connection(), highlight.combo.combo_id, &clip.path) {
+                        log::warn!("Failed to mark highlight {} as clipped: {}", highlight.combo.combo_id, e);
+                    }
+                    created.push(clip);
+                }
+                Err(e) => log::warn!("Failed to clip highlight from {}: {}", highlight.combo.video_path, e),
+            }
+        }
+        if !created.is_empty() {
+            if let Err(e) = app.emit(events::clips::CREATED, &created) {
+                log::error!("Failed to emit {} event: {:?}", events::clips::CREATED, e);
+            }
+            crate::library::clips_added(&app);
+        }
+        created.into_iter().map(|clip| clip.path).collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| Error::RecordingFailed(format!("Highlight task failed: {}", e)))?;
+
+    log::info!("✨ Clipped {} highlight(s)", created.len());
+    Ok(created)
+}
+
+/// The daily highlights run as a scheduled job (off battery, and in the
+/// quiet hours if they're set)
+pub fn scheduled_job() -> Job {
+    Job {
+        name: "highlights",
+        cadence: Cadence::Every { delay: SCHEDULE_STARTUP_DELAY, interval: SCHEDULE_CHECK_INTERVAL },
+        constraints: Constraints { not_on_battery: true, quiet_hours_only: true, ..Default::default() },
+        due: is_due,
+        run: |app| Box::pin(run_scheduled(app)),
+    }
+}
+
+async fn run_scheduled(app: tauri::AppHandle) -> Result<(), Error> {
+    let clipped = generate_highlights(&app, highlight_count(&app)).await;
+    if let Ok(store) = app.store("settings.json") {
+        store.set(LAST_RUN_KEY, chrono::Utc::now().to_rfc3339());
+        if let Err(e) = store.save() {
+            log::warn!("Failed to save highlights run time: {}", e);
+        }
+    }
+    clipped.map(|_| ())
+}
+
+/// Whether the daily run is enabled and due
+fn is_due(app: &tauri::AppHandle) -> bool {
+    let Ok(store) = app.store("settings.json") else { return false };
+    if !store.get(ENABLED_KEY).and_then(|v| v.as_bool()).unwrap_or(false) {
+        return false;
+    }
+    let last_run = store
+        .get(LAST_RUN_KEY)
+        .and_then(|v| v.as_str().and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok()));
+    last_run.is_none_or(|t| (chrono::Utc::now() - t.with_timezone(&chrono::Utc)).num_days() >= 1)
+}
+
+/// How many highlights a run clips
+fn highlight_count(app: &tauri::AppHandle) -> u32 {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(COUNT_KEY))
+        .and_then(|v| v.as_u64())
+        .map(|n| n.clamp(1, 10) as u32)
+        .unwrap_or(DEFAULT_COUNT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combo(start_percent: f64, end_percent: f64, moves: i32, distinct: i32, did_kill: bool) -> ComboRow {
+        ComboRow {
+            recording_id: "r".to_string(),
+            player_index: 0,
+            opponent_index: 1,
+            start_frame: 600,
+            end_frame: 720,
+            start_percent,
+            end_percent,
+            move_count: moves,
+            distinct_moves: distinct,
+            did_kill,
+            highlight_score: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_highlight_score_ranking() {
+        let zero_to_death = highlight_score(&combo(0.0, 110.0, 7, 5, true));
+        let late_kill = highlight_score(&combo(130.0, 145.0, 1, 1, true));
+        let long_chain = highlight_score(&combo(10.0, 70.0, 6, 1, false));
+        let jab = highlight_score(&combo(40.0, 43.0, 1, 1, false));
+
+        assert!(zero_to_death > long_chain && long_chain > late_kill && late_kill > jab);
+        // Damage can't go negative (e.g. the opponent healed)
+        assert!(highlight_score(&combo(50.0, 20.0, 1, 1, false)) >= 0.0);
+    }
+}
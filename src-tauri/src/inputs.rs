@@ -0,0 +1,276 @@
+//! Controller input quality
+//!
+//! A background pass reads each stats game's frames (see slippi::frames) and
+//! counts, per player, the things a worn controller gets wrong:
+//! - dashbacks: a dash turned around. Smashing the stick back should start a
+//!   dash the other way on the next frame; if the stick spends that frame in
+//!   the tilt zone the character tilt turns (Turn) instead
+//! - pivots: a dashback whose new dash is cancelled on its first frame by
+//!   letting the stick go. Released a frame late, the dash carries on
+//! - empty pivots: pivots into standing not followed by an attack or grab
+//! - suspected snapbacks: the stick going from past 0.8 to past -0.2 the
+//!   other way within two frames, and no further (a deliberate turn or
+//!   dashback keeps going past -0.8), which is the stick's spring
+//!   overshooting when it's let go
+//!
+//! Events are judged with LOOKBACK frames before and LOOKAHEAD after, so the
+//! last few frames of a game aren't looked at. Counts are stored on the
+//! player_stats rows and aggregated by database::get_input_quality.
+
+use crate::app_state::AppState;
+use crate::commands::errors::Error;
+use crate::database::{self, InputQualityCounts};
+use crate::metrics;
+use crate::slippi::frames::{self, FrameRow, FrameSelection};
+use crate::work_gate::JobKind;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::Manager;
+
+/// Action states (Melee's IDs)
+const WAIT: u16 = 0x0E;
+const TURN: u16 = 0x12;
+const DASH: u16 = 0x14;
+/// Attack11 (jab) through AttackLw4 (down smash)
+const GROUND_ATTACKS: std::ops::RangeInclusive<u16> = 0x2C..=0x40;
+/// Standing grab
+const CATCH: u16 = 0xD4;
+
+/// Past this the stick is smashed (starts a dash)
+const SMASH_X: f32 = 0.8;
+/// Melee's analog deadzone
+const DEADZONE: f32 = 0.2875;
+/// How far past center a snapback has to overshoot
+const SNAPBACK_OVERSHOOT: f32 = 0.2;
+
+/// Frames looked at before and after the one being judged
+const LOOKBACK: usize = 2;
+const LOOKAHEAD: usize = 4;
+/// A pivot is empty without an attack or grab this many frames after it
+const EMPTY_PIVOT_FRAMES: usize = 3;
+
+/// Whether an analysis pass is running
+static ANALYZING: AtomicBool = AtomicBool::new(false);
+
+/// What the detectors look at on each frame
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    action_state: u16,
+    /// -1 facing left, 1 facing right
+    facing: f32,
+    joystick_x: f32,
+}
+
+impl From<&FrameRow> for Sample {
+    fn from(row: &FrameRow) -> Self {
+        Self { action_state: row.state.action_state, facing: row.state.facing, joystick_x: row.inputs.joystick_x }
+    }
+}
+
+/// Counts one player's events from their frames, in order
+#[derive(Default)]
+struct InputAnalyzer {
+    window: VecDeque<Sample>,
+    counts: InputQualityCounts,
+}
+
+impl InputAnalyzer {
+    fn push(&mut self, sample: Sample) {
+        self.window.push_back(sample);
+        if self.window.len() == LOOKBACK + 1 + LOOKAHEAD {
+            self.judge();
+            self.window.pop_front();
+        }
+    }
+
+    fn finish(self) -> InputQualityCounts {
+        self.counts
+    }
+
+    /// Judge the frame in the middle of the window
+    fn judge(&mut self) {
+        let w = self.window.make_contiguous();
+        let (prev, cur) = (w[LOOKBACK - 1], w[LOOKBACK]);
+        let after = &w[LOOKBACK + 1..];
+
+        let turned_around = cur.action_state == DASH && cur.facing != prev.facing;
+        if prev.action_state == DASH && (turned_around || cur.action_state == TURN) {
+            self.counts.dashback_attempts += 1;
+            if turned_around {
+                self.counts.dashback_successes += 1;
+                judge_pivot(&mut self.counts, after);
+            }
+        }
+
+        let snapback = |side: f32| {
+            let held = w[..LOOKBACK].iter().any(|s| side * s.joystick_x > SMASH_X);
+            let crossed = side * prev.joystick_x >= -SNAPBACK_OVERSHOOT && side * cur.joystick_x < -SNAPBACK_OVERSHOOT;
+            let stopped = w[LOOKBACK..=LOOKBACK + 2].iter().all(|s| side * s.joystick_x > -SMASH_X);
+            held && crossed && stopped
+        };
+        if snapback(1.0) || snapback(-1.0) {
+            self.counts.snapbacks += 1;
+        }
+    }
+}
+
+/// After a dashback: was the stick let go to pivot, and in time?
+fn judge_pivot(counts: &mut InputQualityCounts, after: &[Sample]) {
+    if after[..2].iter().all(|s| s.joystick_x.abs() >= DEADZONE) {
+        return;
+    }
+    counts.pivot_attempts += 1;
+    let next = after[0].action_state;
+    if next != WAIT && !is_attack(next) {
+        return;
+    }
+    counts.pivot_successes += 1;
+    if next == WAIT && !after[1..=EMPTY_PIVOT_FRAMES].iter().any(|s| is_attack(s.action_state)) {
+        counts.empty_pivots += 1;
+    }
+}
+
+fn is_attack(action_state: u16) -> bool {
+    GROUND_ATTACKS.contains(&action_state) || action_state == CATCH
+}
+
+/// Count each port's input events in a replay
+pub fn analyze_replay(path: &Path) -> Result<BTreeMap<u8, InputQualityCounts>, Error> {
+    let mut analyzers: BTreeMap<u8, InputAnalyzer> = BTreeMap::new();
+    frames::read_frames(path, &FrameSelection::default(), |row| {
+        analyzers.entry(row.port).or_default().push(Sample::from(&row));
+        Ok::<_, Error>(())
+    })?;
+    Ok(analyzers.into_iter().map(|(port, analyzer)| (port, analyzer.finish())).collect())
+}
+
+/// Analyze games that haven't been in the background, unless a pass is
+/// already running
+pub fn spawn_analysis(app: &tauri::AppHandle) {
+    if ANALYZING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = metrics::job("input_analysis", analyze_pending(&app)).await {
+            log::warn!("Failed to analyze inputs: {}", e);
+        }
+        ANALYZING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Returns how many games were analyzed
+async fn analyze_pending(app: &tauri::AppHandle) -> Result<usize, Error> {
+    let state = app.state::<AppState>();
+    let pending: Vec<_> = database::get_pending_input_analysis(&state.database.connection())
+        .map_err(Error::from)?
+        .into_iter()
+        .filter(|game| Path::new(&game.slp_path).exists())
+        .collect();
+    if pending.is_empty() {
+        return Ok(0);
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let mut analyzed = 0;
+        for game in pending {
+            // Reading whole replays competes with the encoder
+            let _permit = tauri::async_runtime::block_on(state.work_gate.acquire(JobKind::Stats));
+            let counts = match analyze_replay(Path::new(&game.slp_path)) {
+                Ok(counts) => counts,
+                Err(e) => {
+                    log::debug!("Failed to analyze inputs of {}: {}", game.slp_path, e);
+                    continue;
+                }
+            };
+            let conn = state.database.connection();
+            let stored = counts.iter().try_for_each(|(port, counts)| {
+                database::set_input_quality(&conn, &game.recording_id, i32::from(*port), counts)
+            });
+            match stored.and_then(|()| database::finish_input_analysis(&conn, &game.recording_id)) {
+                Ok(()) => analyzed += 1,
+                Err(e) => log::warn!("Failed to store input quality for {}: {}", game.recording_id, e),
+            }
+        }
+        if analyzed > 0 {
+            log::info!("🎮 Analyzed inputs of {} game(s)", analyzed);
+        }
+        analyzed
+    })
+    .await
+    .map_err(|e| Error::RecordingFailed(format!("Input analysis task failed: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FTILT: u16 = 0x33;
+
+    /// Run the detectors over (action state, facing, stick x) frames, with
+    /// standing frames around them to fill the window
+    fn analyze(frames: &[(u16, f32, f32)]) -> InputQualityCounts {
+        let standing = (WAIT, 1.0, 0.0);
+        let mut analyzer = InputAnalyzer::default();
+        let (before, after) = ([standing; LOOKBACK], [standing; LOOKAHEAD]);
+        for &(action_state, facing, joystick_x) in before.iter().chain(frames).chain(&after) {
+            analyzer.push(Sample { action_state, facing, joystick_x });
+        }
+        analyzer.finish()
+    }
+
+    #[test]
+    fn test_input_analysis_counts_hand_checked_sequences() {
+        let dash_right = [(DASH, 1.0, 1.0); 3];
+
+        // Clean dashback, stick held into the new dash
+        let dashback = [&dash_right[..], &[(DASH, -1.0, -1.0); 5]].concat();
+        assert_eq!(
+            analyze(&dashback),
+            InputQualityCounts { dashback_attempts: 1, dashback_successes: 1, ..Default::default() }
+        );
+
+        // The stick spent a frame in the tilt zone: tilt turn
+        let tilt_turn = [&dash_right[..], &[(TURN, 1.0, -0.5), (TURN, -1.0, -1.0), (DASH, -1.0, -1.0)]].concat();
+        assert_eq!(analyze(&tilt_turn), InputQualityCounts { dashback_attempts: 1, ..Default::default() });
+
+        // Pivot: one frame of dash back, then standing and nothing
+        let empty_pivot = [&dash_right[..], &[(DASH, -1.0, -1.0)], &[(WAIT, -1.0, 0.0); 5]].concat();
+        assert_eq!(
+            analyze(&empty_pivot),
+            InputQualityCounts {
+                dashback_attempts: 1,
+                dashback_successes: 1,
+                pivot_attempts: 1,
+                pivot_successes: 1,
+                empty_pivots: 1,
+                ..Default::default()
+            }
+        );
+
+        // Pivot forward tilt: not empty
+        let pivot_tilt = [&dash_right[..], &[(DASH, -1.0, -1.0), (WAIT, -1.0, 0.0), (FTILT, -1.0, -0.5)]].concat();
+        let counts = analyze(&pivot_tilt);
+        assert_eq!((counts.pivot_attempts, counts.pivot_successes, counts.empty_pivots), (1, 1, 0));
+
+        // Released a frame late: the new dash carries on
+        let late_pivot = [&dash_right[..], &[(DASH, -1.0, -1.0), (DASH, -1.0, -1.0), (DASH, -1.0, 0.0)]].concat();
+        let counts = analyze(&late_pivot);
+        assert_eq!((counts.pivot_attempts, counts.pivot_successes), (1, 0));
+
+        // Let go of a held stick: it overshoots and comes back, both sides
+        let snapbacks = [
+            (WAIT, 1.0, 1.0), (WAIT, 1.0, 1.0), (WAIT, 1.0, -0.4), (WAIT, 1.0, 0.1), (WAIT, 1.0, 0.0),
+            (WAIT, 1.0, 0.9), (WAIT, 1.0, 0.5), (WAIT, 1.0, -0.3), (WAIT, 1.0, 0.0), (WAIT, 1.0, 0.0),
+            (WAIT, -1.0, -1.0), (WAIT, -1.0, 0.45), (WAIT, -1.0, 0.0), (WAIT, -1.0, 0.0),
+        ];
+        assert_eq!(analyze(&snapbacks), InputQualityCounts { snapbacks: 3, ..Default::default() });
+
+        // Smashing the other way on purpose isn't a snapback
+        let turn = [(WAIT, 1.0, 1.0), (WAIT, 1.0, -0.5), (TURN, 1.0, -1.0), (TURN, -1.0, -1.0)];
+        assert_eq!(analyze(&turn), InputQualityCounts::default());
+    }
+}
@@ -0,0 +1,71 @@
+//! Lock file guarding the watcher and recorder against other app processes
+//!
+//! The single-instance plugin hands a second launch over to the running app,
+//! but it can't help with a zombie process that lost its window and still
+//! holds the Slippi watcher. Starting the watcher or a recording takes an OS
+//! lock on `instance.lock` in the app data directory first, so two processes
+//! never fight over the same .slp events. The OS releases the lock when the
+//! holder exits, however it exits.
+
+use crate::commands::errors::Error;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::sync::Mutex;
+use tauri::Manager;
+
+const LOCK_FILE: &str = "instance.lock";
+
+/// The instance lock, once this process holds it
+#[derive(Debug, Default)]
+pub struct InstanceLock {
+    file: Mutex<Option<File>>,
+}
+
+impl InstanceLock {
+    /// Make sure this process holds the lock, taking it if it's free
+    pub fn ensure_held(&self, app: &tauri::AppHandle) -> Result<(), Error> {
+        let mut held = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if held.is_some() {
+            return Ok(());
+        }
+
+        let dir = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| Error::InitializationError(format!("Failed to get app data directory: {}", e)))?;
+        std::fs::create_dir_all(&dir)?;
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(dir.join(LOCK_FILE))?;
+
+        match file.try_lock() {
+            Ok(()) => {
+                // Record who holds it, for the error other processes show
+                file.set_len(0)?;
+                file.rewind()?;
+                write!(file, "{}", std::process::id())?;
+                file.flush()?;
+                *held = Some(file);
+                Ok(())
+            }
+            Err(TryLockError::WouldBlock) => {
+                // Windows doesn't allow reading a locked file, so the pid may be unknown
+                let mut pid = String::new();
+                let _ = file.read_to_string(&mut pid);
+                let holder = match pid.trim() {
+                    "" => "another process".to_string(),
+                    pid => format!("process {}", pid),
+                };
+                Err(Error::InitializationError(format!(
+                    "Another copy of the app ({}) is already watching for games. Close it, or end it from your task manager, and try again",
+                    holder
+                )))
+            }
+            Err(TryLockError::Error(e)) => Err(e.into()),
+        }
+    }
+}
@@ -1,62 +1,148 @@
+mod api;
 mod app_state;
 mod clip_processor;
 mod commands;
-mod database;
+pub mod database;
+mod deep_link;
+mod diagnostics;
+mod discord_presence;
+mod event_batch;
 mod events;
 mod game_detector;
+mod game_session;
+mod health;
+mod highlights;
+pub mod inputs;
+mod instance_lock;
 mod library;
+mod metrics;
+mod overlay;
+mod paths;
+mod play_session;
+mod playback_review;
+mod pre_roll;
+mod readiness;
 mod recorder;
-mod slippi;
+mod recording_rules;
+mod recovery;
+mod retention;
+mod runtime_state;
+mod scheduler;
+mod settings;
+pub mod slippi;
+mod timestamps;
+mod viewport;
+mod warm_start;
+mod watch_filters;
+mod weekly_report;
 mod window_detector;
+mod work_gate;
 
 // Clips commands
 use commands::clips::{
-    apply_video_edit, compress_video_for_upload, create_clip_from_range, delete_temp_file,
-    mark_clip_timestamp, process_clip_markers,
+    apply_video_edit, compress_video_for_upload, create_clip_from_range, delete_clip, delete_temp_file,
+    edit_recording, export_audio, get_media_info, mark_clip_timestamp, process_clip_markers, rename_clip,
+    set_clip_favorite,
 };
 // Cloud commands
 use commands::cloud::get_device_id;
 // Default commands
 use commands::default::{read, write};
+// Diagnostics commands
+use commands::diagnostics::{
+    export_diagnostics, get_api_info, get_command_metrics, get_hwaccel_status, get_health, get_recent_logs,
+    list_scheduled_jobs, run_job_now, run_readiness_check,
+};
 // Library commands
 use commands::library::{
-    delete_recording, get_clips, get_player_stats, get_recordings, get_total_player_stats,
+    check_migrations, repair_game_results, delete_recording, get_clips, get_recording, get_player_stats, get_recordings, get_total_player_stats, compare_stats, get_current_streak, get_input_quality, get_known_players, merge_players,
+    get_character_usage, get_first_and_last_played,
+    get_current_session,
     get_available_filter_options, open_file_location, open_recording_folder, open_video, 
-    refresh_recordings_cache, save_computed_stats, list_slp_files, check_slp_synced,
+    refresh_recordings_cache, refresh_recording, save_computed_stats, wait_for_job_slot, list_slp_files, check_slp_synced, set_my_port, set_warmup,
+    get_cached_analysis, save_cached_analysis, invalidate_analysis_cache,
+    get_outdated_stats_count, get_outdated_stats_games, export_recording_bundle, export_frames, export_anonymized_replay, get_storage_report, import_recording_bundle, import_external_stats,
+    list_archive_candidates, archive_recordings, get_top_highlights, generate_highlights, generate_weekly_report, list_weekly_reports,
+    record_replay_parse_failure, list_unparseable_replays, retry_unparseable_replays, find_duplicate_recordings,
+    set_needs_reparse, flag_outdated_stats, resolve_duplicates, list_silent_recordings, get_recording_games, calibrate_offset,
+    take_pending_deep_link,
 };
+// Overlay commands
+use commands::overlay::{get_overlay_server_status, start_overlay_server, stop_overlay_server};
 // Recording commands
-use commands::recording::{start_generic_recording, start_recording, stop_recording};
+use commands::recording::{
+    run_recording_benchmark, start_generic_recording, start_recording, stop_recording,
+    take_recovery_report, test_capture, test_obs_connection,
+};
+// Recording rules commands
+use commands::recording_rules::{
+    delete_recording_rule, get_recording_rule_log, list_recording_rules, save_recording_rule,
+};
+// Retention commands
+use commands::retention::{
+    apply_retention, delete_retention_rule, list_retention_rules, save_retention_rule,
+};
 // Settings commands
 use commands::settings::{
-    get_recording_directory, get_setting, get_settings_path, open_settings_folder,
+    apply_discord_presence_settings, apply_profile, apply_stats_exclusion_settings, create_profile, delete_profile,
+    disable_autostart, enable_autostart, export_settings, get_autostart_status,
+    get_my_connect_codes, get_recording_directory, get_setting, get_settings_path,
+    import_settings, list_profiles, open_settings_folder, reset_settings_to_defaults,
+    set_my_connect_codes, set_my_local_identity, set_watch_filters, apply_work_gate_settings,
+    apply_hardware_decode_setting,
 };
 // Slippi commands
 use commands::slippi::{
-    get_default_slippi_path, get_last_replay_path, start_watching, stop_watching,
+    analyze_replay, get_default_slippi_path, get_last_replay_path, get_watcher_status, launch_replay, start_playback_review, start_watching,
+    stop_playback_review, stop_watching,
 };
 // Window commands
 use commands::window::{
-    capture_window_preview, check_game_window, get_game_process_name, list_game_windows,
-    set_game_process_name,
+    capture_window_preview, capture_window_preview_for, check_game_window, get_game_process_name,
+    list_game_windows, set_game_process_name,
 };
 
-use tauri::Manager;
+use tauri::{Listener, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
 
 #[allow(clippy::missing_panics_doc)]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        // Must be registered first: a second launch forwards its args here and exits
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            log::info!("🔁 Second instance launched with {:?}, focusing existing window", argv);
+            // A startup launch shouldn't pop the window over whatever the user is doing
+            if argv.iter().any(|arg| arg == commands::settings::MINIMIZED_ARG) {
+                return;
+            }
+            focus_main_window(app);
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .plugin(
+            tauri_plugin_autostart::Builder::new()
+                .arg(commands::settings::MINIMIZED_ARG)
+                .build(),
+        )
         .setup(|app| {
-            // Initialize logging first (so we can see database init logs)
-            if cfg!(debug_assertions) {
-                app.handle().plugin(
-                    tauri_plugin_log::Builder::default()
-                        .level(log::LevelFilter::Info)
-                        .build(),
-                )?;
+            // Initialize logging first (so we can see database init logs). Logs go to
+            // rotating files in every build so users can send them with bug reports
+            app.handle().plugin(diagnostics::log_plugin(app.handle()))?;
+            
+            // The main window starts hidden so a startup launch can stay out of the way
+            if let Some(window) = app.get_webview_window("main") {
+                let shown = if commands::settings::should_start_minimized(app.handle()) {
+                    log::info!("🌙 Launched on startup, starting minimized");
+                    window.minimize()
+                } else {
+                    window.show()
+                };
+                if let Err(e) = shown {
+                    log::warn!("Failed to show main window: {}", e);
+                }
             }
             
             // Initialize SQLite database
@@ -65,41 +151,147 @@ pub fn run() {
             
             let db = database::Database::open(&db_path)
                 .expect("Failed to open database");
-            db.init().expect("Failed to initialize database schema");
+            db.init().map_err(commands::errors::Error::from)?;
             
             log::info!("✅ Database initialized");
             
             // Initialize app state with database
             app.manage(app_state::AppState::with_database(db));
+            // Bookkeeping writes are batched off the hot path
+            database::spawn_write_flusher(std::sync::Arc::downgrade(&app.state::<app_state::AppState>().database));
+
+            // Log panics in background work and restart what can be restarted
+            health::install(app.handle(), app.state::<app_state::AppState>().health.clone());
+            // Time commands and background jobs for get_command_metrics
+            metrics::install(app.state::<app_state::AppState>().metrics.clone());
 
-            // Trigger background sync of recordings cache
+            // buckwheat:// links, both at launch and while running (a second instance
+            // forwards its link through the single-instance plugin)
+            #[cfg(any(windows, target_os = "linux"))]
+            if let Err(e) = app.deep_link().register_all() {
+                log::warn!("Failed to register {}:// links: {}", deep_link::SCHEME, e);
+            }
+            let app_handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                deep_link::handle_urls(&app_handle, event.urls());
+            });
+            if let Ok(Some(urls)) = app.deep_link().get_current() {
+                deep_link::handle_urls(app.handle(), urls);
+            }
+
+            // Take the instance lock early so a leftover process is reported up front
+            if let Err(e) = app.state::<app_state::AppState>().instance_lock.ensure_held(app.handle()) {
+                log::warn!("⚠️ {}", e);
+            }
+
+            // Feed live game events to the overlay session
+            let overlay = app.state::<app_state::AppState>().overlay.clone();
+            app.listen(events::game::FILE_CREATED, move |event| {
+                overlay.game_started(event_path(event.payload()));
+            });
+            // and to the play session
+            let app_handle = app.handle().clone();
+            app.listen(events::game::FILE_CREATED, move |event| {
+                play_session::game_started(&app_handle.state::<app_state::AppState>(), event_path(event.payload()));
+            });
+            let overlay = app.state::<app_state::AppState>().overlay.clone();
+            app.listen(events::game::FILE_MODIFIED, move |event| {
+                overlay.game_ended(event_path(event.payload()));
+            });
+
+            // Recordings are tagged with the last applied settings profile
+            let state = app.state::<app_state::AppState>();
+            if let Ok(mut active) = state.active_profile.lock() {
+                *active = commands::settings::active_profile(app.handle());
+            }
+
+            // Background jobs the user lets run while recording
+            state
+                .work_gate
+                .set_overrides(commands::settings::jobs_while_recording(app.handle()));
+
+            // Decode thumbnails on the GPU unless turned off (the ffmpeg job below
+            // finds the decoder rather than the first thumbnail)
+            clip_processor::set_hardware_decode(commands::settings::hardware_decode(app.handle()));
+
+            // Discord presence follows the same game events (the worker does the IPC);
+            // game_session tells it about new games once their Game Start is read
+            state
+                .discord_presence
+                .update_settings(commands::settings::discord_presence_settings(app.handle()));
+            let app_handle = app.handle().clone();
+            app.listen(events::game::FILE_MODIFIED, move |_| {
+                app_handle.state::<app_state::AppState>().discord_presence.game_ended();
+            });
+
+            // Start the overlay server if enabled in settings
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
-                // Small delay to let the app finish initializing
-                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                
-                if let Err(e) = library::sync_recordings_cache(&app_handle).await {
-                    log::error!("Failed to sync recordings cache: {:?}", e);
-                }
+                let state = app_handle.state::<app_state::AppState>();
+                commands::overlay::start_overlay_server_if_enabled(&app_handle, &state).await;
             });
+
+            // Auto-recording, fed by start_watching's listeners
+            game_session::spawn(app.handle());
+
+            // Watch again (and resume the session) if the last run was watching
+            runtime_state::restore(app.handle());
+
+            // Repair what the last session was recording if it crashed (before the sync indexes it)
+            recovery::spawn_recovery(app.handle());
+
+            // Background jobs, each run when due and its conditions hold
+            let scheduler = &app.state::<app_state::AppState>().scheduler;
+            // Background sync of recordings cache
+            scheduler.register(library::sync_job());
+            // FFmpeg download and GPU decoder detection
+            scheduler.register(clip_processor::ffmpeg_job());
+            // Weekly run of the retention rules
+            scheduler.register(retention::scheduled_job());
+            // Daily highlight clips, if enabled
+            scheduler.register(highlights::scheduled_job());
+            // Last week's report, if enabled and not generated yet
+            scheduler.register(weekly_report::scheduled_job());
+            // Clips folder cap, if set
+            scheduler.register(library::clips_cap_job());
+            scheduler::spawn(app.handle());
             
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             read,
             write,
+            // Diagnostics commands
+            get_recent_logs,
+            export_diagnostics,
+            get_health,
+            get_command_metrics,
+            get_api_info,
+            get_hwaccel_status,
+            run_readiness_check,
+            list_scheduled_jobs,
+            run_job_now,
             get_default_slippi_path,
             start_watching,
             stop_watching,
+            launch_replay,
+            start_playback_review,
+            stop_playback_review,
+            analyze_replay,
             start_recording,
             start_generic_recording,
             stop_recording,
+            take_recovery_report,
+            run_recording_benchmark,
+            test_capture,
+            test_obs_connection,
             get_recordings,
             delete_recording,
             open_video,
             open_recording_folder,
             check_game_window,
             capture_window_preview,
+            capture_window_preview_for,
             list_game_windows,
             get_game_process_name,
             set_game_process_name,
@@ -107,28 +299,129 @@ pub fn run() {
             open_settings_folder,
             get_setting,
             get_recording_directory,
+            get_my_connect_codes,
+            set_my_connect_codes,
+            set_my_local_identity,
+            set_watch_filters,
+            apply_discord_presence_settings,
+            apply_work_gate_settings,
+            apply_hardware_decode_setting,
+            apply_stats_exclusion_settings,
+            enable_autostart,
+            disable_autostart,
+            get_autostart_status,
+            export_settings,
+            import_settings,
+            reset_settings_to_defaults,
+            list_profiles,
+            create_profile,
+            apply_profile,
+            delete_profile,
+            list_recording_rules,
+            save_recording_rule,
+            delete_recording_rule,
+            get_recording_rule_log,
+            list_retention_rules,
+            save_retention_rule,
+            delete_retention_rule,
+            apply_retention,
             open_file_location,
             get_last_replay_path,
+            get_watcher_status,
             refresh_recordings_cache,
+            refresh_recording,
+            get_cached_analysis,
+            save_cached_analysis,
+            invalidate_analysis_cache,
+            get_outdated_stats_count,
+            get_outdated_stats_games,
+            export_recording_bundle,
+            export_frames,
+            export_anonymized_replay,
+            get_storage_report,
+            import_recording_bundle,
+            import_external_stats,
+            list_archive_candidates,
+            archive_recordings,
+            get_top_highlights,
+            generate_highlights,
+            generate_weekly_report,
+            list_weekly_reports,
+            record_replay_parse_failure,
+            list_unparseable_replays,
+            retry_unparseable_replays,
+            set_needs_reparse,
+            flag_outdated_stats,
+            find_duplicate_recordings,
+            resolve_duplicates,
+            list_silent_recordings,
+            take_pending_deep_link,
+            get_recording,
+            get_recording_games,
+            calibrate_offset,
+            check_migrations,
+            repair_game_results,
+            // Overlay commands
+            start_overlay_server,
+            stop_overlay_server,
+            get_overlay_server_status,
             // Clip commands
             mark_clip_timestamp,
             process_clip_markers,
             get_clips,
             apply_video_edit,
+            edit_recording,
             create_clip_from_range,
+            export_audio,
+            delete_clip,
+            rename_clip,
+            set_clip_favorite,
             // Cloud commands
             compress_video_for_upload,
+            get_media_info,
             delete_temp_file,
             get_device_id,
             // Stats commands
             save_computed_stats,
+            wait_for_job_slot,
             get_player_stats,
             get_total_player_stats,
+            compare_stats,
+            get_current_streak,
+            get_input_quality,
+            get_known_players,
+            merge_players,
+            get_character_usage,
+            get_first_and_last_played,
+            get_current_session,
             get_available_filter_options,
+            set_my_port,
+            set_warmup,
             // Historical sync commands
             list_slp_files,
             check_slp_synced,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                commands::overlay::stop_server(&app.state::<app_state::AppState>());
+                playback_review::stop(&app.state::<app_state::AppState>());
+                app.state::<app_state::AppState>().database.flush_writes();
+            }
+        });
+}
+
+/// Bring the main window to the front
+pub(crate) fn focus_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Game event payloads are JSON strings; fall back to the raw payload
+fn event_path(payload: &str) -> String {
+    serde_json::from_str(payload).unwrap_or_else(|_| payload.to_string())
 }
@@ -0,0 +1,224 @@
+//! On-disk cache of derived game analysis
+//!
+//! The frontend derives a recording's analysis (timeline events, conversions,
+//! per-player stats) from the .slp with slippi-js. The result is stored as JSON
+//! in AnalysisCache/<recording id>.json along with a hash of the .slp contents
+//! and the analysis version that produced it, so re-opening a recording doesn't
+//! re-parse the replay. A different hash or version is a miss and the stale
+//! entry is removed, so improved detectors take effect on the next open.
+
+use crate::commands::errors::Error;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+/// Bump when the layout of CacheEntry changes
+const CACHE_FORMAT_VERSION: u32 = 1;
+
+/// A cached analysis file
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry {
+    format_version: u32,
+    /// Version of the frontend analysis code that produced this entry
+    analysis_version: u32,
+    /// Hash of the .slp contents the analysis was derived from
+    slp_hash: String,
+    created_at: String,
+    analysis: serde_json::Value,
+}
+
+/// Directory holding the analysis cache (in the app data directory)
+pub fn get_cache_directory(app: &tauri::AppHandle) -> PathBuf {
+    app.path()
+        .app_data_dir()
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .join("AnalysisCache")
+}
+
+/// Read the cached analysis for a recording if it matches the current .slp and analysis version
+/// Stale entries are deleted
+pub fn read_cached_analysis(
+    cache_dir: &Path,
+    recording_id: &str,
+    slp_path: &Path,
+    analysis_version: u32,
+) -> Result<Option<serde_json::Value>, Error> {
+    let entry_path = entry_path(cache_dir, recording_id);
+    if !entry_path.exists() {
+        return Ok(None);
+    }
+
+    let entry: Option<CacheEntry> = std::fs::read(&entry_path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+
+    let slp_hash = hash_file(slp_path)?;
+    match entry {
+        Some(entry)
+            if entry.format_version == CACHE_FORMAT_VERSION
+                && entry.analysis_version == analysis_version
+                && entry.slp_hash == slp_hash =>
+        {
+            log::debug!("📦 Analysis cache hit for {}", recording_id);
+            Ok(Some(entry.analysis))
+        }
+        _ => {
+            log::debug!("🗑️ Discarding stale analysis cache for {}", recording_id);
+            let _ = std::fs::remove_file(&entry_path);
+            Ok(None)
+        }
+    }
+}
+
+/// Store the analysis for a recording
+pub fn write_cached_analysis(
+    cache_dir: &Path,
+    recording_id: &str,
+    slp_path: &Path,
+    analysis_version: u32,
+    analysis: serde_json::Value,
+) -> Result<(), Error> {
+    std::fs::create_dir_all(cache_dir)?;
+
+    let entry = CacheEntry {
+        format_version: CACHE_FORMAT_VERSION,
+        analysis_version,
+        slp_hash: hash_file(slp_path)?,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        analysis,
+    };
+    let bytes = serde_json::to_vec(&entry).map_err(std::io::Error::from)?;
+
+    // Write to a temp file first so a crash never leaves a half-written entry
+    let entry_path = entry_path(cache_dir, recording_id);
+    let tmp_path = entry_path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, bytes)?;
+    std::fs::rename(&tmp_path, &entry_path)?;
+
+    Ok(())
+}
+
+/// Delete the cached analysis for one recording, or all of them
+/// Returns the number of entries removed
+pub fn invalidate_analysis(cache_dir: &Path, recording_id: Option<&str>) -> Result<usize, Error> {
+    if let Some(recording_id) = recording_id {
+        let entry_path = entry_path(cache_dir, recording_id);
+        if entry_path.exists() {
+            std::fs::remove_file(&entry_path)?;
+            return Ok(1);
+        }
+        return Ok(0);
+    }
+
+    let Ok(entries) = std::fs::read_dir(cache_dir) else {
+        return Ok(0);
+    };
+
+    let mut removed = 0;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.extension().and_then(|s| s.to_str()) == Some("json") && std::fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Move a recording's cached analysis to its new id (the entry is still
+/// checked against the .slp when it's read)
+pub fn rekey_analysis(cache_dir: &Path, old_id: &str, new_id: &str) {
+    let old_path = entry_path(cache_dir, old_id);
+    if old_path.exists() {
+        if let Err(e) = std::fs::rename(&old_path, entry_path(cache_dir, new_id)) {
+            log::warn!("Failed to move analysis cache for {}: {}", old_id, e);
+        }
+    }
+}
+
+// ============================================================================
+// HELPER FUNCTIONS
+// ============================================================================
+
+/// Cache file for a recording (ids are sanitized so they can't escape the cache directory)
+fn entry_path(cache_dir: &Path, recording_id: &str) -> PathBuf {
+    let safe_id: String = recording_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    cache_dir.join(format!("{}.json", safe_id))
+}
+
+/// FNV-1a hash of a file's contents (stable across builds, unlike DefaultHasher)
+fn hash_file(path: &Path) -> Result<String, Error> {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut file = std::fs::File::open(path)?;
+
+    let mut hash = FNV_OFFSET;
+    let mut len: u64 = 0;
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        for byte in &buffer[..read] {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+        len += read as u64;
+    }
+
+    Ok(format!("{:016x}-{}", hash, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_cached_analysis_round_trips_until_the_replay_or_version_changes() {
+        let dir = std::env::temp_dir().join(format!("buckwheat-analysis-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache_dir = dir.join("AnalysisCache");
+        std::fs::create_dir_all(&dir).unwrap();
+        let slp = dir.join("Game_1.slp");
+        std::fs::write(&slp, b"replay").unwrap();
+        let analysis = json!({ "conversions": [{ "startFrame": 10, "damage": 42.5 }] });
+
+        assert_eq!(read_cached_analysis(&cache_dir, "rec/1", &slp, 3).unwrap(), None);
+        write_cached_analysis(&cache_dir, "rec/1", &slp, 3, analysis.clone()).unwrap();
+        assert_eq!(read_cached_analysis(&cache_dir, "rec/1", &slp, 3).unwrap(), Some(analysis.clone()));
+        // The id can't leave the cache directory
+        assert!(entry_path(&cache_dir, "rec/1").starts_with(&cache_dir));
+
+        // Another analysis version is a miss, and the entry is dropped
+        assert_eq!(read_cached_analysis(&cache_dir, "rec/1", &slp, 4).unwrap(), None);
+        assert!(!entry_path(&cache_dir, "rec/1").exists());
+
+        // So is a changed replay
+        write_cached_analysis(&cache_dir, "rec/1", &slp, 3, analysis.clone()).unwrap();
+        std::fs::write(&slp, b"replay, re-recorded").unwrap();
+        assert_eq!(read_cached_analysis(&cache_dir, "rec/1", &slp, 3).unwrap(), None);
+
+        // A missing replay is an I/O error
+        let missing = write_cached_analysis(&cache_dir, "rec/2", &dir.join("gone.slp"), 3, json!({}));
+        assert!(matches!(missing, Err(Error::Io(_))));
+
+        write_cached_analysis(&cache_dir, "rec/1", &slp, 3, analysis.clone()).unwrap();
+        write_cached_analysis(&cache_dir, "rec/2", &slp, 3, analysis.clone()).unwrap();
+        write_cached_analysis(&cache_dir, "rec/3", &slp, 3, analysis).unwrap();
+        assert_eq!(invalidate_analysis(&cache_dir, Some("rec/2")).unwrap(), 1);
+        assert_eq!(invalidate_analysis(&cache_dir, Some("rec/2")).unwrap(), 0);
+        assert_eq!(read_cached_analysis(&cache_dir, "rec/2", &slp, 3).unwrap(), None);
+        assert_eq!(invalidate_analysis(&cache_dir, None).unwrap(), 2);
+        assert_eq!(invalidate_analysis(&cache_dir, None).unwrap(), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
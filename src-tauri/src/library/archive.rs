@@ -0,0 +1,167 @@
+//! Archiving old recordings
+//!
+//! Archiving re-encodes a recording to 720p / 2 Mbps HEVC and replaces the
+//! original, keeping a watchable copy at a fraction of the space. The new file
+//! is written next to the original and checked (its length, and a decode of
+//! its last seconds) before it's renamed over the original, so a failed or
+//! interrupted encode leaves the original as it was. Archived recordings are
+//! flagged is_archived for the library's quality badge.
+//!
+//! Clips are separate files, so clips cut from an archived recording keep
+//! their full quality. Clips themselves are never archived.
+
+use crate::app_state::AppState;
+use crate::clip_processor::{self, VideoCodec};
+use crate::commands::errors::Error;
+use crate::database::{self, RecordingRow};
+use crate::game_detector::suspension;
+use crate::recorder::RecordingQuality;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use tauri::Manager;
+
+const ARCHIVE_VIDEO_BITRATE: u32 = 2_000_000;
+const ARCHIVE_AUDIO_BITRATE: u32 = 96_000;
+/// Preset whose resolution (720p) archived videos are scaled to
+const ARCHIVE_RESOLUTION: RecordingQuality = RecordingQuality::Medium;
+
+/// A recording old enough to archive
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveCandidate {
+    pub recording_id: String,
+    pub video_path: String,
+    pub file_size: Option<u64>,
+    pub start_time: Option<String>,
+}
+
+/// Result of archiving one recording
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedFile {
+    pub recording_id: String,
+    pub video_path: String,
+    pub original_size: u64,
+    pub archived_size: u64,
+    pub freed_bytes: u64,
+    /// Why it wasn't archived (the original is untouched then)
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveReport {
+    pub files: Vec<ArchivedFile>,
+    pub freed_bytes: u64,
+}
+
+/// Recordings at least `older_than_days` old that haven't been archived yet
+pub fn archive_candidates(app: &tauri::AppHandle, older_than_days: u32) -> Result<Vec<ArchiveCandidate>, Error> {
+    let state = app.state::<AppState>();
+    let rows = database::get_all_recordings(&state.database.connection())
+        .map_err(Error::from)?;
+
+    let now = chrono::Utc::now();
+    Ok(rows
+        .into_iter()
+        // Same rule as get_clips
+        .filter(|row| !row.is_archived)
+        .filter_map(|row| Some((row.video_path.clone()?, row)))
+        .filter(|(video_path, _)| !video_path.contains("Clips") && Path::new(video_path).exists())
+        .filter(|(_, row)| {
+            row.start_time
+                .as_deref()
+                .or(row.file_modified_at.as_deref())
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                .is_some_and(|t| (now - t.with_timezone(&chrono::Utc)).num_days() >= older_than_days as i64)
+        })
+        .map(|(video_path, row)| ArchiveCandidate {
+            recording_id: row.id,
+            video_path,
+            file_size: row.file_size.map(|s| s as u64),
+            start_time: row.start_time,
+        })
+        .collect())
+}
+
+/// Archive recordings one after another. A failure is reported per file and
+/// doesn't stop the rest
+pub fn archive_recordings(app: &tauri::AppHandle, recording_ids: &[String]) -> Result<ArchiveReport, Error> {
+    let state = app.state::<AppState>();
+    if state.rewriting_recordings.swap(true, Ordering::SeqCst) {
+        return Err(Error::RecordingFailed("Recordings are already being cleaned up or archived".to_string()));
+    }
+
+    let paused = suspension::suspend(app, "archiving recordings");
+    let files: Vec<ArchivedFile> = recording_ids.iter().map(|id| archive_one(&state, id)).collect();
+    drop(paused);
+    state.rewriting_recordings.store(false, Ordering::SeqCst);
+
+    let freed_bytes = files.iter().map(|f| f.freed_bytes).sum();
+    log::info!(
+        "🗄️ Archived {}/{} recording(s), freed {} MB",
+        files.iter().filter(|f| f.error.is_none()).count(),
+        files.len(),
+        freed_bytes / (1024 * 1024)
+    );
+    Ok(ArchiveReport { files, freed_bytes })
+}
+
+fn archive_one(state: &AppState, recording_id: &str) -> ArchivedFile {
+    let row = database::get_recording_by_id(&state.database.connection(), recording_id).ok().flatten();
+    let original_size = row
+        .as_ref()
+        .and_then(|row| row.video_path.as_deref())
+        .and_then(|video_path| std::fs::metadata(video_path).ok())
+        .map(|m| m.len())
+        .unwrap_or(0);
+
+    let result = match &row {
+        Some(row) => transcode(state, row, original_size),
+        None => Err(Error::InvalidPath(format!("Recording not found: {}", recording_id))),
+    };
+    if let Err(e) = &result {
+        log::warn!("Failed to archive {}: {}", recording_id, e);
+    }
+
+    let archived_size = *result.as_ref().unwrap_or(&original_size);
+    ArchivedFile {
+        recording_id: recording_id.to_string(),
+        video_path: row.and_then(|row| row.video_path).unwrap_or_default(),
+        original_size,
+        archived_size,
+        freed_bytes: original_size.saturating_sub(archived_size),
+        error: result.err().map(|e| e.to_string()),
+    }
+}
+
+/// Re-encode, verify and swap in the archived video. Returns its size
+fn transcode(state: &AppState, row: &RecordingRow, original_size: u64) -> Result<u64, Error> {
+    if row.is_archived {
+        return Err(Error::RecordingFailed("Already archived".to_string()));
+    }
+    let Some(video_path) = row.video_path.as_deref() else {
+        return Err(Error::RecordingFailed("Replay-only entries have no video".to_string()));
+    };
+    if video_path.contains("Clips") {
+        return Err(Error::RecordingFailed("Clips aren't archived".to_string()));
+    }
+
+    let archived = clip_processor::shrink_in_place(
+        Path::new(video_path),
+        "archiving",
+        VideoCodec::Hevc,
+        ARCHIVE_RESOLUTION,
+        ARCHIVE_VIDEO_BITRATE,
+        ARCHIVE_AUDIO_BITRATE,
+    )?;
+
+    let conn = state.database.connection();
+    database::set_recording_file_info(&conn, &row.id, archived.size as i64, archived.modified_at.as_deref())
+        .and_then(|()| database::set_recording_archived(&conn, &row.id))
+        .map_err(|e| Error::database("Archived, but failed to update the library", e))?;
+
+    log::info!("🗄️ Archived {} ({} → {} bytes)", video_path, original_size, archived.size);
+    Ok(archived.size)
+}
@@ -0,0 +1,466 @@
+//! Recording bundles
+//!
+//! A bundle is a zip with everything needed to move one game to another
+//! machine (e.g. sending it to a coach):
+//! - the recording's MP4 and its .slp
+//! - metadata.json with the recording, game_stats and player_stats rows and
+//!   the list of clips cut from the game
+//! - optionally those clips, under clips/
+//!
+//! Files are streamed in and out of the archive so memory stays flat for
+//! multi-GB recordings. Importing unpacks into the library (the .slp goes next
+//! to the video, where sync picks it up) and registers everything under new ids.
+
+use crate::app_state::AppState;
+use crate::commands::errors::Error;
+use crate::commands::settings::{my_identity, stats_exclusion};
+use crate::database::{self, GameStatsRow, PlayerStatsRow, RecordingRow};
+use crate::game_detector::suspension;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Bump when the bundle layout or metadata.json changes incompatibly
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+const METADATA_FILE: &str = "metadata.json";
+const CLIPS_PREFIX: &str = "clips/";
+
+/// Contents of metadata.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleMetadata {
+    pub format_version: u32,
+    pub exported_at: String,
+    /// Archive name of the video
+    pub video_file: String,
+    /// Archive name of the replay, if the recording had one
+    pub slp_file: Option<String>,
+    pub recording: RecordingRow,
+    pub game_stats: Option<GameStatsRow>,
+    pub player_stats: Vec<PlayerStatsRow>,
+    /// File names of the clips cut from this game
+    pub clips: Vec<String>,
+    /// Whether the clips themselves are in the archive
+    pub clips_included: bool,
+}
+
+/// Files written to the library by an import
+#[derive(Debug)]
+struct UnpackedBundle {
+    metadata: BundleMetadata,
+    video_path: PathBuf,
+    clip_paths: Vec<PathBuf>,
+}
+
+/// Export a recording, its replay and stats (and optionally its clips) as a zip
+pub async fn export_recording_bundle(
+    app: &tauri::AppHandle,
+    recording_id: &str,
+    dest_path: &Path,
+    include_clips: bool,
+) -> Result<(), Error> {
+    let (recording, game_stats, player_stats) = {
+        let state = app.state::<AppState>();
+        let conn = state.database.connection();
+        let recording = database::get_recording_by_id(&conn, recording_id)
+            .map_err(Error::from)?
+            .ok_or_else(|| Error::InvalidPath(format!("Recording not found: {}", recording_id)))?;
+        let game_stats = database::get_game_stats(&conn, recording_id)
+            .map_err(Error::from)?;
+        let player_stats = database::get_player_stats_by_recording(&conn, recording_id)
+            .map_err(Error::from)?;
+        (recording, game_stats, player_stats)
+    };
+
+    let video_path = recording
+        .video_path
+        .as_deref()
+        .map(PathBuf::from)
+        .ok_or_else(|| Error::InvalidPath(format!("Recording has no video: {}", recording_id)))?;
+    if !video_path.exists() {
+        return Err(Error::InvalidPath(format!("Recording file not found: {}", video_path.display())));
+    }
+    let slp_path = recording.slp_path.as_deref().map(PathBuf::from).filter(|p| p.exists());
+    let clip_paths = find_game_clips(&get_clips_directory(app).await?, &video_path);
+
+    let metadata = BundleMetadata {
+        format_version: BUNDLE_FORMAT_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        video_file: file_name(&video_path),
+        slp_file: slp_path.as_deref().map(file_name),
+        recording,
+        game_stats,
+        player_stats,
+        clips: clip_paths.iter().map(|p| file_name(p)).collect(),
+        clips_included: include_clips,
+    };
+    let clip_paths = if include_clips { clip_paths } else { Vec::new() };
+
+    log::info!(
+        "📦 Exporting {} to {} ({} clips)",
+        metadata.video_file,
+        dest_path.display(),
+        clip_paths.len()
+    );
+
+    let dest_path = dest_path.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || {
+        write_bundle(&dest_path, &metadata, &video_path, slp_path.as_deref(), &clip_paths)
+    })
+    .await
+    .map_err(|e| Error::RecordingFailed(format!("Bundle export task failed: {}", e)))?
+}
+
+/// Unpack a bundle into the library and register the recording, its stats and clips
+/// Returns the new recording row
+pub async fn import_recording_bundle(app: &tauri::AppHandle, bundle_path: &Path) -> Result<RecordingRow, Error> {
+    let recording_dir = PathBuf::from(super::get_recording_directory(app).await?);
+    let clips_dir = get_clips_directory(app).await?;
+
+    log::info!("📦 Importing bundle {}", bundle_path.display());
+    // Its replay is unpacked next to the video, which may be in the Slippi folder
+    let _paused = suspension::suspend(app, "importing a bundle");
+
+    let bundle_path = bundle_path.to_path_buf();
+    let unpacked = tauri::async_runtime::spawn_blocking(move || {
+        unpack_bundle(&bundle_path, &recording_dir, &clips_dir)
+    })
+    .await
+    .map_err(|e| Error::RecordingFailed(format!("Bundle import task failed: {}", e)))??;
+
+    // Indexing links the .slp unpacked next to the video
+    let recording = super::index_recording(app, &unpacked.video_path).await?;
+
+    {
+        let state = app.state::<AppState>();
+        let conn = state.database.connection();
+
+        if let Some(stats) = unpacked.metadata.game_stats {
+            database::upsert_game_stats(&conn, &GameStatsRow {
+                id: recording.id.clone(),
+                slp_path: recording.slp_path.clone(),
+                ..stats
+            })
+            .map_err(|e| Error::database("Failed to save game stats", e))?;
+        }
+
+        for player in unpacked.metadata.player_stats {
+            database::upsert_player_stats(&conn, &PlayerStatsRow {
+                id: None,
+                recording_id: recording.id.clone(),
+                slp_path: recording.slp_path.clone(),
+                is_me: false,
+                ..player
+            })
+            .map_err(|e| Error::database("Failed to save player stats", e))?;
+        }
+
+        // The exporter's "me" isn't necessarily this user, nor their exclusion settings
+        database::recompute_is_me_for_recording(&conn, &recording.id, &my_identity(app))
+            .map_err(|e| Error::database("Failed to attribute player", e))?;
+        database::update_excluded_reasons(&conn, &stats_exclusion(app), Some(&recording.id))
+            .map_err(|e| Error::database("Failed to flag excluded game", e))?;
+    }
+
+    for clip in &unpacked.clip_paths {
+        if let Err(e) = super::index_recording(app, clip).await {
+            log::warn!("Failed to index imported clip {}: {}", clip.display(), e);
+        }
+    }
+
+    log::info!(
+        "📦 Imported {} as {} ({} clips)",
+        unpacked.metadata.video_file,
+        recording.id,
+        unpacked.clip_paths.len()
+    );
+    Ok(recording)
+}
+
+/// Clips directory (sibling of the recording directory)
+async fn get_clips_directory(app: &tauri::AppHandle) -> Result<PathBuf, Error> {
+    let recording_dir = PathBuf::from(super::get_recording_directory(app).await?);
+    Ok(recording_dir
+        .parent()
+        .map(|p| p.join("Clips"))
+        .unwrap_or_else(|| recording_dir.join("Clips")))
+}
+
+/// Clips cut from a recording. Clip names carry the source's timestamp
+/// (Clip01_<ts>.mp4, Clip_<ts>_001.mp4, Clip_Game_<ts>_<now>.mp4)
+fn find_game_clips(clips_dir: &Path, video_path: &Path) -> Vec<PathBuf> {
+    let stem = video_path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let timestamp = stem.strip_prefix("Game_").unwrap_or(stem);
+    if timestamp.is_empty() {
+        return Vec::new();
+    }
+
+    let mut clips: Vec<PathBuf> = std::fs::read_dir(clips_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            name.starts_with("Clip") && super::is_video_file(path) && name.contains(timestamp)
+        })
+        .collect();
+    clips.sort();
+    clips
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Write the bundle to `<dest>.part` and move it into place once complete
+fn write_bundle(
+    dest_path: &Path,
+    metadata: &BundleMetadata,
+    video_path: &Path,
+    slp_path: Option<&Path>,
+    clip_paths: &[PathBuf],
+) -> Result<(), Error> {
+    let partial_path = dest_path.with_extension("zip.part");
+
+    let result = (|| -> Result<(), Error> {
+        let mut zip = ZipWriter::new(BufWriter::new(File::create(&partial_path)?));
+
+        zip.start_file(METADATA_FILE, SimpleFileOptions::default())
+            .map_err(zip_error)?;
+        serde_json::to_writer_pretty(&mut zip, metadata).map_err(std::io::Error::from)?;
+
+        // MP4 is already compressed; replays deflate well
+        add_file(&mut zip, &metadata.video_file, video_path, CompressionMethod::Stored)?;
+        if let (Some(name), Some(path)) = (&metadata.slp_file, slp_path) {
+            add_file(&mut zip, name, path, CompressionMethod::Deflated)?;
+        }
+        for clip in clip_paths {
+            let name = format!("{}{}", CLIPS_PREFIX, file_name(clip));
+            add_file(&mut zip, &name, clip, CompressionMethod::Stored)?;
+        }
+
+        zip.finish().map_err(zip_error)?.flush()?;
+        std::fs::rename(&partial_path, dest_path)?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial_path);
+    }
+    result
+}
+
+/// Stream a file into the archive
+fn add_file<W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
+    name: &str,
+    path: &Path,
+    compression: CompressionMethod,
+) -> Result<(), Error> {
+    let mut file = File::open(path)?;
+    let options = SimpleFileOptions::default()
+        .compression_method(compression)
+        .large_file(file.metadata()?.len() >= u32::MAX as u64);
+
+    zip.start_file(name, options).map_err(zip_error)?;
+    std::io::copy(&mut file, zip)?;
+    Ok(())
+}
+
+/// Extract a bundle into the recording and clips directories
+/// Existing files are never overwritten, and nothing this call wrote is left
+/// behind if extraction fails part way
+fn unpack_bundle(bundle_path: &Path, recording_dir: &Path, clips_dir: &Path) -> Result<UnpackedBundle, Error> {
+    let mut archive = ZipArchive::new(File::open(bundle_path)?)
+        .map_err(|e| Error::InvalidPath(format!("Not a recording bundle: {}", e)))?;
+
+    let metadata: BundleMetadata = {
+        let entry = archive
+            .by_name(METADATA_FILE)
+            .map_err(|_| Error::InvalidPath(format!("Not a recording bundle: {} is missing", METADATA_FILE)))?;
+        serde_json::from_reader(entry)
+            .map_err(|e| Error::InvalidPath(format!("Invalid bundle metadata: {}", e)))?
+    };
+    if metadata.format_version > BUNDLE_FORMAT_VERSION {
+        return Err(Error::InvalidPath(
+            "This bundle was made by a newer version of Buckwheat. Update to import it".to_string(),
+        ));
+    }
+
+    let video_name = plain_file_name(&metadata.video_file)?;
+    let video_path = recording_dir.join(video_name);
+    if video_path.exists() {
+        return Err(Error::InvalidPath(format!("{} is already in your library", video_name)));
+    }
+    // Indexing links the replay next to the video, so it can't go under another name
+    let slp_path = metadata.slp_file.as_ref().map(|_| video_path.with_extension("slp"));
+    if let Some(slp_path) = slp_path.as_ref().filter(|path| path.exists()) {
+        return Err(Error::InvalidPath(format!("{} is already in your library", slp_path.display())));
+    }
+
+    let mut created = Vec::new();
+    let result = (|| -> Result<Vec<PathBuf>, Error> {
+        extract(&mut archive, video_name, &video_path, &mut created)?;
+
+        if let (Some(slp_file), Some(slp_path)) = (&metadata.slp_file, &slp_path) {
+            extract(&mut archive, plain_file_name(slp_file)?, slp_path, &mut created)?;
+        }
+
+        let mut clip_paths = Vec::new();
+        if metadata.clips_included {
+            std::fs::create_dir_all(clips_dir)?;
+            for clip in &metadata.clips {
+                let clip_name = plain_file_name(clip)?;
+                let clip_path = clips_dir.join(clip_name);
+                if clip_path.exists() {
+                    log::info!("Skipping clip {}, it's already in the library", clip_name);
+                    continue;
+                }
+                extract(&mut archive, &format!("{}{}", CLIPS_PREFIX, clip_name), &clip_path, &mut created)?;
+                clip_paths.push(clip_path);
+            }
+        }
+        Ok(clip_paths)
+    })();
+
+    match result {
+        Ok(clip_paths) => Ok(UnpackedBundle { metadata, video_path, clip_paths }),
+        Err(e) => {
+            for path in created {
+                let _ = std::fs::remove_file(path);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Stream one archive entry to a new file, adding it to `created`. Fails
+/// rather than overwrite a file that's already there
+fn extract<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+    dest_path: &Path,
+    created: &mut Vec<PathBuf>,
+) -> Result<(), Error> {
+    let mut entry = archive
+        .by_name(name)
+        .map_err(|_| Error::InvalidPath(format!("Bundle is missing {}", name)))?;
+
+    let mut file = BufWriter::new(File::create_new(dest_path)?);
+    created.push(dest_path.to_path_buf());
+    std::io::copy(&mut entry, &mut file)?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Names from metadata.json are used as file names, so reject anything with a path in it
+fn plain_file_name(name: &str) -> Result<&str, Error> {
+    match Path::new(name).file_name().and_then(|n| n.to_str()) {
+        Some(file_name) if file_name == name => Ok(file_name),
+        _ => Err(Error::InvalidPath(format!("Unexpected file name in bundle: {}", name))),
+    }
+}
+
+fn zip_error(e: zip::result::ZipError) -> Error {
+    match e {
+        zip::result::ZipError::Io(e) => Error::Io(e),
+        e => Error::RecordingFailed(format!("Failed to write bundle: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A bundle of a game with its replay and one clip, and empty library and
+    /// clips directories to unpack it into
+    fn write_test_bundle(root: &Path) -> (PathBuf, PathBuf, PathBuf) {
+        let (source, library, clips) = (root.join("source"), root.join("library"), root.join("clips"));
+        for dir in [&source, &library, &clips] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+
+        let video = source.join("Game_20251110T194918.mp4");
+        let slp = source.join("Game_20251110T194918.slp");
+        let clip = source.join("Clip01_20251110T194918.mp4");
+        std::fs::write(&video, b"video").unwrap();
+        std::fs::write(&slp, b"replay").unwrap();
+        std::fs::write(&clip, b"clip").unwrap();
+        assert_eq!(find_game_clips(&source, &video), vec![clip.clone()]);
+
+        let metadata = BundleMetadata {
+            format_version: BUNDLE_FORMAT_VERSION,
+            exported_at: String::new(),
+            video_file: file_name(&video),
+            slp_file: Some(file_name(&slp)),
+            recording: RecordingRow {
+                id: "recording".to_string(),
+                video_path: Some(video.to_string_lossy().to_string()),
+                slp_path: Some(slp.to_string_lossy().to_string()),
+                file_size: None,
+                file_modified_at: None,
+                thumbnail_path: None,
+                start_time: None,
+                cached_at: String::new(),
+                needs_reparse: false,
+                dropped_frame_estimate: None,
+                longest_stall_ms: None,
+                profile: None,
+                is_archived: false,
+                parse_error: None,
+                replay_id: None,
+                video_width: None,
+                video_height: None,
+                video_duration_secs: None,
+                has_audio: None,
+                audio_mean_volume_db: None,
+                video_offset_ms: None,
+                source_recording_id: None,
+            },
+            game_stats: None,
+            player_stats: Vec::new(),
+            clips: vec![file_name(&clip)],
+            clips_included: true,
+        };
+        let bundle = root.join("bundle.zip");
+        write_bundle(&bundle, &metadata, &video, Some(&slp), &[clip]).unwrap();
+        (bundle, library, clips)
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let root = std::env::temp_dir().join(format!("bundle-test-{}", uuid::Uuid::new_v4()));
+        let (bundle, library, clips) = write_test_bundle(&root);
+
+        let unpacked = unpack_bundle(&bundle, &library, &clips).unwrap();
+        assert_eq!(std::fs::read(&unpacked.video_path).unwrap(), b"video");
+        assert_eq!(std::fs::read(unpacked.video_path.with_extension("slp")).unwrap(), b"replay");
+        assert_eq!(std::fs::read(&unpacked.clip_paths[0]).unwrap(), b"clip");
+
+        // A second import of the same game is refused
+        assert!(unpack_bundle(&bundle, &library, &clips).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_unpack_keeps_existing_replay() {
+        let root = std::env::temp_dir().join(format!("bundle-test-{}", uuid::Uuid::new_v4()));
+        let (bundle, library, clips) = write_test_bundle(&root);
+        let existing = library.join("Game_20251110T194918.slp");
+        std::fs::write(&existing, b"mine").unwrap();
+
+        assert!(unpack_bundle(&bundle, &library, &clips).is_err());
+        assert_eq!(std::fs::read(&existing).unwrap(), b"mine");
+        assert!(!library.join("Game_20251110T194918.mp4").exists());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
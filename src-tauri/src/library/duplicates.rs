@@ -0,0 +1,292 @@
+//! Finding the same game recorded more than once
+//!
+//! Copying a recordings folder, re-importing a bundle or running two capture
+//! setups leaves several videos of one game. Videos linked to a replay are
+//! duplicates when their replays are the same game (the replay's stable id,
+//! see stable_ids). Unlinked videos are duplicates when they have the same
+//! resolution, lengths within a second and start times within 5 seconds.
+//! Edits made from a recording (trims, crops) are never duplicates of it.
+//! Everything comes from the database; videos not probed yet (see
+//! media_info) can only match through their replay.
+
+use crate::app_state::AppState;
+use crate::commands::errors::Error;
+use crate::database::{self, RecordingRow};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tauri::Manager;
+
+const MAX_DURATION_DIFF_SECS: f64 = 1.0;
+const MAX_START_DIFF_SECS: i64 = 5;
+
+/// Why recordings were grouped as duplicates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateReason {
+    /// Their replays are the same game
+    SameReplay,
+    /// Unlinked videos with the same resolution, length and start time
+    SimilarVideo,
+}
+
+/// A recording in a duplicate set
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateCandidate {
+    pub recording_id: String,
+    pub video_path: String,
+    pub slp_path: Option<String>,
+    pub file_size: Option<u64>,
+    pub start_time: Option<String>,
+    pub duration_secs: Option<f64>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub is_archived: bool,
+}
+
+/// Recordings of the same game
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateSet {
+    pub reason: DuplicateReason,
+    /// The copy to keep unless the user picks another: the best quality
+    /// (unarchived, highest resolution, then largest file)
+    pub suggested_keep: String,
+    pub recordings: Vec<DuplicateCandidate>,
+}
+
+/// Which copy of a duplicate set to keep and which to delete
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateResolution {
+    pub keep: String,
+    pub delete: Vec<String>,
+}
+
+/// Duplicate sets across the library
+pub fn find_duplicates(app: &tauri::AppHandle) -> Result<Vec<DuplicateSet>, Error> {
+    let state = app.state::<AppState>();
+    let rows = database::get_all_recordings(&state.database.connection())
+        .map_err(Error::from)?;
+    Ok(group_duplicates(rows))
+}
+
+/// The copies a batch of resolutions deletes, checked as a whole: a copy
+/// one resolution keeps can't be deleted by another, and no copy is deleted
+/// twice. Each resolution's copies must be in the set holding the copy it
+/// keeps, as found from the database now, not as the frontend saw it
+pub fn copies_to_delete(sets: &[DuplicateSet], resolutions: &[DuplicateResolution]) -> Result<Vec<DuplicateCandidate>, Error> {
+    let kept: HashSet<&str> = resolutions.iter().map(|r| r.keep.as_str()).collect();
+    let mut deleted = HashSet::new();
+    for id in resolutions.iter().flat_map(|r| &r.delete) {
+        if kept.contains(id.as_str()) {
+            return Err(Error::InvalidPath(format!("{} is both kept and deleted", id)));
+        }
+        if !deleted.insert(id.as_str()) {
+            return Err(Error::InvalidPath(format!("{} is deleted twice", id)));
+        }
+    }
+
+    let mut copies = Vec::new();
+    for resolution in resolutions {
+        copies.extend(resolution_copies(sets, resolution)?);
+    }
+    Ok(copies)
+}
+
+/// The copies one resolution deletes
+fn resolution_copies(sets: &[DuplicateSet], resolution: &DuplicateResolution) -> Result<Vec<DuplicateCandidate>, Error> {
+    let not_duplicates = |id: &str| Error::InvalidPath(format!("{} isn't a duplicate of {}", id, resolution.keep));
+    let set = sets
+        .iter()
+        .find(|set| set.recordings.iter().any(|r| r.recording_id == resolution.keep))
+        .ok_or_else(|| Error::InvalidPath(format!("{} has no duplicates", resolution.keep)))?;
+    resolution
+        .delete
+        .iter()
+        .map(|id| {
+            if *id == resolution.keep {
+                return Err(not_duplicates(id));
+            }
+            set.recordings.iter().find(|r| r.recording_id == *id).cloned().ok_or_else(|| not_duplicates(id))
+        })
+        .collect()
+}
+
+fn group_duplicates(rows: Vec<RecordingRow>) -> Vec<DuplicateSet> {
+    let mut by_replay: HashMap<String, Vec<RecordingRow>> = HashMap::new();
+    let mut unlinked = Vec::new();
+    // Replay-only entries have no video to duplicate, and a trim or crop of
+    // a recording (see edit_recording) plays its game on purpose
+    for row in rows.into_iter().filter(|row| row.video_path.is_some() && row.source_recording_id.is_none()) {
+        match row.replay_id.clone() {
+            Some(replay_id) => by_replay.entry(replay_id).or_default().push(row),
+            None if row.slp_path.is_none() => unlinked.push(row),
+            // Linked, but the replay's id isn't known yet
+            None => {}
+        }
+    }
+
+    let mut sets: Vec<DuplicateSet> = by_replay
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|group| duplicate_set(DuplicateReason::SameReplay, group))
+        .collect();
+
+    // Unlinked videos, in start order so matches are next to each other
+    let mut unlinked: Vec<(i64, RecordingRow)> = unlinked
+        .into_iter()
+        .filter(|row| row.video_width.is_some() && row.video_height.is_some() && row.video_duration_secs.is_some())
+        .filter_map(|row| {
+            let start = chrono::DateTime::parse_from_rfc3339(row.start_time.as_deref()?).ok()?;
+            Some((start.timestamp(), row))
+        })
+        .collect();
+    unlinked.sort_by_key(|(start, _)| *start);
+
+    let mut groups: Vec<Vec<(i64, RecordingRow)>> = Vec::new();
+    for (start, row) in unlinked {
+        let group = groups.iter_mut().find(|group| {
+            group.iter().any(|(other_start, other)| {
+                (start - other_start).abs() <= MAX_START_DIFF_SECS
+                    && other.video_width == row.video_width
+                    && other.video_height == row.video_height
+                    && (other.video_duration_secs.unwrap_or(0.0) - row.video_duration_secs.unwrap_or(0.0)).abs()
+                        <= MAX_DURATION_DIFF_SECS
+            })
+        });
+        match group {
+            Some(group) => group.push((start, row)),
+            None => groups.push(vec![(start, row)]),
+        }
+    }
+    sets.extend(
+        groups
+            .into_iter()
+            .filter(|group| group.len() > 1)
+            .map(|group| duplicate_set(DuplicateReason::SimilarVideo, group.into_iter().map(|(_, row)| row).collect())),
+    );
+
+    sets.sort_by(|a, b| b.recordings[0].start_time.cmp(&a.recordings[0].start_time));
+    sets
+}
+
+fn duplicate_set(reason: DuplicateReason, mut rows: Vec<RecordingRow>) -> DuplicateSet {
+    rows.sort_by(|a, b| a.start_time.cmp(&b.start_time).then_with(|| a.video_path.cmp(&b.video_path)));
+    let suggested_keep = rows
+        .iter()
+        .max_by_key(|row| {
+            (
+                !row.is_archived,
+                row.video_width.unwrap_or(0) * row.video_height.unwrap_or(0),
+                row.file_size.unwrap_or(0),
+            )
+        })
+        .map(|row| row.id.clone())
+        .unwrap_or_default();
+
+    DuplicateSet {
+        reason,
+        suggested_keep,
+        recordings: rows
+            .into_iter()
+            .map(|row| DuplicateCandidate {
+                recording_id: row.id,
+                video_path: row.video_path.unwrap_or_default(),
+                slp_path: row.slp_path,
+                file_size: row.file_size.map(|s| s as u64),
+                start_time: row.start_time,
+                duration_secs: row.video_duration_secs,
+                width: row.video_width,
+                height: row.video_height,
+                is_archived: row.is_archived,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(id: &str, replay_id: Option<&str>, start: &str, duration: f64, size: i64) -> RecordingRow {
+        RecordingRow {
+            id: id.to_string(),
+            video_path: Some(format!("/videos/{}.mp4", id)),
+            slp_path: replay_id.map(|_| format!("/slp/{}.slp", id)),
+            file_size: Some(size),
+            file_modified_at: None,
+            thumbnail_path: None,
+            start_time: Some(start.to_string()),
+            cached_at: start.to_string(),
+            needs_reparse: false,
+            dropped_frame_estimate: None,
+            longest_stall_ms: None,
+            profile: None,
+            is_archived: false,
+            parse_error: None,
+            replay_id: replay_id.map(str::to_string),
+            video_width: Some(1920),
+            video_height: Some(1080),
+            video_duration_secs: Some(duration),
+            has_audio: None,
+            audio_mean_volume_db: None,
+            video_offset_ms: None,
+            source_recording_id: None,
+        }
+    }
+
+    #[test]
+    fn test_group_duplicates() {
+        let sets = group_duplicates(vec![
+            // The same game in two folders, started minutes apart
+            row("a", Some("game-1"), "2024-03-01T20:00:00Z", 300.0, 100),
+            row("b", Some("game-1"), "2024-03-01T20:04:00Z", 120.0, 200),
+            row("c", Some("game-2"), "2024-03-01T20:00:00Z", 300.0, 100),
+            // A trim of c plays the same game, but isn't a copy
+            RecordingRow {
+                source_recording_id: Some("c".to_string()),
+                ..row("c-edit", Some("game-2"), "2024-03-01T20:00:00Z", 90.0, 30)
+            },
+            // Unlinked copies of one video, and a different one started right after
+            row("d", None, "2024-03-02T10:00:00Z", 60.2, 50),
+            row("e", None, "2024-03-02T10:00:03Z", 60.9, 50),
+            row("f", None, "2024-03-02T10:00:04Z", 75.0, 50),
+        ]);
+
+        assert_eq!(sets.len(), 2);
+        assert_eq!(sets[0].reason, DuplicateReason::SimilarVideo);
+        let ids: Vec<&str> = sets[0].recordings.iter().map(|r| r.recording_id.as_str()).collect();
+        assert_eq!(ids, vec!["d", "e"]);
+
+        assert_eq!(sets[1].reason, DuplicateReason::SameReplay);
+        assert_eq!(sets[1].recordings.len(), 2);
+        assert_eq!(sets[1].suggested_keep, "b");
+    }
+
+    #[test]
+    fn test_only_copies_of_the_kept_recording_are_deleted() {
+        let sets = group_duplicates(vec![
+            row("a", Some("game-1"), "2024-03-01T20:00:00Z", 300.0, 100),
+            row("b", Some("game-1"), "2024-03-01T20:04:00Z", 120.0, 200),
+            row("c", Some("game-2"), "2024-03-01T20:00:00Z", 300.0, 100),
+            row("d", Some("game-2"), "2024-03-01T20:00:00Z", 300.0, 100),
+        ]);
+        let resolution = |keep: &str, delete: &[&str]| DuplicateResolution {
+            keep: keep.to_string(),
+            delete: delete.iter().map(|id| id.to_string()).collect(),
+        };
+
+        let deleted = copies_to_delete(&sets, &[resolution("b", &["a"]), resolution("c", &["d"])]).unwrap();
+        assert_eq!(deleted.iter().map(|r| r.recording_id.as_str()).collect::<Vec<_>>(), vec!["a", "d"]);
+        // Another game's copy, the kept copy itself, or a recording without copies
+        assert!(copies_to_delete(&sets, &[resolution("b", &["a", "c"])]).is_err());
+        assert!(copies_to_delete(&sets, &[resolution("b", &["b"])]).is_err());
+        assert!(copies_to_delete(&sets, &[resolution("e", &["a"])]).is_err());
+
+        // Two resolutions of one game that keep each other would delete both
+        assert!(copies_to_delete(&sets, &[resolution("a", &["b"]), resolution("b", &["a"])]).is_err());
+        // A copy is deleted once
+        assert!(copies_to_delete(&sets, &[resolution("b", &["a"]), resolution("b", &["a"])]).is_err());
+    }
+}
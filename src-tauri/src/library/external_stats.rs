@@ -0,0 +1,640 @@
+//! Importing stats other tools already computed
+//!
+//! Players coming from other tools can bring their stats instead of parsing
+//! every replay again. Two sources are read:
+//! - Slippi Launcher's replay database, attached read-only. It keeps each
+//!   replay's stage, players and winner but no per-player stats, so those
+//!   games count towards records and character/stage usage only
+//! - a folder of slippi-js getStats() dumps (.json), either
+//!   `{ settings, metadata, stats, filePath }` or getStats() alone, saved
+//!   next to its replay under the same name (the replay's Game Start then
+//!   gives the players)
+//!
+//! Games are keyed by their replay's path, as the historical sync does. A
+//! replay that already has stats from the same source is skipped; one with
+//! stats from anywhere else is a conflict and keeps what it has. Imported
+//! games are tagged with their source (game_stats.stats_source), as their
+//! numbers aren't worked out the way ours are.
+
+use crate::app_state::AppState;
+use crate::commands::errors::Error;
+use crate::commands::settings::{my_identity, stats_exclusion, warmup_settings};
+use crate::database::{self, GameStatsRow, MyIdentity, MyResult, PlayerStatsRow, StatsSource};
+use crate::slippi::game_start::read_game_start;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+use walkdir::WalkDir;
+
+/// Tables of Slippi Launcher's replay database the import reads
+const LAUNCHER_TABLES: [&str; 3] = ["replay", "game_start", "player"];
+
+/// Stocks a player starts with when the dump doesn't say
+const DEFAULT_START_STOCKS: i64 = 4;
+
+static NULL: Value = Value::Null;
+
+/// Where to import stats from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExternalStatsSource {
+    /// Slippi Launcher's replay database file
+    SlippiLauncher,
+    /// A folder of slippi-js getStats() dumps
+    StatsJson,
+}
+
+impl ExternalStatsSource {
+    fn stats_source(self) -> StatsSource {
+        match self {
+            Self::SlippiLauncher => StatsSource::SlippiLauncher,
+            Self::StatsJson => StatsSource::SlippiJs,
+        }
+    }
+}
+
+/// What an import did, in games
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalImportReport {
+    pub imported: u32,
+    /// Already imported from the same source, or not a two-player game that can be read
+    pub skipped: u32,
+    /// Already have stats from another source, which are kept
+    pub conflicting: u32,
+}
+
+/// A game read from another tool. Its rows get their id when saved
+#[derive(Debug)]
+struct ImportedGame {
+    slp_path: String,
+    game: GameStatsRow,
+    players: Vec<PlayerStatsRow>,
+}
+
+/// Games read from a source, and how many entries couldn't be used
+#[derive(Debug, Default)]
+struct ReadGames {
+    games: Vec<ImportedGame>,
+    unusable: u32,
+}
+
+/// What both sources know about a game besides its players
+#[derive(Debug, Default)]
+struct GameInfo {
+    stage: Option<i32>,
+    frames: Option<i32>,
+    created_at: Option<String>,
+    played_on: Option<String>,
+    is_pal: Option<bool>,
+    replay_version: Option<String>,
+    /// The replay never reached Game End (only stats dumps tell, with gameComplete)
+    unfinished: bool,
+}
+
+/// Import the games of `path` (a database file or a folder of dumps)
+pub async fn import_external_stats(
+    app: &tauri::AppHandle,
+    source: ExternalStatsSource,
+    path: &Path,
+) -> Result<ExternalImportReport, Error> {
+    let app = app.clone();
+    let path = path.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || {
+        log::info!("📥 Importing {:?} stats from {}", source, path.display());
+        let state = app.state::<AppState>();
+
+        let read = match source {
+            ExternalStatsSource::SlippiLauncher => read_launcher_database(&state.database.connection(), &path)?,
+            ExternalStatsSource::StatsJson => read_stats_dumps(&path)?,
+        };
+
+        let conn = state.database.connection();
+        let mut report = save_games(&conn, read.games, source.stats_source(), &my_identity(&app))
+            .map_err(|e| Error::database("Failed to save imported stats", e))?;
+        report.skipped += read.unusable;
+
+        database::update_excluded_reasons(&conn, &stats_exclusion(&app), None)
+            .map_err(|e| Error::database("Failed to flag excluded games", e))?;
+        database::recompute_warmups(&conn, &warmup_settings(&app))
+            .map_err(|e| Error::database("Failed to flag warm-up games", e))?;
+        state.stats_cache.invalidate();
+
+        log::info!(
+            "📥 Imported {} games from {} ({} skipped, {} conflicting)",
+            report.imported,
+            path.display(),
+            report.skipped,
+            report.conflicting
+        );
+        Ok(report)
+    })
+    .await
+    .map_err(|e| Error::RecordingFailed(format!("Stats import task failed: {}", e)))?
+}
+
+/// Save the games that have no stats yet, in one transaction
+fn save_games(
+    conn: &Connection,
+    games: Vec<ImportedGame>,
+    source: StatsSource,
+    identity: &MyIdentity,
+) -> rusqlite::Result<ExternalImportReport> {
+    let tx = conn.unchecked_transaction()?;
+    let mut report = ExternalImportReport::default();
+
+    for ImportedGame { slp_path, game, players } in games {
+        // A replay in the library keeps its recording's id
+        let id = match database::get_recording_id_by_slp_path(&tx, &slp_path)? {
+            Some(id) => id,
+            None => historical_id(&slp_path),
+        };
+        let existing = match database::get_stats_source_by_slp_path(&tx, &slp_path)? {
+            Some(existing) => Some(existing),
+            None => database::get_game_stats(&tx, &id)?.map(|stats| stats.stats_source),
+        };
+        match existing {
+            Some(existing) if existing == source => {
+                report.skipped += 1;
+                continue;
+            }
+            Some(existing) => {
+                log::debug!("Keeping the {} stats of {}", existing.as_str(), slp_path);
+                report.conflicting += 1;
+                continue;
+            }
+            None => {}
+        }
+
+        database::upsert_game_stats(&tx, &GameStatsRow {
+            id: id.clone(),
+            slp_path: Some(slp_path.clone()),
+            stats_source: source,
+            ..game
+        })?;
+        for player in players {
+            database::upsert_player_stats(&tx, &PlayerStatsRow {
+                recording_id: id.clone(),
+                slp_path: Some(slp_path.clone()),
+                ..player
+            })?;
+        }
+        database::recompute_is_me_for_recording(&tx, &id, identity)?;
+        report.imported += 1;
+    }
+
+    tx.commit()?;
+    Ok(report)
+}
+
+/// Id of a game without a recording, the one the historical sync gives it
+fn historical_id(slp_path: &str) -> String {
+    let hash: String = Sha256::digest(slp_path.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("historical-{}", &hash[..32])
+}
+
+/// Read the games of Slippi Launcher's replay database
+fn read_launcher_database(conn: &Connection, db_path: &Path) -> Result<ReadGames, Error> {
+    if !db_path.is_file() {
+        return Err(Error::InvalidPath(format!("No database at {}", db_path.display())));
+    }
+    conn.execute("ATTACH DATABASE ?1 AS launcher", params![read_only_uri(db_path)])
+        .map_err(|e| Error::database("Failed to open the Slippi Launcher database", e))?;
+    let read = read_launcher_games(conn, db_path);
+    if let Err(e) = conn.execute("DETACH DATABASE launcher", []) {
+        log::warn!("Failed to detach the Slippi Launcher database: {}", e);
+    }
+    read
+}
+
+fn read_launcher_games(conn: &Connection, db_path: &Path) -> Result<ReadGames, Error> {
+    let context = "Failed to read the Slippi Launcher database";
+    let tables: usize = conn
+        .query_row(
+            &format!(
+                "SELECT COUNT(*) FROM launcher.sqlite_master WHERE type = 'table' AND name IN ({})",
+                LAUNCHER_TABLES.map(|t| format!("'{}'", t)).join(", ")
+            ),
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| Error::database(context, e))?;
+    if tables < LAUNCHER_TABLES.len() {
+        return Err(Error::InvalidPath(format!("{} isn't a Slippi Launcher replay database", db_path.display())));
+    }
+
+    let mut players: HashMap<i64, Vec<(PlayerStatsRow, bool)>> = HashMap::new();
+    let mut stmt = conn
+        .prepare(
+            "SELECT replay_id, \"index\", type, character_id, character_color, is_winner, connect_code, display_name
+             FROM launcher.player
+             ORDER BY replay_id, \"index\"",
+        )
+        .map_err(|e| Error::database(context, e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            let index: i32 = row.get(1)?;
+            let player = PlayerStatsRow {
+                character_color: row.get::<_, Option<i32>>(4)?.unwrap_or(0),
+                connect_code: row.get::<_, Option<String>>(6)?.filter(|c| !c.is_empty()),
+                display_name: row.get::<_, Option<String>>(7)?.filter(|n| !n.is_empty()),
+                // Player type 1 is a CPU
+                is_cpu: row.get::<_, Option<i32>>(2)? == Some(1),
+                ..empty_player(index, index, row.get::<_, Option<i32>>(3)?.unwrap_or(0))
+            };
+            Ok((row.get::<_, i64>(0)?, player, row.get::<_, Option<bool>>(5)?.unwrap_or(false)))
+        })
+        .map_err(|e| Error::database(context, e))?;
+    for row in rows {
+        let (replay_id, player, is_winner) = row.map_err(|e| Error::database(context, e))?;
+        players.entry(replay_id).or_default().push((player, is_winner));
+    }
+
+    let mut read = ReadGames::default();
+    let mut stmt = conn
+        .prepare(
+            "SELECT r._id, r.folder, r.file_name, g.stage, g.start_time, g.platform, g.last_frame
+             FROM launcher.replay r
+             JOIN launcher.game_start g ON g.replay_id = r._id",
+        )
+        .map_err(|e| Error::database(context, e))?;
+    let games = stmt
+        .query_map([], |row| {
+            let folder: String = row.get(1)?;
+            let file_name: String = row.get(2)?;
+            let info = GameInfo {
+                stage: row.get(3)?,
+                created_at: row.get(4)?,
+                played_on: row.get(5)?,
+                frames: row.get(6)?,
+                ..Default::default()
+            };
+            Ok((row.get::<_, i64>(0)?, Path::new(&folder).join(file_name), info))
+        })
+        .map_err(|e| Error::database(context, e))?;
+    for game in games {
+        let (replay_id, slp_path, info) = game.map_err(|e| Error::database(context, e))?;
+        let game_players = players.remove(&replay_id).unwrap_or_default();
+        let winner = game_players.iter().find(|(_, is_winner)| *is_winner).map(|(p, _)| p.player_index);
+        let game_players: Vec<PlayerStatsRow> = game_players.into_iter().map(|(player, _)| player).collect();
+        // Our stats are for singles
+        if game_players.len() != 2 {
+            read.unusable += 1;
+            continue;
+        }
+        read.games.push(imported_game(&slp_path, info, game_players, winner));
+    }
+    Ok(read)
+}
+
+/// URI opening a database read-only (the connection accepts URIs as file names)
+fn read_only_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    // Windows paths need a leading slash before the drive letter
+    let path = if path.starts_with('/') { path } else { format!("/{}", path) };
+    let escaped: String = path
+        .chars()
+        .map(|c| match c {
+            '%' => "%25".to_string(),
+            '?' => "%3f".to_string(),
+            '#' => "%23".to_string(),
+            c => c.to_string(),
+        })
+        .collect();
+    format!("file:{}?mode=ro", escaped)
+}
+
+/// Read every getStats() dump under `dir`
+fn read_stats_dumps(dir: &Path) -> Result<ReadGames, Error> {
+    if !dir.is_dir() {
+        return Err(Error::InvalidPath(format!("Directory does not exist: {}", dir.display())));
+    }
+
+    let mut read = ReadGames::default();
+    for entry in WalkDir::new(dir).max_depth(5).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.extension().and_then(|s| s.to_str()) != Some("json") {
+            continue;
+        }
+        let dump = std::fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Value>(&bytes).ok());
+        match dump.and_then(|dump| dump_game(&dump, path)) {
+            Some(game) => read.games.push(game),
+            None => {
+                log::warn!("Skipping {}: not a two-player slippi-js stats dump", path.display());
+                read.unusable += 1;
+            }
+        }
+    }
+    Ok(read)
+}
+
+/// The game of a getStats() dump, mapped the way the stats pass maps it
+fn dump_game(dump: &Value, dump_path: &Path) -> Option<ImportedGame> {
+    let stats = dump.get("stats").unwrap_or(dump);
+    stats.get("overall")?.as_array()?;
+    let metadata = dump.get("metadata").unwrap_or(&NULL);
+
+    let slp_path = ["filePath", "slpPath"]
+        .iter()
+        .find_map(|key| dump[*key].as_str())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| dump_path.with_extension("slp"));
+
+    let settings = dump.get("settings").filter(|settings| settings["players"].is_array());
+    let players: Vec<PlayerStatsRow> = match settings {
+        Some(settings) => settings["players"]
+            .as_array()?
+            .iter()
+            .filter_map(|player| {
+                let index = player["playerIndex"].as_i64()?;
+                let names = &metadata["players"][index.to_string()]["names"];
+                let text = |value: &Value| value.as_str().filter(|s| !s.is_empty()).map(String::from);
+                let row = PlayerStatsRow {
+                    connect_code: text(&player["connectCode"]).or_else(|| text(&names["code"])),
+                    display_name: text(&player["displayName"]).or_else(|| text(&names["netplay"])),
+                    character_color: int(&player["characterColor"]),
+                    is_cpu: player["type"].as_i64() == Some(1),
+                    ..empty_player(
+                        index as i32,
+                        player["port"].as_i64().unwrap_or(index) as i32,
+                        int(&player["characterId"]),
+                    )
+                };
+                let start_stocks = player["startStocks"].as_i64().unwrap_or(DEFAULT_START_STOCKS);
+                Some(with_dump_stats(row, stats, start_stocks))
+            })
+            .collect(),
+        // getStats() alone: the players are in the replay's Game Start
+        None => read_game_start(&slp_path)?
+            .players
+            .iter()
+            .map(|player| {
+                let row = PlayerStatsRow {
+                    connect_code: player.connect_code.clone(),
+                    is_cpu: player.is_cpu,
+                    ..empty_player(i32::from(player.port), i32::from(player.port), i32::from(player.character_id))
+                };
+                with_dump_stats(row, stats, DEFAULT_START_STOCKS)
+            })
+            .collect(),
+    };
+    if players.len() != 2 {
+        return None;
+    }
+
+    // More stocks left wins, as in save_computed_stats
+    let winner = match players[0].stocks_remaining.cmp(&players[1].stocks_remaining) {
+        std::cmp::Ordering::Greater => Some(players[0].player_index),
+        std::cmp::Ordering::Less => Some(players[1].player_index),
+        std::cmp::Ordering::Equal => None,
+    };
+
+    let settings = settings.unwrap_or(&NULL);
+    let info = GameInfo {
+        stage: settings["stageId"].as_i64().map(|s| s as i32),
+        frames: stats["lastFrame"].as_i64().or_else(|| metadata["lastFrame"].as_i64()).map(|f| f as i32),
+        created_at: metadata["startAt"].as_str().map(String::from).or_else(|| filename_time(&slp_path)),
+        played_on: metadata["playedOn"].as_str().map(String::from),
+        is_pal: settings["isPAL"].as_bool(),
+        replay_version: settings["slpVersion"].as_str().map(String::from),
+        unfinished: stats["gameComplete"].as_bool() == Some(false),
+    };
+    Some(imported_game(&slp_path, info, players, winner))
+}
+
+/// A player's numbers from getStats(): the entries of `overall`,
+/// `actionCounts` and `stocks` with its player index
+fn with_dump_stats(player: PlayerStatsRow, stats: &Value, start_stocks: i64) -> PlayerStatsRow {
+    let index = i64::from(player.player_index);
+    let entries = |key: &str| -> Vec<&Value> {
+        stats[key]
+            .as_array()
+            .map(|entries| entries.iter().filter(|e| e["playerIndex"].as_i64() == Some(index)).collect())
+            .unwrap_or_default()
+    };
+    let overall = entries("overall").first().copied().unwrap_or(&NULL);
+    let actions = entries("actionCounts").first().copied().unwrap_or(&NULL);
+    let stocks = entries("stocks");
+    let lost = stocks.iter().filter(|stock| !stock["endFrame"].is_null()).count() as i64;
+    let last_stock = stocks.last().copied().unwrap_or(&NULL);
+    let lcancels = &actions["lCancelCount"];
+
+    PlayerStatsRow {
+        total_damage: overall["totalDamage"].as_f64().unwrap_or(0.0),
+        kill_count: count(&overall["killCount"]),
+        conversion_count: count(&overall["conversionCount"]),
+        successful_conversions: count(&overall["successfulConversions"]),
+        openings_per_kill: ratio(&overall["openingsPerKill"]),
+        damage_per_opening: ratio(&overall["damagePerOpening"]),
+        neutral_win_ratio: ratio(&overall["neutralWinRatio"]),
+        counter_hit_ratio: ratio(&overall["counterHitRatio"]),
+        beneficial_trade_ratio: ratio(&overall["beneficialTradeRatio"]),
+        inputs_total: count(&overall["inputCounts"]["total"]),
+        inputs_per_minute: ratio(&overall["inputsPerMinute"]),
+        avg_kill_percent: overall["avgKillPercent"].as_f64(),
+        wavedash_count: count(&actions["wavedashCount"]),
+        waveland_count: count(&actions["wavelandCount"]),
+        air_dodge_count: count(&actions["airDodgeCount"]),
+        dash_dance_count: count(&actions["dashDanceCount"]),
+        spot_dodge_count: count(&actions["spotDodgeCount"]),
+        ledgegrab_count: count(&actions["ledgegrabCount"]),
+        roll_count: count(&actions["rollCount"]),
+        grab_count: count(&actions["grabCount"]),
+        throw_count: sum(&actions["throwCount"]),
+        ground_tech_count: sum(&actions["groundTechCount"]),
+        wall_tech_count: sum(&actions["wallTechCount"]),
+        l_cancel_success_count: lcancels["success"].as_i64().map(|n| n as i32),
+        l_cancel_fail_count: lcancels["fail"].as_i64().map(|n| n as i32),
+        stocks_remaining: (start_stocks - lost) as i32,
+        final_percent: last_stock["endPercent"].as_f64().or_else(|| last_stock["currentPercent"].as_f64()),
+        ..player
+    }
+}
+
+/// A number, or 0
+fn int(value: &Value) -> i32 {
+    value.as_i64().unwrap_or(0) as i32
+}
+
+/// A count given as a number, or as { count } or { total }
+fn count(value: &Value) -> i32 {
+    value
+        .as_f64()
+        .or_else(|| value["count"].as_f64())
+        .or_else(|| value["total"].as_f64())
+        .unwrap_or(0.0) as i32
+}
+
+/// A slippi-js ratio ({ ratio }) or a bare number
+fn ratio(value: &Value) -> Option<f64> {
+    value["ratio"].as_f64().or_else(|| value.as_f64())
+}
+
+/// A number, or the sum of an object's numbers (throwCount's up/down/forward/back)
+fn sum(value: &Value) -> i32 {
+    match value {
+        Value::Object(fields) => fields.values().filter_map(Value::as_f64).sum::<f64>() as i32,
+        _ => value.as_f64().unwrap_or(0.0) as i32,
+    }
+}
+
+/// When the game was played, from a Game_YYYYMMDDTHHMMSS.slp name
+fn filename_time(slp_path: &Path) -> Option<String> {
+    let stem = slp_path.file_stem()?.to_str()?;
+    chrono::NaiveDateTime::parse_from_str(stem.strip_prefix("Game_")?, "%Y%m%dT%H%M%S")
+        .ok()
+        .map(|time| time.format("%Y-%m-%dT%H:%M:%S").to_string())
+}
+
+/// A player with nothing but who they were
+fn empty_player(player_index: i32, port: i32, character_id: i32) -> PlayerStatsRow {
+    PlayerStatsRow {
+        id: None,
+        recording_id: String::new(),
+        player_index,
+        connect_code: None,
+        display_name: None,
+        character_id,
+        character_color: 0,
+        port,
+        total_damage: 0.0,
+        kill_count: 0,
+        conversion_count: 0,
+        successful_conversions: 0,
+        openings_per_kill: None,
+        damage_per_opening: None,
+        neutral_win_ratio: None,
+        counter_hit_ratio: None,
+        beneficial_trade_ratio: None,
+        inputs_total: 0,
+        inputs_per_minute: None,
+        avg_kill_percent: None,
+        wavedash_count: 0,
+        waveland_count: 0,
+        air_dodge_count: 0,
+        dash_dance_count: 0,
+        spot_dodge_count: 0,
+        ledgegrab_count: 0,
+        roll_count: 0,
+        grab_count: 0,
+        throw_count: 0,
+        ground_tech_count: 0,
+        wall_tech_count: 0,
+        wall_jump_tech_count: None,
+        l_cancel_success_count: None,
+        l_cancel_fail_count: None,
+        stocks_remaining: 0,
+        final_percent: None,
+        slp_path: None,
+        // Attributed once saved
+        is_me: false,
+        // Nothing for the stats pass to bring up to date
+        stats_version: 0,
+        is_cpu: false,
+    }
+}
+
+/// A two-player game, with the winner given by player index
+fn imported_game(slp_path: &Path, info: GameInfo, players: Vec<PlayerStatsRow>, winner: Option<i32>) -> ImportedGame {
+    let winner = winner.and_then(|index| players.iter().find(|p| p.player_index == index));
+    let loser = winner.and_then(|winner| players.iter().find(|p| p.player_index != winner.player_index));
+    let p1 = players.first();
+    let p2 = players.get(1);
+    let game = GameStatsRow {
+        id: String::new(),
+        player1_id: p1.and_then(|p| p.connect_code.clone()),
+        player2_id: p2.and_then(|p| p.connect_code.clone()),
+        player1_port: p1.map(|p| p.port),
+        player2_port: p2.map(|p| p.port),
+        player1_character: p1.map(|p| p.character_id),
+        player2_character: p2.map(|p| p.character_id),
+        player1_color: p1.map(|p| p.character_color),
+        player2_color: p2.map(|p| p.character_color),
+        winner_port: winner.map(|p| p.port),
+        loser_port: loser.map(|p| p.port),
+        stage: info.stage,
+        game_duration: info.frames,
+        total_frames: info.frames,
+        is_pal: info.is_pal,
+        played_on: info.played_on,
+        created_at: info.created_at,
+        slp_path: None,
+        replay_version: info.replay_version,
+        excluded_reason: None,
+        total_paused_frames: None,
+        winner_code: winner.and_then(|p| p.connect_code.clone()),
+        loser_code: loser.and_then(|p| p.connect_code.clone()),
+        my_result: MyResult::Unknown,
+        is_warmup: false,
+        stats_source: StatsSource::Buckwheat,
+        completed: !info.unfinished,
+    };
+    ImportedGame { slp_path: slp_path.to_string_lossy().to_string(), game, players }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dump_maps_like_the_stats_pass() {
+        let dump = serde_json::json!({
+            "settings": {
+                "stageId": 31,
+                "isPAL": false,
+                "slpVersion": "3.16.0",
+                "players": [
+                    { "playerIndex": 0, "port": 1, "characterId": 2, "characterColor": 1, "connectCode": "ME#1", "type": 0 },
+                    { "playerIndex": 1, "port": 2, "characterId": 9, "characterColor": 0, "type": 0 }
+                ]
+            },
+            "metadata": {
+                "startAt": "2024-03-01T20:00:00Z",
+                "players": { "1": { "names": { "code": "OPP#2", "netplay": "Opp" } } }
+            },
+            "stats": {
+                "lastFrame": 9000,
+                "stocks": [
+                    { "playerIndex": 1, "endFrame": 2000, "endPercent": 120.0 },
+                    { "playerIndex": 1, "endFrame": 5000, "endPercent": 90.0 },
+                    { "playerIndex": 0, "endFrame": 7000, "endPercent": 80.0 }
+                ],
+                "overall": [
+                    { "playerIndex": 0, "killCount": 2, "neutralWinRatio": { "ratio": 0.6 }, "inputCounts": { "total": 900 } },
+                    { "playerIndex": 1, "killCount": 1, "neutralWinRatio": { "ratio": 0.4 }, "inputCounts": { "total": 700 } }
+                ],
+                "actionCounts": [
+                    { "playerIndex": 0, "throwCount": { "up": 1, "down": 2 }, "lCancelCount": { "success": 5, "fail": 1 } }
+                ]
+            }
+        });
+
+        let game = dump_game(&dump, Path::new("/dumps/Game_20240301T200000.json")).unwrap();
+        assert_eq!(game.slp_path, "/dumps/Game_20240301T200000.slp");
+        assert_eq!(game.game.stage, Some(31));
+        assert_eq!(game.game.game_duration, Some(9000));
+        // ME#1 lost one stock to OPP#2's two
+        assert_eq!(game.game.winner_code.as_deref(), Some("ME#1"));
+        assert_eq!(game.game.loser_code.as_deref(), Some("OPP#2"));
+
+        let [me, opponent] = game.players.as_slice() else { panic!("expected two players") };
+        assert_eq!((me.stocks_remaining, opponent.stocks_remaining), (3, 2));
+        assert_eq!(me.neutral_win_ratio, Some(0.6));
+        assert_eq!(me.throw_count, 3);
+        assert_eq!(me.l_cancel_success_count, Some(5));
+        assert_eq!(opponent.l_cancel_success_count, None);
+        assert_eq!(opponent.display_name.as_deref(), Some("Opp"));
+        assert_eq!(opponent.final_percent, Some(90.0));
+
+        assert!(dump_game(&serde_json::json!({ "not": "stats" }), Path::new("x.json")).is_none());
+    }
+}
@@ -0,0 +1,268 @@
+//! Frame data exports
+//!
+//! Writes a recording's per-frame player data (see slippi::frames) next to
+//! its video for analysis in pandas, polars or R, as Parquet or gzipped CSV
+//! with one row per (frame, port). Ports are 0-indexed, Nana is in the
+//! follower_ columns of her row (empty when she's dead or not playing), and
+//! every_nth thins long games down to every Nth frame. Characters are
+//! internal IDs (Popo and Nana apart), with their name alongside.
+//!
+//! Rows are written as they're read, in row groups of ROW_GROUP_ROWS for
+//! Parquet, so memory stays flat however long the game is.
+
+use crate::app_state::AppState;
+use crate::commands::errors::Error;
+use crate::database;
+use crate::slippi::frames::{self, FrameRow, FrameSelection, FrameState};
+use crate::slippi::melee_ids;
+use flate2::write::GzEncoder;
+use parquet::basic::{Compression, ConvertedType, Repetition, Type as PhysicalType};
+use parquet::data_type::{BoolType, ByteArray, ByteArrayType, FloatType, Int64Type};
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::Manager;
+
+/// Rows buffered per Parquet row group
+const ROW_GROUP_ROWS: usize = 65_536;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameExportFormat {
+    Parquet,
+    Csv,
+}
+
+impl FrameExportFormat {
+    /// Replaces the video's extension
+    fn extension(self) -> &'static str {
+        match self {
+            FrameExportFormat::Parquet => "frames.parquet",
+            FrameExportFormat::Csv => "frames.csv.gz",
+        }
+    }
+}
+
+/// A finished export
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameExport {
+    pub path: String,
+    pub rows: u64,
+}
+
+/// How a column gets its value from a row (None is null/empty)
+#[derive(Clone, Copy)]
+enum Column {
+    Int(fn(&FrameRow) -> Option<i64>),
+    Float(fn(&FrameRow) -> Option<f32>),
+    Bool(fn(&FrameRow) -> Option<bool>),
+    Text(fn(&FrameRow) -> Option<&'static str>),
+}
+
+/// Every column, in file order
+const COLUMNS: &[(&str, Column)] = &[
+    ("frame", Column::Int(|r| Some(r.frame.into()))),
+    ("port", Column::Int(|r| Some(r.port.into()))),
+    ("character", Column::Int(|r| Some(r.state.character.into()))),
+    ("character_name", Column::Text(|r| Some(melee_ids::internal_character_name(r.state.character)))),
+    ("action_state", Column::Int(|r| Some(r.state.action_state.into()))),
+    ("x", Column::Float(|r| Some(r.state.x))),
+    ("y", Column::Float(|r| Some(r.state.y))),
+    ("facing", Column::Float(|r| Some(r.state.facing))),
+    ("percent", Column::Float(|r| Some(r.state.percent))),
+    ("shield", Column::Float(|r| Some(r.state.shield))),
+    ("stocks", Column::Int(|r| Some(r.state.stocks.into()))),
+    ("airborne", Column::Bool(|r| r.state.airborne)),
+    ("joystick_x", Column::Float(|r| Some(r.inputs.joystick_x))),
+    ("joystick_y", Column::Float(|r| Some(r.inputs.joystick_y))),
+    ("cstick_x", Column::Float(|r| Some(r.inputs.cstick_x))),
+    ("cstick_y", Column::Float(|r| Some(r.inputs.cstick_y))),
+    ("trigger", Column::Float(|r| Some(r.inputs.trigger))),
+    ("buttons", Column::Int(|r| Some(r.inputs.buttons.into()))),
+    ("physical_buttons", Column::Int(|r| Some(r.inputs.physical_buttons.into()))),
+    ("follower_action_state", Column::Int(|r| follower(r).map(|f| f.action_state.into()))),
+    ("follower_x", Column::Float(|r| follower(r).map(|f| f.x))),
+    ("follower_y", Column::Float(|r| follower(r).map(|f| f.y))),
+    ("follower_facing", Column::Float(|r| follower(r).map(|f| f.facing))),
+    ("follower_percent", Column::Float(|r| follower(r).map(|f| f.percent))),
+    ("follower_shield", Column::Float(|r| follower(r).map(|f| f.shield))),
+    ("follower_stocks", Column::Int(|r| follower(r).map(|f| f.stocks.into()))),
+    ("follower_airborne", Column::Bool(|r| follower(r).and_then(|f| f.airborne))),
+];
+
+fn follower(row: &FrameRow) -> Option<&FrameState> {
+    row.follower.as_ref()
+}
+
+/// Export a recording's frame data next to its video
+pub async fn export_frames(
+    app: &tauri::AppHandle,
+    recording_id: &str,
+    format: FrameExportFormat,
+    selection: FrameSelection,
+) -> Result<FrameExport, Error> {
+    let recording = {
+        let state = app.state::<AppState>();
+        let conn = state.database.connection();
+        database::get_recording_by_id(&conn, recording_id)
+            .map_err(Error::from)?
+            .ok_or_else(|| Error::InvalidPath(format!("Recording not found: {}", recording_id)))?
+    };
+    let slp_path = recording
+        .slp_path
+        .map(PathBuf::from)
+        .filter(|p| p.exists())
+        .ok_or_else(|| Error::InvalidPath(format!("Recording has no replay: {}", recording_id)))?;
+    // Next to the video, or the replay for replay-only entries
+    let dest_path = recording.video_path.as_deref().map_or(slp_path.as_path(), Path::new).with_extension(format.extension());
+
+    log::info!("📊 Exporting frames of {} to {}", slp_path.display(), dest_path.display());
+
+    let path = dest_path.clone();
+    let rows = tauri::async_runtime::spawn_blocking(move || write_frames(&slp_path, &path, format, &selection))
+        .await
+        .map_err(|e| Error::RecordingFailed(format!("Frame export task failed: {}", e)))?
+        .inspect_err(|_| {
+            let _ = std::fs::remove_file(&dest_path);
+        })?;
+
+    log::info!("📊 Exported {} rows to {}", rows, dest_path.display());
+    Ok(FrameExport { path: dest_path.to_string_lossy().to_string(), rows })
+}
+
+/// Returns how many rows were written
+fn write_frames(
+    slp_path: &Path,
+    dest_path: &Path,
+    format: FrameExportFormat,
+    selection: &FrameSelection,
+) -> Result<u64, Error> {
+    let file = File::create(dest_path)?;
+    let mut rows = 0;
+    match format {
+        FrameExportFormat::Parquet => {
+            let mut writer = parquet_writer(file).map_err(parquet_error)?;
+            let mut buffer = Vec::with_capacity(ROW_GROUP_ROWS);
+            frames::read_frames(slp_path, selection, |row| {
+                buffer.push(row);
+                rows += 1;
+                if buffer.len() == ROW_GROUP_ROWS {
+                    write_row_group(&mut writer, &buffer).map_err(parquet_error)?;
+                    buffer.clear();
+                }
+                Ok::<_, Error>(())
+            })?;
+            if !buffer.is_empty() {
+                write_row_group(&mut writer, &buffer).map_err(parquet_error)?;
+            }
+            writer.close().map_err(parquet_error)?;
+        }
+        FrameExportFormat::Csv => {
+            let mut writer = GzEncoder::new(BufWriter::new(file), flate2::Compression::default());
+            let names: Vec<&str> = COLUMNS.iter().map(|(name, _)| *name).collect();
+            writeln!(writer, "{}", names.join(","))?;
+            frames::read_frames(slp_path, selection, |row| {
+                rows += 1;
+                write_csv_row(&mut writer, &row)
+            })?;
+            writer.finish()?.flush()?;
+        }
+    }
+    Ok(rows)
+}
+
+fn write_csv_row(writer: &mut impl Write, row: &FrameRow) -> Result<(), Error> {
+    let fields: Vec<String> = COLUMNS
+        .iter()
+        .map(|(_, column)| match column {
+            Column::Int(get) => get(row).map(|v| v.to_string()),
+            Column::Float(get) => get(row).map(|v| v.to_string()),
+            Column::Bool(get) => get(row).map(|v| u8::from(v).to_string()),
+            // No name has a comma or quote in it
+            Column::Text(get) => get(row).map(str::to_string),
+        })
+        .map(Option::unwrap_or_default)
+        .collect();
+    writeln!(writer, "{}", fields.join(","))?;
+    Ok(())
+}
+
+fn parquet_writer(file: File) -> Result<SerializedFileWriter<File>, ParquetError> {
+    let fields = COLUMNS
+        .iter()
+        .map(|(name, column)| {
+            let (physical, converted) = match column {
+                Column::Int(_) => (PhysicalType::INT64, ConvertedType::NONE),
+                Column::Float(_) => (PhysicalType::FLOAT, ConvertedType::NONE),
+                Column::Bool(_) => (PhysicalType::BOOLEAN, ConvertedType::NONE),
+                Column::Text(_) => (PhysicalType::BYTE_ARRAY, ConvertedType::UTF8),
+            };
+            Type::primitive_type_builder(name, physical)
+                .with_converted_type(converted)
+                .with_repetition(Repetition::OPTIONAL)
+                .build()
+                .map(Arc::new)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let schema = Type::group_type_builder("frames").with_fields(fields).build()?;
+    let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    SerializedFileWriter::new(file, Arc::new(schema), Arc::new(properties))
+}
+
+fn write_row_group(writer: &mut SerializedFileWriter<File>, rows: &[FrameRow]) -> Result<(), ParquetError> {
+    let mut group = writer.next_row_group()?;
+    for (name, column) in COLUMNS {
+        let mut column_writer = group
+            .next_column()?
+            .ok_or_else(|| ParquetError::General(format!("No column for {}", name)))?;
+        match column {
+            Column::Int(get) => {
+                let (values, levels) = definition_levels(rows, get);
+                column_writer.typed::<Int64Type>().write_batch(&values, Some(&levels), None)?;
+            }
+            Column::Float(get) => {
+                let (values, levels) = definition_levels(rows, get);
+                column_writer.typed::<FloatType>().write_batch(&values, Some(&levels), None)?;
+            }
+            Column::Bool(get) => {
+                let (values, levels) = definition_levels(rows, get);
+                column_writer.typed::<BoolType>().write_batch(&values, Some(&levels), None)?;
+            }
+            Column::Text(get) => {
+                let (values, levels) = definition_levels(rows, get);
+                let values: Vec<ByteArray> = values.into_iter().map(ByteArray::from).collect();
+                column_writer.typed::<ByteArrayType>().write_batch(&values, Some(&levels), None)?;
+            }
+        }
+        column_writer.close()?;
+    }
+    group.close()?;
+    Ok(())
+}
+
+/// A column's non-null values, and a level per row saying which rows have one
+fn definition_levels<T>(rows: &[FrameRow], get: &fn(&FrameRow) -> Option<T>) -> (Vec<T>, Vec<i16>) {
+    let mut values = Vec::with_capacity(rows.len());
+    let levels = rows
+        .iter()
+        .map(|row| match get(row) {
+            Some(value) => {
+                values.push(value);
+                1
+            }
+            None => 0,
+        })
+        .collect();
+    (values, levels)
+}
+
+fn parquet_error(e: ParquetError) -> Error {
+    Error::Io(std::io::Error::other(e))
+}
@@ -0,0 +1,248 @@
+//! Post-recording hook
+//!
+//! The postRecordingHook setting runs the user's own processing (uploads,
+//! transcodes, ...) once a recording is finalized and linked to its replay:
+//! - an `http://` or `https://` URL is POSTed a JSON HookPayload
+//! - anything else is a command template; {video}, {slp} and {recording_id}
+//!   are substituted per argument and the program runs directly (no shell),
+//!   so paths with spaces stay one argument
+//!
+//! Hooks run in the background with a timeout. Output goes to the log and the
+//! outcome is emitted as recording-hook-finished so the UI can show failures.
+
+use crate::database::RecordingRow;
+use crate::events::recording as recording_events;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+use tauri_plugin_store::StoreExt;
+
+/// Settings key for the hook (webhook URL or command template)
+const POST_RECORDING_HOOK_KEY: &str = "postRecordingHook";
+
+/// Hooks still running after this are killed (commands) or abandoned (webhooks)
+const HOOK_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Longest hook output kept in the finished event
+const MAX_EVENT_OUTPUT_CHARS: usize = 500;
+
+/// JSON body POSTed to webhooks
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookPayload {
+    pub recording_id: String,
+    pub video_path: String,
+    pub slp_path: Option<String>,
+    /// Recording length in seconds, if the recorder tracked it
+    pub duration_secs: Option<f64>,
+    pub file_size: Option<i64>,
+    pub start_time: Option<String>,
+    pub dropped_frame_estimate: Option<i64>,
+    pub longest_stall_ms: Option<i64>,
+}
+
+impl HookPayload {
+    pub fn new(recording: &RecordingRow, duration_secs: Option<f64>) -> Self {
+        Self {
+            recording_id: recording.id.clone(),
+            video_path: recording.video_path.clone().unwrap_or_default(),
+            slp_path: recording.slp_path.clone(),
+            duration_secs,
+            file_size: recording.file_size,
+            start_time: recording.start_time.clone(),
+            dropped_frame_estimate: recording.dropped_frame_estimate,
+            longest_stall_ms: recording.longest_stall_ms,
+        }
+    }
+}
+
+/// Payload for the recording-hook-finished event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookResult {
+    pub video_path: String,
+    /// "webhook" or "command"
+    pub kind: &'static str,
+    pub success: bool,
+    /// Error, or the (truncated) command output / response body
+    pub message: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+/// The configured hook, if any
+fn configured_hook(app: &tauri::AppHandle) -> Option<String> {
+    app.store("settings.json")
+        .ok()?
+        .get(POST_RECORDING_HOOK_KEY)?
+        .as_str()
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+}
+
+/// Run the post-recording hook for a finished recording, if one is configured
+/// Emits recording-hook-finished when done
+pub async fn run_post_recording_hook(app: &tauri::AppHandle, payload: HookPayload) {
+    let Some(hook) = configured_hook(app) else {
+        return;
+    };
+
+    let is_webhook = hook.starts_with("http://") || hook.starts_with("https://");
+    log::info!(
+        "🪝 Running post-recording {} for {}",
+        if is_webhook { "webhook" } else { "command" },
+        payload.video_path
+    );
+
+    let started = Instant::now();
+    let outcome = if is_webhook {
+        run_webhook(&hook, &payload).await
+    } else {
+        run_command(&hook, &payload).await
+    };
+
+    let result = HookResult {
+        video_path: payload.video_path.clone(),
+        kind: if is_webhook { "webhook" } else { "command" },
+        success: outcome.is_ok(),
+        message: match outcome {
+            Ok(output) => Some(output).filter(|o| !o.is_empty()).map(|o| truncate(&o)),
+            Err(e) => Some(truncate(&e)),
+        },
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    };
+
+    if result.success {
+        log::info!("🪝 Post-recording hook succeeded in {}ms", result.elapsed_ms);
+    } else {
+        log::warn!("🪝 Post-recording hook failed: {}", result.message.as_deref().unwrap_or_default());
+    }
+
+    if let Err(e) = app.emit(recording_events::HOOK_FINISHED, &result) {
+        log::error!("Failed to emit {} event: {:?}", recording_events::HOOK_FINISHED, e);
+    }
+}
+
+/// POST the payload, returning the response body
+async fn run_webhook(url: &str, payload: &HookPayload) -> Result<String, String> {
+    let client = reqwest::Client::builder()
+        .timeout(HOOK_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let response = client
+        .post(url)
+        .json(payload)
+        .send()
+        .await
+        .map_err(|e| format!("Webhook request to {} failed: {}", url, e))?;
+
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    log::info!("🪝 Webhook responded {}: {}", status, body.trim());
+
+    if status.is_success() {
+        Ok(body.trim().to_string())
+    } else {
+        Err(format!("Webhook responded {}: {}", status, body.trim()))
+    }
+}
+
+/// Run the command template, returning its combined output
+async fn run_command(template: &str, payload: &HookPayload) -> Result<String, String> {
+    let args: Vec<String> = split_command_line(template)
+        .iter()
+        .map(|arg| {
+            arg.replace("{video}", &payload.video_path)
+                .replace("{slp}", payload.slp_path.as_deref().unwrap_or_default())
+                .replace("{recording_id}", &payload.recording_id)
+        })
+        .collect();
+    let (program, args) = args.split_first().ok_or("Post-recording command is empty")?;
+
+    let child = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to run '{}': {}", program, e))?;
+
+    // Dropping the future on timeout kills the child
+    let output = tokio::time::timeout(HOOK_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("'{}' timed out after {}s", program, HOOK_TIMEOUT.as_secs()))?
+        .map_err(|e| format!("Failed to wait for '{}': {}", program, e))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    for line in stdout.lines() {
+        log::info!("🪝 [{}] {}", program, line);
+    }
+    for line in stderr.lines() {
+        log::warn!("🪝 [{}] {}", program, line);
+    }
+
+    let combined = format!("{}{}", stdout, stderr).trim().to_string();
+    if output.status.success() {
+        Ok(combined)
+    } else {
+        Err(format!("'{}' exited with {}: {}", program, output.status, combined))
+    }
+}
+
+/// Split a command line into arguments, honouring single and double quotes
+fn split_command_line(command: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+
+    args
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= MAX_EVENT_OUTPUT_CHARS {
+        return text.to_string();
+    }
+    let truncated: String = text.chars().take(MAX_EVENT_OUTPUT_CHARS).collect();
+    format!("{}…", truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_command_line_keeps_quoted_arguments_together() {
+        assert_eq!(
+            split_command_line(r#"rclone copy "{video}" 'nas:Melee Clips' --progress"#),
+            vec!["rclone", "copy", "{video}", "nas:Melee Clips", "--progress"]
+        );
+        assert_eq!(split_command_line(r#"script.sh "" end"#), vec!["script.sh", "", "end"]);
+    }
+}
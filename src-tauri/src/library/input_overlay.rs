@@ -0,0 +1,276 @@
+//! Controller input display burned into clips
+//!
+//! edit_recording can draw one player's controller (both sticks, the analog
+//! trigger and the buttons) into the corner of the clip it makes. The inputs
+//! come from the recording's replay (see slippi::frames), and each video
+//! frame shows the replay frame at the same moment: the game's frame -123 is
+//! where the game starts in the video, corrected by the recording's video
+//! offset. Per-set recordings use the game the clip starts in.
+//!
+//! The overlay is drawn here, as raw RGBA frames at 60 fps, and piped into
+//! the FFmpeg edit (see clip_processor::OverlayStream). Recordings without a
+//! replay get no overlay.
+
+use crate::commands::errors::Error;
+use crate::commands::settings::my_connect_codes;
+use crate::database::{self, RecordingRow};
+use crate::clip_processor::OverlayStream;
+use crate::slippi::frames::{self, FrameInputs, FrameSelection, FIRST_FRAME};
+use crate::slippi::game_start::read_game_start;
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+
+/// Melee's frame rate, and the overlay's
+const FPS: u32 = 60;
+/// Overlay height as a share of the video's
+const HEIGHT_SHARE: u32 = 5;
+/// Smallest overlay height in pixels
+const MIN_HEIGHT: u32 = 60;
+/// The layout below is in units of a 200 x 100 canvas
+const CANVAS_HEIGHT: f32 = 100.0;
+
+/// Button bits of FrameInputs::physical_buttons
+const BUTTON_Z: u16 = 0x0010;
+const BUTTON_R: u16 = 0x0020;
+const BUTTON_L: u16 = 0x0040;
+const BUTTON_A: u16 = 0x0100;
+const BUTTON_B: u16 = 0x0200;
+const BUTTON_X: u16 = 0x0400;
+const BUTTON_Y: u16 = 0x0800;
+const BUTTON_START: u16 = 0x1000;
+
+type Rgba = [u8; 4];
+
+const BACKGROUND: Rgba = [0, 0, 0, 150];
+const GATE: Rgba = [200, 200, 200, 200];
+const STICK: Rgba = [240, 240, 240, 255];
+const C_STICK: Rgba = [250, 210, 40, 255];
+const GREY: Rgba = [210, 210, 210, 255];
+const GREEN: Rgba = [40, 200, 90, 255];
+const RED: Rgba = [220, 50, 50, 255];
+const PURPLE: Rgba = [140, 80, 220, 255];
+/// Alpha of a button that isn't pressed
+const RELEASED_ALPHA: u8 = 70;
+
+/// Whose inputs to draw, and where their game starts in the video
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlaySource {
+    pub slp_path: PathBuf,
+    /// Controller port (0-indexed)
+    pub port: u8,
+    /// Seconds into the video of the game's first frame
+    pub game_start_secs: f64,
+}
+
+/// The replay and player to draw for a clip of `row` starting `clip_start`
+/// seconds into its video. `port` defaults to the user's (by connect code),
+/// then the first human player. None when the recording has no replay
+pub fn overlay_source(
+    app: &tauri::AppHandle,
+    conn: &Connection,
+    row: &RecordingRow,
+    port: Option<u8>,
+    clip_start: f64,
+) -> Result<Option<OverlaySource>, Error> {
+    let video_offset_secs = row.video_offset_ms.map_or(0.0, |ms| ms as f64 / 1000.0);
+    let games = database::get_recording_games(conn, &row.id)?;
+    // The last game started by the clip's start, or the first
+    let Some(game) = games
+        .iter()
+        .rev()
+        .find(|game| game.offset_secs - video_offset_secs <= clip_start)
+        .or(games.first())
+    else {
+        return Ok(None);
+    };
+    let slp_path = PathBuf::from(&game.slp_path);
+    let Some(game_start) = read_game_start(&slp_path) else {
+        log::warn!("No input overlay: replay {} can't be read", game.slp_path);
+        return Ok(None);
+    };
+
+    let port = match port {
+        Some(port) if game_start.players.iter().any(|p| p.port == port) => port,
+        Some(port) => return Err(Error::InvalidPath(format!("No player on port {} in {}", port + 1, game.slp_path))),
+        None => {
+            let my_codes = my_connect_codes(app);
+            let mine = game_start.players.iter().find(|p| {
+                p.connect_code.as_ref().is_some_and(|code| my_codes.iter().any(|c| c.eq_ignore_ascii_case(code)))
+            });
+            match mine.or_else(|| game_start.players.iter().find(|p| !p.is_cpu)).or(game_start.players.first()) {
+                Some(player) => player.port,
+                None => return Ok(None),
+            }
+        }
+    };
+    Ok(Some(OverlaySource { slp_path, port, game_start_secs: game.offset_secs - video_offset_secs }))
+}
+
+/// One player's inputs, by frame from FIRST_FRAME
+struct InputTrack(Vec<Option<FrameInputs>>);
+
+impl InputTrack {
+    fn read(slp_path: &Path, port: u8) -> Result<Self, Error> {
+        let selection = FrameSelection { ports: vec![port], every_nth: 1 };
+        let mut inputs = Vec::new();
+        frames::read_frames(slp_path, &selection, |row| {
+            let index = (row.frame - FIRST_FRAME) as usize;
+            if inputs.len() <= index {
+                inputs.resize(index + 1, None);
+            }
+            inputs[index] = Some(row.inputs);
+            Ok::<_, std::io::Error>(())
+        })
+        .map_err(|e| Error::SlpParse { reason: e.to_string() })?;
+        Ok(Self(inputs))
+    }
+
+    /// Inputs shown `secs` into the video (None before or after the game)
+    fn at(&self, secs: f64, game_start_secs: f64) -> Option<&FrameInputs> {
+        let index = ((secs - game_start_secs) * f64::from(FPS)).round();
+        if index < 0.0 {
+            return None;
+        }
+        self.0.get(index as usize)?.as_ref()
+    }
+}
+
+/// Overlay frames for a clip `clip_length` seconds long, starting `clip_start`
+/// into a video `video_height` pixels high (after cropping)
+pub fn overlay_stream(
+    source: &OverlaySource,
+    clip_start: f64,
+    clip_length: f64,
+    video_height: u32,
+) -> Result<OverlayStream, Error> {
+    let track = InputTrack::read(&source.slp_path, source.port)?;
+    let height = ((video_height / HEIGHT_SHARE).max(MIN_HEIGHT) / 2) * 2;
+    let width = height * 2;
+    let count = (clip_length * f64::from(FPS)).ceil().max(0.0) as u64;
+    let game_start_secs = source.game_start_secs;
+    log::info!(
+        "🎮 Drawing port {} inputs from {} ({}x{}, {} frames)",
+        source.port + 1,
+        source.slp_path.display(),
+        width,
+        height,
+        count
+    );
+
+    let frames = (0..count).map(move |i| {
+        let secs = clip_start + i as f64 / f64::from(FPS);
+        render(track.at(secs, game_start_secs), width, height)
+    });
+    Ok(OverlayStream { width, height, fps: FPS, frames: Box::new(frames) })
+}
+
+/// One overlay frame (transparent without inputs)
+fn render(inputs: Option<&FrameInputs>, width: u32, height: u32) -> Vec<u8> {
+    let mut canvas = Canvas { width, height, scale: height as f32 / CANVAS_HEIGHT, pixels: vec![0; (width * height * 4) as usize] };
+    let Some(inputs) = inputs else {
+        return canvas.pixels;
+    };
+    let pressed = |bit: u16, color: Rgba| {
+        if inputs.physical_buttons & bit != 0 {
+            color
+        } else {
+            [color[0], color[1], color[2], RELEASED_ALPHA]
+        }
+    };
+
+    canvas.rect(0.0, 0.0, 200.0, 100.0, BACKGROUND);
+    // Control stick
+    canvas.ring(35.0, 50.0, 28.0, 2.0, GATE);
+    canvas.circle(35.0 + inputs.joystick_x * 22.0, 50.0 - inputs.joystick_y * 22.0, 8.0, STICK);
+    // C-stick
+    canvas.ring(95.0, 62.0, 18.0, 2.0, [C_STICK[0], C_STICK[1], C_STICK[2], GATE[3]]);
+    canvas.circle(95.0 + inputs.cstick_x * 13.0, 62.0 - inputs.cstick_y * 13.0, 6.0, C_STICK);
+    // Analog trigger, filled from the bottom, and the digital presses
+    canvas.rect(118.0, 15.0, 8.0, 70.0, [GREY[0], GREY[1], GREY[2], RELEASED_ALPHA]);
+    let fill = 70.0 * inputs.trigger.clamp(0.0, 1.0);
+    canvas.rect(118.0, 85.0 - fill, 8.0, fill, GREY);
+    canvas.rect(131.0, 15.0, 8.0, 8.0, pressed(BUTTON_L, GREY));
+    canvas.rect(131.0, 27.0, 8.0, 8.0, pressed(BUTTON_R, GREY));
+    // Face buttons, laid out and colored as on the controller
+    canvas.circle(172.0, 58.0, 13.0, pressed(BUTTON_A, GREEN));
+    canvas.circle(151.0, 74.0, 8.0, pressed(BUTTON_B, RED));
+    canvas.circle(192.0, 48.0, 6.0, pressed(BUTTON_X, GREY));
+    canvas.circle(168.0, 36.0, 6.0, pressed(BUTTON_Y, GREY));
+    canvas.rect(180.0, 14.0, 16.0, 7.0, pressed(BUTTON_Z, PURPLE));
+    canvas.circle(148.0, 44.0, 4.0, pressed(BUTTON_START, GREY));
+    canvas.pixels
+}
+
+/// RGBA pixels drawn on in canvas units; shapes replace what's under them
+struct Canvas {
+    width: u32,
+    height: u32,
+    /// Pixels per unit
+    scale: f32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    /// Color every pixel whose center is `inside` the shape, within a box
+    fn fill(&mut self, (x0, y0, x1, y1): (f32, f32, f32, f32), color: Rgba, inside: impl Fn(f32, f32) -> bool) {
+        let to_px = |v: f32, max: u32| ((v * self.scale).max(0.0) as u32).min(max);
+        for py in to_px(y0, self.height)..to_px(y1 + 1.0, self.height) {
+            for px in to_px(x0, self.width)..to_px(x1 + 1.0, self.width) {
+                let (x, y) = ((px as f32 + 0.5) / self.scale, (py as f32 + 0.5) / self.scale);
+                if inside(x, y) {
+                    let at = ((py * self.width + px) * 4) as usize;
+                    self.pixels[at..at + 4].copy_from_slice(&color);
+                }
+            }
+        }
+    }
+
+    fn rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: Rgba) {
+        self.fill((x, y, x + w, y + h), color, |px, py| px >= x && px < x + w && py >= y && py < y + h);
+    }
+
+    fn circle(&mut self, cx: f32, cy: f32, r: f32, color: Rgba) {
+        self.fill((cx - r, cy - r, cx + r, cy + r), color, |x, y| (x - cx).powi(2) + (y - cy).powi(2) <= r * r);
+    }
+
+    /// A circle's outline, `thickness` units wide inside radius `r`
+    fn ring(&mut self, cx: f32, cy: f32, r: f32, thickness: f32, color: Rgba) {
+        let inner = r - thickness;
+        self.fill((cx - r, cy - r, cx + r, cy + r), color, |x, y| {
+            let d = (x - cx).powi(2) + (y - cy).powi(2);
+            d <= r * r && d >= inner * inner
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(frame: &[u8], width: u32, x: u32, y: u32) -> Rgba {
+        let at = ((y * width + x) * 4) as usize;
+        frame[at..at + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_overlay_follows_the_replay_frame_and_inputs() {
+        // Frame -123 shows 2s into the video, and the stick is held right on frame -63
+        let mut inputs = vec![Some(FrameInputs::default()); 120];
+        inputs[60] = Some(FrameInputs { joystick_x: 1.0, physical_buttons: BUTTON_A, ..Default::default() });
+        let track = InputTrack(inputs);
+        assert_eq!(track.at(1.9, 2.0), None);
+        assert_eq!(track.at(3.0, 2.0).map(|i| i.joystick_x), Some(1.0));
+        assert_eq!(track.at(4.0, 2.0), None);
+
+        // 1:1 scale: units are pixels
+        let (width, height) = (200, 100);
+        assert!(render(None, width, height).iter().all(|&v| v == 0));
+        let held = render(track.at(3.0, 2.0), width, height);
+        assert_eq!(pixel(&held, width, 57, 50), STICK);
+        assert_eq!(pixel(&held, width, 35, 50), BACKGROUND);
+        assert_eq!(pixel(&held, width, 172, 58), GREEN);
+        let idle = render(track.at(2.0, 2.0), width, height);
+        assert_eq!(pixel(&idle, width, 35, 50), STICK);
+        assert_eq!(pixel(&idle, width, 172, 58)[3], RELEASED_ALPHA);
+    }
+}
@@ -0,0 +1,105 @@
+//! Background probing of video files
+//!
+//! After a sync, videos that haven't been probed yet are queued here: ffprobe
+//! reads their dimensions and length, and FFmpeg's volumedetect measures 10
+//! seconds from the middle to tell whether the game audio was captured. The
+//! results are stored on the recordings, so duplicate detection and the
+//! "silent" badge work from the database alone. Probing waits at the work
+//! gate while recording, and is skipped when FFmpeg isn't installed.
+
+use crate::app_state::AppState;
+use crate::clip_processor;
+use crate::commands::errors::Error;
+use crate::database::{self, RecordingRow};
+use crate::metrics;
+use crate::work_gate::JobKind;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::Manager;
+
+/// Length of the audio sample taken from the middle of a video
+const AUDIO_SAMPLE_SECS: f64 = 10.0;
+
+/// Whether a probe pass is running; sync and refresh can both start one
+static PROBING: AtomicBool = AtomicBool::new(false);
+
+/// Probe pending videos in the background, unless a pass is already running
+pub fn spawn_probe(app: &tauri::AppHandle) {
+    if PROBING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = metrics::job("media_probe", probe_pending(&app)).await {
+            log::warn!("Failed to probe videos: {}", e);
+        }
+        PROBING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Probe every video that hasn't been probed yet
+/// Returns how many were probed
+async fn probe_pending(app: &tauri::AppHandle) -> Result<usize, Error> {
+    // Don't download FFmpeg just for this; the clip tools will
+    if !ffmpeg_sidecar::ffprobe::ffprobe_is_installed() {
+        return Ok(0);
+    }
+
+    let state = app.state::<AppState>();
+    let pending: Vec<RecordingRow> = database::get_all_recordings(&state.database.connection())
+        .map_err(Error::from)?
+        .into_iter()
+        .filter(|row| row.video_duration_secs.is_none() || row.has_audio.is_none())
+        .filter(|row| row.video_path.as_deref().is_some_and(|path| Path::new(path).exists()))
+        .collect();
+    if pending.is_empty() {
+        return Ok(0);
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let mut probed = 0;
+        for row in pending {
+            let _permit = tauri::async_runtime::block_on(state.work_gate.acquire(JobKind::MediaProbe));
+            match probe_one(&state, &row) {
+                Ok(()) => probed += 1,
+                Err(e) => log::debug!("Failed to probe {}: {}", row.id, e),
+            }
+        }
+        if probed > 0 {
+            log::info!("🎞️ Probed {} video(s)", probed);
+        }
+        probed
+    })
+    .await
+    .map_err(|e| Error::RecordingFailed(format!("Probe task failed: {}", e)))
+}
+
+/// Store a video's dimensions, length and audio level, whichever the row lacks
+pub(super) fn probe_one(state: &AppState, row: &RecordingRow) -> Result<(), Error> {
+    let Some(video_path) = row.video_path.as_deref() else { return Ok(()) };
+
+    let duration_secs = match row.video_duration_secs {
+        Some(duration_secs) => duration_secs,
+        None => {
+            let probe = clip_processor::probe_video(video_path)?;
+            database::set_recording_video_info(
+                &state.database.connection(),
+                &row.id,
+                Some(probe.width as i64),
+                Some(probe.height as i64),
+                Some(probe.duration_secs),
+            )
+            .map_err(Error::from)?;
+            probe.duration_secs
+        }
+    };
+
+    if row.has_audio.is_none() {
+        let audio = clip_processor::probe_audio(video_path, duration_secs, AUDIO_SAMPLE_SECS)?;
+        database::set_recording_audio_info(&state.database.connection(), &row.id, Some(audio.has_audio), audio.mean_volume_db)
+            .map_err(Error::from)?;
+    }
+    Ok(())
+}
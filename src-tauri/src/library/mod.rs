@@ -3,10 +3,71 @@
 //! This module handles scanning, matching, and managing video recordings
 //! and their associated Slippi replay files.
 
+mod analysis_cache;
+mod archive;
+mod bundle;
+mod duplicates;
+mod external_stats;
+mod frame_export;
+mod hooks;
+mod input_overlay;
+mod media_info;
+mod naming;
+mod path_health;
 mod recordings;
+mod share;
+mod sidecar;
+mod stable_ids;
+mod storage;
 mod sync;
 mod thumbnails;
 
+pub use analysis_cache::{
+    get_cache_directory as get_analysis_cache_directory, invalidate_analysis, read_cached_analysis,
+    write_cached_analysis,
+};
+pub use archive::{archive_candidates, archive_recordings, ArchiveCandidate, ArchiveReport};
+pub use duplicates::{copies_to_delete, find_duplicates, DuplicateResolution, DuplicateSet};
+pub use external_stats::{import_external_stats, ExternalImportReport, ExternalStatsSource};
+pub use bundle::{export_recording_bundle, import_recording_bundle};
+pub use frame_export::{export_frames, FrameExport, FrameExportFormat};
+pub use input_overlay::{overlay_source, overlay_stream};
+pub use media_info::spawn_probe as spawn_media_probe;
+pub use naming::{
+    defer as defer_rename, is_safe_name, rename_deferred, template as recording_name_template, NameFields,
+    DEFAULT_NAME_TEMPLATE,
+};
+pub use path_health::{ensure_writable, PathProblem};
 pub use recordings::get_recording_directory;
-pub use sync::sync_recordings_cache;
+pub use share::{export_anonymized_replay, AnonymizedReplay};
+pub use sidecar::{enabled as sidecar_enabled, sidecar_path, write_for_recording as write_sidecar};
+pub use stable_ids::{choose_recording_id, replay_id_for};
+pub use storage::{clips_added, clips_cap_job, storage_report, StorageReport};
+pub use hooks::{run_post_recording_hook, HookPayload};
+pub use sync::{get_slippi_directory, index_recording, refresh_recording, sync_job, sync_recordings_cache, SYNC_JOB};
 
+
+/// File types the library indexes as videos: what the recorder writes
+/// (recordingContainer) and what OBS can be set to
+pub const VIDEO_EXTENSIONS: [&str; 5] = ["mp4", "mkv", "mov", "webm", "avi"];
+
+/// Whether a file's extension is one of `extensions` (any case)
+pub fn has_extension(path: &std::path::Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+}
+
+/// Whether the library indexes a file as a video
+pub fn is_video_file(path: &std::path::Path) -> bool {
+    has_extension(path, &VIDEO_EXTENSIONS)
+}
+
+/// Extension of a clip cut from `source`: stream copies keep the source's
+/// container, since not every codec fits in an MP4
+pub fn clip_extension(source: &std::path::Path) -> String {
+    match source.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if is_video_file(source) => ext.to_ascii_lowercase(),
+        _ => "mp4".to_string(),
+    }
+}
@@ -0,0 +1,263 @@
+//! Recording file names from a template
+//!
+//! With renameRecordings on, a recording is saved under its usual name
+//! (`Game_<timestamp>`) and renamed by recordingNameTemplate once its game's
+//! stats are saved, since only then are the characters and codes known.
+//! Only recordings made since the app started are renamed, never older ones
+//! being reparsed. Thumbnails are named by recording id, so what moves is the
+//! video, its sidecar, the row's video_path and any clip markers.
+
+use crate::app_state::AppState;
+use crate::commands::errors::Error;
+use crate::database;
+use crate::library::sidecar;
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+use tauri_plugin_store::StoreExt;
+
+/// Settings key turning renaming on
+const RENAME_RECORDINGS_KEY: &str = "renameRecordings";
+/// Settings key for the template
+const NAME_TEMPLATE_KEY: &str = "recordingNameTemplate";
+/// Template used when the setting is empty
+pub const DEFAULT_NAME_TEMPLATE: &str = "{date}_{my_char}_vs_{opp_char}_{opp_code}";
+/// Longest file name (without extension) a template makes
+const MAX_STEM_CHARS: usize = 120;
+/// Names Windows keeps for devices, whatever the extension
+const RESERVED_STEMS: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1",
+    "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Characters Windows refuses in file names (`#` is handled apart)
+const UNSAFE_CHARS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// What a template's placeholders are filled with; None leaves them empty
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NameFields {
+    /// The provisional name, `{original}`
+    pub original: String,
+    /// `{date}` (YYYY-MM-DD)
+    pub date: Option<String>,
+    /// `{time}` (HHMMSS)
+    pub time: Option<String>,
+    pub my_char: Option<String>,
+    pub opp_char: Option<String>,
+    pub my_code: Option<String>,
+    pub opp_code: Option<String>,
+    pub stage: Option<String>,
+    /// `{result}`, "win" or "loss"
+    pub result: Option<String>,
+}
+
+/// The template to rename new recordings by, None while renaming is off
+pub fn template(app: &tauri::AppHandle) -> Option<String> {
+    let store = app.store("settings.json").ok()?;
+    if !store.get(RENAME_RECORDINGS_KEY).and_then(|v| v.as_bool()).unwrap_or(false) {
+        return None;
+    }
+    let template = store.get(NAME_TEMPLATE_KEY).and_then(|v| v.as_str().map(|s| s.trim().to_string()));
+    Some(template.filter(|t| !t.is_empty()).unwrap_or_else(|| DEFAULT_NAME_TEMPLATE.to_string()))
+}
+
+/// Fill in a template and make it a valid file name (without extension).
+/// None when nothing is left of it
+pub fn render(template: &str, fields: &NameFields) -> Option<String> {
+    let values = [
+        ("{original}", Some(fields.original.as_str())),
+        ("{date}", fields.date.as_deref()),
+        ("{time}", fields.time.as_deref()),
+        ("{my_char}", fields.my_char.as_deref()),
+        ("{opp_char}", fields.opp_char.as_deref()),
+        ("{my_code}", fields.my_code.as_deref()),
+        ("{opp_code}", fields.opp_code.as_deref()),
+        ("{stage}", fields.stage.as_deref()),
+        ("{result}", fields.result.as_deref()),
+    ];
+    let mut name = template.to_string();
+    for (placeholder, value) in values {
+        name = name.replace(placeholder, value.unwrap_or(""));
+    }
+    sanitize(&name)
+}
+
+/// A name safe on every platform: no path separators, characters Windows
+/// refuses or `#` (which breaks asset URLs), no runs of separators left by
+/// empty placeholders, no trailing dots or spaces and no device names
+fn sanitize(name: &str) -> Option<String> {
+    let mut cleaned = String::with_capacity(name.len());
+    for c in name.chars() {
+        let c = match c {
+            '#' => '-',
+            c if UNSAFE_CHARS.contains(&c) => '_',
+            c if c.is_control() => continue,
+            c => c,
+        };
+        // "a__b" from an empty placeholder reads as "a_b"
+        if matches!(c, '_' | '-' | ' ') && cleaned.ends_with(c) {
+            continue;
+        }
+        cleaned.push(c);
+    }
+    let trimmed: String = cleaned
+        .trim_matches(|c: char| matches!(c, '_' | '-' | ' ' | '.'))
+        .chars()
+        .take(MAX_STEM_CHARS)
+        .collect();
+    let trimmed = trimmed.trim_end_matches(['.', ' ']).to_string();
+    if trimmed.is_empty() {
+        return None;
+    }
+    if RESERVED_STEMS.iter().any(|r| trimmed.eq_ignore_ascii_case(r)) {
+        return Some(format!("{}_", trimmed));
+    }
+    Some(trimmed)
+}
+
+/// Whether a name typed for a file (without extension) can be used as is:
+/// the same rules as sanitize, but refusing instead of fixing
+pub fn is_safe_name(name: &str) -> bool {
+    !name.trim().is_empty()
+        && name.chars().count() <= MAX_STEM_CHARS
+        && !name.chars().any(|c| c == '#' || c.is_control() || UNSAFE_CHARS.contains(&c))
+        && !name.starts_with('.')
+        && !name.ends_with(['.', ' '])
+        && !RESERVED_STEMS.iter().any(|r| name.eq_ignore_ascii_case(r))
+}
+
+/// `<dir>/<stem>.<ext>`, or with _2, _3... if that (or its sidecar) is taken
+fn free_path(dir: &Path, stem: &str, extension: &str, current: &Path) -> PathBuf {
+    let taken = |path: &Path| path != current && (path.exists() || sidecar::sidecar_path(path).exists());
+    let mut candidate = dir.join(format!("{}.{}", stem, extension));
+    let mut suffix = 2;
+    while taken(&candidate) {
+        candidate = dir.join(format!("{}_{}.{}", stem, suffix, extension));
+        suffix += 1;
+    }
+    candidate
+}
+
+/// Remember a just-finished recording to rename once its stats are saved
+pub fn defer(state: &AppState, video_path: &str) {
+    if let Ok(mut pending) = state.pending_renames.lock() {
+        pending.insert(crate::paths::normalize(video_path));
+    }
+}
+
+/// Rename a recording waiting to be renamed (see defer) by the template.
+/// The row is updated in the same step; if that fails the file is moved
+/// back. Returns the new path, None if it wasn't waiting or keeps its name
+pub fn rename_deferred(
+    state: &AppState,
+    conn: &Connection,
+    recording_id: &str,
+    template: &str,
+    fields: impl FnOnce(&str) -> NameFields,
+) -> Result<Option<String>, Error> {
+    let Some(row) = database::get_recording_by_id(conn, recording_id)? else {
+        return Ok(None);
+    };
+    let Some(video_path) = row.video_path else {
+        return Ok(None);
+    };
+    let waiting = state
+        .pending_renames
+        .lock()
+        .map(|mut pending| pending.remove(&crate::paths::normalize(&video_path)))
+        .unwrap_or(false);
+    if !waiting {
+        return Ok(None);
+    }
+
+    let old_path = PathBuf::from(&video_path);
+    let (Some(dir), Some(original)) = (old_path.parent(), old_path.file_stem().and_then(|s| s.to_str())) else {
+        return Ok(None);
+    };
+    let Some(stem) = render(template, &fields(original)) else {
+        log::warn!("Recording name template {:?} left nothing of {}, keeping it", template, video_path);
+        return Ok(None);
+    };
+    let extension = old_path.extension().and_then(|e| e.to_str()).unwrap_or("mp4");
+    let new_path = free_path(dir, &stem, extension, &old_path);
+    if new_path == old_path {
+        return Ok(None);
+    }
+    let new_path_str = new_path.to_string_lossy().to_string();
+
+    std::fs::rename(&old_path, &new_path)?;
+    if let Err(e) = database::set_recording_video_path(conn, recording_id, &new_path_str) {
+        if let Err(undo) = std::fs::rename(&new_path, &old_path) {
+            log::error!("Failed to move {} back to {}: {}", new_path_str, video_path, undo);
+        }
+        return Err(Error::database("Failed to save the renamed recording", e));
+    }
+
+    let old_sidecar = sidecar::sidecar_path(&old_path);
+    if old_sidecar.exists() {
+        if let Err(e) = std::fs::rename(&old_sidecar, sidecar::sidecar_path(&new_path)) {
+            log::warn!("Failed to rename sidecar {}: {}", old_sidecar.display(), e);
+        }
+    }
+    // Markers match recordings by file name
+    if let Ok(mut markers) = state.clip_markers.lock() {
+        for marker in markers.iter_mut() {
+            if Path::new(&marker.recording_file).file_stem() == old_path.file_stem() {
+                marker.recording_file = new_path_str.clone();
+            }
+        }
+    }
+
+    log::info!("📝 Renamed {} to {}", video_path, new_path_str);
+    Ok(Some(new_path_str))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_templates_make_safe_names() {
+        let fields = NameFields {
+            original: "Game_20240601T120000".into(),
+            date: Some("2024-06-01".into()),
+            my_char: Some("Fox".into()),
+            opp_char: Some("Mr. Game & Watch".into()),
+            opp_code: Some("ABC#123".into()),
+            stage: Some("Pokémon Stadium".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            render(DEFAULT_NAME_TEMPLATE, &fields).as_deref(),
+            Some("2024-06-01_Fox_vs_Mr. Game & Watch_ABC-123")
+        );
+        // Missing values don't leave doubled or trailing separators
+        assert_eq!(render("{date}_{my_code}_{my_char}_{result}", &fields).as_deref(), Some("2024-06-01_Fox"));
+        assert_eq!(render("{original} <a/b>?", &fields).as_deref(), Some("Game_20240601T120000 _a_b"));
+        assert_eq!(render("{my_char}_{stage}", &fields).as_deref(), Some("Fox_Pokémon Stadium"));
+        assert_eq!(render("{result}_{time}", &fields), None);
+        assert_eq!(render("nul", &fields).as_deref(), Some("nul_"));
+    }
+
+    #[test]
+    fn test_is_safe_name() {
+        assert!(is_safe_name("Fox vs Marth - game 3"));
+        assert!(is_safe_name("Pokémon Stadium_4stock"));
+        for name in ["", "  ", "a/b", "..\\up", "ABC#123", "what?", "trailing.", "trailing ", ".hidden", "COM1", "a\tb"] {
+            assert!(!is_safe_name(name), "{:?} passed", name);
+        }
+        assert!(!is_safe_name(&"x".repeat(MAX_STEM_CHARS + 1)));
+    }
+
+    #[test]
+    fn test_free_path_skips_taken_names() {
+        let dir = std::env::temp_dir().join(format!("buckwheat-naming-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let current = dir.join("Game_1.mp4");
+        std::fs::write(dir.join("Fox.mp4"), b"").unwrap();
+        std::fs::write(dir.join("Fox_2.buckwheat.json"), b"").unwrap();
+
+        assert_eq!(free_path(&dir, "Fox", "mp4", &current), dir.join("Fox_3.mp4"));
+        assert_eq!(free_path(&dir, "Falco", "mp4", &current), dir.join("Falco.mp4"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
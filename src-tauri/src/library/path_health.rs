@@ -0,0 +1,194 @@
+//! Checks on the folders named in settings
+//!
+//! recordingPath often points at an external drive. Unplugged, create_dir_all
+//! either fails with an opaque error or (under /Volumes or /media) quietly
+//! recreates the folder on the system disk, and a sync of that empty folder
+//! looks like a library with nothing in it. The recording and Slippi folders
+//! are checked before they're handed out; a problem is returned as
+//! Error::PathUnavailable and sent as settings-path-unavailable for the UI.
+
+use crate::commands::errors::Error;
+use crate::events::settings as settings_events;
+use serde::Serialize;
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+/// Why a folder from settings can't be used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathProblem {
+    /// The drive or share it's on isn't connected
+    VolumeNotMounted,
+    /// It (or, if missing, the folder it would be created in) can't be written to
+    NotWritable,
+    /// A file is in the way
+    NotADirectory,
+}
+
+impl std::fmt::Display for PathProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::VolumeNotMounted => write!(f, "The drive this folder is on isn't connected"),
+            Self::NotWritable => write!(f, "Buckwheat can't write to this folder"),
+            Self::NotADirectory => write!(f, "This path is a file, not a folder"),
+        }
+    }
+}
+
+/// A usable folder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirState {
+    Ready,
+    /// Doesn't exist yet, but can be created
+    Missing,
+}
+
+/// Payload of settings-path-unavailable
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PathUnavailable<'a> {
+    /// Settings key naming the folder
+    setting: &'static str,
+    path: &'a str,
+    problem: PathProblem,
+}
+
+/// Classify `path`. With `need_write`, an existing folder is checked by
+/// writing a file into it, and a missing one by its nearest existing parent
+pub fn check_dir(path: &Path, need_write: bool) -> Result<DirState, PathProblem> {
+    if volume_root(path).is_some_and(|root| !is_mounted(&root)) {
+        return Err(PathProblem::VolumeNotMounted);
+    }
+    if path.is_dir() {
+        return if !need_write || can_write(path) { Ok(DirState::Ready) } else { Err(PathProblem::NotWritable) };
+    }
+    if path.exists() {
+        return Err(PathProblem::NotADirectory);
+    }
+    match path.ancestors().skip(1).find(|ancestor| ancestor.exists()) {
+        Some(parent) if !parent.is_dir() => Err(PathProblem::NotADirectory),
+        Some(parent) if need_write && !can_write(parent) => Err(PathProblem::NotWritable),
+        Some(_) => Ok(DirState::Missing),
+        // Not even the root exists: a drive letter with no drive behind it
+        None => Err(PathProblem::VolumeNotMounted),
+    }
+}
+
+/// Check a folder the app writes to, creating it if it's missing
+pub fn ensure_writable(app: &AppHandle, setting: &'static str, path: &Path) -> Result<(), Error> {
+    let problem = match check_dir(path, true) {
+        Ok(DirState::Ready) => return Ok(()),
+        Ok(DirState::Missing) => match std::fs::create_dir_all(path) {
+            Ok(()) => {
+                log::info!("📁 Created {} folder: {}", setting, path.display());
+                return Ok(());
+            }
+            Err(e) => {
+                log::warn!("Failed to create {}: {}", path.display(), e);
+                PathProblem::NotWritable
+            }
+        },
+        Err(problem) => problem,
+    };
+    Err(unavailable(app, setting, path, problem))
+}
+
+/// Check a folder the app only reads. A missing folder is fine (Slippi
+/// creates it with the first replay), one on a missing drive isn't
+pub fn ensure_readable(app: &AppHandle, setting: &'static str, path: &Path) -> Result<(), Error> {
+    match check_dir(path, false) {
+        Ok(_) => Ok(()),
+        Err(problem) => Err(unavailable(app, setting, path, problem)),
+    }
+}
+
+/// Tell the UI and build the error
+fn unavailable(app: &AppHandle, setting: &'static str, path: &Path, problem: PathProblem) -> Error {
+    let path = path.to_string_lossy().to_string();
+    log::warn!("⚠️ {} is unavailable ({}): {}", setting, problem, path);
+    let payload = PathUnavailable { setting, path: &path, problem };
+    if let Err(e) = app.emit(settings_events::PATH_UNAVAILABLE, payload) {
+        log::error!("Failed to emit {} event: {:?}", settings_events::PATH_UNAVAILABLE, e);
+    }
+    Error::PathUnavailable { setting, path, problem }
+}
+
+/// Where the volume holding `path` is mounted, for paths on drives that come
+/// and go: a drive letter or share on Windows, /Volumes/<name>,
+/// /media/<user>/<name>, /run/media/<user>/<name> or /mnt/<name>
+fn volume_root(path: &Path) -> Option<PathBuf> {
+    let mut components = path.components();
+    match components.next()? {
+        Component::Prefix(prefix) => Some(Path::new(prefix.as_os_str()).join(std::path::MAIN_SEPARATOR_STR)),
+        Component::RootDir => {
+            let names: Vec<&OsStr> = components.take(4).map(|c| c.as_os_str()).collect();
+            let depth = match names.first()?.to_str()? {
+                "Volumes" | "mnt" => 2,
+                "media" => 3,
+                "run" if names.get(1).is_some_and(|name| *name == "media") => 4,
+                _ => return None,
+            };
+            let names = names.get(..depth)?;
+            Some(names.iter().fold(PathBuf::from("/"), |root, name| root.join(name)))
+        }
+        _ => None,
+    }
+}
+
+/// Whether something is mounted at `root`. On Unix an unmounted mount point
+/// can still exist as an empty folder on the parent's device
+fn is_mounted(root: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let parent = root.parent().unwrap_or(root);
+        match (std::fs::metadata(root), std::fs::metadata(parent)) {
+            (Ok(root), Ok(parent)) => root.dev() != parent.dev(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        root.exists()
+    }
+}
+
+/// Whether a file can be created in `dir` (permissions alone don't say on
+/// Windows or on read-only mounts)
+fn can_write(dir: &Path) -> bool {
+    let probe = dir.join(format!(".buckwheat-write-check-{}", std::process::id()));
+    match std::fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_dir_tells_missing_from_unusable() {
+        let (root, dir) = crate::paths::long_test_dir("path_health");
+        assert_eq!(check_dir(&dir, true), Ok(DirState::Ready));
+        assert!(std::fs::read_dir(&dir).unwrap().next().is_none(), "write check left a file behind");
+        assert_eq!(check_dir(&dir.join("Recordings").join("2024"), true), Ok(DirState::Missing));
+
+        let file = dir.join("recordings.txt");
+        std::fs::write(&file, b"").unwrap();
+        assert_eq!(check_dir(&file, true), Err(PathProblem::NotADirectory));
+        assert_eq!(check_dir(&file.join("Recordings"), true), Err(PathProblem::NotADirectory));
+
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(volume_root(Path::new("/Volumes/Games/Buckwheat")), Some(PathBuf::from("/Volumes/Games")));
+        assert_eq!(volume_root(Path::new("/run/media/me/usb/clips")), Some(PathBuf::from("/run/media/me/usb")));
+        assert_eq!(volume_root(Path::new("/media/me")), None);
+        assert_eq!(volume_root(Path::new("/home/me/Videos")), None);
+        assert_eq!(check_dir(Path::new("/Volumes/buckwheat-test-unplugged/Recordings"), true), Err(PathProblem::VolumeNotMounted));
+    }
+}
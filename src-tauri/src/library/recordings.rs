@@ -1,6 +1,7 @@
 //! Recording file scanning and matching
 
 use crate::commands::errors::Error;
+use std::path::Path;
 use tauri::Manager;
 
 /// Get the recording directory from settings or use default
@@ -15,9 +16,7 @@ pub async fn get_recording_directory(app: &tauri::AppHandle) -> Result<String, E
         if let Some(path) = value.as_str() {
             if !path.is_empty() {
                 let path_string = path.to_string();
-                std::fs::create_dir_all(&path_string).map_err(|e| {
-                    Error::RecordingFailed(format!("Failed to create directory: {}", e))
-                })?;
+                super::path_health::ensure_writable(app, "recordingPath", Path::new(&path_string))?;
                 return Ok(path_string);
             }
         }
@@ -30,9 +29,7 @@ pub async fn get_recording_directory(app: &tauri::AppHandle) -> Result<String, E
         .map_err(|e| Error::InitializationError(format!("Failed to get videos directory: {}", e)))?
         .join("Buckwheat");
     
-    std::fs::create_dir_all(&default_dir).map_err(|e| {
-        Error::RecordingFailed(format!("Failed to create default directory: {}", e))
-    })?;
+    super::path_health::ensure_writable(app, "recordingPath", &default_dir)?;
     
     default_dir
         .to_str()
@@ -0,0 +1,57 @@
+//! Anonymized replays for sharing
+//!
+//! export_anonymized_replay writes a copy of a recording's replay with the
+//! players' names and connect codes replaced (see slippi::anonymize) to the
+//! temp folder. The frontend uploads it through the same signed-URL flow as
+//! public clips for a share link, then removes it with delete_temp_file.
+
+use crate::app_state::AppState;
+use crate::commands::errors::Error;
+use crate::database;
+use crate::slippi::anonymize;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+/// An anonymized copy waiting to be uploaded
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnonymizedReplay {
+    pub path: String,
+    /// Name to upload it under. Never the original's, which the name
+    /// template can fill with connect codes
+    pub file_name: String,
+    pub size: u64,
+}
+
+/// Write an anonymized copy of a recording's replay to the temp folder
+pub async fn export_anonymized_replay(app: &tauri::AppHandle, recording_id: &str) -> Result<AnonymizedReplay, Error> {
+    let slp_path = {
+        let state = app.state::<AppState>();
+        let conn = state.database.connection();
+        database::get_recording_by_id(&conn, recording_id)
+            .map_err(Error::from)?
+            .ok_or_else(|| Error::InvalidPath(format!("Recording not found: {}", recording_id)))?
+            .slp_path
+            .map(PathBuf::from)
+            .filter(|p| p.exists())
+            .ok_or_else(|| Error::InvalidPath(format!("Recording has no replay: {}", recording_id)))?
+    };
+
+    tauri::async_runtime::spawn_blocking(move || write_anonymized(&slp_path))
+        .await
+        .map_err(|e| Error::RecordingFailed(format!("Replay anonymization task failed: {}", e)))?
+}
+
+fn write_anonymized(slp_path: &Path) -> Result<AnonymizedReplay, Error> {
+    let data = std::fs::read(slp_path)?;
+    let anonymized = anonymize::anonymize(&data).map_err(|reason| Error::SlpParse { reason })?;
+
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let file_name = format!("replay-{}.slp", &id[..8]);
+    let path = std::env::temp_dir().join(format!("buckwheat-share-{}", file_name));
+    std::fs::write(&path, &anonymized)?;
+    log::info!("🕶️ Anonymized {} to {}", slp_path.display(), path.display());
+
+    Ok(AnonymizedReplay { path: path.to_string_lossy().to_string(), file_name, size: anonymized.len() as u64 })
+}
@@ -0,0 +1,187 @@
+//! Recording metadata sidecars
+//!
+//! With writeSidecars on, each recording gets a `<name>.buckwheat.json` next
+//! to its video for other tools to read: its id, what it was captured with,
+//! its replay, where its games start and its clip markers. It's written when
+//! the recording finishes and rewritten when sync links or re-keys it.
+//!
+//! Sync reads sidecars back (whatever the setting) for videos that aren't
+//! cached, so a deleted cache is rebuilt with the same ids, capture stats and
+//! games. Writes go to a temporary file that's renamed over the sidecar, so
+//! a crash never leaves half of one.
+
+use crate::commands::errors::Error;
+use crate::database::{self, Database, RecordingGame};
+use crate::recorder::CaptureSettings;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri_plugin_store::StoreExt;
+
+/// Settings key turning sidecars on
+const WRITE_SIDECARS_KEY: &str = "writeSidecars";
+/// Replaces the video's extension
+const SIDECAR_EXTENSION: &str = "buckwheat.json";
+/// Bumped when fields change meaning
+const SIDECAR_VERSION: u32 = 1;
+
+/// What a sidecar holds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sidecar {
+    pub version: u32,
+    pub id: String,
+    pub slp_path: Option<String>,
+    pub replay_id: Option<String>,
+    pub start_time: Option<String>,
+    #[serde(default)]
+    pub capture: Option<CaptureSettings>,
+    pub dropped_frame_estimate: Option<i64>,
+    pub longest_stall_ms: Option<i64>,
+    /// How far the video trails its replay, in milliseconds
+    #[serde(default)]
+    pub video_offset_ms: Option<i64>,
+    /// Every game in the video and where it starts
+    #[serde(default)]
+    pub games: Vec<RecordingGame>,
+    /// Clip markers set while recording, in seconds
+    #[serde(default)]
+    pub clip_markers: Vec<f64>,
+}
+
+/// Whether the writeSidecars setting is on
+pub fn enabled(app: &tauri::AppHandle) -> bool {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(WRITE_SIDECARS_KEY))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// `<name>.buckwheat.json` next to `<name>.mp4`
+pub fn sidecar_path(video_path: &Path) -> PathBuf {
+    video_path.with_extension(SIDECAR_EXTENSION)
+}
+
+/// The video's sidecar, None if it has none or it can't be read
+pub fn read(video_path: &Path) -> Option<Sidecar> {
+    let path = sidecar_path(video_path);
+    let contents = std::fs::read_to_string(&path).ok()?;
+    serde_json::from_str(&contents)
+        .inspect_err(|e| log::warn!("Ignoring unreadable sidecar {}: {}", path.display(), e))
+        .ok()
+}
+
+/// Write the sidecar of a cached recording from the database. Capture
+/// settings and clip markers aren't cached, so when not given the ones
+/// already in the sidecar are kept. Returns whether the file changed
+pub fn write_for_recording(
+    db: &Database,
+    video_path: &Path,
+    capture: Option<CaptureSettings>,
+    clip_markers: Option<Vec<f64>>,
+) -> Result<bool, Error> {
+    let (row, games) = {
+        let conn = db.connection();
+        let row = database::get_recording_by_video_path(&conn, &video_path.to_string_lossy())
+            .map_err(Error::from)?
+            .ok_or_else(|| Error::InvalidPath(format!("Recording isn't cached: {}", video_path.display())))?;
+        let games = database::get_recording_games(&conn, &row.id).map_err(Error::from)?;
+        (row, games)
+    };
+
+    let existing = read(video_path);
+    let sidecar = Sidecar {
+        version: SIDECAR_VERSION,
+        id: row.id,
+        slp_path: row.slp_path,
+        replay_id: row.replay_id,
+        start_time: row.start_time,
+        capture: capture.or_else(|| existing.as_ref().and_then(|s| s.capture.clone())),
+        dropped_frame_estimate: row.dropped_frame_estimate,
+        longest_stall_ms: row.longest_stall_ms,
+        video_offset_ms: row.video_offset_ms,
+        games,
+        clip_markers: clip_markers
+            .or_else(|| existing.as_ref().map(|s| s.clip_markers.clone()))
+            .unwrap_or_default(),
+    };
+    if existing.as_ref() == Some(&sidecar) {
+        return Ok(false);
+    }
+    write(video_path, &sidecar)?;
+    Ok(true)
+}
+
+/// Put what the sidecar remembers back into a freshly cached recording
+pub(crate) fn restore(db: &Database, id: &str, video_path: &Path, sidecar: &Sidecar) -> Result<(), Error> {
+    let conn = db.connection();
+    let profile = sidecar.capture.as_ref().and_then(|c| c.profile.as_deref());
+    if sidecar.dropped_frame_estimate.is_some() || profile.is_some() || sidecar.video_offset_ms.is_some() {
+        database::set_recording_capture_info(
+            &conn,
+            id,
+            &video_path.to_string_lossy(),
+            sidecar.dropped_frame_estimate,
+            sidecar.longest_stall_ms,
+            profile,
+            sidecar.video_offset_ms,
+        )
+        .map_err(Error::from)?;
+    }
+    // A single game starting with the video is the plain replay link
+    if !matches!(sidecar.games.as_slice(), [] | [RecordingGame { offset_secs: 0.0, .. }]) {
+        database::set_recording_games(&conn, id, &sidecar.games).map_err(Error::from)?;
+    }
+    Ok(())
+}
+
+/// Write to a temporary file and rename it over the sidecar
+fn write(video_path: &Path, sidecar: &Sidecar) -> Result<(), Error> {
+    let path = sidecar_path(video_path);
+    let temp_path = path.with_extension("json.tmp");
+    let json = serde_json::to_string_pretty(sidecar)
+        .map_err(|e| Error::InvalidPath(format!("Failed to serialize sidecar: {}", e)))?;
+    let written = std::fs::write(&temp_path, json).and_then(|()| std::fs::rename(&temp_path, &path));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    Ok(written?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::RecordingQuality;
+    use crate::settings::RecorderBackend;
+
+    #[test]
+    fn test_sidecar_round_trips_next_to_the_video() {
+        let dir = std::env::temp_dir().join(format!("buckwheat-sidecar-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let video_path = dir.join("Game_20240101T120000.mp4");
+        let sidecar = Sidecar {
+            version: SIDECAR_VERSION,
+            id: "id".to_string(),
+            slp_path: Some("Game_20240101T120000.slp".to_string()),
+            replay_id: None,
+            start_time: None,
+            capture: Some(CaptureSettings {
+                quality: RecordingQuality::High,
+                backend: RecorderBackend::Obs,
+                profile: Some("Tournament".to_string()),
+            }),
+            dropped_frame_estimate: Some(3),
+            longest_stall_ms: Some(40),
+            video_offset_ms: Some(120),
+            games: vec![RecordingGame { game_index: 0, slp_path: "a.slp".to_string(), offset_secs: 12.5 }],
+            clip_markers: vec![30.0],
+        };
+
+        write(&video_path, &sidecar).unwrap();
+
+        assert_eq!(sidecar_path(&video_path), dir.join("Game_20240101T120000.buckwheat.json"));
+        assert_eq!(read(&video_path), Some(sidecar));
+        assert!(!dir.join("Game_20240101T120000.buckwheat.json.tmp").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
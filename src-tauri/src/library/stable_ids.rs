@@ -0,0 +1,198 @@
+//! Stable recording ids
+//!
+//! A recording's id is derived from its content, so the same game gets the
+//! same id on every machine and after the cache is wiped; cloud uploads and
+//! links rely on that. Games use their replay's Game Start: the game info
+//! block (stage, ports, characters, teams) and the random seed Melee picked
+//! for the game, which the replays of both netplay players share. Videos
+//! without a replay (clips, unlinked recordings) use a hash of the file's
+//! size and its first and last 64 KiB, so indexing never reads a whole video.
+//!
+//! Ids are UUID-shaped (version 8). The random v4 ids used before are
+//! replaced as the library syncs, moving stats, combos and cached analysis
+//! along with them. Once a recording has a stable id it keeps it.
+
+use super::analysis_cache;
+use crate::app_state::AppState;
+use crate::commands::errors::Error;
+use crate::database::{self, Database};
+use crate::paths;
+use crate::slippi::game_start::read_game_start_event;
+use sha2::{Digest, Sha256};
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::Path;
+use tauri::Manager;
+use uuid::Uuid;
+
+/// Game info block and random seed, relative to the Game Start command byte.
+/// Present since replay version 0.1.0 and the same in every copy of a game
+const GAME_START_SAMPLE: Range<usize> = 0x5..0x141;
+/// Bytes hashed from each end of a video or replay
+const FILE_SAMPLE_BYTES: u64 = 64 * 1024;
+
+/// Whether an id is a random one from before ids were derived from content
+pub fn is_legacy_id(id: &str) -> bool {
+    Uuid::parse_str(id).is_ok_and(|uuid| uuid.get_version_num() == 4)
+}
+
+/// Stable id of the game in a replay, None if it has no readable Game Start
+pub fn replay_id_for(slp_path: &Path) -> Option<String> {
+    read_game_start_event(slp_path).and_then(|event| replay_id(&event))
+}
+
+/// Id for a video, its replay's id (from replay_id_for) or else derived from
+/// the file, that isn't taken by another video. The same game recorded twice
+/// falls back to its file id; a copy of the same file gets a random (legacy)
+/// id for now
+pub fn choose_recording_id(db: &Database, video_path: &Path, replay_id: Option<&str>) -> String {
+    let video_path_str = paths::normalize(video_path);
+    let is_free = |id: &str| match database::get_recording_by_id(&db.connection(), id) {
+        Ok(Some(other)) => other.video_path.as_deref() == Some(&*video_path_str),
+        Ok(None) => true,
+        Err(_) => false,
+    };
+
+    if let Some(id) = replay_id.filter(|id| is_free(id)) {
+        return id.to_string();
+    }
+    match file_id(video_path) {
+        Ok(id) if is_free(&id) => id,
+        Ok(_) => Uuid::new_v4().to_string(),
+        Err(e) => {
+            log::warn!("Failed to derive an id for {}: {}", video_path_str, e);
+            Uuid::new_v4().to_string()
+        }
+    }
+}
+
+/// Move a recording and everything keyed by its id to a new id
+pub(crate) fn rekey(db: &Database, cache_dir: &Path, old_id: &str, new_id: &str) -> Result<(), Error> {
+    database::rekey_recording(&db.connection(), old_id, new_id)
+        .map_err(Error::from)?;
+    analysis_cache::rekey_analysis(cache_dir, old_id, new_id);
+    log::debug!("🔑 Re-keyed recording {} as {}", old_id, new_id);
+    Ok(())
+}
+
+/// Give every recording that still has a random id its stable id, and
+/// remember the replay ids of linked recordings that don't have one yet
+/// Returns how many were re-keyed
+pub async fn migrate_ids(app: &tauri::AppHandle) -> Result<usize, Error> {
+    let state = app.state::<AppState>();
+    let db = state.database.clone();
+    let cache_dir = analysis_cache::get_cache_directory(app);
+
+    let pending: Vec<database::RecordingRow> = database::get_all_recordings(&db.connection())
+        .map_err(Error::from)?
+        .into_iter()
+        .filter(|row| is_legacy_id(&row.id) || (row.slp_path.is_some() && row.replay_id.is_none()))
+        .filter(|row| row.video_path.as_deref().is_some_and(|path| Path::new(path).exists()))
+        .collect();
+    if pending.is_empty() {
+        return Ok(0);
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut rekeyed = 0;
+        for row in pending {
+            let video_path = row.video_path.as_deref().unwrap_or_default();
+            let replay_id = row.slp_path.as_deref().and_then(|slp| replay_id_for(Path::new(slp)));
+            let mut id = row.id.clone();
+            if is_legacy_id(&row.id) {
+                let new_id = choose_recording_id(&db, Path::new(video_path), replay_id.as_deref());
+                if !is_legacy_id(&new_id) {
+                    match rekey(&db, &cache_dir, &row.id, &new_id) {
+                        Ok(()) => {
+                            rekeyed += 1;
+                            id = new_id;
+                        }
+                        Err(e) => log::warn!("Failed to re-key {}: {}", video_path, e),
+                    }
+                }
+            }
+            if let Some(replay_id) = replay_id.as_deref() {
+                if let Err(e) = database::set_recording_replay_id(&db.connection(), &id, Some(replay_id)) {
+                    log::warn!("Failed to save the replay id of {}: {}", video_path, e);
+                }
+            }
+        }
+        if rekeyed > 0 {
+            log::info!("🔑 Gave {} recording(s) stable ids", rekeyed);
+        }
+        rekeyed
+    })
+    .await
+    .map_err(|e| Error::RecordingFailed(format!("Id migration failed: {}", e)))
+}
+
+fn replay_id(game_start: &[u8]) -> Option<String> {
+    game_start.get(GAME_START_SAMPLE).map(|sample| hashed_id("slp", &[sample]))
+}
+
+fn file_id(path: &Path) -> Result<String, Error> {
+    sampled_hash("video", path)
+}
+
+/// Hash of a replay's size and its first and last 64 KiB, which changes
+/// when the file is replaced or rewritten
+pub fn replay_hash(slp_path: &Path) -> Result<String, Error> {
+    sampled_hash("slp-file", slp_path)
+}
+
+fn sampled_hash(kind: &str, path: &Path) -> Result<String, Error> {
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+
+    let mut head = Vec::new();
+    (&mut file).take(FILE_SAMPLE_BYTES).read_to_end(&mut head)?;
+    let mut tail = Vec::new();
+    if len > FILE_SAMPLE_BYTES * 2 {
+        file.seek(SeekFrom::End(-(FILE_SAMPLE_BYTES as i64)))?;
+        file.read_to_end(&mut tail)?;
+    }
+    Ok(hashed_id(kind, &[&len.to_be_bytes(), &head, &tail]))
+}
+
+fn hashed_id(kind: &str, parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(kind.as_bytes());
+    for part in parts {
+        hasher.update([0]);
+        hasher.update(part);
+    }
+    let digest = hasher.finalize();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_custom_bytes(bytes).into_uuid().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_id_is_stable_across_slippi_versions() {
+        let mut game_start = vec![0u8; 0x2A0];
+        game_start[0] = 0x36;
+        game_start[1..4].copy_from_slice(&[3, 14, 0]);
+        game_start[0x13D..0x141].copy_from_slice(&[1, 2, 3, 4]); // random seed
+        let id = replay_id(&game_start).unwrap();
+
+        // The other player's replay, written by a newer Slippi with a longer Game Start
+        let mut other = game_start.clone();
+        other[1..4].copy_from_slice(&[3, 16, 0]);
+        other.resize(0x300, 0);
+        assert_eq!(replay_id(&other), Some(id.clone()));
+
+        // A different game
+        let mut rematch = game_start.clone();
+        rematch[0x13D] = 9;
+        assert_ne!(replay_id(&rematch), Some(id.clone()));
+
+        assert!(!is_legacy_id(&id));
+        assert!(is_legacy_id(&Uuid::new_v4().to_string()));
+        assert!(!is_legacy_id("historical-Game_20240115T193012"));
+        assert_eq!(replay_id(&game_start[..0x100]), None);
+    }
+}
@@ -0,0 +1,230 @@
+//! Disk usage of the library, and the cap on the Clips folder
+//!
+//! Auto-clipping (clip markers, daily highlights) can fill a drive, so the
+//! Clips folder can be capped with maxClipsSizeGb (0 for no cap). Once it's
+//! over, the oldest clips the user hasn't starred are deleted until it fits,
+//! through the same path as deleting a clip by hand (delete_video), and
+//! ROTATED says what went. Starred clips are never deleted, even when they
+//! alone are over the cap.
+//!
+//! The cap is checked whenever clips are made and hourly by the scheduler.
+
+use crate::app_state::AppState;
+use crate::commands::clips::default_clips_directory;
+use crate::commands::errors::Error;
+use crate::commands::library::delete_video;
+use crate::database::{self, ClipFileRow};
+use crate::events::clips as clip_events;
+use crate::scheduler::{Cadence, Constraints, Job};
+use serde::Serialize;
+use std::path::{Path, MAIN_SEPARATOR};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+
+pub const CLIPS_CAP_JOB: &str = "clipsCap";
+const MAX_CLIPS_SIZE_KEY: &str = "maxClipsSizeGb";
+const BYTES_PER_GB: u64 = 1024 * 1024 * 1024;
+const CAP_STARTUP_DELAY: Duration = Duration::from_secs(3 * 60);
+const CAP_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// What the library takes up on disk
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageReport {
+    /// Videos outside the Clips folder, as indexed
+    pub recordings_bytes: u64,
+    pub recording_count: usize,
+    /// Everything in the Clips folder, thumbnails included
+    pub clips_bytes: u64,
+    pub clip_count: usize,
+    pub favorite_clip_count: usize,
+    /// maxClipsSizeGb in bytes, None without a cap
+    pub clips_cap_bytes: Option<u64>,
+    /// Free space on the drive the recordings are on
+    pub free_bytes: Option<u64>,
+}
+
+/// Payload of ROTATED
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipsRotated {
+    pub deleted: usize,
+    pub failed: usize,
+    pub freed_bytes: u64,
+    /// Size of the Clips folder afterwards; still over the cap if only
+    /// starred clips are left
+    pub clips_bytes: u64,
+    pub cap_bytes: u64,
+}
+
+pub async fn storage_report(app: &AppHandle) -> Result<StorageReport, Error> {
+    let clips_dir = default_clips_directory(app).await?;
+    let recording_dir = super::get_recording_directory(app).await?;
+    let (clips, (recording_count, recordings_bytes)) = {
+        let state = app.state::<AppState>();
+        let conn = state.database.connection();
+        let prefix = dir_prefix(&clips_dir);
+        (database::get_clips_by_age(&conn, &prefix)?, database::get_recordings_size(&conn, &prefix)?)
+    };
+    Ok(StorageReport {
+        recordings_bytes,
+        recording_count,
+        clips_bytes: dir_size(&clips_dir),
+        clip_count: clips.len(),
+        favorite_clip_count: clips.iter().filter(|clip| clip.is_favorite).count(),
+        clips_cap_bytes: clips_cap(app),
+        free_bytes: crate::readiness::free_space_at(Path::new(&recording_dir)),
+    })
+}
+
+/// The cap as a scheduled job; also run right after clips are made (see
+/// clips_added)
+pub fn clips_cap_job() -> Job {
+    Job {
+        name: CLIPS_CAP_JOB,
+        cadence: Cadence::Every { delay: CAP_STARTUP_DELAY, interval: CAP_CHECK_INTERVAL },
+        constraints: Constraints::default(),
+        due: |app| clips_cap(app).is_some(),
+        run: |app| Box::pin(run_clips_cap(app)),
+    }
+}
+
+/// Check the cap now that new clips are in the folder
+pub fn clips_added(app: &AppHandle) {
+    if clips_cap(app).is_none() {
+        return;
+    }
+    if let Err(e) = app.state::<AppState>().scheduler.run_now(app, CLIPS_CAP_JOB) {
+        log::warn!("Failed to check the Clips folder cap: {}", e);
+    }
+}
+
+async fn run_clips_cap(app: AppHandle) -> Result<(), Error> {
+    let clips_dir = default_clips_directory(&app).await?;
+    tauri::async_runtime::spawn_blocking(move || enforce_clips_cap(&app, &clips_dir).map(|_| ()))
+        .await
+        .map_err(|e| Error::RecordingFailed(format!("Clips cap task failed: {}", e)))?
+}
+
+/// Delete the oldest unstarred clips until the folder fits the cap. Returns
+/// None if there's no cap or the folder is under it
+pub fn enforce_clips_cap(app: &AppHandle, clips_dir: &Path) -> Result<Option<ClipsRotated>, Error> {
+    let Some(cap) = clips_cap(app) else { return Ok(None) };
+    let used = dir_size(clips_dir);
+    if used <= cap {
+        return Ok(None);
+    }
+
+    let mut clips = {
+        let state = app.state::<AppState>();
+        let conn = state.database.connection();
+        database::get_clips_by_age(&conn, &dir_prefix(clips_dir))?
+    };
+    // What deleting each one frees: the video and the thumbnail beside it
+    for clip in &mut clips {
+        let video = Path::new(&clip.video_path);
+        clip.file_size = [video.to_path_buf(), video.with_extension("jpg")]
+            .iter()
+            .filter_map(|path| std::fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+    }
+
+    let mut rotated = ClipsRotated { cap_bytes: cap, ..Default::default() };
+    for clip in oldest_over_cap(&clips, used, cap) {
+        match delete_video(app, &clip.video_path) {
+            Ok(()) => rotated.deleted += 1,
+            Err(e) => {
+                log::warn!("Failed to delete clip {} over the Clips folder cap: {}", clip.video_path, e);
+                rotated.failed += 1;
+            }
+        }
+    }
+    rotated.clips_bytes = dir_size(clips_dir);
+    rotated.freed_bytes = used.saturating_sub(rotated.clips_bytes);
+
+    log::info!(
+        "🧹 Clips folder was over its {} GB cap: deleted {} clip(s), freed {} MB",
+        cap / BYTES_PER_GB,
+        rotated.deleted,
+        rotated.freed_bytes / (1024 * 1024)
+    );
+    if rotated.clips_bytes > cap {
+        log::warn!("⚠️ Clips folder is still over its cap; only starred clips are left to delete");
+    }
+    if let Err(e) = app.emit(clip_events::ROTATED, &rotated) {
+        log::error!("Failed to emit {} event: {:?}", clip_events::ROTATED, e);
+    }
+    Ok(Some(rotated))
+}
+
+/// The unstarred clips to delete, oldest first, for `used` bytes to fit `cap`
+fn oldest_over_cap(clips: &[ClipFileRow], used: u64, cap: u64) -> Vec<&ClipFileRow> {
+    let mut over = used.saturating_sub(cap);
+    clips
+        .iter()
+        .filter(|clip| !clip.is_favorite)
+        .take_while(|clip| {
+            let take = over > 0;
+            over = over.saturating_sub(clip.file_size);
+            take
+        })
+        .collect()
+}
+
+/// maxClipsSizeGb in bytes, None when it's 0 or unset
+fn clips_cap(app: &AppHandle) -> Option<u64> {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(MAX_CLIPS_SIZE_KEY))
+        .and_then(|v| v.as_u64())
+        .filter(|gb| *gb > 0)
+        .map(|gb| gb * BYTES_PER_GB)
+}
+
+/// A folder as the start of the stored paths in it
+fn dir_prefix(dir: &Path) -> String {
+    let mut prefix = crate::paths::normalize(dir);
+    if !prefix.ends_with(MAIN_SEPARATOR) {
+        prefix.push(MAIN_SEPARATOR);
+    }
+    prefix
+}
+
+/// Total size of the files in a folder (the Clips folder has no subfolders)
+fn dir_size(dir: &Path) -> u64 {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.metadata().ok())
+                .filter(|metadata| metadata.is_file())
+                .map(|metadata| metadata.len())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(id: &str, file_size: u64, is_favorite: bool) -> ClipFileRow {
+        ClipFileRow { id: id.to_string(), video_path: format!("Clips/{}.mp4", id), file_size, is_favorite }
+    }
+
+    #[test]
+    fn test_oldest_unstarred_clips_go_until_the_folder_fits() {
+        let clips = [clip("a", 40, false), clip("b", 50, true), clip("c", 30, false), clip("d", 20, false)];
+        let ids = |picked: Vec<&ClipFileRow>| picked.iter().map(|clip| clip.id.clone()).collect::<Vec<_>>();
+
+        assert!(oldest_over_cap(&clips, 140, 140).is_empty());
+        // 10 over: the oldest clip is enough
+        assert_eq!(ids(oldest_over_cap(&clips, 150, 140)), vec!["a"]);
+        // 60 over: the starred clip is skipped
+        assert_eq!(ids(oldest_over_cap(&clips, 200, 140)), vec!["a", "c"]);
+        // Starred clips stay even if nothing else is left
+        assert_eq!(ids(oldest_over_cap(&clips, 500, 10)), vec!["a", "c", "d"]);
+    }
+}
@@ -1,21 +1,68 @@
 //! Background sync of recordings cache
 //!
 //! Scans for new, modified, and deleted recordings and updates the SQLite cache.
-//! Note: This only indexes video files and finds matching .slp paths.
+//! Note: This only indexes video files and finds matching .slp paths (and, in
+//! replay-only mode, the Slippi folder's replays as entries without a video).
 //! Actual .slp parsing and stats extraction is done by the frontend (slippi-js).
 
+use super::{analysis_cache, media_info, sidecar, stable_ids};
 use crate::app_state::AppState;
 use crate::commands::errors::Error;
-use crate::database::{self, RecordingRow};
+use crate::commands::settings::replay_only_mode;
+use crate::database::{self, RecordingRow, RecordingWithStats};
 use crate::game_detector::slippi_paths;
+use crate::health::{self, HealthStatus, Subsystem};
+use crate::scheduler::{Cadence, Constraints, Job};
+use crate::settings::RECORDINGS_SCAN_DEPTH;
+use crate::watch_filters::path_matches;
+use crate::{paths, timestamps};
+use crate::work_gate::WorkGate;
 use std::collections::HashSet;
-use std::path::Path;
-use std::time::SystemTime;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use tauri::Manager;
 use tauri_plugin_store::StoreExt;
-use uuid::Uuid;
 use walkdir::WalkDir;
 
+/// Name of the sync's scheduled job
+pub const SYNC_JOB: &str = "sync";
+
+/// How many folders deep the recordings scan goes
+pub const SCAN_DEPTH_KEY: &str = "recordingsScanDepth";
+/// Globs for folders and files the recordings scan skips
+pub const SCAN_IGNORE_KEY: &str = "recordingsScanIgnore";
+
+/// Folders the app writes next to recordings, never holding recordings themselves
+const APP_DIRS: [&str; 3] = ["Thumbnails", ".trash", "Clips"];
+
+/// The sync as a scheduled job, once shortly after startup (the supervisor
+/// runs it again if it panics)
+pub fn sync_job() -> Job {
+    Job {
+        name: SYNC_JOB,
+        // Small delay to let the app finish initializing
+        cadence: Cadence::Startup { delay: Duration::from_millis(100) },
+        constraints: Constraints::default(),
+        due: |_| true,
+        run: |app| Box::pin(run_sync(app)),
+    }
+}
+
+/// Sync, tracked as the sync subsystem, then probe and analyze what it found
+async fn run_sync(app: tauri::AppHandle) -> Result<(), Error> {
+    health::scope(Subsystem::Sync, async move {
+        let health = app.state::<AppState>().health.clone();
+        health.set_status(Subsystem::Sync, HealthStatus::Running);
+        let synced = sync_recordings_cache(&app).await;
+        media_info::spawn_probe(&app);
+        crate::inputs::spawn_analysis(&app);
+        health.set_status(Subsystem::Sync, HealthStatus::Idle);
+        synced
+    })
+    .await
+}
+
 /// Sync the recordings cache with the file system
 /// This runs in the background after app startup
 pub async fn sync_recordings_cache(app: &tauri::AppHandle) -> Result<(), Error> {
@@ -24,9 +71,27 @@ pub async fn sync_recordings_cache(app: &tauri::AppHandle) -> Result<(), Error>
     let state = app.state::<AppState>();
     let db = state.database.clone();
     
-    // Get directories
+    // Get directories (first: on an unplugged drive every recording would look deleted)
     let recording_dir = super::get_recording_directory(app).await?;
-    let slippi_dir = get_slippi_directory(app)?;
+    let context = IndexContext::new(app)?;
+    
+    // Rows stored before paths were normalized, some of them twice
+    match database::normalize_stored_paths(&db.connection()) {
+        Ok(0) => {}
+        Ok(changed) => log::info!("🗂 Normalized the paths of {} recording(s)", changed),
+        Err(e) => log::warn!("Failed to normalize stored paths: {}", e),
+    }
+    
+    // Recordings cached before ids were derived from content get their stable ids
+    if let Err(e) = stable_ids::migrate_ids(app).await {
+        log::warn!("Failed to migrate recording ids: {}", e);
+    }
+    
+    // Replays rewritten since they were indexed are flagged to be reparsed
+    let changed_replays = flag_changed_replays(&db);
+    if changed_replays > 0 {
+        log::info!("🔁 {} replay(s) changed since they were parsed, flagged to reparse", changed_replays);
+    }
     
     // Also scan the Clips directory (sibling to recording_dir)
     let recording_dir_path = Path::new(&recording_dir);
@@ -55,27 +120,28 @@ pub async fn sync_recordings_cache(app: &tauri::AppHandle) -> Result<(), Error>
         clips_dir.to_string_lossy().to_string(),
     ];
     
+    let options = scan_options(app);
     for scan_dir in &dirs_to_scan {
-        if !Path::new(scan_dir).exists() {
-            continue;
-        }
-        
-        for entry in WalkDir::new(scan_dir)
-            .max_depth(3)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            let path = entry.path();
-            if path.extension().and_then(|s| s.to_str()) != Some("mp4") {
-                continue;
-            }
+        let started = Instant::now();
+        let scan = find_videos(Path::new(scan_dir), &options);
+        log::info!(
+            "🔎 Scanned {}: {} video(s) among {} file(s), {} folder(s) skipped, in {} ms",
+            scan_dir,
+            scan.videos.len(),
+            scan.files,
+            scan.skipped,
+            started.elapsed().as_millis()
+        );
+        for path in scan.videos {
+            let path = path.as_path();
             
-            let video_path = path.to_string_lossy().to_string();
+            // Spelled like the cached paths
+            let video_path = paths::normalize(path);
             found_paths.insert(video_path.clone());
             
             // Check if we need to parse this file
             let needs_parse = if cached_paths.contains(&video_path) {
-                // Check if file was modified
+                // Check if file was modified or flagged to reparse
                 check_file_modified(&db, &video_path)
             } else {
                 // New file
@@ -84,7 +150,7 @@ pub async fn sync_recordings_cache(app: &tauri::AppHandle) -> Result<(), Error>
             
             if needs_parse {
                 // Parse and cache the recording
-                match parse_and_cache_recording(path, &slippi_dir, &db).await {
+                match parse_and_cache_recording(path, &context).await {
                     Ok(is_new) => {
                         if is_new {
                             new_count += 1;
@@ -113,17 +179,119 @@ pub async fn sync_recordings_cache(app: &tauri::AppHandle) -> Result<(), Error>
         log::info!("🗑️ Removed {} deleted recordings from cache", deleted.len());
     }
     
+    // Replay-only entries are kept after turning the mode off, until their replay goes
+    if replay_only_mode(app) {
+        new_count += index_replays(&db, &context.slippi_dir);
+    }
+    let deleted_replays = remove_missing_replays(&db);
+    
     log::info!(
         "✅ Sync complete: {} new, {} updated, {} deleted",
         new_count,
         updated_count,
-        deleted.len()
+        deleted.len() + deleted_replays
     );
     
     Ok(())
 }
 
-/// Check if a cached file has been modified since caching
+/// How deep the recordings scan goes and what it leaves out
+#[derive(Debug, Clone)]
+struct ScanOptions {
+    max_depth: usize,
+    ignore_patterns: Vec<String>,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self { max_depth: 3, ignore_patterns: Vec::new() }
+    }
+}
+
+impl ScanOptions {
+    /// Whether an entry below the scanned folder is left out, along with
+    /// everything under it when it's a folder
+    fn skips(&self, entry: &walkdir::DirEntry) -> bool {
+        let path = entry.path().to_string_lossy();
+        if entry.file_type().is_dir() {
+            let name = entry.file_name().to_string_lossy();
+            // So "*/Old/*" leaves out the folder itself, not just what's in it
+            let dir_path = format!("{}/", path.trim_end_matches(['/', '\\']));
+            APP_DIRS.iter().any(|app_dir| name.eq_ignore_ascii_case(app_dir))
+                || self.ignore_patterns.iter().any(|p| path_matches(p, &path) || path_matches(p, &dir_path))
+        } else {
+            self.ignore_patterns.iter().any(|p| path_matches(p, &path))
+        }
+    }
+}
+
+/// The scan depth and ignore patterns from settings
+fn scan_options(app: &tauri::AppHandle) -> ScanOptions {
+    let Ok(store) = app.store("settings.json") else {
+        return ScanOptions::default();
+    };
+    let max_depth = store
+        .get(SCAN_DEPTH_KEY)
+        .and_then(|v| v.as_u64())
+        .map(|depth| depth.clamp(u64::from(*RECORDINGS_SCAN_DEPTH.start()), u64::from(*RECORDINGS_SCAN_DEPTH.end())) as usize)
+        .unwrap_or(ScanOptions::default().max_depth);
+    let ignore_patterns = store
+        .get(SCAN_IGNORE_KEY)
+        .and_then(|v| serde_json::from_value::<Vec<String>>(v).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+    ScanOptions { max_depth, ignore_patterns }
+}
+
+/// What a recordings scan found
+struct Scan {
+    videos: Vec<PathBuf>,
+    /// Files looked at, videos or not (ignored ones aside)
+    files: usize,
+    /// Folders left out without being walked
+    skipped: usize,
+}
+
+/// Videos in a folder and its subfolders, whatever their container; skipped
+/// folders are never walked into
+fn find_videos(dir: &Path, options: &ScanOptions) -> Scan {
+    let mut skipped = 0;
+    let mut files = 0;
+    let videos = WalkDir::new(dir)
+        .max_depth(options.max_depth)
+        .into_iter()
+        .filter_entry(|entry| {
+            let skip = entry.depth() > 0 && options.skips(entry);
+            if skip && entry.file_type().is_dir() {
+                skipped += 1;
+            }
+            !skip
+        })
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .inspect(|_| files += 1)
+        .filter(|entry| super::is_video_file(entry.path()))
+        .map(|entry| entry.into_path())
+        .collect();
+    Scan { videos, files, skipped }
+}
+
+/// Index a single recording right away instead of waiting for the next sync
+/// (used when a recording finishes so it's linked to its .slp before post-processing)
+pub async fn index_recording(app: &tauri::AppHandle, video_path: &Path) -> Result<RecordingRow, Error> {
+    let context = IndexContext::new(app)?;
+    parse_and_cache_recording(video_path, &context).await?;
+    
+    let conn = context.db.connection();
+    database::get_recording_by_video_path(&conn, &video_path.to_string_lossy())
+        .map_err(Error::from)?
+        .ok_or_else(|| Error::InvalidPath(format!("Recording wasn't indexed: {}", video_path.display())))
+}
+
+/// Check if a cached file has been modified since caching (or is flagged to reparse)
 fn check_file_modified(db: &database::Database, video_path: &str) -> bool {
     let conn = db.connection();
     
@@ -132,6 +300,9 @@ fn check_file_modified(db: &database::Database, video_path: &str) -> bool {
         Ok(Some(row)) => row,
         _ => return true,
     };
+    if cached.needs_reparse {
+        return true;
+    }
     
     // Get current file modified time
     let current_modified = match std::fs::metadata(video_path) {
@@ -155,84 +326,264 @@ fn check_file_modified(db: &database::Database, video_path: &str) -> bool {
     }
 }
 
+/// Hash the replays modified since their recording was cached (or never
+/// hashed) and flag the ones whose hash changed to be reparsed. Returns how
+/// many were flagged
+fn flag_changed_replays(db: &database::Database) -> usize {
+    let replays = database::get_replay_hashes(&db.connection()).unwrap_or_default();
+    let mut flagged = 0;
+    for replay in replays {
+        let modified = std::fs::metadata(&replay.slp_path)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .map(chrono::DateTime::<chrono::Utc>::from);
+        let Some(modified) = modified else { continue };
+        let cached_at = chrono::DateTime::parse_from_rfc3339(&replay.cached_at).ok();
+        if replay.slp_hash.is_some() && cached_at.is_some_and(|cached_at| modified <= cached_at) {
+            continue;
+        }
+        
+        let hash = match stable_ids::replay_hash(Path::new(&replay.slp_path)) {
+            Ok(hash) => hash,
+            Err(e) => {
+                log::warn!("Failed to hash replay {}: {:?}", replay.slp_path, e);
+                continue;
+            }
+        };
+        if replay.slp_hash.as_deref() == Some(&*hash) {
+            continue;
+        }
+        // A first hash is only bookkeeping; a changed one flags the replay,
+        // which the rest of the sync reads back
+        if replay.slp_hash.is_none() {
+            let id = replay.recording_id.clone();
+            db.write_behind("replay hash", move |conn| database::set_slp_hash(conn, &id, &hash, false));
+            continue;
+        }
+        match database::set_slp_hash(&db.connection(), &replay.recording_id, &hash, true) {
+            Ok(()) => flagged += 1,
+            Err(e) => log::warn!("Failed to store the hash of {}: {}", replay.slp_path, e),
+        }
+    }
+    flagged
+}
+
+/// Re-read a recording after its video was replaced outside the app (e.g.
+/// re-encoded): file info, linked replay, thumbnail (made again if the video
+/// changed since it was cached) and media info. With `reparse` its replay's
+/// stats are extracted again on the next stats pass
+pub async fn refresh_recording(
+    app: &tauri::AppHandle,
+    recording_id: &str,
+    reparse: bool,
+) -> Result<RecordingWithStats, Error> {
+    let context = IndexContext::new(app)?;
+    let row = database::get_recording_by_id(&context.db.connection(), recording_id)
+        .map_err(Error::from)?
+        .ok_or_else(|| Error::InvalidPath(format!("Recording not found: {}", recording_id)))?;
+    let video_path = row
+        .video_path
+        .as_deref()
+        .map(PathBuf::from)
+        .ok_or_else(|| Error::InvalidPath(format!("Recording {} has no video", recording_id)))?;
+    if !video_path.exists() {
+        return Err(Error::InvalidPath(format!("Video not found: {}", video_path.display())));
+    }
+
+    // The thumbnail is only made when missing
+    if check_file_modified(&context.db, &paths::normalize(&video_path)) {
+        if let Some(thumbnail) = row.thumbnail_path.as_deref() {
+            let _ = std::fs::remove_file(thumbnail);
+        }
+    }
+    parse_and_cache_recording(&video_path, &context).await?;
+
+    // Probed now rather than by the next background pass
+    let row = database::get_recording_by_video_path(&context.db.connection(), &video_path.to_string_lossy())
+        .map_err(Error::from)?
+        .ok_or_else(|| Error::InvalidPath(format!("Recording wasn't indexed: {}", video_path.display())))?;
+    if ffmpeg_sidecar::ffprobe::ffprobe_is_installed() {
+        let (probe_app, probe_row) = (app.clone(), row.clone());
+        let probed = tauri::async_runtime::spawn_blocking(move || {
+            media_info::probe_one(&probe_app.state::<AppState>(), &probe_row)
+        })
+        .await
+        .map_err(|e| Error::RecordingFailed(format!("Probe task failed: {}", e)))?;
+        if let Err(e) = probed {
+            log::warn!("Failed to probe {}: {}", row.id, e);
+        }
+    }
+
+    let conn = context.db.connection();
+    if reparse && row.slp_path.is_some() {
+        database::set_needs_reparse(&conn, &row.id).map_err(Error::from)?;
+    }
+    log::info!("🔄 Refreshed recording {}", row.id);
+    database::get_recording_with_stats(&conn, &row.id)
+        .map_err(Error::from)?
+        .ok_or_else(|| Error::InvalidPath(format!("Recording not found: {}", row.id)))
+}
+
+/// What indexing a video needs besides its path
+struct IndexContext {
+    slippi_dir: String,
+    cache_dir: PathBuf,
+    db: Arc<database::Database>,
+    gate: Arc<WorkGate>,
+    write_sidecars: bool,
+}
+
+impl IndexContext {
+    fn new(app: &tauri::AppHandle) -> Result<Self, Error> {
+        let state = app.state::<AppState>();
+        Ok(Self {
+            slippi_dir: get_slippi_directory(app)?,
+            cache_dir: analysis_cache::get_cache_directory(app),
+            db: state.database.clone(),
+            gate: state.work_gate.clone(),
+            write_sidecars: sidecar::enabled(app),
+        })
+    }
+}
+
 /// Index a recording and cache it in the database.
 /// This only stores file metadata and finds the matching .slp path.
 /// Actual .slp parsing is done by the frontend (slippi-js) via save_computed_stats.
-async fn parse_and_cache_recording(
-    video_path: &Path,
-    slippi_dir: &str,
-    db: &database::Database,
-) -> Result<bool, Error> {
+/// A video that isn't cached yet takes its id, capture stats and games from its
+/// sidecar; `write_sidecars` rewrites an existing sidecar to match the cache.
+async fn parse_and_cache_recording(video_path: &Path, context: &IndexContext) -> Result<bool, Error> {
+    let IndexContext { slippi_dir, cache_dir, db, gate, write_sidecars } = context;
     let video_path_str = video_path.to_string_lossy().to_string();
     
-    // Check if this recording already exists (by video path)
-    let (id, is_new) = {
-        let conn = db.connection();
-        match database::get_recording_by_video_path(&conn, &video_path_str) {
-            Ok(Some(existing)) => (existing.id, false),
-            _ => (Uuid::new_v4().to_string(), true),
-        }
-    };
-    
     // Get file metadata
     let file_meta = std::fs::metadata(video_path)
         .map_err(|e| Error::InvalidPath(format!("Failed to read file metadata: {}", e)))?;
     
     let file_size = file_meta.len() as i64;
-    let file_modified_at = file_meta
-        .modified()
+    let file_modified_at = file_meta.modified().ok().map(timestamps::from_system_time);
+    
+    // Check if this recording already exists (by video path); new recordings and
+    // ones still on a random id get an id derived from their content
+    let existing = database::get_recording_by_video_path(&db.connection(), &video_path_str)
         .ok()
-        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
-        .map(|d| {
-            chrono::DateTime::from_timestamp(d.as_secs() as i64, 0)
-                .unwrap_or_default()
-                .to_rfc3339()
-        });
+        .flatten();
+    let sidecar = existing.is_none().then(|| sidecar::read(video_path)).flatten();
     
     // Find matching .slp file (just the path, no parsing)
     let video_filename = video_path
         .file_stem()
         .and_then(|s| s.to_str())
         .unwrap_or("");
-    let slp_path = find_matching_slp_sync(video_filename, slippi_dir);
+    // Imported bundles keep their .slp next to the video
+    let found_slp = find_matching_slp_sync(video_filename, slippi_dir).or_else(|| {
+        let sibling = video_path.with_extension("slp");
+        sibling.exists().then(|| sibling.to_string_lossy().to_string())
+    });
+    // A renamed video no longer matches its replay by name, so it keeps the one
+    // it was linked to (or its sidecar was, when the cache was wiped)
+    let (slp_path, replay_id) = match found_slp {
+        Some(slp) => {
+            let replay_id = stable_ids::replay_id_for(Path::new(&slp));
+            (Some(slp), replay_id)
+        }
+        None => match (&existing, &sidecar) {
+            (Some(row), _) => (row.slp_path.clone(), row.replay_id.clone()),
+            (None, Some(sidecar)) => (sidecar.slp_path.clone(), sidecar.replay_id.clone()),
+            (None, None) => (None, None),
+        },
+    };
+    
+    // A replay-only entry of the same game becomes this video's, keeping its stats
+    let existing_id = existing.map(|existing| existing.id).or_else(|| {
+        let slp = slp_path.as_deref()?;
+        database::get_replay_only_recording_id(&db.connection(), slp).ok().flatten()
+    });
+    let (id, is_new) = match existing_id {
+        Some(id) if !stable_ids::is_legacy_id(&id) => (id, false),
+        existing_id => {
+            let sidecar_id = sidecar.as_ref().map(|s| s.id.clone()).filter(|id| {
+                matches!(database::get_recording_by_id(&db.connection(), id), Ok(None))
+            });
+            let id = sidecar_id
+                .unwrap_or_else(|| stable_ids::choose_recording_id(db, video_path, replay_id.as_deref()));
+            match existing_id {
+                Some(old_id) if stable_ids::is_legacy_id(&id) => (old_id, false),
+                Some(old_id) => {
+                    stable_ids::rekey(db, cache_dir, &old_id, &id)?;
+                    (id, false)
+                }
+                None => (id, true),
+            }
+        }
+    };
     
     // Use file creation/modification time as start_time
     let start_time = file_meta
         .created()
         .or_else(|_| file_meta.modified())
         .ok()
-        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
-        .map(|d| {
-            chrono::DateTime::from_timestamp(d.as_secs() as i64, 0)
-                .unwrap_or_default()
-                .to_rfc3339()
-        });
+        .map(timestamps::from_system_time);
     
     // Generate thumbnail (use video filename for thumbnail naming)
     let thumbnail_id = video_path
         .file_name()
         .and_then(|s| s.to_str())
         .unwrap_or(&id);
-    let thumbnail_path = super::thumbnails::generate_thumbnail_if_missing(video_path, thumbnail_id);
+    let thumbnail_path = super::thumbnails::thumbnail_or_defer(gate, db, video_path, thumbnail_id);
     
     // Create recording row (no game_stats - that comes from frontend slippi-js parsing)
     let row = RecordingRow {
         id: id.clone(),
-        video_path: video_path_str,
+        video_path: Some(video_path_str),
         slp_path,
         file_size: Some(file_size),
         file_modified_at,
         thumbnail_path,
-        start_time: start_time.or_else(|| Some(chrono::Utc::now().to_rfc3339())),
+        start_time: sidecar
+            .as_ref()
+            .and_then(|s| s.start_time.clone())
+            .or(start_time)
+            .or_else(|| Some(timestamps::now())),
         cached_at: chrono::Utc::now().to_rfc3339(),
         needs_reparse: false,
+        dropped_frame_estimate: None,
+        longest_stall_ms: None,
+        profile: None,
+        is_archived: false,
+        parse_error: None,
+        replay_id: None,
+        video_width: None,
+        video_height: None,
+        video_duration_secs: None,
+        has_audio: None,
+        audio_mean_volume_db: None,
+        video_offset_ms: None,
+        source_recording_id: None,
     };
     
-    // Insert/update in database
+    // Insert/update in database; a changed video is probed again
     {
         let conn = db.connection();
         database::upsert_recording(&conn, &row)
-            .map_err(|e| Error::InitializationError(format!("Database error: {}", e)))?;
+            .and_then(|()| database::set_recording_replay_id(&conn, &id, replay_id.as_deref()))
+            .and_then(|()| match is_new {
+                true => Ok(()),
+                false => database::set_recording_video_info(&conn, &id, None, None, None)
+                    .and_then(|()| database::set_recording_audio_info(&conn, &id, None, None)),
+            })
+            .map_err(Error::from)?;
+    }
+    
+    if let Some(sidecar) = &sidecar {
+        match sidecar::restore(db, &id, video_path, sidecar) {
+            Ok(()) => log::debug!("📄 Restored {} from its sidecar", id),
+            Err(e) => log::warn!("Failed to restore {} from its sidecar: {}", id, e),
+        }
+    }
+    if *write_sidecars && sidecar::sidecar_path(video_path).exists() {
+        if let Err(e) = sidecar::write_for_recording(db, video_path, None, None) {
+            log::warn!("Failed to update the sidecar of {}: {}", id, e);
+        }
     }
     
     if is_new {
@@ -244,6 +595,94 @@ async fn parse_and_cache_recording(
     Ok(is_new)
 }
 
+/// Add the Slippi folder's replays that no recording links to as replay-only
+/// entries. Returns how many were added
+fn index_replays(db: &database::Database, slippi_dir: &str) -> usize {
+    let linked: HashSet<String> = database::get_linked_slp_paths(&db.connection())
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+
+    let mut added = 0;
+    for entry in WalkDir::new(slippi_dir)
+        .max_depth(3)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        let path = entry.path();
+        if path.extension().and_then(|s| s.to_str()) != Some("slp") || linked.contains(&paths::normalize(path)) {
+            continue;
+        }
+        match cache_replay(db, path) {
+            Ok(()) => added += 1,
+            Err(e) => log::warn!("Failed to index replay {:?}: {:?}", path, e),
+        }
+    }
+    if added > 0 {
+        log::info!("🎮 Indexed {} replay(s) without a video", added);
+    }
+    added
+}
+
+/// Cache a replay as a replay-only entry. Its id is the one a video of the
+/// game would get, so stats carry over if it's recorded later
+fn cache_replay(db: &database::Database, slp_path: &Path) -> Result<(), Error> {
+    let file_meta = std::fs::metadata(slp_path)?;
+    let file_modified_at = file_meta.modified().ok().map(timestamps::from_system_time);
+    // Slippi creates the replay as the game starts
+    let start_time = file_meta.created().ok().map(timestamps::from_system_time).or_else(|| file_modified_at.clone());
+
+    let replay_id = stable_ids::replay_id_for(slp_path);
+    let id = stable_ids::choose_recording_id(db, slp_path, replay_id.as_deref());
+    let row = RecordingRow {
+        id: id.clone(),
+        video_path: None,
+        slp_path: Some(slp_path.to_string_lossy().to_string()),
+        file_size: Some(file_meta.len() as i64),
+        file_modified_at,
+        thumbnail_path: None,
+        start_time,
+        cached_at: chrono::Utc::now().to_rfc3339(),
+        needs_reparse: false,
+        dropped_frame_estimate: None,
+        longest_stall_ms: None,
+        profile: None,
+        is_archived: false,
+        parse_error: None,
+        replay_id: None,
+        video_width: None,
+        video_height: None,
+        video_duration_secs: None,
+        has_audio: None,
+        audio_mean_volume_db: None,
+        video_offset_ms: None,
+        source_recording_id: None,
+    };
+
+    let conn = db.connection();
+    database::upsert_recording(&conn, &row)
+        .and_then(|()| database::set_recording_replay_id(&conn, &id, replay_id.as_deref()))
+        .map_err(Error::from)
+}
+
+/// Remove replay-only entries whose replay was deleted. Returns how many were
+fn remove_missing_replays(db: &database::Database) -> usize {
+    let conn = db.connection();
+    let missing: Vec<String> = database::get_replay_only_recordings(&conn)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(_, slp_path)| !Path::new(slp_path).exists())
+        .map(|(id, _)| id)
+        .collect();
+    for id in &missing {
+        let _ = database::delete_recording(&conn, id);
+    }
+    if !missing.is_empty() {
+        log::info!("🗑️ Removed {} replay-only entries whose replay is gone", missing.len());
+    }
+    missing.len()
+}
+
 /// Find matching .slp file (sync version for background task)
 fn find_matching_slp_sync(video_filename: &str, slippi_dir: &str) -> Option<String> {
     if !video_filename.starts_with("Game_") {
@@ -268,7 +707,7 @@ fn find_matching_slp_sync(video_filename: &str, slippi_dir: &str) -> Option<Stri
 }
 
 /// Get Slippi directory from settings
-fn get_slippi_directory(app: &tauri::AppHandle) -> Result<String, Error> {
+pub fn get_slippi_directory(app: &tauri::AppHandle) -> Result<String, Error> {
     let store = app.store("settings.json").map_err(|e| {
         Error::InitializationError(format!("Failed to open settings store: {}", e))
     })?;
@@ -276,6 +715,7 @@ fn get_slippi_directory(app: &tauri::AppHandle) -> Result<String, Error> {
     if let Some(value) = store.get("slippiPath") {
         if let Some(path) = value.as_str() {
             if !path.is_empty() {
+                super::path_health::ensure_readable(app, "slippiPath", Path::new(path))?;
                 return Ok(path.to_string());
             }
         }
@@ -286,3 +726,125 @@ fn get_slippi_directory(app: &tauri::AppHandle) -> Result<String, Error> {
         .unwrap_or("")
         .to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_videos_in_any_container() {
+        let (root, dir) = paths::long_test_dir("videos");
+        let names = ["Game_1.mp4", "Game_2.MKV", "obs 3.mov", "Game_4.webm", "Game_5.avi", "Game_1.slp", "notes.txt"];
+        for name in names {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+        std::fs::create_dir(dir.join("Thumbnails.mp4")).unwrap();
+
+        let mut found: Vec<_> = find_videos(&dir, &ScanOptions::default()).videos.iter().filter_map(|p| p.file_name()?.to_str().map(str::to_string)).collect();
+        found.sort();
+        assert_eq!(found, ["Game_1.mp4", "Game_2.MKV", "Game_4.webm", "Game_5.avi", "obs 3.mov"]);
+        assert_eq!(crate::library::clip_extension(&dir.join("Game_2.MKV")), "mkv");
+        assert_eq!(crate::library::clip_extension(&dir.join("Game_1.slp")), "mp4");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_scan_skips_app_folders_ignored_paths_and_deep_folders() {
+        let (root, dir) = paths::long_test_dir("scan");
+        for sub in ["Thumbnails", ".trash", "clips", "Old Stuff", "Sessions/A/B/C", "Games"] {
+            std::fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        let files = [
+            "Thumbnails/t.mp4",
+            ".trash/d.mp4",
+            "clips/c.mp4",
+            "Old Stuff/o.mp4",
+            "Games/g.mp4",
+            "Games/g.partial.mp4",
+            "Sessions/A/s.mp4",
+            "Sessions/A/B/C/deep.mp4",
+        ];
+        for file in files {
+            std::fs::write(dir.join(file), b"").unwrap();
+        }
+        let options = ScanOptions {
+            max_depth: 3,
+            ignore_patterns: vec!["*/old*/*".to_string(), "*.partial.mp4".to_string()],
+        };
+
+        let scan = find_videos(&dir, &options);
+        let mut found: Vec<_> = scan.videos.iter().filter_map(|p| p.file_name()?.to_str().map(str::to_string)).collect();
+        found.sort();
+        assert_eq!(found, ["g.mp4", "s.mp4"]);
+        assert_eq!(scan.skipped, 4);
+        assert_eq!(scan.files, 2);
+
+        let deeper = ScanOptions { max_depth: 5, ..options };
+        assert_eq!(find_videos(&dir, &deeper).videos.len(), 3);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_renamed_recording_keeps_its_replay() {
+        let (root, dir) = paths::long_test_dir("renamed");
+        let slippi_dir = dir.join("Slippi");
+        std::fs::create_dir_all(dir.join("Thumbnails")).unwrap();
+        std::fs::create_dir(&slippi_dir).unwrap();
+        let slp = slippi_dir.join("Game_20240301T180211.slp");
+        std::fs::write(&slp, b"not a finished replay").unwrap();
+        let video = dir.join("Game_20240301T180211.mp4");
+        let renamed = dir.join("Fox vs Marth.mp4");
+        std::fs::write(&video, b"video").unwrap();
+        // Thumbnails already made, so indexing doesn't reach for FFmpeg
+        for name in ["Game_20240301T180211.mp4", "Fox vs Marth.mp4"] {
+            std::fs::write(dir.join("Thumbnails").join(format!("{}.jpg", name)), b"").unwrap();
+        }
+        let db = Arc::new(database::Database::open_in_memory().unwrap());
+        let context = IndexContext {
+            slippi_dir: slippi_dir.to_string_lossy().to_string(),
+            cache_dir: dir.join("cache"),
+            db: db.clone(),
+            gate: Arc::new(WorkGate::default()),
+            write_sidecars: false,
+        };
+
+        assert!(parse_and_cache_recording(&video, &context).await.unwrap());
+        let id = database::get_recording_by_video_path(&db.connection(), &video.to_string_lossy()).unwrap().unwrap().id;
+        database::set_recording_replay_id(&db.connection(), &id, Some("replay-1")).unwrap();
+
+        // What rename_deferred does once the game's stats are saved
+        std::fs::rename(&video, &renamed).unwrap();
+        database::set_recording_video_path(&db.connection(), &id, &renamed.to_string_lossy()).unwrap();
+
+        // Re-indexed under a name that no longer matches the replay's
+        assert!(!parse_and_cache_recording(&renamed, &context).await.unwrap());
+        let row = database::get_recording_by_id(&db.connection(), &id).unwrap().unwrap();
+        assert_eq!(row.slp_path.map(paths::normalize), Some(paths::normalize(&slp)));
+        assert_eq!(row.replay_id.as_deref(), Some("replay-1"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_replays_under_long_non_ascii_paths() {
+        let (root, dir) = paths::long_test_dir("sync");
+        let slp = dir.join("Game_20240301T180211.slp");
+        std::fs::write(&slp, b"not a finished replay").unwrap();
+        let slippi_dir = dir.to_str().unwrap();
+        let db = database::Database::open_in_memory().unwrap();
+
+        assert_eq!(index_replays(&db, slippi_dir), 1);
+        // Stored under the spelling the next walk finds, so it isn't added twice
+        assert_eq!(index_replays(&db, slippi_dir), 0);
+        let stored = database::get_replay_only_recordings(&db.connection()).unwrap();
+        assert!(Path::new(&stored[0].1).exists());
+        assert_eq!(remove_missing_replays(&db), 0);
+        let found = find_matching_slp_sync("Game_20240301T180211", slippi_dir).unwrap();
+        assert_eq!(paths::normalize(found), stored[0].1);
+
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(remove_missing_replays(&db), 1);
+    }
+}
@@ -1,42 +1,64 @@
 //! Thumbnail generation for recordings
 
-use std::path::Path;
+use crate::database::{self, Database};
+use crate::work_gate::{JobKind, WorkGate};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Generate a thumbnail for a video if one doesn't already exist
 /// Returns the thumbnail path if successful
 pub fn generate_thumbnail_if_missing(video_path: &Path, id: &str) -> Option<String> {
-    let Some(parent) = video_path.parent() else {
-        return None;
-    };
-    
-    let thumbnails_dir = parent.join("Thumbnails");
-    if let Err(e) = std::fs::create_dir_all(&thumbnails_dir) {
-        log::warn!("Failed to create thumbnails directory: {}", e);
-    }
-    
-    let thumbnail_filename = format!("{}.jpg", id);
-    let thumbnail_path = thumbnails_dir.join(&thumbnail_filename);
-    
+    let thumbnail_path = thumbnail_path_for(video_path, id)?;
+
     // Generate thumbnail if it doesn't exist
     if !thumbnail_path.exists() {
         // Ensure FFmpeg is available
         if crate::clip_processor::ensure_ffmpeg().is_err() {
             return None;
         }
-        
-        let video_path_str = video_path.to_string_lossy();
-        let thumbnail_path_str = thumbnail_path.to_string_lossy();
-        
-        if let Err(e) = crate::clip_processor::generate_thumbnail(
-            &video_path_str,
-            &thumbnail_path_str,
-            None,
-        ) {
+
+        if let Err(e) = crate::clip_processor::generate_thumbnail(video_path, &thumbnail_path, None) {
             log::warn!("Failed to generate thumbnail: {}", e);
             return None;
         }
     }
-    
+
     thumbnail_path.to_str().map(|s| s.to_string())
 }
 
+/// A video's thumbnail if it has one; while recording a missing one is made
+/// once the work gate lets it through, and stored on the recording then
+pub fn thumbnail_or_defer(gate: &Arc<WorkGate>, db: &Arc<Database>, video_path: &Path, id: &str) -> Option<String> {
+    if !gate.is_recording() || thumbnail_path_for(video_path, id).is_some_and(|path| path.exists()) {
+        return generate_thumbnail_if_missing(video_path, id);
+    }
+
+    let (gate, db) = (gate.clone(), db.clone());
+    let (video_path, id) = (video_path.to_path_buf(), id.to_string());
+    tauri::async_runtime::spawn(async move {
+        let key = video_path.to_string_lossy().to_string();
+        // Already waiting from an earlier sync
+        let Some(_permit) = gate.acquire_once(JobKind::Thumbnails, &key).await else { return };
+        let generated = tauri::async_runtime::spawn_blocking(move || {
+            let thumbnail_path = generate_thumbnail_if_missing(&video_path, &id)?;
+            Some(database::set_recording_thumbnail(&db.connection(), &key, &thumbnail_path))
+        })
+        .await;
+        match generated {
+            Ok(Some(Err(e))) => log::warn!("Failed to store a deferred thumbnail: {}", e),
+            Err(e) => log::warn!("Deferred thumbnail task failed: {}", e),
+            Ok(_) => {}
+        }
+    });
+    None
+}
+
+/// Where a video's thumbnail goes (a Thumbnails folder next to it)
+fn thumbnail_path_for(video_path: &Path, id: &str) -> Option<PathBuf> {
+    let parent = video_path.parent()?;
+    let thumbnails_dir = parent.join("Thumbnails");
+    if let Err(e) = std::fs::create_dir_all(&thumbnails_dir) {
+        log::warn!("Failed to create thumbnails directory: {}", e);
+    }
+    Some(thumbnails_dir.join(format!("{}.jpg", id)))
+}
@@ -0,0 +1,193 @@
+//! Timing of commands and background jobs
+//!
+//! Every command is marked `#[timed]`, and long background work (sync, media
+//! probing, retention, FFmpeg runs) runs inside `job` or `job_sync` or is
+//! marked `#[timed(job = "...")]`. Each run is logged at debug level and added
+//! to the registry
+//! kept in AppState: counts, failures and the latencies of the last
+//! SAMPLES_KEPT runs, from which get_command_metrics reports p50 and p95.
+//! The same summary goes into the diagnostics export.
+
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+pub use peppi_macros::timed;
+
+/// Latencies kept per name for the percentiles
+const SAMPLES_KEPT: usize = 512;
+
+/// Registry the wrappers report to, installed at startup
+static REGISTRY: OnceLock<Arc<Metrics>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MetricKind {
+    Command,
+    Job,
+}
+
+/// Timings of one command or job since the app started
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricSummary {
+    pub name: String,
+    pub kind: MetricKind,
+    pub count: u64,
+    pub failures: u64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+    pub total_ms: f64,
+}
+
+#[derive(Debug, Default)]
+struct Timings {
+    count: u64,
+    failures: u64,
+    total: Duration,
+    max: Duration,
+    recent: VecDeque<Duration>,
+}
+
+/// Timings by command or job name
+#[derive(Debug, Default)]
+pub struct Metrics {
+    timings: Mutex<HashMap<(MetricKind, &'static str), Timings>>,
+}
+
+impl Metrics {
+    pub fn record(&self, kind: MetricKind, name: &'static str, elapsed: Duration, succeeded: bool) {
+        let Ok(mut timings) = self.timings.lock() else {
+            return;
+        };
+        let timings = timings.entry((kind, name)).or_default();
+        timings.count += 1;
+        if !succeeded {
+            timings.failures += 1;
+        }
+        timings.total += elapsed;
+        timings.max = timings.max.max(elapsed);
+        if timings.recent.len() == SAMPLES_KEPT {
+            timings.recent.pop_front();
+        }
+        timings.recent.push_back(elapsed);
+    }
+
+    /// Everything recorded so far, slowest total first
+    pub fn snapshot(&self) -> Vec<MetricSummary> {
+        let Ok(timings) = self.timings.lock() else {
+            return Vec::new();
+        };
+        let mut summaries: Vec<MetricSummary> = timings
+            .iter()
+            .map(|(&(kind, name), timings)| {
+                let mut sorted: Vec<Duration> = timings.recent.iter().copied().collect();
+                sorted.sort();
+                MetricSummary {
+                    name: name.to_string(),
+                    kind,
+                    count: timings.count,
+                    failures: timings.failures,
+                    p50_ms: millis(percentile(&sorted, 0.50)),
+                    p95_ms: millis(percentile(&sorted, 0.95)),
+                    max_ms: millis(timings.max),
+                    total_ms: millis(timings.total),
+                }
+            })
+            .collect();
+        summaries.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+        summaries
+    }
+}
+
+/// Report to this registry from now on
+pub fn install(metrics: Arc<Metrics>) {
+    let _ = REGISTRY.set(metrics);
+}
+
+/// Time a background job
+pub async fn job<T, E>(name: &'static str, future: impl Future<Output = Result<T, E>>) -> Result<T, E> {
+    let started = Instant::now();
+    let output = future.await;
+    report(MetricKind::Job, name, started.elapsed(), output.is_ok());
+    output
+}
+
+/// Time blocking background work
+pub fn job_sync<T, E>(name: &'static str, f: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+    let started = Instant::now();
+    let output = f();
+    report(MetricKind::Job, name, started.elapsed(), output.is_ok());
+    output
+}
+
+/// Pins down what an async `#[timed]` body returns, so `?` in it converts
+/// errors to the function's error type
+#[doc(hidden)]
+pub fn returning<T, F: Future<Output = T>>(future: F) -> F {
+    future
+}
+
+/// Log and record one run. `#[timed]` functions call it
+#[doc(hidden)]
+pub fn report(kind: MetricKind, name: &'static str, elapsed: Duration, succeeded: bool) {
+    log::debug!(
+        "⏱️ {} {} {} in {:.1}ms",
+        if kind == MetricKind::Command { "Command" } else { "Job" },
+        name,
+        if succeeded { "succeeded" } else { "failed" },
+        millis(elapsed)
+    );
+    if let Some(metrics) = REGISTRY.get() {
+        metrics.record(kind, name, elapsed, succeeded);
+    }
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_reports_counts_and_percentiles() {
+        let metrics = Metrics::default();
+        for ms in 1..=100 {
+            metrics.record(MetricKind::Command, "get_recordings", Duration::from_millis(ms), ms != 100);
+        }
+        metrics.record(MetricKind::Job, "sync", Duration::from_secs(30), true);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot[0].name, "sync");
+        assert_eq!(snapshot[0].kind, MetricKind::Job);
+
+        let command = &snapshot[1];
+        assert_eq!(command.count, 100);
+        assert_eq!(command.failures, 1);
+        assert_eq!(command.p50_ms, 50.0);
+        assert_eq!(command.p95_ms, 95.0);
+        assert_eq!(command.max_ms, 100.0);
+
+        // Only the latest samples count towards the percentiles
+        for _ in 0..SAMPLES_KEPT {
+            metrics.record(MetricKind::Command, "get_recordings", Duration::from_millis(2), true);
+        }
+        let command = metrics.snapshot().into_iter().find(|m| m.name == "get_recordings").unwrap();
+        assert_eq!(command.p95_ms, 2.0);
+        assert_eq!(command.max_ms, 100.0);
+    }
+}
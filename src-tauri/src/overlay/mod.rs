@@ -0,0 +1,264 @@
+//! Live session data for stream overlays
+//!
+//! The hub follows live games through the existing backend events (game
+//! started/ended from the game detector, stats saved by save_computed_stats)
+//! and keeps a small "today" summary. The optional local HTTP server in
+//! `server` exposes it to OBS browser sources as JSON and an SSE stream.
+//! Characters come with their name, in English or the language a source
+//! asks for with `?lang=`.
+
+mod server;
+
+pub use server::{OverlayServer, DEFAULT_PORT};
+
+use crate::slippi::melee_ids::{self, Locale};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+/// How many undelivered events a slow SSE client may fall behind by
+const EVENT_BUFFER: usize = 32;
+
+/// A player as shown on the overlay
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverlayPlayer {
+    pub connect_code: Option<String>,
+    pub display_name: Option<String>,
+    pub character_id: i32,
+    pub character_name: String,
+}
+
+impl OverlayPlayer {
+    pub fn new(connect_code: Option<String>, display_name: Option<String>, character_id: i32) -> Self {
+        let mut player = Self { connect_code, display_name, character_id, character_name: String::new() };
+        player.localize(Locale::En);
+        player
+    }
+
+    fn localize(&mut self, locale: Locale) {
+        self.character_name = u8::try_from(self.character_id)
+            .map_or(melee_ids::UNKNOWN, |id| melee_ids::character_name_in(id, locale))
+            .to_string();
+    }
+}
+
+/// Result of a finished live game, from my point of view
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverlayGameResult {
+    pub recording_id: String,
+    /// None when there was no winner or I couldn't be identified
+    pub won: Option<bool>,
+    pub openings_per_kill: Option<f64>,
+    pub opponent: Option<OverlayPlayer>,
+}
+
+/// Session summary served by the overlay endpoint
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverlaySession {
+    /// Local date the counts belong to (YYYY-MM-DD)
+    pub date: String,
+    pub wins: u32,
+    pub losses: u32,
+    /// Whether a game is in progress
+    pub in_game: bool,
+    /// Opponent of the latest game
+    pub current_opponent: Option<OverlayPlayer>,
+    pub last_game: Option<OverlayGameResult>,
+}
+
+impl OverlaySession {
+    /// With character names in `locale`
+    pub fn localized(mut self, locale: Locale) -> Self {
+        if let Some(opponent) = self.current_opponent.as_mut() {
+            opponent.localize(locale);
+        }
+        if let Some(opponent) = self.last_game.as_mut().and_then(|game| game.opponent.as_mut()) {
+            opponent.localize(locale);
+        }
+        self
+    }
+}
+
+/// Session events pushed to SSE clients
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum OverlayEvent {
+    GameStarted { slp_path: String },
+    GameEnded { slp_path: String },
+    StatsCalculated(OverlayGameResult),
+}
+
+impl OverlayEvent {
+    /// With character names in `locale`
+    pub fn localized(mut self, locale: Locale) -> Self {
+        if let OverlayEvent::StatsCalculated(OverlayGameResult { opponent: Some(opponent), .. }) = &mut self {
+            opponent.localize(locale);
+        }
+        self
+    }
+
+    /// SSE event name
+    pub fn name(&self) -> &'static str {
+        match self {
+            OverlayEvent::GameStarted { .. } => "gameStarted",
+            OverlayEvent::GameEnded { .. } => "gameEnded",
+            OverlayEvent::StatsCalculated(_) => "statsCalculated",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct HubState {
+    session: OverlaySession,
+    /// .slp files seen live this session whose result hasn't been counted yet
+    /// (keeps historical syncs and recomputes out of the W/L)
+    pending_games: HashSet<String>,
+}
+
+/// Tracks the live session and fans out events to overlay clients
+pub struct OverlayHub {
+    state: Mutex<HubState>,
+    events: broadcast::Sender<OverlayEvent>,
+}
+
+impl Default for OverlayHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OverlayHub {
+    pub fn new() -> Self {
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        Self {
+            state: Mutex::new(HubState::default()),
+            events,
+        }
+    }
+
+    /// Current session summary
+    pub fn snapshot(&self) -> OverlaySession {
+        let mut state = self.lock();
+        roll_over_day(&mut state.session);
+        state.session.clone()
+    }
+
+    /// Receive events published after this call
+    pub fn subscribe(&self) -> broadcast::Receiver<OverlayEvent> {
+        self.events.subscribe()
+    }
+
+    /// A live game started writing its .slp
+    pub fn game_started(&self, slp_path: String) {
+        {
+            let mut state = self.lock();
+            state.session.in_game = true;
+            state.pending_games.insert(slp_path.clone());
+        }
+        self.publish(OverlayEvent::GameStarted { slp_path });
+    }
+
+    /// A live game finished
+    pub fn game_ended(&self, slp_path: String) {
+        self.lock().session.in_game = false;
+        self.publish(OverlayEvent::GameEnded { slp_path });
+    }
+
+    /// Stats were saved for a game. Only games seen live are counted, once each
+    pub fn stats_calculated(&self, slp_path: &str, result: OverlayGameResult) {
+        {
+            let mut state = self.lock();
+            if !state.pending_games.remove(slp_path) {
+                return;
+            }
+
+            let session = &mut state.session;
+            roll_over_day(session);
+            match result.won {
+                Some(true) => session.wins += 1,
+                Some(false) => session.losses += 1,
+                None => {}
+            }
+            if result.opponent.is_some() {
+                session.current_opponent = result.opponent.clone();
+            }
+            session.last_game = Some(result.clone());
+        }
+        self.publish(OverlayEvent::StatsCalculated(result));
+    }
+
+    fn publish(&self, event: OverlayEvent) {
+        // No receivers just means no overlay is connected
+        let _ = self.events.send(event);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HubState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Reset the counts when the local date changes
+fn roll_over_day(session: &mut OverlaySession) {
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    if session.date != today {
+        *session = OverlaySession {
+            date: today,
+            in_game: session.in_game,
+            ..Default::default()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(won: Option<bool>) -> OverlayGameResult {
+        OverlayGameResult {
+            recording_id: "game".to_string(),
+            won,
+            openings_per_kill: Some(4.5),
+            opponent: None,
+        }
+    }
+
+    #[test]
+    fn test_only_live_games_are_counted_once() {
+        let hub = OverlayHub::new();
+
+        // Historical sync of a game we never saw live
+        hub.stats_calculated("old.slp", result(Some(true)));
+        assert_eq!(hub.snapshot().wins, 0);
+
+        hub.game_started("live.slp".to_string());
+        assert!(hub.snapshot().in_game);
+        hub.game_ended("live.slp".to_string());
+        hub.stats_calculated("live.slp", result(Some(false)));
+        // Recomputing the same game doesn't count it again
+        hub.stats_calculated("live.slp", result(Some(false)));
+
+        let session = hub.snapshot();
+        assert!(!session.in_game);
+        assert_eq!((session.wins, session.losses), (0, 1));
+        assert_eq!(session.last_game.and_then(|g| g.openings_per_kill), Some(4.5));
+    }
+
+    #[test]
+    fn test_character_names_follow_the_requested_language() {
+        let hub = OverlayHub::new();
+        hub.game_started("live.slp".to_string());
+        let opponent = OverlayPlayer::new(Some("ABC#123".to_string()), None, 2);
+        assert_eq!(opponent.character_name, "Fox");
+        hub.stats_calculated("live.slp", OverlayGameResult { opponent: Some(opponent), ..result(Some(true)) });
+
+        let session = hub.snapshot().localized(Locale::Ja);
+        assert_eq!(session.current_opponent.map(|p| p.character_name).as_deref(), Some("フォックス"));
+        // The hub keeps English for other clients
+        let session = hub.snapshot();
+        assert_eq!(session.last_game.and_then(|g| g.opponent).map(|p| p.character_name).as_deref(), Some("Fox"));
+    }
+}
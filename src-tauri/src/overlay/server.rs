@@ -0,0 +1,206 @@
+//! Minimal local HTTP server for the overlay
+//!
+//! Bound to 127.0.0.1 only. Routes:
+//! - `GET /session` - current OverlaySession as JSON
+//! - `GET /events`  - SSE stream: a `session` event on connect, then every
+//!   OverlayEvent followed by the updated `session`
+//!
+//! Either takes `?lang=` (e.g. `ja`) for character names in that language.
+//!
+//! No CORS headers are sent: a website open in the user's browser must not be
+//! able to read the live session (opponents' connect codes) from localhost.
+//!
+//! Each connection holds a shutdown receiver, so stop() also ends open SSE streams.
+
+use super::{OverlayEvent, OverlayHub};
+use crate::commands::errors::Error;
+use crate::slippi::melee_ids::Locale;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, watch};
+
+/// Port used when the overlayServerPort setting is unset
+pub const DEFAULT_PORT: u16 = 7650;
+
+/// Requests larger than this are rejected (we only need the request line)
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// How long a client has to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Comment sent on idle SSE streams so proxies and OBS keep them open
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// A running overlay server
+pub struct OverlayServer {
+    port: u16,
+    shutdown: watch::Sender<bool>,
+}
+
+impl OverlayServer {
+    /// Bind to 127.0.0.1:port and start serving in the background
+    pub async fn start(hub: Arc<OverlayHub>, port: u16) -> Result<Self, Error> {
+        let listener = TcpListener::bind(("127.0.0.1", port)).await.map_err(|e| {
+            Error::InitializationError(format!("Failed to start overlay server on port {}: {}", port, e))
+        })?;
+        let port = listener.local_addr().map(|a| a.port()).unwrap_or(port);
+
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        tauri::async_runtime::spawn(accept_loop(listener, hub, shutdown_rx));
+
+        log::info!("📡 Overlay server listening on http://127.0.0.1:{}", port);
+        Ok(Self { port, shutdown })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Stop accepting connections and close open streams
+    pub fn stop(self) {
+        let _ = self.shutdown.send(true);
+        log::info!("📡 Overlay server on port {} stopped", self.port);
+    }
+}
+
+async fn accept_loop(listener: TcpListener, hub: Arc<OverlayHub>, mut shutdown: watch::Receiver<bool>) {
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => break,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    let hub = hub.clone();
+                    let shutdown = shutdown.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = handle_connection(stream, hub, shutdown).await {
+                            log::debug!("Overlay connection closed: {}", e);
+                        }
+                    });
+                }
+                Err(e) => log::warn!("Overlay server failed to accept connection: {}", e),
+            },
+        }
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    hub: Arc<OverlayHub>,
+    shutdown: watch::Receiver<bool>,
+) -> std::io::Result<()> {
+    let target = match tokio::time::timeout(REQUEST_TIMEOUT, read_request_target(&mut stream)).await {
+        Ok(result) => result?,
+        Err(_) => return Ok(()),
+    };
+    let (path, locale) = match target.as_deref().map(split_target) {
+        Some((path, locale)) => (Some(path), locale),
+        None => (None, Locale::En),
+    };
+
+    match path {
+        Some("/session") | Some("/") => {
+            let body = serde_json::to_vec(&hub.snapshot().localized(locale)).unwrap_or_default();
+            write_response(&mut stream, "200 OK", "application/json", &body).await
+        }
+        Some("/events") => stream_events(stream, hub, locale, shutdown).await,
+        Some(_) => write_response(&mut stream, "404 Not Found", "text/plain", b"Not found").await,
+        None => write_response(&mut stream, "400 Bad Request", "text/plain", b"Bad request").await,
+    }
+}
+
+/// Read the request head and return the target of a GET request
+async fn read_request_target(stream: &mut TcpStream) -> std::io::Result<Option<String>> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buffer.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 || buffer.len() + read > MAX_REQUEST_BYTES {
+            return Ok(None);
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    let head = String::from_utf8_lossy(&buffer);
+    let mut parts = head.lines().next().unwrap_or_default().split_whitespace();
+    if parts.next() != Some("GET") {
+        return Ok(None);
+    }
+
+    Ok(parts.next().map(str::to_string))
+}
+
+/// A request target's path, and the locale its `lang` parameter asks for
+fn split_target(target: &str) -> (&str, Locale) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let locale = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("lang="))
+        .map_or(Locale::En, Locale::from_tag);
+    (path, locale)
+}
+
+async fn write_response(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) -> std::io::Result<()> {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body).await?;
+    stream.shutdown().await
+}
+
+async fn stream_events(
+    mut stream: TcpStream,
+    hub: Arc<OverlayHub>,
+    locale: Locale,
+    mut shutdown: watch::Receiver<bool>,
+) -> std::io::Result<()> {
+    // Subscribe before the initial snapshot so nothing falls in between
+    let mut events = hub.subscribe();
+
+    stream
+        .write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+        )
+        .await?;
+    write_session(&mut stream, &hub, locale).await?;
+
+    let mut keepalive = tokio::time::interval(KEEPALIVE_INTERVAL);
+    keepalive.tick().await;
+
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => break,
+            _ = keepalive.tick() => stream.write_all(b": keepalive\n\n").await?,
+            received = events.recv() => match received {
+                Ok(event) => {
+                    write_event(&mut stream, event.localized(locale)).await?;
+                    write_session(&mut stream, &hub, locale).await?;
+                }
+                // Missed some events - the next session snapshot catches the client up
+                Err(broadcast::error::RecvError::Lagged(_)) => write_session(&mut stream, &hub, locale).await?,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+
+    stream.shutdown().await
+}
+
+async fn write_event(stream: &mut TcpStream, event: OverlayEvent) -> std::io::Result<()> {
+    let data = serde_json::to_string(&event).unwrap_or_default();
+    stream
+        .write_all(format!("event: {}\ndata: {}\n\n", event.name(), data).as_bytes())
+        .await
+}
+
+async fn write_session(stream: &mut TcpStream, hub: &OverlayHub, locale: Locale) -> std::io::Result<()> {
+    let data = serde_json::to_string(&hub.snapshot().localized(locale)).unwrap_or_default();
+    stream
+        .write_all(format!("event: session\ndata: {}\n\n", data).as_bytes())
+        .await
+}
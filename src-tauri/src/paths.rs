@@ -0,0 +1,215 @@
+//! One spelling per stored file path
+//!
+//! Paths reach the database from WalkDir (backslashes on Windows), from
+//! format!("{}/{}") (forward slashes) and from the frontend, and a file stored
+//! under two spellings is indexed twice. Every path the database stores or
+//! looks up goes through normalize: the canonical path of the nearest folder
+//! that exists, followed by the rest of the path. A file's key is the same
+//! before and after it's deleted, so a row can still be found to clean up.
+//!
+//! Paths handed to FFmpeg and ffprobe go through process_arg instead, which
+//! keeps them as OS strings and lets Windows open them past MAX_PATH.
+
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf, MAIN_SEPARATOR};
+
+/// Longest Windows path (in UTF-16 units) that opens without the long-path
+/// prefix: MAX_PATH less the terminating NUL
+const MAX_PATH: usize = 259;
+
+/// The one spelling of a path, whether or not the file exists
+pub fn normalize(path: impl AsRef<Path>) -> String {
+    let path = path.as_ref();
+    let components: Vec<Component> = path.components().collect();
+    for existing in (1..=components.len()).rev() {
+        let Ok(mut resolved) = std::fs::canonicalize(components[..existing].iter().collect::<PathBuf>()) else {
+            continue;
+        };
+        for component in &components[existing..] {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    resolved.pop();
+                }
+                other => resolved.push(other),
+            }
+        }
+        return with_separator(&strip_verbatim(&resolved.to_string_lossy()), MAIN_SEPARATOR);
+    }
+    with_separator(&path.to_string_lossy(), MAIN_SEPARATOR)
+}
+
+/// Whether `path` is `dir` or somewhere under it, however either is spelled
+pub fn is_within(path: impl AsRef<Path>, dir: impl AsRef<Path>) -> bool {
+    Path::new(&normalize(path)).starts_with(normalize(dir))
+}
+
+/// Whether two paths lead to the same existing file, e.g. two spellings
+/// differing in case on a case-insensitive filesystem
+pub fn same_file(a: impl AsRef<Path>, b: impl AsRef<Path>) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (std::fs::metadata(a), std::fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    // Windows canonicalizes to the spelling on disk
+    #[cfg(not(unix))]
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// A path as an argument for FFmpeg or another program the app starts. It's
+/// passed as the OS string, not a lossy copy, and on Windows a path too long
+/// for MAX_PATH gets the `\\?\` prefix, without which the program can't open it
+pub fn process_arg(path: impl AsRef<Path>) -> OsString {
+    let path = path.as_ref();
+    if cfg!(windows) {
+        if let Some(long) = path.to_str().and_then(long_path) {
+            return long.into();
+        }
+    }
+    path.as_os_str().to_owned()
+}
+
+/// An absolute Windows path past MAX_PATH with the long-path prefix. Windows
+/// takes prefixed paths literally, so `/` becomes `\` and paths with `.` or
+/// `..` components are left alone
+fn long_path(path: &str) -> Option<String> {
+    if path.encode_utf16().count() <= MAX_PATH || path.starts_with(r"\\?\") || path.starts_with(r"\\.\") {
+        return None;
+    }
+    let path = with_separator(path, '\\');
+    if path.split('\\').any(|component| component == "." || component == "..") {
+        return None;
+    }
+    if let Some(share) = path.strip_prefix(r"\\") {
+        Some(format!(r"\\?\UNC\{}", share))
+    } else if path.as_bytes().first().is_some_and(u8::is_ascii_alphabetic) && path.get(1..3) == Some(r":\") {
+        Some(format!(r"\\?\{}", path))
+    } else {
+        None
+    }
+}
+
+/// A new folder under the temp dir whose path is non-ASCII and past MAX_PATH,
+/// like a recordings folder under a CJK user name. Returns (root to remove, folder)
+#[cfg(test)]
+pub fn long_test_dir(name: &str) -> (std::path::PathBuf, std::path::PathBuf) {
+    let root = std::env::temp_dir().join(format!("buckwheat-{}-{}", name, std::process::id()));
+    let mut dir = root.clone();
+    while dir.to_string_lossy().chars().count() <= MAX_PATH {
+        dir.push("ユーザー録画フォルダ");
+    }
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&dir).unwrap();
+    (root, dir)
+}
+
+/// Windows canonicalizes to verbatim paths (`\\?\C:\...`), which other tools
+/// and older rows don't use
+fn strip_verbatim(path: &str) -> std::borrow::Cow<'_, str> {
+    if let Some(share) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", share).into()
+    } else {
+        path.strip_prefix(r"\\?\").unwrap_or(path).into()
+    }
+}
+
+/// Use `separator` between components, once. With `\` (Windows) `/` is a
+/// separator too and a leading `\\` (a network share) is kept
+fn with_separator(path: &str, separator: char) -> String {
+    let is_separator = |c: char| c == separator || (separator == '\\' && c == '/');
+    let share = separator == '\\' && path.chars().take(2).filter(|&c| is_separator(c)).count() == 2;
+
+    let mut normalized = String::with_capacity(path.len());
+    if share {
+        normalized.push(separator);
+    }
+    let mut previous_separator = false;
+    for c in path.chars() {
+        if is_separator(c) {
+            if !previous_separator {
+                normalized.push(separator);
+            }
+            previous_separator = true;
+        } else {
+            normalized.push(c);
+            previous_separator = false;
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_separator() {
+        assert_eq!(with_separator(r"C:\Videos/Buckwheat\\Game_1.mp4", '\\'), r"C:\Videos\Buckwheat\Game_1.mp4");
+        assert_eq!(with_separator(r"\\nas\videos/Game_1.mp4", '\\'), r"\\nas\videos\Game_1.mp4");
+        assert_eq!(with_separator("//home/me//videos/Game_1.mp4", '/'), "/home/me/videos/Game_1.mp4");
+        // A backslash is part of a Unix file name
+        assert_eq!(with_separator(r"/home/me/odd\name.mp4", '/'), r"/home/me/odd\name.mp4");
+        assert_eq!(strip_verbatim(r"\\?\C:\Videos\Game_1.mp4"), r"C:\Videos\Game_1.mp4");
+        assert_eq!(strip_verbatim(r"\\?\UNC\nas\videos\Game_1.mp4"), r"\\nas\videos\Game_1.mp4");
+    }
+
+    #[test]
+    fn test_normalize_is_the_same_once_the_file_is_gone() {
+        let (root, dir) = long_test_dir("normalize");
+        let video = dir.join("Game_1.mp4");
+        std::fs::write(&video, b"video").unwrap();
+        let spelling = dir.join("Clips/../Game_1.mp4");
+
+        let existing = normalize(&video);
+        assert_eq!(normalize(&spelling), existing);
+        std::fs::remove_file(&video).unwrap();
+        assert_eq!(normalize(&video), existing);
+        assert_eq!(normalize(&spelling), existing);
+        // A missing folder is followed as written
+        let expected = format!("{}{}Clips{}Game_1.mp4", normalize(&dir), MAIN_SEPARATOR, MAIN_SEPARATOR);
+        assert_eq!(normalize(dir.join("Clips/Game_1.mp4")), expected);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_same_file_and_is_within() {
+        let (root, dir) = long_test_dir("same-file");
+        let clips = dir.join("Clips");
+        std::fs::create_dir(&clips).unwrap();
+        std::fs::write(clips.join("a.mp4"), b"a").unwrap();
+        std::fs::write(dir.join("b.mp4"), b"b").unwrap();
+
+        assert!(same_file(clips.join("a.mp4"), dir.join("Clips/./a.mp4")));
+        assert!(!same_file(clips.join("a.mp4"), dir.join("b.mp4")));
+        assert!(!same_file(clips.join("a.mp4"), clips.join("missing.mp4")));
+        assert!(is_within(clips.join("a.mp4"), &clips));
+        assert!(!is_within(dir.join("b.mp4"), &clips));
+        // A sibling folder sharing the name's start isn't inside
+        assert!(!is_within(dir.join("Clips2/a.mp4"), &clips));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_long_path() {
+        // A CJK user name and a folder name past MAX_PATH
+        let folder = "録画フォルダ".repeat(45);
+        let long = format!(r"C:\Users\ユーザー\{}/Game_1.mp4", folder);
+        assert_eq!(long_path(&long), Some(format!(r"\\?\C:\Users\ユーザー\{}\Game_1.mp4", folder)));
+        let share = format!(r"\\nas\videos\{}\Game_1.mp4", folder);
+        assert_eq!(long_path(&share), Some(format!(r"\\?\UNC\nas\videos\{}\Game_1.mp4", folder)));
+
+        assert_eq!(long_path(r"C:\Videos\Game_1.mp4"), None);
+        assert_eq!(long_path(&format!(r"\\?\C:\{}\Game_1.mp4", folder)), None);
+        assert_eq!(long_path(&format!(r"C:\{}\..\Game_1.mp4", folder)), None);
+        assert_eq!(long_path(&format!(r"{}\Game_1.mp4", folder)), None);
+    }
+}
@@ -0,0 +1,353 @@
+//! The play session behind the live dashboard
+//!
+//! A session starts when watching for games begins (start_watching) and ends
+//! when it stops. A restart doesn't end it: runtime_state resumes it with
+//! the games stored since it started. Games are added as save_computed_stats saves them; like the
+//! overlay's session, only games whose replay was created while watching
+//! count, so library syncs and recomputes don't. After each game the summary
+//! is emitted as session-updated, with my stats compared against my
+//! BASELINE_DAYS averages.
+
+use crate::app_state::AppState;
+use crate::database::{self, MyGameRow, MyResult, StatAverages};
+use crate::events;
+use crate::timestamps;
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use tauri::{Emitter, Manager};
+
+/// Days of history the session is compared against
+pub const BASELINE_DAYS: i64 = 30;
+
+/// My side of a game finished this session
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionGame {
+    pub recording_id: String,
+    /// None when there was no winner
+    pub won: Option<bool>,
+    pub my_character_id: i32,
+    pub opponent_character_id: Option<i32>,
+    pub l_cancel_success_count: Option<i32>,
+    pub l_cancel_fail_count: Option<i32>,
+    pub openings_per_kill: Option<f64>,
+    pub damage_per_opening: Option<f64>,
+    pub neutral_win_ratio: Option<f64>,
+    pub inputs_per_minute: Option<f64>,
+}
+
+/// Record in one matchup (my character against theirs)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchupRecord {
+    pub my_character_id: i32,
+    pub opponent_character_id: Option<i32>,
+    pub games: u32,
+    pub wins: u32,
+    pub losses: u32,
+}
+
+/// A stat this session against my baseline
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatDelta {
+    /// "lCancelPercent", "openingsPerKill", "damagePerOpening",
+    /// "neutralWinPercent" or "inputsPerMinute"
+    pub stat: &'static str,
+    pub session: Option<f64>,
+    pub baseline: Option<f64>,
+    /// session - baseline, when both are known
+    pub delta: Option<f64>,
+}
+
+/// What get_current_session returns and session-updated carries
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    pub started_at: String,
+    pub games_played: u32,
+    pub wins: u32,
+    pub losses: u32,
+    /// Most played first
+    pub matchups: Vec<MatchupRecord>,
+    pub deltas: Vec<StatDelta>,
+    /// Games the baseline was averaged over
+    pub baseline_games: i64,
+}
+
+impl From<MyGameRow> for SessionGame {
+    fn from(row: MyGameRow) -> Self {
+        Self {
+            recording_id: row.recording_id,
+            won: match row.my_result {
+                MyResult::Win => Some(true),
+                MyResult::Loss => Some(false),
+                MyResult::Unknown => None,
+            },
+            my_character_id: row.my_character_id,
+            opponent_character_id: row.opponent_character_id,
+            l_cancel_success_count: row.l_cancel_success_count,
+            l_cancel_fail_count: row.l_cancel_fail_count,
+            openings_per_kill: row.openings_per_kill,
+            damage_per_opening: row.damage_per_opening,
+            neutral_win_ratio: row.neutral_win_ratio,
+            inputs_per_minute: row.inputs_per_minute,
+        }
+    }
+}
+
+/// Games of the current session
+#[derive(Debug)]
+pub struct PlaySession {
+    started_at: DateTime<Utc>,
+    /// Replays created while watching whose stats haven't been saved yet
+    pending_games: HashSet<String>,
+    games: Vec<SessionGame>,
+}
+
+impl PlaySession {
+    pub fn new(started_at: DateTime<Utc>) -> Self {
+        Self { started_at, pending_games: HashSet::new(), games: Vec::new() }
+    }
+
+    /// A session that started earlier (before a restart), with the games it had
+    pub fn resumed(started_at: DateTime<Utc>, games: Vec<SessionGame>) -> Self {
+        Self { started_at, pending_games: HashSet::new(), games }
+    }
+
+    /// A live game started writing its .slp
+    pub fn game_started(&mut self, slp_path: String) {
+        self.pending_games.insert(slp_path);
+    }
+
+    /// Stats were saved for a game. Returns whether it's one of this
+    /// session's (each is counted once)
+    pub fn stats_calculated(&mut self, slp_path: &str, game: SessionGame) -> bool {
+        if !self.pending_games.remove(slp_path) {
+            return false;
+        }
+        self.games.push(game);
+        true
+    }
+
+    /// Summarize the session against my averages over the baseline period
+    pub fn summary(&self, baseline: &StatAverages) -> SessionSummary {
+        let mut matchups: BTreeMap<(i32, Option<i32>), MatchupRecord> = BTreeMap::new();
+        for game in &self.games {
+            let record = matchups
+                .entry((game.my_character_id, game.opponent_character_id))
+                .or_insert_with(|| MatchupRecord {
+                    my_character_id: game.my_character_id,
+                    opponent_character_id: game.opponent_character_id,
+                    games: 0,
+                    wins: 0,
+                    losses: 0,
+                });
+            record.games += 1;
+            match game.won {
+                Some(true) => record.wins += 1,
+                Some(false) => record.losses += 1,
+                None => {}
+            }
+        }
+        let mut matchups: Vec<MatchupRecord> = matchups.into_values().collect();
+        matchups.sort_by_key(|m| std::cmp::Reverse(m.games));
+
+        let session = self.averages();
+        let delta = |stat, session: Option<f64>, baseline: Option<f64>| StatDelta {
+            stat,
+            session,
+            baseline,
+            delta: session.zip(baseline).map(|(s, b)| s - b),
+        };
+
+        SessionSummary {
+            started_at: self.started_at.to_rfc3339(),
+            games_played: self.games.len() as u32,
+            wins: self.games.iter().filter(|g| g.won == Some(true)).count() as u32,
+            losses: self.games.iter().filter(|g| g.won == Some(false)).count() as u32,
+            matchups,
+            deltas: vec![
+                delta("lCancelPercent", session.l_cancel_percent, baseline.l_cancel_percent),
+                delta("openingsPerKill", session.openings_per_kill, baseline.openings_per_kill),
+                delta("damagePerOpening", session.damage_per_opening, baseline.damage_per_opening),
+                delta("neutralWinPercent", session.neutral_win_percent, baseline.neutral_win_percent),
+                delta("inputsPerMinute", session.inputs_per_minute, baseline.inputs_per_minute),
+            ],
+            baseline_games: baseline.games,
+        }
+    }
+
+    /// Per-game averages, defined like get_my_stat_averages
+    fn averages(&self) -> StatAverages {
+        let average = |values: Vec<f64>| {
+            (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+        };
+        let of = |stat: fn(&SessionGame) -> Option<f64>| average(self.games.iter().filter_map(stat).collect());
+
+        StatAverages {
+            games: self.games.len() as i64,
+            l_cancel_percent: of(|g| {
+                let (success, fail) = g.l_cancel_success_count.zip(g.l_cancel_fail_count)?;
+                (success + fail > 0).then(|| f64::from(success) / f64::from(success + fail) * 100.0)
+            }),
+            openings_per_kill: of(|g| g.openings_per_kill),
+            damage_per_opening: of(|g| g.damage_per_opening),
+            neutral_win_percent: of(|g| g.neutral_win_ratio.map(|r| r * 100.0)),
+            inputs_per_minute: of(|g| g.inputs_per_minute),
+        }
+    }
+}
+
+/// Start a session, unless one is running already (watching restarted)
+pub fn start(state: &AppState) {
+    if let Ok(mut session) = state.play_session.lock() {
+        session.get_or_insert_with(|| PlaySession::new(Utc::now()));
+    }
+}
+
+/// Pick up a session the last run left unfinished. Its games are the ones
+/// played since it started, as stored. Does nothing if a session is running
+pub fn resume(state: &AppState, conn: &Connection, started_at: DateTime<Utc>) -> rusqlite::Result<()> {
+    let games = database::get_my_games_since(conn, &timestamps::format(started_at))?;
+    if let Ok(mut session) = state.play_session.lock() {
+        session.get_or_insert_with(|| PlaySession::resumed(started_at, games.into_iter().map(SessionGame::from).collect()));
+    }
+    Ok(())
+}
+
+/// When the current session started, None when not watching
+pub fn started_at(state: &AppState) -> Option<DateTime<Utc>> {
+    state.play_session.lock().ok().and_then(|session| session.as_ref().map(|s| s.started_at))
+}
+
+/// End the session
+pub fn end(state: &AppState) {
+    if let Ok(mut session) = state.play_session.lock() {
+        *session = None;
+    }
+}
+
+/// Note a game that started while watching
+pub fn game_started(state: &AppState, slp_path: String) {
+    if let Ok(mut session) = state.play_session.lock() {
+        if let Some(session) = session.as_mut() {
+            session.game_started(slp_path);
+        }
+    }
+}
+
+/// Add a game whose stats were just saved, and tell the frontend if it
+/// changed the session. `conn` is the connection the stats were saved on
+pub fn stats_calculated(app: &tauri::AppHandle, conn: &Connection, slp_path: &str, game: SessionGame) {
+    let state = app.state::<AppState>();
+    let counted = match state.play_session.lock() {
+        Ok(mut session) => session.as_mut().is_some_and(|s| s.stats_calculated(slp_path, game)),
+        Err(_) => false,
+    };
+    if !counted {
+        return;
+    }
+
+    match summary(&state, conn) {
+        Ok(Some(summary)) => {
+            if let Err(e) = app.emit(events::session::UPDATED, &summary) {
+                log::warn!("Failed to emit {}: {}", events::session::UPDATED, e);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => log::warn!("Failed to summarize the session: {}", e),
+    }
+}
+
+/// The current session's summary, None when not watching
+pub fn summary(state: &AppState, conn: &Connection) -> rusqlite::Result<Option<SessionSummary>> {
+    let since = timestamps::format(Utc::now() - chrono::Duration::days(BASELINE_DAYS));
+    let baseline = database::get_my_stat_averages(conn, &since)?;
+    Ok(state
+        .play_session
+        .lock()
+        .ok()
+        .and_then(|session| session.as_ref().map(|s| s.summary(&baseline))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(slp: &str, won: Option<bool>, opponent: i32, l_cancels: (i32, i32)) -> (String, SessionGame) {
+        let game = SessionGame {
+            recording_id: slp.to_string(),
+            won,
+            my_character_id: 2,
+            opponent_character_id: Some(opponent),
+            l_cancel_success_count: Some(l_cancels.0),
+            l_cancel_fail_count: Some(l_cancels.1),
+            openings_per_kill: Some(4.0),
+            ..Default::default()
+        };
+        (slp.to_string(), game)
+    }
+
+    #[test]
+    fn test_only_live_games_count_once() {
+        let mut session = PlaySession::new(Utc::now());
+        session.game_started("live.slp".to_string());
+
+        let (slp, live) = game("live.slp", Some(true), 9, (8, 2));
+        assert!(session.stats_calculated(&slp, live.clone()));
+        assert!(!session.stats_calculated(&slp, live));
+        let (slp, old) = game("old.slp", Some(true), 9, (8, 2));
+        assert!(!session.stats_calculated(&slp, old));
+
+        assert_eq!(session.summary(&StatAverages::default()).games_played, 1);
+    }
+
+    #[test]
+    fn test_resumed_session_keeps_stored_games() {
+        let row = |id: &str, my_result| MyGameRow {
+            recording_id: id.to_string(),
+            my_result,
+            my_character_id: 2,
+            opponent_character_id: Some(9),
+            ..Default::default()
+        };
+        let games = vec![row("1", MyResult::Win), row("2", MyResult::Loss), row("3", MyResult::Unknown)];
+        let mut session = PlaySession::resumed(Utc::now(), games.into_iter().map(SessionGame::from).collect());
+
+        // Live games keep counting after the restart
+        session.game_started("4.slp".to_string());
+        let (slp, live) = game("4.slp", Some(true), 9, (8, 2));
+        assert!(session.stats_calculated(&slp, live));
+
+        let summary = session.summary(&StatAverages::default());
+        assert_eq!((summary.games_played, summary.wins, summary.losses), (4, 2, 1));
+    }
+
+    #[test]
+    fn test_summary_records_matchups_and_deltas() {
+        let mut session = PlaySession::new(Utc::now());
+        for (slp, won, opponent, l_cancels) in [
+            ("1.slp", Some(true), 9, (9, 1)),
+            ("2.slp", Some(false), 9, (7, 3)),
+            ("3.slp", None, 20, (0, 0)),
+        ] {
+            session.game_started(slp.to_string());
+            let (slp, g) = game(slp, won, opponent, l_cancels);
+            session.stats_calculated(&slp, g);
+        }
+
+        let baseline = StatAverages { games: 40, l_cancel_percent: Some(75.0), ..Default::default() };
+        let summary = session.summary(&baseline);
+        assert_eq!((summary.games_played, summary.wins, summary.losses), (3, 1, 1));
+        let matchups: Vec<_> = summary.matchups.iter().map(|m| (m.opponent_character_id, m.wins, m.losses)).collect();
+        assert_eq!(matchups, vec![(Some(9), 1, 1), (Some(20), 0, 0)]);
+
+        // The game without L-cancels doesn't pull the rate down
+        let l_cancel = &summary.deltas[0];
+        assert_eq!((l_cancel.session, l_cancel.delta), (Some(80.0), Some(5.0)));
+        // No baseline, no delta
+        assert_eq!(summary.deltas[1].delta, None);
+    }
+}
@@ -0,0 +1,158 @@
+//! Reviewing a replay in playback Dolphin alongside its recording
+//!
+//! start_playback_review opens the replay the way launch_replay does, but
+//! with `--cout`, so Dolphin writes what it's playing to stdout: a
+//! `[CURRENT_FRAME] <frame>` line for every frame shown, among others. A
+//! thread reads those lines and emits each frame (batched) with the time it
+//! is at in the recording, so the frontend scrubs the video along with
+//! Dolphin. Frames are followed as Dolphin shows them, so slow motion, frame
+//! advance, pausing and rewinding in Dolphin all carry over.
+//!
+//! One review runs at a time. It ends when Dolphin closes (stdout ends) or
+//! the app stops it (stop_playback_review, starting another review, or
+//! exiting, which close Dolphin); either way ENDED is emitted once.
+
+use crate::app_state::AppState;
+use crate::commands::errors::Error;
+use crate::event_batch;
+use crate::events::playback as playback_events;
+use crate::slippi::frames::FIRST_FRAME;
+use serde::Serialize;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, ChildStdout};
+use tauri::{Emitter, Manager};
+
+const FRAMES_PER_SECOND: f64 = 60.0;
+
+/// The review in progress: the playback Dolphin showing its replay
+#[derive(Debug)]
+pub struct PlaybackReview {
+    id: String,
+    child: Child,
+}
+
+/// What playback-frame carries
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackFrame {
+    pub recording_id: String,
+    /// Slippi frame number (the game starts on frame -123)
+    pub frame: i32,
+    /// Where that frame is in the recording's video, in seconds
+    pub video_time: f64,
+}
+
+/// A line of Dolphin's `--cout` output we act on
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DolphinMessage {
+    CurrentFrame(i32),
+    /// The replay finished (Dolphin stays open on its last frame)
+    GameEnd,
+}
+
+fn parse_line(line: &str) -> Option<DolphinMessage> {
+    let (tag, value) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+    match tag {
+        "[CURRENT_FRAME]" => value.trim().parse().ok().map(DolphinMessage::CurrentFrame),
+        "[GAME_END_FRAME]" | "[NO_GAME]" => Some(DolphinMessage::GameEnd),
+        _ => None,
+    }
+}
+
+/// Where `frame` is in the video, for a game that starts `video_start_secs` into it
+fn video_time(video_start_secs: f64, frame: i32) -> f64 {
+    (video_start_secs + f64::from(frame - FIRST_FRAME) / FRAMES_PER_SECOND).max(0.0)
+}
+
+/// Follow a playback Dolphin spawned with `--cout` and piped stdout,
+/// replacing any review in progress
+pub fn start(app: &tauri::AppHandle, mut child: Child, recording_id: String, video_start_secs: f64) -> Result<(), Error> {
+    let Some(stdout) = child.stdout.take() else {
+        let _ = child.kill();
+        return Err(Error::InitializationError("Playback Dolphin's output isn't available".to_string()));
+    };
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let state = app.state::<AppState>();
+    stop(&state);
+    state
+        .playback_review
+        .lock()
+        .map_err(|e| Error::InitializationError(format!("Failed to lock playback review: {}", e)))?
+        .replace(PlaybackReview { id: id.clone(), child });
+
+    let app = app.clone();
+    std::thread::Builder::new()
+        .name("playback-review".to_string())
+        .spawn(move || follow(&app, stdout, &id, &recording_id, video_start_secs))
+        .map_err(|e| Error::InitializationError(format!("Failed to follow playback Dolphin: {}", e)))?;
+    Ok(())
+}
+
+/// Close the review's Dolphin, if a review is running (its thread then ends it)
+pub fn stop(state: &AppState) {
+    let review = state.playback_review.lock().ok().and_then(|mut review| review.take());
+    if let Some(mut review) = review {
+        if let Err(e) = review.child.kill() {
+            log::warn!("Failed to close playback Dolphin: {}", e);
+        }
+        let _ = review.child.wait();
+    }
+}
+
+fn follow(app: &tauri::AppHandle, stdout: ChildStdout, id: &str, recording_id: &str, video_start_secs: f64) {
+    let mut last_frame = None;
+    for line in BufReader::new(stdout).lines() {
+        let Ok(line) = line else { break };
+        match parse_line(&line) {
+            // Dolphin repeats the frame while paused
+            Some(DolphinMessage::CurrentFrame(frame)) if last_frame != Some(frame) => {
+                last_frame = Some(frame);
+                let payload = PlaybackFrame {
+                    recording_id: recording_id.to_string(),
+                    frame,
+                    video_time: video_time(video_start_secs, frame),
+                };
+                event_batch::emit_batched(app, playback_events::FRAME, payload);
+            }
+            Some(DolphinMessage::GameEnd) => log::debug!("🐬 Playback of {} reached the end", recording_id),
+            _ => {}
+        }
+    }
+
+    // Dolphin closed by itself, unless stop() got to it first
+    let state = app.state::<AppState>();
+    let review = state
+        .playback_review
+        .lock()
+        .ok()
+        .and_then(|mut review| review.take_if(|review| review.id == id));
+    if let Some(mut review) = review {
+        let _ = review.child.wait();
+    }
+    log::info!("🐬 Playback review of {} ended", recording_id);
+    if let Err(e) = app.emit(playback_events::ENDED, recording_id) {
+        log::error!("Failed to emit {} event: {:?}", playback_events::ENDED, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_frames_from_dolphin_output() {
+        assert_eq!(parse_line("[CURRENT_FRAME] 240\n"), Some(DolphinMessage::CurrentFrame(240)));
+        assert_eq!(parse_line("[CURRENT_FRAME] -123"), Some(DolphinMessage::CurrentFrame(-123)));
+        assert_eq!(parse_line("[NO_GAME]"), Some(DolphinMessage::GameEnd));
+        assert_eq!(parse_line("[GAME_END_FRAME] 5000"), Some(DolphinMessage::GameEnd));
+        assert_eq!(parse_line("[PLAYBACK_START_FRAME] -123"), None);
+        assert_eq!(parse_line("[CURRENT_FRAME] soon"), None);
+
+        // The game's first frame is at its start in the video
+        assert_eq!(video_time(12.5, FIRST_FRAME), 12.5);
+        assert_eq!(video_time(12.5, FIRST_FRAME + 120), 14.5);
+        // A video that started after the game can't go below zero
+        assert_eq!(video_time(-1.0, FIRST_FRAME), 0.0);
+    }
+}
@@ -0,0 +1,209 @@
+//! Recording character select before a game
+//!
+//! A replay is only created once the game starts, after character select and
+//! stage bans, so auto-recordings begin with the countdown. With preRollMode
+//! on, recording starts as soon as the game window appears (and again after
+//! each game while it stays open), into a PreRoll_ file. The next replay to
+//! be created adopts that recording instead of starting a new one, noting
+//! how far into it the game starts.
+//!
+//! When the recording stops, everything but the last preRollSeconds before
+//! the game is cut off and the video takes the replay's name. The cut is a
+//! stream copy, which starts on a keyframe, so the amount actually cut is
+//! measured from the durations. Where the game starts in the final video is
+//! stored with the recording's games, which the replay viewer, highlights and
+//! opening replays in Dolphin go by.
+//!
+//! A pre-roll no game adopts is thrown away when the window closes, and
+//! restarted every PRE_ROLL_MAX_AGE so sitting in menus doesn't fill the disk.
+
+use crate::app_state::AppState;
+use crate::clip_processor;
+use crate::commands::errors::Error;
+use crate::commands::recording::{
+    configure_target_window, resolve_recording_quality, start_recording_with_quality, stop_capture,
+};
+use crate::health::{self, Subsystem};
+use crate::library;
+use crate::commands::settings::replay_only_mode;
+use crate::settings::{self, Settings};
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tauri_plugin_store::StoreExt;
+
+/// Settings keys
+const PRE_ROLL_MODE_KEY: &str = "preRollMode";
+const PRE_ROLL_SECONDS_KEY: &str = "preRollSeconds";
+const AUTO_START_KEY: &str = "autoStartRecording";
+
+/// Longest a pre-roll runs without a game before it's started over
+const PRE_ROLL_MAX_AGE: Duration = Duration::from_secs(10 * 60);
+/// Cuts shorter than this aren't worth an FFmpeg run
+const MIN_CUT_SECS: f64 = 1.0;
+
+/// A recording started before its game
+#[derive(Debug, Clone)]
+pub struct PreRoll {
+    pub output_path: String,
+    pub started_at: Instant,
+    /// The replay that adopted it, and when its game starts (seconds into the video)
+    pub game: Option<(String, f64)>,
+}
+
+/// Start a pre-roll if it's enabled, the game window is open and nothing is
+/// recording
+pub fn start(app: &tauri::AppHandle) {
+    if !enabled(app) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(health::scope(Subsystem::Recorder, async move {
+        loop {
+            let Some(output_path) = begin(&app).await else { return };
+            tokio::time::sleep(PRE_ROLL_MAX_AGE).await;
+            if !discard_if(&app, Some(&output_path)) {
+                return;
+            }
+            log::info!("🎞️ No game after {} minutes, starting the pre-roll over", PRE_ROLL_MAX_AGE.as_secs() / 60);
+        }
+    }));
+}
+
+/// Throw away the pre-roll if no game adopted it (the game window closed)
+pub fn discard(app: &tauri::AppHandle) {
+    discard_if(app, None);
+}
+
+/// Let a new game take over the pre-roll
+/// Returns when the pre-roll started and how far into it the game starts
+pub(crate) fn adopt(state: &AppState, slp_path: &str) -> Option<(Instant, f64)> {
+    let mut pre_roll = state.pre_roll.lock().ok()?;
+    let pre_roll = pre_roll.as_mut().filter(|p| p.game.is_none())?;
+    let offset = pre_roll.started_at.elapsed().as_secs_f64();
+    pre_roll.game = Some((slp_path.to_string(), offset));
+    log::info!("🎞️ Game started {:.1}s into the pre-roll: {}", offset, slp_path);
+    Some((pre_roll.started_at, offset))
+}
+
+/// Whether a pre-roll is recording, waiting for its game
+pub(crate) fn waiting(state: &AppState) -> bool {
+    state.pre_roll.lock().map(|p| p.as_ref().is_some_and(|p| p.game.is_none())).unwrap_or(false)
+}
+
+/// Cut a stopped pre-roll down to the last preRollSeconds before its game,
+/// under the replay's name. Returns the final path and how much was cut
+pub(crate) fn finish(app: &tauri::AppHandle, output_path: &str, slp_path: &str, game_offset: f64) -> (String, f64) {
+    let stem = Path::new(slp_path).file_stem().and_then(|s| s.to_str()).unwrap_or("recording");
+    let final_path = Path::new(output_path).with_file_name(format!("{}.mp4", stem)).to_string_lossy().to_string();
+
+    let cut = (game_offset - f64::from(pre_roll_seconds(app))).max(0.0);
+    if cut >= MIN_CUT_SECS {
+        match trim(output_path, &final_path, cut) {
+            Ok(actual_cut) => {
+                if let Err(e) = std::fs::remove_file(output_path) {
+                    log::warn!("Failed to remove the untrimmed pre-roll {}: {}", output_path, e);
+                }
+                log::info!("🎞️ Cut {:.1}s of character select from {}", actual_cut, final_path);
+                return (final_path, actual_cut);
+            }
+            Err(e) => {
+                let _ = std::fs::remove_file(&final_path);
+                log::warn!("Failed to trim the pre-roll, keeping all of it: {}", e);
+            }
+        }
+    }
+
+    match std::fs::rename(output_path, &final_path) {
+        Ok(()) => (final_path, 0.0),
+        Err(e) => {
+            log::warn!("Failed to rename the pre-roll {}: {}", output_path, e);
+            (output_path.to_string(), 0.0)
+        }
+    }
+}
+
+/// Returns how much was actually cut from the start
+fn trim(input_path: &str, output_path: &str, cut: f64) -> Result<f64, Error> {
+    let before = clip_processor::probe_video(input_path)?.duration_secs;
+    clip_processor::trim_start(input_path, output_path, cut)?;
+    let after = clip_processor::probe_video(output_path)?.duration_secs;
+    Ok((before - after).clamp(0.0, cut))
+}
+
+/// Start recording into a new PreRoll_ file. Returns its path
+async fn begin(app: &tauri::AppHandle) -> Option<String> {
+    let state = app.state::<AppState>();
+    let window_open = state.game_window_open.load(Ordering::SeqCst);
+    let idle = state.recorder.lock().map(|r| r.is_none()).unwrap_or(false);
+    let watching = state.game_detector.lock().map(|d| d.is_some()).unwrap_or(false);
+    if !window_open || !idle || !watching {
+        return None;
+    }
+
+    let recording_dir = library::get_recording_directory(app).await.ok()?;
+    let output_path = format!("{}/PreRoll_{}.mp4", recording_dir, chrono::Local::now().format("%Y%m%dT%H%M%S"));
+    let quality = resolve_recording_quality(&state).ok()?;
+    configure_target_window(&state);
+    if let Err(e) = start_recording_with_quality(app, &state, &output_path, quality) {
+        log::warn!("Failed to start the pre-roll: {}", e);
+        return None;
+    }
+
+    if let Ok(mut pre_roll) = state.pre_roll.lock() {
+        *pre_roll = Some(PreRoll { output_path: output_path.clone(), started_at: Instant::now(), game: None });
+    }
+    log::info!("🎞️ Recording character select into {}", output_path);
+    Some(output_path)
+}
+
+/// Stop and delete the pre-roll (only the one at `output_path`, if given)
+/// unless a game adopted it. Returns whether it was discarded
+fn discard_if(app: &tauri::AppHandle, output_path: Option<&str>) -> bool {
+    let state = app.state::<AppState>();
+    // Holding the recorder keeps a new game from starting a recording meanwhile
+    let Ok(mut recorder) = state.recorder.lock() else { return false };
+    let pre_roll = state.pre_roll.lock().ok().and_then(|mut pre_roll| {
+        let discardable = pre_roll
+            .as_ref()
+            .is_some_and(|p| p.game.is_none() && output_path.is_none_or(|path| path == p.output_path));
+        if discardable {
+            pre_roll.take()
+        } else {
+            None
+        }
+    });
+    let Some(pre_roll) = pre_roll else { return false };
+
+    if let Some(active) = recorder.as_mut() {
+        if let Err(e) = stop_capture(app, &state, active.as_mut()) {
+            log::warn!("Failed to stop the pre-roll: {}", e);
+        }
+    }
+    *recorder = None;
+    drop(recorder);
+    state.work_gate.set_recording(false);
+
+    if let Err(e) = std::fs::remove_file(&pre_roll.output_path) {
+        log::warn!("Failed to delete the pre-roll {}: {}", pre_roll.output_path, e);
+    }
+    true
+}
+
+fn enabled(app: &tauri::AppHandle) -> bool {
+    let Ok(store) = app.store("settings.json") else { return false };
+    let get = |key: &str| store.get(key).and_then(|v| v.as_bool());
+    get(PRE_ROLL_MODE_KEY).unwrap_or(false) && get(AUTO_START_KEY).unwrap_or(true) && !replay_only_mode(app)
+}
+
+/// Character select kept before the game, in seconds
+fn pre_roll_seconds(app: &tauri::AppHandle) -> u32 {
+    let range = settings::PRE_ROLL_SECONDS;
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(PRE_ROLL_SECONDS_KEY))
+        .and_then(|v| v.as_u64())
+        .map(|v| u32::try_from(v).unwrap_or(u32::MAX).clamp(*range.start(), *range.end()))
+        .unwrap_or(Settings::default().pre_roll_seconds)
+}
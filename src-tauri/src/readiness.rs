@@ -0,0 +1,350 @@
+//! Pre-game readiness check
+//!
+//! One report covering everything auto-recording depends on: the Slippi
+//! folder and its watcher, FFmpeg, free disk space, the game window, the audio
+//! device and the recorder backend. Every probe runs concurrently under its
+//! own timeout, so a hung probe (an unreachable OBS, a stuck audio driver)
+//! shows up as a failed check instead of holding up the report.
+//!
+//! The check also runs once each time the game window appears, when the
+//! readinessCheckOnGameWindow setting is on; failures are sent to the frontend
+//! as READINESS_WARNING.
+
+use crate::app_state::AppState;
+use crate::commands::settings::replay_only_mode;
+use crate::events;
+use crate::game_session::{self, SessionEvent};
+use crate::library;
+use crate::pre_roll;
+use crate::warm_start;
+use crate::window_detector;
+use serde::Serialize;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+
+/// Longest any single probe may take (OBS needs up to 8s to answer)
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Below this much free space recordings are likely to be cut short
+pub(crate) const MIN_FREE_SPACE: u64 = 2 * 1024 * 1024 * 1024;
+/// Below this much free space a long session may not fit
+const LOW_FREE_SPACE: u64 = 10 * 1024 * 1024 * 1024;
+
+/// Settings key for running the check when the game window appears (on by default)
+const AUTO_CHECK_KEY: &str = "readinessCheckOnGameWindow";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReadinessCheck {
+    SlippiFolder,
+    Ffmpeg,
+    DiskSpace,
+    GameWindow,
+    AudioDevice,
+    Recorder,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Failed,
+    /// Doesn't apply to this platform or configuration
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckResult {
+    pub check: ReadinessCheck,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub suggested_fix: Option<String>,
+}
+
+/// Outcome of one probe, before it's labelled with its check
+#[derive(Debug, Clone, PartialEq)]
+struct Outcome {
+    status: CheckStatus,
+    detail: String,
+    suggested_fix: Option<String>,
+}
+
+impl Outcome {
+    fn ok(detail: impl Into<String>) -> Self {
+        Self { status: CheckStatus::Ok, detail: detail.into(), suggested_fix: None }
+    }
+
+    fn warning(detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { status: CheckStatus::Warning, detail: detail.into(), suggested_fix: Some(fix.into()) }
+    }
+
+    fn failed(detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { status: CheckStatus::Failed, detail: detail.into(), suggested_fix: Some(fix.into()) }
+    }
+
+    fn skipped(detail: impl Into<String>) -> Self {
+        Self { status: CheckStatus::Skipped, detail: detail.into(), suggested_fix: None }
+    }
+}
+
+/// Run every probe and return the report in a fixed order
+pub async fn run(app: &tauri::AppHandle) -> Vec<CheckResult> {
+    let state = app.state::<AppState>();
+    let watched = state
+        .game_detector
+        .lock()
+        .ok()
+        .and_then(|detector| detector.as_ref().map(|d| d.slippi_path().clone()));
+    let stored_window = state
+        .settings
+        .lock()
+        .ok()
+        .and_then(|settings| settings.get("game_process_name").and_then(|v| v.as_str()).map(|s| s.trim().to_string()))
+        .filter(|s| !s.is_empty());
+
+    let slippi = {
+        let app = app.clone();
+        blocking(move || check_slippi_folder(library::get_slippi_directory(&app).ok(), watched))
+    };
+    let disk = {
+        let app = app.clone();
+        async move {
+            match library::get_recording_directory(&app).await {
+                Ok(dir) => blocking(move || check_disk_space(Path::new(&dir))).await,
+                Err(e) => Outcome::failed(
+                    format!("Recording folder is unavailable: {}", e),
+                    "Choose a recording folder you can write to in Settings → Recording",
+                ),
+            }
+        }
+    };
+    let recorder = {
+        let app = app.clone();
+        blocking(move || check_recorder(&app))
+    };
+
+    let (slippi, ffmpeg, disk, window, audio, recorder) = tokio::join!(
+        probe(ReadinessCheck::SlippiFolder, slippi),
+        probe(ReadinessCheck::Ffmpeg, blocking(check_ffmpeg)),
+        probe(ReadinessCheck::DiskSpace, disk),
+        probe(ReadinessCheck::GameWindow, blocking(move || check_game_window(stored_window))),
+        probe(ReadinessCheck::AudioDevice, blocking(check_audio_device)),
+        probe(ReadinessCheck::Recorder, recorder),
+    );
+    vec![slippi, ffmpeg, disk, window, audio, recorder]
+}
+
+/// Track the game window: start or drop the pre-roll and the prewarmed
+/// recorder, and run the check in the background when it appears
+pub fn game_window_changed(app: &tauri::AppHandle, open: bool) {
+    let state = app.state::<AppState>();
+    let was_open = state.game_window_open.swap(open, std::sync::atomic::Ordering::SeqCst);
+    match (was_open, open) {
+        (false, true) => {
+            warm_start::prewarm(app);
+            pre_roll::start(app);
+        }
+        (true, false) => {
+            warm_start::release(app);
+            pre_roll::discard(app);
+            game_session::send(app, SessionEvent::GameWindowClosed);
+        }
+        _ => {}
+    }
+    if !open || was_open {
+        return;
+    }
+
+    let enabled = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get(AUTO_CHECK_KEY))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+    // Nothing is recorded in replay-only mode
+    if !enabled || replay_only_mode(app) {
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let failures: Vec<CheckResult> =
+            run(&app).await.into_iter().filter(|r| r.status == CheckStatus::Failed).collect();
+        if failures.is_empty() {
+            log::info!("✅ Readiness check passed");
+            return;
+        }
+        for failure in &failures {
+            log::warn!("⚠️ Readiness check {:?} failed: {}", failure.check, failure.detail);
+        }
+        if let Err(e) = app.emit(events::readiness::WARNING, &failures) {
+            log::warn!("Failed to emit readiness warning: {}", e);
+        }
+    });
+}
+
+/// Bound a probe by PROBE_TIMEOUT
+async fn probe(check: ReadinessCheck, outcome: impl Future<Output = Outcome>) -> CheckResult {
+    let outcome = tokio::time::timeout(PROBE_TIMEOUT, outcome).await.unwrap_or_else(|_| {
+        Outcome::failed(
+            format!("Didn't finish within {}s", PROBE_TIMEOUT.as_secs()),
+            "Run the check again; if it keeps timing out, export diagnostics and report it",
+        )
+    });
+    CheckResult { check, status: outcome.status, detail: outcome.detail, suggested_fix: outcome.suggested_fix }
+}
+
+/// Run a blocking probe off the async runtime
+/// (on timeout the thread is left to finish on its own)
+async fn blocking(f: impl FnOnce() -> Outcome + Send + 'static) -> Outcome {
+    tokio::task::spawn_blocking(f).await.unwrap_or_else(|e| {
+        Outcome::failed(format!("The check crashed: {}", e), "Export diagnostics and report it")
+    })
+}
+
+fn check_slippi_folder(configured: Option<String>, watched: Option<PathBuf>) -> Outcome {
+    let Some(configured) = configured.filter(|p| !p.is_empty()).map(PathBuf::from) else {
+        return Outcome::failed("No Slippi replay folder is set", "Choose your replay folder in Settings → Slippi");
+    };
+    if !configured.is_dir() {
+        return Outcome::failed(
+            format!("Slippi replay folder not found: {}", configured.display()),
+            "Check the replay folder in Settings → Slippi matches the one set in Slippi Launcher",
+        );
+    }
+    match watched {
+        Some(watched) if watched == configured => Outcome::ok(format!("Watching {}", configured.display())),
+        Some(watched) => Outcome::warning(
+            format!("Watching {} instead of {}", watched.display(), configured.display()),
+            "Turn Watch for games off and on again to pick up the new folder",
+        ),
+        None => Outcome::failed(
+            format!("{} isn't being watched, so games won't be recorded", configured.display()),
+            "Turn on Watch for games in Settings → Slippi",
+        ),
+    }
+}
+
+fn check_ffmpeg() -> Outcome {
+    match ffmpeg_sidecar::version::ffmpeg_version() {
+        Ok(version) => Outcome::ok(format!("FFmpeg {}", version)),
+        Err(e) => Outcome::warning(
+            format!("FFmpeg isn't available yet: {}", e),
+            "It's downloaded in the background after startup (or when you first make a clip); check your internet connection",
+        ),
+    }
+}
+
+fn check_disk_space(dir: &Path) -> Outcome {
+    match free_space_at(dir) {
+        Some(free) => disk_space_outcome(free),
+        None => Outcome::skipped(format!("Couldn't tell which drive {} is on", dir.display())),
+    }
+}
+
+/// Free space on the disk holding `dir`, None if its drive can't be found
+pub(crate) fn free_space_at(dir: &Path) -> Option<u64> {
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    let mounts: Vec<(PathBuf, u64)> =
+        disks.iter().map(|d| (d.mount_point().to_path_buf(), d.available_space())).collect();
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    free_space(&dir, &mounts)
+}
+
+/// Free space on the disk holding `dir` (the most specific mount point wins)
+fn free_space(dir: &Path, mounts: &[(PathBuf, u64)]) -> Option<u64> {
+    mounts
+        .iter()
+        .filter(|(mount, _)| dir.starts_with(mount))
+        .max_by_key(|(mount, _)| mount.as_os_str().len())
+        .map(|(_, free)| *free)
+}
+
+fn disk_space_outcome(free: u64) -> Outcome {
+    let gb = free as f64 / (1024.0 * 1024.0 * 1024.0);
+    let fix = "Free up space or move recordings to another drive in Settings → Recording";
+    if free < MIN_FREE_SPACE {
+        Outcome::failed(format!("Only {:.1} GB free for recordings", gb), fix)
+    } else if free < LOW_FREE_SPACE {
+        Outcome::warning(format!("{:.1} GB free for recordings", gb), fix)
+    } else {
+        Outcome::ok(format!("{:.1} GB free", gb))
+    }
+}
+
+fn check_game_window(stored: Option<String>) -> Outcome {
+    if !cfg!(target_os = "windows") {
+        return Outcome::skipped("Game window detection is only available on Windows");
+    }
+    if window_detector::check_game_window_open(stored.as_deref()) {
+        Outcome::ok(format!("Found {}", stored.as_deref().unwrap_or("Slippi Dolphin")))
+    } else {
+        Outcome::warning(
+            "No game window found",
+            "Launch Slippi Dolphin, or pick its window under Game Window Detection",
+        )
+    }
+}
+
+#[cfg(all(target_os = "windows", feature = "real-recording"))]
+fn check_audio_device() -> Outcome {
+    match crate::recorder::windows_v2::audio_device() {
+        Ok(Some(device)) => Outcome::ok(format!("Capturing audio from {}", device)),
+        Ok(None) => Outcome::skipped("Audio capture is turned off"),
+        Err(e) => Outcome::warning(
+            format!("{}; recordings will have no sound", e),
+            "Plug in or enable a playback device and make it the Windows default",
+        ),
+    }
+}
+
+#[cfg(not(all(target_os = "windows", feature = "real-recording")))]
+fn check_audio_device() -> Outcome {
+    Outcome::skipped("Audio is only captured by the Windows recorder")
+}
+
+fn check_recorder(app: &tauri::AppHandle) -> Outcome {
+    if crate::commands::recording::uses_obs(app) {
+        let config = crate::commands::recording::obs_config(app);
+        return match crate::recorder::obs::ObsRecorder::test_connection(&config) {
+            Ok(version) => Outcome::ok(format!("Connected to OBS {}", version)),
+            Err(e) => Outcome::failed(
+                e.to_string(),
+                "Start OBS and check the WebSocket server settings in Settings → Recording",
+            ),
+        };
+    }
+
+    if cfg!(feature = "real-recording") {
+        Outcome::ok("Built-in recorder")
+    } else {
+        Outcome::warning(
+            "This build uses the mock recorder and won't save video",
+            "Install a release build to record games",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disk_space_uses_the_recording_drive() {
+        let mounts = vec![
+            (PathBuf::from("/"), 50 * 1024 * 1024 * 1024),
+            (PathBuf::from("/mnt/videos"), 1024 * 1024 * 1024),
+        ];
+        let free = free_space(Path::new("/mnt/videos/Buckwheat"), &mounts).unwrap();
+        assert_eq!(disk_space_outcome(free).status, CheckStatus::Failed);
+
+        let free = free_space(Path::new("/home/me/Videos"), &mounts).unwrap();
+        assert_eq!(disk_space_outcome(free).status, CheckStatus::Ok);
+        assert_eq!(disk_space_outcome(5 * 1024 * 1024 * 1024).status, CheckStatus::Warning);
+    }
+}
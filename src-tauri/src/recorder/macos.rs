@@ -88,7 +88,7 @@ impl MacOSRecorder {
 
     fn find_dolphin_window(&self) -> Result<SCWindow, Error> {
         let content = SCShareableContent::get().map_err(|e| {
-            Error::InitializationError(format!("Failed to enumerate windows: {}", e))
+            Error::WindowCapture(format!("Failed to enumerate windows: {}", e))
         })?;
 
         let dolphin_window = content.windows().into_iter().find(|window| {
@@ -158,7 +158,7 @@ impl MacOSRecorder {
         let handler_id = stream
             .add_output_handler(handler, SCStreamOutputType::Screen)
             .ok_or_else(|| {
-                Error::InitializationError("Failed to attach screen output handler".into())
+                Error::WindowCapture("Failed to attach screen output handler".into())
             })?;
 
         self.stream = Some(Arc::new(Mutex::new(stream)));
@@ -195,7 +195,7 @@ impl Recorder for MacOSRecorder {
                 .map_err(|e| Error::InitializationError(format!("Failed to lock stream: {e}")))?;
             stream_guard
                 .start_capture()
-                .map_err(|e| Error::RecordingFailed(format!("Failed to start capture: {e}")))?;
+                .map_err(|e| Error::WindowCapture(format!("Failed to start capture: {e}")))?;
         } else {
             return Err(Error::InitializationError(
                 "Stream was not initialized".into(),
@@ -1,10 +1,11 @@
-use super::{Error, Recorder};
+use super::{CaptureStats, Error, Recorder};
 use std::time::Instant;
 
 pub struct MockRecorder {
     is_recording: bool,
     start_time: Option<Instant>,
     output_path: Option<String>,
+    prewarmed: bool,
 }
 
 impl MockRecorder {
@@ -13,6 +14,7 @@ impl MockRecorder {
             is_recording: false,
             start_time: None,
             output_path: None,
+            prewarmed: false,
         }
     }
 }
@@ -32,13 +34,15 @@ impl Recorder for MockRecorder {
             .map(|(w, h)| format!("{}x{}", w, h))
             .unwrap_or_else(|| "native".to_string());
         println!(
-            "🎥 [MOCK] Starting recording to: {} with {:?} quality ({}, {} Mbps)",
+            "🎥 [MOCK] Starting recording to: {} with {:?} quality ({}, {} Mbps{})",
             output_path,
             quality,
             resolution_info,
-            quality.bitrate() / 1_000_000
+            quality.bitrate() / 1_000_000,
+            if self.prewarmed { ", prewarmed" } else { "" }
         );
         self.is_recording = true;
+        self.prewarmed = false;
         self.start_time = Some(Instant::now());
         self.output_path = Some(output_path.to_string());
 
@@ -76,6 +80,42 @@ impl Recorder for MockRecorder {
     fn is_recording(&self) -> bool {
         self.is_recording
     }
+
+    fn capture_stats(&self) -> Option<CaptureStats> {
+        // Pretend every frame arrived on time
+        self.start_time.map(|start| {
+            let elapsed_secs = start.elapsed().as_secs_f64();
+            CaptureStats {
+                frame_count: (elapsed_secs * super::performance::TARGET_FPS) as u64,
+                elapsed_secs,
+                longest_stall_ms: (1000.0 / super::performance::TARGET_FPS).round() as u64,
+            }
+        })
+    }
+
+    fn target_description(&self) -> Option<String> {
+        self.is_recording.then(|| "Mock capture".to_string())
+    }
+
+    fn first_frame_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        let elapsed = chrono::Duration::from_std(self.start_time?.elapsed()).ok()?;
+        Some(chrono::Utc::now() - elapsed)
+    }
+
+    fn prewarm(&mut self) -> Result<bool, Error> {
+        if self.is_recording {
+            return Err(Error::RecordingFailed("Already recording".to_string()));
+        }
+        println!("🔥 [MOCK] Prewarmed");
+        self.prewarmed = true;
+        Ok(true)
+    }
+
+    fn release_prewarm(&mut self) {
+        if std::mem::take(&mut self.prewarmed) {
+            println!("🧊 [MOCK] Released prewarm");
+        }
+    }
 }
 
 impl Default for MockRecorder {
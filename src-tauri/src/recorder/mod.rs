@@ -1,4 +1,6 @@
 pub mod mock;
+pub mod obs;
+pub mod performance;
 
 #[cfg(all(target_os = "windows", feature = "real-recording"))]
 pub mod windows_v2;
@@ -7,8 +9,11 @@ pub mod windows_v2;
 pub mod macos;
 
 use crate::commands::errors::Error;
+use crate::settings::RecorderBackend;
 use serde::{Deserialize, Serialize};
 
+pub use performance::CaptureStats;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum RecordingQuality {
@@ -18,7 +23,35 @@ pub enum RecordingQuality {
     Ultra,
 }
 
+/// What a recording was captured with, saved in its sidecar
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureSettings {
+    pub quality: RecordingQuality,
+    pub backend: RecorderBackend,
+    /// Settings profile applied when it started
+    pub profile: Option<String>,
+}
+
 impl RecordingQuality {
+    /// All presets, lowest to highest
+    pub const ALL: [RecordingQuality; 4] = [
+        RecordingQuality::Low,
+        RecordingQuality::Medium,
+        RecordingQuality::High,
+        RecordingQuality::Ultra,
+    ];
+
+    /// Position in ALL (higher = heavier preset)
+    pub fn rank(&self) -> usize {
+        Self::ALL.iter().position(|q| q == self).unwrap_or(0)
+    }
+
+    /// The next preset down, or None for Low
+    pub fn lower(&self) -> Option<RecordingQuality> {
+        self.rank().checked_sub(1).map(|idx| Self::ALL[idx])
+    }
+
     /// Get the bitrate in bits per second for this quality level
     pub fn bitrate(&self) -> u32 {
         match self {
@@ -84,6 +117,26 @@ pub trait Recorder {
     ) -> Result<(), Error>;
     fn stop_recording(&mut self) -> Result<String, Error>;
     fn is_recording(&self) -> bool;
+    /// Frame counters for the current capture, if the backend tracks them
+    fn capture_stats(&self) -> Option<CaptureStats> {
+        None
+    }
+    /// What the current capture is pointed at, if the backend picks its own target
+    fn target_description(&self) -> Option<String> {
+        None
+    }
+    /// Wall-clock time the current capture's first frame arrived, if the backend tracks it
+    fn first_frame_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        None
+    }
+    /// Get ready to record ahead of time (find the target, open the capture
+    /// paused) so start_recording has less to do.
+    /// Returns false if the backend has nothing to prepare
+    fn prewarm(&mut self) -> Result<bool, Error> {
+        Ok(false)
+    }
+    /// Tear down what prewarm prepared, if recording never started
+    fn release_prewarm(&mut self) {}
 }
 
 pub fn get_recorder() -> Box<dyn Recorder + Send> {
@@ -0,0 +1,428 @@
+//! OBS recorder backend (obs-websocket v5)
+//!
+//! For users who already have OBS tuned: game detection and the library work
+//! as usual, but recordings are started and stopped in OBS through its
+//! WebSocket server (OBS 28+, Tools → WebSocket Server Settings). OBS owns the
+//! scene, encoder and quality settings. When a recording stops, the file OBS
+//! wrote is moved to the path we asked for so the library can pair it with
+//! its replay.
+
+use super::{Error, Recorder, RecordingQuality};
+use base64::Engine;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tungstenite::{Message, WebSocket};
+
+/// obs-websocket's default port
+pub const DEFAULT_PORT: u16 = 4455;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long OBS gets to finalize the file after StopRecord
+const STOP_TIMEOUT: Duration = Duration::from_secs(15);
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// obs-websocket RPC version we speak
+const RPC_VERSION: u64 = 1;
+/// eventSubscriptions bit for output events (RecordStateChanged)
+const OUTPUTS_EVENT_SUBSCRIPTION: u64 = 1 << 6;
+/// Close code OBS sends when the password is wrong
+const AUTHENTICATION_FAILED_CLOSE_CODE: u16 = 4009;
+
+/// obs-websocket message opcodes
+mod op {
+    pub const HELLO: u64 = 0;
+    pub const IDENTIFY: u64 = 1;
+    pub const IDENTIFIED: u64 = 2;
+    pub const EVENT: u64 = 5;
+    pub const REQUEST: u64 = 6;
+    pub const REQUEST_RESPONSE: u64 = 7;
+}
+
+/// Where to reach OBS
+#[derive(Debug, Clone)]
+pub struct ObsConfig {
+    pub host: String,
+    pub port: u16,
+    /// WebSocket server password, if authentication is enabled in OBS
+    pub password: Option<String>,
+}
+
+impl ObsConfig {
+    fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+pub struct ObsRecorder {
+    config: ObsConfig,
+    /// Open while recording (a Mutex so is_recording can query OBS through &self)
+    connection: Mutex<Option<ObsConnection>>,
+    output_path: Option<String>,
+    is_recording: bool,
+}
+
+impl ObsRecorder {
+    pub fn new(config: ObsConfig) -> Self {
+        Self {
+            config,
+            connection: Mutex::new(None),
+            output_path: None,
+            is_recording: false,
+        }
+    }
+
+    /// Connect, identify and return the OBS version (for the settings "Test connection" button)
+    pub fn test_connection(config: &ObsConfig) -> Result<String, Error> {
+        let mut connection = ObsConnection::connect(config)?;
+        let version = connection.request("GetVersion", json!({}))?;
+        connection.close();
+
+        Ok(version
+            .get("obsVersion")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown")
+            .to_string())
+    }
+}
+
+impl Recorder for ObsRecorder {
+    fn start_recording(&mut self, output_path: &str, quality: RecordingQuality) -> Result<(), Error> {
+        if self.is_recording {
+            return Err(Error::RecordingFailed("Already recording".to_string()));
+        }
+
+        log::info!(
+            "🎬 Starting OBS recording at {} (quality {:?} is ignored - OBS output settings apply)",
+            self.config.address(),
+            quality
+        );
+
+        let mut connection = ObsConnection::connect(&self.config)?;
+
+        let status = connection.request("GetRecordStatus", json!({}))?;
+        if status.get("outputActive").and_then(|v| v.as_bool()).unwrap_or(false) {
+            connection.close();
+            return Err(Error::Obs(
+                "OBS is already recording. Stop the recording in OBS first.".to_string(),
+            ));
+        }
+
+        // Best effort - SetRecordDirectory needs obs-websocket 5.3+, and we move the file afterwards anyway
+        if let Some(dir) = Path::new(output_path).parent().and_then(|d| d.to_str()) {
+            if let Err(e) = connection.request("SetRecordDirectory", json!({ "recordDirectory": dir })) {
+                log::warn!("Couldn't set OBS record directory, OBS's own directory will be used: {}", e);
+            }
+        }
+
+        connection.request("StartRecord", json!({}))?;
+
+        *self.connection.lock().unwrap_or_else(|e| e.into_inner()) = Some(connection);
+        self.output_path = Some(output_path.to_string());
+        self.is_recording = true;
+
+        Ok(())
+    }
+
+    fn stop_recording(&mut self) -> Result<String, Error> {
+        if !self.is_recording {
+            return Err(Error::RecordingFailed("Not currently recording".to_string()));
+        }
+        self.is_recording = false;
+
+        let connection = self.connection.lock().unwrap_or_else(|e| e.into_inner()).take();
+        let mut connection = match connection {
+            Some(connection) => connection,
+            None => ObsConnection::connect(&self.config)?,
+        };
+
+        let obs_path = match connection.request("StopRecord", json!({})) {
+            Ok(response) => match response.get("outputPath").and_then(|v| v.as_str()) {
+                Some(path) => Some(path.to_string()),
+                None => connection.wait_for_stopped_path()?,
+            },
+            // Already stopped in OBS - use the path from the RecordStateChanged event if we saw it
+            Err(e) => match connection.stopped_output_path.take() {
+                Some(path) => Some(path),
+                None => {
+                    connection.close();
+                    return Err(e);
+                }
+            },
+        };
+        connection.close();
+
+        let obs_path = obs_path.ok_or_else(|| {
+            Error::Obs("OBS stopped recording but didn't report where the file was saved".to_string())
+        })?;
+        let requested = self.output_path.take().unwrap_or_else(|| obs_path.clone());
+
+        Ok(place_recording(&obs_path, &requested))
+    }
+
+    fn is_recording(&self) -> bool {
+        let mut connection = self.connection.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(connection) = connection.as_mut() else {
+            return self.is_recording;
+        };
+
+        // Reflect OBS's state (the user may have stopped it in OBS)
+        match connection.request("GetRecordStatus", json!({})) {
+            Ok(status) => status
+                .get("outputActive")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(self.is_recording),
+            Err(e) => {
+                log::warn!("Failed to query OBS record status: {}", e);
+                self.is_recording
+            }
+        }
+    }
+}
+
+/// An identified obs-websocket session
+struct ObsConnection {
+    socket: WebSocket<TcpStream>,
+    next_request_id: u64,
+    /// outputPath from the latest RecordStateChanged "stopped" event
+    stopped_output_path: Option<String>,
+}
+
+impl ObsConnection {
+    fn connect(config: &ObsConfig) -> Result<Self, Error> {
+        let address = config.address();
+        let socket_address = address
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addrs| addrs.next())
+            .ok_or_else(|| Error::Obs(format!("Couldn't resolve OBS host '{}'. Check the OBS host setting.", config.host)))?;
+
+        let stream = TcpStream::connect_timeout(&socket_address, CONNECT_TIMEOUT).map_err(|e| {
+            Error::Obs(format!(
+                "Couldn't connect to OBS at {} ({}). Make sure OBS is running and the WebSocket server is enabled in Tools → WebSocket Server Settings.",
+                address, e
+            ))
+        })?;
+        stream.set_read_timeout(Some(RESPONSE_TIMEOUT))?;
+
+        let (socket, _) = tungstenite::client(format!("ws://{}", address), stream).map_err(|e| {
+            Error::Obs(format!(
+                "{} doesn't look like an obs-websocket server ({}). Check the OBS port setting.",
+                address, e
+            ))
+        })?;
+
+        let mut connection = Self {
+            socket,
+            next_request_id: 0,
+            stopped_output_path: None,
+        };
+        connection.identify(config)?;
+
+        log::info!("🔌 Connected to OBS at {}", address);
+        Ok(connection)
+    }
+
+    /// Hello → Identify (with authentication if OBS asks for it) → Identified
+    fn identify(&mut self, config: &ObsConfig) -> Result<(), Error> {
+        let hello = self.receive_op(op::HELLO)?;
+
+        let mut identify = json!({
+            "rpcVersion": RPC_VERSION,
+            "eventSubscriptions": OUTPUTS_EVENT_SUBSCRIPTION,
+        });
+        if let Some(auth) = hello.get("authentication") {
+            let password = config.password.as_deref().filter(|p| !p.is_empty()).ok_or_else(|| {
+                Error::Obs("OBS requires a WebSocket password. Enter it in Settings → Recording.".to_string())
+            })?;
+            let challenge = auth.get("challenge").and_then(|v| v.as_str()).unwrap_or_default();
+            let salt = auth.get("salt").and_then(|v| v.as_str()).unwrap_or_default();
+            identify["authentication"] = json!(authentication_response(password, salt, challenge));
+        }
+
+        self.send(op::IDENTIFY, identify)?;
+        self.receive_op(op::IDENTIFIED)?;
+        Ok(())
+    }
+
+    /// Send a request and wait for its response data
+    fn request(&mut self, request_type: &str, request_data: Value) -> Result<Value, Error> {
+        self.next_request_id += 1;
+        let request_id = self.next_request_id.to_string();
+        self.send(
+            op::REQUEST,
+            json!({
+                "requestType": request_type,
+                "requestId": request_id,
+                "requestData": request_data,
+            }),
+        )?;
+
+        let deadline = Instant::now() + RESPONSE_TIMEOUT;
+        while Instant::now() < deadline {
+            let response = self.receive_op(op::REQUEST_RESPONSE)?;
+            if response.get("requestId").and_then(|v| v.as_str()) != Some(request_id.as_str()) {
+                continue;
+            }
+
+            let status = response.get("requestStatus");
+            if status.and_then(|s| s.get("result")).and_then(|v| v.as_bool()) != Some(true) {
+                let reason = status
+                    .and_then(|s| s.get("comment"))
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| {
+                        format!("code {}", status.and_then(|s| s.get("code")).cloned().unwrap_or_default())
+                    });
+                return Err(Error::Obs(format!("{} failed: {}", request_type, reason)));
+            }
+
+            return Ok(response.get("responseData").cloned().unwrap_or(Value::Null));
+        }
+
+        Err(Error::Obs(format!("OBS didn't answer {} in time", request_type)))
+    }
+
+    /// Poll until OBS reports the recording stopped, returning the file path
+    fn wait_for_stopped_path(&mut self) -> Result<Option<String>, Error> {
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        while Instant::now() < deadline {
+            // Requests also pick up RecordStateChanged events that arrive in between
+            let status = self.request("GetRecordStatus", json!({}))?;
+            if self.stopped_output_path.is_some()
+                || !status.get("outputActive").and_then(|v| v.as_bool()).unwrap_or(false)
+            {
+                return Ok(self.stopped_output_path.take());
+            }
+            std::thread::sleep(STOP_POLL_INTERVAL);
+        }
+
+        Err(Error::Obs("OBS didn't finish stopping the recording in time".to_string()))
+    }
+
+    fn send(&mut self, op: u64, data: Value) -> Result<(), Error> {
+        let message = json!({ "op": op, "d": data }).to_string();
+        self.socket
+            .send(Message::Text(message))
+            .map_err(|e| Error::Obs(format!("Lost connection to OBS: {}", e)))
+    }
+
+    /// Read messages until one with the given opcode arrives, handling events on the way
+    fn receive_op(&mut self, expected_op: u64) -> Result<Value, Error> {
+        loop {
+            let message = match self.socket.read() {
+                Ok(message) => message,
+                Err(tungstenite::Error::Io(e))
+                    if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) =>
+                {
+                    return Err(Error::Obs("OBS didn't respond in time".to_string()));
+                }
+                Err(e) => return Err(Error::Obs(format!("Lost connection to OBS: {}", e))),
+            };
+
+            let text = match message {
+                Message::Text(text) => text,
+                Message::Close(frame) => {
+                    let code = frame.as_ref().map(|f| u16::from(f.code));
+                    if code == Some(AUTHENTICATION_FAILED_CLOSE_CODE) {
+                        return Err(Error::Obs(
+                            "OBS rejected the WebSocket password. Check it matches Tools → WebSocket Server Settings."
+                                .to_string(),
+                        ));
+                    }
+                    let reason = frame.map(|f| f.reason.to_string()).unwrap_or_default();
+                    return Err(Error::Obs(format!("OBS closed the connection {}", reason).trim_end().to_string()));
+                }
+                _ => continue,
+            };
+
+            let Ok(value) = serde_json::from_str::<Value>(&text) else {
+                continue;
+            };
+            let op = value.get("op").and_then(|v| v.as_u64());
+            let data = value.get("d").cloned().unwrap_or(Value::Null);
+
+            if op == Some(op::EVENT) {
+                self.handle_event(&data);
+            }
+            if op == Some(expected_op) {
+                return Ok(data);
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: &Value) {
+        if event.get("eventType").and_then(|v| v.as_str()) != Some("RecordStateChanged") {
+            return;
+        }
+        let Some(data) = event.get("eventData") else {
+            return;
+        };
+        if data.get("outputState").and_then(|v| v.as_str()) == Some("OBS_WEBSOCKET_OUTPUT_STOPPED") {
+            self.stopped_output_path = data.get("outputPath").and_then(|v| v.as_str()).map(str::to_string);
+        }
+    }
+
+    fn close(mut self) {
+        let _ = self.socket.close(None);
+        let _ = self.socket.flush();
+    }
+}
+
+/// base64(sha256(base64(sha256(password + salt)) + challenge))
+fn authentication_response(password: &str, salt: &str, challenge: &str) -> String {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let secret = engine.encode(Sha256::digest(format!("{}{}", password, salt)));
+    engine.encode(Sha256::digest(format!("{}{}", secret, challenge)))
+}
+
+/// Move OBS's file to the requested path (keeping OBS's container extension)
+/// Returns where the recording ended up
+fn place_recording(obs_path: &str, requested: &str) -> String {
+    let source = Path::new(obs_path);
+    let target = match source.extension() {
+        Some(ext) => Path::new(requested).with_extension(ext),
+        None => Path::new(requested).to_path_buf(),
+    };
+    if !crate::library::is_video_file(source) {
+        log::warn!("OBS recorded {} - only .mp4 and .mkv recordings show up in the library, set OBS's recording format to one of them", obs_path);
+    }
+    if target == source {
+        return obs_path.to_string();
+    }
+
+    match std::fs::rename(source, &target) {
+        Ok(()) => target.to_string_lossy().to_string(),
+        Err(e) => {
+            log::warn!(
+                "Couldn't move OBS recording {} to {}, keeping OBS's location: {}",
+                obs_path,
+                target.display(),
+                e
+            );
+            obs_path.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authentication_response_matches_protocol_example() {
+        // Example from the obs-websocket v5 protocol documentation
+        assert_eq!(
+            authentication_response(
+                "supersecretpassword",
+                "lM1GncleQOaCu9lT1yeUZhFYnqhsLLP1G5lAGo3ixaI=",
+                "+IxH4CnCiqpX1rM9scsNynZzbOe4KhDeYcTNS3PDaeY="
+            ),
+            "1Ct943GAT+6YQUUX47Ia/ncufilbe6+oD6lY+5kaCu4="
+        );
+    }
+}
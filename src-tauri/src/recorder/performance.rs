@@ -0,0 +1,139 @@
+//! Capture performance tracking
+//!
+//! Recorders report how many frames they actually captured; comparing that to
+//! wall-clock elapsed time at the target frame rate tells us how many frames
+//! were dropped. Used by the recording benchmark and the live performance warning.
+
+use super::{CaptureSettings, RecordingQuality};
+use serde::Serialize;
+
+/// Frame rate recorders aim for (Melee runs at 60 fps)
+pub const TARGET_FPS: f64 = 60.0;
+
+/// Dropped-frame ratio above which a preset is considered too heavy
+pub const DROPPED_FRAME_WARNING_RATIO: f64 = 0.05;
+
+/// Frame counters for an active (or just-finished) capture
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureStats {
+    /// Frames delivered to the encoder
+    pub frame_count: u64,
+    /// Seconds since the first frame arrived
+    pub elapsed_secs: f64,
+    /// Longest gap between two consecutive frames, in milliseconds
+    pub longest_stall_ms: u64,
+}
+
+impl CaptureStats {
+    /// Frames we should have captured at TARGET_FPS
+    pub fn expected_frames(&self) -> u64 {
+        (self.elapsed_secs * TARGET_FPS).round() as u64
+    }
+
+    /// Estimated frames dropped (never negative)
+    pub fn dropped_frames(&self) -> u64 {
+        self.expected_frames().saturating_sub(self.frame_count)
+    }
+
+    /// Fraction of expected frames that were dropped (0.0 - 1.0)
+    pub fn dropped_ratio(&self) -> f64 {
+        let expected = self.expected_frames();
+        if expected == 0 {
+            return 0.0;
+        }
+        self.dropped_frames() as f64 / expected as f64
+    }
+
+    /// Average frames per second actually achieved
+    pub fn achieved_fps(&self) -> f64 {
+        if self.elapsed_secs <= 0.0 {
+            return 0.0;
+        }
+        self.frame_count as f64 / self.elapsed_secs
+    }
+
+    /// Stats for the interval between an earlier sample and this one
+    /// (longest_stall_ms stays the whole-capture maximum)
+    pub fn since(&self, earlier: &CaptureStats) -> CaptureStats {
+        CaptureStats {
+            frame_count: self.frame_count.saturating_sub(earlier.frame_count),
+            elapsed_secs: (self.elapsed_secs - earlier.elapsed_secs).max(0.0),
+            longest_stall_ms: self.longest_stall_ms,
+        }
+    }
+}
+
+/// What stop_recording returns: the output file plus capture quality, if the backend tracks it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingResult {
+    pub output_path: String,
+    /// Frames dropped compared to TARGET_FPS over the whole recording
+    pub dropped_frame_estimate: Option<u64>,
+    /// Longest gap between two frames, in milliseconds
+    pub longest_stall_ms: Option<u64>,
+    /// Seconds from first to last captured frame
+    pub duration_secs: Option<f64>,
+    /// Quality, backend and profile it was recorded with
+    pub capture: Option<CaptureSettings>,
+    /// When the first frame was captured, to line the video up with its replay
+    #[serde(skip)]
+    pub first_frame_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl RecordingResult {
+    pub fn new(output_path: String, stats: Option<CaptureStats>) -> Self {
+        Self {
+            output_path,
+            dropped_frame_estimate: stats.map(|s| s.dropped_frames()),
+            longest_stall_ms: stats.map(|s| s.longest_stall_ms),
+            duration_secs: stats.map(|s| s.elapsed_secs),
+            capture: None,
+            first_frame_at: None,
+        }
+    }
+}
+
+/// Result of benchmarking a single quality preset
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetBenchmark {
+    pub quality: RecordingQuality,
+    pub achieved_fps: f64,
+    pub dropped_frames: u64,
+    pub dropped_ratio: f64,
+    /// Set if the preset couldn't be benchmarked at all
+    pub error: Option<String>,
+}
+
+/// Result of the full recording benchmark
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkResult {
+    pub presets: Vec<PresetBenchmark>,
+    /// Highest preset that stayed under the dropped-frame threshold
+    pub recommended: RecordingQuality,
+}
+
+/// Payload for the recording-performance-warning event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerformanceWarning {
+    pub output_path: String,
+    pub quality: RecordingQuality,
+    pub dropped_ratio: f64,
+    /// Next preset down, if there is one
+    pub suggested_quality: Option<RecordingQuality>,
+}
+
+/// Pick the highest preset that kept dropped frames under the threshold
+/// Falls back to Low if every preset struggled
+pub fn recommend_quality(presets: &[PresetBenchmark]) -> RecordingQuality {
+    presets
+        .iter()
+        .filter(|p| p.error.is_none() && p.dropped_ratio <= DROPPED_FRAME_WARNING_RATIO)
+        .map(|p| p.quality)
+        .max_by_key(|q| q.rank())
+        .unwrap_or(RecordingQuality::Low)
+}
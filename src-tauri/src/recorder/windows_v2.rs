@@ -10,7 +10,9 @@
 )]
 
 #[cfg(all(target_os = "windows", feature = "real-recording"))]
-use super::{Error, Recorder};
+use super::{CaptureStats, Error, Recorder};
+#[cfg(all(target_os = "windows", feature = "real-recording"))]
+use crate::health::{self, Subsystem};
 
 #[cfg(all(target_os = "windows", feature = "real-recording"))]
 use log::{debug, error, info, warn};
@@ -23,7 +25,7 @@ use std::sync::mpsc;
 #[cfg(all(target_os = "windows", feature = "real-recording"))]
 use std::sync::{Arc, Mutex};
 #[cfg(all(target_os = "windows", feature = "real-recording"))]
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 #[cfg(all(target_os = "windows", feature = "real-recording"))]
 use windows_capture::{
@@ -54,7 +56,16 @@ struct CaptureState {
     stop_requested: bool,
     frame_count: u64,
     start_time: Option<Instant>,
+    /// When the previous frame arrived, for stall tracking
+    last_frame_at: Option<Instant>,
+    /// Longest gap between two consecutive frames
+    longest_frame_gap: Duration,
     audio_receiver: Option<mpsc::Receiver<Vec<u8>>>,
+    /// When recording was asked for. Until then (a prewarmed capture) frames
+    /// and audio are dropped
+    armed_at: Option<Instant>,
+    /// Encoder initialization info (deferred until the first armed frame)
+    encoder_config: Option<EncoderConfig>,
 }
 
 /// Frame handler with VideoEncoder
@@ -62,8 +73,6 @@ struct CaptureState {
 struct FrameHandler {
     encoder: Option<VideoEncoder>,
     state: Arc<Mutex<CaptureState>>,
-    /// Encoder initialization info (deferred until first frame)
-    encoder_config: Option<EncoderConfig>,
 }
 
 /// Configuration for deferred encoder creation
@@ -81,7 +90,9 @@ struct CaptureFlags {
     width: u32,
     /// Target height (used as fallback if use_frame_dimensions is false)
     height: u32,
-    output_path: String,
+    /// Only needed if use_frame_dimensions is false; otherwise the encoder
+    /// config is set in the state when the capture is armed
+    output_path: Option<String>,
     enable_audio: bool,
     bitrate: u32,
     state: Arc<Mutex<CaptureState>>,
@@ -110,13 +121,9 @@ impl GraphicsCaptureApiHandler for FrameHandler {
             Ok(Self {
                 encoder: None,
                 state: flags.state,
-                encoder_config: Some(EncoderConfig {
-                    output_path: flags.output_path,
-                    enable_audio: flags.enable_audio,
-                    bitrate: flags.bitrate,
-                }),
             })
         } else {
+            let output_path = flags.output_path.ok_or("Encoder needs an output path up front")?;
             // Create encoder immediately with specified dimensions
             warn!(
                 "🎥 ENCODER DIMENSIONS: {}x{} (H.264, {} Mbps, audio: {})",
@@ -143,7 +150,7 @@ impl GraphicsCaptureApiHandler for FrameHandler {
                 video_settings,
                 audio_settings,
                 ContainerSettingsBuilder::default(),
-                &flags.output_path,
+                &output_path,
             )?;
 
             info!("VideoEncoder initialized successfully");
@@ -151,7 +158,6 @@ impl GraphicsCaptureApiHandler for FrameHandler {
             Ok(Self {
                 encoder: Some(encoder),
                 state: flags.state,
-                encoder_config: None,
             })
         }
     }
@@ -174,6 +180,14 @@ impl GraphicsCaptureApiHandler for FrameHandler {
             return Ok(());
         }
 
+        // Prewarmed and not recording yet: drop the frame, and the audio with it
+        let Some(armed_at) = state.armed_at else {
+            if let Some(ref receiver) = state.audio_receiver {
+                while receiver.try_recv().is_ok() {}
+            }
+            return Ok(());
+        };
+
         // Initialize start time on first frame
         let is_first_frame = state.start_time.is_none();
         if is_first_frame {
@@ -182,12 +196,12 @@ impl GraphicsCaptureApiHandler for FrameHandler {
             // Log the actual captured frame dimensions
             let frame_width = frame.width();
             let frame_height = frame.height();
-            info!("🎬 First frame received!");
+            info!("🎬 First frame received {} ms after recording started", armed_at.elapsed().as_millis());
             info!("📐 ACTUAL FRAME DIMENSIONS: {}x{}", frame_width, frame_height);
             
             // Create encoder with actual frame dimensions if deferred
             if self.encoder.is_none() {
-                if let Some(config) = self.encoder_config.take() {
+                if let Some(config) = state.encoder_config.take() {
                     warn!(
                         "🎥 Creating encoder with ACTUAL frame size: {}x{} (H.264, {} Mbps)",
                         frame_width, frame_height,
@@ -239,6 +253,12 @@ impl GraphicsCaptureApiHandler for FrameHandler {
             }
         }
 
+        let now = Instant::now();
+        if let Some(last_frame_at) = state.last_frame_at {
+            state.longest_frame_gap = state.longest_frame_gap.max(now - last_frame_at);
+        }
+        state.last_frame_at = Some(now);
+        
         state.frame_count += 1;
         let frame_count = state.frame_count;
         
@@ -303,6 +323,7 @@ impl AudioCapture {
 
         // Spawn thread to own the stream (cpal::Stream is not Send)
         let thread_handle = std::thread::spawn(move || {
+            let _subsystem = health::enter(Subsystem::Recorder);
             if let Err(e) = Self::run_audio_capture(sender, stop_flag_clone) {
                 error!("Audio capture thread error: {}", e);
             }
@@ -416,7 +437,8 @@ enum CaptureTarget {
 type WindowCaptureControl = CaptureControl<FrameHandler, Box<dyn std::error::Error + Send + Sync>>;
 
 /// Get the DPI scale factor for a window.
-/// Windows capture uses physical pixels, but window.rect() returns logical pixels.
+/// Windows capture uses physical pixels; window.rect() is logical pixels only
+/// when this thread isn't per-monitor DPI aware (see rect_is_physical).
 /// This function returns the scale factor to convert from logical to physical.
 #[cfg(all(target_os = "windows", feature = "real-recording"))]
 fn get_window_dpi_scale(window: &Window) -> f64 {
@@ -442,12 +464,25 @@ fn get_window_dpi_scale(window: &Window) -> f64 {
     1.0
 }
 
+/// Whether window rects on this thread are physical pixels, which they are
+/// when it's per-monitor DPI aware (as Tauri makes the app)
+#[cfg(all(target_os = "windows", feature = "real-recording"))]
+fn rect_is_physical() -> bool {
+    use windows::Win32::UI::HiDpi::{
+        GetAwarenessFromDpiAwarenessContext, GetThreadDpiAwarenessContext, DPI_AWARENESS_PER_MONITOR_AWARE,
+    };
+    unsafe { GetAwarenessFromDpiAwarenessContext(GetThreadDpiAwarenessContext()) == DPI_AWARENESS_PER_MONITOR_AWARE }
+}
+
 #[cfg(all(target_os = "windows", feature = "real-recording"))]
 pub struct WindowsRecorder {
     capture_control: Option<WindowCaptureControl>,
     capture_state: Option<Arc<Mutex<CaptureState>>>,
     audio_capture: Option<AudioCapture>,
     output_path: Option<String>,
+    target_description: Option<String>,
+    /// Size of the target when the capture was opened
+    source_size: Option<(u32, u32)>,
     is_recording: bool,
 }
 
@@ -459,10 +494,118 @@ impl WindowsRecorder {
             capture_state: None,
             audio_capture: None,
             output_path: None,
+            target_description: None,
+            source_size: None,
             is_recording: false,
         }
     }
 
+    /// Whether a capture is open (prewarmed or recording) and still running
+    fn pipeline_open(&self) -> bool {
+        self.capture_control.as_ref().is_some_and(|control| !control.is_finished())
+    }
+
+    /// Open a capture of the target, with audio, dropping frames until armed
+    fn open_pipeline(&mut self, target: CaptureTarget) -> Result<(), Error> {
+        let (source_width, source_height) = self.get_target_size(&target)?;
+        self.target_description = Some(Self::describe_target(&target));
+
+        // Check if audio should be enabled
+        let enable_audio = resolve_audio_enabled();
+        
+        // Start audio capture with cpal
+        let audio_receiver = if enable_audio {
+            match AudioCapture::start() {
+                Ok((audio_capture, receiver)) => {
+                    self.audio_capture = Some(audio_capture);
+                    Some(receiver)
+                }
+                Err(e) => {
+                    warn!("Failed to start audio capture: {}, continuing without audio", e);
+                    None
+                }
+            }
+        } else {
+            info!("Audio capture disabled");
+            None
+        };
+
+        // Create shared state
+        let capture_state = Arc::new(Mutex::new(CaptureState {
+            stop_requested: false,
+            frame_count: 0,
+            start_time: None,
+            last_frame_at: None,
+            longest_frame_gap: Duration::ZERO,
+            audio_receiver,
+            armed_at: None,
+            encoder_config: None,
+        }));
+
+        // Create flags for the capture handler
+        //
+        // ⚠️ CRITICAL: use_frame_dimensions MUST be true!
+        // 
+        // The Windows Graphics Capture API captures at LOGICAL pixel dimensions,
+        // but window.rect() may return different values due to DPI scaling quirks.
+        // If the encoder is configured with dimensions that don't match the actual
+        // captured frames, the video will be CROPPED to the top-left portion only!
+        //
+        // Solution: Defer encoder creation until the first frame arrives, then use
+        // frame.width()/height() to get the ACTUAL capture dimensions. This ensures
+        // the encoder always matches the captured content exactly.
+        //
+        // See: https://github.com/user/peppi/issues/XXX (recording cropped on high-DPI displays)
+        //
+        let flags = CaptureFlags {
+            width: source_width,
+            height: source_height,
+            output_path: None,
+            enable_audio: self.audio_capture.is_some(),
+            bitrate: 0,
+            state: capture_state.clone(),
+            use_frame_dimensions: true,
+        };
+
+        // Start capture
+        let capture_control = match target {
+            CaptureTarget::Window(window) => self.start_window_capture(window, flags)?,
+            CaptureTarget::Monitor(monitor) => self.start_monitor_capture(monitor, flags)?,
+        };
+
+        self.capture_control = Some(capture_control);
+        self.capture_state = Some(capture_state);
+        self.source_size = Some((source_width, source_height));
+        Ok(())
+    }
+
+    /// Stop the capture and audio, finishing the encoder if it was recording
+    fn close_pipeline(&mut self) {
+        // Stop audio first
+        if let Some(mut audio) = self.audio_capture.take() {
+            audio.stop();
+        }
+
+        // Signal stop
+        if let Some(ref state) = self.capture_state {
+            if let Ok(mut s) = state.lock() {
+                s.stop_requested = true;
+                if s.armed_at.is_some() {
+                    info!("Recorded {} frames", s.frame_count);
+                }
+            }
+        }
+
+        // Stop capture
+        if let Some(control) = self.capture_control.take() {
+            let _ = control.stop();
+        }
+
+        self.capture_state = None;
+        self.target_description = None;
+        self.source_size = None;
+    }
+
     fn ensure_output_dir(&self, output_path: &str) -> Result<(), Error> {
         if let Some(parent) = Path::new(output_path).parent() {
             if !parent.as_os_str().is_empty() {
@@ -478,7 +621,7 @@ impl WindowsRecorder {
         let selection = TargetSelection::from_env();
 
         let windows = Window::enumerate()
-            .map_err(|e| Error::RecordingFailed(format!("Failed to enumerate windows: {}", e)))?;
+            .map_err(|e| Error::WindowCapture(format!("Failed to enumerate windows: {}", e)))?;
 
         let best_match = if selection.pid.is_some() || selection.title.is_some() {
             let hint = selection.title.as_deref();
@@ -523,28 +666,47 @@ impl WindowsRecorder {
         } else {
             info!("No matching window found, capturing primary monitor");
             let monitor = Monitor::primary()
-                .map_err(|e| Error::RecordingFailed(format!("Failed to get primary monitor: {}", e)))?;
+                .map_err(|e| Error::WindowCapture(format!("Failed to get primary monitor: {}", e)))?;
             Ok(CaptureTarget::Monitor(monitor))
         }
     }
 
+    fn describe_target(target: &CaptureTarget) -> String {
+        match target {
+            CaptureTarget::Window(window) => {
+                let title = window.title().unwrap_or_else(|_| "Untitled window".to_string());
+                match window.process_name() {
+                    Ok(process) => format!("Window '{}' ({})", title, process),
+                    Err(_) => format!("Window '{}'", title),
+                }
+            }
+            CaptureTarget::Monitor(monitor) => match monitor.name() {
+                Ok(name) => format!("Primary monitor ({})", name),
+                Err(_) => "Primary monitor".to_string(),
+            },
+        }
+    }
+
     fn get_target_size(&self, target: &CaptureTarget) -> Result<(u32, u32), Error> {
         match target {
             CaptureTarget::Window(window) => {
-                // Use DwmGetWindowAttribute to get the actual capture dimensions
-                // This accounts for DPI scaling properly
                 let rect = window.rect()
-                    .map_err(|e| Error::RecordingFailed(format!("Failed to get window rect: {}", e)))?;
-                
-                let logical_w = (rect.right - rect.left).max(640) as u32;
-                let logical_h = (rect.bottom - rect.top).max(480) as u32;
+                    .map_err(|e| Error::WindowCapture(format!("Failed to get window rect: {}", e)))?;
+                let rect_w = rect.right - rect.left;
+                let rect_h = rect.bottom - rect.top;
                 
                 // Get DPI scale factor for the window
                 let dpi_scale = get_window_dpi_scale(window);
                 
-                // Calculate physical (capture) dimensions
-                let physical_w = ((logical_w as f64 * dpi_scale) as u32 / 2) * 2; // Ensure even
-                let physical_h = ((logical_h as f64 * dpi_scale) as u32 / 2) * 2;
+                // Calculate physical (capture) dimensions, scaling only a logical rect
+                let rect_is_physical = rect_is_physical();
+                let (physical_w, physical_h) =
+                    crate::window_detector::dpi::physical_size(rect_w, rect_h, dpi_scale, rect_is_physical);
+                let (logical_w, logical_h) = if rect_is_physical {
+                    crate::window_detector::dpi::logical_size(rect_w, rect_h, dpi_scale)
+                } else {
+                    (rect_w, rect_h)
+                };
                 
                 info!(
                     "Window size: {}x{} logical, {}x{} physical (DPI scale: {:.2})",
@@ -578,7 +740,7 @@ impl WindowsRecorder {
         );
 
         FrameHandler::start_free_threaded(settings)
-            .map_err(|e| Error::RecordingFailed(format!("Failed to start window capture: {}", e)))
+            .map_err(|e| Error::WindowCapture(format!("Failed to start window capture: {}", e)))
     }
 
     fn start_monitor_capture(
@@ -598,7 +760,7 @@ impl WindowsRecorder {
         );
 
         FrameHandler::start_free_threaded(settings)
-            .map_err(|e| Error::RecordingFailed(format!("Failed to start monitor capture: {}", e)))
+            .map_err(|e| Error::WindowCapture(format!("Failed to start monitor capture: {}", e)))
     }
 }
 
@@ -615,8 +777,15 @@ impl Recorder for WindowsRecorder {
 
         self.ensure_output_dir(output_path)?;
 
-        let target = self.find_target()?;
-        let (source_width, source_height) = self.get_target_size(&target)?;
+        if self.capture_control.is_some() && !self.pipeline_open() {
+            warn!("Prewarmed capture ended before recording started, opening a new one");
+            self.close_pipeline();
+        }
+        if self.capture_control.is_none() {
+            let target = self.find_target()?;
+            self.open_pipeline(target)?;
+        }
+        let (source_width, source_height) = self.source_size.unwrap_or_default();
         
         // Scale dimensions based on quality setting
         let (width, height) = quality.scale_dimensions(source_width, source_height);
@@ -626,67 +795,20 @@ impl Recorder for WindowsRecorder {
             source_width, source_height, width, height, quality
         );
 
-        // Check if audio should be enabled
-        let enable_audio = resolve_audio_enabled();
-        
-        // Start audio capture with cpal
-        let audio_receiver = if enable_audio {
-            match AudioCapture::start() {
-                Ok((audio_capture, receiver)) => {
-                    self.audio_capture = Some(audio_capture);
-                    Some(receiver)
-                }
-                Err(e) => {
-                    warn!("Failed to start audio capture: {}, continuing without audio", e);
-                    None
-                }
-            }
-        } else {
-            info!("Audio capture disabled");
-            None
-        };
-
-        // Create shared state
-        let capture_state = Arc::new(Mutex::new(CaptureState {
-            stop_requested: false,
-            frame_count: 0,
-            start_time: None,
-            audio_receiver,
-        }));
-
-        // Create flags for the capture handler
-        //
-        // ⚠️ CRITICAL: use_frame_dimensions MUST be true!
-        // 
-        // The Windows Graphics Capture API captures at LOGICAL pixel dimensions,
-        // but window.rect() may return different values due to DPI scaling quirks.
-        // If the encoder is configured with dimensions that don't match the actual
-        // captured frames, the video will be CROPPED to the top-left portion only!
-        //
-        // Solution: Defer encoder creation until the first frame arrives, then use
-        // frame.width()/height() to get the ACTUAL capture dimensions. This ensures
-        // the encoder always matches the captured content exactly.
-        //
-        // See: https://github.com/user/peppi/issues/XXX (recording cropped on high-DPI displays)
-        //
-        let flags = CaptureFlags {
-            width,
-            height,
-            output_path: output_path.to_string(),
-            enable_audio: self.audio_capture.is_some(),
-            bitrate: quality.bitrate(),
-            state: capture_state.clone(),
-            use_frame_dimensions: true,
-        };
-
-        // Start capture
-        let capture_control = match target {
-            CaptureTarget::Window(window) => self.start_window_capture(window, flags)?,
-            CaptureTarget::Monitor(monitor) => self.start_monitor_capture(monitor, flags)?,
-        };
+        // Arm the capture: the encoder is created on the next frame
+        let armed = self.capture_state.as_ref().and_then(|state| state.lock().ok()).map(|mut state| {
+            state.encoder_config = Some(EncoderConfig {
+                output_path: output_path.to_string(),
+                enable_audio: self.audio_capture.is_some(),
+                bitrate: quality.bitrate(),
+            });
+            state.armed_at = Some(Instant::now());
+        });
+        if armed.is_none() {
+            self.close_pipeline();
+            return Err(Error::RecordingFailed("Capture state unavailable".into()));
+        }
 
-        self.capture_control = Some(capture_control);
-        self.capture_state = Some(capture_state);
         self.output_path = Some(output_path.to_string());
         self.is_recording = true;
 
@@ -700,27 +822,9 @@ impl Recorder for WindowsRecorder {
         }
 
         info!("Stopping recording...");
-
-        // Stop audio first
-        if let Some(mut audio) = self.audio_capture.take() {
-            audio.stop();
-        }
-
-        // Signal stop
-        if let Some(ref state) = self.capture_state {
-            if let Ok(mut s) = state.lock() {
-                s.stop_requested = true;
-                info!("Recorded {} frames", s.frame_count);
-            }
-        }
-
-        // Stop capture
-        if let Some(control) = self.capture_control.take() {
-            let _ = control.stop();
-        }
+        self.close_pipeline();
 
         let output = self.output_path.take().unwrap_or_default();
-        self.capture_state = None;
         self.is_recording = false;
 
         info!("Recording saved to {}", output);
@@ -730,6 +834,49 @@ impl Recorder for WindowsRecorder {
     fn is_recording(&self) -> bool {
         self.is_recording
     }
+
+    fn capture_stats(&self) -> Option<CaptureStats> {
+        let state = self.capture_state.as_ref()?.lock().ok()?;
+        let start_time = state.start_time?;
+        Some(CaptureStats {
+            frame_count: state.frame_count,
+            elapsed_secs: start_time.elapsed().as_secs_f64(),
+            longest_stall_ms: state.longest_frame_gap.as_millis() as u64,
+        })
+    }
+
+    fn target_description(&self) -> Option<String> {
+        self.target_description.clone()
+    }
+
+    fn first_frame_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        let state = self.capture_state.as_ref()?.lock().ok()?;
+        let elapsed = chrono::Duration::from_std(state.start_time?.elapsed()).ok()?;
+        Some(chrono::Utc::now() - elapsed)
+    }
+
+    fn prewarm(&mut self) -> Result<bool, Error> {
+        if self.is_recording {
+            return Err(Error::RecordingFailed("Already recording".into()));
+        }
+        self.release_prewarm();
+
+        // The monitor fallback would keep capturing the monitor once the game opens
+        let target = self.find_target()?;
+        if matches!(target, CaptureTarget::Monitor(_)) {
+            return Err(Error::WindowCapture("No game window to prewarm for".into()));
+        }
+        self.open_pipeline(target)?;
+        info!("Capture prewarmed: {}", self.target_description.as_deref().unwrap_or("unknown target"));
+        Ok(true)
+    }
+
+    fn release_prewarm(&mut self) {
+        if !self.is_recording && self.capture_control.is_some() {
+            self.close_pipeline();
+            info!("Prewarmed capture released");
+        }
+    }
 }
 
 #[cfg(all(target_os = "windows", feature = "real-recording"))]
@@ -755,6 +902,20 @@ fn resolve_audio_enabled() -> bool {
     }
 }
 
+/// Device game audio will be captured from, None when audio capture is off
+#[cfg(all(target_os = "windows", feature = "real-recording"))]
+pub fn audio_device() -> Result<Option<String>, String> {
+    use cpal::traits::{DeviceTrait, HostTrait};
+
+    if !resolve_audio_enabled() {
+        return Ok(None);
+    }
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or_else(|| "No output device available".to_string())?;
+    Ok(Some(device.name().unwrap_or_else(|_| "Unknown".to_string())))
+}
+
 #[cfg(all(target_os = "windows", feature = "real-recording"))]
 fn score_window(window: &Window, hint: Option<&str>) -> i32 {
     let mut score = 0;
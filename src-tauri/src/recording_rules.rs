@@ -0,0 +1,213 @@
+//! Recording rules: record, skip or change the quality of games by who's in them
+//!
+//! Rules live in the recording_rules table and match on an opponent's connect
+//! code, the character I play, an opponent's character and whether the game
+//! is online; criteria left empty match every game. They're checked on each
+//! new replay once its Game Start is written (game_session reads it with a
+//! short retry), and the first enabled rule that matches (oldest first)
+//! decides. A record rule overrides the watch filters on the game and the CPU
+//! setting, but not the ignore patterns on the replay's path.
+//!
+//! Me is found as for Discord presence: by connect code, then by my preferred
+//! characters if only one player plays one. When I can't be told apart, rules
+//! on my character don't match and every player counts as an opponent. Each
+//! decision is written to recording_rule_log.
+
+use crate::app_state::AppState;
+use crate::commands::settings::my_identity;
+use crate::database::{self, MyIdentity, RecordingRuleAction, RecordingRuleRow};
+use crate::recorder::RecordingQuality;
+use crate::slippi::game_start::{GameStart, GameStartPlayer};
+use crate::watch_filters::ConnectionFilter;
+use tauri::Manager;
+
+/// Check a rule before it's saved
+pub fn validate_rule(rule: &RecordingRuleRow) -> Result<(), String> {
+    if rule.name.trim().is_empty() {
+        return Err("Rule name can't be empty".to_string());
+    }
+    if rule.action == RecordingRuleAction::RecordAtQuality && rule.quality.is_none() {
+        return Err("Pick the quality to record at".to_string());
+    }
+    let has_criteria = rule.opponent_code.as_ref().is_some_and(|c| !c.trim().is_empty())
+        || rule.my_character.is_some()
+        || rule.opponent_character.is_some()
+        || rule.connection != ConnectionFilter::Any;
+    // A rule without criteria would decide every game
+    if !has_criteria {
+        return Err("Pick at least one thing to match (opponent, characters or online/offline)".to_string());
+    }
+    Ok(())
+}
+
+/// The enabled rules, read on every new replay so changes apply right away
+pub fn enabled_rules(app: &tauri::AppHandle) -> Vec<RecordingRuleRow> {
+    let state = app.state::<AppState>();
+    let conn = state.database.connection();
+    match database::get_recording_rules(&conn) {
+        Ok(rules) => rules.into_iter().filter(|rule| rule.enabled).collect(),
+        Err(e) => {
+            log::warn!("Failed to read recording rules, recording as usual: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// The rule deciding a new game, if any. It's logged, and its quality kept
+/// for the recording
+pub fn decide(app: &tauri::AppHandle, slp_path: &str, rules: &[RecordingRuleRow], game: &GameStart) -> Option<RecordingRuleRow> {
+    let state = app.state::<AppState>();
+    let rule = matching_rule(rules, game, &my_identity(app)).cloned();
+    let quality = rule
+        .as_ref()
+        .filter(|rule| rule.action == RecordingRuleAction::RecordAtQuality)
+        .and_then(|rule| rule.quality);
+    if let Ok(mut rule_quality) = state.rule_quality.lock() {
+        *rule_quality = quality.map(|quality| (slp_path.to_string(), quality));
+    }
+
+    let rule = rule?;
+    log::info!("📋 Recording rule '{}' decided {}: {:?} {:?}", rule.name, slp_path, rule.action, quality);
+    // Decided as the game starts, so the log entry can wait
+    let (logged_path, logged_rule) = (slp_path.to_string(), rule.clone());
+    state.database.write_behind("recording rule log", move |conn| {
+        database::log_recording_rule(conn, &logged_path, &logged_rule)
+    });
+    Some(rule)
+}
+
+/// The quality a rule picked for the recording of `slp_path`, if any
+pub(crate) fn take_quality(state: &AppState, slp_path: &str) -> Option<RecordingQuality> {
+    let mut rule_quality = state.rule_quality.lock().ok()?;
+    match rule_quality.take() {
+        Some((path, quality)) if path == slp_path => Some(quality),
+        _ => None,
+    }
+}
+
+/// The first rule (in order) matching the game
+fn matching_rule<'a>(rules: &'a [RecordingRuleRow], game: &GameStart, identity: &MyIdentity) -> Option<&'a RecordingRuleRow> {
+    let me = my_port(game, identity);
+    let my_character = me.and_then(|port| game.players.iter().find(|p| p.port == port)).map(|p| p.character_id);
+    let opponents: Vec<&GameStartPlayer> = game.players.iter().filter(|p| Some(p.port) != me).collect();
+    let online = game.is_online();
+
+    rules.iter().filter(|rule| rule.enabled).find(|rule| {
+        let connection = match rule.connection {
+            ConnectionFilter::Any => true,
+            ConnectionFilter::Online => online,
+            ConnectionFilter::Offline => !online,
+        };
+        let character = rule.my_character.is_none_or(|c| my_character == Some(c));
+        // Both opponent criteria have to hold for the same opponent
+        let opponent = opponents.iter().any(|p| {
+            rule.opponent_code
+                .as_ref()
+                .is_none_or(|code| p.connect_code.as_ref().is_some_and(|c| c.eq_ignore_ascii_case(code.trim())))
+                && rule.opponent_character.is_none_or(|c| p.character_id == c)
+        });
+        let matched = connection && character && opponent;
+        log::debug!("Recording rule '{}' {}", rule.name, if matched { "matched" } else { "passed" });
+        matched
+    })
+}
+
+/// My port: by connect code, then by preferred character if only one player
+/// plays one
+fn my_port(game: &GameStart, identity: &MyIdentity) -> Option<u8> {
+    let by_code = game.players.iter().find(|p| {
+        p.connect_code
+            .as_ref()
+            .is_some_and(|c| identity.connect_codes.contains(&c.to_uppercase()))
+    });
+    if let Some(player) = by_code {
+        return Some(player.port);
+    }
+    let preferred: Vec<&GameStartPlayer> = game
+        .players
+        .iter()
+        .filter(|p| !p.is_cpu && identity.preferred_characters.contains(&i32::from(p.character_id)))
+        .collect();
+    match preferred.as_slice() {
+        [player] => Some(player.port),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOX: u8 = 2;
+    const MARTH: u8 = 9;
+
+    fn player(port: u8, character_id: u8, connect_code: Option<&str>) -> GameStartPlayer {
+        GameStartPlayer { port, character_id, connect_code: connect_code.map(String::from), is_cpu: false }
+    }
+
+    fn game(players: Vec<GameStartPlayer>) -> GameStart {
+        GameStart {
+            version: crate::slippi::ReplayVersion(3, 16, 0),
+            stage: 31,
+            is_teams: false,
+            players,
+            match_id: None,
+        }
+    }
+
+    fn rule(name: &str, action: RecordingRuleAction) -> RecordingRuleRow {
+        RecordingRuleRow {
+            id: None,
+            name: name.to_string(),
+            enabled: true,
+            opponent_code: None,
+            my_character: None,
+            opponent_character: None,
+            connection: ConnectionFilter::Any,
+            action,
+            quality: None,
+        }
+    }
+
+    #[test]
+    fn test_first_matching_rule_decides() {
+        let identity = MyIdentity { connect_codes: vec!["ME#1".to_string()], ..Default::default() };
+        let always_plup = RecordingRuleRow { opponent_code: Some("plup#123".to_string()), ..rule("PLUP", RecordingRuleAction::Record) };
+        let roommate_dittos = RecordingRuleRow {
+            opponent_code: Some("ROOM#1".to_string()),
+            my_character: Some(FOX),
+            opponent_character: Some(FOX),
+            ..rule("Roommate dittos", RecordingRuleAction::Skip)
+        };
+        let online_low = RecordingRuleRow {
+            connection: ConnectionFilter::Online,
+            quality: Some(RecordingQuality::Low),
+            ..rule("Online", RecordingRuleAction::RecordAtQuality)
+        };
+        let rules = [always_plup, roommate_dittos, online_low];
+        let name = |game: &GameStart, identity: &MyIdentity| matching_rule(&rules, game, identity).map(|r| r.name.as_str());
+
+        let plup = game(vec![player(0, FOX, Some("ME#1")), player(1, MARTH, Some("PLUP#123"))]);
+        assert_eq!(name(&plup, &identity), Some("PLUP"));
+        let ditto = game(vec![player(0, FOX, Some("ROOM#1")), player(1, FOX, Some("ME#1"))]);
+        assert_eq!(name(&ditto, &identity), Some("Roommate dittos"));
+        let not_a_ditto = game(vec![player(0, MARTH, Some("ROOM#1")), player(1, FOX, Some("ME#1"))]);
+        assert_eq!(name(&not_a_ditto, &identity), Some("Online"));
+        assert_eq!(name(&game(vec![player(0, FOX, None), player(1, FOX, None)]), &identity), None);
+
+        // Without knowing which player I am, my character can't be matched
+        assert_eq!(name(&ditto, &MyIdentity::default()), Some("Online"));
+
+        let mut disabled = rules.clone();
+        disabled[0].enabled = false;
+        assert_eq!(matching_rule(&disabled, &plup, &identity).map(|r| r.name.as_str()), Some("Online"));
+    }
+
+    #[test]
+    fn test_rules_need_criteria_and_a_quality() {
+        assert!(validate_rule(&rule("Everything", RecordingRuleAction::Skip)).is_err());
+        let online = RecordingRuleRow { connection: ConnectionFilter::Online, ..rule("Online", RecordingRuleAction::RecordAtQuality) };
+        assert!(validate_rule(&online).is_err());
+        assert!(validate_rule(&RecordingRuleRow { quality: Some(RecordingQuality::Low), ..online }).is_ok());
+    }
+}
@@ -0,0 +1,315 @@
+//! Recovering recordings interrupted by a crash or power loss
+//!
+//! An MP4 only gets its index when the recorder finishes it, so a recording
+//! cut off by a bluescreen is a large file most players refuse to open. A
+//! small journal in the app data directory notes each recording when it
+//! starts and is removed when it stops cleanly; finding it on startup means
+//! the previous session ended while recording.
+//!
+//! Recovery then looks at the journaled file and any other video in the
+//! recording directory modified since that recording started. Each one that
+//! doesn't probe as a playable video is remuxed with FFmpeg, which rebuilds
+//! the index when the streams can still be found. Files without any index
+//! need untrunc (if installed), which rebuilds it from a healthy recording
+//! made by the same recorder. A recovered file replaces the original and is
+//! indexed; the rest are reported and left as they were.
+
+use crate::app_state::AppState;
+use crate::clip_processor;
+use crate::commands::errors::Error;
+use crate::events;
+use crate::library;
+use crate::metrics;
+use crate::paths;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+use tauri::{Emitter, Manager};
+use walkdir::WalkDir;
+
+const JOURNAL_FILE: &str = "recording-journal.json";
+
+/// The recording in progress, as written to the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JournalEntry {
+    path: String,
+    started_at: String,
+}
+
+/// What recovery found after an unclean shutdown
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryReport {
+    /// When the interrupted recording started
+    pub started_at: String,
+    /// Files repaired and added to the library
+    pub recovered: Vec<String>,
+    pub unrecoverable: Vec<UnrecoverableFile>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnrecoverableFile {
+    pub path: String,
+    pub error: String,
+}
+
+impl metrics::Outcome for RecoveryReport {
+    fn succeeded(&self) -> bool {
+        self.unrecoverable.is_empty()
+    }
+}
+
+/// Note a recording that just started
+pub fn journal_started(app: &tauri::AppHandle, output_path: &str) {
+    let entry = JournalEntry { path: output_path.to_string(), started_at: chrono::Utc::now().to_rfc3339() };
+    let written = journal_path(app).and_then(|path| {
+        let json = serde_json::to_vec(&entry).map_err(|e| Error::RecordingFailed(e.to_string()))?;
+        std::fs::write(path, json)?;
+        Ok(())
+    });
+    if let Err(e) = written {
+        log::warn!("Failed to write the recording journal: {}", e);
+    }
+}
+
+/// The recording stopped cleanly
+pub fn journal_stopped(app: &tauri::AppHandle) {
+    if let Ok(path) = journal_path(app) {
+        if path.exists() {
+            if let Err(e) = std::fs::remove_file(&path) {
+                log::warn!("Failed to clear the recording journal: {}", e);
+            }
+        }
+    }
+}
+
+/// Recover what the last session was recording, if it ended uncleanly
+/// The report is kept for take_recovery_report and announced with an event
+pub fn spawn_recovery(app: &tauri::AppHandle) {
+    let Some(entry) = read_journal(app) else { return };
+    log::warn!("⚠️ The last session ended while recording {}", entry.path);
+
+    // Anything modified after this is the current session's
+    let session_start = SystemTime::now();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let report = recover(&app, &entry, session_start).await;
+        log::info!(
+            "🩹 Recovery: {} recovered, {} unrecoverable",
+            report.recovered.len(),
+            report.unrecoverable.len()
+        );
+
+        let state = app.state::<AppState>();
+        if let Ok(mut pending) = state.pending_recovery.lock() {
+            *pending = Some(report.clone());
+        }
+        if let Err(e) = app.emit(events::recording::RECOVERY_FINISHED, report) {
+            log::error!("Failed to emit {} event: {:?}", events::recording::RECOVERY_FINISHED, e);
+        }
+        journal_stopped(&app);
+    });
+}
+
+#[metrics::timed(job = "recovery")]
+async fn recover(app: &tauri::AppHandle, entry: &JournalEntry, session_start: SystemTime) -> RecoveryReport {
+    let mut report = RecoveryReport {
+        started_at: entry.started_at.clone(),
+        recovered: Vec::new(),
+        unrecoverable: Vec::new(),
+    };
+    let recording_dir = library::get_recording_directory(app).await.ok();
+    let started_at = chrono::DateTime::parse_from_rfc3339(&entry.started_at)
+        .map(SystemTime::from)
+        .unwrap_or(session_start);
+
+    let (candidates, others) = scan(recording_dir.as_deref(), &entry.path, started_at, session_start);
+    if candidates.is_empty() {
+        return report;
+    }
+
+    let checked = tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = clip_processor::ensure_ffmpeg() {
+            return candidates.into_iter().map(|path| (path, Err(e.to_string()))).collect();
+        }
+        let reference = others.iter().find(|path| is_playable(path)).cloned();
+        candidates
+            .into_iter()
+            .map(|path| {
+                let result = repair(&path, reference.as_deref()).map_err(|e| e.to_string());
+                (path, result)
+            })
+            .collect::<Vec<(PathBuf, Result<bool, String>)>>()
+    })
+    .await
+    .unwrap_or_default();
+
+    for (path, result) in checked {
+        let path_str = path.to_string_lossy().to_string();
+        match result {
+            Ok(repaired) => {
+                if let Err(e) = library::index_recording(app, &path).await {
+                    log::warn!("Failed to index recovered recording {}: {}", path_str, e);
+                }
+                if repaired {
+                    report.recovered.push(path_str);
+                }
+            }
+            Err(error) => {
+                log::warn!("Couldn't recover {}: {}", path_str, error);
+                report.unrecoverable.push(UnrecoverableFile { path: path_str, error });
+            }
+        }
+    }
+    report
+}
+
+/// Videos that may have been cut off (the journaled one, and anything
+/// modified while it was recording), and the rest of the library, newest first
+fn scan(
+    recording_dir: Option<&str>,
+    journaled: &str,
+    started_at: SystemTime,
+    session_start: SystemTime,
+) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let mut candidates = Vec::new();
+    if Path::new(journaled).exists() {
+        candidates.push(PathBuf::from(journaled));
+    }
+    let mut others: Vec<(SystemTime, PathBuf)> = Vec::new();
+
+    let entries = recording_dir
+        .into_iter()
+        .flat_map(|dir| WalkDir::new(dir).max_depth(3).into_iter().filter_map(|e| e.ok()));
+    for entry in entries {
+        let path = entry.path();
+        if path.extension().and_then(|s| s.to_str()) != Some("mp4") || path == Path::new(journaled) {
+            continue;
+        }
+        let Some(modified) = entry.metadata().ok().and_then(|m| m.modified().ok()) else { continue };
+        if modified >= started_at && modified <= session_start {
+            candidates.push(path.to_path_buf());
+        } else {
+            others.push((modified, path.to_path_buf()));
+        }
+    }
+    others.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    (candidates, others.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Make a video playable. Returns whether it had to be repaired
+fn repair(path: &Path, reference: Option<&Path>) -> Result<bool, Error> {
+    if is_playable(path) {
+        return Ok(false);
+    }
+    let temp = path.with_extension("recovering.mp4");
+
+    // First pass: remux, which works whenever FFmpeg can read the streams
+    let remuxed = clip_processor::remux_video(path, &temp).map(|()| temp.clone());
+    // Second pass: rebuild the index from a healthy recording
+    let repaired = match remuxed {
+        Ok(repaired) if is_playable(&repaired) => Ok(repaired),
+        _ => {
+            let _ = std::fs::remove_file(&temp);
+            match reference {
+                Some(reference) => untrunc(reference, path),
+                None => Err(Error::RecordingFailed(
+                    "FFmpeg couldn't read the video, and there's no healthy recording to rebuild it from".to_string(),
+                )),
+            }
+        }
+    }?;
+
+    clip_processor::replace_with(path, &repaired)?;
+    log::info!("🩹 Recovered {}", path.display());
+    Ok(true)
+}
+
+/// Rebuild a video's index with untrunc, using a recording from the same
+/// recorder as the template. Returns the repaired copy
+fn untrunc(reference: &Path, broken: &Path) -> Result<PathBuf, Error> {
+    let mut command = Command::new("untrunc");
+    command.arg(paths::process_arg(reference)).arg(paths::process_arg(broken));
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = command.output().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => Error::RecordingFailed(
+            "The video has no index left; install untrunc to try rebuilding it".to_string(),
+        ),
+        _ => Error::RecordingFailed(format!("Failed to run untrunc: {}", e)),
+    })?;
+    // untrunc writes <file>_fixed.mp4 next to the broken file
+    let mut fixed = broken.as_os_str().to_owned();
+    fixed.push("_fixed.mp4");
+    let fixed = PathBuf::from(fixed);
+    if !output.status.success() || !is_playable(&fixed) {
+        let _ = std::fs::remove_file(&fixed);
+        return Err(Error::RecordingFailed("untrunc couldn't rebuild the video".to_string()));
+    }
+    Ok(fixed)
+}
+
+fn is_playable(path: &Path) -> bool {
+    clip_processor::probe_video(path).is_ok_and(|probe| probe.duration_secs > 0.0)
+}
+
+fn read_journal(app: &tauri::AppHandle) -> Option<JournalEntry> {
+    let json = std::fs::read(journal_path(app).ok()?).ok()?;
+    match serde_json::from_slice(&json) {
+        Ok(entry) => Some(entry),
+        Err(e) => {
+            log::warn!("Ignoring an unreadable recording journal: {}", e);
+            journal_stopped(app);
+            None
+        }
+    }
+}
+
+fn journal_path(app: &tauri::AppHandle) -> Result<PathBuf, Error> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| Error::InitializationError(format!("Failed to get app data directory: {}", e)))?;
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join(JOURNAL_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_scan_finds_files_written_while_recording() {
+        let dir = std::env::temp_dir().join(format!("recovery-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let now = SystemTime::now();
+        let touch = |name: &str, modified: SystemTime| {
+            let path = dir.join(name);
+            std::fs::File::create(&path).unwrap().set_modified(modified).unwrap();
+            path
+        };
+        let started_at = now - Duration::from_secs(3600);
+        let journaled = touch("Game_1.mp4", now - Duration::from_secs(1800));
+        let split = touch("Game_2.mp4", now - Duration::from_secs(600));
+        let old = touch("Game_0.mp4", now - Duration::from_secs(86_400));
+        let older = touch("Game_00.mp4", now - Duration::from_secs(2 * 86_400));
+        touch("notes.txt", now - Duration::from_secs(600));
+
+        let (candidates, others) = scan(Some(&dir.to_string_lossy()), &journaled.to_string_lossy(), started_at, now);
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0], journaled);
+        assert!(candidates.contains(&split));
+        assert_eq!(others, vec![old, older]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,455 @@
+//! Retention: delete or compress recordings as they age
+//!
+//! Rules live in the retention_rules table. Each one matches on a player's
+//! in-game tag, the opponent (connect code or tag), the result from the
+//! user's point of view and a minimum age; criteria left empty match every
+//! recording. A recording matched by a keep rule is never touched, otherwise
+//! the first matching rule (oldest first) decides. Clips (anything in the
+//! Clips folder) are never touched.
+//!
+//! Compression re-encodes with the two-pass encoder at the Low quality preset
+//! into a temp file next to the video, then renames it over the original so
+//! the library never sees a half-written file.
+//!
+//! Enabled rules run on their own once a week (never while recording or on
+//! battery, and only in the quiet hours if they're set) and report what they
+//! did with a COMPLETED event.
+
+use crate::app_state::AppState;
+use crate::clip_processor;
+use crate::commands::clips::default_clips_directory;
+use crate::commands::errors::Error;
+use crate::database::{self, RecordingWithStats, ResultFilter, RetentionAction, RetentionRuleRow};
+use crate::events;
+use crate::game_detector::suspension;
+use crate::library;
+use crate::paths;
+use crate::recorder::RecordingQuality;
+use crate::scheduler::{Cadence, Constraints, Job};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+
+/// Settings key for when the scheduled run last happened
+const LAST_RUN_KEY: &str = "retentionLastRun";
+const SCHEDULE_INTERVAL_DAYS: i64 = 7;
+/// How often the scheduler checks whether a run is due
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Leave startup (cache sync, stats) alone before the first check
+const SCHEDULE_STARTUP_DELAY: Duration = Duration::from_secs(5 * 60);
+/// Quality preset compressed videos are re-encoded to
+const COMPRESS_QUALITY: RecordingQuality = RecordingQuality::Low;
+const COMPRESS_AUDIO_BITRATE: u32 = 96_000;
+/// Videos already within this factor of the compressed bitrate are left alone
+const COMPRESS_MIN_RATIO: f64 = 1.5;
+
+/// What a rule did (or would do) to one recording
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionOutcome {
+    pub recording_id: String,
+    pub video_path: String,
+    pub rule_id: i64,
+    pub rule_name: String,
+    pub action: RetentionAction,
+    /// Bytes freed; estimated on a dry run, None if unknown
+    pub freed_bytes: Option<u64>,
+    /// Why the action failed
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionReport {
+    pub dry_run: bool,
+    pub outcomes: Vec<RetentionOutcome>,
+}
+
+/// Payload of the COMPLETED event
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionSummary {
+    pub deleted: usize,
+    pub compressed: usize,
+    pub failed: usize,
+    pub freed_bytes: u64,
+}
+
+impl RetentionReport {
+    pub fn summary(&self) -> RetentionSummary {
+        let mut summary = RetentionSummary::default();
+        for outcome in &self.outcomes {
+            if outcome.error.is_some() {
+                summary.failed += 1;
+                continue;
+            }
+            match outcome.action {
+                RetentionAction::Compress => summary.compressed += 1,
+                _ => summary.deleted += 1,
+            }
+            summary.freed_bytes += outcome.freed_bytes.unwrap_or(0);
+        }
+        summary
+    }
+}
+
+/// What the rules can see of a recording
+#[derive(Debug, Default)]
+struct Candidate {
+    /// Every player's in-game tag, lowercase
+    tags: Vec<String>,
+    /// Opponent's connect code and tag, lowercase (empty when "me" is unknown)
+    opponent: Vec<String>,
+    result: Option<ResultFilter>,
+    age_days: Option<i64>,
+}
+
+impl Candidate {
+    fn from_recording(rws: &RecordingWithStats, now: chrono::DateTime<chrono::Utc>) -> Self {
+        let recorded_at = rws
+            .recording
+            .start_time
+            .as_deref()
+            .or(rws.recording.file_modified_at.as_deref())
+            .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok());
+        let me = rws.player_stats.iter().find(|ps| ps.is_me);
+        let opponent = me.and_then(|me| rws.player_stats.iter().find(|ps| ps.player_index != me.player_index));
+
+        Self {
+            tags: rws
+                .player_stats
+                .iter()
+                .filter_map(|ps| ps.display_name.as_deref())
+                .map(str::to_lowercase)
+                .collect(),
+            opponent: opponent
+                .map(|ps| [&ps.connect_code, &ps.display_name].into_iter().flatten().map(|s| s.to_lowercase()).collect())
+                .unwrap_or_default(),
+            result: me.zip(rws.stats.as_ref()).and_then(|(me, gs)| gs.won_by(me)).map(|won| {
+                if won { ResultFilter::Win } else { ResultFilter::Loss }
+            }),
+            age_days: recorded_at.map(|t| (now - t.with_timezone(&chrono::Utc)).num_days()),
+        }
+    }
+
+    fn matches(&self, rule: &RetentionRuleRow) -> bool {
+        let matches_text = |wanted: &Option<String>, values: &[String]| match wanted {
+            Some(wanted) => values.contains(&wanted.trim().to_lowercase()),
+            None => true,
+        };
+
+        rule.enabled
+            && matches_text(&rule.tag, &self.tags)
+            && matches_text(&rule.opponent, &self.opponent)
+            && rule.result.is_none_or(|result| self.result == Some(result))
+            && rule.min_age_days.is_none_or(|days| self.age_days.is_some_and(|age| age >= days))
+    }
+}
+
+/// The rule that applies to a recording, if any (None when a keep rule matches)
+fn select_rule<'r>(rules: &'r [RetentionRuleRow], candidate: &Candidate) -> Option<&'r RetentionRuleRow> {
+    let mut matching = rules.iter().filter(|rule| candidate.matches(rule));
+    if matching.clone().any(|rule| rule.action == RetentionAction::Keep) {
+        return None;
+    }
+    matching.next()
+}
+
+/// Check a rule before saving it
+pub fn validate_rule(rule: &RetentionRuleRow) -> Result<(), String> {
+    if rule.name.trim().is_empty() {
+        return Err("Rule name can't be empty".to_string());
+    }
+    if rule.min_age_days.is_some_and(|days| days < 0) {
+        return Err("Age must be zero or more days".to_string());
+    }
+    let has_criteria = [&rule.tag, &rule.opponent].iter().any(|c| c.as_ref().is_some_and(|c| !c.trim().is_empty()))
+        || rule.result.is_some()
+        || rule.min_age_days.is_some();
+    // A rule without criteria would delete or compress the whole library
+    if !has_criteria && rule.action != RetentionAction::Keep {
+        return Err("Pick at least one thing to match (tag, opponent, result or age)".to_string());
+    }
+    Ok(())
+}
+
+/// Evaluate the rules against the library and, unless `dry_run`, carry them out.
+/// Videos in `clips_dir` are left alone
+pub fn apply(app: &tauri::AppHandle, clips_dir: &Path, dry_run: bool) -> Result<RetentionReport, Error> {
+    let state = app.state::<AppState>();
+    if state.rewriting_recordings.swap(true, Ordering::SeqCst) {
+        return Err(Error::Io(std::io::Error::new(
+            std::io::ErrorKind::ResourceBusy,
+            "Recordings are already being cleaned up or archived",
+        )));
+    }
+    let _paused = (!dry_run).then(|| suspension::suspend(app, "applying retention rules"));
+    let result = run(app, &state, clips_dir, dry_run);
+    state.rewriting_recordings.store(false, Ordering::SeqCst);
+    result
+}
+
+fn run(app: &tauri::AppHandle, state: &AppState, clips_dir: &Path, dry_run: bool) -> Result<RetentionReport, Error> {
+    let (rules, recordings) = {
+        let conn = state.database.connection();
+        let rules = database::get_retention_rules(&conn)
+            .map_err(Error::from)?;
+        let (recordings, _) = database::get_recordings_paginated(&conn, i32::MAX, 0)
+            .map_err(Error::from)?;
+        (rules, recordings)
+    };
+
+    let now = chrono::Utc::now();
+    let mut outcomes = Vec::new();
+    for rws in &recordings {
+        // Replay-only entries have nothing to clean up
+        let Some(video_path) = rws.recording.video_path.as_deref() else {
+            continue;
+        };
+        if paths::is_within(video_path, clips_dir) || !Path::new(video_path).exists() {
+            continue;
+        }
+        let Some(rule) = select_rule(&rules, &Candidate::from_recording(rws, now)) else {
+            continue;
+        };
+        if rule.action == RetentionAction::Compress && !worth_compressing(rws) {
+            continue;
+        }
+
+        let (freed_bytes, error) = if dry_run {
+            (estimate_freed(rws, rule.action), None)
+        } else {
+            match carry_out(app, state, rws, rule.action) {
+                Ok(freed) => (Some(freed), None),
+                Err(e) => {
+                    log::warn!("Retention rule '{}' failed on {}: {}", rule.name, video_path, e);
+                    (None, Some(e.to_string()))
+                }
+            }
+        };
+        outcomes.push(RetentionOutcome {
+            recording_id: rws.recording.id.clone(),
+            video_path: video_path.to_string(),
+            rule_id: rule.id.unwrap_or_default(),
+            rule_name: rule.name.clone(),
+            action: rule.action,
+            freed_bytes,
+            error,
+        });
+    }
+
+    let report = RetentionReport { dry_run, outcomes };
+    if !dry_run {
+        let summary = report.summary();
+        log::info!(
+            "🧹 Retention: {} deleted, {} compressed, {} failed, {} MB freed",
+            summary.deleted,
+            summary.compressed,
+            summary.failed,
+            summary.freed_bytes / (1024 * 1024)
+        );
+    }
+    Ok(report)
+}
+
+/// Game length in seconds, from the replay
+fn game_duration_secs(rws: &RecordingWithStats) -> Option<f64> {
+    rws.stats
+        .as_ref()
+        .and_then(|gs| gs.game_duration)
+        .filter(|frames| *frames > 0)
+        .map(|frames| frames as f64 / 60.0)
+}
+
+fn compressed_size(duration_secs: f64) -> u64 {
+    ((COMPRESS_QUALITY.bitrate() + COMPRESS_AUDIO_BITRATE) as f64 * duration_secs / 8.0) as u64
+}
+
+/// Skip videos that are already about as small as compression would make them
+fn worth_compressing(rws: &RecordingWithStats) -> bool {
+    if rws.recording.is_archived {
+        return false;
+    }
+    match (rws.recording.file_size, game_duration_secs(rws)) {
+        (Some(size), Some(duration)) => size as f64 > compressed_size(duration) as f64 * COMPRESS_MIN_RATIO,
+        _ => true,
+    }
+}
+
+fn estimate_freed(rws: &RecordingWithStats, action: RetentionAction) -> Option<u64> {
+    let video = rws.recording.file_size.map(|size| size as u64);
+    match action {
+        RetentionAction::Keep => Some(0),
+        RetentionAction::DeleteVideo => video,
+        RetentionAction::DeleteVideoAndReplay => {
+            let replay = rws
+                .recording
+                .slp_path
+                .as_deref()
+                .and_then(|slp| std::fs::metadata(slp).ok())
+                .map(|m| m.len())
+                .unwrap_or(0);
+            video.map(|video| video + replay)
+        }
+        RetentionAction::Compress => video
+            .zip(game_duration_secs(rws))
+            .map(|(video, duration)| video.saturating_sub(compressed_size(duration))),
+    }
+}
+
+/// Carry out an action, returning the bytes freed
+fn carry_out(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    rws: &RecordingWithStats,
+    action: RetentionAction,
+) -> Result<u64, Error> {
+    let row = &rws.recording;
+    let Some(video_path) = row.video_path.as_deref() else {
+        return Err(Error::InvalidPath("Replay-only entries have no video".to_string()));
+    };
+    let video_size = std::fs::metadata(video_path)?.len();
+
+    match action {
+        RetentionAction::Keep => Ok(0),
+        RetentionAction::DeleteVideo | RetentionAction::DeleteVideoAndReplay => {
+            let mut freed = video_size;
+            std::fs::remove_file(video_path)?;
+            if let Some(thumbnail) = row.thumbnail_path.as_deref() {
+                let _ = std::fs::remove_file(thumbnail);
+            }
+            if action == RetentionAction::DeleteVideoAndReplay {
+                if let Some(slp) = row.slp_path.as_deref().filter(|slp| Path::new(slp).exists()) {
+                    freed += std::fs::metadata(slp).map(|m| m.len()).unwrap_or(0);
+                    std::fs::remove_file(slp)?;
+                }
+            }
+
+            let conn = state.database.connection();
+            let _ = database::delete_recording(&conn, &row.id);
+            let _ = library::invalidate_analysis(&library::get_analysis_cache_directory(app), Some(&row.id));
+            log::info!("🗑️ Retention deleted {}", video_path);
+            Ok(freed)
+        }
+        RetentionAction::Compress => {
+            let compressed = clip_processor::shrink_in_place(
+                Path::new(video_path),
+                "compressing",
+                clip_processor::VideoCodec::H264,
+                COMPRESS_QUALITY,
+                COMPRESS_QUALITY.bitrate(),
+                COMPRESS_AUDIO_BITRATE,
+            )?;
+
+            let conn = state.database.connection();
+            let modified_at = compressed.modified_at.as_deref();
+            database::set_recording_file_info(&conn, &row.id, compressed.size as i64, modified_at)
+                .map_err(|e| Error::database("Failed to update file size", e))?;
+            log::info!("🗜️ Retention compressed {} ({} → {} bytes)", video_path, video_size, compressed.size);
+            Ok(video_size - compressed.size)
+        }
+    }
+}
+
+/// Run the rules once a week as a scheduled job. Re-encoding competes with
+/// the capture for CPU and drains a battery, so it waits for neither
+pub fn scheduled_job() -> Job {
+    Job {
+        name: "retention",
+        cadence: Cadence::Every { delay: SCHEDULE_STARTUP_DELAY, interval: SCHEDULE_CHECK_INTERVAL },
+        constraints: Constraints { not_while_recording: true, not_on_battery: true, quiet_hours_only: true },
+        due: is_due,
+        run: |app| {
+            Box::pin(async move {
+                let clips_dir = default_clips_directory(&app).await?;
+                tauri::async_runtime::spawn_blocking(move || run_scheduled(&app, &clips_dir))
+                    .await
+                    .map_err(|e| Error::Io(std::io::Error::other(format!("Retention run stopped: {}", e))))?
+            })
+        },
+    }
+}
+
+fn is_due(app: &tauri::AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    if !database::has_enabled_retention_rules(&state.database.connection()).unwrap_or(false) {
+        return false;
+    }
+
+    let last_run = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get(LAST_RUN_KEY))
+        .and_then(|v| v.as_str().and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok()));
+    last_run.is_none_or(|t| (chrono::Utc::now() - t.with_timezone(&chrono::Utc)).num_days() >= SCHEDULE_INTERVAL_DAYS)
+}
+
+fn run_scheduled(app: &tauri::AppHandle, clips_dir: &Path) -> Result<(), Error> {
+    log::info!("🧹 Running scheduled retention");
+    let report = apply(app, clips_dir, false)?;
+
+    if let Ok(store) = app.store("settings.json") {
+        store.set(LAST_RUN_KEY, chrono::Utc::now().to_rfc3339());
+        if let Err(e) = store.save() {
+            log::warn!("Failed to save retention run time: {}", e);
+        }
+    }
+    if let Err(e) = app.emit(events::retention::COMPLETED, report.summary()) {
+        log::warn!("Failed to emit retention summary: {}", e);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, action: RetentionAction) -> RetentionRuleRow {
+        RetentionRuleRow {
+            id: None,
+            name: name.to_string(),
+            enabled: true,
+            tag: None,
+            opponent: None,
+            result: None,
+            min_age_days: None,
+            action,
+        }
+    }
+
+    #[test]
+    fn test_keep_rules_win_and_criteria_all_have_to_match() {
+        let friendly = Candidate {
+            tags: vec!["me".to_string(), "buddy".to_string()],
+            opponent: vec!["budd#123".to_string(), "buddy".to_string()],
+            result: Some(ResultFilter::Loss),
+            age_days: Some(45),
+        };
+        let tournament = Candidate { tags: vec!["me".to_string(), "ggs".to_string()], ..Default::default() };
+
+        let keep_tournament = RetentionRuleRow { tag: Some("GGS".to_string()), ..rule("Tournament", RetentionAction::Keep) };
+        let old_losses = RetentionRuleRow {
+            result: Some(ResultFilter::Loss),
+            min_age_days: Some(30),
+            ..rule("Old losses", RetentionAction::DeleteVideo)
+        };
+        let old = RetentionRuleRow { min_age_days: Some(30), ..rule("Old", RetentionAction::Compress) };
+        let rules = vec![old_losses, old, keep_tournament];
+
+        assert_eq!(select_rule(&rules, &friendly).map(|r| r.name.as_str()), Some("Old losses"));
+        // Without a date the age criterion can't match
+        assert!(select_rule(&rules, &tournament).is_none());
+        let old_tournament = Candidate { age_days: Some(400), ..tournament };
+        assert!(select_rule(&rules, &old_tournament).is_none());
+
+        let by_opponent = RetentionRuleRow { opponent: Some(" BUDD#123 ".to_string()), ..rule("Buddy", RetentionAction::DeleteVideo) };
+        assert!(friendly.matches(&by_opponent));
+        assert!(!friendly.matches(&RetentionRuleRow { enabled: false, ..by_opponent }));
+
+        assert!(validate_rule(&rule("Everything", RetentionAction::DeleteVideoAndReplay)).is_err());
+        assert!(validate_rule(&rules[0]).is_ok());
+    }
+}
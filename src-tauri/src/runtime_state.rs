@@ -0,0 +1,100 @@
+//! Runtime state carried over a restart
+//!
+//! Whether games are being watched, and in which folder, and when the play
+//! session that watching runs started are runtime state, not settings: they
+//! change as the app runs. They're kept in the settings store as they change
+//! (cleared again by stop_watching), so if the app is closed or crashes while
+//! watching, restore() starts watching the same folder on the next launch and
+//! resumes the session with the games stored since it started. Turning off
+//! restoreStateOnLaunch skips that and forgets the old session.
+//!
+//! The overlay server and the clip hotkey need nothing from here: the server
+//! starts from its overlayServer setting, and the frontend registers the
+//! hotkey and starts polling for the game window whenever it loads.
+
+use crate::app_state::AppState;
+use crate::commands::settings::restore_state_on_launch;
+use crate::commands::slippi::start_watching_in;
+use crate::play_session;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+use tauri_plugin_store::StoreExt;
+
+/// Store key for the folder being watched (absent when not watching)
+const WATCHED_PATH_KEY: &str = "watchedPath";
+/// Store key for when the play session started (absent without one)
+const SESSION_STARTED_AT_KEY: &str = "sessionStartedAt";
+
+/// Note that `folder` is being watched, along with the running session
+pub fn watching_started(app: &tauri::AppHandle, folder: &Path) {
+    let started_at = play_session::started_at(&app.state::<AppState>());
+    save(app, Some(folder), started_at.map(|t| t.to_rfc3339()));
+}
+
+/// Watching stopped, so there's nothing to restore
+pub fn watching_stopped(app: &tauri::AppHandle) {
+    save(app, None, None);
+}
+
+fn save(app: &tauri::AppHandle, folder: Option<&Path>, session_started_at: Option<String>) {
+    let Ok(store) = app.store("settings.json") else {
+        return;
+    };
+    match folder {
+        Some(folder) => store.set(WATCHED_PATH_KEY, folder.to_string_lossy().to_string()),
+        None => {
+            store.delete(WATCHED_PATH_KEY);
+        }
+    }
+    match session_started_at {
+        Some(started_at) => store.set(SESSION_STARTED_AT_KEY, started_at),
+        None => {
+            store.delete(SESSION_STARTED_AT_KEY);
+        }
+    }
+    if let Err(e) = store.save() {
+        log::warn!("Failed to save the runtime state: {}", e);
+    }
+}
+
+/// Start watching and resume the session where the last run left them, if
+/// restoreStateOnLaunch is on. A folder that's gone is logged and forgotten
+pub fn restore(app: &tauri::AppHandle) {
+    let Ok(store) = app.store("settings.json") else {
+        return;
+    };
+    let folder = store.get(WATCHED_PATH_KEY).and_then(|v| v.as_str().map(PathBuf::from));
+    let session_started_at = store
+        .get(SESSION_STARTED_AT_KEY)
+        .and_then(|v| v.as_str().and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok()))
+        .map(|t| t.with_timezone(&chrono::Utc));
+
+    let Some(folder) = folder else {
+        return;
+    };
+    if !restore_state_on_launch(app) {
+        watching_stopped(app);
+        return;
+    }
+    if !folder.is_dir() {
+        log::warn!("⚠️ Not watching {:?} again: the folder is gone", folder);
+        watching_stopped(app);
+        return;
+    }
+
+    // The session goes in first so starting the watcher keeps it
+    let state = app.state::<AppState>();
+    if let Some(started_at) = session_started_at {
+        if let Err(e) = play_session::resume(&state, &state.database.connection(), started_at) {
+            log::warn!("Failed to resume the play session: {}", e);
+        }
+    }
+    match start_watching_in(app, folder.clone()) {
+        Ok(()) => log::info!("🔁 Watching {:?} again, as before the restart", folder),
+        Err(e) => {
+            log::warn!("Failed to watch {:?} again: {}", folder, e);
+            play_session::end(&state);
+            watching_stopped(app);
+        }
+    }
+}
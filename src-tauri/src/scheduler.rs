@@ -0,0 +1,357 @@
+//! Background jobs on a schedule
+//!
+//! Maintenance work (the library sync, FFmpeg setup, retention, highlights,
+//! the weekly report) registers here with a cadence, once after startup or
+//! every so often, and the conditions it runs under: not while recording,
+//! not on battery, only in the quiet hours set in settings. A loop starts the
+//! jobs that are due and whose conditions hold; each job still decides
+//! whether it has anything to do (its feature turned on, its last run old
+//! enough). run_job_now starts one right away, conditions or not.
+
+use crate::app_state::AppState;
+use crate::commands::errors::Error;
+use crate::metrics;
+use crate::settings::QuietHours;
+use crate::timestamps;
+use crate::work_gate;
+use chrono::Timelike;
+use serde::Serialize;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+use tokio::sync::Notify;
+
+/// Settings key for the hours heavy jobs are kept to
+const QUIET_HOURS_KEY: &str = "quietHours";
+/// How often held-back jobs are checked again (and the longest the loop sleeps)
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+pub type JobFuture = Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>;
+
+/// When a job runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cadence {
+    /// Once, this long after startup
+    Startup { delay: Duration },
+    /// First after `delay`, then every `interval`
+    Every { delay: Duration, interval: Duration },
+}
+
+impl Cadence {
+    fn delay(self) -> Duration {
+        match self {
+            Self::Startup { delay } | Self::Every { delay, .. } => delay,
+        }
+    }
+
+    fn interval(self) -> Option<Duration> {
+        match self {
+            Self::Startup { .. } => None,
+            Self::Every { interval, .. } => Some(interval),
+        }
+    }
+}
+
+/// Conditions a job runs under
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Constraints {
+    pub not_while_recording: bool,
+    pub not_on_battery: bool,
+    /// Only inside the quietHours setting (any time when it isn't set)
+    pub quiet_hours_only: bool,
+}
+
+/// Why a due job is held back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Blocker {
+    Recording,
+    OnBattery,
+    OutsideQuietHours,
+}
+
+/// What the constraints are checked against
+#[derive(Debug, Clone, Copy)]
+struct Conditions {
+    recording: bool,
+    on_battery: bool,
+    quiet_hours: Option<QuietHours>,
+    /// Local hour of the day
+    hour: u32,
+}
+
+impl Conditions {
+    fn current(app: &AppHandle) -> Self {
+        let quiet_hours = app
+            .store("settings.json")
+            .ok()
+            .and_then(|store| store.get(QUIET_HOURS_KEY))
+            .and_then(|v| serde_json::from_value::<QuietHours>(v).ok())
+            .filter(|hours| hours.is_valid());
+        Self {
+            recording: work_gate::recording(),
+            on_battery: on_battery(),
+            quiet_hours,
+            hour: chrono::Local::now().hour(),
+        }
+    }
+}
+
+impl Constraints {
+    /// The first condition that holds the job back, if any
+    fn blocker(&self, conditions: &Conditions) -> Option<Blocker> {
+        if self.not_while_recording && conditions.recording {
+            Some(Blocker::Recording)
+        } else if self.not_on_battery && conditions.on_battery {
+            Some(Blocker::OnBattery)
+        } else if self.quiet_hours_only && conditions.quiet_hours.is_some_and(|hours| !hours.contains(conditions.hour)) {
+            Some(Blocker::OutsideQuietHours)
+        } else {
+            None
+        }
+    }
+}
+
+/// A job the scheduler runs
+pub struct Job {
+    pub name: &'static str,
+    pub cadence: Cadence,
+    pub constraints: Constraints,
+    /// Whether there's anything to do, asked once the constraints hold
+    pub due: fn(&AppHandle) -> bool,
+    pub run: fn(AppHandle) -> JobFuture,
+}
+
+struct Entry {
+    job: Job,
+    /// When the job is next looked at (None once a startup job is done)
+    next_check: Option<Instant>,
+    running: bool,
+    blocked_by: Option<Blocker>,
+    last_run_at: Option<String>,
+    last_error: Option<String>,
+}
+
+impl Entry {
+    fn status(&self, now: Instant) -> JobStatus {
+        let next_check_at = self.next_check.map(|at| {
+            let wait = chrono::Duration::from_std(at.saturating_duration_since(now)).unwrap_or_default();
+            timestamps::format(chrono::Utc::now() + wait)
+        });
+        JobStatus {
+            name: self.job.name,
+            interval_secs: self.job.cadence.interval().map(|interval| interval.as_secs()),
+            constraints: self.job.constraints,
+            running: self.running,
+            blocked_by: self.blocked_by,
+            next_check_at,
+            last_run_at: self.last_run_at.clone(),
+            last_error: self.last_error.clone(),
+        }
+    }
+}
+
+/// A job as list_scheduled_jobs reports it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobStatus {
+    pub name: &'static str,
+    /// Seconds between runs, None for a job run once after startup
+    pub interval_secs: Option<u64>,
+    pub constraints: Constraints,
+    pub running: bool,
+    /// Condition that held the job back at the last check
+    pub blocked_by: Option<Blocker>,
+    /// When the scheduler next looks at the job
+    pub next_check_at: Option<String>,
+    pub last_run_at: Option<String>,
+    /// Error of the last run, if it failed
+    pub last_error: Option<String>,
+}
+
+/// The registered jobs
+#[derive(Default)]
+pub struct Scheduler {
+    jobs: Mutex<Vec<Entry>>,
+    /// Wakes the loop early (a job was registered)
+    wake: Notify,
+}
+
+impl Scheduler {
+    pub fn register(&self, job: Job) {
+        let entry = Entry {
+            next_check: Some(Instant::now() + job.cadence.delay()),
+            job,
+            running: false,
+            blocked_by: None,
+            last_run_at: None,
+            last_error: None,
+        };
+        self.lock().push(entry);
+        self.wake.notify_one();
+    }
+
+    pub fn list(&self) -> Vec<JobStatus> {
+        let now = Instant::now();
+        self.lock().iter().map(|entry| entry.status(now)).collect()
+    }
+
+    /// Start a job now, skipping its constraints and due check. A job that's
+    /// already running is left to finish
+    pub fn run_now(&self, app: &AppHandle, name: &str) -> Result<JobStatus, Error> {
+        let mut jobs = self.lock();
+        let entry = jobs
+            .iter_mut()
+            .find(|entry| entry.job.name == name)
+            .ok_or_else(|| Error::UnknownJob { name: name.to_string() })?;
+        if !entry.running {
+            log::info!("⏰ Running {} now", entry.job.name);
+            entry.running = true;
+            entry.blocked_by = None;
+            start(app, entry.job.name, entry.job.run);
+        }
+        Ok(entry.status(Instant::now()))
+    }
+
+    /// Start the jobs that are due and allowed to run. Returns how long to
+    /// sleep before the next check
+    fn start_due(&self, app: &AppHandle) -> Duration {
+        let mut jobs = self.lock();
+        let now = Instant::now();
+        // Read only when a job is due (the battery probe can run a process)
+        let mut conditions = None;
+        for entry in jobs.iter_mut() {
+            if entry.running || entry.next_check.is_none_or(|at| at > now) {
+                continue;
+            }
+            let conditions = *conditions.get_or_insert_with(|| Conditions::current(app));
+            entry.blocked_by = entry.job.constraints.blocker(&conditions);
+            if let Some(blocker) = entry.blocked_by {
+                log::debug!("⏰ {} is held back: {:?}", entry.job.name, blocker);
+                entry.next_check = Some(now + CHECK_INTERVAL);
+                continue;
+            }
+            entry.next_check = entry.job.cadence.interval().map(|interval| now + interval);
+            if (entry.job.due)(app) {
+                entry.running = true;
+                start(app, entry.job.name, entry.job.run);
+            }
+        }
+        jobs.iter()
+            .filter(|entry| !entry.running)
+            .filter_map(|entry| entry.next_check)
+            .map(|at| at.saturating_duration_since(now))
+            .min()
+            .map_or(CHECK_INTERVAL, |wait| wait.min(CHECK_INTERVAL))
+    }
+
+    fn finish(&self, name: &'static str, result: Result<(), Error>) {
+        if let Err(e) = &result {
+            log::warn!("Scheduled {} run failed: {}", name, e);
+        }
+        if let Some(entry) = self.lock().iter_mut().find(|entry| entry.job.name == name) {
+            entry.running = false;
+            entry.last_run_at = Some(timestamps::now());
+            entry.last_error = result.err().map(|e| e.to_string());
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Entry>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn start(app: &AppHandle, name: &'static str, run: fn(AppHandle) -> JobFuture) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        // In its own task so a panic still ends the run (and run_now can restart it)
+        let job = tauri::async_runtime::spawn(run(app.clone()));
+        let result = metrics::job(name, async move {
+            job.await.unwrap_or_else(|e| Err(Error::RecordingFailed(format!("{} stopped: {}", name, e))))
+        })
+        .await;
+        app.state::<AppState>().scheduler.finish(name, result);
+    });
+}
+
+/// Check the registered jobs in the background
+pub fn spawn(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let scheduler = &state.scheduler;
+        loop {
+            let wait = scheduler.start_due(&app);
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = scheduler.wake.notified() => {}
+            }
+        }
+    });
+}
+
+/// Whether the machine is running on battery (false when it can't tell,
+/// which includes desktops)
+#[cfg(windows)]
+fn on_battery() -> bool {
+    use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+    let mut status = SYSTEM_POWER_STATUS::default();
+    // ACLineStatus is 0 offline, 1 online, 255 unknown
+    unsafe { GetSystemPowerStatus(&mut status) }.is_ok() && status.ACLineStatus == 0
+}
+
+#[cfg(target_os = "linux")]
+fn on_battery() -> bool {
+    let Ok(supplies) = std::fs::read_dir("/sys/class/power_supply") else { return false };
+    let (mut battery, mut plugged_in) = (false, false);
+    for supply in supplies.flatten() {
+        let read = |file: &str| std::fs::read_to_string(supply.path().join(file)).unwrap_or_default();
+        match read("type").trim() {
+            "Battery" => battery = true,
+            "Mains" | "USB" => plugged_in |= read("online").trim() == "1",
+            _ => {}
+        }
+    }
+    battery && !plugged_in
+}
+
+#[cfg(target_os = "macos")]
+fn on_battery() -> bool {
+    std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("'Battery Power'"))
+}
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+fn on_battery() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constraints_hold_jobs_back() {
+        let idle = Conditions { recording: false, on_battery: false, quiet_hours: None, hour: 14 };
+        let heavy = Constraints { not_while_recording: true, not_on_battery: true, quiet_hours_only: true };
+        assert_eq!(heavy.blocker(&idle), None);
+        assert_eq!(heavy.blocker(&Conditions { recording: true, on_battery: true, ..idle }), Some(Blocker::Recording));
+        assert_eq!(heavy.blocker(&Conditions { on_battery: true, ..idle }), Some(Blocker::OnBattery));
+        assert_eq!(Constraints::default().blocker(&Conditions { recording: true, on_battery: true, ..idle }), None);
+
+        // 23:00 to 06:00 spans midnight
+        let overnight = Some(QuietHours { start_hour: 23, end_hour: 6 });
+        assert_eq!(heavy.blocker(&Conditions { quiet_hours: overnight, ..idle }), Some(Blocker::OutsideQuietHours));
+        assert_eq!(heavy.blocker(&Conditions { quiet_hours: overnight, hour: 23, ..idle }), None);
+        assert_eq!(heavy.blocker(&Conditions { quiet_hours: overnight, hour: 5, ..idle }), None);
+        assert_eq!(heavy.blocker(&Conditions { quiet_hours: overnight, hour: 6, ..idle }), Some(Blocker::OutsideQuietHours));
+        let afternoon = Some(QuietHours { start_hour: 13, end_hour: 17 });
+        assert_eq!(heavy.blocker(&Conditions { quiet_hours: afternoon, ..idle }), None);
+    }
+}
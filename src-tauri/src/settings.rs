@@ -0,0 +1,606 @@
+//! Typed settings, for moving them between machines
+//!
+//! The frontend owns most of settings.json, so day to day it's read key by
+//! key. Export, import and reset go through the `Settings` struct instead so
+//! every value is checked against its type before it's written.
+//!
+//! Exports leave out secrets (the OBS password and post-recording hook, which
+//! can embed tokens) and the user's identity (connect codes, tag and
+//! characters, which would attribute someone else's games to them), plus
+//! launch-on-startup, which mirrors this machine's OS entry.
+//!
+//! Imports are all or nothing: any invalid value rejects the whole file with
+//! an error per field. Paths that don't exist on this machine are replaced by
+//! the platform default (an empty path) and reported.
+//!
+//! Profiles are named snapshots of the capture setup (recorder backend and
+//! OBS connection, quality, recording and replay folders, the clip hotkey and
+//! the game window), for switching between e.g. a netplay and a tournament
+//! setup. They live in settings.json under `profiles`. Audio isn't part of a
+//! profile: the native recorder always captures the system's default device.
+
+use crate::recorder::RecordingQuality;
+use crate::watch_filters::WatchFilters;
+use crate::work_gate::JobKind;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+/// Bumped when the export layout changes
+const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// Left out of exports and ignored on import
+const PRIVATE_KEYS: [&str; 6] = [
+    "obsPassword",
+    "postRecordingHook",
+    "slippiCode",
+    "myConnectCodes",
+    "myInGameTag",
+    "myPreferredCharacters",
+];
+/// How long a replay must go unwritten before its game counts as over
+pub const REPLAY_QUIET_PERIOD_MS: RangeInclusive<u32> = 250..=10_000;
+/// Scan interval once the watcher falls back to polling
+pub const WATCHER_POLL_INTERVAL_MS: RangeInclusive<u32> = 250..=10_000;
+/// Wait after the game ends for Slippi to finish the replay
+pub const STOP_RECORDING_DELAY_SECONDS: RangeInclusive<u32> = 0..=30;
+/// Extra footage after the game ends (post-game screen and chat)
+pub const END_RECORDING_DELAY_SECONDS: RangeInclusive<u32> = 0..=120;
+/// Time without a new game after which a per-set recording stops
+pub const SET_END_TIMEOUT_MINUTES: RangeInclusive<u32> = 1..=30;
+/// Character select kept before each game, with preRollMode on
+pub const PRE_ROLL_SECONDS: RangeInclusive<u32> = 0..=120;
+/// Games shorter than this are left out of stats
+pub const MIN_GAME_SECONDS_FOR_STATS: RangeInclusive<u32> = 0..=300;
+/// Games at the start of each session flagged as warm-ups
+pub const WARMUP_GAMES: RangeInclusive<u32> = 0..=5;
+/// Break between games after which a new session starts
+pub const SESSION_GAP_MINUTES: RangeInclusive<u32> = 15..=240;
+/// Size the Clips folder is kept under (0 for no cap)
+pub const MAX_CLIPS_SIZE_GB: RangeInclusive<u32> = 0..=10_000;
+/// Folders the library scan goes into below the recording folder
+pub const RECORDINGS_SCAN_DEPTH: RangeInclusive<u32> = 1..=8;
+/// Most patterns the library scan skips
+pub const MAX_SCAN_IGNORE_PATTERNS: usize = 32;
+
+/// Describes this machine rather than the user's preferences
+const MACHINE_KEYS: [&str; 1] = ["launchOnStartup"];
+/// Settings holding a file or folder path, where empty means the platform default
+const PATH_KEYS: [&str; 4] = ["recordingPath", "slippiPath", "playbackDolphinPath", "meleeIsoPath"];
+/// Settings a profile snapshots
+const PROFILE_KEYS: [&str; 8] = [
+    "recorderBackend",
+    "obsHost",
+    "obsPort",
+    "obsPassword",
+    "recordingQuality",
+    "recordingPath",
+    "slippiPath",
+    "createClipHotkey",
+];
+const MAX_PROFILE_NAME_LEN: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Light,
+    Dark,
+    System,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecorderBackend {
+    Native,
+    Obs,
+}
+
+/// File type of finished recordings. The native recorder writes MP4, which
+/// is remuxed to MKV when that's picked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingContainer {
+    Mp4,
+    Mkv,
+}
+
+impl RecordingContainer {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::Mkv => "mkv",
+        }
+    }
+}
+
+/// One video per game, or one per set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecordingGranularity {
+    PerGame,
+    PerSet,
+}
+
+/// Local hours heavy background jobs are kept to (see scheduler), e.g. 2 to
+/// 7. The end hour isn't included; a start after the end spans midnight
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHours {
+    pub start_hour: u32,
+    pub end_hour: u32,
+}
+
+impl QuietHours {
+    pub fn is_valid(self) -> bool {
+        self.start_hour < 24 && self.end_hour < 24 && self.start_hour != self.end_hour
+    }
+
+    pub fn contains(self, hour: u32) -> bool {
+        if self.start_hour <= self.end_hour {
+            (self.start_hour..self.end_hour).contains(&hour)
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+/// Everything in settings.json, with the frontend's defaults
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct Settings {
+    pub theme: Theme,
+    pub recording_path: String,
+    pub recording_quality: RecordingQuality,
+    pub recording_container: RecordingContainer,
+    pub auto_crop_game_viewport: bool,
+    pub auto_start_recording: bool,
+    pub replay_quiet_period_ms: u32,
+    pub watcher_poll_interval_ms: u32,
+    pub stop_recording_delay_seconds: u32,
+    pub end_recording_delay_seconds: u32,
+    pub recording_granularity: RecordingGranularity,
+    pub set_end_timeout_minutes: u32,
+    pub pre_roll_mode: bool,
+    pub pre_roll_seconds: u32,
+    pub capture_issue_threshold: u32,
+    pub recorder_backend: RecorderBackend,
+    pub obs_host: String,
+    pub obs_port: u16,
+    pub obs_password: String,
+    pub post_recording_hook: String,
+    pub write_sidecars: bool,
+    pub rename_recordings: bool,
+    pub recording_name_template: String,
+    pub recordings_scan_depth: u32,
+    pub recordings_scan_ignore: Vec<String>,
+    pub slippi_path: String,
+    pub playback_dolphin_path: String,
+    pub melee_iso_path: String,
+    pub watch_for_games: bool,
+    pub create_clip_hotkey: String,
+    pub clip_duration: u32,
+    pub max_clips_size_gb: u32,
+    pub slippi_code: String,
+    pub overlay_server: bool,
+    pub overlay_server_port: u16,
+    pub enable_discord_presence: bool,
+    pub discord_presence_show_opponent: bool,
+    pub launch_on_startup: bool,
+    pub start_minimized: bool,
+    pub restore_state_on_launch: bool,
+    pub readiness_check_on_game_window: bool,
+    pub auto_highlights: bool,
+    pub auto_highlights_count: u32,
+    pub auto_weekly_report: bool,
+    pub min_game_seconds_for_stats: u32,
+    pub warmup_games: u32,
+    pub session_gap_minutes: u32,
+    pub record_cpu_matches: bool,
+    pub count_unfinished_games: bool,
+    pub replay_only_mode: bool,
+    pub watch_filters: WatchFilters,
+    pub jobs_while_recording: Vec<JobKind>,
+    pub hardware_decode: bool,
+    pub quiet_hours: Option<QuietHours>,
+    pub my_connect_codes: Vec<String>,
+    pub my_in_game_tag: Option<String>,
+    pub my_preferred_characters: Vec<i32>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: Theme::System,
+            recording_path: String::new(),
+            recording_quality: RecordingQuality::High,
+            recording_container: RecordingContainer::Mp4,
+            auto_crop_game_viewport: false,
+            auto_start_recording: true,
+            replay_quiet_period_ms: 1000,
+            watcher_poll_interval_ms: 1000,
+            stop_recording_delay_seconds: 3,
+            end_recording_delay_seconds: 0,
+            recording_granularity: RecordingGranularity::PerGame,
+            set_end_timeout_minutes: 5,
+            pre_roll_mode: false,
+            pre_roll_seconds: 15,
+            capture_issue_threshold: 30,
+            recorder_backend: RecorderBackend::Native,
+            obs_host: "127.0.0.1".to_string(),
+            obs_port: 4455,
+            obs_password: String::new(),
+            post_recording_hook: String::new(),
+            write_sidecars: false,
+            rename_recordings: false,
+            recording_name_template: crate::library::DEFAULT_NAME_TEMPLATE.to_string(),
+            recordings_scan_depth: 3,
+            recordings_scan_ignore: Vec::new(),
+            slippi_path: String::new(),
+            playback_dolphin_path: String::new(),
+            melee_iso_path: String::new(),
+            watch_for_games: true,
+            create_clip_hotkey: "F9".to_string(),
+            clip_duration: 30,
+            max_clips_size_gb: 0,
+            slippi_code: String::new(),
+            overlay_server: false,
+            overlay_server_port: 7650,
+            enable_discord_presence: false,
+            discord_presence_show_opponent: false,
+            launch_on_startup: false,
+            start_minimized: true,
+            restore_state_on_launch: true,
+            readiness_check_on_game_window: true,
+            auto_highlights: false,
+            auto_highlights_count: 3,
+            auto_weekly_report: false,
+            min_game_seconds_for_stats: 0,
+            warmup_games: 0,
+            session_gap_minutes: 60,
+            record_cpu_matches: true,
+            count_unfinished_games: false,
+            replay_only_mode: false,
+            watch_filters: WatchFilters::default(),
+            jobs_while_recording: Vec::new(),
+            hardware_decode: true,
+            quiet_hours: None,
+            my_connect_codes: Vec::new(),
+            my_in_game_tag: None,
+            my_preferred_characters: Vec::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// Settings as settings.json entries
+    pub fn to_entries(&self) -> Map<String, Value> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        }
+    }
+
+    /// Check values serde can't (ranges)
+    fn check_field(&self, key: &str) -> Result<(), String> {
+        match key {
+            "obsPort" if self.obs_port == 0 => Err("Port must be between 1 and 65535".to_string()),
+            "overlayServerPort" if self.overlay_server_port < 1024 => {
+                Err("Port must be between 1024 and 65535".to_string())
+            }
+            "replayQuietPeriodMs" if !REPLAY_QUIET_PERIOD_MS.contains(&self.replay_quiet_period_ms) => {
+                Err("Quiet period must be between 250 and 10000 ms".to_string())
+            }
+            "watcherPollIntervalMs" if !WATCHER_POLL_INTERVAL_MS.contains(&self.watcher_poll_interval_ms) => {
+                Err("Polling interval must be between 250 and 10000 ms".to_string())
+            }
+            "stopRecordingDelaySeconds" if !STOP_RECORDING_DELAY_SECONDS.contains(&self.stop_recording_delay_seconds) => {
+                Err("Stop delay must be between 0 and 30 seconds".to_string())
+            }
+            "endRecordingDelaySeconds" if !END_RECORDING_DELAY_SECONDS.contains(&self.end_recording_delay_seconds) => {
+                Err("Post-game delay must be between 0 and 120 seconds".to_string())
+            }
+            "setEndTimeoutMinutes" if !SET_END_TIMEOUT_MINUTES.contains(&self.set_end_timeout_minutes) => {
+                Err("Set end timeout must be between 1 and 30 minutes".to_string())
+            }
+            "preRollSeconds" if !PRE_ROLL_SECONDS.contains(&self.pre_roll_seconds) => {
+                Err("Character select kept must be between 0 and 120 seconds".to_string())
+            }
+            "clipDuration" if !(5..=60).contains(&self.clip_duration) => {
+                Err("Clip duration must be between 5 and 60 seconds".to_string())
+            }
+            "recordingsScanDepth" if !RECORDINGS_SCAN_DEPTH.contains(&self.recordings_scan_depth) => {
+                Err("Library scan depth must be between 1 and 8 folders".to_string())
+            }
+            "recordingsScanIgnore" if self.recordings_scan_ignore.len() > MAX_SCAN_IGNORE_PATTERNS => {
+                Err(format!("At most {} skipped folder patterns are kept", MAX_SCAN_IGNORE_PATTERNS))
+            }
+            "maxClipsSizeGb" if !MAX_CLIPS_SIZE_GB.contains(&self.max_clips_size_gb) => {
+                Err("Clips folder cap must be between 0 (no cap) and 10000 GB".to_string())
+            }
+            "minGameSecondsForStats" if !MIN_GAME_SECONDS_FOR_STATS.contains(&self.min_game_seconds_for_stats) => {
+                Err("Minimum game length must be between 0 and 300 seconds".to_string())
+            }
+            "warmupGames" if !WARMUP_GAMES.contains(&self.warmup_games) => {
+                Err("Warm-up games must be between 0 and 5".to_string())
+            }
+            "sessionGapMinutes" if !SESSION_GAP_MINUTES.contains(&self.session_gap_minutes) => {
+                Err("Session gap must be between 15 and 240 minutes".to_string())
+            }
+            "watchFilters" => self.watch_filters.check(),
+            "autoHighlightsCount" if !(1..=10).contains(&self.auto_highlights_count) => {
+                Err("Highlight count must be between 1 and 10".to_string())
+            }
+            "quietHours" if self.quiet_hours.is_some_and(|hours| !hours.is_valid()) => {
+                Err("Quiet hours must start and end at different hours between 0 and 23".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Problem with one setting in an imported file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldIssue {
+    pub field: String,
+    pub message: String,
+}
+
+/// Outcome of an import
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    /// False when any field was invalid; nothing is written then
+    pub applied: bool,
+    /// Invalid values
+    pub errors: Vec<FieldIssue>,
+    /// Paths missing on this machine, replaced by the platform default
+    pub replaced_paths: Vec<FieldIssue>,
+    /// Settings that weren't imported (unknown, private or machine-specific)
+    pub ignored: Vec<String>,
+}
+
+/// Build an export file from the current settings.json entries
+pub fn export(entries: &Map<String, Value>) -> Value {
+    // Round-trip through the typed struct so only known, valid values leave
+    let defaults = Settings::default().to_entries();
+    let mut settings = Map::new();
+    for (key, default) in defaults {
+        if PRIVATE_KEYS.contains(&key.as_str()) || MACHINE_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        let value = entries
+            .get(&key)
+            .filter(|value| parse_field(&key, value).is_ok())
+            .cloned()
+            .unwrap_or(default);
+        settings.insert(key, value);
+    }
+
+    serde_json::json!({
+        "formatVersion": EXPORT_FORMAT_VERSION,
+        "appVersion": env!("CARGO_PKG_VERSION"),
+        "settings": settings,
+    })
+}
+
+/// Validate an export file (or a raw settings.json)
+/// Returns the entries to write, or the report when it must be rejected
+pub fn validate_import(file: &Value) -> (Map<String, Value>, ImportReport) {
+    let mut report = ImportReport::default();
+    let Some(imported) = file.get("settings").unwrap_or(file).as_object() else {
+        report.errors.push(FieldIssue {
+            field: "settings".to_string(),
+            message: "Not a settings file".to_string(),
+        });
+        return (Map::new(), report);
+    };
+    if let Some(version) = file.get("formatVersion").and_then(Value::as_u64) {
+        if version > EXPORT_FORMAT_VERSION as u64 {
+            report.errors.push(FieldIssue {
+                field: "formatVersion".to_string(),
+                message: "Exported by a newer version of the app; update to import it".to_string(),
+            });
+            return (Map::new(), report);
+        }
+    }
+
+    let known = Settings::default().to_entries();
+    let mut entries = Map::new();
+    for (key, value) in imported {
+        if !known.contains_key(key) || PRIVATE_KEYS.contains(&key.as_str()) || MACHINE_KEYS.contains(&key.as_str()) {
+            report.ignored.push(key.clone());
+            continue;
+        }
+        if let Err(message) = parse_field(key, value) {
+            report.errors.push(FieldIssue { field: key.clone(), message });
+            continue;
+        }
+
+        let missing = PATH_KEYS.contains(&key.as_str())
+            && value.as_str().is_some_and(|path| !path.trim().is_empty() && !Path::new(path.trim()).exists());
+        if missing {
+            report.replaced_paths.push(FieldIssue {
+                field: key.clone(),
+                message: format!("{} doesn't exist on this computer; using the default", value.as_str().unwrap_or_default()),
+            });
+            entries.insert(key.clone(), Value::String(String::new()));
+        } else {
+            entries.insert(key.clone(), value.clone());
+        }
+    }
+
+    report.applied = report.errors.is_empty();
+    if !report.applied {
+        entries.clear();
+    }
+    (entries, report)
+}
+
+/// Check one value against its field's type and range
+fn parse_field(key: &str, value: &Value) -> Result<(), String> {
+    let mut single = Map::new();
+    single.insert(key.to_string(), value.clone());
+    let settings: Settings = serde_json::from_value(Value::Object(single)).map_err(|e| {
+        // serde_json appends the position, which means nothing here
+        let message = e.to_string();
+        message.split(" at line ").next().unwrap_or(&message).to_string()
+    })?;
+    settings.check_field(key)
+}
+
+/// Named snapshot of the capture setup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub name: String,
+    pub created_at: String,
+    /// Process name of the game window to capture, if one was picked
+    pub game_window: Option<String>,
+    /// Values of the snapshotted settings
+    pub settings: Map<String, Value>,
+}
+
+/// Snapshot the current settings.json entries into a profile
+pub fn snapshot_profile(name: &str, entries: &Map<String, Value>, game_window: Option<String>) -> Result<Profile, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Profile name can't be empty".to_string());
+    }
+    if name.chars().count() > MAX_PROFILE_NAME_LEN {
+        return Err(format!("Profile name can't be longer than {} characters", MAX_PROFILE_NAME_LEN));
+    }
+
+    let defaults = Settings::default().to_entries();
+    let settings = PROFILE_KEYS
+        .iter()
+        .map(|&key| {
+            let value = entries
+                .get(key)
+                .filter(|value| parse_field(key, value).is_ok())
+                .or_else(|| defaults.get(key))
+                .cloned()
+                .unwrap_or(Value::Null);
+            (key.to_string(), value)
+        })
+        .collect();
+
+    Ok(Profile {
+        name: name.to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        game_window: game_window.filter(|w| !w.trim().is_empty()),
+        settings,
+    })
+}
+
+/// Entries to write when applying a profile
+/// Values that are no longer valid (e.g. edited by hand) are reported instead
+pub fn profile_entries(profile: &Profile) -> Result<Map<String, Value>, Vec<FieldIssue>> {
+    let mut entries = Map::new();
+    let mut errors = Vec::new();
+    for (key, value) in &profile.settings {
+        if !PROFILE_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        match parse_field(key, value) {
+            Ok(()) => {
+                entries.insert(key.clone(), value.clone());
+            }
+            Err(message) => errors.push(FieldIssue { field: key.clone(), message }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(entries)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_export_strips_secrets_and_round_trips() {
+        let mut current = Settings::default().to_entries();
+        current.insert("obsPassword".to_string(), json!("hunter2"));
+        current.insert("myConnectCodes".to_string(), json!(["ME#1"]));
+        current.insert("clipDuration".to_string(), json!(45));
+        current.insert("obsPort".to_string(), json!("not a port"));
+
+        let exported = export(&current);
+        let settings = exported["settings"].as_object().unwrap();
+        assert!(!settings.contains_key("obsPassword"));
+        assert!(!settings.contains_key("myConnectCodes"));
+        assert!(!settings.contains_key("launchOnStartup"));
+        assert_eq!(settings["clipDuration"], json!(45));
+        // Invalid values fall back to the default
+        assert_eq!(settings["obsPort"], json!(4455));
+
+        let (entries, report) = validate_import(&exported);
+        assert!(report.applied);
+        assert!(report.errors.is_empty() && report.replaced_paths.is_empty());
+        assert_eq!(entries["clipDuration"], json!(45));
+    }
+
+    #[test]
+    fn test_import_reports_every_bad_field_and_replaces_missing_paths() {
+        let existing = std::env::temp_dir();
+        let file = json!({
+            "settings": {
+                "theme": "purple",
+                "overlayServerPort": 80,
+                "endRecordingDelaySeconds": 600,
+                "recordingQuality": "ultra",
+                "slippiPath": "/definitely/not/here/Slippi",
+                "recordingPath": existing.to_string_lossy(),
+                "obsPassword": "secret",
+                "somethingNew": true
+            }
+        });
+
+        let (entries, report) = validate_import(&file);
+        assert!(!report.applied);
+        assert!(entries.is_empty());
+        let mut fields: Vec<&str> = report.errors.iter().map(|e| e.field.as_str()).collect();
+        fields.sort();
+        assert_eq!(fields, vec!["endRecordingDelaySeconds", "overlayServerPort", "theme"]);
+        let mut ignored = report.ignored.clone();
+        ignored.sort();
+        assert_eq!(ignored, vec!["obsPassword", "somethingNew"]);
+
+        let file = json!({ "slippiPath": "/definitely/not/here/Slippi", "recordingPath": existing.to_string_lossy() });
+        let (entries, report) = validate_import(&file);
+        assert!(report.applied);
+        assert_eq!(report.replaced_paths[0].field, "slippiPath");
+        assert_eq!(entries["slippiPath"], json!(""));
+        assert_eq!(entries["recordingPath"], json!(existing.to_string_lossy()));
+    }
+
+    #[test]
+    fn test_profile_snapshots_only_the_capture_setup() {
+        let mut current = Settings::default().to_entries();
+        current.insert("recordingQuality".to_string(), json!("extreme"));
+        current.insert("obsHost".to_string(), json!("192.168.1.20"));
+        current.insert("theme".to_string(), json!("dark"));
+
+        assert!(snapshot_profile("  ", &current, None).is_err());
+        let profile = snapshot_profile(" Tournament ", &current, Some("Dolphin.exe".to_string())).unwrap();
+        assert_eq!(profile.name, "Tournament");
+        assert_eq!(profile.game_window.as_deref(), Some("Dolphin.exe"));
+        assert!(!profile.settings.contains_key("theme"));
+        // Invalid values fall back to the default
+        assert_eq!(profile.settings["recordingQuality"], json!("high"));
+
+        let entries = profile_entries(&profile).unwrap();
+        assert_eq!(entries["obsHost"], json!("192.168.1.20"));
+        assert_eq!(entries.len(), PROFILE_KEYS.len());
+
+        let mut edited = profile.clone();
+        edited.settings.insert("obsPort".to_string(), json!(0));
+        assert_eq!(profile_entries(&edited).unwrap_err()[0].field, "obsPort");
+    }
+}
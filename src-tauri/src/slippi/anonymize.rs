@@ -0,0 +1,368 @@
+//! Anonymized copies of replays, for sharing
+//!
+//! A shared replay shouldn't say who played. Names and connect codes are in
+//! two places: Game Start, in fixed-size fields per port, and the UBJSON
+//! metadata Slippi appends after the raw events (each player's netplay name
+//! and code, and the console's nickname). Game Start's fields are
+//! overwritten in place, so the raw element keeps its length and every other
+//! byte. The metadata is copied value by value with those strings swapped
+//! for placeholders by port ("Player 1", "PLYR#1"). Frame data is never
+//! touched.
+//!
+//! Anything in the metadata this reader doesn't understand fails the copy
+//! rather than risk writing a replay Dolphin can't open.
+
+use super::game_start::{encode_connect_code, game_start_range, CONNECT_CODES_OFFSET, CONNECT_CODE_SIZE, RAW_HEADER};
+use super::version::ReplayVersion;
+
+/// In-game tags, since 1.3.0
+const NAMETAGS_OFFSET: usize = 0x161;
+const NAMETAG_SIZE: usize = 0x10;
+const NAMETAGS: ReplayVersion = ReplayVersion(1, 3, 0);
+/// Display names, since 3.9.0 (with connect codes)
+const DISPLAY_NAMES_OFFSET: usize = 0x1A5;
+const DISPLAY_NAME_SIZE: usize = 0x1F;
+/// Slippi user IDs, since 3.11.0
+const USER_IDS_OFFSET: usize = 0x249;
+const USER_ID_SIZE: usize = 0x1D;
+const USER_IDS: ReplayVersion = ReplayVersion(3, 11, 0);
+
+/// Metadata keys of the console's nickname, a player's names and their code
+const CONSOLE_NICK_KEY: &str = "consoleNick";
+const NAMES_KEY: &str = "names";
+const CODE_KEY: &str = "code";
+const CONSOLE_PLACEHOLDER: &str = "Slippi";
+/// Deeper than Slippi ever nests; taken as a broken file
+const MAX_DEPTH: usize = 16;
+
+/// Copy of a replay with the players' names, codes and user IDs replaced
+pub fn anonymize(data: &[u8]) -> Result<Vec<u8>, String> {
+    let game_start = game_start_range(data).ok_or("The file isn't a replay or has no Game Start")?;
+    let mut out = data.to_vec();
+    anonymize_game_start(&mut out[game_start]);
+
+    let header_len = RAW_HEADER.len() + 4;
+    let raw_len = u32::from_be_bytes([data[header_len - 4], data[header_len - 3], data[header_len - 2], data[header_len - 1]]);
+    // Slippi fills in the length (and writes the metadata) when the game ends
+    if raw_len == 0 {
+        return Ok(out);
+    }
+    let raw_end = header_len + raw_len as usize;
+    if raw_end > data.len() {
+        return Err("The file is shorter than its header says".to_string());
+    }
+
+    // The rest of the root object, after `raw`: `metadata`, then its end
+    out.truncate(raw_end);
+    let mut reader = Reader { data, pos: raw_end };
+    if reader.peek().is_some() {
+        copy_container(&mut reader, &mut out, &mut Vec::new(), true, 0)?;
+    }
+    out.extend_from_slice(&data[reader.pos..]);
+    Ok(out)
+}
+
+/// Display name standing in for the player on `port` (0-indexed)
+pub fn display_placeholder(port: Option<usize>) -> String {
+    port.map_or_else(|| "Player".to_string(), |port| format!("Player {}", port + 1))
+}
+
+/// Connect code standing in for the player on `port` (0-indexed)
+pub fn code_placeholder(port: Option<usize>) -> String {
+    format!("PLYR#{}", port.map_or(0, |port| port + 1))
+}
+
+fn anonymize_game_start(event: &mut [u8]) {
+    let Some(version) = event.get(1..4).map(|v| ReplayVersion(v[0], v[1], v[2])) else { return };
+    for port in 0..4 {
+        if version >= NAMETAGS {
+            replace_field(event, NAMETAGS_OFFSET + NAMETAG_SIZE * port, NAMETAG_SIZE, &[]);
+        }
+        if version >= ReplayVersion::NETPLAY_NAMES {
+            let name = display_placeholder(Some(port));
+            replace_field(event, DISPLAY_NAMES_OFFSET + DISPLAY_NAME_SIZE * port, DISPLAY_NAME_SIZE, name.as_bytes());
+            let code = encode_connect_code(&code_placeholder(Some(port)));
+            replace_field(event, CONNECT_CODES_OFFSET + CONNECT_CODE_SIZE * port, CONNECT_CODE_SIZE, &code);
+        }
+        if version >= USER_IDS {
+            replace_field(event, USER_IDS_OFFSET + USER_ID_SIZE * port, USER_ID_SIZE, &[]);
+        }
+    }
+}
+
+/// Overwrite a null-padded field that has something in it (empty ports and
+/// local games stay empty)
+fn replace_field(event: &mut [u8], offset: usize, size: usize, value: &[u8]) {
+    let Some(field) = event.get_mut(offset..offset + size) else { return };
+    if field.iter().all(|&b| b == 0) {
+        return;
+    }
+    field.fill(0);
+    // Leave the last byte as the terminator
+    let len = value.len().min(size - 1);
+    field[..len].copy_from_slice(&value[..len]);
+}
+
+/// What a string at `path` (keys from the root object) is replaced with
+fn placeholder(path: &[String]) -> Option<String> {
+    match path {
+        [.., key] if key == CONSOLE_NICK_KEY => Some(CONSOLE_PLACEHOLDER.to_string()),
+        [.., port, names, key] if names == NAMES_KEY => {
+            let port = port.parse::<usize>().ok();
+            Some(if key == CODE_KEY { code_placeholder(port) } else { display_placeholder(port) })
+        }
+        _ => None,
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.data.get(self.pos..end))
+            .ok_or_else(|| "The replay's metadata is cut off".to_string())?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    /// A UBJSON integer used as a length or count
+    fn length(&mut self) -> Result<usize, String> {
+        let marker = self.byte()?;
+        let value = match marker {
+            b'i' => i64::from(i8::from_be_bytes(self.array()?)),
+            b'U' => i64::from(self.byte()?),
+            b'I' => i64::from(i16::from_be_bytes(self.array()?)),
+            b'l' => i64::from(i32::from_be_bytes(self.array()?)),
+            b'L' => i64::from_be_bytes(self.array()?),
+            _ => return Err(format!("Unexpected length type 0x{:02x} in the replay's metadata", marker)),
+        };
+        usize::try_from(value).map_err(|_| "Negative length in the replay's metadata".to_string())
+    }
+}
+
+/// Copy one value, marker first
+fn copy_value(reader: &mut Reader, out: &mut Vec<u8>, path: &mut Vec<String>, depth: usize) -> Result<(), String> {
+    let marker = reader.byte()?;
+    out.push(marker);
+    copy_payload(reader, out, path, marker, depth)
+}
+
+/// Copy a value of type `marker` (already written, or given once for a
+/// typed container)
+fn copy_payload(reader: &mut Reader, out: &mut Vec<u8>, path: &mut Vec<String>, marker: u8, depth: usize) -> Result<(), String> {
+    let size = match marker {
+        b'Z' | b'N' | b'T' | b'F' => 0,
+        b'i' | b'U' | b'C' => 1,
+        b'I' => 2,
+        b'l' | b'd' => 4,
+        b'L' | b'D' => 8,
+        b'S' | b'H' => {
+            let start = reader.pos;
+            let len = reader.length()?;
+            reader.take(len)?;
+            match placeholder(path).filter(|_| marker == b'S') {
+                Some(text) => {
+                    write_length(out, text.len());
+                    out.extend_from_slice(text.as_bytes());
+                }
+                None => out.extend_from_slice(&reader.data[start..reader.pos]),
+            }
+            return Ok(());
+        }
+        b'{' | b'[' => return copy_container(reader, out, path, marker == b'{', depth + 1),
+        _ => return Err(format!("Unexpected value type 0x{:02x} in the replay's metadata", marker)),
+    };
+    out.extend_from_slice(reader.take(size)?);
+    Ok(())
+}
+
+/// Copy an object's members or an array's items, through its end marker
+/// (counted containers have none)
+fn copy_container(reader: &mut Reader, out: &mut Vec<u8>, path: &mut Vec<String>, object: bool, depth: usize) -> Result<(), String> {
+    if depth > MAX_DEPTH {
+        return Err("The replay's metadata is nested too deeply".to_string());
+    }
+    let end = if object { b'}' } else { b']' };
+
+    let mut item_type = None;
+    if reader.peek() == Some(b'$') {
+        let typed = reader.take(2)?;
+        out.extend_from_slice(typed);
+        item_type = Some(typed[1]);
+    }
+    let mut count = None;
+    if reader.peek() == Some(b'#') {
+        let start = reader.pos;
+        reader.byte()?;
+        count = Some(reader.length()?);
+        out.extend_from_slice(&reader.data[start..reader.pos]);
+    } else if item_type.is_some() {
+        return Err("Typed container without a count in the replay's metadata".to_string());
+    }
+
+    let mut index = 0;
+    loop {
+        match count {
+            Some(count) if index == count => return Ok(()),
+            None if reader.peek() == Some(end) => {
+                out.push(reader.byte()?);
+                return Ok(());
+            }
+            _ => {}
+        }
+        let key = if object {
+            let start = reader.pos;
+            let len = reader.length()?;
+            let key = String::from_utf8_lossy(reader.take(len)?).into_owned();
+            out.extend_from_slice(&reader.data[start..reader.pos]);
+            key
+        } else {
+            index.to_string()
+        };
+
+        path.push(key);
+        match item_type {
+            Some(marker) => copy_payload(reader, out, path, marker, depth)?,
+            None => copy_value(reader, out, path, depth)?,
+        }
+        path.pop();
+        index += 1;
+    }
+}
+
+fn write_length(out: &mut Vec<u8>, len: usize) {
+    match u8::try_from(len) {
+        Ok(len) => out.extend_from_slice(&[b'U', len]),
+        Err(_) => {
+            out.push(b'l');
+            out.extend_from_slice(&i32::try_from(len).unwrap_or(i32::MAX).to_be_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::game_start::{EVENT_PAYLOADS, GAME_START};
+    use super::*;
+
+    fn key(name: &str) -> Vec<u8> {
+        let mut key = vec![b'U', name.len() as u8];
+        key.extend_from_slice(name.as_bytes());
+        key
+    }
+
+    fn string(value: &str) -> Vec<u8> {
+        let mut string = vec![b'S'];
+        string.extend(key(value));
+        string
+    }
+
+    fn replay() -> Vec<u8> {
+        let size = 0x300;
+        let mut game_start = vec![0u8; 1 + size];
+        game_start[0] = GAME_START;
+        game_start[1..4].copy_from_slice(&[3, 16, 0]);
+        game_start[CONNECT_CODES_OFFSET..CONNECT_CODES_OFFSET + 9].copy_from_slice(b"HATS\x81\x94982");
+        game_start[DISPLAY_NAMES_OFFSET..DISPLAY_NAMES_OFFSET + 4].copy_from_slice(b"hats");
+        game_start[USER_IDS_OFFSET..USER_IDS_OFFSET + 4].copy_from_slice(b"uid0");
+        let frame = [0x37, 0xff, 0xff, 0xff, 0x85];
+
+        let mut events = vec![EVENT_PAYLOADS, 7, GAME_START];
+        events.extend_from_slice(&(size as u16).to_be_bytes());
+        events.extend_from_slice(&[0x37, 0, 4]);
+        events.extend(game_start);
+        events.extend_from_slice(&frame);
+
+        let mut data = RAW_HEADER.to_vec();
+        data.extend_from_slice(&(events.len() as u32).to_be_bytes());
+        data.extend(events);
+        data.extend(key("metadata"));
+        data.push(b'{');
+        data.extend(key("startAt"));
+        data.extend(string("2024-03-01T18:02:11Z"));
+        data.extend(key("lastFrame"));
+        data.extend_from_slice(b"l\x00\x00\x1c\x20");
+        data.extend(key("players"));
+        data.push(b'{');
+        data.extend(key("0"));
+        data.extend_from_slice(b"{");
+        data.extend(key("names"));
+        data.push(b'{');
+        data.extend(key("netplay"));
+        data.extend(string("hats"));
+        data.extend(key("code"));
+        data.extend(string("HATS#982"));
+        data.extend_from_slice(b"}");
+        data.extend(key("characters"));
+        data.extend_from_slice(b"{U\x012l\x00\x00\x1c\x20}");
+        data.extend_from_slice(b"}}");
+        data.extend(key("playedOn"));
+        data.extend(string("dolphin"));
+        data.extend(key("consoleNick"));
+        data.extend(string("hats' wii"));
+        data.extend_from_slice(b"}}");
+        data
+    }
+
+    #[test]
+    fn test_anonymize_replaces_names_and_keeps_everything_else() {
+        let data = replay();
+        let anonymized = anonymize(&data).unwrap();
+
+        // Game Start keeps its size; only the name fields change
+        let game_start = game_start_range(&data).unwrap();
+        let raw_end = game_start.end + 5;
+        assert_eq!(anonymized[..game_start.start], data[..game_start.start]);
+        assert_eq!(anonymized[game_start.end..raw_end], data[game_start.end..raw_end]);
+        let event = &anonymized[game_start];
+        assert_eq!(&event[CONNECT_CODES_OFFSET..CONNECT_CODES_OFFSET + 8], b"PLYR\x81\x941\x00");
+        assert_eq!(&event[DISPLAY_NAMES_OFFSET..DISPLAY_NAMES_OFFSET + 9], b"Player 1\x00");
+        assert!(event[USER_IDS_OFFSET..USER_IDS_OFFSET + USER_ID_SIZE].iter().all(|&b| b == 0));
+        // Port 2 was empty and stays empty
+        assert!(event[CONNECT_CODES_OFFSET + CONNECT_CODE_SIZE..][..CONNECT_CODE_SIZE].iter().all(|&b| b == 0));
+
+        let metadata = &anonymized[raw_end..];
+        let contains = |needle: &[u8]| metadata.windows(needle.len()).any(|w| w == needle);
+        assert!(contains(&[key("netplay"), string("Player 1")].concat()));
+        assert!(contains(&[key("code"), string("PLYR#1")].concat()));
+        assert!(contains(&[key("consoleNick"), string("Slippi")].concat()));
+        assert!(contains(&[key("startAt"), string("2024-03-01T18:02:11Z")].concat()));
+        assert!(contains(b"U\x012l\x00\x00\x1c\x20"));
+        assert!(!contains(b"hats") && !contains(b"HATS"));
+        assert_eq!(metadata.last(), Some(&b'}'));
+
+        // Placeholders are already anonymous
+        assert_eq!(anonymize(&anonymized).unwrap(), anonymized);
+
+        // A crashed replay has no metadata; a cut-off one can't be copied safely
+        let mut crashed = data[..raw_end].to_vec();
+        crashed[RAW_HEADER.len()..RAW_HEADER.len() + 4].copy_from_slice(&[0; 4]);
+        let events = RAW_HEADER.len() + 4;
+        assert_eq!(anonymize(&crashed).unwrap()[events..], anonymized[events..raw_end]);
+        assert!(anonymize(&data[..data.len() - 20]).is_err());
+        // So can a length running past the end of memory
+        let mut reader = Reader { data: &data, pos: raw_end };
+        assert!(reader.take(usize::MAX).is_err());
+        assert_eq!(reader.pos, raw_end);
+    }
+}
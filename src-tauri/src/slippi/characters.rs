@@ -0,0 +1,46 @@
+//! The character a player played a game as
+//!
+//! Game Start has the character picked, but Zelda and Sheik can transform
+//! mid-game, so for them the character played is the one Post-Frame shows
+//! most often once the game starts (see slippi::frames).
+
+use super::frames::{self, FrameSelection};
+use super::melee_ids;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+/// External character IDs (as in Game Start)
+pub const ZELDA: u8 = 18;
+pub const SHEIK: u8 = 19;
+
+/// Whether a picked character can end up played as another
+pub fn can_transform(character_id: u8) -> bool {
+    character_id == ZELDA || character_id == SHEIK
+}
+
+/// The external character each of `ports` spent most of the game as, for
+/// ports that played Zelda or Sheik
+pub fn dominant_characters(path: &Path, ports: &[u8]) -> io::Result<BTreeMap<u8, u8>> {
+    if ports.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    let selection = FrameSelection { ports: ports.to_vec(), every_nth: 1 };
+    let mut frames_as: BTreeMap<u8, BTreeMap<u8, u32>> = BTreeMap::new();
+    frames::read_frames(path, &selection, |row| {
+        // The countdown is always as the character picked
+        if row.frame >= 0 {
+            *frames_as.entry(row.port).or_default().entry(row.state.character).or_default() += 1;
+        }
+        Ok::<_, io::Error>(())
+    })?;
+    Ok(frames_as
+        .into_iter()
+        .filter_map(|(port, counts)| {
+            let (internal, _) = counts.into_iter().max_by_key(|&(_, frames)| frames)?;
+            melee_ids::external_character(internal)
+                .filter(|&played| can_transform(played))
+                .map(|played| (port, played))
+        })
+        .collect())
+}
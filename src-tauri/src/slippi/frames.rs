@@ -0,0 +1,322 @@
+//! Streaming reader for per-frame player data
+//!
+//! Reads the Pre-Frame (inputs) and Post-Frame (position, percent, state)
+//! events of a replay into one row per (frame, port), with Nana in the
+//! follower columns of the Ice Climbers' row. Offsets follow the Slippi
+//! replay spec and are relative to the command byte.
+//!
+//! Netplay replays re-send frames that were rolled back, so the last copy of
+//! a frame is the one that happened. Rows are held until the game is more
+//! than MAX_ROLLBACK_FRAMES past them, then handed out in frame order, which
+//! keeps memory flat however long the game is. Truncated replays yield every
+//! frame up to where they stop.
+
+use super::game_start::{EVENT_PAYLOADS, RAW_HEADER};
+use std::collections::BTreeMap;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+const PRE_FRAME: u8 = 0x37;
+const POST_FRAME: u8 = 0x38;
+const GAME_END: u8 = 0x39;
+
+/// The first frame of every game (the countdown starts at -123)
+pub const FIRST_FRAME: i32 = -123;
+/// Slippi never rolls back further than this
+const MAX_ROLLBACK_FRAMES: i32 = 7;
+
+/// Controller inputs from Pre-Frame
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FrameInputs {
+    pub joystick_x: f32,
+    pub joystick_y: f32,
+    pub cstick_x: f32,
+    pub cstick_y: f32,
+    /// Processed analog trigger, the larger of L and R
+    pub trigger: f32,
+    /// Processed button bits
+    pub buttons: u32,
+    /// Button bits as read from the controller
+    pub physical_buttons: u16,
+}
+
+/// A character's state after the frame, from Post-Frame
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FrameState {
+    /// Internal character ID
+    pub character: u8,
+    pub action_state: u16,
+    pub x: f32,
+    pub y: f32,
+    /// -1 facing left, 1 facing right
+    pub facing: f32,
+    pub percent: f32,
+    pub shield: f32,
+    pub stocks: u8,
+    /// Added in replay version 2.0.0
+    pub airborne: Option<bool>,
+}
+
+/// One port on one frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameRow {
+    pub frame: i32,
+    /// Controller port (0-indexed)
+    pub port: u8,
+    pub inputs: FrameInputs,
+    pub state: FrameState,
+    /// Nana, for Ice Climbers while she's alive
+    pub follower: Option<FrameState>,
+}
+
+/// A row waiting for its frame to be final
+#[derive(Default)]
+struct PendingRow {
+    inputs: FrameInputs,
+    state: Option<FrameState>,
+    follower: Option<FrameState>,
+}
+
+/// Which rows to read: only `ports` (all when empty) and only every
+/// `every_nth` frame counting from FIRST_FRAME
+#[derive(Debug, Clone, Default)]
+pub struct FrameSelection {
+    pub ports: Vec<u8>,
+    pub every_nth: u32,
+}
+
+impl FrameSelection {
+    fn includes(&self, frame: i32, port: u8) -> bool {
+        let every_nth = i64::from(self.every_nth.max(1));
+        (self.ports.is_empty() || self.ports.contains(&port))
+            && (i64::from(frame) - i64::from(FIRST_FRAME)).rem_euclid(every_nth) == 0
+    }
+}
+
+/// Read the frames of a .slp, handing each selected row to `on_row` in
+/// frame order
+pub fn read_frames<E: From<io::Error>>(
+    path: &Path,
+    selection: &FrameSelection,
+    on_row: impl FnMut(FrameRow) -> Result<(), E>,
+) -> Result<(), E> {
+    let file = std::fs::File::open(path)?;
+    read_frames_from(BufReader::new(file), selection, on_row)
+}
+
+fn read_frames_from<E: From<io::Error>>(
+    mut reader: impl Read,
+    selection: &FrameSelection,
+    mut on_row: impl FnMut(FrameRow) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut header = [0u8; RAW_HEADER.len() + 4];
+    reader.read_exact(&mut header)?;
+    if !header.starts_with(RAW_HEADER) {
+        return Err(invalid("Not a Slippi replay").into());
+    }
+    // Slippi fills in the length when the game ends; 0 means it never did
+    let raw_len = u32::from_be_bytes(header[RAW_HEADER.len()..].try_into().unwrap_or_default());
+    let mut events = reader.take(if raw_len == 0 { u64::MAX } else { u64::from(raw_len) });
+
+    let mut payloads = [0u8; 2];
+    events.read_exact(&mut payloads)?;
+    if payloads[0] != EVENT_PAYLOADS {
+        return Err(invalid("The replay doesn't start with Event Payloads").into());
+    }
+    let mut table = vec![0u8; (payloads[1] as usize).saturating_sub(1)];
+    events.read_exact(&mut table)?;
+    let mut sizes = [None; 256];
+    for entry in table.chunks_exact(3) {
+        sizes[entry[0] as usize] = Some(u16::from_be_bytes([entry[1], entry[2]]) as usize);
+    }
+
+    let mut pending: BTreeMap<(i32, u8), PendingRow> = BTreeMap::new();
+    let mut latest_frame = FIRST_FRAME;
+    let mut event = Vec::new();
+    loop {
+        let mut command = [0u8; 1];
+        // A missing or cut off event ends the replay
+        let Some(size) = events.read_exact(&mut command).ok().and_then(|()| sizes[command[0] as usize]) else {
+            break;
+        };
+        event.clear();
+        event.push(command[0]);
+        event.resize(1 + size, 0);
+        if events.read_exact(&mut event[1..]).is_err() {
+            break;
+        }
+
+        match command[0] {
+            PRE_FRAME => {
+                let Some((frame, port, follower)) = frame_header(&event) else { continue };
+                if !follower && selection.includes(frame, port) {
+                    // A re-sent frame replaces the rolled back one
+                    let inputs = read_inputs(&event).unwrap_or_default();
+                    pending.insert((frame, port), PendingRow { inputs, ..Default::default() });
+                }
+                if frame > latest_frame {
+                    latest_frame = frame;
+                    let still_pending = pending.split_off(&(latest_frame - MAX_ROLLBACK_FRAMES, 0));
+                    emit(std::mem::replace(&mut pending, still_pending), &mut on_row)?;
+                }
+            }
+            POST_FRAME => {
+                let Some((frame, port, follower)) = frame_header(&event) else { continue };
+                let (Some(row), Some(state)) = (pending.get_mut(&(frame, port)), read_state(&event)) else {
+                    continue;
+                };
+                if follower {
+                    row.follower = Some(state);
+                } else {
+                    row.state = Some(state);
+                }
+            }
+            GAME_END => break,
+            _ => {}
+        }
+    }
+    emit(pending, &mut on_row)
+}
+
+/// Hand out finished rows (those with a Post-Frame) in frame order
+fn emit<E>(
+    rows: BTreeMap<(i32, u8), PendingRow>,
+    on_row: &mut impl FnMut(FrameRow) -> Result<(), E>,
+) -> Result<(), E> {
+    for ((frame, port), row) in rows {
+        if let Some(state) = row.state {
+            on_row(FrameRow { frame, port, inputs: row.inputs, state, follower: row.follower })?;
+        }
+    }
+    Ok(())
+}
+
+/// Frame number, port and whether it's the follower, shared by both frame events
+fn frame_header(event: &[u8]) -> Option<(i32, u8, bool)> {
+    Some((i32::from_be_bytes(event.get(0x01..0x05)?.try_into().ok()?), *event.get(0x05)?, *event.get(0x06)? != 0))
+}
+
+fn read_inputs(event: &[u8]) -> Option<FrameInputs> {
+    Some(FrameInputs {
+        joystick_x: f32_at(event, 0x19)?,
+        joystick_y: f32_at(event, 0x1D)?,
+        cstick_x: f32_at(event, 0x21)?,
+        cstick_y: f32_at(event, 0x25)?,
+        trigger: f32_at(event, 0x29)?,
+        buttons: u32::from_be_bytes(event.get(0x2D..0x31)?.try_into().ok()?),
+        physical_buttons: u16_at(event, 0x31)?,
+    })
+}
+
+fn read_state(event: &[u8]) -> Option<FrameState> {
+    Some(FrameState {
+        character: *event.get(0x07)?,
+        action_state: u16_at(event, 0x08)?,
+        x: f32_at(event, 0x0A)?,
+        y: f32_at(event, 0x0E)?,
+        facing: f32_at(event, 0x12)?,
+        percent: f32_at(event, 0x16)?,
+        shield: f32_at(event, 0x1A)?,
+        stocks: *event.get(0x21)?,
+        airborne: event.get(0x2F).map(|&b| b != 0),
+    })
+}
+
+fn f32_at(event: &[u8], offset: usize) -> Option<f32> {
+    Some(f32::from_be_bytes(event.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+fn u16_at(event: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(event.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slippi::game_start::GAME_START;
+
+    const PRE_SIZE: usize = 0x3A;
+    const POST_SIZE: usize = 0x30;
+
+    fn frame_event(command: u8, size: usize, frame: i32, port: u8, follower: bool) -> Vec<u8> {
+        let mut event = vec![0u8; 1 + size];
+        event[0] = command;
+        event[0x01..0x05].copy_from_slice(&frame.to_be_bytes());
+        event[0x05] = port;
+        event[0x06] = u8::from(follower);
+        event
+    }
+
+    fn pre(frame: i32, port: u8, follower: bool, joystick_x: f32) -> Vec<u8> {
+        let mut event = frame_event(PRE_FRAME, PRE_SIZE, frame, port, follower);
+        event[0x19..0x1D].copy_from_slice(&joystick_x.to_be_bytes());
+        event
+    }
+
+    fn post(frame: i32, port: u8, follower: bool, x: f32) -> Vec<u8> {
+        let mut event = frame_event(POST_FRAME, POST_SIZE, frame, port, follower);
+        event[0x0A..0x0E].copy_from_slice(&x.to_be_bytes());
+        event[0x21] = 4;
+        event[0x2F] = 1;
+        event
+    }
+
+    fn replay(frames: &[Vec<u8>]) -> Vec<u8> {
+        let mut events = vec![EVENT_PAYLOADS, 13];
+        for (command, size) in [(GAME_START, 4), (PRE_FRAME, PRE_SIZE), (POST_FRAME, POST_SIZE), (GAME_END, 2)] {
+            events.push(command);
+            events.extend_from_slice(&(size as u16).to_be_bytes());
+        }
+        events.extend_from_slice(&[GAME_START, 3, 16, 0, 0]);
+        frames.iter().for_each(|e| events.extend_from_slice(e));
+        events.extend_from_slice(&[GAME_END, 2, 0]);
+
+        let mut data = RAW_HEADER.to_vec();
+        data.extend_from_slice(&(events.len() as u32).to_be_bytes());
+        data.extend_from_slice(&events);
+        data
+    }
+
+    fn read(data: &[u8], selection: &FrameSelection) -> Vec<FrameRow> {
+        let mut rows = Vec::new();
+        read_frames_from::<io::Error>(data, selection, |row| {
+            rows.push(row);
+            Ok(())
+        })
+        .unwrap();
+        rows
+    }
+
+    #[test]
+    fn test_read_frames_keeps_the_last_copy_of_rolled_back_frames() {
+        let mut events = Vec::new();
+        for frame in FIRST_FRAME..FIRST_FRAME + 20 {
+            // Port 1 is Ice Climbers, with Nana following
+            events.extend([pre(frame, 0, false, 0.5), pre(frame, 1, false, 0.0), pre(frame, 1, true, 0.0)]);
+            events.extend([post(frame, 0, false, frame as f32), post(frame, 1, false, 1.0), post(frame, 1, true, 2.0)]);
+        }
+        // Frame -108 rolled back and re-sent after frame -104
+        events.extend([pre(-108, 0, false, -1.0), post(-108, 0, false, 99.0)]);
+        let data = replay(&events);
+
+        let rows = read(&data, &FrameSelection::default());
+        assert_eq!(rows.len(), 40);
+        assert!(rows.windows(2).all(|w| (w[0].frame, w[0].port) < (w[1].frame, w[1].port)));
+        let resent = rows.iter().find(|r| r.frame == -108 && r.port == 0).unwrap();
+        assert_eq!((resent.inputs.joystick_x, resent.state.x), (-1.0, 99.0));
+        let ice_climbers = rows.iter().find(|r| r.port == 1).unwrap();
+        assert_eq!(ice_climbers.state.airborne, Some(true));
+        assert_eq!(ice_climbers.follower.map(|nana| nana.x), Some(2.0));
+
+        let sampled = read(&data, &FrameSelection { ports: vec![0], every_nth: 5 });
+        assert_eq!(sampled.iter().map(|r| r.frame).collect::<Vec<_>>(), vec![-123, -118, -113, -108]);
+
+        // Cut off mid-game: everything before the cut is still read
+        let truncated = &data[..data.len() - 200];
+        assert!(!read(truncated, &FrameSelection::default()).is_empty());
+    }
+}
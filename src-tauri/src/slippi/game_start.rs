@@ -0,0 +1,229 @@
+//! Minimal reader for the Game Start event of a live .slp
+//!
+//! Only used while a game is in progress: to show who is playing (e.g. Discord
+//! presence) and to tell whether it continues the set being recorded. Full
+//! replays are still parsed by the frontend with slippi-js. Offsets follow
+//! the Slippi replay spec and are relative to the command byte.
+
+use super::version::ReplayVersion;
+use serde::Serialize;
+use std::io::Read;
+use std::ops::Range;
+use std::path::Path;
+
+/// UBJSON prefix of the `raw` element, followed by its u32 length
+pub(super) const RAW_HEADER: &[u8] = b"{U\x03raw[$U#l";
+
+pub(super) const EVENT_PAYLOADS: u8 = 0x35;
+pub(super) const GAME_START: u8 = 0x36;
+
+const IS_TEAMS_OFFSET: usize = 0x0D;
+const STAGE_OFFSET: usize = 0x13;
+const PLAYERS_OFFSET: usize = 0x65;
+const PLAYER_BLOCK_SIZE: usize = 0x24;
+/// Connect codes were added in replay version 3.11.0
+pub(super) const CONNECT_CODES_OFFSET: usize = 0x221;
+pub(super) const CONNECT_CODE_SIZE: usize = 0x0A;
+/// Match IDs were added in replay version 3.14.0
+const MATCH_ID_OFFSET: usize = 0x2BE;
+const MATCH_ID_SIZE: usize = 0x33;
+const PLAYER_TYPE_CPU: u8 = 1;
+const PLAYER_TYPE_EMPTY: u8 = 3;
+
+/// Game Start is written within the first few hundred bytes
+const MAX_HEADER_BYTES: u64 = 4096;
+
+/// A player slot from the Game Start event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameStartPlayer {
+    /// Controller port (0-indexed)
+    pub port: u8,
+    /// External character ID
+    pub character_id: u8,
+    /// Netplay connect code (None for local games and older replays)
+    pub connect_code: Option<String>,
+    pub is_cpu: bool,
+}
+
+/// What is known about a game as soon as it starts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameStart {
+    /// Replay format version, which decides what else the replay records
+    pub version: ReplayVersion,
+    pub stage: u16,
+    pub is_teams: bool,
+    pub players: Vec<GameStartPlayer>,
+    /// Same for every game of a netplay set (None for local games and older replays)
+    pub match_id: Option<String>,
+}
+
+impl GameStart {
+    /// Whether the only opponents are CPUs: a CPU and at most one human
+    /// (teams aren't looked at)
+    pub fn is_cpu_match(&self) -> bool {
+        let cpus = self.players.iter().filter(|p| p.is_cpu).count();
+        cpus > 0 && self.players.len() - cpus <= 1
+    }
+
+    /// Whether the game is played over netplay: its players have connect
+    /// codes or it has a match ID (older replays can't tell, and read as local)
+    pub fn is_online(&self) -> bool {
+        self.match_id.is_some() || self.players.iter().any(|p| p.connect_code.is_some())
+    }
+}
+
+/// Read the Game Start event of a .slp
+/// Returns None if the file isn't a replay or Game Start hasn't been written yet
+pub fn read_game_start(path: &Path) -> Option<GameStart> {
+    parse_game_start(&read_header(path)?)
+}
+
+/// Read the raw Game Start event of a .slp, starting with its command byte
+pub fn read_game_start_event(path: &Path) -> Option<Vec<u8>> {
+    game_start_event(&read_header(path)?).map(<[u8]>::to_vec)
+}
+
+fn read_header(path: &Path) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    std::fs::File::open(path)
+        .ok()?
+        .take(MAX_HEADER_BYTES)
+        .read_to_end(&mut data)
+        .ok()?;
+    Some(data)
+}
+
+fn parse_game_start(data: &[u8]) -> Option<GameStart> {
+    let game_start = game_start_event(data)?;
+    let version = ReplayVersion(*game_start.get(1)?, *game_start.get(2)?, *game_start.get(3)?);
+    let is_teams = *game_start.get(IS_TEAMS_OFFSET)? != 0;
+    let stage = u16::from_be_bytes([*game_start.get(STAGE_OFFSET)?, *game_start.get(STAGE_OFFSET + 1)?]);
+    let players = (0..4)
+        .filter_map(|port| {
+            let block = PLAYERS_OFFSET + PLAYER_BLOCK_SIZE * port;
+            let character_id = *game_start.get(block)?;
+            let player_type = *game_start.get(block + 1)?;
+            if player_type == PLAYER_TYPE_EMPTY {
+                return None;
+            }
+
+            let code = CONNECT_CODES_OFFSET + CONNECT_CODE_SIZE * port;
+            Some(GameStartPlayer {
+                port: port as u8,
+                character_id,
+                connect_code: game_start.get(code..code + CONNECT_CODE_SIZE).and_then(decode_connect_code),
+                is_cpu: player_type == PLAYER_TYPE_CPU,
+            })
+        })
+        .collect();
+
+    let match_id = game_start
+        .get(MATCH_ID_OFFSET..MATCH_ID_OFFSET + MATCH_ID_SIZE)
+        .and_then(decode_match_id);
+
+    Some(GameStart { version, stage, is_teams, players, match_id })
+}
+
+/// The Game Start event, found through the Event Payloads table
+fn game_start_event(data: &[u8]) -> Option<&[u8]> {
+    data.get(game_start_range(data)?)
+}
+
+/// Where the Game Start event is in a replay's bytes, from its command byte
+pub(super) fn game_start_range(data: &[u8]) -> Option<Range<usize>> {
+    let events_start = RAW_HEADER.len() + 4;
+    let events = data.strip_prefix(RAW_HEADER)?.get(4..)?;
+    if *events.first()? != EVENT_PAYLOADS {
+        return None;
+    }
+
+    // Event Payloads lists (command, u16 payload size) for every other event
+    let payloads_size = *events.get(1)? as usize;
+    let game_start_size = events
+        .get(2..1 + payloads_size)?
+        .chunks_exact(3)
+        .find(|entry| entry[0] == GAME_START)
+        .map(|entry| u16::from_be_bytes([entry[1], entry[2]]) as usize)?;
+
+    let start = events_start + 1 + payloads_size;
+    let range = start..start + 1 + game_start_size;
+    (*data.get(range.clone())?.first()? == GAME_START).then_some(range)
+}
+
+/// Match IDs are null-terminated ASCII
+fn decode_match_id(bytes: &[u8]) -> Option<String> {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    let id = std::str::from_utf8(&bytes[..end]).ok()?;
+    Some(id.to_string()).filter(|id| !id.is_empty() && id.is_ascii())
+}
+
+/// A connect code as Game Start stores it
+pub(super) fn encode_connect_code(code: &str) -> Vec<u8> {
+    code.bytes().flat_map(|b| if b == b'#' { vec![0x81, 0x94] } else { vec![b] }).collect()
+}
+
+/// Connect codes are Shift-JIS: ASCII plus a full-width '#'
+fn decode_connect_code(bytes: &[u8]) -> Option<String> {
+    let mut code = String::new();
+    let mut iter = bytes.iter().copied();
+    while let Some(byte) = iter.next() {
+        match byte {
+            0 => break,
+            0x81 if iter.next() == Some(0x94) => code.push('#'),
+            b if b.is_ascii_graphic() => code.push(b as char),
+            _ => return None,
+        }
+    }
+    Some(code).filter(|c| !c.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_game_start_reads_players_and_codes() {
+        let game_start_size = 0x300;
+        let mut game_start = vec![0u8; game_start_size + 1];
+        game_start[0] = GAME_START;
+        game_start[1..4].copy_from_slice(&[3, 16, 0]);
+        game_start[STAGE_OFFSET + 1] = 31; // Battlefield
+        for port in 0..4 {
+            game_start[PLAYERS_OFFSET + PLAYER_BLOCK_SIZE * port + 1] = PLAYER_TYPE_EMPTY;
+        }
+        // Port 1 Fox, port 2 Marth (CPU)
+        game_start[PLAYERS_OFFSET] = 2;
+        game_start[PLAYERS_OFFSET + 1] = 0;
+        game_start[PLAYERS_OFFSET + PLAYER_BLOCK_SIZE] = 9;
+        game_start[PLAYERS_OFFSET + PLAYER_BLOCK_SIZE + 1] = PLAYER_TYPE_CPU;
+        game_start[CONNECT_CODES_OFFSET..CONNECT_CODES_OFFSET + 7].copy_from_slice(b"ABCD\x81\x941");
+        let match_id = b"mode.ranked-2024-03-01T18:02:11.52-0";
+        game_start[MATCH_ID_OFFSET..MATCH_ID_OFFSET + match_id.len()].copy_from_slice(match_id);
+
+        let mut data = RAW_HEADER.to_vec();
+        data.extend_from_slice(&[0, 0, 0, 0]);
+        data.extend_from_slice(&[EVENT_PAYLOADS, 4, GAME_START]);
+        data.extend_from_slice(&(game_start_size as u16).to_be_bytes());
+        data.extend_from_slice(&game_start);
+
+        let parsed = parse_game_start(&data).unwrap();
+        assert_eq!(parsed.version, ReplayVersion(3, 16, 0));
+        assert_eq!(parsed.stage, 31);
+        assert_eq!(
+            parsed.players,
+            vec![
+                GameStartPlayer { port: 0, character_id: 2, connect_code: Some("ABCD#1".to_string()), is_cpu: false },
+                GameStartPlayer { port: 1, character_id: 9, connect_code: None, is_cpu: true },
+            ]
+        );
+        assert!(parsed.is_cpu_match());
+        assert!(!parsed.is_teams);
+        assert!(parsed.is_online());
+        assert_eq!(parsed.match_id.as_deref(), Some("mode.ranked-2024-03-01T18:02:11.52-0"));
+
+        // Not written yet
+        assert_eq!(parse_game_start(&data[..40]), None);
+    }
+}
@@ -0,0 +1,164 @@
+//! Classifying .slp files that fail to parse
+//!
+//! A crash or disconnect mid-game leaves a replay without its Game End event
+//! (and with a zero raw length, which Slippi only fills in when the game
+//! ends). Such a file fails to parse every time, so when slippi-js gives up
+//! the failure is classified here and recorded on the recording, and stats
+//! passes skip it until the user retries. Broken replays usually still have
+//! their Game Start, which read_game_start salvages to show who played.
+
+use super::game_start::{EVENT_PAYLOADS, GAME_START, RAW_HEADER};
+use super::version::ReplayVersion;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const GAME_END: u8 = 0x39;
+
+/// Why a replay can't be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseErrorKind {
+    /// The file stops before the game ended (crash, disconnect, disk full)
+    Truncated,
+    /// Written by a newer Slippi than the app can read
+    UnsupportedVersion,
+    /// Anything else: not a replay, unknown events, or slippi-js failed on it
+    Corrupt,
+}
+
+impl ParseErrorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ParseErrorKind::Truncated => "truncated",
+            ParseErrorKind::UnsupportedVersion => "unsupported_version",
+            ParseErrorKind::Corrupt => "corrupt",
+        }
+    }
+}
+
+/// What's wrong with a replay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayProblem {
+    pub kind: ParseErrorKind,
+    pub detail: String,
+}
+
+impl ReplayProblem {
+    fn truncated(detail: &str) -> Self {
+        Self { kind: ParseErrorKind::Truncated, detail: detail.to_string() }
+    }
+
+    fn corrupt(detail: impl Into<String>) -> Self {
+        Self { kind: ParseErrorKind::Corrupt, detail: detail.into() }
+    }
+}
+
+/// Check a replay's structure: header, event sizes, version, and that it
+/// reaches Game End. Ok doesn't guarantee slippi-js can parse it
+pub fn check_replay(path: &Path) -> Result<(), ReplayProblem> {
+    let data = std::fs::read(path).map_err(|e| ReplayProblem::corrupt(format!("Can't read the file: {}", e)))?;
+    check_replay_bytes(&data)
+}
+
+fn check_replay_bytes(data: &[u8]) -> Result<(), ReplayProblem> {
+    let rest = data.strip_prefix(RAW_HEADER).ok_or_else(|| ReplayProblem::corrupt("Not a Slippi replay"))?;
+    let raw_len = rest
+        .get(..4)
+        .map(|len| u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize)
+        .ok_or_else(|| ReplayProblem::truncated("The file ends in its header"))?;
+    let body = &rest[4..];
+    // Slippi fills in the length when the game ends; 0 means it never did
+    let events = match raw_len {
+        0 => body,
+        len if len > body.len() => return Err(ReplayProblem::truncated("The file is shorter than its header says")),
+        len => &body[..len],
+    };
+
+    match events.first() {
+        None => return Err(ReplayProblem::truncated("The file has no game data")),
+        Some(&EVENT_PAYLOADS) => {}
+        Some(_) => return Err(ReplayProblem::corrupt("The replay doesn't start with Event Payloads")),
+    }
+    let payloads_size = *events.get(1).ok_or_else(|| ReplayProblem::truncated("The file ends in Event Payloads"))? as usize;
+    let table = events
+        .get(2..1 + payloads_size)
+        .ok_or_else(|| ReplayProblem::truncated("The file ends in Event Payloads"))?;
+    let mut sizes = [None; 256];
+    for entry in table.chunks_exact(3) {
+        sizes[entry[0] as usize] = Some(u16::from_be_bytes([entry[1], entry[2]]) as usize);
+    }
+
+    let mut pos = 1 + payloads_size;
+    // Game Start comes first and carries the replay version
+    match events.get(pos) {
+        None => return Err(ReplayProblem::truncated("The file ends before Game Start")),
+        Some(&GAME_START) => {}
+        Some(_) => return Err(ReplayProblem::corrupt("Game Start isn't the first event")),
+    }
+    if let Some(version) = events.get(pos + 1..pos + 4) {
+        let version = ReplayVersion(version[0], version[1], version[2]);
+        if version.capabilities().newer_than_supported {
+            return Err(ReplayProblem {
+                kind: ParseErrorKind::UnsupportedVersion,
+                detail: format!("Replay version {} is newer than this app supports", version),
+            });
+        }
+    }
+
+    while let Some(&command) = events.get(pos) {
+        let size = sizes[command as usize]
+            .ok_or_else(|| ReplayProblem::corrupt(format!("Unknown event 0x{:02x} at byte {}", command, pos)))?;
+        if pos + 1 + size > events.len() {
+            return Err(ReplayProblem::truncated("The file ends in the middle of an event"));
+        }
+        if command == GAME_END {
+            return Ok(());
+        }
+        pos += 1 + size;
+    }
+    Err(ReplayProblem::truncated("The game never ended (no Game End event)"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: u8 = 0x38;
+
+    /// A two-frame replay with the given Game Start version, finished unless cut
+    fn replay(version: [u8; 3]) -> Vec<u8> {
+        let mut events = vec![EVENT_PAYLOADS, 10];
+        events.extend_from_slice(&[GAME_START, 0, 8, FRAME, 0, 4, GAME_END, 0, 2]);
+        events.extend_from_slice(&[GAME_START, version[0], version[1], version[2], 0, 0, 0, 0, 0]);
+        events.extend_from_slice(&[FRAME, 1, 1, 1, 1, FRAME, 2, 2, 2, 2]);
+        events.extend_from_slice(&[GAME_END, 2, 0]);
+
+        let mut data = RAW_HEADER.to_vec();
+        data.extend_from_slice(&(events.len() as u32).to_be_bytes());
+        data.extend_from_slice(&events);
+        data
+    }
+
+    #[test]
+    fn test_check_replay_classifies_failures() {
+        let complete = replay([3, 16, 0]);
+        assert_eq!(check_replay_bytes(&complete), Ok(()));
+
+        // A crash mid-game: Slippi never wrote the length or Game End
+        let mut crashed = complete[..complete.len() - 3 - 5].to_vec();
+        crashed[RAW_HEADER.len()..RAW_HEADER.len() + 4].copy_from_slice(&[0; 4]);
+        assert_eq!(check_replay_bytes(&crashed).unwrap_err().kind, ParseErrorKind::Truncated);
+        // Cut off inside a frame, with the length still claiming the full game
+        let cut = &complete[..complete.len() - 6];
+        assert_eq!(check_replay_bytes(cut).unwrap_err().kind, ParseErrorKind::Truncated);
+
+        let future = replay([4, 0, 0]);
+        assert_eq!(check_replay_bytes(&future).unwrap_err().kind, ParseErrorKind::UnsupportedVersion);
+
+        let mut garbage = complete.clone();
+        let first_frame = garbage.len() - 3 - 10;
+        garbage[first_frame] = 0x7f;
+        assert_eq!(check_replay_bytes(&garbage).unwrap_err().kind, ParseErrorKind::Corrupt);
+        assert_eq!(check_replay_bytes(b"not a replay").unwrap_err().kind, ParseErrorKind::Corrupt);
+    }
+}
@@ -0,0 +1,388 @@
+//! Names of Melee's character, stage and move IDs
+//!
+//! The one place the backend turns IDs into names, for file name templates,
+//! frame exports, reports, the overlay and Discord presence. The frontend's
+//! tables (utils/characters.ts) are checked against these in the tests.
+//!
+//! Characters come as external IDs (character select order, used by Game
+//! Start and stats) or internal IDs (used by Post-Frame, where Sheik and
+//! Zelda and Popo and Nana are separate). Names are in English unless a
+//! Locale is asked for; a locale's map only has the names it translates and
+//! falls back to English for the rest.
+
+/// Name of IDs with no entry
+pub const UNKNOWN: &str = "Unknown";
+
+/// Languages names can be shown in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    En,
+    Ja,
+}
+
+impl Locale {
+    /// The locale for a language tag such as "ja" or "ja-JP" (English when not translated)
+    pub fn from_tag(tag: &str) -> Locale {
+        match tag.trim().split(['-', '_']).next().map(str::to_ascii_lowercase).as_deref() {
+            Some("ja") => Locale::Ja,
+            _ => Locale::En,
+        }
+    }
+
+    /// Translated character names, by external ID
+    fn characters(self) -> &'static [(u8, &'static str)] {
+        match self {
+            Locale::En => &[],
+            Locale::Ja => &JA_CHARACTERS,
+        }
+    }
+
+    /// Translated stage names, by stage ID
+    fn stages(self) -> &'static [(u16, &'static str)] {
+        match self {
+            Locale::En => &[],
+            Locale::Ja => &JA_STAGES,
+        }
+    }
+}
+
+/// External character IDs: the 26 playable characters, then the special ones
+const CHARACTERS: [&str; 33] = [
+    "Captain Falcon", "Donkey Kong", "Fox", "Mr. Game & Watch", "Kirby", "Bowser", "Link", "Luigi", "Mario",
+    "Marth", "Mewtwo", "Ness", "Peach", "Pikachu", "Ice Climbers", "Jigglypuff", "Samus", "Yoshi", "Zelda",
+    "Sheik", "Falco", "Young Link", "Dr. Mario", "Roy", "Pichu", "Ganondorf", "Master Hand", "Wireframe Male",
+    "Wireframe Female", "Giga Bowser", "Crazy Hand", "Sandbag", "Popo",
+];
+
+/// Internal character IDs, with the external character each is played as
+const INTERNAL_CHARACTERS: [(&str, u8); 33] = [
+    ("Mario", 8),
+    ("Fox", 2),
+    ("Captain Falcon", 0),
+    ("Donkey Kong", 1),
+    ("Kirby", 4),
+    ("Bowser", 5),
+    ("Link", 6),
+    ("Sheik", 19),
+    ("Ness", 11),
+    ("Peach", 12),
+    ("Popo", 14),
+    ("Nana", 14),
+    ("Pikachu", 13),
+    ("Samus", 16),
+    ("Yoshi", 17),
+    ("Jigglypuff", 15),
+    ("Mewtwo", 10),
+    ("Luigi", 7),
+    ("Marth", 9),
+    ("Zelda", 18),
+    ("Young Link", 21),
+    ("Dr. Mario", 22),
+    ("Falco", 20),
+    ("Pichu", 24),
+    ("Mr. Game & Watch", 3),
+    ("Ganondorf", 25),
+    ("Roy", 23),
+    ("Master Hand", 26),
+    ("Crazy Hand", 30),
+    ("Wireframe Male", 27),
+    ("Wireframe Female", 28),
+    ("Giga Bowser", 29),
+    ("Sandbag", 31),
+];
+
+/// Stage IDs, in ID order (1 and 21 aren't used)
+const STAGES: [(u16, &str); 56] = [
+    (2, "Fountain of Dreams"),
+    (3, "Pokémon Stadium"),
+    (4, "Princess Peach's Castle"),
+    (5, "Kongo Jungle"),
+    (6, "Brinstar"),
+    (7, "Corneria"),
+    (8, "Yoshi's Story"),
+    (9, "Onett"),
+    (10, "Mute City"),
+    (11, "Rainbow Cruise"),
+    (12, "Jungle Japes"),
+    (13, "Great Bay"),
+    (14, "Hyrule Temple"),
+    (15, "Brinstar Depths"),
+    (16, "Yoshi's Island"),
+    (17, "Green Greens"),
+    (18, "Fourside"),
+    (19, "Mushroom Kingdom I"),
+    (20, "Mushroom Kingdom II"),
+    (22, "Venom"),
+    (23, "Poké Floats"),
+    (24, "Big Blue"),
+    (25, "Icicle Mountain"),
+    (26, "Icetop"),
+    (27, "Flat Zone"),
+    (28, "Dream Land"),
+    (29, "Yoshi's Island N64"),
+    (30, "Kongo Jungle N64"),
+    (31, "Battlefield"),
+    (32, "Final Destination"),
+    (33, "Target Test (Mario)"),
+    (34, "Target Test (Captain Falcon)"),
+    (35, "Target Test (Young Link)"),
+    (36, "Target Test (Donkey Kong)"),
+    (37, "Target Test (Dr. Mario)"),
+    (38, "Target Test (Falco)"),
+    (39, "Target Test (Fox)"),
+    (40, "Target Test (Ice Climbers)"),
+    (41, "Target Test (Kirby)"),
+    (42, "Target Test (Bowser)"),
+    (43, "Target Test (Link)"),
+    (44, "Target Test (Luigi)"),
+    (45, "Target Test (Marth)"),
+    (46, "Target Test (Mewtwo)"),
+    (47, "Target Test (Ness)"),
+    (48, "Target Test (Peach)"),
+    (49, "Target Test (Pichu)"),
+    (50, "Target Test (Pikachu)"),
+    (51, "Target Test (Jigglypuff)"),
+    (52, "Target Test (Samus)"),
+    (53, "Target Test (Sheik)"),
+    (54, "Target Test (Yoshi)"),
+    (55, "Target Test (Zelda)"),
+    (56, "Target Test (Mr. Game & Watch)"),
+    (57, "Target Test (Roy)"),
+    (58, "Target Test (Ganondorf)"),
+];
+
+/// Home-Run Contest, far past the other stages
+const HOME_RUN_STADIUM: (u16, &str) = (84, "Home-Run Stadium");
+
+/// Move IDs (as in Post-Frame's last attack landed and slippi-js' stats)
+const MOVES: [(u8, &str); 30] = [
+    (1, "Miscellaneous"),
+    (2, "Jab"),
+    (3, "Jab 2"),
+    (4, "Jab 3"),
+    (5, "Rapid Jabs"),
+    (6, "Dash Attack"),
+    (7, "Forward Tilt"),
+    (8, "Up Tilt"),
+    (9, "Down Tilt"),
+    (10, "Forward Smash"),
+    (11, "Up Smash"),
+    (12, "Down Smash"),
+    (13, "Neutral Air"),
+    (14, "Forward Air"),
+    (15, "Back Air"),
+    (16, "Up Air"),
+    (17, "Down Air"),
+    (18, "Neutral B"),
+    (19, "Side B"),
+    (20, "Up B"),
+    (21, "Down B"),
+    (50, "Getup Attack"),
+    (51, "Getup Attack (Slow)"),
+    (52, "Pummel"),
+    (53, "Forward Throw"),
+    (54, "Back Throw"),
+    (55, "Up Throw"),
+    (56, "Down Throw"),
+    (61, "Edge Attack (Slow)"),
+    (62, "Edge Attack"),
+];
+
+const JA_CHARACTERS: [(u8, &str); 26] = [
+    (0, "キャプテン・ファルコン"),
+    (1, "ドンキーコング"),
+    (2, "フォックス"),
+    (3, "Mr.ゲーム&ウォッチ"),
+    (4, "カービィ"),
+    (5, "クッパ"),
+    (6, "リンク"),
+    (7, "ルイージ"),
+    (8, "マリオ"),
+    (9, "マルス"),
+    (10, "ミュウツー"),
+    (11, "ネス"),
+    (12, "ピーチ"),
+    (13, "ピカチュウ"),
+    (14, "アイスクライマー"),
+    (15, "プリン"),
+    (16, "サムス"),
+    (17, "ヨッシー"),
+    (18, "ゼルダ"),
+    (19, "シーク"),
+    (20, "ファルコ"),
+    (21, "こどもリンク"),
+    (22, "ドクターマリオ"),
+    (23, "ロイ"),
+    (24, "ピチュー"),
+    (25, "ガノンドロフ"),
+];
+
+const JA_STAGES: [(u16, &str); 6] = [
+    (2, "夢の泉"),
+    (3, "ポケモンスタジアム"),
+    (8, "ヨッシーストーリー"),
+    (28, "プププランド"),
+    (31, "戦場"),
+    (32, "終点"),
+];
+
+fn lookup<K: PartialEq + Copy>(table: &[(K, &'static str)], id: K) -> Option<&'static str> {
+    table.iter().find(|(key, _)| *key == id).map(|(_, name)| *name)
+}
+
+/// English name of an external character ID
+pub fn character_name(character_id: u8) -> &'static str {
+    character_name_in(character_id, Locale::En)
+}
+
+/// Name of an external character ID in `locale`
+pub fn character_name_in(character_id: u8, locale: Locale) -> &'static str {
+    lookup(locale.characters(), character_id)
+        .or_else(|| CHARACTERS.get(character_id as usize).copied())
+        .unwrap_or(UNKNOWN)
+}
+
+/// External character ID of a name in any locale (ignoring case)
+pub fn character_id(name: &str) -> Option<u8> {
+    let name = name.trim();
+    let english = CHARACTERS.iter().position(|n| n.eq_ignore_ascii_case(name)).map(|id| id as u8);
+    english.or_else(|| JA_CHARACTERS.iter().find(|(_, n)| *n == name).map(|(id, _)| *id))
+}
+
+/// Name of an internal character ID (Popo and Nana apart)
+pub fn internal_character_name(internal_id: u8) -> &'static str {
+    INTERNAL_CHARACTERS.get(internal_id as usize).map_or(UNKNOWN, |(name, _)| *name)
+}
+
+/// The external character an internal character ID is played as
+pub fn external_character(internal_id: u8) -> Option<u8> {
+    INTERNAL_CHARACTERS.get(internal_id as usize).map(|(_, external)| *external)
+}
+
+/// English name of a stage ID
+pub fn stage_name(stage_id: u16) -> &'static str {
+    stage_name_in(stage_id, Locale::En)
+}
+
+/// Name of a stage ID in `locale`
+pub fn stage_name_in(stage_id: u16, locale: Locale) -> &'static str {
+    lookup(locale.stages(), stage_id)
+        .or_else(|| lookup(&STAGES, stage_id))
+        .or_else(|| lookup(&[HOME_RUN_STADIUM], stage_id))
+        .unwrap_or(UNKNOWN)
+}
+
+/// Stage ID of a name in any locale (ignoring case)
+pub fn stage_id(name: &str) -> Option<u16> {
+    let name = name.trim();
+    STAGES
+        .iter()
+        .chain([&HOME_RUN_STADIUM])
+        .chain(JA_STAGES.iter())
+        .find(|(_, n)| n.eq_ignore_ascii_case(name))
+        .map(|(id, _)| *id)
+}
+
+/// Name of a move ID
+pub fn move_name(move_id: u8) -> &'static str {
+    lookup(&MOVES, move_id).unwrap_or(UNKNOWN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_id_slippi_writes_has_a_name() {
+        // External IDs run 0-32 with no gaps, internal ones 0-32 (0x20)
+        for id in 0..=32 {
+            assert_ne!(character_name(id), UNKNOWN, "external character {}", id);
+            assert_ne!(internal_character_name(id), UNKNOWN, "internal character {}", id);
+            let external = external_character(id).unwrap();
+            assert!((external as usize) < CHARACTERS.len(), "internal character {}", id);
+        }
+        assert_eq!(character_name(33), UNKNOWN);
+        assert_eq!(external_character(33), None);
+
+        // Stages 2-58 but 21, and the Home-Run Stadium
+        for id in (2..=58).filter(|&id| id != 21).chain([84]) {
+            assert_ne!(stage_name(id), UNKNOWN, "stage {}", id);
+        }
+        assert!(STAGES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        for id in (1..=21).chain(50..=56).chain([61, 62]) {
+            assert_ne!(move_name(id), UNKNOWN, "move {}", id);
+        }
+        assert!(MOVES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn test_internal_characters_agree_with_external_ones() {
+        for (internal_id, (name, external)) in INTERNAL_CHARACTERS.iter().enumerate() {
+            match *name {
+                "Popo" | "Nana" => assert_eq!(character_name(*external), "Ice Climbers"),
+                name => assert_eq!(character_name(*external), name, "internal character {}", internal_id),
+            }
+        }
+        // What slippi::characters relies on for Zelda and Sheik
+        assert_eq!(external_character(0x13), Some(crate::slippi::characters::ZELDA));
+        assert_eq!(external_character(0x07), Some(crate::slippi::characters::SHEIK));
+    }
+
+    #[test]
+    fn test_locales_fall_back_to_english_and_names_round_trip() {
+        assert_eq!(Locale::from_tag("ja-JP"), Locale::Ja);
+        assert_eq!(Locale::from_tag("JA"), Locale::Ja);
+        assert_eq!(Locale::from_tag("fr"), Locale::En);
+        assert_eq!(character_name_in(2, Locale::Ja), "フォックス");
+        assert_eq!(character_name_in(31, Locale::Ja), "Sandbag");
+        assert_eq!(stage_name_in(31, Locale::Ja), "戦場");
+        assert_eq!(stage_name_in(7, Locale::Ja), "Corneria");
+
+        for id in 0..CHARACTERS.len() as u8 {
+            assert_eq!(character_id(character_name(id)), Some(id));
+            assert_eq!(character_id(character_name_in(id, Locale::Ja)), Some(id));
+        }
+        assert_eq!(character_id("  marth "), Some(9));
+        for (id, name) in STAGES {
+            assert_eq!(stage_id(name), Some(id));
+        }
+        assert_eq!(stage_id("終点"), Some(32));
+        assert_eq!(stage_id("Hyrule Castle"), None);
+    }
+
+    #[test]
+    fn test_frontend_tables_match() {
+        let types = include_str!("../../../src/lib/types/recording.ts");
+        let names = include_str!("../../../src/lib/utils/characters.ts");
+        // `FOX = 2,` in the CharacterId and StageId enums
+        let enum_ids = |enum_name: &str| -> Vec<(String, u16)> {
+            let body = types.split(&format!("enum {} {{", enum_name)).nth(1).unwrap();
+            body.split('}')
+                .next()
+                .unwrap()
+                .lines()
+                .filter_map(|line| {
+                    let (key, id) = line.trim().trim_end_matches(',').split_once(" = ")?;
+                    Some((key.to_string(), id.parse().ok()?))
+                })
+                .collect()
+        };
+        // `[CharacterId.FOX]: "Fox",` in the name tables
+        let name_of = |enum_name: &str, key: &str| {
+            let line = names.lines().find(|line| line.contains(&format!("[{}.{}]:", enum_name, key)))?;
+            line.split('"').nth(1)
+        };
+
+        let characters = enum_ids("CharacterId");
+        assert_eq!(characters.len(), 26);
+        for (key, id) in characters {
+            assert_eq!(name_of("CharacterId", &key), Some(character_name(id as u8)), "{}", key);
+        }
+        let stages = enum_ids("StageId");
+        assert!(!stages.is_empty());
+        for (key, id) in stages {
+            assert_eq!(name_of("StageId", &key), Some(stage_name(id)), "{}", key);
+        }
+    }
+}
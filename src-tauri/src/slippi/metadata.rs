@@ -0,0 +1,75 @@
+//! Minimal reader for a finished replay's metadata
+//!
+//! Slippi appends a UBJSON `metadata` object after the raw events when the
+//! game ends. Only `startAt`, the wall-clock time the game started, is read
+//! here (to line a recording's video up with its replay); slippi-js reads the
+//! rest.
+
+use super::game_start::RAW_HEADER;
+use chrono::{DateTime, Utc};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// UBJSON key of the start time, followed by its string value (`S`)
+const START_AT_KEY: &[u8] = b"U\x07startAtS";
+
+/// The metadata is a few hundred bytes; anything much larger isn't it
+const MAX_METADATA_BYTES: u64 = 64 * 1024;
+
+/// When the game of a finished replay started
+/// Returns None for replays still being written, or without a start time
+pub fn read_start_at(path: &Path) -> Option<DateTime<Utc>> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut header = [0u8; RAW_HEADER.len() + 4];
+    file.read_exact(&mut header).ok()?;
+    let raw_len = u32::from_be_bytes(header.strip_prefix(RAW_HEADER)?.try_into().ok()?);
+    // Slippi fills in the length when the game ends; 0 means it hasn't
+    if raw_len == 0 {
+        return None;
+    }
+
+    file.seek(SeekFrom::Start(header.len() as u64 + u64::from(raw_len))).ok()?;
+    let mut metadata = Vec::new();
+    file.take(MAX_METADATA_BYTES).read_to_end(&mut metadata).ok()?;
+    parse_start_at(&metadata)
+}
+
+/// Find startAt in the bytes after the raw events
+fn parse_start_at(metadata: &[u8]) -> Option<DateTime<Utc>> {
+    let start = metadata.windows(START_AT_KEY.len()).position(|w| w == START_AT_KEY)? + START_AT_KEY.len();
+    // The string's length is a UBJSON integer; a timestamp fits in a byte
+    let (len, value) = match metadata.get(start..)? {
+        [b'U' | b'i', len, value @ ..] => (*len as usize, value),
+        _ => return None,
+    };
+    let value = std::str::from_utf8(value.get(..len)?).ok()?;
+    // Older Slippi versions leave out the time zone; they write UTC
+    DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_rfc3339(&format!("{}Z", value)))
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(start_at: &str) -> Vec<u8> {
+        let mut data = b"U\x08metadata{".to_vec();
+        data.extend_from_slice(START_AT_KEY);
+        data.push(b'U');
+        data.push(start_at.len() as u8);
+        data.extend_from_slice(start_at.as_bytes());
+        data.extend_from_slice(b"U\x09lastFramel\x00\x00\x1c\x20}}");
+        data
+    }
+
+    #[test]
+    fn test_parse_start_at() {
+        let expected = DateTime::parse_from_rfc3339("2024-03-01T18:02:11Z").unwrap().with_timezone(&Utc);
+        assert_eq!(parse_start_at(&metadata("2024-03-01T18:02:11Z")), Some(expected));
+        assert_eq!(parse_start_at(&metadata("2024-03-01T18:02:11")), Some(expected));
+        assert_eq!(parse_start_at(&metadata("yesterday")), None);
+        assert_eq!(parse_start_at(b"U\x08metadata{}}"), None);
+    }
+}
@@ -3,7 +3,17 @@
 //! This module contains type definitions used by the API.
 //! Actual .slp parsing is done in the frontend using slippi-js.
 
+pub mod anonymize;
+pub mod characters;
+pub mod frames;
+pub mod game_start;
+pub mod integrity;
+pub mod melee_ids;
+pub mod metadata;
+pub mod timeline;
 pub mod types;
+pub mod version;
 
 // Re-export types used by the API
-pub use types::{PlayerInfo, RecordingSession, SlippiMetadata};
+pub use types::{GameResult, PlayerInfo, RecordingSession, SlippiMetadata};
+pub use version::ReplayVersion;
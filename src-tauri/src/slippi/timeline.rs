@@ -0,0 +1,43 @@
+//! Timeline samples of a replay
+//!
+//! Each player's position, percent and stocks every SAMPLE_FRAMES frames, for
+//! drawing how a game went without reading every frame (see slippi::frames).
+
+use super::frames::{self, FrameSelection};
+use serde::Serialize;
+use std::io;
+use std::path::Path;
+
+/// Frames between samples (half a second)
+pub const SAMPLE_FRAMES: u32 = 30;
+
+/// One player at one point of the game
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineSample {
+    pub frame: i32,
+    /// Controller port (0-indexed)
+    pub port: u8,
+    pub x: f32,
+    pub y: f32,
+    pub percent: f32,
+    pub stocks: u8,
+}
+
+/// Sample every player of a replay, in frame order
+pub fn read_timeline(path: &Path) -> io::Result<Vec<TimelineSample>> {
+    let selection = FrameSelection { ports: Vec::new(), every_nth: SAMPLE_FRAMES };
+    let mut samples = Vec::new();
+    frames::read_frames(path, &selection, |row| {
+        samples.push(TimelineSample {
+            frame: row.frame,
+            port: row.port,
+            x: row.state.x,
+            y: row.state.y,
+            percent: row.state.percent,
+            stocks: row.state.stocks,
+        });
+        Ok::<_, io::Error>(())
+    })?;
+    Ok(samples)
+}
@@ -3,6 +3,8 @@
 //! These types are used by the API to return data to the frontend.
 //! Actual .slp parsing is done in the frontend using slippi-js.
 
+use super::version::ReplayCapabilities;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 // ============================================================================
@@ -10,7 +12,7 @@ use serde::{Deserialize, Serialize};
 // ============================================================================
 
 /// Metadata extracted from a Slippi replay file
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct SlippiMetadata {
     /// Character IDs for each player
     pub characters: Vec<u8>,
@@ -30,10 +32,19 @@ pub struct SlippiMetadata {
     pub played_on: Option<String>,
     /// Total number of frames
     pub total_frames: i32,
+    /// Frames the game was paused for, None if not looked for
+    #[serde(default)]
+    pub paused_frames: Option<i32>,
+    /// Replay format version (e.g. "3.16.0"), None if it wasn't recorded
+    #[serde(default)]
+    pub replay_version: Option<String>,
+    /// What the replay's format records, None if the version is unknown
+    #[serde(default)]
+    pub capabilities: Option<ReplayCapabilities>,
 }
 
 /// Information about a player in the game
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct PlayerInfo {
     /// Character ID (internal Melee ID)
     pub character_id: u8,
@@ -52,7 +63,7 @@ pub struct PlayerInfo {
 // ============================================================================
 
 /// A recording session that links a video file to its Slippi replay
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct RecordingSession {
     /// Unique identifier (usually filename)
     pub id: String,
@@ -68,8 +79,56 @@ pub struct RecordingSession {
     pub thumbnail_path: Option<String>,
     /// Duration in seconds
     pub duration: Option<u64>,
+    /// Duration in seconds without the time the game was paused, if known
+    #[serde(default)]
+    pub unpaused_duration: Option<u64>,
     /// File size in bytes
     pub file_size: Option<u64>,
     /// Parsed Slippi metadata
     pub slippi_metadata: Option<SlippiMetadata>,
+    /// Character I played, if one of the players is me
+    pub my_character: Option<u8>,
+    /// Whether I won or lost, if I played and the winner is known
+    pub my_result: Option<GameResult>,
+    /// Whether it's one of the first games of a play session
+    #[serde(default)]
+    pub is_warmup: bool,
+    /// Opponent's connect code, if one of the players is me
+    pub opponent_code: Option<String>,
+    /// Frames dropped while recording, if capture stats were tracked
+    pub dropped_frame_estimate: Option<u64>,
+    /// Longest gap between captured frames in milliseconds, if tracked
+    pub longest_stall_ms: Option<u64>,
+    /// Settings profile active when it was recorded
+    pub profile: Option<String>,
+    /// Whether the video was probed and has no audible sound
+    #[serde(default)]
+    pub is_silent: bool,
+    /// Whether the video was re-encoded to the archive format
+    #[serde(default)]
+    pub is_archived: bool,
+    /// Why the .slp couldn't be parsed, if it couldn't
+    #[serde(default)]
+    pub parse_error: Option<String>,
+    /// Whether its metadata and stats are due to be extracted again
+    #[serde(default)]
+    pub needs_reparse: bool,
+    /// Recording this one was trimmed or cropped from, if it was
+    #[serde(default)]
+    pub source_recording_id: Option<String>,
+    /// Whether it's a starred clip (kept over the Clips folder cap)
+    #[serde(default)]
+    pub is_favorite: bool,
+    /// Whether only the game's Game Start has been read so far: its players
+    /// are provisional until its stats are computed
+    #[serde(default)]
+    pub stats_pending: bool,
+}
+
+/// Outcome of a game from the local user's point of view
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GameResult {
+    Win,
+    Loss,
 }
@@ -0,0 +1,96 @@
+//! Replay format versions and what each one records
+//!
+//! Slippi has added fields to the replay format over the years, so a 2018
+//! replay simply doesn't have some of the data a stats pass would look for
+//! (e.g. L-cancel status or netplay names). Stats that depend on such a field
+//! are stored as NULL ("unknown") for older replays rather than 0, and
+//! aggregates skip them. The version comes from the replay's Game Start.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Version of the replay format, as written in Game Start
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReplayVersion(pub u8, pub u8, pub u8);
+
+impl ReplayVersion {
+    /// Post-frame L-cancel status (L-cancel counts)
+    pub const L_CANCEL_STATUS: Self = Self(2, 0, 0);
+    /// Display names and connect codes in Game Start
+    pub const NETPLAY_NAMES: Self = Self(3, 9, 0);
+    /// Newest major version slippi-js is known to read
+    pub const NEWEST_SUPPORTED_MAJOR: u8 = 3;
+
+    /// What this version of the format records
+    pub fn capabilities(self) -> ReplayCapabilities {
+        ReplayCapabilities {
+            l_cancel_status: self >= Self::L_CANCEL_STATUS,
+            netplay_names: self >= Self::NETPLAY_NAMES,
+            newer_than_supported: self.0 > Self::NEWEST_SUPPORTED_MAJOR,
+        }
+    }
+}
+
+/// Serialized as "major.minor.build", like slippi-js reports it
+impl Serialize for ReplayVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl fmt::Display for ReplayVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+impl FromStr for ReplayVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<u8> = s
+            .split('.')
+            .map(|part| part.trim().parse::<u8>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("Invalid replay version: {}", s))?;
+        match parts.as_slice() {
+            [major, minor, build] => Ok(Self(*major, *minor, *build)),
+            [major, minor] => Ok(Self(*major, *minor, 0)),
+            _ => Err(format!("Invalid replay version: {}", s)),
+        }
+    }
+}
+
+/// Which optional data a replay has
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ReplayCapabilities {
+    /// L-cancel counts are known
+    pub l_cancel_status: bool,
+    /// Display names and connect codes are known
+    pub netplay_names: bool,
+    /// Written by a Slippi newer than the app knows; stats may be incomplete
+    pub newer_than_supported: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities_by_version() {
+        let v2018: ReplayVersion = "1.7.1".parse().unwrap();
+        assert_eq!(
+            v2018.capabilities(),
+            ReplayCapabilities { l_cancel_status: false, netplay_names: false, newer_than_supported: false }
+        );
+
+        let current: ReplayVersion = "3.16.0".parse().unwrap();
+        assert!(current.capabilities().l_cancel_status && current.capabilities().netplay_names);
+        assert!("4.0.0".parse::<ReplayVersion>().unwrap().capabilities().newer_than_supported);
+        // Versions compare numerically, not as strings
+        assert!("3.10.0".parse::<ReplayVersion>().unwrap() > ReplayVersion::NETPLAY_NAMES);
+        assert!("3.x".parse::<ReplayVersion>().is_err());
+    }
+}
@@ -0,0 +1,115 @@
+//! One spelling per stored timestamp
+//!
+//! Game and recording times reach the database from the replay's startAt
+//! (UTC, without the Z in older replays), from file times, from sidecars and
+//! from Utc::now(), spelled with different offsets (Z, +00:00, a local offset)
+//! and precisions. SQLite compares them as text, so sorting and date filters
+//! were off by hours around midnight and DST changes. Every stored time goes
+//! through normalize: UTC, to the second, ending in Z, so text order is time
+//! order.
+//!
+//! Date ranges and daily trends are asked for in the user's time zone; the
+//! client sends its offset from UTC and filter_bound and local_sql apply it.
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeZone, Utc};
+use std::time::SystemTime;
+
+/// Spellings of a time without an offset (taken as UTC when stored)
+const NAIVE_FORMATS: [&str; 2] = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"];
+
+/// The one spelling of a time
+pub fn format(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Now, as stored
+pub fn now() -> String {
+    format(Utc::now())
+}
+
+/// A file's created or modified time, as stored
+pub fn from_system_time(time: SystemTime) -> String {
+    format(DateTime::<Utc>::from(time))
+}
+
+/// Read a time in any of the spellings stored so far. One without an offset
+/// is UTC (replays from before Slippi wrote the Z)
+pub fn parse(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    parse_naive(value).map(|naive| naive.and_utc())
+}
+
+/// The stored spelling of `value`, or None if it isn't a time
+pub fn normalize(value: &str) -> Option<String> {
+    parse(value).map(format)
+}
+
+/// A time about to be stored: normalized, or as it was if it can't be read
+/// (the recording is flagged, see start_time_invalid)
+pub fn for_storage(value: Option<&str>) -> Option<String> {
+    value.map(|v| normalize(v).unwrap_or_else(|| v.to_string()))
+}
+
+/// A date-range bound from the client, as stored. A bare date is that day in
+/// the client's time zone (`utc_offset_minutes` east of UTC): its first
+/// second, or for an end bound its last. A time without an offset is the
+/// client's local time. Without an offset the client is taken to be on UTC
+pub fn filter_bound(value: &str, utc_offset_minutes: Option<i32>, end: bool) -> Option<String> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(format(time.with_timezone(&Utc)));
+    }
+    let local = match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        Ok(date) if end => date.and_hms_opt(23, 59, 59)?,
+        Ok(date) => date.and_time(NaiveTime::MIN),
+        Err(_) => parse_naive(value)?,
+    };
+    let offset = FixedOffset::east_opt(utc_offset_minutes.unwrap_or(0).checked_mul(60)?)?;
+    offset.from_local_datetime(&local).single().map(|time| format(time.with_timezone(&Utc)))
+}
+
+/// SQL for a stored time `column` in the client's time zone, to group by
+/// date() or strftime()
+pub fn local_sql(column: &str, utc_offset_minutes: Option<i32>) -> String {
+    match utc_offset_minutes {
+        Some(minutes) if minutes != 0 => format!("datetime({}, '{:+} minutes')", column, minutes),
+        _ => column.to_string(),
+    }
+}
+
+fn parse_naive(value: &str) -> Option<NaiveDateTime> {
+    NAIVE_FORMATS.iter().find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_source_gets_one_spelling() {
+        // startAt, Utc::now().to_rfc3339(), a sidecar written with a local offset, an old replay
+        assert_eq!(normalize("2024-03-01T18:02:11Z").unwrap(), "2024-03-01T18:02:11Z");
+        assert_eq!(normalize("2024-03-01T18:02:11.123456789+00:00").unwrap(), "2024-03-01T18:02:11Z");
+        assert_eq!(normalize("2024-03-01T19:02:11+01:00").unwrap(), "2024-03-01T18:02:11Z");
+        assert_eq!(normalize("2020-06-26T02:20:28").unwrap(), "2020-06-26T02:20:28Z");
+        assert_eq!(normalize("2020-06-26 02:20:28").unwrap(), "2020-06-26T02:20:28Z");
+        assert_eq!(normalize("last tuesday"), None);
+        assert_eq!(for_storage(Some("last tuesday")).as_deref(), Some("last tuesday"));
+
+        let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(1_709_316_131_500);
+        assert_eq!(from_system_time(modified), "2024-03-01T18:02:11Z");
+
+        // 1 March in New York (UTC-5) runs from 05:00 UTC to 04:59:59 UTC the next day
+        assert_eq!(filter_bound("2024-03-01", Some(-300), false).unwrap(), "2024-03-01T05:00:00Z");
+        assert_eq!(filter_bound("2024-03-01", Some(-300), true).unwrap(), "2024-03-02T04:59:59Z");
+        assert_eq!(filter_bound("2024-03-01T23:30:00", Some(60), false).unwrap(), "2024-03-01T22:30:00Z");
+        assert_eq!(filter_bound("2024-03-01T23:30:00-05:00", Some(60), false).unwrap(), "2024-03-02T04:30:00Z");
+        assert_eq!(filter_bound("2024-03-01", None, false).unwrap(), "2024-03-01T00:00:00Z");
+
+        assert_eq!(local_sql("g.created_at", Some(-300)), "datetime(g.created_at, '-300 minutes')");
+        assert_eq!(local_sql("g.created_at", Some(0)), "g.created_at");
+    }
+}
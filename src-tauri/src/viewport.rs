@@ -0,0 +1,375 @@
+//! Cropping recordings down to the game's picture
+//!
+//! Dolphin draws Melee at 73:60 (4:3 with some settings) inside its window,
+//! with black bars wherever the window's shape differs and its menu and
+//! status bars above and below. With autoCropGameViewport on, the game window
+//! is captured as a recording starts and the picture found in it: the bounds
+//! of everything that isn't black, narrowed to the largest 73:60 or 4:3
+//! region centered in them (which also leaves out the menu and status bars).
+//! The region is kept as fractions of the window, and once the recording
+//! stops the video is cropped to it. A crop can't be stream copied, so that
+//! re-encodes the video.
+//!
+//! The window is checked every RECHECK_INTERVAL while recording. Until the
+//! picture is found (the first probe can land on a dark screen) it's looked
+//! for again; once found, it's found again whenever the window is resized,
+//! and if it moved the crop is dropped, since one crop can't fit both parts
+//! of the video. Whenever there's no picture to go by (the window can't be
+//! captured, the screen stays dark, or the OBS backend is recording its own
+//! scene) the recording keeps the full window.
+
+use crate::app_state::AppState;
+use crate::clip_processor::{self, CropRegion};
+use crate::commands::errors::Error;
+use crate::commands::recording::uses_obs;
+use std::path::Path;
+use std::time::Duration;
+use tauri::Manager;
+use tauri_plugin_store::StoreExt;
+
+/// Settings key for cropping recordings to the game's picture
+const AUTO_CROP_KEY: &str = "autoCropGameViewport";
+
+/// How often the window is checked for a resize while recording
+const RECHECK_INTERVAL: Duration = Duration::from_secs(2);
+/// Pixels at or under this luma count as black
+const BLACK_LUMA: u32 = 24;
+/// Share of a row or column that has to be lit for it to be part of the picture
+const MIN_CONTENT_FRACTION: f64 = 0.02;
+/// Shapes Dolphin draws Melee in (width / height)
+const ASPECT_RATIOS: [f64; 2] = [73.0 / 60.0, 4.0 / 3.0];
+/// How far the lit area's shape may be from them before it isn't taken for the picture
+const MAX_ASPECT_ERROR: f64 = 0.1;
+/// How far (as a fraction of the window) the picture may shift and still be the same
+const REGION_TOLERANCE: f64 = 0.01;
+
+/// Where the game's picture is in the window, as fractions of its size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewportRegion {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl ViewportRegion {
+    /// Whether it leaves nothing worth cropping
+    fn is_full(&self) -> bool {
+        self.width >= 1.0 - REGION_TOLERANCE && self.height >= 1.0 - REGION_TOLERANCE
+    }
+
+    fn is_near(&self, other: &ViewportRegion) -> bool {
+        [
+            (self.x, other.x),
+            (self.y, other.y),
+            (self.width, other.width),
+            (self.height, other.height),
+        ]
+        .iter()
+        .all(|(a, b)| (a - b).abs() <= REGION_TOLERANCE)
+    }
+
+    /// The region in a `video_width`x`video_height` video, with even dimensions
+    fn to_crop(self, video_width: u32, video_height: u32) -> CropRegion {
+        let scale = |fraction: f64, size: u32| (fraction * f64::from(size)).round() as u32;
+        CropRegion {
+            x: scale(self.x, video_width),
+            y: scale(self.y, video_height),
+            width: scale(self.width, video_width) & !1,
+            height: scale(self.height, video_height) & !1,
+        }
+    }
+}
+
+/// What's known of the current recording's picture
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Detection {
+    /// Not found yet, in a window of this size
+    Pending { window_size: (u32, u32) },
+    /// Found, in a window of this size
+    Found { window_size: (u32, u32), region: ViewportRegion },
+    /// No crop fits the recording
+    FullWindow,
+}
+
+impl Detection {
+    /// Update with a capture of the window at `window_size`, running `detect`
+    /// on it if the picture has to be looked for
+    fn next(self, window_size: (u32, u32), detect: impl FnOnce() -> Option<ViewportRegion>) -> Detection {
+        match self {
+            Detection::Pending { window_size: size } if size == window_size => match detect() {
+                Some(region) => Detection::Found { window_size, region },
+                None => self,
+            },
+            // Resized before the picture was found: where it was until now is unknown
+            Detection::Pending { .. } => Detection::FullWindow,
+            Detection::Found { window_size: size, .. } if size == window_size => self,
+            Detection::Found { region, .. } => match detect() {
+                Some(moved) if moved.is_near(&region) => Detection::Found { window_size, region },
+                _ => Detection::FullWindow,
+            },
+            Detection::FullWindow => self,
+        }
+    }
+}
+
+/// The picture being tracked for the current recording
+#[derive(Debug)]
+pub struct ViewportCrop {
+    /// Tells this recording's monitor apart from a later one's
+    id: String,
+    detection: Detection,
+}
+
+/// The autoCropGameViewport setting (off by default)
+fn enabled(app: &tauri::AppHandle) -> bool {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(AUTO_CROP_KEY))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// The game window the recorder captures, as picked in settings
+fn target_window(state: &AppState) -> Option<String> {
+    let settings = state.settings.lock().ok()?;
+    settings
+        .get("game_process_name")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Find the game's picture in an RGBA capture of the window, or None if no
+/// lit area there has its shape
+pub fn detect(width: u32, height: u32, pixels: &[u8]) -> Option<ViewportRegion> {
+    if width == 0 || height == 0 || pixels.len() < (width * height * 4) as usize {
+        return None;
+    }
+    let mut lit_in_row = vec![0u32; height as usize];
+    let mut lit_in_column = vec![0u32; width as usize];
+    for (i, pixel) in pixels.chunks_exact(4).take((width * height) as usize).enumerate() {
+        let luma = (u32::from(pixel[0]) * 299 + u32::from(pixel[1]) * 587 + u32::from(pixel[2]) * 114) / 1000;
+        if luma > BLACK_LUMA {
+            lit_in_row[i / width as usize] += 1;
+            lit_in_column[i % width as usize] += 1;
+        }
+    }
+    // First and last rows/columns with enough lit in them
+    let span = |lit: &[u32], across: u32| {
+        let min = (f64::from(across) * MIN_CONTENT_FRACTION).max(1.0) as u32;
+        let first = lit.iter().position(|&n| n >= min)?;
+        let last = lit.iter().rposition(|&n| n >= min)?;
+        Some((first as f64, (last + 1 - first) as f64))
+    };
+    let (left, lit_width) = span(&lit_in_column, height)?;
+    let (top, lit_height) = span(&lit_in_row, width)?;
+
+    let lit_ratio = lit_width / lit_height;
+    let ratio = ASPECT_RATIOS
+        .into_iter()
+        .min_by(|a, b| (lit_ratio / a).ln().abs().total_cmp(&(lit_ratio / b).ln().abs()))?;
+    if (lit_ratio / ratio).ln().abs() > MAX_ASPECT_ERROR {
+        return None;
+    }
+    let (picture_width, picture_height) = if lit_ratio > ratio {
+        (lit_height * ratio, lit_height)
+    } else {
+        (lit_width, lit_width / ratio)
+    };
+    let (width, height) = (f64::from(width), f64::from(height));
+    Some(ViewportRegion {
+        x: (left + (lit_width - picture_width) / 2.0) / width,
+        y: (top + (lit_height - picture_height) / 2.0) / height,
+        width: picture_width / width,
+        height: picture_height / height,
+    })
+}
+
+/// Capture the game window: its size and RGBA pixels
+fn probe(window: &str) -> Option<((u32, u32), Vec<u8>)> {
+    match crate::window_detector::capture_window_rgba(window) {
+        Ok((width, height, pixels)) => Some(((width, height), pixels)),
+        Err(e) => {
+            log::debug!("Couldn't capture {} to find the game's picture: {}", window, e);
+            None
+        }
+    }
+}
+
+/// Start tracking the game's picture for a recording that just started, if
+/// autoCropGameViewport is on and the native recorder is capturing
+pub fn start(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    // Left over from a recording that was thrown away
+    if let Ok(mut crop) = state.viewport_crop.lock() {
+        *crop = None;
+    }
+    if !enabled(app) || uses_obs(app) {
+        return;
+    }
+    let Some(window) = target_window(&state) else {
+        log::info!("🖼️ No game window picked, recording the full capture");
+        return;
+    };
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let detection = match probe(&window) {
+        Some((window_size, pixels)) => {
+            Detection::Pending { window_size }.next(window_size, || detect(window_size.0, window_size.1, &pixels))
+        }
+        None => Detection::FullWindow,
+    };
+    match detection {
+        Detection::Found { region, .. } => log::info!("🖼️ Found the game's picture at {:?}", region),
+        Detection::Pending { .. } => log::info!("🖼️ No game picture on screen yet, looking again while recording"),
+        Detection::FullWindow => log::info!("🖼️ Couldn't capture the game window, recording the full window"),
+    }
+    if let Ok(mut crop) = state.viewport_crop.lock() {
+        *crop = Some(ViewportCrop { id: id.clone(), detection });
+    }
+    if detection == Detection::FullWindow {
+        return;
+    }
+
+    let app = app.clone();
+    let spawned = std::thread::Builder::new()
+        .name("viewport-crop".to_string())
+        .spawn(move || monitor(&app, &window, &id));
+    if let Err(e) = spawned {
+        log::warn!("Failed to watch the game window for resizes: {}", e);
+    }
+}
+
+/// Check the window for resizes until the recording stops or the picture is given up on
+fn monitor(app: &tauri::AppHandle, window: &str, id: &str) {
+    loop {
+        std::thread::sleep(RECHECK_INTERVAL);
+        let state = app.state::<AppState>();
+        let recording = state
+            .recorder
+            .lock()
+            .ok()
+            .is_some_and(|recorder| recorder.as_ref().is_some_and(|r| r.is_recording()));
+        if !recording {
+            return;
+        }
+        // Hidden or minimized for now: nothing to tell
+        let Some((window_size, pixels)) = probe(window) else { continue };
+
+        let Ok(mut crop) = state.viewport_crop.lock() else { return };
+        let Some(crop) = crop.as_mut().filter(|crop| crop.id == id) else { return };
+        let previous = crop.detection;
+        crop.detection = previous.next(window_size, || detect(window_size.0, window_size.1, &pixels));
+        match (previous, crop.detection) {
+            (Detection::Pending { .. }, Detection::Found { region, .. }) => {
+                log::info!("🖼️ Found the game's picture at {:?}", region)
+            }
+            (Detection::Found { .. }, Detection::Found { .. }) if previous != crop.detection => {
+                log::info!("🖼️ Game window resized to {}x{}, its picture stayed put", window_size.0, window_size.1)
+            }
+            (_, Detection::FullWindow) => {
+                log::info!("🖼️ Game window resized and its picture moved, recording the full window");
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Crop a stopped recording to the game's picture, if one was found for it.
+/// The video is replaced in place; it's left whole if FFmpeg fails.
+/// Returns whether it was cropped
+pub fn finish(state: &AppState, output_path: &str) -> bool {
+    let crop = state.viewport_crop.lock().ok().and_then(|mut crop| crop.take());
+    let Some(ViewportCrop { detection: Detection::Found { region, .. }, .. }) = crop else {
+        return false;
+    };
+    let path = Path::new(output_path);
+    if region.is_full() || !path.exists() {
+        return false;
+    }
+
+    let cropped = clip_processor::ensure_ffmpeg()
+        .and_then(|()| clip_processor::media_info(path))
+        .and_then(|info| {
+            let (width, height) = info.width.zip(info.height).ok_or_else(|| Error::MediaUnreadable {
+                path: output_path.to_string(),
+                reason: "No video stream".to_string(),
+            })?;
+            clip_processor::replace_video(path, "cropping", |target| {
+                clip_processor::crop_video(path, target, &region.to_crop(width, height))
+            })
+        });
+    match cropped {
+        Ok(()) => {
+            log::info!("🖼️ Cropped {} to the game's picture", output_path);
+            true
+        }
+        Err(e) => {
+            log::warn!("Failed to crop {} to the game's picture, keeping the full window: {}", output_path, e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A black `width`x`height` frame, lit grey inside the given rectangles
+    fn frame(width: u32, height: u32, lit: &[(u32, u32, u32, u32)]) -> Vec<u8> {
+        let mut pixels = vec![0u8; (width * height * 4) as usize];
+        for &(x0, y0, w, h) in lit {
+            for y in y0..y0 + h {
+                for x in x0..x0 + w {
+                    let at = ((y * width + x) * 4) as usize;
+                    pixels[at..at + 4].copy_from_slice(&[128, 128, 128, 255]);
+                }
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn test_finds_the_picture_between_letterbox_bars() {
+        let pixel_crop = |region: ViewportRegion, width, height| {
+            let crop = region.to_crop(width, height);
+            (crop.x, crop.y, crop.width, crop.height)
+        };
+        // 73:60 picture pillarboxed in a wide window
+        let region = detect(400, 120, &frame(400, 120, &[(127, 0, 146, 120)])).unwrap();
+        assert_eq!(pixel_crop(region, 400, 120), (127, 0, 146, 120));
+        // Scaled with the video
+        assert_eq!(pixel_crop(region, 800, 240), (254, 0, 292, 240));
+
+        // Menu and status bars above and below the picture are left out
+        let pixels = frame(292, 256, &[(0, 0, 292, 6), (0, 8, 292, 240), (0, 250, 292, 6)]);
+        let region = detect(292, 256, &pixels).unwrap();
+        assert_eq!(pixel_crop(region, 292, 256), (0, 8, 292, 240));
+
+        // A dark screen or a lit area of another shape isn't the picture
+        assert_eq!(detect(100, 100, &frame(100, 100, &[])), None);
+        assert_eq!(detect(400, 100, &frame(400, 100, &[(0, 0, 400, 100)])), None);
+    }
+
+    #[test]
+    fn test_resizes_keep_the_crop_only_if_the_picture_stays_put() {
+        let region = ViewportRegion { x: 0.1, y: 0.0, width: 0.8, height: 1.0 };
+        let moved = ViewportRegion { x: 0.2, ..region };
+
+        let pending = Detection::Pending { window_size: (800, 600) };
+        assert_eq!(pending.next((800, 600), || None), pending);
+        let found = pending.next((800, 600), || Some(region));
+        assert_eq!(found, Detection::Found { window_size: (800, 600), region });
+        assert_eq!(pending.next((1024, 768), || Some(region)), Detection::FullWindow);
+
+        assert_eq!(found.next((800, 600), || unreachable!()), found);
+        assert_eq!(
+            found.next((1024, 768), || Some(region)),
+            Detection::Found { window_size: (1024, 768), region }
+        );
+        assert_eq!(found.next((1024, 600), || Some(moved)), Detection::FullWindow);
+        assert_eq!(found.next((1024, 600), || None), Detection::FullWindow);
+    }
+}
@@ -0,0 +1,189 @@
+//! Getting the recorder ready before a game starts
+//!
+//! A native recording used to find the game window, open the capture session
+//! and start the audio stream only once the replay appeared, which put a
+//! second or two between the game starting and the first frame. Now, when the
+//! game window appears, watching starts or a game ends with the window still
+//! open, a recorder is prepared ahead of time: its target resolved and its
+//! capture open but dropping frames. The next recording takes it over and
+//! only has to point it at a file.
+//!
+//! A prepared recorder nobody takes is torn down after PREWARM_MAX_AGE (or when
+//! the window closes or watching stops), so it doesn't hold the GPU capture
+//! for nothing. Starts are timed as the recorder.start_warm and
+//! recorder.start_cold jobs, and each warm start is logged against the last
+//! cold one.
+
+use crate::app_state::AppState;
+use crate::commands::recording::{configure_target_window, resolve_recorder, uses_obs};
+use crate::commands::settings::replay_only_mode;
+use crate::recorder::Recorder;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+/// Longest a prepared recorder is kept waiting for a game
+const PREWARM_MAX_AGE: Duration = Duration::from_secs(2 * 60);
+
+struct Prepared {
+    recorder: Box<dyn Recorder + Send>,
+    at: Instant,
+    /// Whether it's the OBS backend (the setting may change meanwhile)
+    obs: bool,
+}
+
+/// The recorder prepared for the next recording, if any
+#[derive(Default)]
+pub struct WarmStart {
+    prepared: Mutex<Option<Prepared>>,
+    /// How long the last start without a prepared recorder took
+    last_cold_start: Mutex<Option<Duration>>,
+}
+
+impl WarmStart {
+    /// Keep a prepared recorder, handing back the one it replaces
+    fn store(&self, recorder: Box<dyn Recorder + Send>, obs: bool, at: Instant) -> Option<Box<dyn Recorder + Send>> {
+        let mut prepared = self.prepared.lock().ok()?;
+        prepared.replace(Prepared { recorder, at, obs }).map(|p| p.recorder)
+    }
+
+    /// Take the prepared recorder if it's for this backend and not too old
+    /// (one that isn't is released)
+    fn take_at(&self, obs: bool, now: Instant) -> Option<Box<dyn Recorder + Send>> {
+        let mut prepared = self.prepared.lock().ok()?.take()?;
+        if prepared.obs == obs && now.duration_since(prepared.at) < PREWARM_MAX_AGE {
+            return Some(prepared.recorder);
+        }
+        prepared.recorder.release_prewarm();
+        None
+    }
+
+    /// Take out the recorder prepared at `at`, if it's still waiting
+    fn take_if_prepared_at(&self, at: Instant) -> Option<Box<dyn Recorder + Send>> {
+        let mut prepared = self.prepared.lock().ok()?;
+        if prepared.as_ref()?.at != at {
+            return None;
+        }
+        prepared.take().map(|p| p.recorder)
+    }
+}
+
+/// Prepare a recorder in the background, unless one is recording
+pub fn prewarm(app: &tauri::AppHandle) {
+    if replay_only_mode(app) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        if state.recorder.lock().map(|r| r.is_some()).unwrap_or(true) {
+            return;
+        }
+
+        configure_target_window(&state);
+        let obs = uses_obs(&app);
+        let mut recorder = resolve_recorder(&app);
+        let started = Instant::now();
+        match recorder.prewarm() {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => {
+                log::debug!("Recorder not prewarmed: {}", e);
+                return;
+            }
+        }
+        let elapsed = started.elapsed();
+
+        // Holding the recorder keeps a recording from starting meanwhile
+        let Ok(active) = state.recorder.lock() else { return };
+        if active.is_some() {
+            drop(active);
+            recorder.release_prewarm();
+            return;
+        }
+        let at = Instant::now();
+        let replaced = state.warm_start.store(recorder, obs, at);
+        drop(active);
+        if let Some(mut replaced) = replaced {
+            replaced.release_prewarm();
+        }
+        log::info!("🔥 Recorder prewarmed in {} ms", elapsed.as_millis());
+
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(PREWARM_MAX_AGE).await;
+            let state = app.state::<AppState>();
+            if let Some(mut recorder) = state.warm_start.take_if_prepared_at(at) {
+                recorder.release_prewarm();
+                log::info!("🧊 Prewarmed recorder unused for {} minutes, released", PREWARM_MAX_AGE.as_secs() / 60);
+            }
+        });
+    });
+}
+
+/// Tear down the prepared recorder (the game window closed, watching stopped)
+pub fn release(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let prepared = state.warm_start.prepared.lock().ok().and_then(|mut p| p.take());
+    if let Some(mut prepared) = prepared {
+        prepared.recorder.release_prewarm();
+        log::info!("🧊 Prewarmed recorder released");
+    }
+}
+
+/// The prepared recorder for the next recording, if it suits the backend
+pub(crate) fn take(state: &AppState, obs: bool) -> Option<Box<dyn Recorder + Send>> {
+    state.warm_start.take_at(obs, Instant::now())
+}
+
+/// Log how long starting the recorder took, warm against the last cold start
+pub(crate) fn log_start(state: &AppState, warm: bool, elapsed: Duration) {
+    let Ok(mut last_cold_start) = state.warm_start.last_cold_start.lock() else { return };
+    if !warm {
+        *last_cold_start = Some(elapsed);
+        log::info!("⏱️ Recorder started cold in {} ms", elapsed.as_millis());
+        return;
+    }
+    match *last_cold_start {
+        Some(cold) => log::info!(
+            "⏱️ Recorder started warm in {} ms ({} ms faster than the last cold start)",
+            elapsed.as_millis(),
+            cold.as_millis() as i128 - elapsed.as_millis() as i128
+        ),
+        None => log::info!("⏱️ Recorder started warm in {} ms", elapsed.as_millis()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::mock::MockRecorder;
+
+    fn prepared() -> Box<dyn Recorder + Send> {
+        let mut recorder = MockRecorder::new();
+        assert!(recorder.prewarm().unwrap());
+        Box::new(recorder)
+    }
+
+    #[test]
+    fn test_prepared_recorder_is_taken_once_while_fresh() {
+        let warm_start = WarmStart::default();
+        let start = Instant::now();
+        assert!(warm_start.take_at(false, start).is_none());
+
+        assert!(warm_start.store(prepared(), false, start).is_none());
+        assert!(warm_start.store(prepared(), false, start).is_some());
+        assert!(warm_start.take_at(false, start + Duration::from_secs(30)).is_some());
+        assert!(warm_start.take_at(false, start + Duration::from_secs(30)).is_none());
+
+        // The backend changed, or nothing started in time
+        warm_start.store(prepared(), false, start);
+        assert!(warm_start.take_at(true, start).is_none());
+        warm_start.store(prepared(), false, start);
+        assert!(warm_start.take_at(false, start + PREWARM_MAX_AGE).is_none());
+
+        warm_start.store(prepared(), false, start);
+        assert!(warm_start.take_if_prepared_at(start + Duration::from_secs(1)).is_none());
+        assert!(warm_start.take_if_prepared_at(start).is_some());
+    }
+}
@@ -0,0 +1,265 @@
+//! Which new replays start an auto-recording
+//!
+//! The Slippi folder also gets replays the user never wants recorded
+//! (spectated games, Doubles, local practice). Rules on the replay's path are
+//! checked first; rules on the game need its Game Start, which is only read
+//! when one of them is set. A replay whose Game Start can't be read is
+//! recorded. Each rule's outcome is logged at debug level, to find out why a
+//! game was or wasn't recorded.
+
+use crate::slippi::game_start::GameStart;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use tauri_plugin_store::StoreExt;
+
+/// Settings key for the rules
+pub const WATCH_FILTERS_KEY: &str = "watchFilters";
+/// Shortest time allowed between two auto-recordings starting
+pub const MIN_TRIGGER_INTERVAL_SECONDS: RangeInclusive<u32> = 0..=600;
+/// Most patterns kept
+const MAX_PATTERNS: usize = 32;
+
+/// Games recorded by where they're played
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionFilter {
+    #[default]
+    Any,
+    Online,
+    Offline,
+}
+
+/// Rules a new replay must pass to be recorded (nothing is filtered by default)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct WatchFilters {
+    /// Replays never recorded, by file name (`*` and `?`, any case). A
+    /// pattern with a slash is matched against the whole path instead
+    pub ignore_patterns: Vec<String>,
+    /// Seconds after an auto-recording starts before another may (0: no limit)
+    pub min_trigger_interval_seconds: u32,
+    /// Fewest players (CPUs included)
+    pub min_players: Option<u8>,
+    /// Most players (CPUs included)
+    pub max_players: Option<u8>,
+    /// Skip teams games
+    pub skip_teams: bool,
+    pub connection: ConnectionFilter,
+}
+
+impl WatchFilters {
+    /// Trimmed, without empty or repeated patterns, and checked
+    pub fn normalized(mut self) -> Result<Self, String> {
+        let mut patterns: Vec<String> = Vec::new();
+        for pattern in self.ignore_patterns.iter().map(|p| p.trim()) {
+            if !pattern.is_empty() && !patterns.iter().any(|p| p.eq_ignore_ascii_case(pattern)) {
+                patterns.push(pattern.to_string());
+            }
+        }
+        self.ignore_patterns = patterns;
+        self.check()?;
+        Ok(self)
+    }
+
+    /// Check values serde can't (ranges)
+    pub fn check(&self) -> Result<(), String> {
+        if self.ignore_patterns.len() > MAX_PATTERNS {
+            return Err(format!("At most {} ignore patterns are kept", MAX_PATTERNS));
+        }
+        if !MIN_TRIGGER_INTERVAL_SECONDS.contains(&self.min_trigger_interval_seconds) {
+            return Err("Time between recordings must be between 0 and 600 seconds".to_string());
+        }
+        let players = 1..=4;
+        if self.min_players.is_some_and(|n| !players.contains(&n))
+            || self.max_players.is_some_and(|n| !players.contains(&n))
+        {
+            return Err("Player counts must be between 1 and 4".to_string());
+        }
+        if let (Some(min), Some(max)) = (self.min_players, self.max_players) {
+            if min > max {
+                return Err("The fewest players can't be more than the most players".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether any rule looks at the game, so Game Start must be read
+    pub fn needs_game_start(&self) -> bool {
+        self.min_players.is_some() || self.max_players.is_some() || self.skip_teams || self.connection != ConnectionFilter::Any
+    }
+
+    /// The first ignore pattern matching a replay's path
+    pub fn ignored_by(&self, slp_path: &str) -> Option<&str> {
+        let file_name = Path::new(slp_path).file_name().and_then(|n| n.to_str()).unwrap_or(slp_path);
+        self.ignore_patterns
+            .iter()
+            .map(String::as_str)
+            .find(|pattern| {
+                let matched = path_matches(pattern, slp_path);
+                log::debug!("Watch filter: pattern {:?} {} {}", pattern, verdict(matched), file_name);
+                matched
+            })
+    }
+
+    /// Why a game isn't recorded, None if every rule on it passes
+    pub fn rejects(&self, game: &GameStart) -> Option<String> {
+        let players = game.players.len();
+        let online = game.is_online();
+        let rules = [
+            (
+                self.min_players.is_some_and(|min| players < usize::from(min)),
+                "minPlayers",
+                format!("{} player(s), fewer than {:?}", players, self.min_players),
+            ),
+            (
+                self.max_players.is_some_and(|max| players > usize::from(max)),
+                "maxPlayers",
+                format!("{} player(s), more than {:?}", players, self.max_players),
+            ),
+            (self.skip_teams && game.is_teams, "skipTeams", "a teams game".to_string()),
+            (
+                match self.connection {
+                    ConnectionFilter::Any => false,
+                    ConnectionFilter::Online => !online,
+                    ConnectionFilter::Offline => online,
+                },
+                "connection",
+                format!("{} game, recording {:?} games", if online { "an online" } else { "a local" }, self.connection),
+            ),
+        ];
+        let mut rejection = None;
+        for (matched, rule, reason) in rules {
+            log::debug!("Watch filter: {} {} ({})", rule, verdict(matched), reason);
+            if matched && rejection.is_none() {
+                rejection = Some(format!("{}: {}", rule, reason));
+            }
+        }
+        rejection
+    }
+}
+
+fn verdict(matched: bool) -> &'static str {
+    if matched {
+        "matched"
+    } else {
+        "passed"
+    }
+}
+
+/// The saved rules, read on every new replay so changes apply right away.
+/// Invalid rules are ignored (nothing is filtered)
+pub fn watch_filters(app: &tauri::AppHandle) -> WatchFilters {
+    let Some(value) = app.store("settings.json").ok().and_then(|store| store.get(WATCH_FILTERS_KEY)) else {
+        return WatchFilters::default();
+    };
+    match serde_json::from_value::<WatchFilters>(value).map_err(|e| e.to_string()).and_then(WatchFilters::normalized) {
+        Ok(filters) => filters,
+        Err(e) => {
+            log::warn!("Ignoring invalid watch filters: {}", e);
+            WatchFilters::default()
+        }
+    }
+}
+
+/// Whether a path matches an ignore pattern: its file name does, or for a
+/// pattern with a slash the whole path (either slash works in both)
+pub(crate) fn path_matches(pattern: &str, path: &str) -> bool {
+    let path = path.replace('\\', "/");
+    if pattern.contains(['/', '\\']) {
+        glob_matches(&pattern.replace('\\', "/"), &path)
+    } else {
+        let file_name = path.rsplit('/').next().unwrap_or(&path);
+        glob_matches(pattern, file_name)
+    }
+}
+
+/// Whether `text` matches `pattern`, where `*` is any run of characters and
+/// `?` any one character, ignoring case
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Where the last `*` was, and how much of the text it has taken
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // Let the `*` take one more character
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slippi::game_start::GameStartPlayer;
+
+    fn player(port: u8, connect_code: Option<&str>) -> GameStartPlayer {
+        GameStartPlayer { port, character_id: 2, connect_code: connect_code.map(String::from), is_cpu: false }
+    }
+
+    #[test]
+    fn test_rules_on_paths_and_games() {
+        let filters = WatchFilters {
+            ignore_patterns: vec![" spectate_*.slp ".into(), "".into(), "*/Spectate/*".into()],
+            max_players: Some(2),
+            skip_teams: true,
+            connection: ConnectionFilter::Online,
+            ..Default::default()
+        }
+        .normalized()
+        .unwrap();
+        assert_eq!(filters.ignore_patterns, vec!["spectate_*.slp", "*/Spectate/*"]);
+        assert!(filters.needs_game_start());
+
+        assert_eq!(filters.ignored_by("C:\\Slippi\\Spectate_2024.SLP"), Some("spectate_*.slp"));
+        assert_eq!(filters.ignored_by("C:\\Slippi\\Spectate\\Game_1.slp"), Some("*/Spectate/*"));
+        assert_eq!(filters.ignored_by("C:\\Slippi\\Game_20240101T120000.slp"), None);
+
+        let mut game = GameStart {
+            version: crate::slippi::ReplayVersion(3, 16, 0),
+            stage: 31,
+            is_teams: false,
+            players: vec![player(0, Some("ABC#1")), player(1, Some("XYZ#2"))],
+            match_id: None,
+        };
+        assert_eq!(filters.rejects(&game), None);
+        game.players.extend([player(2, Some("DEF#3")), player(3, Some("GHI#4"))]);
+        game.is_teams = true;
+        assert!(filters.rejects(&game).unwrap().starts_with("maxPlayers"));
+        game.players.truncate(2);
+        assert!(filters.rejects(&game).unwrap().starts_with("skipTeams"));
+        game.is_teams = false;
+        game.players = vec![player(0, None), player(1, None)];
+        assert!(filters.rejects(&game).unwrap().starts_with("connection"));
+
+        let bad = WatchFilters { min_players: Some(3), max_players: Some(2), ..Default::default() };
+        assert!(bad.normalized().is_err());
+    }
+
+    #[test]
+    fn test_glob_matching() {
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("game_*t12????.slp", "Game_20240101T120000.slp"));
+        assert!(glob_matches("a*b*c", "aXXbYbZc"));
+        assert!(!glob_matches("a*b*c", "aXXbYbZ"));
+        assert!(!glob_matches("?", ""));
+    }
+}
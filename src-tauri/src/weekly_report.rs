@@ -0,0 +1,338 @@
+//! Weekly report: a digest of the last completed week
+//!
+//! Weeks run Monday to Monday, local time. A report has the games I played,
+//! my record, the stats that improved most on the week before (as
+//! compare_stats pairs them) and the week's best unclipped highlights as clip
+//! candidates. Reports are kept in the reports table, one per week, and can
+//! be rendered into a shareable HTML card in the app data's Reports folder.
+//!
+//! With autoWeeklyReport on, the report of the last completed week (with its
+//! card) is generated shortly after the app starts, unless it already exists,
+//! and announced with the reports GENERATED event.
+
+use crate::app_state::AppState;
+use crate::commands::errors::Error;
+use crate::commands::settings::my_identity;
+use crate::database::{self, MetricComparison, PlayerScope, ReportRow, StatsFilter};
+use crate::events;
+use crate::highlights::{self, Highlight};
+use crate::scheduler::{Cadence, Constraints, Job};
+use crate::slippi::melee_ids::character_name;
+use chrono::{Datelike, NaiveDate, TimeZone};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+
+/// `kind` of weekly reports in the reports table
+pub const REPORT_KIND: &str = "weekly";
+/// Settings key for generating the report on start
+const AUTO_KEY: &str = "autoWeeklyReport";
+/// Leave startup (cache sync, stats) alone before generating
+const STARTUP_DELAY: Duration = Duration::from_secs(2 * 60);
+/// Furthest back a report can be generated for
+const MAX_WEEK_OFFSET: u32 = 104;
+const MAX_IMPROVEMENTS: usize = 3;
+const MAX_CLIP_CANDIDATES: u32 = 5;
+/// Metrics where going down is the improvement
+const LOWER_IS_BETTER: &[&str] = &["avgOpeningsPerKill"];
+/// Counts rather than how well I played
+const NOT_IMPROVEMENTS: &[&str] = &["totalGames", "totalWins"];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeeklyReport {
+    /// The Monday the week starts on (YYYY-MM-DD)
+    pub week_start: String,
+    /// The Monday after it
+    pub week_end: String,
+    pub games_played: i64,
+    pub wins: i64,
+    /// Games I didn't win (including ones without a winner)
+    pub losses: i64,
+    /// None without games
+    pub win_rate: Option<f64>,
+    /// Biggest improvements on the week before, best first
+    pub improvements: Vec<MetricComparison>,
+    /// Best unclipped highlights of the week
+    pub clip_candidates: Vec<Highlight>,
+}
+
+/// The week `week_offset` weeks before the last completed one, as its Monday
+/// and the Monday after
+fn week_bounds(today: NaiveDate, week_offset: u32) -> (NaiveDate, NaiveDate) {
+    let this_monday = today - chrono::Duration::days(i64::from(today.weekday().num_days_from_monday()));
+    let start = this_monday - chrono::Duration::weeks(i64::from(week_offset) + 1);
+    (start, start + chrono::Duration::weeks(1))
+}
+
+/// Games from local midnight on `start` until just before `end`
+fn week_filter(start: NaiveDate, end: NaiveDate) -> StatsFilter {
+    let midnight = |date: NaiveDate| {
+        chrono::Local
+            .from_local_datetime(&date.and_time(chrono::NaiveTime::MIN))
+            .earliest()
+            .map(|t| t.with_timezone(&chrono::Utc))
+    };
+    StatsFilter {
+        start_time: midnight(start).map(|t| t.to_rfc3339()),
+        end_time: midnight(end).map(|t| (t - chrono::Duration::seconds(1)).to_rfc3339()),
+        ..Default::default()
+    }
+}
+
+/// The metrics that got better, most improved first
+fn improvements(metrics: Vec<MetricComparison>) -> Vec<MetricComparison> {
+    let mut improved: Vec<(f64, MetricComparison)> = metrics
+        .into_iter()
+        .filter(|m| !NOT_IMPROVEMENTS.contains(&m.metric))
+        .filter_map(|m| {
+            let change = m.percent_change?;
+            let gain = if LOWER_IS_BETTER.contains(&m.metric) { -change } else { change };
+            (gain > 0.0).then_some((gain, m))
+        })
+        .collect();
+    improved.sort_by(|a, b| b.0.total_cmp(&a.0));
+    improved.into_iter().take(MAX_IMPROVEMENTS).map(|(_, m)| m).collect()
+}
+
+/// Build the report of the week `week_offset` weeks before the last completed
+/// one, save it and, with `render_card`, its card
+pub fn generate(app: &tauri::AppHandle, week_offset: u32, render_card: bool) -> Result<ReportRow, Error> {
+    let state = app.state::<AppState>();
+    let today = chrono::Local::now().date_naive();
+    let week_offset = week_offset.min(MAX_WEEK_OFFSET);
+    let (start, end) = week_bounds(today, week_offset);
+    let (previous_start, _) = week_bounds(today, week_offset + 1);
+
+    let stats = |filter: StatsFilter| {
+        database::get_aggregated_player_stats(&state.database.connection(), PlayerScope::Me, Some(filter))
+            .map_err(|e| Error::database("Failed to get the week's stats", e))
+    };
+    let week = stats(week_filter(start, end))?;
+    let previous = stats(week_filter(previous_start, start))?;
+    let (games_played, wins) = (week.total_games, week.total_wins);
+    let comparison = database::compare_stats(previous, week);
+
+    // Everyone's combos are fair game until the user has told us who they are
+    let scope = if my_identity(app).is_configured() { PlayerScope::Me } else { PlayerScope::All };
+    let clip_candidates = highlights::top_highlights(app, scope, Some(week_filter(start, end)), true, MAX_CLIP_CANDIDATES)?;
+
+    let report = WeeklyReport {
+        week_start: start.to_string(),
+        week_end: end.to_string(),
+        games_played,
+        wins,
+        losses: games_played - wins,
+        win_rate: (games_played > 0).then(|| wins as f64 / games_played as f64 * 100.0),
+        improvements: improvements(comparison.metrics),
+        clip_candidates,
+    };
+
+    let card_path = if render_card {
+        let dir = reports_directory(app);
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("weekly-{}.html", report.week_start));
+        std::fs::write(&path, render_html(&report))?;
+        Some(path.to_string_lossy().to_string())
+    } else {
+        None
+    };
+
+    let payload = serde_json::to_value(&report).unwrap_or(serde_json::Value::Null);
+    let conn = state.database.connection();
+    let id = database::save_report(&conn, REPORT_KIND, &report.week_start, &report.week_end, &payload, card_path.as_deref())
+        .map_err(|e| Error::database("Failed to save the weekly report", e))?;
+    log::info!(
+        "🗓️ Weekly report for {}: {} game(s), {}-{}",
+        report.week_start,
+        report.games_played,
+        report.wins,
+        report.losses
+    );
+
+    Ok(ReportRow {
+        id,
+        kind: REPORT_KIND.to_string(),
+        period_start: report.week_start,
+        period_end: report.week_end,
+        payload,
+        card_path,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// Where rendered cards go (in the app data directory)
+fn reports_directory(app: &tauri::AppHandle) -> PathBuf {
+    app.path()
+        .app_data_dir()
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .join("Reports")
+}
+
+/// Generate last week's report once after startup, if enabled and not done
+/// yet, as a scheduled job
+pub fn scheduled_job() -> Job {
+    Job {
+        name: "weekly_report",
+        cadence: Cadence::Startup { delay: STARTUP_DELAY },
+        constraints: Constraints::default(),
+        due: is_due,
+        run: |app| Box::pin(run_scheduled(app)),
+    }
+}
+
+async fn run_scheduled(app: tauri::AppHandle) -> Result<(), Error> {
+    let job_app = app.clone();
+    let report = tauri::async_runtime::spawn_blocking(move || generate(&job_app, 0, true))
+        .await
+        .map_err(|e| Error::RecordingFailed(format!("Weekly report stopped: {}", e)))??;
+    if let Err(e) = app.emit(events::reports::GENERATED, &report) {
+        log::error!("Failed to emit {} event: {:?}", events::reports::GENERATED, e);
+    }
+    Ok(())
+}
+
+/// Whether automatic reports are on and last week has none yet
+fn is_due(app: &tauri::AppHandle) -> bool {
+    let enabled = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get(AUTO_KEY))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if !enabled {
+        return false;
+    }
+    let (start, _) = week_bounds(chrono::Local::now().date_naive(), 0);
+    let state = app.state::<AppState>();
+    let conn = state.database.connection();
+    !database::has_report(&conn, REPORT_KIND, &start.to_string()).unwrap_or(true)
+}
+
+/// A self-contained HTML card of the report
+fn render_html(report: &WeeklyReport) -> String {
+    let win_rate = report.win_rate.map(|rate| format!("{:.0}%", rate)).unwrap_or_else(|| "-".to_string());
+    let improvements: String = report
+        .improvements
+        .iter()
+        .map(|m| {
+            format!(
+                "<li><span>{}</span><b>{} → {} ({:+.0}%)</b></li>",
+                escape(&metric_label(m.metric)),
+                format_value(m.first),
+                format_value(m.second),
+                m.percent_change.unwrap_or(0.0)
+            )
+        })
+        .collect();
+    let candidates: String = report
+        .clip_candidates
+        .iter()
+        .map(|h| {
+            let combo = &h.combo;
+            let opponent = combo.opponent_character_id.map(|id| format!(" vs {}", character_name(id as u8))).unwrap_or_default();
+            format!(
+                "<li><span>{}{}: {:.0}% → {:.0}%{}</span><b>{:.0}</b></li>",
+                escape(character_name(combo.character_id as u8)),
+                escape(&opponent),
+                combo.start_percent,
+                combo.end_percent,
+                if combo.did_kill { ", kill" } else { "" },
+                combo.highlight_score
+            )
+        })
+        .collect();
+    let section = |title: &str, items: &str| {
+        if items.is_empty() {
+            String::new()
+        } else {
+            format!("<h2>{}</h2><ul>{}</ul>", title, items)
+        }
+    };
+
+    format!(
+        r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><title>Week of {start}</title>
+<style>
+body {{ margin: 0; padding: 24px; background: #111318; font-family: system-ui, sans-serif; color: #e8e8ec; }}
+.card {{ max-width: 560px; margin: auto; padding: 24px; border-radius: 16px; background: #1c1f26; }}
+h1 {{ margin: 0 0 4px; font-size: 22px; }}
+h2 {{ margin: 20px 0 8px; font-size: 14px; text-transform: uppercase; color: #9aa0ab; }}
+.dates {{ color: #9aa0ab; font-size: 13px; }}
+.numbers {{ display: flex; gap: 24px; margin-top: 16px; }}
+.numbers div {{ font-size: 28px; font-weight: 700; }}
+.numbers span {{ display: block; font-size: 12px; font-weight: 400; color: #9aa0ab; }}
+ul {{ margin: 0; padding: 0; list-style: none; }}
+li {{ display: flex; justify-content: space-between; padding: 6px 0; border-bottom: 1px solid #2a2e37; font-size: 14px; }}
+</style></head>
+<body><div class="card">
+<h1>Weekly report</h1>
+<div class="dates">{start} to {end}</div>
+<div class="numbers"><div>{games}<span>games</span></div><div>{wins}-{losses}<span>record</span></div><div>{win_rate}<span>win rate</span></div></div>
+{improvements}{candidates}
+</div></body></html>
+"#,
+        start = report.week_start,
+        end = report.week_end,
+        games = report.games_played,
+        wins = report.wins,
+        losses = report.losses,
+        win_rate = win_rate,
+        improvements = section("Most improved", &improvements),
+        candidates = section("Clip candidates", &candidates),
+    )
+}
+
+/// How a metric is shown on the card
+fn metric_label(metric: &str) -> String {
+    match metric {
+        "winRate" => "Win rate".to_string(),
+        "avgLCancelPercent" => "L-cancel %".to_string(),
+        "avgRollsPerGame" => "Rolls per game".to_string(),
+        "avgOpeningsPerKill" => "Openings per kill".to_string(),
+        "avgDamagePerOpening" => "Damage per opening".to_string(),
+        "avgNeutralWins" => "Neutral wins".to_string(),
+        "avgInputsPerMinute" => "Inputs per minute".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn format_value(value: Option<f64>) -> String {
+    value.map(|v| format!("{:.1}", v)).unwrap_or_else(|| "-".to_string())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metric(metric: &'static str, percent_change: Option<f64>) -> MetricComparison {
+        MetricComparison { metric, first: Some(1.0), second: Some(1.0), delta: Some(0.0), percent_change }
+    }
+
+    #[test]
+    fn test_weeks_and_improvements() {
+        let wednesday = NaiveDate::from_ymd_opt(2024, 7, 3).unwrap();
+        let monday = |d: u32| NaiveDate::from_ymd_opt(2024, 7, d).unwrap();
+        assert_eq!(week_bounds(wednesday, 0), (NaiveDate::from_ymd_opt(2024, 6, 24).unwrap(), monday(1)));
+        assert_eq!(week_bounds(monday(8), 0), (monday(1), monday(8)));
+        assert_eq!(week_bounds(monday(8), 1).1, monday(1));
+
+        let improved = improvements(vec![
+            metric("totalGames", Some(300.0)),
+            metric("winRate", Some(10.0)),
+            metric("avgOpeningsPerKill", Some(-25.0)),
+            metric("avgRollsPerGame", Some(-5.0)),
+            metric("avgNeutralWins", None),
+            metric("avgLCancelPercent", Some(2.0)),
+            metric("avgInputsPerMinute", Some(1.0)),
+        ]);
+        let names: Vec<&str> = improved.iter().map(|m| m.metric).collect();
+        assert_eq!(names, vec!["avgOpeningsPerKill", "winRate", "avgLCancelPercent"]);
+    }
+}
@@ -7,9 +7,11 @@ use windows::Win32::Graphics::Gdi::{
     HGDIOBJ, SRCCOPY,
 };
 use windows::Win32::UI::WindowsAndMessaging::{
-    EnumWindows, GetClientRect, GetWindowTextW, GetWindowThreadProcessId,
+    EnumWindows, GetClientRect, GetWindowTextW, GetWindowThreadProcessId, IsWindow,
 };
 
+use super::preview::{self, PreviewError};
+
 /// Context for window search enumeration
 struct WindowSearchContext {
     pid: Option<u32>,
@@ -26,7 +28,36 @@ pub fn capture_window_preview(identifier: &str) -> Result<Vec<u8>, String> {
             identifier.trim()
         )
     })?;
-    capture_hwnd_png(hwnd)
+    let (width, height, pixels) = capture_hwnd_rgba(hwnd)?;
+    preview::encode_png(width, height, &pixels)
+}
+
+/// Capture a window identified by title/PID string, shrunk to at most
+/// max_width wide. Returns PNG bytes on success
+pub fn capture_window_thumbnail(identifier: &str, max_width: u32) -> Result<Vec<u8>, PreviewError> {
+    let hwnd = find_window_handle(identifier).ok_or(PreviewError::Gone)?;
+    let (width, height, pixels) = capture_hwnd_rgba(hwnd).map_err(|err| {
+        // It can also close while being captured
+        if unsafe { IsWindow(hwnd) }.as_bool() {
+            PreviewError::Failed(err)
+        } else {
+            PreviewError::Gone
+        }
+    })?;
+    let (width, height, pixels) = preview::downscale_rgba(width, height, &pixels, max_width);
+    preview::encode_png(width, height, &pixels).map_err(PreviewError::Failed)
+}
+
+/// Capture a window identified by title/PID string as RGBA pixels
+/// (width, height, pixels), at full size
+pub fn capture_window_rgba(identifier: &str) -> Result<(u32, u32, Vec<u8>), String> {
+    let hwnd = find_window_handle(identifier).ok_or_else(|| {
+        format!(
+            "No window found matching identifier '{}'",
+            identifier.trim()
+        )
+    })?;
+    capture_hwnd_rgba(hwnd)
 }
 
 /// Parse identifier string to extract title and optional PID
@@ -113,8 +144,8 @@ unsafe extern "system" fn find_window_enum_callback(hwnd: HWND, lparam: LPARAM)
     }
 }
 
-/// Capture a window to PNG bytes
-fn capture_hwnd_png(hwnd: HWND) -> Result<Vec<u8>, String> {
+/// Capture a window's client area as RGBA pixels (width, height, pixels)
+fn capture_hwnd_rgba(hwnd: HWND) -> Result<(u32, u32, Vec<u8>), String> {
     unsafe {
         let mut rect = RECT::default();
         if GetClientRect(hwnd, &mut rect).is_err() {
@@ -215,21 +246,7 @@ fn capture_hwnd_png(hwnd: HWND) -> Result<Vec<u8>, String> {
             chunk.swap(0, 2);
         }
         
-        // Encode to PNG
-        let mut png_data = Vec::new();
-        {
-            let mut encoder = png::Encoder::new(&mut png_data, width as u32, height as u32);
-            encoder.set_color(png::ColorType::Rgba);
-            encoder.set_depth(png::BitDepth::Eight);
-            let mut writer = encoder
-                .write_header()
-                .map_err(|e| format!("Failed to write PNG header: {}", e))?;
-            writer
-                .write_image_data(&pixels)
-                .map_err(|e| format!("Failed to encode PNG: {}", e))?;
-        }
-        
-        Ok(png_data)
+        Ok((width as u32, height as u32, pixels))
     }
 }
 
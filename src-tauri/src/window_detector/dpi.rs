@@ -0,0 +1,49 @@
+//! Converting window sizes between logical and physical pixels
+//!
+//! Buckwheat is per-monitor DPI aware, so the window rects Windows gives it are
+//! already physical pixels; scaling them again by the monitor's DPI is what
+//! turned 1920x1080 windows on 150% monitors into 2880x1620 recordings.
+
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+/// Scale factor for a DPI (1.0 at 96 DPI, 1.5 at 144). 1.0 if unknown
+pub fn scale_for_dpi(dpi: u32) -> f64 {
+    if dpi == 0 {
+        1.0
+    } else {
+        dpi as f64 / 96.0
+    }
+}
+
+/// Size of a window as the user sees it (the size it would have at 100%)
+pub fn logical_size(width: i32, height: i32, scale: f64) -> (i32, i32) {
+    if scale <= 0.0 {
+        return (width, height);
+    }
+    ((width as f64 / scale).round() as i32, (height as f64 / scale).round() as i32)
+}
+
+/// Size of a window in physical pixels, rounded down to even for the encoder.
+/// `rect_is_physical` is whether the rect came from a per-monitor DPI aware
+/// thread; otherwise Windows gave it in logical pixels
+pub fn physical_size(width: i32, height: i32, scale: f64, rect_is_physical: bool) -> (u32, u32) {
+    let scale = if rect_is_physical || scale <= 0.0 { 1.0 } else { scale };
+    let even = |v: i32| ((v.max(0) as f64 * scale).round() as u32 / 2) * 2;
+    (even(width), even(height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_physical_rects_are_not_scaled_twice() {
+        let scale = scale_for_dpi(144);
+        assert_eq!(scale, 1.5);
+        assert_eq!(physical_size(1920, 1080, scale, true), (1920, 1080));
+        assert_eq!(physical_size(1280, 720, scale, false), (1920, 1080));
+        assert_eq!(physical_size(1281, 721, 1.0, true), (1280, 720));
+        assert_eq!(logical_size(1920, 1080, scale), (1280, 720));
+        assert_eq!(scale_for_dpi(0), 1.0);
+    }
+}
@@ -3,6 +3,8 @@
 //! This module handles detecting game windows (Slippi/Dolphin) and capturing
 //! preview screenshots. Platform-specific implementations are in submodules.
 
+pub mod dpi;
+mod preview;
 mod types;
 
 #[cfg(target_os = "windows")]
@@ -12,11 +14,12 @@ mod windows;
 mod capture;
 
 // Re-export public types
+pub use preview::PreviewError;
 pub use types::GameWindow;
 
 // Re-export platform-specific implementations
 #[cfg(target_os = "windows")]
-pub use capture::capture_window_preview;
+pub use capture::{capture_window_preview, capture_window_rgba, capture_window_thumbnail};
 #[cfg(target_os = "windows")]
 pub use windows::{check_game_window_open, find_game_windows};
 
@@ -36,3 +39,12 @@ pub fn capture_window_preview(_identifier: &str) -> Result<Vec<u8>, String> {
     Err("Window capture not supported on this platform".to_string())
 }
 
+#[cfg(not(target_os = "windows"))]
+pub fn capture_window_thumbnail(_identifier: &str, _max_width: u32) -> Result<Vec<u8>, PreviewError> {
+    Err(PreviewError::Failed("Window capture not supported on this platform".to_string()))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn capture_window_rgba(_identifier: &str) -> Result<(u32, u32, Vec<u8>), String> {
+    Err("Window capture not supported on this platform".to_string())
+}
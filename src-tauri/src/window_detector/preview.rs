@@ -0,0 +1,89 @@
+//! Shrinking and encoding window captures for previews
+
+/// Why a window preview couldn't be captured
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreviewError {
+    /// The window closed between listing and capture
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    Gone,
+    /// Capture failed for another reason
+    Failed(String),
+}
+
+/// Shrink an RGBA image to at most max_width wide, keeping its aspect ratio.
+/// Each output pixel averages the source pixels it covers
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn downscale_rgba(width: u32, height: u32, pixels: &[u8], max_width: u32) -> (u32, u32, Vec<u8>) {
+    if width <= max_width || max_width == 0 {
+        return (width, height, pixels.to_vec());
+    }
+    let out_width = max_width;
+    let out_height = ((height as u64 * max_width as u64 / width as u64) as u32).max(1);
+    let mut out = Vec::with_capacity((out_width * out_height * 4) as usize);
+    for oy in 0..out_height {
+        let y0 = (oy as u64 * height as u64 / out_height as u64) as u32;
+        let y1 = (((oy + 1) as u64 * height as u64 / out_height as u64) as u32).max(y0 + 1);
+        for ox in 0..out_width {
+            let x0 = (ox as u64 * width as u64 / out_width as u64) as u32;
+            let x1 = (((ox + 1) as u64 * width as u64 / out_width as u64) as u32).max(x0 + 1);
+            let mut sum = [0u64; 4];
+            for y in y0..y1 {
+                let row = (y * width) as usize * 4;
+                for x in x0..x1 {
+                    let at = row + x as usize * 4;
+                    for (channel, total) in sum.iter_mut().enumerate() {
+                        *total += pixels[at + channel] as u64;
+                    }
+                }
+            }
+            let count = ((y1 - y0) * (x1 - x0)) as u64;
+            out.extend(sum.iter().map(|total| (total / count) as u8));
+        }
+    }
+    (out_width, out_height, out)
+}
+
+/// Encode RGBA pixels as a PNG
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn encode_png(width: u32, height: u32, pixels: &[u8]) -> Result<Vec<u8>, String> {
+    let mut png_data = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png_data, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| format!("Failed to write PNG header: {}", e))?;
+        writer
+            .write_image_data(pixels)
+            .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    }
+    Ok(png_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_downscale_averages_and_keeps_aspect() {
+        // 4x2: left half black, right half white
+        let mut pixels = Vec::new();
+        for _ in 0..2 {
+            for x in 0..4 {
+                let v = if x < 2 { 0 } else { 255 };
+                pixels.extend([v, v, v, 255]);
+            }
+        }
+        let (w, h, out) = downscale_rgba(4, 2, &pixels, 2);
+        assert_eq!((w, h), (2, 1));
+        assert_eq!(out, vec![0, 0, 0, 255, 255, 255, 255, 255]);
+
+        let (w, h, out) = downscale_rgba(4, 2, &pixels, 8);
+        assert_eq!((w, h), (4, 2));
+        assert_eq!(out, pixels);
+
+        let png = encode_png(w, h, &out).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+    }
+}
@@ -7,16 +7,41 @@ use serde::{Deserialize, Serialize};
 pub struct GameWindow {
     pub process_name: String,
     pub window_title: String,
+    /// Physical pixels
     pub width: i32,
+    /// Physical pixels
     pub height: i32,
     pub process_id: u32,
     pub class_name: String,
     pub is_cloaked: bool,
     pub is_child: bool,
     pub has_owner: bool,
+    /// Device name of the monitor the window is mostly on (e.g. `\\.\DISPLAY1`)
+    #[serde(default)]
+    pub monitor_name: Option<String>,
+    /// Position of that monitor in the system's monitor list
+    #[serde(default)]
+    pub monitor_index: Option<u32>,
+    /// That monitor's scale (1.0 at 100%, 1.5 at 150%)
+    #[serde(default = "default_scale_factor")]
+    pub scale_factor: f64,
+    #[serde(default)]
+    pub is_maximized: bool,
+    /// Covers its whole monitor (borderless or exclusive fullscreen)
+    #[serde(default)]
+    pub is_fullscreen: bool,
+}
+
+fn default_scale_factor() -> f64 {
+    1.0
 }
 
 impl GameWindow {
+    /// Size as the user sees it, the same on any monitor scale
+    pub fn logical_size(&self) -> (i32, i32) {
+        super::dpi::logical_size(self.width, self.height, self.scale_factor)
+    }
+
     /// Score a window to determine if it's likely the actual game render window
     /// Higher scores = more likely to be the game window
     pub fn score(&self) -> i32 {
@@ -37,7 +62,7 @@ impl GameWindow {
         }
         
         // Good size and not hidden
-        if self.width >= 640 && self.height >= 480 && !self.is_cloaked {
+        if self.is_valid_candidate() {
             s += 3;
         }
         
@@ -80,7 +105,8 @@ impl GameWindow {
     
     /// Check if window is a valid game window candidate (right size, not hidden)
     pub fn is_valid_candidate(&self) -> bool {
-        self.width >= 640 && self.height >= 480 && !self.is_cloaked
+        let (width, height) = self.logical_size();
+        width >= 640 && height >= 480 && !self.is_cloaked
     }
 }
 
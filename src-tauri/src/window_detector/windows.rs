@@ -1,13 +1,19 @@
 //! Windows-specific window enumeration and detection
 
+use super::dpi;
 use super::types::GameWindow;
 use std::collections::{HashMap, HashSet};
 use sysinfo::System;
 use windows::Win32::Foundation::{BOOL, HWND, LPARAM, RECT};
 use windows::Win32::Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_CLOAKED};
+use windows::Win32::Graphics::Gdi::{
+    EnumDisplayMonitors, GetMonitorInfoW, MonitorFromWindow, HDC, HMONITOR, MONITORINFO,
+    MONITORINFOEXW, MONITOR_DEFAULTTONEAREST,
+};
+use windows::Win32::UI::HiDpi::GetDpiForWindow;
 use windows::Win32::UI::WindowsAndMessaging::{
     EnumWindows, GetClassNameW, GetWindow, GetWindowRect, GetWindowTextW,
-    GetWindowThreadProcessId, GW_OWNER,
+    GetWindowThreadProcessId, IsZoomed, GW_OWNER,
 };
 
 /// Context for child window enumeration
@@ -16,6 +22,14 @@ struct ChildEnumContext {
     parent_pid: u32,
 }
 
+/// Which monitor a window is on, at what scale, and how it fills it
+struct WindowPlacement {
+    monitor_name: Option<String>,
+    scale_factor: f64,
+    is_maximized: bool,
+    is_fullscreen: bool,
+}
+
 /// Find all potential game windows (Slippi/Dolphin)
 pub fn find_game_windows() -> Vec<GameWindow> {
     // Get all processes
@@ -87,9 +101,19 @@ pub fn find_game_windows() -> Vec<GameWindow> {
         seen.insert(key)
     });
     
+    // Number monitors in the system's order
+    let monitors = monitor_names();
+    for window in &mut game_windows {
+        window.monitor_index = window
+            .monitor_name
+            .as_ref()
+            .and_then(|name| monitors.iter().position(|m| m == name))
+            .map(|i| i as u32);
+    }
+    
     for window in &game_windows {
         log::info!(
-            "  - PID: {} | Title: {} | Size: {}x{} | Class: {} | Cloaked: {} | Child: {} | HasOwner: {}",
+            "  - PID: {} | Title: {} | Size: {}x{} | Class: {} | Cloaked: {} | Child: {} | HasOwner: {} | Monitor: {:?} @ {:.2}x | Maximized: {} | Fullscreen: {}",
             window.process_id,
             window.window_title,
             window.width,
@@ -97,7 +121,11 @@ pub fn find_game_windows() -> Vec<GameWindow> {
             window.class_name,
             window.is_cloaked,
             window.is_child,
-            window.has_owner
+            window.has_owner,
+            window.monitor_name,
+            window.scale_factor,
+            window.is_maximized,
+            window.is_fullscreen
         );
     }
     
@@ -167,6 +195,62 @@ pub fn check_game_window_open(stored_id: Option<&str>) -> bool {
     false
 }
 
+/// Device names of the connected monitors, in the system's order
+fn monitor_names() -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    unsafe {
+        let _ = EnumDisplayMonitors(
+            HDC::default(),
+            None,
+            Some(enum_monitors_callback),
+            LPARAM(&mut names as *mut Vec<String> as isize),
+        );
+    }
+    names
+}
+
+unsafe extern "system" fn enum_monitors_callback(
+    monitor: HMONITOR,
+    _hdc: HDC,
+    _rect: *mut RECT,
+    lparam: LPARAM,
+) -> BOOL {
+    let names = &mut *(lparam.0 as *mut Vec<String>);
+    names.push(monitor_info(monitor).map(|(name, _)| name).unwrap_or_default());
+    BOOL::from(true) // Continue enumeration
+}
+
+/// A monitor's device name and bounds
+unsafe fn monitor_info(monitor: HMONITOR) -> Option<(String, RECT)> {
+    let mut info = MONITORINFOEXW::default();
+    info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+    if !GetMonitorInfoW(monitor, &mut info as *mut MONITORINFOEXW as *mut MONITORINFO).as_bool() {
+        return None;
+    }
+    let len = info.szDevice.iter().position(|&c| c == 0).unwrap_or(info.szDevice.len());
+    Some((String::from_utf16_lossy(&info.szDevice[..len]), info.monitorInfo.rcMonitor))
+}
+
+/// Monitor, scale and maximized/fullscreen state of a window with this rect
+unsafe fn window_placement(hwnd: HWND, rect: &RECT) -> WindowPlacement {
+    let is_maximized = IsZoomed(hwnd).as_bool();
+    let monitor = monitor_info(MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST));
+    // Maximized windows overhang their monitor by the border, so don't count those
+    let is_fullscreen = !is_maximized
+        && monitor.as_ref().is_some_and(|(_, bounds)| {
+            rect.left <= bounds.left
+                && rect.top <= bounds.top
+                && rect.right >= bounds.right
+                && rect.bottom >= bounds.bottom
+        });
+    WindowPlacement {
+        monitor_name: monitor.map(|(name, _)| name),
+        scale_factor: dpi::scale_for_dpi(GetDpiForWindow(hwnd)),
+        is_maximized,
+        is_fullscreen,
+    }
+}
+
 /// Parse a stored identifier string into PID and/or title filter
 fn parse_stored_identifier(stored_id: Option<&str>) -> (Option<u32>, Option<String>) {
     let Some(id) = stored_id else {
@@ -230,6 +314,7 @@ unsafe extern "system" fn enum_windows_callback(hwnd: HWND, lparam: LPARAM) -> B
             .map(|h| !h.is_invalid())
             .unwrap_or(false);
         
+        let placement = window_placement(hwnd, &rect);
         windows.push(GameWindow {
             process_name: format!("PID: {}", process_id),
             window_title: window_title.clone(),
@@ -240,6 +325,11 @@ unsafe extern "system" fn enum_windows_callback(hwnd: HWND, lparam: LPARAM) -> B
             is_cloaked: cloaked,
             is_child: false,
             has_owner,
+            monitor_name: placement.monitor_name,
+            monitor_index: None,
+            scale_factor: placement.scale_factor,
+            is_maximized: placement.is_maximized,
+            is_fullscreen: placement.is_fullscreen,
         });
     }
     
@@ -292,6 +382,7 @@ unsafe extern "system" fn enum_child_windows_callback(hwnd: HWND, lparam: LPARAM
             
             // Only add if it has reasonable dimensions
             if width > 100 && height > 100 {
+                let placement = window_placement(hwnd, &rect);
                 context.windows.push(GameWindow {
                     process_name: format!("PID: {} (Child)", process_id),
                     window_title,
@@ -302,6 +393,11 @@ unsafe extern "system" fn enum_child_windows_callback(hwnd: HWND, lparam: LPARAM
                     is_cloaked: cloaked,
                     is_child: true,
                     has_owner,
+                    monitor_name: placement.monitor_name,
+                    monitor_index: None,
+                    scale_factor: placement.scale_factor,
+                    is_maximized: placement.is_maximized,
+                    is_fullscreen: placement.is_fullscreen,
                 });
             }
         }
@@ -0,0 +1,292 @@
+//! Holding background work back while a game is being recorded
+//!
+//! When a game ends its thumbnail, stats and clips are made while the next
+//! game may already be recording, and an FFmpeg spike drops frames from it.
+//! Background jobs ask the gate before running: while a recording is active
+//! they wait, in the order they asked, and run when it stops. Kinds the user
+//! lets through anyway (the jobsWhileRecording setting) run one at a time
+//! instead, and every FFmpeg started while recording gets a single thread.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::{oneshot, OwnedSemaphorePermit, Semaphore};
+
+/// Whether a recording is active, for FFmpeg commands built away from the app state
+static RECORDING: AtomicBool = AtomicBool::new(false);
+
+/// Background work the gate holds back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobKind {
+    /// Library thumbnails (FFmpeg)
+    Thumbnails,
+    /// Replay stats and input analysis
+    Stats,
+    /// Highlight clips (FFmpeg)
+    Clips,
+    /// Probing new videos for their size, length and audio (FFmpeg)
+    MediaProbe,
+}
+
+/// How a job may run
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Release {
+    Free,
+    /// One at a time, while recording
+    Throttled,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    kind: JobKind,
+    /// Jobs with the same kind and key are only queued once
+    key: Option<String>,
+    release: oneshot::Sender<Release>,
+}
+
+#[derive(Debug, Default)]
+struct GateState {
+    recording: bool,
+    /// Kinds that run (throttled) while recording
+    overrides: HashSet<JobKind>,
+    /// Jobs held back until recording stops, in the order they asked
+    waiting: VecDeque<Waiter>,
+}
+
+impl GateState {
+    /// How a job may run right now, None if it has to wait
+    fn admit(&self, kind: JobKind) -> Option<Release> {
+        if !self.recording {
+            Some(Release::Free)
+        } else if self.overrides.contains(&kind) {
+            Some(Release::Throttled)
+        } else {
+            None
+        }
+    }
+
+    /// Let waiting jobs go (in order) that may run now
+    fn release_admitted(&mut self) {
+        let mut held = VecDeque::new();
+        for waiter in self.waiting.drain(..).collect::<Vec<_>>() {
+            match self.admit(waiter.kind) {
+                Some(release) => {
+                    // A receiver that's gone was cancelled while waiting
+                    let _ = waiter.release.send(release);
+                }
+                None => held.push_back(waiter),
+            }
+        }
+        self.waiting = held;
+    }
+}
+
+/// Lets a job run; hold it until the job is done
+#[derive(Debug)]
+pub struct JobPermit {
+    throttled: Option<OwnedSemaphorePermit>,
+}
+
+impl JobPermit {
+    /// Whether the job runs alongside a recording
+    pub fn is_throttled(&self) -> bool {
+        self.throttled.is_some()
+    }
+}
+
+/// The "recording active" gate background jobs go through
+#[derive(Debug)]
+pub struct WorkGate {
+    state: Mutex<GateState>,
+    /// One throttled job at a time
+    throttle: Arc<Semaphore>,
+}
+
+impl Default for WorkGate {
+    fn default() -> Self {
+        Self { state: Mutex::new(GateState::default()), throttle: Arc::new(Semaphore::new(1)) }
+    }
+}
+
+impl WorkGate {
+    fn lock(&self) -> MutexGuard<'_, GateState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Wait until a job of this kind may run
+    pub async fn acquire(&self, kind: JobKind) -> JobPermit {
+        match self.enter(kind, None).await {
+            Some(permit) => permit,
+            None => unreachable!("unkeyed jobs are never coalesced"),
+        }
+    }
+
+    /// Like acquire, but None right away when the same job (kind and key) is
+    /// already waiting, so repeated requests are done once
+    pub async fn acquire_once(&self, kind: JobKind, key: &str) -> Option<JobPermit> {
+        self.enter(kind, Some(key.to_string())).await
+    }
+
+    async fn enter(&self, kind: JobKind, key: Option<String>) -> Option<JobPermit> {
+        let admitted = {
+            let mut state = self.lock();
+            let queued = key.is_some()
+                && state.waiting.iter().any(|w| w.kind == kind && w.key == key && !w.release.is_closed());
+            if queued {
+                return None;
+            }
+            match state.admit(kind) {
+                Some(release) => Ok(release),
+                None => {
+                    let (release, released) = oneshot::channel();
+                    state.waiting.push_back(Waiter { kind, key, release });
+                    Err(released)
+                }
+            }
+        };
+        let release = match admitted {
+            Ok(release) => release,
+            // Only dropped along with the gate
+            Err(released) => released.await.unwrap_or(Release::Free),
+        };
+
+        let throttled = match release {
+            Release::Free => None,
+            // The semaphore is never closed
+            Release::Throttled => self.throttle.clone().acquire_owned().await.ok(),
+        };
+        Some(JobPermit { throttled })
+    }
+
+    /// Follow the recorder; stopping lets waiting jobs go in the order they asked
+    pub fn set_recording(&self, recording: bool) {
+        RECORDING.store(recording, Ordering::SeqCst);
+        let mut state = self.lock();
+        state.recording = recording;
+        state.release_admitted();
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.lock().recording
+    }
+
+    /// Set the kinds that run (throttled) while recording; waiting jobs of
+    /// those kinds go right away
+    pub fn set_overrides(&self, overrides: impl IntoIterator<Item = JobKind>) {
+        let mut state = self.lock();
+        state.overrides = overrides.into_iter().collect();
+        state.release_admitted();
+    }
+}
+
+/// Whether a recording is active (for work done away from the app state)
+pub fn recording() -> bool {
+    RECORDING.load(Ordering::SeqCst)
+}
+
+/// FFmpeg arguments limiting it to one thread while recording (none otherwise).
+/// Given before an input they limit decoding, before the output encoding
+pub fn ffmpeg_thread_args() -> &'static [&'static str] {
+    if recording() {
+        &["-threads", "1"]
+    } else {
+        &[]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex as StdMutex;
+
+    /// Spawn a job that records its name once the gate lets it run
+    fn spawn_job(
+        gate: &Arc<WorkGate>,
+        ran: &Arc<StdMutex<Vec<&'static str>>>,
+        kind: JobKind,
+        name: &'static str,
+    ) -> tokio::task::JoinHandle<bool> {
+        let (gate, ran) = (gate.clone(), ran.clone());
+        tokio::spawn(async move {
+            let permit = gate.acquire(kind).await;
+            ran.lock().unwrap().push(name);
+            permit.is_throttled()
+        })
+    }
+
+    async fn settle() {
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_jobs_wait_for_recording_to_stop_and_resume_in_order() {
+        let gate = Arc::new(WorkGate::default());
+        let ran = Arc::new(StdMutex::new(Vec::new()));
+        gate.set_recording(true);
+
+        let jobs = vec![
+            spawn_job(&gate, &ran, JobKind::Thumbnails, "thumbnail"),
+            spawn_job(&gate, &ran, JobKind::Stats, "stats"),
+            spawn_job(&gate, &ran, JobKind::Clips, "clip"),
+        ];
+        settle().await;
+        assert!(ran.lock().unwrap().is_empty());
+
+        gate.set_recording(false);
+        for job in jobs {
+            assert!(!job.await.unwrap());
+        }
+        assert_eq!(*ran.lock().unwrap(), vec!["thumbnail", "stats", "clip"]);
+
+        // Nothing waits once recording has stopped
+        assert!(!gate.acquire(JobKind::Stats).await.is_throttled());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_overridden_kinds_run_one_at_a_time_while_recording() {
+        let gate = Arc::new(WorkGate::default());
+        let ran = Arc::new(StdMutex::new(Vec::new()));
+        gate.set_recording(true);
+
+        let thumbnail = spawn_job(&gate, &ran, JobKind::Thumbnails, "thumbnail");
+        let stats = spawn_job(&gate, &ran, JobKind::Stats, "stats");
+        settle().await;
+
+        // Overriding a kind lets its waiting jobs go, throttled
+        gate.set_overrides([JobKind::Stats]);
+        assert!(stats.await.unwrap());
+        assert_eq!(*ran.lock().unwrap(), vec!["stats"]);
+
+        let first = gate.acquire(JobKind::Stats).await;
+        let second = spawn_job(&gate, &ran, JobKind::Stats, "second stats");
+        settle().await;
+        assert_eq!(ran.lock().unwrap().len(), 1);
+        drop(first);
+        assert!(second.await.unwrap());
+
+        gate.set_recording(false);
+        assert!(!thumbnail.await.unwrap());
+        assert_eq!(*ran.lock().unwrap(), vec!["stats", "second stats", "thumbnail"]);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_acquire_once_coalesces_waiting_jobs() {
+        let gate = Arc::new(WorkGate::default());
+        gate.set_recording(true);
+
+        let waiting = {
+            let gate = gate.clone();
+            tokio::spawn(async move { gate.acquire_once(JobKind::Thumbnails, "a.mp4").await.is_some() })
+        };
+        settle().await;
+        assert!(gate.acquire_once(JobKind::Thumbnails, "a.mp4").await.is_none());
+
+        gate.set_recording(false);
+        assert!(waiting.await.unwrap());
+        assert!(gate.acquire_once(JobKind::Thumbnails, "a.mp4").await.is_some());
+    }
+}
@@ -0,0 +1,267 @@
+//! Finding NaN and infinite floats in what a command would send
+//!
+//! serde_json writes them as null, so a command that computes one sends the
+//! frontend null where it expects a number. This walks a value the way
+//! serialization does and names every field holding one.
+
+use serde::ser::{self, Serialize, Serializer};
+use std::fmt;
+
+/// Paths (e.g. "characterStats[].winRate") of the non-finite floats in `value`
+pub fn non_finite_fields<T: Serialize + ?Sized>(value: &T) -> Vec<String> {
+    let mut finder = Finder::default();
+    value.serialize(&mut finder).unwrap();
+    finder.found
+}
+
+#[derive(Default)]
+struct Finder {
+    path: Vec<String>,
+    found: Vec<String>,
+}
+
+impl Finder {
+    fn check(&mut self, value: f64) {
+        if !value.is_finite() {
+            self.found.push(self.path.join(".").replace(".[]", "[]"));
+        }
+    }
+
+    fn nested<T: Serialize + ?Sized>(&mut self, segment: &str, value: &T) -> Result<(), Never> {
+        self.path.push(segment.to_string());
+        let result = value.serialize(&mut *self);
+        self.path.pop();
+        result
+    }
+}
+
+/// Finding never fails
+#[derive(Debug)]
+struct Never;
+
+impl fmt::Display for Never {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unreachable")
+    }
+}
+
+impl std::error::Error for Never {}
+
+impl ser::Error for Never {
+    fn custom<T: fmt::Display>(_: T) -> Self {
+        Never
+    }
+}
+
+impl Serializer for &mut Finder {
+    type Ok = ();
+    type Error = Never;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, _: bool) -> Result<(), Never> {
+        Ok(())
+    }
+    fn serialize_i8(self, _: i8) -> Result<(), Never> {
+        Ok(())
+    }
+    fn serialize_i16(self, _: i16) -> Result<(), Never> {
+        Ok(())
+    }
+    fn serialize_i32(self, _: i32) -> Result<(), Never> {
+        Ok(())
+    }
+    fn serialize_i64(self, _: i64) -> Result<(), Never> {
+        Ok(())
+    }
+    fn serialize_u8(self, _: u8) -> Result<(), Never> {
+        Ok(())
+    }
+    fn serialize_u16(self, _: u16) -> Result<(), Never> {
+        Ok(())
+    }
+    fn serialize_u32(self, _: u32) -> Result<(), Never> {
+        Ok(())
+    }
+    fn serialize_u64(self, _: u64) -> Result<(), Never> {
+        Ok(())
+    }
+    fn serialize_char(self, _: char) -> Result<(), Never> {
+        Ok(())
+    }
+    fn serialize_str(self, _: &str) -> Result<(), Never> {
+        Ok(())
+    }
+    fn serialize_bytes(self, _: &[u8]) -> Result<(), Never> {
+        Ok(())
+    }
+    fn serialize_none(self) -> Result<(), Never> {
+        Ok(())
+    }
+    fn serialize_unit(self) -> Result<(), Never> {
+        Ok(())
+    }
+    fn serialize_unit_struct(self, _: &'static str) -> Result<(), Never> {
+        Ok(())
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<(), Never> {
+        self.check(f64::from(value));
+        Ok(())
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<(), Never> {
+        self.check(value);
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Never> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, _: u32, _: &'static str) -> Result<(), Never> {
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _: &'static str, value: &T) -> Result<(), Never> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Never> {
+        self.nested(variant, value)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self, Never> {
+        Ok(self)
+    }
+    fn serialize_tuple(self, _: usize) -> Result<Self, Never> {
+        Ok(self)
+    }
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self, Never> {
+        Ok(self)
+    }
+    fn serialize_map(self, _: Option<usize>) -> Result<Self, Never> {
+        Ok(self)
+    }
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self, Never> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(self, _: &'static str, _: u32, variant: &'static str, _: usize) -> Result<Self, Never> {
+        self.path.push(variant.to_string());
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(self, _: &'static str, _: u32, variant: &'static str, _: usize) -> Result<Self, Never> {
+        self.path.push(variant.to_string());
+        Ok(self)
+    }
+}
+
+impl ser::SerializeSeq for &mut Finder {
+    type Ok = ();
+    type Error = Never;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Never> {
+        self.nested("[]", value)
+    }
+
+    fn end(self) -> Result<(), Never> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for &mut Finder {
+    type Ok = ();
+    type Error = Never;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Never> {
+        self.nested("[]", value)
+    }
+
+    fn end(self) -> Result<(), Never> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for &mut Finder {
+    type Ok = ();
+    type Error = Never;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Never> {
+        self.nested("[]", value)
+    }
+
+    fn end(self) -> Result<(), Never> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleVariant for &mut Finder {
+    type Ok = ();
+    type Error = Never;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Never> {
+        self.nested("[]", value)
+    }
+
+    fn end(self) -> Result<(), Never> {
+        self.path.pop();
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for &mut Finder {
+    type Ok = ();
+    type Error = Never;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, _: &T) -> Result<(), Never> {
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Never> {
+        self.nested("{}", value)
+    }
+
+    fn end(self) -> Result<(), Never> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for &mut Finder {
+    type Ok = ();
+    type Error = Never;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Never> {
+        self.nested(key, value)
+    }
+
+    fn end(self) -> Result<(), Never> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for &mut Finder {
+    type Ok = ();
+    type Error = Never;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Never> {
+        self.nested(key, value)
+    }
+
+    fn end(self) -> Result<(), Never> {
+        self.path.pop();
+        Ok(())
+    }
+}
@@ -0,0 +1,346 @@
+//! Fixtures and builders shared by the integration tests
+//!
+//! Fixture replays are generated from scripts (see replay.rs) into a cached
+//! directory on first use, each with the ground truth it was scripted to
+//! contain. Databases are in memory with the full migration chain applied.
+#![allow(dead_code)]
+
+pub mod finite;
+pub mod replay;
+
+use peppi_lib::database::{Database, GameStatsRow, InputQualityCounts, MyResult, PlayerStatsRow, StatsSource};
+use peppi_lib::slippi::frames::{self, FrameRow, FrameSelection};
+use peppi_lib::slippi::game_start::{GameStart, GameStartPlayer};
+use replay::{PlayerFrame, ReplayBuilder, ReplayPlayer, DASH, FIRST_FRAME, TURN, WAIT};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const BATTLEFIELD: u16 = 31;
+pub const YOSHIS_STORY: u16 = 8;
+pub const FOX: u8 = 2;
+pub const MARTH: u8 = 9;
+pub const ZELDA: u8 = 18;
+pub const FALCO: u8 = 20;
+
+/// A fixture replay and what it's known to contain
+pub struct Fixture {
+    pub path: PathBuf,
+    pub stage: u16,
+    /// (port, external character ID, connect code, CPU)
+    pub players: Vec<(u8, u8, Option<&'static str>, bool)>,
+    /// Frames from -123 on, once rollbacks are resolved
+    pub frames: usize,
+    /// Whether it reaches Game End
+    pub complete: bool,
+    pub winner_port: Option<u8>,
+    /// Input analysis counts per port
+    pub inputs: BTreeMap<u8, InputQualityCounts>,
+}
+
+impl Fixture {
+    /// The same replay under another name, like the next game of a set
+    pub fn copied_as(self, name: &str) -> Fixture {
+        let data = std::fs::read(&self.path).unwrap();
+        Fixture { path: write_fixture(name, &data), ..self }
+    }
+}
+
+const NETPLAY_FRAMES: usize = 400;
+/// Frame (counted from the start) re-sent after the next one, with port 1
+/// moved to x = ROLLED_BACK_X
+pub const ROLLED_BACK_INDEX: usize = 396;
+pub const ROLLED_BACK_X: f32 = 5.0;
+
+/// Ranked netplay Fox (HATS#982, port 1) vs Marth (ABCD#123, port 2) on
+/// Battlefield. Fox dashbacks cleanly once and lets go of a held stick once
+/// (a snapback); Marth tilt turns out of a dash once and loses three
+/// stocks. The last frames roll back once
+pub fn netplay() -> Fixture {
+    Fixture {
+        path: write_fixture("netplay", &netplay_game().build()),
+        stage: BATTLEFIELD,
+        players: vec![(0, FOX, Some("HATS#982"), false), (1, MARTH, Some("ABCD#123"), false)],
+        frames: NETPLAY_FRAMES,
+        complete: true,
+        winner_port: Some(0),
+        inputs: BTreeMap::from([
+            (0, InputQualityCounts { dashback_attempts: 1, dashback_successes: 1, snapbacks: 1, ..Default::default() }),
+            (1, InputQualityCounts { dashback_attempts: 1, ..Default::default() }),
+        ]),
+    }
+}
+
+/// The netplay game, cut off by a crash after `frames` frames
+pub fn crashed(frames: usize) -> Fixture {
+    let builder = netplay_builder(frames).crashed();
+    Fixture {
+        path: write_fixture(&format!("crashed_{}", frames), &builder.build()),
+        complete: false,
+        winner_port: None,
+        inputs: BTreeMap::new(),
+        frames,
+        ..netplay()
+    }
+}
+
+/// A local game of Falco against a CPU Fox on Yoshi's Story, 1.5 seconds long
+pub fn cpu_match() -> Fixture {
+    let players = vec![
+        ReplayPlayer { port: 0, character_id: FALCO, internal_character_id: 0x16, connect_code: None, cpu: false },
+        ReplayPlayer { port: 1, character_id: FOX, internal_character_id: 0x01, connect_code: None, cpu: true },
+    ];
+    let builder = ReplayBuilder::new(YOSHIS_STORY, players).frames(90, |_, _| PlayerFrame::default());
+    Fixture {
+        path: write_fixture("cpu_match", &builder.build()),
+        stage: YOSHIS_STORY,
+        players: vec![(0, FALCO, None, false), (1, FOX, None, true)],
+        frames: 90,
+        complete: true,
+        winner_port: None,
+        inputs: BTreeMap::from([(0, InputQualityCounts::default()), (1, InputQualityCounts::default())]),
+    }
+}
+
+/// When the netplay game started, in its metadata
+pub const NETPLAY_START_AT: &str = "2024-03-01T18:02:11Z";
+
+/// The netplay game as Slippi writes it once the game ends: with display
+/// names in Game Start and the UBJSON metadata after the raw events
+pub fn netplay_with_metadata() -> Fixture {
+    let builder = netplay_game().metadata(NETPLAY_START_AT);
+    Fixture { path: write_fixture("netplay_with_metadata", &builder.build()), ..netplay() }
+}
+
+/// The netplay game after the players swapped ports: Marth on port 1, Fox
+/// (still winning) on port 2
+pub fn netplay_swapped() -> Fixture {
+    let players = vec![
+        ReplayPlayer { port: 1, character_id: FOX, internal_character_id: 0x01, connect_code: Some("HATS#982"), cpu: false },
+        ReplayPlayer { port: 0, character_id: MARTH, internal_character_id: 0x12, connect_code: Some("ABCD#123"), cpu: false },
+    ];
+    let builder = ReplayBuilder::new(BATTLEFIELD, players)
+        .match_id("mode.ranked-2024-03-01T18:02:11.52-0")
+        .frames(NETPLAY_FRAMES, netplay_script);
+    let netplay = netplay();
+    Fixture {
+        path: write_fixture("netplay_swapped", &builder.build()),
+        players: vec![(1, FOX, Some("HATS#982"), false), (0, MARTH, Some("ABCD#123"), false)],
+        winner_port: Some(1),
+        inputs: BTreeMap::from([(1, netplay.inputs[&0]), (0, netplay.inputs[&1])]),
+        ..netplay
+    }
+}
+
+/// The netplay game with Fox swapped for a Zelda who transforms into Sheik
+/// during the countdown and stays Sheik
+pub fn zelda_as_sheik() -> Fixture {
+    let players = vec![
+        ReplayPlayer { port: 0, character_id: ZELDA, internal_character_id: 0x07, connect_code: Some("HATS#982"), cpu: false },
+        ReplayPlayer { port: 1, character_id: MARTH, internal_character_id: 0x12, connect_code: Some("ABCD#123"), cpu: false },
+    ];
+    let builder = ReplayBuilder::new(BATTLEFIELD, players).frames(NETPLAY_FRAMES, netplay_script);
+    Fixture {
+        path: write_fixture("zelda_as_sheik", &builder.build()),
+        players: vec![(0, ZELDA, Some("HATS#982"), false), (1, MARTH, Some("ABCD#123"), false)],
+        ..netplay()
+    }
+}
+
+/// The netplay game with Fox's dashback let go into an empty pivot: one frame
+/// of the new dash, then standing facing left with nothing after it
+pub fn empty_pivot() -> Fixture {
+    let players = vec![
+        ReplayPlayer { port: 0, character_id: FOX, internal_character_id: 0x01, connect_code: Some("HATS#982"), cpu: false },
+        ReplayPlayer { port: 1, character_id: MARTH, internal_character_id: 0x12, connect_code: Some("ABCD#123"), cpu: false },
+    ];
+    let builder = ReplayBuilder::new(BATTLEFIELD, players).frames(NETPLAY_FRAMES, |index, player| {
+        let standing = PlayerFrame { x: index as f32 * 0.1, ..PlayerFrame::default() };
+        match (player, index) {
+            (0, 100..=102) => PlayerFrame { action_state: DASH, joystick_x: 1.0, ..standing },
+            (0, 103) => PlayerFrame { action_state: DASH, facing: -1.0, joystick_x: -1.0, ..standing },
+            (0, 104..) => PlayerFrame { facing: -1.0, ..standing },
+            (0, _) => standing,
+            _ => marth(index),
+        }
+    });
+    let pivot = InputQualityCounts {
+        dashback_attempts: 1,
+        dashback_successes: 1,
+        pivot_attempts: 1,
+        pivot_successes: 1,
+        empty_pivots: 1,
+        ..Default::default()
+    };
+    Fixture {
+        path: write_fixture("empty_pivot", &builder.build()),
+        inputs: BTreeMap::from([(0, pivot), (1, InputQualityCounts::default())]),
+        ..netplay()
+    }
+}
+
+/// The netplay game, rollback included
+fn netplay_game() -> ReplayBuilder {
+    let rolled_back = PlayerFrame { x: ROLLED_BACK_X, ..netplay_script(ROLLED_BACK_INDEX, 1) };
+    netplay_builder(ROLLED_BACK_INDEX + 2)
+        .match_id("mode.ranked-2024-03-01T18:02:11.52-0")
+        .resend(FIRST_FRAME + ROLLED_BACK_INDEX as i32, vec![netplay_script(ROLLED_BACK_INDEX, 0), rolled_back])
+        .frames(NETPLAY_FRAMES - ROLLED_BACK_INDEX - 2, netplay_script)
+}
+
+fn netplay_builder(frames: usize) -> ReplayBuilder {
+    let players = vec![
+        ReplayPlayer { port: 0, character_id: FOX, internal_character_id: 0x01, connect_code: Some("HATS#982"), cpu: false },
+        ReplayPlayer { port: 1, character_id: MARTH, internal_character_id: 0x12, connect_code: Some("ABCD#123"), cpu: false },
+    ];
+    ReplayBuilder::new(BATTLEFIELD, players).frames(frames, netplay_script)
+}
+
+/// What each player does on each frame of the netplay game
+fn netplay_script(index: usize, player: usize) -> PlayerFrame {
+    let standing = PlayerFrame { x: index as f32 * 0.1, ..PlayerFrame::default() };
+    match (player, index) {
+        // Fox: dash right, then back left (a dashback)
+        (0, 100..=102) => PlayerFrame { action_state: DASH, joystick_x: 1.0, ..standing },
+        (0, 103..=107) => PlayerFrame { action_state: DASH, facing: -1.0, joystick_x: -1.0, ..standing },
+        // Fox: stick held right, let go, overshoots to -0.4
+        (0, 200..=201) => PlayerFrame { joystick_x: 1.0, ..standing },
+        (0, 202) => PlayerFrame { joystick_x: -0.4, ..standing },
+        (0, _) => standing,
+        // Marth: dash right, then a frame in the tilt zone (a tilt turn)
+        (_, 150..=152) => PlayerFrame { action_state: DASH, joystick_x: 1.0, ..marth(index) },
+        (_, 153) => PlayerFrame { action_state: TURN, joystick_x: -0.5, ..marth(index) },
+        (_, 154) => PlayerFrame { action_state: TURN, facing: -1.0, joystick_x: -1.0, ..marth(index) },
+        _ => marth(index),
+    }
+}
+
+/// Marth loses a stock every 100 frames, taking 0.5% a frame in between
+fn marth(index: usize) -> PlayerFrame {
+    PlayerFrame {
+        action_state: WAIT,
+        percent: (index % 100) as f32 * 0.5,
+        stocks: 4 - (index / 100).min(4) as u8,
+        ..PlayerFrame::default()
+    }
+}
+
+/// Write a fixture into the cache directory (atomically, tests run in parallel)
+fn write_fixture(name: &str, data: &[u8]) -> PathBuf {
+    let dir = std::env::temp_dir().join("buckwheat-fixtures");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}.slp", name));
+    if std::fs::read(&path).is_ok_and(|existing| existing == data) {
+        return path;
+    }
+    let temp = dir.join(format!("{}.{}.tmp", name, std::process::id()));
+    std::fs::write(&temp, data).unwrap();
+    std::fs::rename(&temp, &path).unwrap();
+    path
+}
+
+/// A fully migrated in-memory database
+pub fn database() -> Database {
+    Database::open_in_memory().unwrap()
+}
+
+/// Every frame row of a replay, in order
+pub fn read_all_frames(path: &Path) -> Vec<FrameRow> {
+    let mut rows = Vec::new();
+    frames::read_frames(path, &FrameSelection::default(), |row| {
+        rows.push(row);
+        Ok::<_, io::Error>(())
+    })
+    .unwrap();
+    rows
+}
+
+/// What the frontend's stats pass would save for a game, worked out from
+/// its frames: stocks and percent at the end, and the winner by stocks
+pub fn stats_rows(recording_id: &str, start: &GameStart, frames: &[FrameRow], created_at: &str) -> (GameStatsRow, Vec<PlayerStatsRow>) {
+    let last: BTreeMap<u8, &FrameRow> = frames.iter().map(|row| (row.port, row)).collect();
+    let total_frames = frames.iter().map(|row| row.frame).max().map_or(0, |frame| frame - replay::FIRST_FRAME + 1);
+    let most_stocks = last.values().map(|row| row.state.stocks).max();
+    let leaders: Vec<&FrameRow> = last.values().copied().filter(|row| Some(row.state.stocks) == most_stocks).collect();
+    let winner = (leaders.len() == 1).then(|| leaders[0]);
+    let player = |index: usize| start.players.get(index);
+    let code = |port: u8| start.players.iter().find(|p| p.port == port).and_then(|p| p.connect_code.clone());
+    let loser = winner.and_then(|w| last.keys().copied().find(|&port| port != w.port));
+
+    let game = GameStatsRow {
+        id: recording_id.to_string(),
+        player1_id: player(0).and_then(|p| p.connect_code.clone()),
+        player2_id: player(1).and_then(|p| p.connect_code.clone()),
+        player1_port: player(0).map(|p| i32::from(p.port) + 1),
+        player2_port: player(1).map(|p| i32::from(p.port) + 1),
+        player1_character: player(0).map(|p| i32::from(p.character_id)),
+        player2_character: player(1).map(|p| i32::from(p.character_id)),
+        player1_color: Some(0),
+        player2_color: Some(0),
+        winner_port: winner.map(|w| i32::from(w.port) + 1),
+        loser_port: loser.map(|port| i32::from(port) + 1),
+        stage: Some(i32::from(start.stage)),
+        game_duration: Some(total_frames),
+        total_frames: Some(total_frames),
+        is_pal: Some(false),
+        played_on: Some("dolphin".to_string()),
+        created_at: Some(created_at.to_string()),
+        slp_path: None,
+        replay_version: Some(start.version.to_string()),
+        excluded_reason: None,
+        total_paused_frames: None,
+        winner_code: winner.and_then(|w| code(w.port)),
+        loser_code: loser.and_then(code),
+        my_result: MyResult::Unknown,
+        is_warmup: false,
+        stats_source: StatsSource::Buckwheat,
+        completed: true,
+    };
+    let players = start.players.iter().map(|p| player_row(recording_id, p, last.get(&p.port).copied())).collect();
+    (game, players)
+}
+
+fn player_row(recording_id: &str, player: &GameStartPlayer, last: Option<&FrameRow>) -> PlayerStatsRow {
+    PlayerStatsRow {
+        id: None,
+        recording_id: recording_id.to_string(),
+        player_index: i32::from(player.port),
+        connect_code: player.connect_code.clone(),
+        display_name: None,
+        character_id: i32::from(player.character_id),
+        character_color: 0,
+        port: i32::from(player.port) + 1,
+        total_damage: 0.0,
+        kill_count: 0,
+        conversion_count: 0,
+        successful_conversions: 0,
+        openings_per_kill: None,
+        damage_per_opening: None,
+        neutral_win_ratio: None,
+        counter_hit_ratio: None,
+        beneficial_trade_ratio: None,
+        inputs_total: 0,
+        inputs_per_minute: None,
+        avg_kill_percent: None,
+        wavedash_count: 0,
+        waveland_count: 0,
+        air_dodge_count: 0,
+        dash_dance_count: 0,
+        spot_dodge_count: 0,
+        ledgegrab_count: 0,
+        roll_count: 0,
+        grab_count: 0,
+        throw_count: 0,
+        ground_tech_count: 0,
+        wall_tech_count: 0,
+        wall_jump_tech_count: None,
+        l_cancel_success_count: None,
+        l_cancel_fail_count: None,
+        stocks_remaining: last.map_or(0, |row| i32::from(row.state.stocks)),
+        final_percent: last.map(|row| f64::from(row.state.percent)),
+        slp_path: None,
+        is_me: false,
+        stats_version: 1,
+        is_cpu: player.is_cpu,
+    }
+}
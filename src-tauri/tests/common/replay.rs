@@ -0,0 +1,279 @@
+//! Writes .slp files byte for byte from a script of frames
+//!
+//! Only the events and fields the app reads are filled in (Game Start,
+//! Pre-Frame, Post-Frame, Game End), at the offsets of the Slippi replay
+//! spec; everything else is zero. Payload sizes are those of a 3.16 replay.
+
+const RAW_HEADER: &[u8] = b"{U\x03raw[$U#l";
+
+const EVENT_PAYLOADS: u8 = 0x35;
+const GAME_START: u8 = 0x36;
+const PRE_FRAME: u8 = 0x37;
+const POST_FRAME: u8 = 0x38;
+const GAME_END: u8 = 0x39;
+
+const GAME_START_SIZE: usize = 0x300;
+const PRE_FRAME_SIZE: usize = 0x40;
+const POST_FRAME_SIZE: usize = 0x54;
+const GAME_END_SIZE: usize = 0x02;
+
+const PLAYER_TYPE_HUMAN: u8 = 0;
+const PLAYER_TYPE_CPU: u8 = 1;
+const PLAYER_TYPE_EMPTY: u8 = 3;
+
+pub const FIRST_FRAME: i32 = -123;
+
+/// Action states used by the scripts
+pub const WAIT: u16 = 0x0E;
+pub const TURN: u16 = 0x12;
+pub const DASH: u16 = 0x14;
+
+/// A player slot in Game Start
+#[derive(Debug, Clone)]
+pub struct ReplayPlayer {
+    /// 0-indexed
+    pub port: u8,
+    /// External character ID
+    pub character_id: u8,
+    /// Internal character ID, as written in Post-Frame
+    pub internal_character_id: u8,
+    pub connect_code: Option<&'static str>,
+    pub cpu: bool,
+}
+
+/// One player on one frame
+#[derive(Debug, Clone, Copy)]
+pub struct PlayerFrame {
+    pub action_state: u16,
+    pub facing: f32,
+    pub x: f32,
+    pub percent: f32,
+    pub stocks: u8,
+    pub joystick_x: f32,
+}
+
+impl Default for PlayerFrame {
+    fn default() -> Self {
+        Self { action_state: WAIT, facing: 1.0, x: 0.0, percent: 0.0, stocks: 4, joystick_x: 0.0 }
+    }
+}
+
+pub struct ReplayBuilder {
+    version: [u8; 3],
+    stage: u16,
+    players: Vec<ReplayPlayer>,
+    match_id: Option<&'static str>,
+    /// (frame number, one entry per player), in the order written
+    frames: Vec<(i32, Vec<PlayerFrame>)>,
+    finished: bool,
+    /// startAt of the UBJSON metadata; without it none is written
+    start_at: Option<&'static str>,
+}
+
+impl ReplayBuilder {
+    pub fn new(stage: u16, players: Vec<ReplayPlayer>) -> Self {
+        Self { version: [3, 16, 0], stage, players, match_id: None, frames: Vec::new(), finished: true, start_at: None }
+    }
+
+    pub fn match_id(mut self, match_id: &'static str) -> Self {
+        self.match_id = Some(match_id);
+        self
+    }
+
+    /// Add `count` frames after the latest one, each player's from `script`
+    /// (called with the frame's index from the start of the game and the
+    /// player's position in the player list)
+    pub fn frames(mut self, count: usize, script: impl Fn(usize, usize) -> PlayerFrame) -> Self {
+        let start = self.frames.iter().map(|(frame, _)| frame + 1).max().unwrap_or(FIRST_FRAME);
+        for i in 0..count {
+            let index = (start - FIRST_FRAME) as usize + i;
+            let players = (0..self.players.len()).map(|p| script(index, p)).collect();
+            self.frames.push((start + i as i32, players));
+        }
+        self
+    }
+
+    /// Write `frame` again with different data, like a netplay rollback does
+    pub fn resend(mut self, frame: i32, players: Vec<PlayerFrame>) -> Self {
+        self.frames.push((frame, players));
+        self
+    }
+
+    /// Write the metadata Slippi adds when the game ends (start time, last
+    /// frame, each player's netplay name and code) and display names in Game
+    /// Start. Only finished replays have it
+    pub fn metadata(mut self, start_at: &'static str) -> Self {
+        self.start_at = Some(start_at);
+        self
+    }
+
+    /// Stop without Game End or the raw length, like a crash mid-game
+    pub fn crashed(mut self) -> Self {
+        self.finished = false;
+        self
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let mut events = vec![EVENT_PAYLOADS, 1 + 3 * 4];
+        for (command, size) in [
+            (GAME_START, GAME_START_SIZE),
+            (PRE_FRAME, PRE_FRAME_SIZE),
+            (POST_FRAME, POST_FRAME_SIZE),
+            (GAME_END, GAME_END_SIZE),
+        ] {
+            events.push(command);
+            events.extend_from_slice(&(size as u16).to_be_bytes());
+        }
+        events.extend(self.game_start());
+
+        for (frame, players) in &self.frames {
+            for (player, data) in self.players.iter().zip(players) {
+                events.extend(pre_frame(*frame, player.port, data));
+            }
+            for (player, data) in self.players.iter().zip(players) {
+                events.extend(post_frame(*frame, player, data));
+            }
+        }
+
+        if self.finished {
+            let mut game_end = vec![0u8; 1 + GAME_END_SIZE];
+            game_end[0] = GAME_END;
+            game_end[1] = 2; // GAME!
+            events.extend(game_end);
+        }
+
+        let mut data = RAW_HEADER.to_vec();
+        let raw_len = if self.finished { events.len() as u32 } else { 0 };
+        data.extend_from_slice(&raw_len.to_be_bytes());
+        data.extend(events);
+        if self.finished {
+            if let Some(start_at) = self.start_at {
+                data.extend(self.ubjson_metadata(start_at));
+            }
+            data.push(b'}');
+        }
+        data
+    }
+
+    fn ubjson_metadata(&self, start_at: &str) -> Vec<u8> {
+        let last_frame = self.frames.iter().map(|(frame, _)| *frame).max().unwrap_or(FIRST_FRAME);
+        let mut metadata = ubjson_key("metadata");
+        metadata.push(b'{');
+        metadata.extend(ubjson_key("startAt"));
+        metadata.extend(ubjson_string(start_at));
+        metadata.extend(ubjson_key("lastFrame"));
+        metadata.push(b'l');
+        metadata.extend_from_slice(&last_frame.to_be_bytes());
+        metadata.extend(ubjson_key("players"));
+        metadata.push(b'{');
+        for player in &self.players {
+            metadata.extend(ubjson_key(&player.port.to_string()));
+            metadata.push(b'{');
+            metadata.extend(ubjson_key("names"));
+            metadata.push(b'{');
+            if let Some(code) = player.connect_code {
+                metadata.extend(ubjson_key("netplay"));
+                metadata.extend(ubjson_string(display_name(code)));
+                metadata.extend(ubjson_key("code"));
+                metadata.extend(ubjson_string(code));
+            }
+            metadata.push(b'}');
+            metadata.extend(ubjson_key("characters"));
+            // Frames played per internal character ID
+            metadata.push(b'{');
+            metadata.extend(ubjson_key(&player.internal_character_id.to_string()));
+            metadata.push(b'l');
+            metadata.extend_from_slice(&(self.frames.len() as i32).to_be_bytes());
+            metadata.push(b'}');
+            metadata.push(b'}');
+        }
+        metadata.push(b'}');
+        metadata.extend(ubjson_key("playedOn"));
+        metadata.extend(ubjson_string("dolphin"));
+        metadata.push(b'}');
+        metadata
+    }
+
+    fn game_start(&self) -> Vec<u8> {
+        let mut event = vec![0u8; 1 + GAME_START_SIZE];
+        event[0] = GAME_START;
+        event[1..4].copy_from_slice(&self.version);
+        event[0x13..0x15].copy_from_slice(&self.stage.to_be_bytes());
+        for port in 0..4 {
+            event[0x65 + 0x24 * port + 1] = PLAYER_TYPE_EMPTY;
+        }
+        for player in &self.players {
+            let block = 0x65 + 0x24 * player.port as usize;
+            event[block] = player.character_id;
+            event[block + 1] = if player.cpu { PLAYER_TYPE_CPU } else { PLAYER_TYPE_HUMAN };
+            if let Some(code) = player.connect_code {
+                let encoded = encode_connect_code(code);
+                let offset = 0x221 + 0x0A * player.port as usize;
+                event[offset..offset + encoded.len()].copy_from_slice(&encoded);
+            }
+        }
+        if self.start_at.is_some() {
+            for player in &self.players {
+                if let Some(code) = player.connect_code {
+                    let name = display_name(code);
+                    let offset = 0x1A5 + 0x1F * player.port as usize;
+                    event[offset..offset + name.len()].copy_from_slice(name.as_bytes());
+                }
+            }
+        }
+        if let Some(match_id) = self.match_id {
+            event[0x2BE..0x2BE + match_id.len()].copy_from_slice(match_id.as_bytes());
+        }
+        event
+    }
+}
+
+fn pre_frame(frame: i32, port: u8, data: &PlayerFrame) -> Vec<u8> {
+    let mut event = vec![0u8; 1 + PRE_FRAME_SIZE];
+    event[0] = PRE_FRAME;
+    event[0x01..0x05].copy_from_slice(&frame.to_be_bytes());
+    event[0x05] = port;
+    event[0x0B..0x0D].copy_from_slice(&data.action_state.to_be_bytes());
+    event[0x0D..0x11].copy_from_slice(&data.x.to_be_bytes());
+    event[0x15..0x19].copy_from_slice(&data.facing.to_be_bytes());
+    event[0x19..0x1D].copy_from_slice(&data.joystick_x.to_be_bytes());
+    event[0x3C..0x40].copy_from_slice(&data.percent.to_be_bytes());
+    event
+}
+
+fn post_frame(frame: i32, player: &ReplayPlayer, data: &PlayerFrame) -> Vec<u8> {
+    let mut event = vec![0u8; 1 + POST_FRAME_SIZE];
+    event[0] = POST_FRAME;
+    event[0x01..0x05].copy_from_slice(&frame.to_be_bytes());
+    event[0x05] = player.port;
+    event[0x07] = player.internal_character_id;
+    event[0x08..0x0A].copy_from_slice(&data.action_state.to_be_bytes());
+    event[0x0A..0x0E].copy_from_slice(&data.x.to_be_bytes());
+    event[0x12..0x16].copy_from_slice(&data.facing.to_be_bytes());
+    event[0x16..0x1A].copy_from_slice(&data.percent.to_be_bytes());
+    event[0x1A..0x1E].copy_from_slice(&60.0f32.to_be_bytes());
+    event[0x21] = data.stocks;
+    event
+}
+
+/// A netplay display name: the connect code's tag
+fn display_name(code: &str) -> &str {
+    code.split('#').next().unwrap_or(code)
+}
+
+fn ubjson_key(key: &str) -> Vec<u8> {
+    let mut bytes = vec![b'U', key.len() as u8];
+    bytes.extend_from_slice(key.as_bytes());
+    bytes
+}
+
+fn ubjson_string(value: &str) -> Vec<u8> {
+    let mut bytes = vec![b'S'];
+    bytes.extend(ubjson_key(value));
+    bytes
+}
+
+/// Shift-JIS, with a full-width '#'
+fn encode_connect_code(code: &str) -> Vec<u8> {
+    code.bytes().flat_map(|b| if b == b'#' { vec![0x81, 0x94] } else { vec![b] }).collect()
+}
@@ -0,0 +1,120 @@
+//! Reading fixture replays: Game Start, integrity, frames and input analysis
+
+mod common;
+
+use common::{
+    cpu_match, crashed, empty_pivot, netplay, netplay_swapped, netplay_with_metadata, read_all_frames, NETPLAY_START_AT,
+    ROLLED_BACK_INDEX, ROLLED_BACK_X,
+};
+use peppi_lib::inputs;
+use peppi_lib::slippi::anonymize::anonymize;
+use peppi_lib::slippi::frames::{self, FrameSelection, FIRST_FRAME};
+use peppi_lib::slippi::game_start::read_game_start;
+use peppi_lib::slippi::integrity::{check_replay, ParseErrorKind};
+use peppi_lib::slippi::metadata::read_start_at;
+use peppi_lib::slippi::timeline::{read_timeline, SAMPLE_FRAMES};
+use std::io;
+
+#[test]
+fn test_read_game_start_from_fixtures() {
+    for fixture in [netplay(), cpu_match()] {
+        let start = read_game_start(&fixture.path).unwrap();
+        assert_eq!(start.stage, fixture.stage);
+        let players: Vec<_> = start
+            .players
+            .iter()
+            .map(|p| (p.port, p.character_id, p.connect_code.as_deref(), p.is_cpu))
+            .collect();
+        assert_eq!(players, fixture.players);
+    }
+
+    let netplay = read_game_start(&netplay().path).unwrap();
+    assert_eq!(netplay.version.to_string(), "3.16.0");
+    assert_eq!(netplay.match_id.as_deref(), Some("mode.ranked-2024-03-01T18:02:11.52-0"));
+    assert!(!netplay.is_cpu_match());
+    assert!(read_game_start(&cpu_match().path).unwrap().is_cpu_match());
+
+    // A crash doesn't lose who played
+    let crashed = crashed(200);
+    assert_eq!(read_game_start(&crashed.path).unwrap().players.len(), 2);
+}
+
+#[test]
+fn test_check_replay_flags_crashed_replays() {
+    assert_eq!(check_replay(&netplay().path), Ok(()));
+    assert_eq!(check_replay(&cpu_match().path), Ok(()));
+    assert_eq!(check_replay(&crashed(200).path).unwrap_err().kind, ParseErrorKind::Truncated);
+}
+
+#[test]
+fn test_read_frames_resolves_rollbacks() {
+    for fixture in [netplay(), cpu_match(), crashed(200)] {
+        let rows = read_all_frames(&fixture.path);
+        assert_eq!(rows.len(), fixture.frames * fixture.players.len(), "{}", fixture.path.display());
+        let last = FIRST_FRAME + fixture.frames as i32 - 1;
+        assert!(rows.windows(2).all(|w| (w[0].frame, w[0].port) < (w[1].frame, w[1].port)));
+        assert_eq!(rows.last().map(|row| row.frame), Some(last));
+    }
+
+    // The re-sent frame replaces the first one
+    let rows = read_all_frames(&netplay().path);
+    let rolled_back = FIRST_FRAME + ROLLED_BACK_INDEX as i32;
+    let marth = rows.iter().find(|row| row.frame == rolled_back && row.port == 1).unwrap();
+    assert_eq!(marth.state.x, ROLLED_BACK_X);
+
+    // Marth ends on one stock, Fox on four
+    let stocks: Vec<_> = rows.iter().rev().take(2).map(|row| (row.port, row.state.stocks)).collect();
+    assert_eq!(stocks, vec![(1, 1), (0, 4)]);
+
+    let selection = FrameSelection { ports: vec![1], every_nth: 10 };
+    let mut selected = 0;
+    frames::read_frames(&netplay().path, &selection, |row| {
+        assert_eq!((row.port, (row.frame - FIRST_FRAME) % 10), (1, 0));
+        selected += 1;
+        Ok::<_, io::Error>(())
+    })
+    .unwrap();
+    assert_eq!(selected, 40);
+
+    // Half-second samples of both players
+    let timeline = read_timeline(&netplay().path).unwrap();
+    assert_eq!(timeline.len(), 2 * 400_usize.div_ceil(SAMPLE_FRAMES as usize));
+    assert_eq!(timeline.last().map(|sample| (sample.port, sample.stocks)), Some((1, 1)));
+}
+
+#[test]
+fn test_analyze_replay_counts_scripted_inputs() {
+    // Counts follow the player to their port
+    for fixture in [netplay(), netplay_swapped(), empty_pivot(), cpu_match()] {
+        assert_eq!(inputs::analyze_replay(&fixture.path).unwrap(), fixture.inputs, "{}", fixture.path.display());
+    }
+}
+
+#[test]
+fn test_anonymized_replay_reads_like_the_original() {
+    let fixture = netplay_with_metadata();
+    let original = std::fs::read(&fixture.path).unwrap();
+    let anonymized = anonymize(&original).unwrap();
+    let path = fixture.path.with_file_name(format!("anonymized-{}.slp", std::process::id()));
+    std::fs::write(&path, &anonymized).unwrap();
+
+    // Same game, same frames, same metadata; only who played is gone
+    assert_eq!(check_replay(&path), Ok(()));
+    let start = read_game_start(&path).unwrap();
+    let players: Vec<_> = start.players.iter().map(|p| (p.port, p.character_id, p.connect_code.as_deref())).collect();
+    assert_eq!(players, vec![(0, common::FOX, Some("PLYR#1")), (1, common::MARTH, Some("PLYR#2"))]);
+    assert_eq!(start.match_id, read_game_start(&fixture.path).unwrap().match_id);
+    assert_eq!(read_all_frames(&path), read_all_frames(&fixture.path));
+    let start_at = read_start_at(&path).map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+    assert_eq!(start_at.as_deref(), Some(NETPLAY_START_AT));
+    for name in [&b"HATS"[..], b"ABCD"] {
+        assert!(!anonymized.windows(name.len()).any(|w| w == name));
+    }
+
+    // A crashed replay has no metadata, and Game Start is enough
+    let crashed = anonymize(&std::fs::read(crashed(200).path).unwrap()).unwrap();
+    std::fs::write(&path, &crashed).unwrap();
+    assert_eq!(read_all_frames(&path).len(), 200 * 2);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,326 @@
+//! Fixture replays through the stats pipeline: rows saved, attributed to me,
+//! CPU games excluded, inputs analyzed, and aggregated
+
+mod common;
+
+use common::finite::non_finite_fields;
+use common::{cpu_match, crashed, database, netplay, netplay_swapped, read_all_frames, stats_rows, zelda_as_sheik, Fixture, FOX, MARTH, ZELDA};
+use peppi_lib::database::{self, MyIdentity, MyResult, PlayerScope, StatsExclusion, StatsFilter, UsageBucket};
+use peppi_lib::inputs;
+use peppi_lib::slippi::characters::{self, SHEIK};
+use peppi_lib::slippi::game_start::read_game_start;
+use rusqlite::Connection;
+use std::path::Path;
+
+/// Save a fixture's stats rows the way the stats pass does, returning the
+/// player_index attributed to me
+fn ingest(conn: &Connection, recording_id: &str, fixture: &Fixture, created_at: &str) -> Option<i32> {
+    let start = read_game_start(&fixture.path).unwrap();
+    let (mut game, players) = stats_rows(recording_id, &start, &read_all_frames(&fixture.path), created_at);
+    let slp_path = fixture.path.to_string_lossy().to_string();
+    game.slp_path = Some(slp_path.clone());
+    game.completed = fixture.complete;
+    database::upsert_game_stats(conn, &game).unwrap();
+    for mut player in players {
+        player.slp_path = Some(slp_path.clone());
+        database::upsert_player_stats(conn, &player).unwrap();
+    }
+    let identity = MyIdentity { connect_codes: vec!["HATS#982".to_string()], ..Default::default() };
+    database::recompute_is_me_for_recording(conn, recording_id, &identity).unwrap()
+}
+
+fn excluded_reason(conn: &Connection, recording_id: &str) -> Option<String> {
+    conn.query_row("SELECT excluded_reason FROM game_stats WHERE id = ?1", [recording_id], |row| row.get(0))
+        .unwrap()
+}
+
+#[test]
+fn test_fixture_replays_through_stats_pipeline() {
+    let db = database();
+    let conn = db.connection();
+    let (netplay, rematch, cpu_match) = (netplay(), netplay().copied_as("netplay_rematch"), cpu_match());
+
+    assert_eq!(ingest(&conn, "game-1", &netplay, "2024-03-01T18:02:11Z"), Some(0));
+    assert_eq!(ingest(&conn, "game-2", &rematch, "2024-03-02T19:30:00Z"), Some(0));
+    assert_eq!(ingest(&conn, "game-3", &cpu_match, "2024-03-02T20:00:00Z"), None);
+
+    let game = database::get_game_stats(&conn, "game-1").unwrap().unwrap();
+    let winner = netplay.winner_port.map(|port| i32::from(port) + 1);
+    assert_eq!((game.winner_port, game.total_frames), (winner, Some(netplay.frames as i32)));
+
+    // The CPU match is also short, but CPU wins
+    let exclusion = StatsExclusion { min_frames: 300, exclude_cpu: true, exclude_unfinished: true };
+    assert_eq!(database::update_excluded_reasons(&conn, &exclusion, None).unwrap(), 1);
+    assert_eq!(excluded_reason(&conn, "game-3").as_deref(), Some("cpu"));
+    assert_eq!(excluded_reason(&conn, "game-1"), None);
+
+    let me = database::get_aggregated_player_stats(&conn, PlayerScope::Me, None).unwrap();
+    assert_eq!((me.total_games, me.total_wins), (2, 2));
+    // Per opponent character
+    let matchups: Vec<_> = me.character_stats.iter().map(|c| (c.character_id, c.games, c.wins)).collect();
+    assert_eq!(matchups, vec![(i32::from(MARTH), 2, 2)]);
+
+    let everyone = |include_excluded| {
+        let filter = StatsFilter { include_excluded, ..Default::default() };
+        database::get_aggregated_player_stats(&conn, PlayerScope::All, Some(filter)).unwrap().total_games
+    };
+    assert_eq!((everyone(false), everyone(true)), (4, 6));
+
+    // What the background analysis pass does with each pending game
+    let pending = database::get_pending_input_analysis(&conn).unwrap();
+    assert_eq!(pending.len(), 3);
+    for game in pending {
+        for (port, counts) in inputs::analyze_replay(Path::new(&game.slp_path)).unwrap() {
+            database::set_input_quality(&conn, &game.recording_id, i32::from(port), &counts).unwrap();
+        }
+        database::finish_input_analysis(&conn, &game.recording_id).unwrap();
+    }
+    assert!(database::get_pending_input_analysis(&conn).unwrap().is_empty());
+
+    let quality = database::get_input_quality(&conn, PlayerScope::Me, None).unwrap();
+    assert_eq!(quality.games, 2);
+    assert_eq!(quality.counts.dashback_attempts, 2 * netplay.inputs[&0].dashback_attempts);
+    assert_eq!((quality.dashback_success_rate, quality.snapbacks_per_game), (Some(1.0), Some(1.0)));
+    let days: Vec<_> = quality.trend.iter().map(|day| (day.date.as_str(), day.games)).collect();
+    assert_eq!(days, vec![("2024-03-01", 1), ("2024-03-02", 1)]);
+
+    let opponent = database::get_input_quality(&conn, PlayerScope::Code("ABCD#123"), None).unwrap();
+    assert_eq!((opponent.games, opponent.dashback_success_rate), (2, Some(0.0)));
+}
+
+#[test]
+fn test_merged_connect_codes_count_as_one_player() {
+    let db = database();
+    let conn = db.connection();
+    ingest(&conn, "game-1", &netplay(), "2024-03-01T18:02:11Z");
+    ingest(&conn, "game-2", &netplay().copied_as("netplay_rematch"), "2024-03-02T19:30:00Z");
+    // The opponent played the rematch on an alt
+    conn.execute("UPDATE player_stats SET connect_code = 'ALT#456' WHERE recording_id = 'game-2' AND connect_code = 'ABCD#123'", [])
+        .unwrap();
+    database::record_player_identity(&conn, "ABCD#123", Some("abcd"), "2024-03-01T18:02:11Z").unwrap();
+    database::record_player_identity(&conn, "ALT#456", Some("Secret"), "2024-03-02T19:30:00Z").unwrap();
+    database::record_player_identity(&conn, "HATS#982", Some("hats"), "2024-03-01T18:02:11Z").unwrap();
+
+    let games = |code| database::get_aggregated_player_stats(&conn, PlayerScope::Code(code), None).unwrap().total_games;
+    assert_eq!((games("ABCD#123"), games("ALT#456")), (1, 1));
+
+    database::merge_players(&conn, "alt#456", "ABCD#123").unwrap();
+    assert_eq!((games("ABCD#123"), games("ALT#456")), (2, 2));
+    let codes = database::get_available_filter_options(&conn, PlayerScope::All).unwrap().connect_codes;
+    assert_eq!(codes, vec!["ABCD#123", "HATS#982"]);
+
+    // Found by the alt's name, with the newest name first
+    let found = database::get_known_players(&conn, Some("secret"), 10).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!((found[0].connect_code.as_str(), found[0].codes.len()), ("ABCD#123", 2));
+    assert_eq!(found[0].display_names, vec!["Secret", "abcd"]);
+    assert_eq!(found[0].first_seen.as_deref(), Some("2024-03-01T18:02:11Z"));
+    assert_eq!(database::get_known_players(&conn, None, 10).unwrap().len(), 2);
+}
+
+#[test]
+fn test_character_usage_counts_the_character_played() {
+    let db = database();
+    let conn = db.connection();
+    let zelda = zelda_as_sheik();
+    ingest(&conn, "game-1", &netplay(), "2024-03-01T18:02:11Z");
+    ingest(&conn, "game-2", &zelda, "2024-03-20T19:30:00Z");
+    ingest(&conn, "game-3", &zelda_as_sheik().copied_as("zelda_as_sheik_rematch"), "2024-04-02T20:00:00Z");
+
+    // Zelda picked, Sheik played
+    let played = characters::dominant_characters(&zelda.path, &[0]).unwrap();
+    assert_eq!(played.into_iter().collect::<Vec<_>>(), vec![(0, SHEIK)]);
+    for game in ["game-2", "game-3"] {
+        database::set_played_character(&conn, game, 0, i32::from(SHEIK)).unwrap();
+    }
+
+    let usage = database::get_character_usage(&conn, PlayerScope::Me, UsageBucket::Month, None).unwrap();
+    let months: Vec<_> = usage
+        .iter()
+        .map(|month| {
+            let characters: Vec<_> = month.characters.iter().map(|c| (c.character_id, c.games, c.wins)).collect();
+            (month.start.as_str(), characters)
+        })
+        .collect();
+    let (fox, sheik) = (i32::from(FOX), i32::from(SHEIK));
+    assert_eq!(months, vec![("2024-03-01", vec![(fox, 1, 1), (sheik, 1, 1)]), ("2024-04-01", vec![(sheik, 1, 1)])]);
+    assert_eq!(usage[1].characters[0].win_rate, Some(1.0));
+
+    let weeks = database::get_character_usage(&conn, PlayerScope::Me, UsageBucket::Week, None).unwrap();
+    let starts: Vec<_> = weeks.iter().map(|week| week.start.as_str()).collect();
+    assert_eq!(starts, vec!["2024-02-26", "2024-03-18", "2024-04-01"]);
+
+    let range = database::get_first_and_last_played(&conn, PlayerScope::Me, sheik).unwrap().unwrap();
+    assert_eq!((range.games, range.wins), (2, 2));
+    assert_eq!(range.first_played.as_deref(), Some("2024-03-20T19:30:00Z"));
+    assert_eq!(range.last_played.as_deref(), Some("2024-04-02T20:00:00Z"));
+    assert_eq!(database::get_first_and_last_played(&conn, PlayerScope::Me, i32::from(ZELDA)).unwrap(), None);
+}
+
+#[test]
+fn test_results_follow_connect_codes_when_ports_swap() {
+    let db = database();
+    let conn = db.connection();
+    // Game 2 of the set has me (HATS#982) on port 2 instead of port 1
+    ingest(&conn, "game-1", &netplay(), "2024-03-01T18:02:11Z");
+    ingest(&conn, "game-2", &netplay_swapped(), "2024-03-01T18:07:40Z");
+    let my_record = || {
+        let me = database::get_aggregated_player_stats(&conn, PlayerScope::Me, None).unwrap();
+        (me.total_games, me.total_wins)
+    };
+    assert_eq!(my_record(), (2, 2));
+    let opponent = database::get_aggregated_player_stats(&conn, PlayerScope::Code("ABCD#123"), None).unwrap();
+    assert_eq!(opponent.total_wins, 0);
+
+    // A winner port left on my old port doesn't matter while the code is there
+    conn.execute("UPDATE game_stats SET winner_port = 1, loser_port = 2 WHERE id = 'game-2'", []).unwrap();
+    assert_eq!(my_record(), (2, 2));
+
+    // Saved before results were kept by code: the port decides, wrongly, until repaired
+    conn.execute("UPDATE game_stats SET winner_code = NULL, loser_code = NULL WHERE id = 'game-2'", []).unwrap();
+    assert_eq!(my_record(), (2, 1));
+    assert_eq!(database::repair_game_results(&conn).unwrap(), 1);
+    assert_eq!(my_record(), (2, 2));
+    let game = database::get_game_stats(&conn, "game-2").unwrap().unwrap();
+    assert_eq!((game.winner_port, game.winner_code.as_deref()), (Some(2), Some("HATS#982")));
+    assert_eq!((game.loser_port, game.loser_code.as_deref()), (Some(1), Some("ABCD#123")));
+    assert_eq!(game.my_result, MyResult::Win);
+    assert_eq!(database::repair_game_results(&conn).unwrap(), 0);
+
+    let streak = |scope| {
+        let streak = database::get_current_streak(&conn, scope).unwrap();
+        (streak.result, streak.length)
+    };
+    assert_eq!(streak(PlayerScope::Me), (MyResult::Win, 2));
+    assert_eq!(streak(PlayerScope::Code("ABCD#123")), (MyResult::Loss, 2));
+}
+
+#[test]
+fn test_reparse_flag_survives_resync_until_stats_are_saved() {
+    let db = database();
+    let conn = db.connection();
+    let fixture = netplay();
+    let slp_path = fixture.path.to_string_lossy().to_string();
+    let row = database::RecordingRow {
+        id: "game-1".to_string(),
+        video_path: Some("/videos/game-1.mp4".to_string()),
+        slp_path: Some(slp_path.clone()),
+        file_size: Some(1024),
+        file_modified_at: None,
+        thumbnail_path: None,
+        start_time: Some("2024-03-01T18:02:11Z".to_string()),
+        cached_at: "2024-03-01T18:10:00Z".to_string(),
+        needs_reparse: false,
+        dropped_frame_estimate: None,
+        longest_stall_ms: None,
+        profile: None,
+        is_archived: false,
+        parse_error: None,
+        replay_id: None,
+        video_width: None,
+        video_height: None,
+        video_duration_secs: None,
+        has_audio: None,
+        audio_mean_volume_db: None,
+        video_offset_ms: None,
+        source_recording_id: None,
+    };
+    database::upsert_recording(&conn, &row).unwrap();
+    ingest(&conn, "game-1", &fixture, "2024-03-01T18:02:11Z");
+    let needs_reparse = || database::get_recording_by_id(&conn, "game-1").unwrap().unwrap().needs_reparse;
+
+    // Stats from version 1 are outdated once the stats code moves on
+    assert_eq!(database::flag_outdated_stats(&conn, 1).unwrap(), 0);
+    assert_eq!(database::flag_outdated_stats(&conn, 2).unwrap(), 1);
+    assert_eq!(database::flag_outdated_stats(&conn, 2).unwrap(), 0);
+
+    // A sync re-indexing the video leaves it flagged
+    database::upsert_recording(&conn, &row).unwrap();
+    assert!(needs_reparse());
+    assert!(database::finish_reparse(&conn, "game-1").unwrap());
+    assert!(!needs_reparse());
+    assert!(!database::finish_reparse(&conn, "game-1").unwrap());
+
+    // A replay that changed is flagged, one hashed for the first time isn't
+    database::set_slp_hash(&conn, "game-1", "first", false).unwrap();
+    assert!(!needs_reparse());
+    database::set_slp_hash(&conn, "game-1", "second", true).unwrap();
+    assert!(needs_reparse());
+    let hashes = database::get_replay_hashes(&conn).unwrap();
+    assert_eq!(hashes[0].slp_hash.as_deref(), Some("second"));
+    database::finish_reparse(&conn, "game-1").unwrap();
+
+    // Asked for from the UI, which also retries a replay that failed to parse
+    database::set_recording_parse_error(&conn, "game-1", Some("corrupt")).unwrap();
+    assert!(database::set_needs_reparse(&conn, "game-1").unwrap());
+    let recording = database::get_recording_by_id(&conn, "game-1").unwrap().unwrap();
+    assert!(recording.needs_reparse && recording.parse_error.is_none());
+    let clip = database::RecordingRow { id: "clip".to_string(), video_path: Some("/videos/clip.mp4".to_string()), slp_path: None, ..row };
+    database::upsert_recording(&conn, &clip).unwrap();
+    assert!(!database::set_needs_reparse(&conn, "clip").unwrap());
+}
+
+#[test]
+fn test_provisional_row_is_replaced_by_full_stats() {
+    let db = database();
+    let conn = db.connection();
+    let fixture = netplay();
+    let slp_path = fixture.path.to_string_lossy().to_string();
+
+    // What game_session saves from Game Start alone
+    let start = read_game_start(&fixture.path).unwrap();
+    let (mut provisional, _) = stats_rows("game-1", &start, &[], "2024-03-01T18:02:11Z");
+    provisional.slp_path = Some(slp_path.clone());
+    provisional.excluded_reason = Some("provisional".to_string());
+    assert!(database::insert_provisional_game_stats(&conn, &provisional).unwrap());
+    assert!(!database::insert_provisional_game_stats(&conn, &provisional).unwrap());
+    assert_eq!(excluded_reason(&conn, "game-1").as_deref(), Some("provisional"));
+    // Not synced yet, so the stats pass still parses it
+    assert!(!database::game_stats_exists_by_slp_path(&conn, &slp_path).unwrap());
+
+    // Recomputing exclusions keeps it out of aggregates
+    let exclusion = StatsExclusion { min_frames: 0, exclude_cpu: true, exclude_unfinished: true };
+    database::update_excluded_reasons(&conn, &exclusion, None).unwrap();
+    assert_eq!(excluded_reason(&conn, "game-1").as_deref(), Some("provisional"));
+
+    assert_eq!(ingest(&conn, "game-1", &fixture, "2024-03-01T18:02:11Z"), Some(0));
+    assert_eq!(excluded_reason(&conn, "game-1"), None);
+    assert!(database::game_stats_exists_by_slp_path(&conn, &slp_path).unwrap());
+    let me = database::get_aggregated_player_stats(&conn, PlayerScope::Me, None).unwrap();
+    assert_eq!(me.total_games, 1);
+}
+
+#[test]
+fn test_unfinished_games_are_left_out_and_never_send_nan() {
+    let db = database();
+    let conn = db.connection();
+    ingest(&conn, "game-1", &netplay(), "2024-03-01T18:02:11Z");
+    // Two frames in, then a crash: no Game End, and next to nothing to average over
+    ingest(&conn, "game-2", &crashed(2), "2024-03-01T18:10:00Z");
+    assert!(!database::get_game_stats(&conn, "game-2").unwrap().unwrap().completed);
+
+    let exclusion = StatsExclusion { exclude_unfinished: true, ..Default::default() };
+    assert_eq!(database::update_excluded_reasons(&conn, &exclusion, None).unwrap(), 1);
+    assert_eq!(excluded_reason(&conn, "game-2").as_deref(), Some("unfinished"));
+    assert_eq!(database::get_aggregated_player_stats(&conn, PlayerScope::Me, None).unwrap().total_games, 1);
+
+    // Counted anyway, everything the stats commands send stays a number
+    assert_eq!(database::update_excluded_reasons(&conn, &StatsExclusion::default(), None).unwrap(), 0);
+    let me = database::get_aggregated_player_stats(&conn, PlayerScope::Me, None).unwrap();
+    assert_eq!(me.total_games, 2);
+    let nobody = database::get_aggregated_player_stats(&conn, PlayerScope::Code("NOBODY#1"), None).unwrap();
+    assert_eq!(non_finite_fields(&me), Vec::<String>::new());
+    assert_eq!(non_finite_fields(&nobody), Vec::<String>::new());
+    assert_eq!(non_finite_fields(&database::compare_stats(me, nobody)), Vec::<String>::new());
+    let usage = database::get_character_usage(&conn, PlayerScope::Me, UsageBucket::Day, None).unwrap();
+    assert_eq!(non_finite_fields(&usage), Vec::<String>::new());
+    let averages = database::get_my_stat_averages(&conn, "2024-01-01T00:00:00Z").unwrap();
+    assert_eq!(non_finite_fields(&averages), Vec::<String>::new());
+    let quality = database::get_input_quality(&conn, PlayerScope::Me, None).unwrap();
+    assert_eq!(non_finite_fields(&quality), Vec::<String>::new());
+    let players = database::get_player_stats_by_recording(&conn, "game-2").unwrap();
+    assert_eq!(non_finite_fields(&players), Vec::<String>::new());
+
+    // The check itself finds them
+    assert_eq!(non_finite_fields(&[(1.0, f64::NAN)]), vec!["[][]"]);
+}
@@ -51,9 +51,9 @@ export interface GameWindow {
     process_name: string;
     /** Window title text */
     window_title: string;
-    /** Window width in pixels */
+    /** Window width in physical pixels */
     width: number;
-    /** Window height in pixels */
+    /** Window height in physical pixels */
     height: number;
     /** Operating system process ID */
     process_id: number;
@@ -65,6 +65,16 @@ export interface GameWindow {
     is_child: boolean;
     /** Whether this window has an owner window */
     has_owner: boolean;
+    /** Device name of the monitor it's on (Windows), null if unknown */
+    monitor_name: string | null;
+    /** Position of that monitor in the system's list, null if unknown */
+    monitor_index: number | null;
+    /** That monitor's scale (1 at 100%, 1.5 at 150%) */
+    scale_factor: number;
+    /** Whether the window is maximized */
+    is_maximized: boolean;
+    /** Whether the window covers its whole monitor */
+    is_fullscreen: boolean;
 }
 
 /**
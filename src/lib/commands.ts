@@ -51,9 +51,9 @@ export interface GameWindow {
     process_name: string;
     /** Window title text */
     window_title: string;
-    /** Window width in pixels */
+    /** Window width in physical pixels */
     width: number;
-    /** Window height in pixels */
+    /** Window height in physical pixels */
     height: number;
     /** Operating system process ID */
     process_id: number;
@@ -65,6 +65,16 @@ export interface GameWindow {
     is_child: boolean;
     /** Whether this window has an owner window */
     has_owner: boolean;
+    /** Device name of the monitor it's on (Windows), null if unknown */
+    monitor_name: string | null;
+    /** Position of that monitor in the system's list, null if unknown */
+    monitor_index: number | null;
+    /** That monitor's scale (1 at 100%, 1.5 at 150%) */
+    scale_factor: number;
+    /** Whether the window is maximized */
+    is_maximized: boolean;
+    /** Whether the window covers its whole monitor */
+    is_fullscreen: boolean;
 }
 
 /**
@@ -95,6 +105,16 @@ export async function captureWindowPreview(): Promise<string | null> {
     }
 }
 
+/**
+ * Capture a small preview of any window, e.g. each candidate in the window picker.
+ * @param identifier - Window title or "title (PID: n)", as listed by listGameWindows
+ * @returns Base64-encoded PNG string
+ * @throws Error with code 'window_gone' if the window closed since it was listed
+ */
+export async function captureWindowPreviewFor(identifier: string): Promise<string> {
+    return await invoke<string>('capture_window_preview_for', { identifier });
+}
+
 /**
  * Get the currently configured game process identifier.
  * @returns The stored process identifier, or null if not set
@@ -123,3 +143,1594 @@ export async function setGameProcessName(processName: string): Promise<void> {
     }
 }
 
+/**
+ * Status of the local stream overlay server.
+ */
+export interface OverlayServerStatus {
+    running: boolean;
+    port: number | null;
+    /** Base URL for OBS browser sources (/session for JSON, /events for SSE) */
+    url: string | null;
+}
+
+/**
+ * Start the local overlay server (bound to 127.0.0.1).
+ * @param port - Port to listen on; defaults to the overlayServerPort setting
+ * @returns The server status
+ * @throws Error if the port can't be bound
+ */
+export async function startOverlayServer(port?: number): Promise<OverlayServerStatus> {
+    return await invoke<OverlayServerStatus>('start_overlay_server', { port });
+}
+
+/**
+ * Stop the local overlay server if it's running.
+ */
+export async function stopOverlayServer(): Promise<void> {
+    await invoke('stop_overlay_server');
+}
+
+/**
+ * Get the local overlay server status.
+ */
+export async function getOverlayServerStatus(): Promise<OverlayServerStatus> {
+    return await invoke<OverlayServerStatus>('get_overlay_server_status');
+}
+
+/**
+ * How the Slippi folder is being watched.
+ */
+export interface WatcherStatus {
+    watching: boolean;
+    path: string | null;
+    /** 'polling' once the platform watcher stopped delivering events (network drives, OneDrive) */
+    backend: 'native' | 'polling' | null;
+    /** Set while polling */
+    pollIntervalMs: number | null;
+    /** Why the platform watcher was given up on */
+    fallbackReason: string | null;
+}
+
+/**
+ * Get the file watcher's status, for support.
+ */
+export async function getWatcherStatus(): Promise<WatcherStatus> {
+    return await invoke<WatcherStatus>('get_watcher_status');
+}
+
+/**
+ * Connect to OBS with the saved obs-websocket settings.
+ * @returns The OBS version
+ * @throws Error with an actionable message if OBS can't be reached or rejects the password
+ */
+export async function testObsConnection(): Promise<string> {
+    return await invoke<string>('test_obs_connection');
+}
+
+/**
+ * Result of a test recording.
+ */
+export interface TestCaptureResult {
+    /** The window or monitor that was captured */
+    target: string;
+    width: number;
+    height: number;
+    durationSecs: number;
+    /** Base64 JPEG of the first frame */
+    thumbnail: string | null;
+}
+
+/**
+ * Record two seconds exactly the way auto-recording would, then delete the file.
+ * @returns What was captured, with a thumbnail of the first frame
+ * @throws Error if nothing could be captured
+ */
+export async function testCapture(): Promise<TestCaptureResult> {
+    return await invoke<TestCaptureResult>('test_capture');
+}
+
+/** A recording as cached in the database (snake_case, as stored) */
+export interface CachedRecording {
+    id: string;
+    video_path: string | null;
+    slp_path: string | null;
+    file_size: number | null;
+    file_modified_at: string | null;
+    thumbnail_path: string | null;
+    start_time: string | null;
+    cached_at: string;
+    needs_reparse: boolean;
+    video_width: number | null;
+    video_height: number | null;
+    video_duration_secs: number | null;
+    has_audio: boolean | null;
+    audio_mean_volume_db: number | null;
+}
+
+/** A cached recording with its game and player stats (the stats rows as stored) */
+export interface RecordingWithStats {
+    recording: CachedRecording;
+    stats: Record<string, unknown> | null;
+    player_stats: Record<string, unknown>[];
+}
+
+/**
+ * Re-read one recording from disk after its video was replaced outside the app
+ * (file size, linked replay, thumbnail, video info) without a full sync.
+ * @param recordingId - Recording to refresh
+ * @param reparse - Also extract its replay's stats again on the next stats pass
+ * @returns The refreshed recording
+ * @throws Error if the recording or its video doesn't exist
+ */
+export async function refreshRecording(recordingId: string, reparse = false): Promise<RecordingWithStats> {
+    return await invoke<RecordingWithStats>('refresh_recording', { recordingId, reparse });
+}
+
+/**
+ * Export a recording with its replay, stats and clip list as a zip bundle.
+ * @param recordingId - Recording to export
+ * @param destPath - Where to write the .zip
+ * @param includeClips - Also include the clips cut from this game (default true)
+ */
+export async function exportRecordingBundle(recordingId: string, destPath: string, includeClips = true): Promise<void> {
+    await invoke('export_recording_bundle', { recordingId, destPath, includeClips });
+}
+
+/** A finished frame data export */
+export interface FrameExport {
+    path: string;
+    rows: number;
+}
+
+/**
+ * Export a recording's per-frame positions, percents, states and inputs next to its video,
+ * one row per (frame, port), as Parquet or gzipped CSV.
+ * @param recordingId - Recording to export
+ * @param format - 'parquet' or 'csv'
+ * @param ports - Ports to keep, 0-indexed (default all)
+ * @param everyNth - Keep only every Nth frame (default every frame)
+ * @throws Error if the recording has no replay
+ */
+export async function exportFrames(
+    recordingId: string,
+    format: 'parquet' | 'csv',
+    ports?: number[],
+    everyNth?: number
+): Promise<FrameExport> {
+    return await invoke<FrameExport>('export_frames', { recordingId, format, ports, everyNth });
+}
+
+/** An anonymized copy of a replay in the temp folder, waiting to be uploaded */
+export interface AnonymizedReplay {
+    path: string;
+    /** Name to upload it under (never the original's) */
+    fileName: string;
+    size: number;
+}
+
+/**
+ * Write a copy of a recording's replay with the players' names and connect codes replaced
+ * by placeholders ("Player 1", "PLYR#1"). Frame data is untouched. Delete it with
+ * delete_temp_file once it's uploaded.
+ * @param recordingId - Recording whose replay to copy
+ * @throws Error if the recording has no replay or its metadata can't be read
+ */
+export async function exportAnonymizedReplay(recordingId: string): Promise<AnonymizedReplay> {
+    return await invoke<AnonymizedReplay>('export_anonymized_replay', { recordingId });
+}
+
+/** A crop in source pixels */
+export interface EditCrop {
+    x: number;
+    y: number;
+    width: number;
+    height: number;
+}
+
+/** A clip made from a recording, as sent with 'clips-created' */
+export interface ClipResult {
+    path: string;
+    /** Video the clip was cut from */
+    sourceRecording: string;
+    /** Marker the clip was made for (null when not made from a marker) */
+    markerTimestamp: number | null;
+    /** Seconds into the source the clip starts */
+    startOffset: number;
+    duration: number;
+    thumbnailPath: string | null;
+}
+
+/** Payload of 'clips-rotated', sent after the Clips folder cap deleted old clips */
+export interface ClipsRotated {
+    deleted: number;
+    failed: number;
+    freedBytes: number;
+    /** Size of the Clips folder afterwards (still over the cap if only starred clips are left) */
+    clipsBytes: number;
+    capBytes: number;
+}
+
+/** Disk usage of the library */
+export interface StorageReport {
+    /** Videos outside the Clips folder */
+    recordingsBytes: number;
+    recordingCount: number;
+    /** Everything in the Clips folder, thumbnails included */
+    clipsBytes: number;
+    clipCount: number;
+    favoriteClipCount: number;
+    /** The maxClipsSizeGb cap in bytes, null without one */
+    clipsCapBytes: number | null;
+    /** Free space on the recordings drive */
+    freeBytes: number | null;
+}
+
+/**
+ * How much space recordings and clips take, against the Clips folder cap.
+ */
+export async function getStorageReport(): Promise<StorageReport> {
+    return await invoke<StorageReport>('get_storage_report');
+}
+
+/**
+ * Cut a clip around each marker placed while recording.
+ * @param recordingFile - Video the markers were placed in
+ * @returns The clips made
+ */
+export async function processClipMarkers(recordingFile: string): Promise<ClipResult[]> {
+    return await invoke<ClipResult[]>('process_clip_markers', { recordingFile });
+}
+
+/** A clip after renameClip or setClipFavorite, also emitted as 'clip-updated' */
+export interface ClipUpdated {
+    id: string;
+    path: string;
+    /** Where the clip was before a rename (the same as path otherwise) */
+    previousPath: string;
+    thumbnailPath: string | null;
+    isFavorite: boolean;
+}
+
+/** Payload of 'clip-deleted' */
+export interface ClipDeleted {
+    id: string;
+    path: string;
+}
+
+/**
+ * Delete a clip with its thumbnail and library entry.
+ * Pending uploads of it are cancelled.
+ * @param id - Clip (recording) id
+ */
+export async function deleteClip(id: string): Promise<void> {
+    await invoke('delete_clip', { id });
+}
+
+/**
+ * Rename a clip's video and thumbnail, keeping the extension.
+ * Fails if the name isn't a valid file name on every platform or is taken.
+ * @param id - Clip (recording) id
+ * @param newName - File name without extension
+ */
+export async function renameClip(id: string, newName: string): Promise<ClipUpdated> {
+    return await invoke<ClipUpdated>('rename_clip', { id, newName });
+}
+
+/**
+ * Star or unstar a clip. Starred clips are kept over the Clips folder limit.
+ * @param id - Clip (recording) id
+ */
+export async function setClipFavorite(id: string, favorite: boolean): Promise<ClipUpdated> {
+    return await invoke<ClipUpdated>('set_clip_favorite', { id, favorite });
+}
+
+/** Progress of editRecording, emitted as 'clip-edit-progress' (batched into arrays, oldest first) */
+export interface EditProgress {
+    sourceRecordingId: string;
+    outputPath: string;
+    /** 0 to 1 */
+    progress: number;
+}
+
+/** The recording or clip made by editRecording */
+export interface EditedRecording {
+    id: string;
+    videoPath: string;
+}
+
+/**
+ * Trim and/or crop a library recording into a new file linked back to it.
+ * @param source - Recording id or video path
+ * @param output - 'clip' for a new clip, 'recording' for a new recording of the same game
+ * @param trimStart - Seconds to start at (default the start)
+ * @param trimEnd - Seconds to end at (default the end)
+ * @param crop - Region to keep (default the whole frame)
+ * @param renderInputOverlay - Draw a player's controller inputs from the replay in the corner
+ *   (skipped for recordings without a replay)
+ * @param overlayPort - Port (0-indexed) whose inputs to draw (default the user's)
+ * @throws Error if the trim or crop doesn't fit the video
+ */
+export async function editRecording(
+    source: string,
+    output: 'clip' | 'recording',
+    trimStart?: number,
+    trimEnd?: number,
+    crop?: EditCrop,
+    renderInputOverlay?: boolean,
+    overlayPort?: number
+): Promise<EditedRecording> {
+    return await invoke<EditedRecording>('edit_recording', {
+        source,
+        trimStart,
+        trimEnd,
+        crop,
+        output,
+        renderInputOverlay,
+        overlayPort
+    });
+}
+
+/** What ffprobe reads from a video file. Fields it couldn't read are null */
+export interface MediaInfo {
+    /** e.g. "mov,mp4,m4a,3gp,3g2,mj2" */
+    container: string;
+    /** 0 if unknown */
+    durationSecs: number;
+    videoCodec: string | null;
+    width: number | null;
+    height: number | null;
+    fps: number | null;
+    /** Bits per second, whole file */
+    bitrate: number | null;
+    audioCodec: string | null;
+    audioChannels: number | null;
+    hasAudio: boolean;
+}
+
+/**
+ * Read a video file's container, streams and length.
+ * @param path - Video file
+ * @throws Error (code media_unreadable) if the file is corrupt or not a video
+ */
+export async function getMediaInfo(path: string): Promise<MediaInfo> {
+    return await invoke<MediaInfo>('get_media_info', { path });
+}
+
+/** Audio formats exportAudio writes ('aac' is saved as .m4a) */
+export type AudioFormat = 'mp3' | 'aac' | 'wav';
+
+/** An audio file written by exportAudio */
+export interface ExportedAudio {
+    path: string;
+    durationSecs: number;
+}
+
+/**
+ * Save the audio of a clip or recording to a file next to it.
+ * Audio already in the chosen format is copied without re-encoding.
+ * @param inputPath - Video file
+ * @param format - Format to write
+ * @param startTime - Seconds to start at (default the start)
+ * @param endTime - Seconds to end at (default the end)
+ * @throws Error (code no_audio) if the video has no audio
+ */
+export async function exportAudio(
+    inputPath: string,
+    format: AudioFormat,
+    startTime?: number,
+    endTime?: number
+): Promise<ExportedAudio> {
+    return await invoke<ExportedAudio>('export_audio', { inputPath, format, startTime, endTime });
+}
+
+/** Where import_external_stats reads from */
+export type ExternalStatsSource = 'slippiLauncher' | 'statsJson';
+
+/** Games an external stats import went through */
+export interface ExternalImportReport {
+    imported: number;
+    /** Already imported from the same source, or unreadable */
+    skipped: number;
+    /** Already had stats from elsewhere, which were kept */
+    conflicting: number;
+}
+
+/**
+ * Import stats computed by another tool, without parsing the replays again.
+ * @param source - 'slippiLauncher' for its replay database file, 'statsJson' for a folder of slippi-js getStats() dumps
+ * @param path - The database file or the folder
+ */
+export async function importExternalStats(source: ExternalStatsSource, path: string): Promise<ExternalImportReport> {
+    return await invoke<ExternalImportReport>('import_external_stats', { source, path });
+}
+
+/**
+ * Import a recording bundle into the library.
+ * @param bundlePath - The .zip made by exportRecordingBundle
+ * @returns The id of the imported recording
+ * @throws Error if the bundle is invalid or the game is already in the library
+ */
+export async function importRecordingBundle(bundlePath: string): Promise<string> {
+    return await invoke<string>('import_recording_bundle', { bundlePath });
+}
+
+/**
+ * A recording old enough to archive.
+ */
+export interface ArchiveCandidate {
+    recordingId: string;
+    videoPath: string;
+    fileSize: number | null;
+    startTime: string | null;
+}
+
+/**
+ * Result of archiving one recording. On error the original is untouched.
+ */
+export interface ArchivedFile {
+    recordingId: string;
+    videoPath: string;
+    originalSize: number;
+    archivedSize: number;
+    freedBytes: number;
+    error: string | null;
+}
+
+export interface ArchiveReport {
+    files: ArchivedFile[];
+    freedBytes: number;
+}
+
+/**
+ * List recordings that haven't been archived yet. Clips are never listed.
+ * @param olderThanDays - Minimum age of the recordings in days
+ */
+export async function listArchiveCandidates(olderThanDays: number): Promise<ArchiveCandidate[]> {
+    return await invoke<ArchiveCandidate[]>('list_archive_candidates', { olderThanDays });
+}
+
+/**
+ * Re-encode recordings to 720p HEVC, replacing each original once the new
+ * file is verified to play.
+ * @param recordingIds - Recordings to archive
+ * @returns Space freed per file and in total
+ */
+export async function archiveRecordings(recordingIds: string[]): Promise<ArchiveReport> {
+    return await invoke<ArchiveReport>('archive_recordings', { recordingIds });
+}
+
+/**
+ * A scored combo, with the part of the video to clip.
+ */
+export interface Highlight {
+    comboId: number;
+    recordingId: string;
+    videoPath: string;
+    slpPath: string | null;
+    /** Player who performed the combo */
+    playerIndex: number;
+    connectCode: string | null;
+    characterId: number;
+    opponentCharacterId: number | null;
+    stage: number | null;
+    playedAt: string | null;
+    startFrame: number;
+    endFrame: number;
+    startPercent: number;
+    endPercent: number;
+    moveCount: number;
+    didKill: boolean;
+    highlightScore: number;
+    /** Clip already made from this highlight */
+    clipPath: string | null;
+    /** Where the game starts in the video (later games of a per-set recording) */
+    videoOffsetSecs: number;
+    clipStartSecs: number;
+    clipEndSecs: number;
+}
+
+/**
+ * Get the best highlights across the library.
+ * @param connectCode - Whose combos; defaults to mine
+ * @param limit - How many (default 10)
+ */
+export async function getTopHighlights(connectCode?: string, limit?: number): Promise<Highlight[]> {
+    return await invoke<Highlight[]>('get_top_highlights', { connectCode, limit });
+}
+
+/** Controller input quality on one day */
+export interface InputQualityPoint {
+    /** YYYY-MM-DD */
+    date: string;
+    games: number;
+    dashbackSuccessRate: number | null;
+    pivotSuccessRate: number | null;
+    snapbacksPerGame: number | null;
+}
+
+/** Controller input quality over a set of games */
+export interface InputQuality {
+    games: number;
+    dashbackAttempts: number;
+    dashbackSuccesses: number;
+    pivotAttempts: number;
+    pivotSuccesses: number;
+    /** Pivots not followed by an attack or grab */
+    emptyPivots: number;
+    /** Suspected stick snapback */
+    snapbacks: number;
+    dashbackSuccessRate: number | null;
+    pivotSuccessRate: number | null;
+    snapbacksPerGame: number | null;
+    /** One point per day played, oldest first */
+    trend: InputQualityPoint[];
+}
+
+/**
+ * Get dashback, pivot and snapback counts from the analyzed replays.
+ * @param connectCode - Whose inputs; defaults to mine
+ * @param filter - Same filter as the stats page; its date range bounds the trend,
+ *   whose days are in its utcOffsetMinutes time zone (UTC without one)
+ */
+export async function getInputQuality(connectCode?: string, filter?: Record<string, unknown>): Promise<InputQuality> {
+    return await invoke<InputQuality>('get_input_quality', { connectCode, filter });
+}
+
+/** One side of compareStats */
+export interface StatsSubject {
+    /** Defaults to mine */
+    connectCode?: string;
+    /** Same filter as the stats page */
+    filter?: Record<string, unknown>;
+}
+
+/** One metric on both sides; null where a side has no games (or, for percentChange, first is 0) */
+export interface MetricComparison {
+    /** e.g. "winRate", "avgLCancelPercent" */
+    metric: string;
+    first: number | null;
+    second: number | null;
+    /** second - first */
+    delta: number | null;
+    percentChange: number | null;
+}
+
+export interface StatsComparison {
+    /** The stats page's totals, null when the side has no games */
+    first: Record<string, unknown> | null;
+    second: Record<string, unknown> | null;
+    metrics: MetricComparison[];
+}
+
+/**
+ * Two players' stats side by side, or one player's over two periods.
+ * Changes go from the first subject to the second.
+ */
+export async function compareStats(first: StatsSubject, second: StatsSubject): Promise<StatsComparison> {
+    return await invoke<StatsComparison>('compare_stats', { first, second });
+}
+
+/** The streak the most recent games are on */
+export interface CurrentStreak {
+    /** "unknown" when there are no games or the latest has no known result */
+    result: "win" | "loss" | "unknown";
+    length: number;
+    /** The game without a known result that ended the streak, if that's what did */
+    unknownGame: string | null;
+}
+
+/** Defaults to my streak */
+export async function getCurrentStreak(connectCode?: string): Promise<CurrentStreak> {
+    return await invoke<CurrentStreak>('get_current_streak', { connectCode });
+}
+
+/** Games as one character; wins and win rate by the character played most */
+export interface CharacterUsage {
+    characterId: number;
+    games: number;
+    wins: number;
+    /** Null without games that had a winner */
+    winRate: number | null;
+}
+
+/** One day, week (from Monday) or month of the character usage timeline */
+export interface CharacterUsagePeriod {
+    /** First day of the period (YYYY-MM-DD) */
+    start: string;
+    games: number;
+    /** Most played first */
+    characters: CharacterUsage[];
+}
+
+/** When a character was first and last played, and its record */
+export interface CharacterPlayedRange {
+    characterId: number;
+    games: number;
+    wins: number;
+    firstPlayed: string | null;
+    lastPlayed: string | null;
+}
+
+/**
+ * Get which characters were played in each period, oldest first. Zelda and
+ * Sheik games count as whichever was played for most of the game.
+ * @param connectCode - Whose games; defaults to mine
+ * @param bucket - Length of each period (default month)
+ * @param utcOffsetMinutes - Time zone the periods are in, minutes east of UTC (default this machine's)
+ */
+export async function getCharacterUsage(
+    connectCode?: string,
+    bucket?: 'day' | 'week' | 'month',
+    utcOffsetMinutes = -new Date().getTimezoneOffset()
+): Promise<CharacterUsagePeriod[]> {
+    return await invoke<CharacterUsagePeriod[]>('get_character_usage', { connectCode, bucket, utcOffsetMinutes });
+}
+
+/**
+ * Get when a character was first and last played. Null if it never was.
+ * @param characterId - External character ID
+ * @param connectCode - Whose games; defaults to mine
+ */
+export async function getFirstAndLastPlayed(
+    characterId: number,
+    connectCode?: string
+): Promise<CharacterPlayedRange | null> {
+    return await invoke<CharacterPlayedRange | null>('get_first_and_last_played', { characterId, connectCode });
+}
+
+/**
+ * Work out every saved game's winner and loser again from its players' stats,
+ * keeping them by connect code (ports can change between games of a set).
+ * @returns How many games' results changed
+ */
+export async function repairGameResults(): Promise<number> {
+    return await invoke<number>('repair_game_results');
+}
+
+/** A player seen in stats, under the code their merged codes count as */
+export interface KnownPlayer {
+    connectCode: string;
+    /** This code and those merged into it */
+    codes: string[];
+    /** Most recently seen first */
+    displayNames: string[];
+    firstSeen: string | null;
+    lastSeen: string | null;
+}
+
+/**
+ * Find players seen in stats for autocomplete, most recently seen first.
+ * @param search - Matches any of a player's codes or display names, ignoring case
+ * @param limit - At most this many (default 20)
+ */
+export async function getKnownPlayers(search?: string, limit?: number): Promise<KnownPlayer[]> {
+    return await invoke<KnownPlayer[]>('get_known_players', { search, limit });
+}
+
+/**
+ * Declare that two connect codes belong to the same person; stats for
+ * `intoCode` then include the other code's games.
+ * @param connectCode - The code to merge (e.g. an alt account)
+ * @param intoCode - The code it counts as
+ */
+export async function mergePlayers(connectCode: string, intoCode: string): Promise<void> {
+    await invoke('merge_players', { connectCode, intoCode });
+}
+
+/** Record in one matchup of the play session */
+export interface SessionMatchup {
+    myCharacterId: number;
+    opponentCharacterId: number | null;
+    games: number;
+    wins: number;
+    losses: number;
+}
+
+/** A stat this session against my 30-day average */
+export interface SessionStatDelta {
+    stat: "lCancelPercent" | "openingsPerKill" | "damagePerOpening" | "neutralWinPercent" | "inputsPerMinute";
+    session: number | null;
+    baseline: number | null;
+    /** session - baseline, when both are known */
+    delta: number | null;
+}
+
+/** Games since watching began. Also the payload of session-updated */
+export interface SessionSummary {
+    startedAt: string;
+    gamesPlayed: number;
+    wins: number;
+    losses: number;
+    /** Most played first */
+    matchups: SessionMatchup[];
+    deltas: SessionStatDelta[];
+    /** Games the 30-day averages cover */
+    baselineGames: number;
+}
+
+/**
+ * Get the play session (games since watching began).
+ * @returns null while not watching for games
+ */
+export async function getCurrentSession(): Promise<SessionSummary | null> {
+    return await invoke<SessionSummary | null>('get_current_session');
+}
+
+/** What the input analysis counts for one player in one game */
+export type InputQualityCounts = Pick<
+    InputQuality,
+    'dashbackAttempts' | 'dashbackSuccesses' | 'pivotAttempts' | 'pivotSuccesses' | 'emptyPivots' | 'snapbacks'
+>;
+
+/** A player slot from a replay's Game Start */
+export interface GameStartPlayer {
+    /** 0-indexed */
+    port: number;
+    characterId: number;
+    connectCode: string | null;
+    isCpu: boolean;
+}
+
+/** One player at one point of a game (every half second) */
+export interface TimelineSample {
+    frame: number;
+    /** 0-indexed */
+    port: number;
+    x: number;
+    y: number;
+    percent: number;
+    stocks: number;
+}
+
+/** What the backend reads from a replay analyzed outside the library */
+export interface ReplayAnalysis {
+    gameStart: {
+        version: string;
+        stage: number;
+        isTeams: boolean;
+        players: GameStartPlayer[];
+        matchId: string | null;
+    };
+    timeline: TimelineSample[];
+    /** Keyed by 0-indexed port */
+    inputs: Record<number, InputQualityCounts>;
+}
+
+/**
+ * Check and read a .slp that isn't in the library, without saving anything.
+ * Use analyzeReplayFile (services/game-analysis) for the slippi-js stats too.
+ * @param slpPath - Path to the .slp file
+ * @throws Error if the file is too large, isn't a readable replay, or takes too long to read
+ */
+export async function analyzeReplay(slpPath: string): Promise<ReplayAnalysis> {
+    return await invoke<ReplayAnalysis>('analyze_replay', { slpPath });
+}
+
+/**
+ * Clip the best highlights of the last day into the Clips folder.
+ * @param count - How many highlights to clip
+ * @returns Paths of the new clips
+ */
+export async function generateHighlights(count?: number): Promise<string[]> {
+    return await invoke<string[]>('generate_highlights', { count });
+}
+
+/** A week's digest (see generateWeeklyReport) */
+export interface WeeklyReport {
+    /** The Monday the week starts on (YYYY-MM-DD) */
+    weekStart: string;
+    /** The Monday after it */
+    weekEnd: string;
+    gamesPlayed: number;
+    wins: number;
+    /** Games I didn't win (including ones without a winner) */
+    losses: number;
+    /** null without games */
+    winRate: number | null;
+    /** Biggest improvements on the week before, best first */
+    improvements: MetricComparison[];
+    /** Best unclipped highlights of the week */
+    clipCandidates: Highlight[];
+}
+
+/** A saved weekly report */
+export interface WeeklyReportRecord {
+    id: number;
+    kind: 'weekly';
+    periodStart: string;
+    periodEnd: string;
+    payload: WeeklyReport;
+    /** Shareable HTML card, if one was rendered */
+    cardPath: string | null;
+    createdAt: string;
+}
+
+/**
+ * Summarize a week and save it (replacing that week's report).
+ * Also sent with the "weekly-report-generated" event when autoWeeklyReport generates one on start.
+ * @param weekOffset - 0 (default) for the last completed week, 1 for the one before, ...
+ * @param renderCard - Also write a shareable HTML card
+ */
+export async function generateWeeklyReport(weekOffset?: number, renderCard?: boolean): Promise<WeeklyReportRecord> {
+    return await invoke<WeeklyReportRecord>('generate_weekly_report', { weekOffset, renderCard });
+}
+
+/**
+ * Saved weekly reports, latest week first.
+ * @param limit - Most reports to return (52 by default)
+ */
+export async function listWeeklyReports(limit?: number): Promise<WeeklyReportRecord[]> {
+    return await invoke<WeeklyReportRecord[]>('list_weekly_reports', { limit });
+}
+
+/**
+ * A game within a recording, and where it starts in the video.
+ */
+export interface RecordingGame {
+    gameIndex: number;
+    slpPath: string;
+    offsetSecs: number;
+}
+
+/**
+ * Get the games in a recording: one per game of a per-set recording, or just its replay at 0.
+ * @param recordingId - Recording to look up
+ */
+export async function getRecordingGames(recordingId: string): Promise<RecordingGame[]> {
+    return await invoke<RecordingGame[]>('get_recording_games', { recordingId });
+}
+
+/**
+ * Correct how far a recording's video trails its replay, when the measured offset is off.
+ * @param recordingId - Recording to correct
+ * @param offsetMs - Milliseconds the video trails the replay (negative if it leads)
+ */
+export async function calibrateOffset(recordingId: string, offsetMs: number): Promise<void> {
+    await invoke('calibrate_offset', { recordingId, offsetMs });
+}
+
+/**
+ * Open a recording's replay in Slippi's playback Dolphin.
+ * @param recordingId - Recording whose .slp to play
+ * @param startFrame - Slippi frame to start from (the game starts on frame -123)
+ * @param endFrame - Slippi frame to stop at
+ * @param gameIndex - Game of a per-set recording (defaults to the first)
+ * @throws Error if a recording is in progress, or playback Dolphin or the ISO can't be found
+ */
+export async function launchReplay(
+    recordingId: string,
+    startFrame?: number,
+    endFrame?: number,
+    gameIndex?: number,
+): Promise<void> {
+    await invoke('launch_replay', { recordingId, gameIndex, startFrame, endFrame });
+}
+
+/** A frame shown by the playback review's Dolphin, emitted as 'playback-frame' (batched into arrays, oldest first) */
+export interface PlaybackFrame {
+    recordingId: string;
+    /** Slippi frame number (the game starts on frame -123) */
+    frame: number;
+    /** Where the frame is in the recording's video, in seconds */
+    videoTime: number;
+}
+
+/**
+ * Open a recording's replay in playback Dolphin and follow it: 'playback-frame' events report each
+ * frame shown until Dolphin closes or the review is stopped, then 'playback-review-ended' is emitted.
+ * Replaces a review already running.
+ * @param recordingId - Recording whose .slp to play
+ * @param startFrame - Slippi frame to start from (the game starts on frame -123)
+ * @param gameIndex - Game of a per-set recording (defaults to the first)
+ * @throws Error if a recording is in progress, or playback Dolphin or the ISO can't be found
+ */
+export async function startPlaybackReview(recordingId: string, startFrame?: number, gameIndex?: number): Promise<void> {
+    await invoke('start_playback_review', { recordingId, gameIndex, startFrame });
+}
+
+/** Stop the playback review, closing its Dolphin. */
+export async function stopPlaybackReview(): Promise<void> {
+    await invoke('stop_playback_review');
+}
+
+/**
+ * Apply the saved Discord Rich Presence settings (call after changing them).
+ */
+export async function applyDiscordPresenceSettings(): Promise<void> {
+    await invoke('apply_discord_presence_settings');
+}
+
+/** Rules a new replay must pass to be auto-recorded (see set_watch_filters) */
+export interface WatchFilters {
+    /** File name patterns (`*` and `?`, any case); with a slash, matched against the whole path */
+    ignorePatterns: string[];
+    /** Seconds after an auto-recording starts before another may (0: no limit) */
+    minTriggerIntervalSeconds: number;
+    /** 1-4, CPUs included */
+    minPlayers: number | null;
+    maxPlayers: number | null;
+    skipTeams: boolean;
+    connection: 'any' | 'online' | 'offline';
+}
+
+/**
+ * Save which new replays start an auto-recording. Applies from the next game.
+ * @returns The rules as saved (trimmed, without empty or repeated patterns)
+ * @throws Error if a value is out of range
+ */
+export async function setWatchFilters(filters: WatchFilters): Promise<WatchFilters> {
+    return await invoke<WatchFilters>('set_watch_filters', { filters });
+}
+
+/** Background work held back while a game is being recorded */
+export type JobKind = 'thumbnails' | 'stats' | 'clips' | 'mediaProbe';
+
+/**
+ * Apply jobsWhileRecording to the background job gate (call after changing it).
+ */
+export async function applyWorkGateSettings(): Promise<void> {
+    await invoke('apply_work_gate_settings');
+}
+
+/**
+ * Apply hardwareDecode to FFmpeg (call after changing it).
+ */
+export async function applyHardwareDecodeSetting(): Promise<void> {
+    await invoke('apply_hardware_decode_setting');
+}
+
+/**
+ * Wait until a background job may run: while recording, until the recording
+ * stops (unless the kind is in jobsWhileRecording).
+ * @returns Whether it runs alongside a recording
+ */
+export async function waitForJobSlot(kind: JobKind): Promise<boolean> {
+    return await invoke<boolean>('wait_for_job_slot', { kind });
+}
+
+/**
+ * Apply minGameSecondsForStats, recordCpuMatches, countUnfinishedGames and the warm-up settings to every stored game (call after changing them).
+ * @returns How many games are left out of stats
+ */
+export async function applyStatsExclusionSettings(): Promise<number> {
+    return await invoke<number>('apply_stats_exclusion_settings');
+}
+
+/**
+ * Mark a game as a warm-up or not by hand.
+ * @param isWarmup - null to go back to the one worked out from its session
+ */
+export async function setWarmup(recordingId: string, isWarmup: boolean | null): Promise<void> {
+    await invoke('set_warmup', { recordingId, isWarmup });
+}
+
+/**
+ * Launch the app on system startup (with --minimized, honoured when startMinimized is on).
+ */
+export async function enableAutostart(): Promise<void> {
+    await invoke('enable_autostart');
+}
+
+/**
+ * Stop launching the app on system startup.
+ */
+export async function disableAutostart(): Promise<void> {
+    await invoke('disable_autostart');
+}
+
+/**
+ * Whether the OS will launch the app on startup (the actual entry, not the stored preference).
+ */
+export async function getAutostartStatus(): Promise<boolean> {
+    return await invoke<boolean>('get_autostart_status');
+}
+
+/**
+ * Where a buckwheat:// link asked the app to navigate.
+ */
+export interface DeepLinkTarget {
+    recordingId: string;
+    isClip: boolean;
+}
+
+/**
+ * Take the target of the last buckwheat:// link (cleared once taken).
+ * Check on startup and whenever a `deep-link-navigate` event arrives.
+ */
+export async function takePendingDeepLink(): Promise<DeepLinkTarget | null> {
+    return await invoke<DeepLinkTarget | null>('take_pending_deep_link');
+}
+
+/**
+ * What recovery found after the last session ended while recording.
+ */
+export interface RecoveryReport {
+    /** When the interrupted recording started */
+    startedAt: string;
+    /** Files repaired and added to the library */
+    recovered: string[];
+    unrecoverable: { path: string; error: string }[];
+}
+
+/**
+ * Take the last recovery report (cleared once taken).
+ * Check on startup and whenever a `recording-recovery-finished` event arrives.
+ */
+export async function takeRecoveryReport(): Promise<RecoveryReport | null> {
+    return await invoke<RecoveryReport | null>('take_recovery_report');
+}
+
+/**
+ * Get the last lines of the app log, oldest first (secrets and connect codes redacted).
+ * @param lines - How many lines to return (default 200, max 5000)
+ */
+export async function getRecentLogs(lines?: number): Promise<string[]> {
+    return await invoke<string[]>('get_recent_logs', { lines });
+}
+
+/**
+ * Zip the recent log files, redacted settings and system info into a file for bug reports.
+ * @param destPath - Where to write the .zip
+ */
+export async function exportDiagnostics(destPath: string): Promise<void> {
+    await invoke('export_diagnostics', { destPath });
+}
+
+/** Background subsystems tracked by get_health */
+export type Subsystem = 'watcher' | 'recorder' | 'sync' | 'stats';
+
+/**
+ * Health of one background subsystem.
+ */
+export interface SubsystemHealth {
+    subsystem: Subsystem;
+    status: 'idle' | 'running' | 'failed' | 'restarting';
+    /** Last panic message (kept after the subsystem recovers) */
+    lastError: string | null;
+    failedAt: string | null;
+    /** Automatic restarts since the app started */
+    restarts: number;
+}
+
+/** Why a folder from settings can't be used */
+export type PathProblem = 'volume_not_mounted' | 'not_writable' | 'not_a_directory';
+
+/**
+ * Payload of the `settings-path-unavailable` event, sent when the recording or
+ * Slippi folder can't be used (e.g. it's on an unplugged drive).
+ */
+export interface SettingsPathUnavailable {
+    /** Settings key naming the folder, e.g. 'recordingPath' */
+    setting: string;
+    path: string;
+    problem: PathProblem;
+}
+
+/**
+ * Payload of the `background-task-failed` event.
+ */
+export interface BackgroundTaskFailed {
+    subsystem: Subsystem;
+    message: string;
+    /** Whether the app will restart it automatically */
+    restarting: boolean;
+}
+
+/**
+ * Get the health of the watcher, recorder, sync and stats subsystems.
+ */
+export async function getHealth(): Promise<SubsystemHealth[]> {
+    return await invoke<SubsystemHealth[]>('get_health');
+}
+
+/**
+ * Timings of one command or background job since the app started.
+ */
+export interface MetricSummary {
+    name: string;
+    kind: 'command' | 'job';
+    count: number;
+    failures: number;
+    /** Latency percentiles over the last 512 runs */
+    p50Ms: number;
+    p95Ms: number;
+    maxMs: number;
+    totalMs: number;
+}
+
+/**
+ * Get the timings of every command and background job, slowest total first.
+ */
+export async function getCommandMetrics(): Promise<MetricSummary[]> {
+    return await invoke<MetricSummary[]>('get_command_metrics');
+}
+
+/**
+ * Version of the stable command payloads (recordings, stats, clips), bumped
+ * whenever one of them changes shape.
+ */
+export interface ApiInfo {
+    apiVersion: number;
+    /** JSON schema (draft 7) of each stable payload, by type name */
+    schemas: Record<string, unknown>;
+}
+
+/**
+ * Get the API version and the schemas of the stable payloads.
+ */
+export async function getApiInfo(): Promise<ApiInfo> {
+    return await invoke<ApiInfo>('get_api_info');
+}
+
+/**
+ * Whether FFmpeg decodes with the GPU.
+ */
+export interface HwAccelStatus {
+    /** The hardwareDecode setting */
+    enabled: boolean;
+    /** GPU decoder found at startup, null if none (or not looked for yet) */
+    detected: string | null;
+    /** It failed to start, so software decoding is used instead */
+    failed: boolean;
+    /** "software" or the decoder in use */
+    active: string;
+}
+
+/**
+ * Get which decoder FFmpeg uses for thumbnails and decode checks.
+ */
+export async function getHwAccelStatus(): Promise<HwAccelStatus> {
+    return await invoke<HwAccelStatus>('get_hwaccel_status');
+}
+
+/** Probes run by run_readiness_check, in report order */
+export type ReadinessCheck = 'slippiFolder' | 'ffmpeg' | 'diskSpace' | 'gameWindow' | 'audioDevice' | 'recorder';
+
+/**
+ * Result of one readiness probe.
+ */
+export interface ReadinessCheckResult {
+    check: ReadinessCheck;
+    /** `skipped` when the check doesn't apply to this platform or setup */
+    status: 'ok' | 'warning' | 'failed' | 'skipped';
+    detail: string;
+    suggestedFix: string | null;
+}
+
+/**
+ * Check the Slippi folder and watcher, FFmpeg, disk space, game window, audio device and recorder.
+ * Each probe is time-limited, so a hung one shows up as failed.
+ * The `readiness-warning` event carries the failed checks when this runs automatically.
+ */
+export async function runReadinessCheck(): Promise<ReadinessCheckResult[]> {
+    return await invoke<ReadinessCheckResult[]>('run_readiness_check');
+}
+
+/**
+ * A background job and the conditions it runs under.
+ */
+export interface ScheduledJob {
+    name: 'sync' | 'ffmpeg' | 'retention' | 'highlights' | 'weekly_report';
+    /** Seconds between runs, null for a job run once after startup */
+    intervalSecs: number | null;
+    constraints: {
+        notWhileRecording: boolean;
+        notOnBattery: boolean;
+        /** Only inside the quietHours setting (any time when it isn't set) */
+        quietHoursOnly: boolean;
+    };
+    running: boolean;
+    /** Condition that held the job back at the last check */
+    blockedBy: 'recording' | 'onBattery' | 'outsideQuietHours' | null;
+    /** When the scheduler next looks at the job (null once a startup job is done) */
+    nextCheckAt: string | null;
+    lastRunAt: string | null;
+    /** Error of the last run, if it failed */
+    lastError: string | null;
+}
+
+/**
+ * Get the background jobs, their conditions and how their last runs went.
+ */
+export async function listScheduledJobs(): Promise<ScheduledJob[]> {
+    return await invoke<ScheduledJob[]>('list_scheduled_jobs');
+}
+
+/**
+ * Start a background job now, whether or not it's due or its conditions hold.
+ * A job that's already running is left to finish.
+ * @throws Error with code 'unknown_job' for a name no job is registered under
+ */
+export async function runJobNow(name: ScheduledJob['name']): Promise<ScheduledJob> {
+    return await invoke<ScheduledJob>('run_job_now', { name });
+}
+
+/**
+ * Problem with one setting in an imported file.
+ */
+export interface SettingsFieldIssue {
+    field: string;
+    message: string;
+}
+
+/**
+ * Outcome of importing a settings file.
+ */
+export interface SettingsImportReport {
+    /** False when any field was invalid; nothing is changed then */
+    applied: boolean;
+    errors: SettingsFieldIssue[];
+    /** Paths missing on this computer, replaced by the default */
+    replacedPaths: SettingsFieldIssue[];
+    /** Settings that weren't imported (unknown, private or machine-specific) */
+    ignored: string[];
+}
+
+/**
+ * Export settings to a JSON file, without passwords, hooks or connect codes.
+ * @param destPath - Where to write the file
+ */
+export async function exportSettings(destPath: string): Promise<void> {
+    return await invoke('export_settings', { destPath });
+}
+
+/**
+ * Import a settings file. Every value is validated first; if any is invalid nothing is changed.
+ * @param path - File to import
+ * @returns Per-field errors, replaced paths and ignored settings
+ */
+export async function importSettings(path: string): Promise<SettingsImportReport> {
+    return await invoke<SettingsImportReport>('import_settings', { path });
+}
+
+/**
+ * Reset all settings to their defaults (connect codes and local identity are kept).
+ */
+export async function resetSettingsToDefaults(): Promise<void> {
+    return await invoke('reset_settings_to_defaults');
+}
+
+/**
+ * Named snapshot of the capture setup (recorder, quality, folders, clip hotkey and game window).
+ */
+export interface SettingsProfile {
+    name: string;
+    createdAt: string;
+    /** Process name of the game window to capture, if one was picked */
+    gameWindow: string | null;
+    settings: Record<string, unknown>;
+}
+
+/**
+ * Saved profiles and the one applied last.
+ */
+export interface SettingsProfileList {
+    profiles: SettingsProfile[];
+    active: string | null;
+}
+
+/**
+ * List saved settings profiles.
+ */
+export async function listProfiles(): Promise<SettingsProfileList> {
+    return await invoke<SettingsProfileList>('list_profiles');
+}
+
+/**
+ * Save the current capture setup as a profile (replacing one with the same name).
+ * @param name - Profile name
+ */
+export async function createProfile(name: string): Promise<SettingsProfile> {
+    return await invoke<SettingsProfile>('create_profile', { name });
+}
+
+/**
+ * Apply a profile. Emits "settings-changed" and moves the watcher to the profile's replay folder.
+ * @param name - Profile name
+ */
+export async function applyProfile(name: string): Promise<SettingsProfile> {
+    return await invoke<SettingsProfile>('apply_profile', { name });
+}
+
+/**
+ * Delete a profile. The settings it applied are left as they are.
+ * @param name - Profile name
+ */
+export async function deleteProfile(name: string): Promise<void> {
+    return await invoke('delete_profile', { name });
+}
+
+/**
+ * What a retention rule does to the recordings it matches. Keep rules take precedence over every other rule.
+ */
+export type RetentionAction = "keep" | "deleteVideo" | "deleteVideoAndReplay" | "compress";
+
+/**
+ * Rule for deleting or compressing recordings. Criteria left null match every recording.
+ */
+export interface RetentionRule {
+    /** null for a rule that hasn't been saved yet */
+    id: number | null;
+    name: string;
+    enabled: boolean;
+    /** In-game tag of any player */
+    tag: string | null;
+    /** Opponent's connect code or in-game tag */
+    opponent: string | null;
+    result: "win" | "loss" | null;
+    /** Only recordings at least this many days old */
+    minAgeDays: number | null;
+    action: RetentionAction;
+}
+
+/**
+ * What a rule did (or would do) to one recording.
+ */
+export interface RetentionOutcome {
+    recordingId: string;
+    videoPath: string;
+    ruleId: number;
+    ruleName: string;
+    action: RetentionAction;
+    /** Bytes freed, estimated on a dry run */
+    freedBytes: number | null;
+    error: string | null;
+}
+
+export interface RetentionReport {
+    dryRun: boolean;
+    outcomes: RetentionOutcome[];
+}
+
+/**
+ * Payload of the "retention-completed" event sent after the weekly run.
+ */
+export interface RetentionSummary {
+    deleted: number;
+    compressed: number;
+    failed: number;
+    freedBytes: number;
+}
+
+/**
+ * List retention rules, oldest first.
+ */
+export async function listRetentionRules(): Promise<RetentionRule[]> {
+    return await invoke<RetentionRule[]>('list_retention_rules');
+}
+
+/**
+ * Create a retention rule, or update it if it has an id.
+ * @param rule - The rule to save
+ * @returns The saved rule (with its id)
+ */
+export async function saveRetentionRule(rule: RetentionRule): Promise<RetentionRule> {
+    return await invoke<RetentionRule>('save_retention_rule', { rule });
+}
+
+/**
+ * Delete a retention rule.
+ * @param id - Rule id
+ */
+export async function deleteRetentionRule(id: number): Promise<void> {
+    return await invoke('delete_retention_rule', { id });
+}
+
+/**
+ * Apply the retention rules now. Clips are never touched.
+ * @param dryRun - Only report what would happen
+ */
+export async function applyRetention(dryRun: boolean): Promise<RetentionReport> {
+    return await invoke<RetentionReport>('apply_retention', { dryRun });
+}
+
+/**
+ * What a recording rule does with the new games it matches. A record rule overrides the
+ * watch filters on the game and the CPU setting, but not the ignore patterns.
+ */
+export type RecordingRuleAction = "record" | "skip" | "recordAtQuality";
+
+/**
+ * Rule for recording or skipping new games by who's in them. Criteria left null match
+ * every game; the first enabled rule that matches (oldest first) decides.
+ */
+export interface RecordingRule {
+    /** null for a rule that hasn't been saved yet */
+    id: number | null;
+    name: string;
+    enabled: boolean;
+    /** Connect code of any opponent */
+    opponentCode: string | null;
+    /** External character ID I play */
+    myCharacter: number | null;
+    /** External character ID of any opponent */
+    opponentCharacter: number | null;
+    connection: 'any' | 'online' | 'offline';
+    action: RecordingRuleAction;
+    /** For recordAtQuality */
+    quality: "low" | "medium" | "high" | "ultra" | null;
+}
+
+/**
+ * Which rule decided a new game. Name and action are as they were then.
+ */
+export interface RecordingRuleLogEntry {
+    slpPath: string;
+    ruleId: number;
+    ruleName: string;
+    action: RecordingRuleAction;
+    quality: "low" | "medium" | "high" | "ultra" | null;
+    createdAt: string;
+}
+
+/**
+ * List recording rules, oldest first.
+ */
+export async function listRecordingRules(): Promise<RecordingRule[]> {
+    return await invoke<RecordingRule[]>('list_recording_rules');
+}
+
+/**
+ * Create a recording rule, or update it if it has an id. Applies from the next game.
+ * @param rule - The rule to save
+ * @returns The saved rule (with its id)
+ */
+export async function saveRecordingRule(rule: RecordingRule): Promise<RecordingRule> {
+    return await invoke<RecordingRule>('save_recording_rule', { rule });
+}
+
+/**
+ * Delete a recording rule. Its log entries are kept.
+ * @param id - Rule id
+ */
+export async function deleteRecordingRule(id: number): Promise<void> {
+    return await invoke('delete_recording_rule', { id });
+}
+
+/**
+ * Which rule decided each recent game, newest first.
+ * @param limit - Most entries to return (100 by default)
+ */
+export async function getRecordingRuleLog(limit?: number): Promise<RecordingRuleLogEntry[]> {
+    return await invoke<RecordingRuleLogEntry[]>('get_recording_rule_log', { limit });
+}
+
+/**
+ * What's known about a game from its replay's Game Start event.
+ */
+export interface GameStart {
+    stage: number;
+    players: { port: number; characterId: number; connectCode: string | null }[];
+}
+
+/**
+ * A game that just started, sent with 'game-started' as soon as its replay's Game Start is written.
+ */
+export interface StartedGame {
+    slpPath: string;
+    /** Id its recording and stats will have, if known */
+    recordingId: string | null;
+    game: GameStart;
+    /** Index in game.players of the local player, if found */
+    me: number | null;
+    /** e.g. "Fox vs Marth", the local player's character first */
+    matchup: string;
+}
+
+/**
+ * A recording whose replay couldn't be parsed. Stats passes skip it until retried.
+ */
+export interface UnparseableReplay {
+    recordingId: string;
+    slpPath: string;
+    /** null for replay-only entries */
+    videoPath: string | null;
+    parseError: "truncated" | "unsupported_version" | "corrupt";
+    /** Stage and players salvaged from the replay, if it got that far */
+    gameStart: GameStart | null;
+}
+
+/**
+ * List recordings whose replays couldn't be parsed.
+ */
+export async function listUnparseableReplays(): Promise<UnparseableReplay[]> {
+    return await invoke<UnparseableReplay[]>('list_unparseable_replays');
+}
+
+/**
+ * Let the next stats pass try unparseable replays again.
+ * @param recordingIds - Which recordings; all of them if omitted
+ * @returns How many were cleared
+ */
+export async function retryUnparseableReplays(recordingIds?: string[]): Promise<number> {
+    return await invoke<number>('retry_unparseable_replays', { recordingIds });
+}
+
+/**
+ * Have a recording's replay parsed again (metadata and stats) on the next
+ * stats pass. A "recording-reparsed" event is emitted once it's saved.
+ * @param recordingId - Recording with a replay
+ */
+export async function setNeedsReparse(recordingId: string): Promise<void> {
+    await invoke('set_needs_reparse', { recordingId });
+}
+
+/**
+ * Flag games whose stats came from an older STATS_VERSION to be reparsed.
+ * @param currentVersion - The current STATS_VERSION
+ * @returns How many were newly flagged
+ */
+export async function flagOutdatedStats(currentVersion: number): Promise<number> {
+    return await invoke<number>('flag_outdated_stats', { currentVersion });
+}
+
+/**
+ * A recording in a duplicate set.
+ */
+export interface DuplicateCandidate {
+    recordingId: string;
+    videoPath: string;
+    slpPath: string | null;
+    fileSize: number | null;
+    startTime: string | null;
+    durationSecs: number | null;
+    width: number | null;
+    height: number | null;
+    isArchived: boolean;
+}
+
+/**
+ * Recordings of the same game: linked to the same replay, or unlinked videos
+ * with the same resolution, length and start time.
+ */
+export interface DuplicateSet {
+    reason: "same_replay" | "similar_video";
+    /** The best quality copy */
+    suggestedKeep: string;
+    recordings: DuplicateCandidate[];
+}
+
+export interface DuplicateResolution {
+    keep: string;
+    delete: string[];
+}
+
+export interface DuplicateCleanup {
+    deleted: number;
+    freedBytes: number;
+}
+
+/**
+ * Find recordings of the same game across folders. Only the library cache is
+ * read; videos not probed yet only match through their replay.
+ */
+export async function findDuplicateRecordings(): Promise<DuplicateSet[]> {
+    return await invoke<DuplicateSet[]>('find_duplicate_recordings');
+}
+
+/**
+ * Delete the copies not kept, like deleting them from the library.
+ * @param resolutions - Per set, the copy to keep and the ones to delete
+ */
+export async function resolveDuplicates(resolutions: DuplicateResolution[]): Promise<DuplicateCleanup> {
+    return await invoke<DuplicateCleanup>('resolve_duplicates', { resolutions });
+}
+
+/**
+ * A recording with no audible sound.
+ */
+export interface SilentRecording {
+    recordingId: string;
+    videoPath: string;
+    startTime: string | null;
+    /** false if the video has no audio stream at all */
+    hasAudio: boolean;
+    meanVolumeDb: number | null;
+}
+
+/**
+ * List recordings the background probe found silent, newest first.
+ */
+export async function listSilentRecordings(): Promise<SilentRecording[]> {
+    return await invoke<SilentRecording[]>('list_silent_recordings');
+}
@@ -0,0 +1,167 @@
+/**
+ * Game Analysis Service
+ *
+ * Derives everything the replay viewer shows from a .slp file (timeline events,
+ * conversions, per-player stats) in one slippi-js pass, and caches the result
+ * on disk through the Rust backend. Re-opening a recording reads the cache as
+ * long as the .slp contents and ANALYSIS_VERSION are unchanged. Replays that
+ * aren't in the library go through the same pass with analyzeReplayFile.
+ *
+ * @module services/game-analysis
+ */
+
+import { SlippiGame } from "@slippi/slippi-js";
+import { readFile } from "@tauri-apps/plugin-fs";
+import { invoke } from "@tauri-apps/api/core";
+import { GameEventType, type DeathEvent, type SlippiMetadata } from "$lib/types/recording";
+import type { ConversionForDisplay, GameStatsForDB } from "$lib/types/slippi-stats";
+import { buildConversions, buildGameStats } from "$lib/services/slippi-stats";
+import { buildSlippiMetadata } from "$lib/utils/slippi";
+import { analyzeReplay, type ReplayAnalysis } from "$lib/commands";
+
+/**
+ * Version of the analysis code.
+ * Bump whenever the derived data changes (new detectors, fixes) so cached
+ * analyses are recomputed.
+ */
+export const ANALYSIS_VERSION = 1;
+
+/** Frames before the game timer starts (frame -123 is the first frame) */
+const FIRST_FRAME = -123;
+
+/** Everything derived from a .slp for the replay viewer */
+export interface GameAnalysis {
+	/** Timeline events (deaths, etc.) */
+	events: DeathEvent[];
+	/** Conversions grouped by the player who performed them */
+	conversions: Record<number, ConversionForDisplay[]>;
+	/** Game and per-player stats */
+	stats: GameStatsForDB | null;
+}
+
+/**
+ * Get the analysis for a recording, from the cache if possible.
+ * @param slpPath - Path to the .slp file
+ * @param recordingId - ID of the recording in the database
+ * @returns The analysis, or null if the .slp couldn't be parsed
+ */
+export async function getGameAnalysis(
+	slpPath: string,
+	recordingId: string
+): Promise<GameAnalysis | null> {
+	try {
+		const cached = await invoke<GameAnalysis | null>("get_cached_analysis", {
+			recordingId,
+			slpPath,
+			analysisVersion: ANALYSIS_VERSION,
+		});
+		if (cached) {
+			console.log("[GameAnalysis] Using cached analysis for", recordingId);
+			return cached;
+		}
+	} catch (error) {
+		console.warn("[GameAnalysis] Failed to read analysis cache:", error);
+	}
+
+	const analysis = await computeGameAnalysis(slpPath, recordingId);
+	if (analysis) {
+		invoke("save_cached_analysis", {
+			recordingId,
+			slpPath,
+			analysisVersion: ANALYSIS_VERSION,
+			analysis,
+		}).catch((error) => console.warn("[GameAnalysis] Failed to cache analysis:", error));
+	}
+
+	return analysis;
+}
+
+/**
+ * Drop cached analyses so they are recomputed on next open.
+ * @param recordingId - Recording to invalidate, or undefined for all recordings
+ * @returns Number of cache entries removed
+ */
+export async function invalidateAnalysisCache(recordingId?: string): Promise<number> {
+	return await invoke<number>("invalidate_analysis_cache", { recordingId: recordingId ?? null });
+}
+
+/**
+ * Parse a .slp file and derive the full analysis (no cache).
+ * @param slpPath - Path to the .slp file
+ * @param recordingId - ID of the recording in the database
+ */
+async function computeGameAnalysis(
+	slpPath: string,
+	recordingId: string
+): Promise<GameAnalysis | null> {
+	try {
+		console.log("[GameAnalysis] Computing analysis for:", slpPath);
+
+		const fileData = await readFile(slpPath);
+		return analyzeGame(new SlippiGame(fileData.buffer), slpPath, recordingId);
+	} catch (error) {
+		console.error("[GameAnalysis] Failed to compute analysis:", error);
+		return null;
+	}
+}
+
+/**
+ * Derive the full analysis of an already-loaded game. Shared by the library
+ * (cached) and analyzeReplayFile (nothing saved).
+ * @param game - Game loaded from the .slp file
+ * @param slpPath - Path to the .slp file
+ * @param recordingId - ID of the recording in the database, "" outside the library
+ */
+function analyzeGame(game: SlippiGame, slpPath: string, recordingId: string): GameAnalysis {
+	// eslint-disable-next-line @typescript-eslint/no-explicit-any
+	const stats: any = game.getStats();
+	const settings = game.getSettings();
+
+	// Deaths come from stocks that ended
+	const events: DeathEvent[] = [];
+	// eslint-disable-next-line @typescript-eslint/no-explicit-any
+	for (const stock of (stats?.stocks ?? []) as any[]) {
+		if (stock.endFrame == null) continue;
+
+		const player = settings?.players.find((p) => p.playerIndex === stock.playerIndex);
+		const port = (player?.port ?? stock.playerIndex) + 1;
+		events.push({
+			type: GameEventType.DEATH,
+			frame: stock.endFrame,
+			timestamp: Math.max(0, (stock.endFrame - FIRST_FRAME) / 60),
+			port,
+			player_tag: player?.connectCode || player?.displayName || `P${port}`,
+		});
+	}
+	events.sort((a, b) => a.frame - b.frame);
+
+	return {
+		events,
+		conversions: Object.fromEntries(buildConversions(stats)),
+		stats: buildGameStats(game, slpPath, recordingId),
+	};
+}
+
+/** A replay analyzed outside the library */
+export interface ReplayFileAnalysis extends GameAnalysis, ReplayAnalysis {
+	metadata: SlippiMetadata | null;
+}
+
+/**
+ * Analyze a .slp that isn't in the library (e.g. one a friend sent) with the
+ * same code as library games, without saving anything. The backend checks
+ * the file first (size, structure, read time) so a bad file fails fast
+ * instead of hanging slippi-js.
+ * @param slpPath - Path to the .slp file
+ * @throws Error if the backend rejects the file or slippi-js can't parse it
+ */
+export async function analyzeReplayFile(slpPath: string): Promise<ReplayFileAnalysis> {
+	const replay = await analyzeReplay(slpPath);
+	const fileData = await readFile(slpPath);
+	const game = new SlippiGame(fileData.buffer);
+	return {
+		...replay,
+		...analyzeGame(game, slpPath, ""),
+		metadata: buildSlippiMetadata(game, slpPath),
+	};
+}
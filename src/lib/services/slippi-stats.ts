@@ -7,10 +7,11 @@
  * @module services/slippi-stats
  */
 
-import { SlippiGame } from "@slippi/slippi-js";
+import { SlippiGame, type FrameEntryType } from "@slippi/slippi-js";
 import { readFile } from "@tauri-apps/plugin-fs";
 import { invoke } from "@tauri-apps/api/core";
-import type { GameStatsForDB, PlayerStatsForDB, ConversionForDisplay } from "$lib/types/slippi-stats";
+import { waitForJobSlot } from "$lib/commands";
+import { STATS_VERSION, type ComboForDB, type GameStatsForDB, type PlayerStatsForDB, type ConversionForDisplay } from "$lib/types/slippi-stats";
 
 // eslint-disable-next-line @typescript-eslint/no-explicit-any
 type SlippiStats = any;
@@ -19,17 +20,104 @@ type SlippiOverall = any;
 // eslint-disable-next-line @typescript-eslint/no-explicit-any
 type SlippiActionCounts = any;
 
+/**
+ * Oldest replay version that records the data each detector needs.
+ * Mirrors ReplayVersion in src-tauri/src/slippi/version.rs.
+ */
+const MIN_REPLAY_VERSION = {
+	/** Post-frame L-cancel status */
+	lCancel: [2, 0, 0],
+	/** Display names and connect codes in Game Start */
+	netplayNames: [3, 9, 0],
+} as const;
+
+/** Newest replay major version slippi-js is known to read */
+const NEWEST_SUPPORTED_MAJOR = 3;
+
+/**
+ * Whether a replay version ("major.minor.build") is at least the given one.
+ * An unknown version is treated as too old.
+ */
+function replayVersionAtLeast(version: string | null | undefined, min: readonly number[]): boolean {
+	if (!version) return false;
+	const parts = version.split(".").map(Number);
+	for (let i = 0; i < min.length; i++) {
+		const part = parts[i] ?? 0;
+		if (Number.isNaN(part)) return false;
+		if (part !== min[i]) return part > min[i];
+	}
+	return true;
+}
+
+/**
+ * A run of frames where no character moves or animates shorter than this is
+ * hitlag (at most ~20 frames), not a pause.
+ */
+const MIN_PAUSE_FRAMES = 30;
+
+/** Frames per minute, for per-minute rates */
+const FRAMES_PER_MINUTE = 3600;
+
+/**
+ * Count the frames the game spent paused. Slippi keeps writing frames while
+ * the game is paused, but every character's position, action state and
+ * animation counter stays frozen, so runs of such frames are the pauses.
+ * @param game - Game loaded from the .slp file
+ */
+export function countPausedFrames(game: SlippiGame): number {
+	const frames = game.getFrames();
+	const frameNumbers = Object.keys(frames)
+		.map(Number)
+		.sort((a, b) => a - b);
+
+	// Where every character is and how far into its animation, null without players
+	const snapshot = (frame: FrameEntryType | undefined): string | null => {
+		const players = Object.values(frame?.players ?? {}).filter((p) => p != null);
+		if (players.length === 0) return null;
+		return players
+			.map(({ post }) =>
+				[post.positionX, post.positionY, post.actionStateId, post.actionStateCounter, post.percent].join(",")
+			)
+			.join("|");
+	};
+
+	let paused = 0;
+	let run = 0;
+	let previous: string | null = null;
+	for (const frameNumber of frameNumbers) {
+		const current = snapshot(frames[frameNumber]);
+		if (current !== null && current === previous) {
+			run++;
+		} else {
+			if (run >= MIN_PAUSE_FRAMES) paused += run;
+			run = 0;
+		}
+		previous = current;
+	}
+	if (run >= MIN_PAUSE_FRAMES) paused += run;
+	return paused;
+}
+
+/**
+ * A number that can be sent to the backend, or null. Averages over games cut
+ * short (no kills, no openings, a frame or two) come out as NaN or Infinity,
+ * which JSON can't carry.
+ */
+function finite(val: unknown): number | null {
+	return typeof val === "number" && Number.isFinite(val) ? val : null;
+}
+
 /**
  * Safely get a number from a value that might be a number or an object with count/total.
  */
 function getNumber(val: unknown, fallback = 0): number {
-	if (typeof val === "number") return val;
+	if (typeof val === "number") return finite(val) ?? fallback;
 	if (val && typeof val === "object") {
-		if ("count" in val && typeof (val as { count: number }).count === "number") {
-			return (val as { count: number }).count;
+		if ("count" in val) {
+			return finite((val as { count: unknown }).count) ?? fallback;
 		}
-		if ("total" in val && typeof (val as { total: number }).total === "number") {
-			return (val as { total: number }).total;
+		if ("total" in val) {
+			return finite((val as { total: unknown }).total) ?? fallback;
 		}
 	}
 	return fallback;
@@ -40,11 +128,9 @@ function getNumber(val: unknown, fallback = 0): number {
  */
 function getRatio(val: unknown): number | null {
 	if (val && typeof val === "object" && "ratio" in val) {
-		const ratio = (val as { ratio: number | null }).ratio;
-		return typeof ratio === "number" ? ratio : null;
+		return finite((val as { ratio: unknown }).ratio);
 	}
-	if (typeof val === "number") return val;
-	return null;
+	return finite(val);
 }
 
 /**
@@ -54,7 +140,7 @@ function sumObjectValues(val: unknown, fallback = 0): number {
 	if (typeof val === "number") return val;
 	if (val && typeof val === "object") {
 		return Object.values(val as Record<string, number>)
-			.filter((v) => typeof v === "number")
+			.filter((v) => finite(v) !== null)
 			.reduce((sum, v) => sum + v, 0);
 	}
 	return fallback;
@@ -76,8 +162,26 @@ export async function parseSlippiStats(
 		const fileData = await readFile(slpPath);
 
 		// Create SlippiGame from the binary data
-		const game = new SlippiGame(fileData.buffer);
+		return buildGameStats(new SlippiGame(fileData.buffer), slpPath, recordingId);
+	} catch (error) {
+		console.error("[SlippiStats] Failed to parse Slippi stats:", error);
+		return null;
+	}
+}
 
+/**
+ * Compute all stats for an already-loaded game.
+ * @param game - Game loaded from the .slp file
+ * @param slpPath - Path to the .slp file
+ * @param recordingId - ID of the recording in the database
+ * @returns Computed stats ready for database storage
+ */
+export function buildGameStats(
+	game: SlippiGame,
+	slpPath: string,
+	recordingId: string
+): GameStatsForDB | null {
+	try {
 		// Get all the data we need
 		const settings = game.getSettings();
 		const metadata = game.getMetadata();
@@ -89,6 +193,19 @@ export async function parseSlippiStats(
 			return null;
 		}
 
+		// Old replays lack some fields; their stats are unknown rather than 0
+		const replayVersion: string | null = settings.slpVersion ?? null;
+		const hasLCancel = replayVersionAtLeast(replayVersion, MIN_REPLAY_VERSION.lCancel);
+		const hasNetplayNames = replayVersionAtLeast(replayVersion, MIN_REPLAY_VERSION.netplayNames);
+		if (replayVersion && Number(replayVersion.split(".")[0]) > NEWEST_SUPPORTED_MAJOR) {
+			console.warn(`[SlippiStats] Replay version ${replayVersion} is newer than supported, some stats may be missing`);
+		}
+
+		// Per-minute rates count the time the game was actually running
+		const totalPausedFrames = countPausedFrames(game);
+		const playableFrames: number = stats.playableFrameCount ?? 0;
+		const unpausedMinutes = Math.max(playableFrames - totalPausedFrames, 0) / FRAMES_PER_MINUTE;
+
 		// Build player stats
 		const players: PlayerStatsForDB[] = [];
 
@@ -116,14 +233,15 @@ export async function parseSlippiStats(
 
 			// Get final percent (from last stock)
 			const lastStock = playerStocks[playerStocks.length - 1];
-			const finalPercent = lastStock?.endPercent ?? lastStock?.currentPercent ?? null;
+			const finalPercent = finite(lastStock?.endPercent ?? lastStock?.currentPercent);
 
 			// Get netplay info
 			const metadataPlayer = metadata?.players?.[playerIndex];
+			// Game Start only has names since 3.9.0; older netplay replays may have them in metadata
 			const connectCode =
-				player.connectCode ?? metadataPlayer?.names?.code ?? null;
+				(hasNetplayNames ? player.connectCode : null) || metadataPlayer?.names?.code || null;
 			const displayName =
-				player.displayName ?? metadataPlayer?.names?.netplay ?? null;
+				(hasNetplayNames ? player.displayName : null) || metadataPlayer?.names?.netplay || null;
 
 			// Handle grabCount which could be number or object
 			const grabCount = getNumber(actionCounts?.grabCount);
@@ -149,7 +267,7 @@ export async function parseSlippiStats(
 				port,
 
 				// Overall performance
-				totalDamage: overall?.totalDamage ?? 0,
+				totalDamage: finite(overall?.totalDamage) ?? 0,
 				killCount: overall?.killCount ?? 0,
 				conversionCount: overall?.conversionCount ?? 0,
 				successfulConversions: getNumber(overall?.successfulConversions),
@@ -161,8 +279,11 @@ export async function parseSlippiStats(
 
 				// Input stats
 				inputsTotal: overall?.inputCounts?.total ?? 0,
-				inputsPerMinute: getRatio(overall?.inputsPerMinute),
-				avgKillPercent: overall?.avgKillPercent ?? null,
+				inputsPerMinute:
+					totalPausedFrames > 0 && unpausedMinutes > 0
+						? (overall?.inputCounts?.total ?? 0) / unpausedMinutes
+						: getRatio(overall?.inputsPerMinute),
+				avgKillPercent: finite(overall?.avgKillPercent),
 
 				// Action counts
 				wavedashCount: actionCounts?.wavedashCount ?? 0,
@@ -176,11 +297,11 @@ export async function parseSlippiStats(
 				throwCount,
 				groundTechCount,
 				wallTechCount,
-				wallJumpTechCount: 0, // Not available in slippi-js
+				wallJumpTechCount: null, // Not available in slippi-js
 
 				// L-Cancel stats
-				lCancelSuccessCount: actionCounts?.lCancelCount?.success ?? 0,
-				lCancelFailCount: actionCounts?.lCancelCount?.fail ?? 0,
+				lCancelSuccessCount: hasLCancel ? (actionCounts?.lCancelCount?.success ?? 0) : null,
+				lCancelFailCount: hasLCancel ? (actionCounts?.lCancelCount?.fail ?? 0) : null,
 
 				// Final state
 				stocksRemaining,
@@ -249,6 +370,7 @@ export async function parseSlippiStats(
 			stage: settings.stageId ?? 0,
 			gameDuration: stats.lastFrame ?? metadata?.lastFrame ?? 0,
 			totalFrames: stats.lastFrame ?? metadata?.lastFrame ?? 0,
+			totalPausedFrames,
 			isPal: settings.isPAL ?? false,
 			playedOn: metadata?.playedOn ?? null,
 			matchId: settings.matchInfo?.matchId ?? null,
@@ -259,9 +381,15 @@ export async function parseSlippiStats(
 			winnerIndex,
 			loserIndex,
 			gameEndMethod,
+			// Crashes and quit-outs leave no Game End, and their stats stop wherever the replay does
+			completed: gameEnd != null,
+			statsVersion: STATS_VERSION,
+			replayVersion,
 
 			// Player stats
 			players,
+
+			combos: buildCombos(stats),
 		};
 
 		console.log(
@@ -274,6 +402,36 @@ export async function parseSlippiStats(
 	}
 }
 
+/**
+ * Flatten slippi-js conversions into combos for the database.
+ * A conversion's playerIndex is the player being hit; the attacker is lastHitBy.
+ * @param stats - Result of game.getStats()
+ */
+function buildCombos(stats: SlippiStats): ComboForDB[] {
+	const combos: ComboForDB[] = [];
+	// eslint-disable-next-line @typescript-eslint/no-explicit-any
+	for (const conv of (stats.conversions ?? []) as any[]) {
+		const moves: { moveId: number; playerIndex: number; frame: number }[] = conv.moves ?? [];
+		const attacker = conv.lastHitBy ?? moves[0]?.playerIndex;
+		if (attacker == null || moves.length === 0) continue;
+
+		const startFrame = conv.startFrame ?? 0;
+		const startPercent = finite(conv.startPercent) ?? 0;
+		combos.push({
+			playerIndex: attacker,
+			opponentIndex: conv.playerIndex,
+			startFrame,
+			endFrame: conv.endFrame ?? moves[moves.length - 1]?.frame ?? startFrame,
+			startPercent,
+			endPercent: finite(conv.endPercent ?? conv.currentPercent) ?? startPercent,
+			moveCount: moves.length,
+			distinctMoves: new Set(moves.map((m) => m.moveId)).size,
+			didKill: conv.didKill ?? false,
+		});
+	}
+	return combos;
+}
+
 /**
  * Parse a .slp file and save stats to the database.
  * This is the main entry point called when a recording ends.
@@ -286,11 +444,17 @@ export async function parseAndSaveSlippiStats(
 ): Promise<boolean> {
 	try {
 		console.log("[SlippiStats] parseAndSaveSlippiStats called for", slpPath, "recordingId:", recordingId);
-		
+
+		// Parsing competes with the encoder; wait while a game is being recorded
+		await waitForJobSlot("stats");
 		const stats = await parseSlippiStats(slpPath, recordingId);
 
 		if (!stats) {
 			console.warn("[SlippiStats] No stats to save");
+			// Quarantine the replay so stats passes stop retrying it
+			await invoke<string>("record_replay_parse_failure", { recordingId, slpPath, error: null }).catch((e) =>
+				console.warn("[SlippiStats] Failed to record parse failure:", e)
+			);
 			return false;
 		}
 
@@ -376,10 +540,20 @@ export async function getConversionsFromSlp(
 		// Create SlippiGame from the binary data
 		const game = new SlippiGame(fileData.buffer);
 
-		// Get conversions from stats
-		// eslint-disable-next-line @typescript-eslint/no-explicit-any
-		const stats: any = game.getStats();
+		return buildConversions(game.getStats());
+	} catch (error) {
+		console.error("[SlippiStats] Failed to get conversions:", error);
+		return new Map();
+	}
+}
 
+/**
+ * Group conversions from computed slippi-js stats by the player who performed them.
+ * @param stats - Result of game.getStats()
+ * @returns Array of conversions grouped by player index
+ */
+export function buildConversions(stats: SlippiStats): Map<number, ConversionForDisplay[]> {
+	try {
 		if (!stats || !stats.conversions) {
 			console.warn("[SlippiStats] No conversions in stats");
 			return new Map();
@@ -426,7 +600,7 @@ export async function getConversionsFromSlp(
 
 		return conversionsByPlayer;
 	} catch (error) {
-		console.error("[SlippiStats] Failed to get conversions:", error);
+		console.error("[SlippiStats] Failed to build conversions:", error);
 		return new Map();
 	}
 }
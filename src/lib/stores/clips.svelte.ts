@@ -18,6 +18,12 @@
 import { invoke } from '@tauri-apps/api/core';
 import type { RecordingSession } from '$lib/types/recording';
 import type { ClipMarker, ClipSession } from '$lib/types/clip';
+import {
+	deleteClip as deleteClipCommand,
+	renameClip as renameClipCommand,
+	setClipFavorite,
+	type ClipUpdated,
+} from '$lib/commands';
 
 // Re-export types for convenience
 export type { ClipMarker, ClipSession } from '$lib/types/clip';
@@ -48,6 +54,7 @@ function mapRecordingSessionToClip(session: RecordingSession): ClipSession {
 		file_size: session.file_size,
 		slp_path,
 		slippi_metadata: session.slippi_metadata,
+		is_favorite: session.is_favorite ?? false,
 	};
 }
 
@@ -114,23 +121,52 @@ class ClipsStore {
 	}
 
 	/**
-	 * Delete a clip and its video file.
+	 * Delete a clip with its video file, thumbnail and pending uploads.
 	 * @param clipId - ID of the clip to delete
-	 * @param videoPath - Path to the video file
 	 * @throws Error if deletion fails
 	 */
-	async deleteClip(clipId: string, videoPath: string) {
+	async deleteClip(clipId: string) {
 		try {
-			await invoke('delete_recording', {
-				videoPath,
-				slpPath: '' // Empty string since clips don't have .slp files
-			});
-			await this.refresh();
+			await deleteClipCommand(clipId);
+			this.clips = this.clips.filter(c => c.id !== clipId);
 		} catch (error) {
 			console.error('Failed to delete clip:', error);
 			throw error;
 		}
 	}
+
+	/**
+	 * Rename a clip's file (without extension).
+	 * @param clipId - ID of the clip to rename
+	 * @param newName - New file name, which must be a valid file name
+	 * @throws Error if the name is invalid or taken
+	 */
+	async renameClip(clipId: string, newName: string) {
+		this.applyUpdate(await renameClipCommand(clipId, newName));
+	}
+
+	/**
+	 * Star or unstar a clip.
+	 * @param clipId - ID of the clip
+	 * @param favorite - Whether it's starred
+	 */
+	async setFavorite(clipId: string, favorite: boolean) {
+		this.applyUpdate(await setClipFavorite(clipId, favorite));
+	}
+
+	/**
+	 * Apply a renamed or starred clip (from a command or 'clip-updated').
+	 * @param update - The clip as it is now
+	 */
+	applyUpdate(update: ClipUpdated) {
+		this.clips = this.clips.map(c => c.id !== update.id ? c : {
+			...c,
+			filename: update.path.split(/[/\\]/).pop() || c.filename,
+			video_path: update.path,
+			thumbnail_path: update.thumbnailPath,
+			is_favorite: update.isFavorite,
+		});
+	}
 }
 
 /** Singleton clips store instance */
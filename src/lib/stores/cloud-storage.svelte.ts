@@ -1,6 +1,8 @@
 import { readFile, writeFile } from '@tauri-apps/plugin-fs';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { auth } from './auth.svelte';
+import { exportAnonymizedReplay, type ClipDeleted } from '$lib/commands';
 import type { Upload, CloudClip, UploadQueueItem } from '$lib/types/cloud';
 
 // Re-export types for convenience
@@ -66,6 +68,9 @@ class CloudStorageStore {
 	}
 
 	private async init() {
+		// A deleted clip can't finish uploading
+		listen<ClipDeleted>('clip-deleted', (event) => this.cancelUploadsOf(event.payload.path));
+
 		// Get device ID for anonymous uploads
 		try {
 			this.deviceId = await invoke<string>('get_device_id');
@@ -75,6 +80,21 @@ class CloudStorageStore {
 		}
 	}
 
+	/**
+	 * Cancel queued and running uploads of a local video and drop them from the queue.
+	 * @param videoPath - Local path to the video
+	 */
+	cancelUploadsOf(videoPath: string) {
+		const uploads = this.uploadQueue.filter(item => item.videoPath === videoPath);
+		for (const item of uploads) {
+			item.abortController?.abort();
+			item.xhr?.abort();
+		}
+		if (uploads.length > 0) {
+			this.uploadQueue = this.uploadQueue.filter(item => item.videoPath !== videoPath);
+		}
+	}
+
 	/**
 	 * Upload a video file to cloud storage.
 	 * @param videoPath - Local path to the video file
@@ -189,7 +209,49 @@ class CloudStorageStore {
 
 		// Read the video file
 		const videoData = await readFile(videoPath);
-		
+		return this.uploadPublicFile(videoData, filename, 'video/mp4', deviceId, metadata || null, metadata?.duration || null);
+	}
+
+	/**
+	 * Share a recording's replay with the players' names and connect codes replaced.
+	 * An anonymized copy is uploaded like a public clip; frame data is untouched, so it
+	 * still plays in Dolphin.
+	 * @param recordingId - Recording whose replay to share
+	 * @param deviceId - Device ID for anonymous uploads
+	 * @returns The uploaded clip and its share link
+	 */
+	async sharePublicReplay(recordingId: string, deviceId: string): Promise<{ clip: CloudClip; url: string }> {
+		const replay = await exportAnonymizedReplay(recordingId);
+		try {
+			const replayData = await readFile(replay.path);
+			const { clip } = await this.uploadPublicFile(
+				replayData,
+				replay.fileName,
+				'application/octet-stream',
+				deviceId,
+				{ kind: 'replay', anonymized: true },
+				null
+			);
+			return { clip, url: `https://clips.peppi.app/${clip.share_code}` };
+		} finally {
+			await invoke('delete_temp_file', { path: replay.path }).catch((e) =>
+				console.warn('Failed to delete anonymized replay:', e)
+			);
+		}
+	}
+
+	/**
+	 * Upload a file through a signed URL from generate-clip-upload-url, which also
+	 * creates the clip record (or finds an existing one).
+	 */
+	private async uploadPublicFile(
+		fileData: Uint8Array,
+		filename: string,
+		contentType: string,
+		deviceId: string,
+		metadata: Record<string, unknown> | null,
+		duration: number | null
+	): Promise<{ clip: CloudClip; alreadyExists: boolean }> {
 		// Generate upload URL from edge function
 		// This also creates the database record and checks for duplicates
 		const { data: uploadData, error: uploadError } = await auth.supabase.functions.invoke(
@@ -197,9 +259,9 @@ class CloudStorageStore {
 			{
 				body: {
 					fileName: filename,
-					fileSize: videoData.byteLength,
+					fileSize: fileData.byteLength,
 					deviceId,
-					metadata: metadata || null,
+					metadata,
 				},
 			}
 		);
@@ -225,9 +287,9 @@ class CloudStorageStore {
 		// Upload to B2/R2 using the signed URL
 		const uploadResponse = await fetch(uploadData.uploadUrl, {
 			method: 'PUT',
-			body: videoData,
+			body: fileData,
 			headers: {
-				'Content-Type': 'video/mp4',
+				'Content-Type': contentType,
 			},
 		});
 
@@ -246,8 +308,8 @@ class CloudStorageStore {
 			filename: clipData.filename || filename,
 			b2_file_id: clipData.b2_file_id || null,
 			b2_file_name: clipData.b2_file_name || null,
-			file_size: clipData.file_size || videoData.byteLength,
-			duration_seconds: metadata?.duration || null,
+			file_size: clipData.file_size || fileData.byteLength,
+			duration_seconds: duration,
 			share_code: clipData.share_code || uploadData.shareCode,
 			uploaded_at: clipData.uploaded_at || new Date().toISOString(),
 			metadata: clipData.metadata || metadata || null,
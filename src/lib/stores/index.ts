@@ -33,3 +33,7 @@ export { recordingsStore } from './recordings.svelte';
 export { settings } from './settings.svelte';
 export type { Settings } from './settings.svelte';
 
+// Stats jobs store
+export { statsJobs } from './stats-jobs.svelte';
+export type { StatsJob, StatsJobStatus, StatsJobItemResult } from './stats-jobs.svelte';
+
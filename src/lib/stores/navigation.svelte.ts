@@ -21,7 +21,7 @@
  */
 
 /** Available page identifiers */
-type Page = "home" | "settings" | "replay" | "cloud" | "profile" | "clips" | "stats" | "total_stats";
+type Page = "home" | "settings" | "replay" | "cloud" | "profile" | "clips" | "stats" | "total_stats" | "diagnostics";
 
 /** Page state with type-safe replay/stats info */
 type PageInfo<TPage extends Page = Page> = TPage extends "replay"
@@ -16,6 +16,8 @@
  * @module stores/recording
  */
 
+import type { StartedGame } from "$lib/commands";
+
 /** Recording status indicator values */
 export type RecordingStatus = "recording" | "ready" | "waiting" | "no-window";
 
@@ -34,6 +36,8 @@ class RecordingStore {
 	startTimestamp = $state<number | null>(null);
 	/** Path to current recording/replay file */
 	currentReplayPath = $state<string | null>(null);
+	/** The game in progress, from its replay's Game Start */
+	liveGame = $state<StartedGame | null>(null);
 
 	/**
 	 * Derived status for UI indicator.
@@ -62,6 +66,7 @@ class RecordingStore {
 	stop() {
 		this.isRecording = false;
 		this.startTimestamp = null;
+		this.liveGame = null;
 	}
 
 	/**
@@ -87,6 +92,14 @@ class RecordingStore {
 	setReplayPath(path: string | null) {
 		this.currentReplayPath = path;
 	}
+
+	/**
+	 * Set the game in progress.
+	 * @param game - Game from game-started, or null once it's over
+	 */
+	setLiveGame(game: StartedGame | null) {
+		this.liveGame = game;
+	}
 }
 
 /** Singleton recording state store instance */
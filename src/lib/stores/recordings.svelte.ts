@@ -22,11 +22,14 @@
 
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
-import type { RecordingSession, RecordingWithMetadata, GameEvent, PaginatedRecordings } from "$lib/types/recording";
-import { handleTauriError, showSuccess } from "$lib/utils/errors";
+import type { RecordingSession, RecordingWithMetadata, GameEvent, PaginatedRecordings, PerformanceWarning, PostRecordingHookResult, RecordingResult } from "$lib/types/recording";
+import { handleTauriError, showInfo, showSuccess } from "$lib/utils/errors";
 import { recording } from "$lib/stores/recording.svelte";
 import { settings } from "$lib/stores/settings.svelte";
 import { clipsStore, type ClipSession } from "$lib/stores/clips.svelte";
+import { statsJobs } from "$lib/stores/stats-jobs.svelte";
+import { flagOutdatedStats, processClipMarkers, setNeedsReparse, type StartedGame } from "$lib/commands";
+import { STATS_VERSION } from "$lib/types/slippi-stats";
 
 /**
  * Manages the recordings list, selection state, and recording controls.
@@ -112,10 +115,12 @@ class RecordingsStore {
 	
 	/** Whether we're currently parsing missing stats (to prevent recursive refresh) */
 	private isParsingMissingStats = false;
+	/** Stats job parsing missing stats, if one is running */
+	missingStatsJobId = $state<string | null>(null);
 	
 	/**
-	 * Parse and save stats for recordings that are missing slippi_metadata.
-	 * Runs in background without blocking the UI.
+	 * Parse and save stats for recordings that are missing slippi_metadata or
+	 * are flagged to reparse. Runs in background without blocking the UI.
 	 */
 	private async parseStatsForMissingRecordings() {
 		// Prevent recursive calls from the refresh after parsing
@@ -123,10 +128,13 @@ class RecordingsStore {
 			return;
 		}
 		
-		// Find recordings with slp_path but no slippi_metadata (or no players)
+		// Find recordings with slp_path but no slippi_metadata (or no players, or
+		// only the provisional ones from the game's start) or flagged to reparse,
+		// skipping replays already known to be unparseable
 		const needsParsing = this.recordings.filter(rec => 
 			rec.slp_path && 
-			(!rec.slippi_metadata || !rec.slippi_metadata.players || rec.slippi_metadata.players.length === 0)
+			!rec.parse_error &&
+			(rec.needs_reparse || rec.stats_pending || !rec.slippi_metadata || !rec.slippi_metadata.players || rec.slippi_metadata.players.length === 0)
 		);
 		
 		if (needsParsing.length === 0) {
@@ -146,25 +154,27 @@ class RecordingsStore {
 				console.log(`[SlippiStats] Only parsing first 10 of ${needsParsing.length} recordings (others will parse on next refresh)`);
 			}
 			
-			// Parse each recording (don't await all at once to avoid overwhelming)
-			let successCount = 0;
-			for (const rec of toParse) {
-				try {
-					const success = await parseAndSaveSlippiStats(rec.slp_path!, rec.id);
-					if (success) {
-						successCount++;
+			// Parse one recording at a time as a cancellable stats job
+			this.missingStatsJobId = statsJobs.start(
+				"Missing recording stats",
+				toParse,
+				async (rec) => ((await parseAndSaveSlippiStats(rec.slp_path!, rec.id)) ? "done" : "failed"),
+				async (job) => {
+					try {
+						const successCount = job.current - job.failed - job.skipped;
+						if (successCount > 0) {
+							console.log(`[SlippiStats] Successfully parsed ${successCount}/${needsParsing.length} recordings`);
+							// Refresh to show the new metadata
+							await this.refresh();
+						}
+					} finally {
+						this.isParsingMissingStats = false;
+						this.missingStatsJobId = null;
 					}
-				} catch (error) {
-					console.warn(`[SlippiStats] Failed to parse stats for ${rec.id}:`, error);
 				}
-			}
-			
-			if (successCount > 0) {
-				console.log(`[SlippiStats] Successfully parsed ${successCount}/${needsParsing.length} recordings`);
-				// Refresh to show the new metadata
-				await this.refresh();
-			}
-		} finally {
+			);
+		} catch (error) {
+			console.warn("[SlippiStats] Failed to start stats job:", error);
 			this.isParsingMissingStats = false;
 		}
 	}
@@ -325,7 +335,10 @@ class RecordingsStore {
 
 		if (!this.listenersActive) {
 			this.listenersActive = true;
-			void this.refresh();
+			// Games from an older STATS_VERSION are picked up by the stats pass
+			void flagOutdatedStats(STATS_VERSION)
+				.catch((error) => console.warn("[SlippiStats] Failed to flag outdated stats:", error))
+				.finally(() => this.refresh());
 			this.setupRecordingListeners();
 		}
 
@@ -373,8 +386,8 @@ class RecordingsStore {
 		this.isManualStopping = true;
 
 		try {
-			const outputPath = await invoke<string>("stop_recording");
-			console.log("⏹️  Recording stopped:", outputPath);
+			const result = await invoke<RecordingResult>("stop_recording");
+			console.log("⏹️  Recording stopped:", result.outputPath);
 			recording.stop();
 			showSuccess("Recording stopped");
 			await this.refresh();
@@ -410,6 +423,55 @@ class RecordingsStore {
 			})
 		);
 
+		this.eventListenerPromises.push(
+			// Batched: the ids reparsed in the last moment
+			listen<string[]>("recording-reparsed", (event) => {
+				// The stats pass refreshes once it's done
+				if (!this.isParsingMissingStats && this.recordings.some((rec) => event.payload.includes(rec.id))) {
+					void this.refresh();
+				}
+			})
+		);
+
+		this.eventListenerPromises.push(
+			// Batched: every rename in the last moment, in order
+			listen<{ recordingId: string; videoPath: string }[]>("recording-renamed", (event) => {
+				for (const { recordingId, videoPath } of event.payload) {
+					const recording = this.recordings.find((rec) => rec.id === recordingId);
+					if (recording) {
+						recording.video_path = videoPath;
+					}
+				}
+			})
+		);
+
+		this.eventListenerPromises.push(
+			listen<PerformanceWarning>("recording-performance-warning", (event) => {
+				const { droppedRatio, suggestedQuality } = event.payload;
+				const percent = Math.round(droppedRatio * 100);
+				showInfo(
+					suggestedQuality
+						? `Recording is dropping ${percent}% of frames - try the ${suggestedQuality} quality preset`
+						: `Recording is dropping ${percent}% of frames`
+				);
+			})
+		);
+
+		this.eventListenerPromises.push(
+			listen<PostRecordingHookResult>("recording-hook-finished", (event) => {
+				const { kind, success, message } = event.payload;
+				if (!success) {
+					handleTauriError(message ?? "Unknown error", `Post-recording ${kind} failed`);
+				}
+			})
+		);
+
+		this.eventListenerPromises.push(
+			listen<StartedGame>("game-started", (event) => {
+				recording.setLiveGame(event.payload);
+			})
+		);
+
 		this.eventListenerPromises.push(
 			listen<string>("last-replay-updated", (event) => {
 				// Always store the slp path for stats parsing later
@@ -418,7 +480,7 @@ class RecordingsStore {
 				
 				// Only set .slp path if we're not already recording with a video path
 				// (for auto recordings, recording-started will set the video path)
-				if (!recording.isRecording || !recording.currentReplayPath?.endsWith('.mp4')) {
+				if (!recording.isRecording || !/\.(mp4|mkv|mov|webm|avi)$/i.test(recording.currentReplayPath ?? '')) {
 					recording.setReplayPath(event.payload);
 				}
 			})
@@ -434,9 +496,7 @@ class RecordingsStore {
 				console.log("[SlippiStats] Using video path:", videoPath);
 				if (videoPath) {
 					try {
-						const clips = await invoke<string[]>("process_clip_markers", {
-							recordingFile: videoPath
-						});
+						const clips = await processClipMarkers(videoPath);
 						if (clips.length > 0) {
 							showSuccess(`Recording stopped - ${clips.length} clip(s) created!`);
 						} else {
@@ -580,13 +640,48 @@ class RecordingsStore {
 	}
 
 	/**
-	 * Parse and load game events from a .slp file.
+	 * Get a recording by ID, loading it from the backend if it isn't on the current page.
+	 * @param id - Recording ID to find
+	 * @param fresh - Load it from the backend even if it's on the current page
+	 * @returns The recording or undefined if it isn't in the library
+	 */
+	async fetchSlippiRecording(id: string, fresh = false): Promise<RecordingWithMetadata | undefined> {
+		const cached = fresh ? undefined : this.getSlippiRecording(id);
+		if (cached) return cached;
+
+		const session = await invoke<RecordingSession | null>("get_recording", { recordingId: id });
+		return session ? { ...session, is_selected: this.selectedIds.has(session.id) } : undefined;
+	}
+
+	/**
+	 * Parse a recording's replay again and save its stats. Listeners of
+	 * "recording-reparsed" refresh once it's saved.
+	 * @param recording - Recording with a replay
+	 */
+	async reparse(recording: RecordingSession) {
+		if (!recording.slp_path) return;
+		try {
+			await setNeedsReparse(recording.id);
+			const { parseAndSaveSlippiStats } = await import("$lib/services/slippi-stats");
+			if (!(await parseAndSaveSlippiStats(recording.slp_path, recording.id))) {
+				handleTauriError("The replay couldn't be parsed", "Failed to reparse replay");
+			}
+		} catch (error) {
+			handleTauriError(error, "Failed to reparse replay");
+		}
+	}
+
+	/**
+	 * Load game events for a recording (from the analysis cache when possible).
 	 * @param slpPath - Path to the .slp file
+	 * @param recordingId - ID of the recording (cache key)
 	 * @returns Array of game events (deaths, etc.)
 	 */
-	async loadSlippiEvents(slpPath: string): Promise<GameEvent[]> {
+	async loadSlippiEvents(slpPath: string, recordingId: string): Promise<GameEvent[]> {
 		try {
-			return await invoke<GameEvent[]>("parse_slp_events", { slpPath });
+			const { getGameAnalysis } = await import("$lib/services/game-analysis");
+			const analysis = await getGameAnalysis(slpPath, recordingId);
+			return analysis?.events ?? [];
 		} catch (error) {
 			handleTauriError(error, "Failed to parse replay events");
 			return [];
@@ -18,6 +18,10 @@
  */
 
 import { Store } from "@tauri-apps/plugin-store";
+import type { WatchFilters } from "$lib/commands";
+
+/** Local hours (0-23) heavy background jobs are kept to, e.g. 2 to 7 */
+export type QuietHours = { startHour: number; endHour: number };
 
 /**
  * Application settings shape.
@@ -31,11 +35,57 @@ export type Settings = {
 	recordingPath: string;
 	/** Video quality preset for recordings */
 	recordingQuality: "low" | "medium" | "high" | "ultra";
+	/** File type of finished recordings (MKV stays playable if the app is cut off mid-write) */
+	recordingContainer: "mp4" | "mkv";
+	/** Crop recordings to the game's picture, leaving out Dolphin's black bars and menus (native recorder) */
+	autoCropGameViewport: boolean;
 	/** Whether to auto-start recording when game is detected */
 	autoStartRecording: boolean;
+	/** How long the replay must go unwritten before its game counts as over (250-10000 ms) */
+	replayQuietPeriodMs: number;
+	/** How often the folder is scanned when file events don't arrive (network drives, OneDrive; 250-10000 ms) */
+	watcherPollIntervalMs: number;
+	/** Wait after a game ends for Slippi to finish the replay (0-30 seconds) */
+	stopRecordingDelaySeconds: number;
+	/** Extra recording after a game ends, for the post-game screen (0-120 seconds) */
+	endRecordingDelaySeconds: number;
+	/** One video per game, or one per set (kept running between the set's games) */
+	recordingGranularity: "per-game" | "per-set";
+	/** Time without a new game after which a per-set recording stops (1-30 minutes) */
+	setEndTimeoutMinutes: number;
+	/** Start recording when the game window appears, keeping some character select before each game */
+	preRollMode: boolean;
+	/** Character select kept before each game with preRollMode (0-120 seconds) */
+	preRollSeconds: number;
+	/** Dropped frames (or stall length, in frames) at which a recording gets a quality badge */
+	captureIssueThreshold: number;
+	/** Which recorder to use: built-in capture or OBS via obs-websocket */
+	recorderBackend: "native" | "obs";
+	/** obs-websocket host */
+	obsHost: string;
+	/** obs-websocket port */
+	obsPort: number;
+	/** obs-websocket password (empty if authentication is disabled) */
+	obsPassword: string;
+	/** Webhook URL or command template run after each recording (empty to disable) */
+	postRecordingHook: string;
+	/** Write a .buckwheat.json file with each recording's metadata next to its video */
+	writeSidecars: boolean;
+	/** Rename new recordings by recordingNameTemplate once their game's stats are saved */
+	renameRecordings: boolean;
+	/** File name for renamed recordings: {date}, {time}, {my_char}, {opp_char}, {my_code}, {opp_code}, {stage}, {result}, {original} */
+	recordingNameTemplate: string;
+	/** How many folders deep the library looks for recordings (1-8) */
+	recordingsScanDepth: number;
+	/** Folders and files the library scan skips, by name (`*` and `?`), or by path with a slash */
+	recordingsScanIgnore: string[];
 
 	/** Directory where Slippi .slp files are saved */
 	slippiPath: string;
+	/** Slippi playback Dolphin executable (empty to auto-detect) */
+	playbackDolphinPath: string;
+	/** Melee ISO used for playback (empty to use Slippi Launcher's) */
+	meleeIsoPath: string;
 	/** Whether to watch for new .slp files */
 	watchForGames: boolean;
 
@@ -43,9 +93,52 @@ export type Settings = {
 	createClipHotkey: string;
 	/** Duration in seconds for clips */
 	clipDuration: number;
+	/** Size in GB the Clips folder is kept under by deleting the oldest unstarred clips (0 for no cap) */
+	maxClipsSizeGb: number;
 
 	/** User's Slippi connect code (e.g., "HATS#982") */
 	slippiCode: string;
+
+	/** Whether to run the local stream overlay server */
+	overlayServer: boolean;
+	/** Port for the overlay server (bound to 127.0.0.1) */
+	overlayServerPort: number;
+	/** Show the current session as Discord Rich Presence */
+	enableDiscordPresence: boolean;
+	/** Include the opponent's connect code in Discord Rich Presence */
+	discordPresenceShowOpponent: boolean;
+	/** Launch the app on system startup */
+	launchOnStartup: boolean;
+	/** Start minimized when launched on system startup */
+	startMinimized: boolean;
+	/** Watch the same folder and resume the play session after a restart */
+	restoreStateOnLaunch: boolean;
+	/** Run the readiness check when the game window appears */
+	readinessCheckOnGameWindow: boolean;
+	/** Clip the day's best highlights automatically */
+	autoHighlights: boolean;
+	/** How many highlights the daily job clips */
+	autoHighlightsCount: number;
+	/** Generate last week's report on start once a new week has completed */
+	autoWeeklyReport: boolean;
+	/** Games shorter than this are left out of stats (0-300 seconds, 0 counts every game) */
+	minGameSecondsForStats: number;
+	warmupGames: number;
+	sessionGapMinutes: number;
+	/** Record games against CPUs and count them in stats */
+	recordCpuMatches: boolean;
+	/** Count games whose replay never reached Game End (crashes, quit-outs) in stats */
+	countUnfinishedGames: boolean;
+	/** Index the Slippi folder's replays without recording videos */
+	replayOnlyMode: boolean;
+	/** Which new replays start an auto-recording (save with setWatchFilters) */
+	watchFilters: WatchFilters;
+	/** Background jobs that keep running (one at a time) while recording; the rest wait for it to stop */
+	jobsWhileRecording: ("thumbnails" | "stats" | "clips" | "mediaProbe")[];
+	/** Decode video on the GPU for thumbnails and checks when FFmpeg can */
+	hardwareDecode: boolean;
+	/** Local hours retention and highlight clipping are kept to (end excluded, may span midnight); null for any time */
+	quietHours: QuietHours | null;
 };
 
 /** Default settings values */
@@ -53,12 +146,64 @@ const DEFAULT_SETTINGS: Settings = {
 	theme: "system",
 	recordingPath: "",
 	recordingQuality: "high",
+	recordingContainer: "mp4",
+	autoCropGameViewport: false,
 	autoStartRecording: true,
+	replayQuietPeriodMs: 1000,
+	watcherPollIntervalMs: 1000,
+	stopRecordingDelaySeconds: 3,
+	endRecordingDelaySeconds: 0,
+	recordingGranularity: "per-game",
+	setEndTimeoutMinutes: 5,
+	preRollMode: false,
+	preRollSeconds: 15,
+	captureIssueThreshold: 30,
+	recorderBackend: "native",
+	obsHost: "127.0.0.1",
+	obsPort: 4455,
+	obsPassword: "",
+	postRecordingHook: "",
+	writeSidecars: false,
+	renameRecordings: false,
+	recordingNameTemplate: "{date}_{my_char}_vs_{opp_char}_{opp_code}",
+	recordingsScanDepth: 3,
+	recordingsScanIgnore: [],
 	slippiPath: "",
+	playbackDolphinPath: "",
+	meleeIsoPath: "",
 	watchForGames: true,
 	createClipHotkey: "F9",
 	clipDuration: 30,
+	maxClipsSizeGb: 0,
 	slippiCode: "",
+	overlayServer: false,
+	overlayServerPort: 7650,
+	enableDiscordPresence: false,
+	discordPresenceShowOpponent: false,
+	launchOnStartup: false,
+	startMinimized: true,
+	restoreStateOnLaunch: true,
+	readinessCheckOnGameWindow: true,
+	autoHighlights: false,
+	autoHighlightsCount: 3,
+	autoWeeklyReport: false,
+	minGameSecondsForStats: 0,
+	warmupGames: 0,
+	sessionGapMinutes: 60,
+	recordCpuMatches: true,
+	countUnfinishedGames: false,
+	replayOnlyMode: false,
+	watchFilters: {
+		ignorePatterns: [],
+		minTriggerIntervalSeconds: 0,
+		minPlayers: null,
+		maxPlayers: null,
+		skipTeams: false,
+		connection: "any",
+	},
+	jobsWhileRecording: [],
+	hardwareDecode: true,
+	quietHours: null,
 };
 
 /**
@@ -76,18 +221,86 @@ class SettingsStore {
 	recordingPath = $state("");
 	/** Video quality preset */
 	recordingQuality = $state<Settings["recordingQuality"]>("high");
+	/** MP4 or MKV */
+	recordingContainer = $state<Settings["recordingContainer"]>("mp4");
+	/** Crop to the game's picture */
+	autoCropGameViewport = $state(false);
 	/** Auto-start recording on game detection */
 	autoStartRecording = $state(true);
+	/** Game end detection and post-game recording */
+	replayQuietPeriodMs = $state(1000);
+	watcherPollIntervalMs = $state(1000);
+	stopRecordingDelaySeconds = $state(3);
+	endRecordingDelaySeconds = $state(0);
+	/** Per-game or per-set recordings */
+	recordingGranularity = $state<Settings["recordingGranularity"]>("per-game");
+	setEndTimeoutMinutes = $state(5);
+	preRollMode = $state(false);
+	preRollSeconds = $state(15);
+	/** Capture quality badge threshold in frames */
+	captureIssueThreshold = $state(30);
+	/** Recorder backend */
+	recorderBackend = $state<Settings["recorderBackend"]>("native");
+	/** OBS WebSocket host */
+	obsHost = $state("127.0.0.1");
+	/** OBS WebSocket port */
+	obsPort = $state(4455);
+	/** OBS WebSocket password */
+	obsPassword = $state("");
+	/** Post-recording webhook URL or command */
+	postRecordingHook = $state("");
+	writeSidecars = $state(false);
+	renameRecordings = $state(false);
+	recordingNameTemplate = $state("{date}_{my_char}_vs_{opp_char}_{opp_code}");
+	recordingsScanDepth = $state(3);
+	recordingsScanIgnore = $state<string[]>([]);
 	/** Slippi replay directory */
 	slippiPath = $state("");
+	/** Playback Dolphin executable */
+	playbackDolphinPath = $state("");
+	/** Melee ISO for playback */
+	meleeIsoPath = $state("");
 	/** Watch for new .slp files */
 	watchForGames = $state(true);
 	/** Hotkey for clip creation */
 	createClipHotkey = $state("F9");
 	/** Clip duration in seconds */
 	clipDuration = $state(30);
+	/** Clips folder cap in GB (0 for no cap) */
+	maxClipsSizeGb = $state(0);
 	/** User's Slippi connect code */
 	slippiCode = $state("");
+	/** Run the stream overlay server */
+	overlayServer = $state(false);
+	/** Overlay server port */
+	overlayServerPort = $state(7650);
+	/** Discord Rich Presence */
+	enableDiscordPresence = $state(false);
+	/** Show opponent in Discord Rich Presence */
+	discordPresenceShowOpponent = $state(false);
+	/** Launch on system startup */
+	launchOnStartup = $state(false);
+	/** Start minimized on system startup */
+	startMinimized = $state(true);
+	restoreStateOnLaunch = $state(true);
+	/** Readiness check when the game window appears */
+	readinessCheckOnGameWindow = $state(true);
+	/** Daily highlight clips */
+	autoHighlights = $state(false);
+	autoHighlightsCount = $state(3);
+	/** Weekly report on start */
+	autoWeeklyReport = $state(false);
+	minGameSecondsForStats = $state(0);
+	warmupGames = $state(0);
+	sessionGapMinutes = $state(60);
+	recordCpuMatches = $state(true);
+	countUnfinishedGames = $state(false);
+	replayOnlyMode = $state(false);
+	watchFilters = $state<WatchFilters>(DEFAULT_SETTINGS.watchFilters);
+	jobsWhileRecording = $state<Settings["jobsWhileRecording"]>([]);
+	hardwareDecode = $state(true);
+	/** Hours heavy background jobs are kept to */
+	quietHours = $state<QuietHours | null>(null);
 
 	/** Whether settings are currently loading */
 	isLoading = $state(true);
@@ -114,6 +327,15 @@ class SettingsStore {
 		}
 	}
 
+	/**
+	 * Re-read settings from disk after the backend changed them (import or reset).
+	 */
+	async reload(): Promise<void> {
+		if (!this.store) return;
+		await this.store.reload();
+		await this.load();
+	}
+
 	/** Load settings from persistent store */
 	private async load(): Promise<void> {
 		if (!this.store) return;
@@ -122,12 +344,57 @@ class SettingsStore {
 		this.theme = settings.theme;
 		this.recordingPath = settings.recordingPath;
 		this.recordingQuality = settings.recordingQuality;
+		this.recordingContainer = settings.recordingContainer;
+		this.autoCropGameViewport = settings.autoCropGameViewport;
 		this.autoStartRecording = settings.autoStartRecording;
+		this.replayQuietPeriodMs = settings.replayQuietPeriodMs;
+		this.watcherPollIntervalMs = settings.watcherPollIntervalMs;
+		this.stopRecordingDelaySeconds = settings.stopRecordingDelaySeconds;
+		this.endRecordingDelaySeconds = settings.endRecordingDelaySeconds;
+		this.recordingGranularity = settings.recordingGranularity;
+		this.setEndTimeoutMinutes = settings.setEndTimeoutMinutes;
+		this.preRollMode = settings.preRollMode;
+		this.preRollSeconds = settings.preRollSeconds;
+		this.captureIssueThreshold = settings.captureIssueThreshold;
+		this.recorderBackend = settings.recorderBackend;
+		this.obsHost = settings.obsHost;
+		this.obsPort = settings.obsPort;
+		this.obsPassword = settings.obsPassword;
+		this.postRecordingHook = settings.postRecordingHook;
+		this.writeSidecars = settings.writeSidecars;
+		this.renameRecordings = settings.renameRecordings;
+		this.recordingNameTemplate = settings.recordingNameTemplate;
+		this.recordingsScanDepth = settings.recordingsScanDepth;
+		this.recordingsScanIgnore = settings.recordingsScanIgnore;
 		this.slippiPath = settings.slippiPath;
+		this.playbackDolphinPath = settings.playbackDolphinPath;
+		this.meleeIsoPath = settings.meleeIsoPath;
 		this.watchForGames = settings.watchForGames;
 		this.createClipHotkey = settings.createClipHotkey;
 		this.clipDuration = settings.clipDuration;
+		this.maxClipsSizeGb = settings.maxClipsSizeGb;
 		this.slippiCode = settings.slippiCode;
+		this.overlayServer = settings.overlayServer;
+		this.overlayServerPort = settings.overlayServerPort;
+		this.enableDiscordPresence = settings.enableDiscordPresence;
+		this.discordPresenceShowOpponent = settings.discordPresenceShowOpponent;
+		this.launchOnStartup = settings.launchOnStartup;
+		this.startMinimized = settings.startMinimized;
+		this.restoreStateOnLaunch = settings.restoreStateOnLaunch;
+		this.readinessCheckOnGameWindow = settings.readinessCheckOnGameWindow;
+		this.autoHighlights = settings.autoHighlights;
+		this.autoHighlightsCount = settings.autoHighlightsCount;
+		this.autoWeeklyReport = settings.autoWeeklyReport;
+		this.minGameSecondsForStats = settings.minGameSecondsForStats;
+		this.warmupGames = settings.warmupGames;
+		this.sessionGapMinutes = settings.sessionGapMinutes;
+		this.recordCpuMatches = settings.recordCpuMatches;
+		this.countUnfinishedGames = settings.countUnfinishedGames;
+		this.replayOnlyMode = settings.replayOnlyMode;
+		this.watchFilters = settings.watchFilters;
+		this.jobsWhileRecording = settings.jobsWhileRecording;
+		this.hardwareDecode = settings.hardwareDecode;
+		this.quietHours = settings.quietHours;
 	}
 
 	/** Reset reactive state to default values */
@@ -135,12 +402,57 @@ class SettingsStore {
 		this.theme = DEFAULT_SETTINGS.theme;
 		this.recordingPath = DEFAULT_SETTINGS.recordingPath;
 		this.recordingQuality = DEFAULT_SETTINGS.recordingQuality;
+		this.recordingContainer = DEFAULT_SETTINGS.recordingContainer;
+		this.autoCropGameViewport = DEFAULT_SETTINGS.autoCropGameViewport;
 		this.autoStartRecording = DEFAULT_SETTINGS.autoStartRecording;
+		this.replayQuietPeriodMs = DEFAULT_SETTINGS.replayQuietPeriodMs;
+		this.watcherPollIntervalMs = DEFAULT_SETTINGS.watcherPollIntervalMs;
+		this.stopRecordingDelaySeconds = DEFAULT_SETTINGS.stopRecordingDelaySeconds;
+		this.endRecordingDelaySeconds = DEFAULT_SETTINGS.endRecordingDelaySeconds;
+		this.recordingGranularity = DEFAULT_SETTINGS.recordingGranularity;
+		this.setEndTimeoutMinutes = DEFAULT_SETTINGS.setEndTimeoutMinutes;
+		this.preRollMode = DEFAULT_SETTINGS.preRollMode;
+		this.preRollSeconds = DEFAULT_SETTINGS.preRollSeconds;
+		this.captureIssueThreshold = DEFAULT_SETTINGS.captureIssueThreshold;
+		this.recorderBackend = DEFAULT_SETTINGS.recorderBackend;
+		this.obsHost = DEFAULT_SETTINGS.obsHost;
+		this.obsPort = DEFAULT_SETTINGS.obsPort;
+		this.obsPassword = DEFAULT_SETTINGS.obsPassword;
+		this.postRecordingHook = DEFAULT_SETTINGS.postRecordingHook;
+		this.writeSidecars = DEFAULT_SETTINGS.writeSidecars;
+		this.renameRecordings = DEFAULT_SETTINGS.renameRecordings;
+		this.recordingNameTemplate = DEFAULT_SETTINGS.recordingNameTemplate;
+		this.recordingsScanDepth = DEFAULT_SETTINGS.recordingsScanDepth;
+		this.recordingsScanIgnore = DEFAULT_SETTINGS.recordingsScanIgnore;
 		this.slippiPath = DEFAULT_SETTINGS.slippiPath;
+		this.playbackDolphinPath = DEFAULT_SETTINGS.playbackDolphinPath;
+		this.meleeIsoPath = DEFAULT_SETTINGS.meleeIsoPath;
 		this.watchForGames = DEFAULT_SETTINGS.watchForGames;
 		this.createClipHotkey = DEFAULT_SETTINGS.createClipHotkey;
 		this.clipDuration = DEFAULT_SETTINGS.clipDuration;
+		this.maxClipsSizeGb = DEFAULT_SETTINGS.maxClipsSizeGb;
 		this.slippiCode = DEFAULT_SETTINGS.slippiCode;
+		this.overlayServer = DEFAULT_SETTINGS.overlayServer;
+		this.overlayServerPort = DEFAULT_SETTINGS.overlayServerPort;
+		this.enableDiscordPresence = DEFAULT_SETTINGS.enableDiscordPresence;
+		this.discordPresenceShowOpponent = DEFAULT_SETTINGS.discordPresenceShowOpponent;
+		this.launchOnStartup = DEFAULT_SETTINGS.launchOnStartup;
+		this.startMinimized = DEFAULT_SETTINGS.startMinimized;
+		this.restoreStateOnLaunch = DEFAULT_SETTINGS.restoreStateOnLaunch;
+		this.readinessCheckOnGameWindow = DEFAULT_SETTINGS.readinessCheckOnGameWindow;
+		this.autoHighlights = DEFAULT_SETTINGS.autoHighlights;
+		this.autoHighlightsCount = DEFAULT_SETTINGS.autoHighlightsCount;
+		this.autoWeeklyReport = DEFAULT_SETTINGS.autoWeeklyReport;
+		this.minGameSecondsForStats = DEFAULT_SETTINGS.minGameSecondsForStats;
+		this.warmupGames = DEFAULT_SETTINGS.warmupGames;
+		this.sessionGapMinutes = DEFAULT_SETTINGS.sessionGapMinutes;
+		this.recordCpuMatches = DEFAULT_SETTINGS.recordCpuMatches;
+		this.countUnfinishedGames = DEFAULT_SETTINGS.countUnfinishedGames;
+		this.replayOnlyMode = DEFAULT_SETTINGS.replayOnlyMode;
+		this.watchFilters = DEFAULT_SETTINGS.watchFilters;
+		this.jobsWhileRecording = DEFAULT_SETTINGS.jobsWhileRecording;
+		this.hardwareDecode = DEFAULT_SETTINGS.hardwareDecode;
+		this.quietHours = DEFAULT_SETTINGS.quietHours;
 	}
 
 	/** Get all settings from persistent store */
@@ -151,12 +463,63 @@ class SettingsStore {
 			theme: ((await this.store.get("theme")) as Settings["theme"]) ?? DEFAULT_SETTINGS.theme,
 			recordingPath: ((await this.store.get("recordingPath")) as string) ?? DEFAULT_SETTINGS.recordingPath,
 			recordingQuality: ((await this.store.get("recordingQuality")) as Settings["recordingQuality"]) ?? DEFAULT_SETTINGS.recordingQuality,
+			recordingContainer: ((await this.store.get("recordingContainer")) as Settings["recordingContainer"]) ?? DEFAULT_SETTINGS.recordingContainer,
+			autoCropGameViewport: ((await this.store.get("autoCropGameViewport")) as boolean) ?? DEFAULT_SETTINGS.autoCropGameViewport,
 			autoStartRecording: ((await this.store.get("autoStartRecording")) as boolean) ?? DEFAULT_SETTINGS.autoStartRecording,
+			replayQuietPeriodMs: ((await this.store.get("replayQuietPeriodMs")) as number) ?? DEFAULT_SETTINGS.replayQuietPeriodMs,
+			watcherPollIntervalMs: ((await this.store.get("watcherPollIntervalMs")) as number) ?? DEFAULT_SETTINGS.watcherPollIntervalMs,
+			stopRecordingDelaySeconds: ((await this.store.get("stopRecordingDelaySeconds")) as number) ?? DEFAULT_SETTINGS.stopRecordingDelaySeconds,
+			endRecordingDelaySeconds: ((await this.store.get("endRecordingDelaySeconds")) as number) ?? DEFAULT_SETTINGS.endRecordingDelaySeconds,
+			recordingGranularity: ((await this.store.get("recordingGranularity")) as Settings["recordingGranularity"]) ?? DEFAULT_SETTINGS.recordingGranularity,
+			setEndTimeoutMinutes: ((await this.store.get("setEndTimeoutMinutes")) as number) ?? DEFAULT_SETTINGS.setEndTimeoutMinutes,
+			preRollMode: ((await this.store.get("preRollMode")) as boolean) ?? DEFAULT_SETTINGS.preRollMode,
+			preRollSeconds: ((await this.store.get("preRollSeconds")) as number) ?? DEFAULT_SETTINGS.preRollSeconds,
+			captureIssueThreshold: ((await this.store.get("captureIssueThreshold")) as number) ?? DEFAULT_SETTINGS.captureIssueThreshold,
+			recorderBackend: ((await this.store.get("recorderBackend")) as Settings["recorderBackend"]) ?? DEFAULT_SETTINGS.recorderBackend,
+			obsHost: ((await this.store.get("obsHost")) as string) ?? DEFAULT_SETTINGS.obsHost,
+			obsPort: ((await this.store.get("obsPort")) as number) ?? DEFAULT_SETTINGS.obsPort,
+			obsPassword: ((await this.store.get("obsPassword")) as string) ?? DEFAULT_SETTINGS.obsPassword,
+			postRecordingHook: ((await this.store.get("postRecordingHook")) as string) ?? DEFAULT_SETTINGS.postRecordingHook,
+			writeSidecars: ((await this.store.get("writeSidecars")) as boolean) ?? DEFAULT_SETTINGS.writeSidecars,
+			renameRecordings: ((await this.store.get("renameRecordings")) as boolean) ?? DEFAULT_SETTINGS.renameRecordings,
+			recordingNameTemplate:
+				((await this.store.get("recordingNameTemplate")) as string) ?? DEFAULT_SETTINGS.recordingNameTemplate,
+			recordingsScanDepth: ((await this.store.get("recordingsScanDepth")) as number) ?? DEFAULT_SETTINGS.recordingsScanDepth,
+			recordingsScanIgnore:
+				((await this.store.get("recordingsScanIgnore")) as string[]) ?? DEFAULT_SETTINGS.recordingsScanIgnore,
 			slippiPath: ((await this.store.get("slippiPath")) as string) ?? DEFAULT_SETTINGS.slippiPath,
+			playbackDolphinPath: ((await this.store.get("playbackDolphinPath")) as string) ?? DEFAULT_SETTINGS.playbackDolphinPath,
+			meleeIsoPath: ((await this.store.get("meleeIsoPath")) as string) ?? DEFAULT_SETTINGS.meleeIsoPath,
 			watchForGames: ((await this.store.get("watchForGames")) as boolean) ?? DEFAULT_SETTINGS.watchForGames,
 			createClipHotkey: ((await this.store.get("createClipHotkey")) as string) ?? DEFAULT_SETTINGS.createClipHotkey,
 			clipDuration: ((await this.store.get("clipDuration")) as number) ?? DEFAULT_SETTINGS.clipDuration,
+			maxClipsSizeGb: ((await this.store.get("maxClipsSizeGb")) as number) ?? DEFAULT_SETTINGS.maxClipsSizeGb,
 			slippiCode: ((await this.store.get("slippiCode")) as string) ?? DEFAULT_SETTINGS.slippiCode,
+			overlayServer: ((await this.store.get("overlayServer")) as boolean) ?? DEFAULT_SETTINGS.overlayServer,
+			overlayServerPort: ((await this.store.get("overlayServerPort")) as number) ?? DEFAULT_SETTINGS.overlayServerPort,
+			enableDiscordPresence: ((await this.store.get("enableDiscordPresence")) as boolean) ?? DEFAULT_SETTINGS.enableDiscordPresence,
+			discordPresenceShowOpponent: ((await this.store.get("discordPresenceShowOpponent")) as boolean) ?? DEFAULT_SETTINGS.discordPresenceShowOpponent,
+			launchOnStartup: ((await this.store.get("launchOnStartup")) as boolean) ?? DEFAULT_SETTINGS.launchOnStartup,
+			startMinimized: ((await this.store.get("startMinimized")) as boolean) ?? DEFAULT_SETTINGS.startMinimized,
+			restoreStateOnLaunch: ((await this.store.get("restoreStateOnLaunch")) as boolean) ?? DEFAULT_SETTINGS.restoreStateOnLaunch,
+			readinessCheckOnGameWindow: ((await this.store.get("readinessCheckOnGameWindow")) as boolean) ?? DEFAULT_SETTINGS.readinessCheckOnGameWindow,
+			autoHighlights: ((await this.store.get("autoHighlights")) as boolean) ?? DEFAULT_SETTINGS.autoHighlights,
+			autoHighlightsCount: ((await this.store.get("autoHighlightsCount")) as number) ?? DEFAULT_SETTINGS.autoHighlightsCount,
+			minGameSecondsForStats: ((await this.store.get("minGameSecondsForStats")) as number) ?? DEFAULT_SETTINGS.minGameSecondsForStats,
+			warmupGames: ((await this.store.get("warmupGames")) as number) ?? DEFAULT_SETTINGS.warmupGames,
+			sessionGapMinutes: ((await this.store.get("sessionGapMinutes")) as number) ?? DEFAULT_SETTINGS.sessionGapMinutes,
+			recordCpuMatches: ((await this.store.get("recordCpuMatches")) as boolean) ?? DEFAULT_SETTINGS.recordCpuMatches,
+			countUnfinishedGames: ((await this.store.get("countUnfinishedGames")) as boolean) ?? DEFAULT_SETTINGS.countUnfinishedGames,
+			replayOnlyMode: ((await this.store.get("replayOnlyMode")) as boolean) ?? DEFAULT_SETTINGS.replayOnlyMode,
+			watchFilters: {
+				...DEFAULT_SETTINGS.watchFilters,
+				...((await this.store.get("watchFilters")) as Partial<WatchFilters> | undefined),
+			},
+			jobsWhileRecording:
+				((await this.store.get("jobsWhileRecording")) as Settings["jobsWhileRecording"]) ??
+				DEFAULT_SETTINGS.jobsWhileRecording,
+			hardwareDecode: ((await this.store.get("hardwareDecode")) as boolean) ?? DEFAULT_SETTINGS.hardwareDecode,
+			quietHours: ((await this.store.get("quietHours")) as QuietHours | null) ?? DEFAULT_SETTINGS.quietHours,
 		};
 	}
 
@@ -178,12 +541,81 @@ class SettingsStore {
 			case "recordingQuality":
 				this.recordingQuality = value as Settings["recordingQuality"];
 				break;
+			case "recordingContainer":
+				this.recordingContainer = value as Settings["recordingContainer"];
+				break;
+			case "autoCropGameViewport":
+				this.autoCropGameViewport = value as boolean;
+				break;
 			case "autoStartRecording":
 				this.autoStartRecording = value as boolean;
 				break;
+			case "replayQuietPeriodMs":
+				this.replayQuietPeriodMs = value as number;
+				break;
+			case "watcherPollIntervalMs":
+				this.watcherPollIntervalMs = value as number;
+				break;
+			case "stopRecordingDelaySeconds":
+				this.stopRecordingDelaySeconds = value as number;
+				break;
+			case "endRecordingDelaySeconds":
+				this.endRecordingDelaySeconds = value as number;
+				break;
+			case "recordingGranularity":
+				this.recordingGranularity = value as Settings["recordingGranularity"];
+				break;
+			case "setEndTimeoutMinutes":
+				this.setEndTimeoutMinutes = value as number;
+				break;
+			case "preRollMode":
+				this.preRollMode = value as boolean;
+				break;
+			case "preRollSeconds":
+				this.preRollSeconds = value as number;
+				break;
+			case "captureIssueThreshold":
+				this.captureIssueThreshold = value as number;
+				break;
+			case "recorderBackend":
+				this.recorderBackend = value as Settings["recorderBackend"];
+				break;
+			case "obsHost":
+				this.obsHost = value as string;
+				break;
+			case "obsPort":
+				this.obsPort = value as number;
+				break;
+			case "obsPassword":
+				this.obsPassword = value as string;
+				break;
+			case "postRecordingHook":
+				this.postRecordingHook = value as string;
+				break;
+			case "writeSidecars":
+				this.writeSidecars = value as boolean;
+				break;
+			case "renameRecordings":
+				this.renameRecordings = value as boolean;
+				break;
+			case "recordingNameTemplate":
+				this.recordingNameTemplate = value as string;
+				break;
+			case "recordingsScanDepth":
+				this.recordingsScanDepth = value as number;
+				break;
+			case "recordingsScanIgnore":
+				this.recordingsScanIgnore = value as string[];
+				break;
 			case "slippiPath":
 				this.slippiPath = value as string;
 				break;
+			case "playbackDolphinPath":
+				this.playbackDolphinPath = value as string;
+				break;
+			case "meleeIsoPath":
+				this.meleeIsoPath = value as string;
+				break;
 			case "watchForGames":
 				this.watchForGames = value as boolean;
 				break;
@@ -193,9 +625,72 @@ class SettingsStore {
 			case "clipDuration":
 				this.clipDuration = value as number;
 				break;
+			case "maxClipsSizeGb":
+				this.maxClipsSizeGb = value as number;
+				break;
 			case "slippiCode":
 				this.slippiCode = value as string;
 				break;
+			case "overlayServer":
+				this.overlayServer = value as boolean;
+				break;
+			case "overlayServerPort":
+				this.overlayServerPort = value as number;
+				break;
+			case "enableDiscordPresence":
+				this.enableDiscordPresence = value as boolean;
+				break;
+			case "discordPresenceShowOpponent":
+				this.discordPresenceShowOpponent = value as boolean;
+				break;
+			case "launchOnStartup":
+				this.launchOnStartup = value as boolean;
+				break;
+			case "startMinimized":
+				this.startMinimized = value as boolean;
+				break;
+			case "restoreStateOnLaunch":
+				this.restoreStateOnLaunch = value as boolean;
+				break;
+			case "readinessCheckOnGameWindow":
+				this.readinessCheckOnGameWindow = value as boolean;
+				break;
+			case "autoHighlights":
+				this.autoHighlights = value as boolean;
+				break;
+			case "autoHighlightsCount":
+				this.autoHighlightsCount = value as number;
+				break;
+			case "minGameSecondsForStats":
+				this.minGameSecondsForStats = value as number;
+				break;
+			case "warmupGames":
+				this.warmupGames = value as number;
+				break;
+			case "sessionGapMinutes":
+				this.sessionGapMinutes = value as number;
+				break;
+			case "recordCpuMatches":
+				this.recordCpuMatches = value as boolean;
+				break;
+			case "countUnfinishedGames":
+				this.countUnfinishedGames = value as boolean;
+				break;
+			case "replayOnlyMode":
+				this.replayOnlyMode = value as boolean;
+				break;
+			case "watchFilters":
+				this.watchFilters = value as WatchFilters;
+				break;
+			case "jobsWhileRecording":
+				this.jobsWhileRecording = value as Settings["jobsWhileRecording"];
+				break;
+			case "hardwareDecode":
+				this.hardwareDecode = value as boolean;
+				break;
+			case "quietHours":
+				this.quietHours = value as QuietHours | null;
+				break;
 		}
 		
 		// Persist to store if available
@@ -216,12 +711,56 @@ class SettingsStore {
 			"theme",
 			"recordingPath",
 			"recordingQuality",
+			"recordingContainer",
+			"autoCropGameViewport",
 			"autoStartRecording",
+			"replayQuietPeriodMs",
+			"watcherPollIntervalMs",
+			"stopRecordingDelaySeconds",
+			"endRecordingDelaySeconds",
+			"recordingGranularity",
+			"setEndTimeoutMinutes",
+			"preRollMode",
+			"preRollSeconds",
+			"captureIssueThreshold",
+			"recorderBackend",
+			"obsHost",
+			"obsPort",
+			"obsPassword",
+			"postRecordingHook",
+			"writeSidecars",
+			"renameRecordings",
+			"recordingNameTemplate",
+			"recordingsScanDepth",
+			"recordingsScanIgnore",
 			"slippiPath",
+			"playbackDolphinPath",
+			"meleeIsoPath",
 			"watchForGames",
 			"createClipHotkey",
 			"clipDuration",
+			"maxClipsSizeGb",
 			"slippiCode",
+			"overlayServer",
+			"overlayServerPort",
+			"enableDiscordPresence",
+			"discordPresenceShowOpponent",
+			"launchOnStartup",
+			"startMinimized",
+			"restoreStateOnLaunch",
+			"readinessCheckOnGameWindow",
+			"autoHighlights",
+			"autoHighlightsCount",
+			"minGameSecondsForStats",
+			"warmupGames",
+			"sessionGapMinutes",
+			"recordCpuMatches",
+			"countUnfinishedGames",
+			"replayOnlyMode",
+			"watchFilters",
+			"jobsWhileRecording",
+			"hardwareDecode",
+			"quietHours",
 		];
 
 		for (const key of keys) {
@@ -0,0 +1,184 @@
+/**
+ * Stats jobs store for running batch .slp stats calculation in the background.
+ * Stats are computed by slippi-js one replay at a time, so a job reports progress
+ * per file and can be cancelled between files.
+ *
+ * Jobs live here rather than in the backend because the backend computes no
+ * stats: there is no calculate_game_stats or reparse_library command to hand a
+ * cancellation token to. A cancelled job stops before its next file; the replay
+ * slippi-js is already parsing finishes first.
+ *
+ * @example
+ * // Start a job - returns immediately with the job ID
+ * const jobId = statsJobs.start("Historical sync", slpFiles, async (slpPath) => {
+ *   return await parseAndSaveSlippiStats(slpPath, idFor(slpPath)) ? "done" : "failed";
+ * });
+ *
+ * // Show progress reactively
+ * const job = statsJobs.get(jobId);
+ * console.log(`${job?.current}/${job?.total}`);
+ *
+ * // Cancel it (stops before the next file)
+ * statsJobs.cancel(jobId);
+ *
+ * @module stores/stats-jobs
+ */
+
+/** Lifecycle state of a stats job */
+export type StatsJobStatus = "running" | "completed" | "cancelled" | "failed";
+
+/** Outcome of processing one item in a job */
+export type StatsJobItemResult = "done" | "skipped" | "failed";
+
+/** Progress snapshot for a stats job */
+export interface StatsJob {
+	/** Unique job ID */
+	id: string;
+	/** Human-readable description of the job */
+	label: string;
+	/** Current lifecycle state */
+	status: StatsJobStatus;
+	/** Number of items to process */
+	total: number;
+	/** Number of items processed so far (including skipped and failed) */
+	current: number;
+	/** Items skipped (e.g. already synced) */
+	skipped: number;
+	/** Items that failed to parse */
+	failed: number;
+	/** Error that stopped the job, if status is "failed" */
+	error: string | null;
+}
+
+/** How long finished jobs stay visible before being cleared */
+const FINISHED_JOB_TTL_MS = 10_000;
+
+/**
+ * Tracks running stats jobs and their cancellation signals.
+ */
+class StatsJobsStore {
+	/** All known jobs, keyed by ID */
+	jobs = $state<Record<string, StatsJob>>({});
+
+	/** Abort controllers for running jobs */
+	private controllers = new Map<string, AbortController>();
+
+	/** Whether any job is currently running */
+	get isRunning(): boolean {
+		return Object.values(this.jobs).some((job) => job.status === "running");
+	}
+
+	/**
+	 * Get a job by ID.
+	 * @param jobId - ID returned by start()
+	 */
+	get(jobId: string | null | undefined): StatsJob | undefined {
+		return jobId ? this.jobs[jobId] : undefined;
+	}
+
+	/**
+	 * Start a job in the background.
+	 * @param label - Description for logs and UI
+	 * @param items - Items to process, in order
+	 * @param processItem - Processes one item; called sequentially
+	 * @param onFinished - Called once the job completes, is cancelled, or fails
+	 * @returns The job ID (immediately, before any item is processed)
+	 */
+	start<T>(
+		label: string,
+		items: T[],
+		processItem: (item: T, signal: AbortSignal) => Promise<StatsJobItemResult>,
+		onFinished?: (job: StatsJob) => void | Promise<void>
+	): string {
+		const id = crypto.randomUUID();
+		const controller = new AbortController();
+		this.controllers.set(id, controller);
+
+		this.jobs[id] = {
+			id,
+			label,
+			status: "running",
+			total: items.length,
+			current: 0,
+			skipped: 0,
+			failed: 0,
+			error: null,
+		};
+
+		console.log(`[StatsJobs] Started job ${id} (${label}) with ${items.length} items`);
+		void this.run(id, items, processItem, controller.signal, onFinished);
+
+		return id;
+	}
+
+	/**
+	 * Cancel a running job. The item currently being parsed finishes first.
+	 * @param jobId - ID returned by start()
+	 * @returns True if the job was running
+	 */
+	cancel(jobId: string): boolean {
+		const controller = this.controllers.get(jobId);
+		if (!controller) {
+			return false;
+		}
+
+		controller.abort();
+		console.log(`[StatsJobs] Cancellation requested for job ${jobId}`);
+		return true;
+	}
+
+	/** Process items sequentially, checking for cancellation between items */
+	private async run<T>(
+		id: string,
+		items: T[],
+		processItem: (item: T, signal: AbortSignal) => Promise<StatsJobItemResult>,
+		signal: AbortSignal,
+		onFinished?: (job: StatsJob) => void | Promise<void>
+	) {
+		const job = this.jobs[id];
+
+		try {
+			for (const item of items) {
+				if (signal.aborted) {
+					break;
+				}
+
+				try {
+					const result = await processItem(item, signal);
+					if (result === "skipped") {
+						job.skipped++;
+					} else if (result === "failed") {
+						job.failed++;
+					}
+				} catch (error) {
+					console.warn(`[StatsJobs] Job ${id} item failed:`, error);
+					job.failed++;
+				}
+
+				job.current++;
+			}
+
+			job.status = signal.aborted ? "cancelled" : "completed";
+		} catch (error) {
+			job.status = "failed";
+			job.error = error instanceof Error ? error.message : String(error);
+		} finally {
+			this.controllers.delete(id);
+		}
+
+		console.log(
+			`[StatsJobs] Job ${id} ${job.status}: ${job.current}/${job.total} processed, ${job.skipped} skipped, ${job.failed} failed`
+		);
+
+		try {
+			await onFinished?.(job);
+		} finally {
+			setTimeout(() => {
+				delete this.jobs[id];
+			}, FINISHED_JOB_TTL_MS);
+		}
+	}
+}
+
+/** Singleton stats jobs store instance */
+export const statsJobs = new StatsJobsStore();
@@ -40,5 +40,7 @@ export interface ClipSession {
 	slp_path: string | null;
 	/** Slippi metadata from the associated replay file */
 	slippi_metadata: SlippiMetadata | null;
+	/** Starred, so never rotated out by the Clips folder limit */
+	is_favorite: boolean;
 }
 
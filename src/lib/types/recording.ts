@@ -90,6 +90,25 @@ export interface SlippiMetadata {
 	played_on: string | null;
 	/** Total frames in the recording */
 	total_frames: number;
+	/** Frames the game was paused for, null if not looked for */
+	paused_frames?: number | null;
+	/** Replay format version (e.g. "3.16.0"), null if it wasn't recorded */
+	replay_version?: string | null;
+	/** What the replay's format records, null if the version is unknown */
+	capabilities?: ReplayCapabilities | null;
+}
+
+/**
+ * Optional data a replay has, depending on its format version.
+ * Stats that need missing data are unknown (null), not zero.
+ */
+export interface ReplayCapabilities {
+	/** L-cancel counts are known */
+	l_cancel_status: boolean;
+	/** Display names and connect codes are known */
+	netplay_names: boolean;
+	/** Written by a Slippi newer than the app knows; stats may be incomplete */
+	newer_than_supported: boolean;
 }
 
 /**
@@ -111,10 +130,42 @@ export interface RecordingSession {
 	thumbnail_path: string | null;
 	/** Recording duration in seconds */
 	duration: number | null;
+	/** Duration in seconds without the time the game was paused, null if unknown */
+	unpaused_duration?: number | null;
 	/** Video file size in bytes */
 	file_size: number | null;
 	/** Parsed Slippi metadata, null if .slp file not available */
 	slippi_metadata: SlippiMetadata | null;
+	/** Character I played, null if none of the players is one of my connect codes */
+	my_character: number | null;
+	/** My result in this game, null if I didn't play or the winner is unknown */
+	my_result: "win" | "loss" | null;
+	/** One of the first games of a play session (left out of stats with excludeWarmups) */
+	is_warmup: boolean;
+	/** Opponent's connect code, null if I didn't play */
+	opponent_code: string | null;
+	/** Frames dropped while recording, null if not tracked */
+	dropped_frame_estimate: number | null;
+	/** Longest gap between captured frames in milliseconds, null if not tracked */
+	longest_stall_ms: number | null;
+	/** Settings profile active when it was recorded, null if none */
+	profile: string | null;
+	/** Probed and found to have no audible sound (e.g. the wrong audio device) */
+	is_silent: boolean;
+	/** Re-encoded to 720p HEVC to save space */
+	is_archived: boolean;
+	/** Why the replay can't be parsed ('truncated', 'unsupported_version' or 'corrupt'), null if it can */
+	parse_error: string | null;
+	/** Due to have its replay's metadata and stats extracted again */
+	needs_reparse: boolean;
+	/** How many milliseconds the video trails its replay, null if unknown */
+	video_offset_ms: number | null;
+	/** Recording this one was trimmed or cropped from, null if it wasn't */
+	source_recording_id: string | null;
+	/** Starred clip, kept over the Clips folder limit */
+	is_favorite: boolean;
+	/** Only the game's start has been read: players are provisional until its stats are saved */
+	stats_pending: boolean;
 }
 
 /**
@@ -181,3 +232,71 @@ export interface DeathEvent extends GameEvent {
 	player_tag: string;
 }
 
+/** Recording quality preset names (match the Rust RecordingQuality enum) */
+export type RecordingQuality = 'low' | 'medium' | 'high' | 'ultra';
+
+/**
+ * Payload of the recording-performance-warning event.
+ * Emitted when a recording drops too many frames at its current preset.
+ */
+export interface PerformanceWarning {
+	/** Output path of the affected recording */
+	outputPath: string;
+	/** Preset the recording is using */
+	quality: RecordingQuality;
+	/** Fraction of frames dropped over the last sample window (0-1) */
+	droppedRatio: number;
+	/** Next preset down, or null if already at the lowest */
+	suggestedQuality: RecordingQuality | null;
+}
+
+/** Result of the stop_recording command */
+export interface RecordingResult {
+	/** Path of the finished video file */
+	outputPath: string;
+	/** Frames dropped compared to 60 fps, null if not tracked */
+	droppedFrameEstimate: number | null;
+	/** Longest gap between captured frames in milliseconds, null if not tracked */
+	longestStallMs: number | null;
+	/** Recording length in seconds, null if not tracked */
+	durationSecs: number | null;
+	/** Quality, backend and settings profile it was recorded with */
+	capture: CaptureSettings | null;
+}
+
+/** What a recording was captured with */
+export interface CaptureSettings {
+	quality: RecordingQuality;
+	backend: "native" | "obs";
+	/** Settings profile applied when it started */
+	profile: string | null;
+}
+
+/** Payload of the recording-hook-finished event */
+export interface PostRecordingHookResult {
+	/** Recording the hook ran for */
+	videoPath: string;
+	/** Whether the hook was a webhook URL or a local command */
+	kind: "webhook" | "command";
+	success: boolean;
+	/** Error, or the (truncated) hook output */
+	message: string | null;
+	elapsedMs: number;
+}
+
+/** Benchmark result for a single quality preset */
+export interface PresetBenchmark {
+	quality: RecordingQuality;
+	achievedFps: number;
+	droppedFrames: number;
+	droppedRatio: number;
+	/** Set if the preset couldn't be benchmarked */
+	error: string | null;
+}
+
+/** Result of the run_recording_benchmark command */
+export interface BenchmarkResult {
+	presets: PresetBenchmark[];
+	/** Highest preset that stayed under the dropped-frame threshold */
+	recommended: RecordingQuality;
+}
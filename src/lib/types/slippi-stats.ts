@@ -45,11 +45,12 @@ export interface PlayerStatsForDB {
 	throwCount: number;
 	groundTechCount: number;
 	wallTechCount: number;
-	wallJumpTechCount: number;
+	/** Null when not computed (slippi-js doesn't count them) */
+	wallJumpTechCount: number | null;
 
-	// L-Cancel stats
-	lCancelSuccessCount: number;
-	lCancelFailCount: number;
+	// L-Cancel stats, null when the replay is too old to record them
+	lCancelSuccessCount: number | null;
+	lCancelFailCount: number | null;
 
 	// Final stock state
 	stocksRemaining: number;
@@ -83,6 +84,13 @@ export interface ConversionForDisplay {
 	didKill: boolean;
 }
 
+/**
+ * Version of the stats calculation in services/slippi-stats.
+ * Bump whenever computed values change (detector fixes, new counts) so rows
+ * saved by older versions show up as outdated and can be recomputed.
+ */
+export const STATS_VERSION = 5;
+
 /**
  * Complete game stats to save to the database.
  * Sent to Rust backend after parsing.
@@ -96,6 +104,8 @@ export interface GameStatsForDB {
 	stage: number;
 	gameDuration: number;
 	totalFrames: number;
+	/** Frames the game was paused for (left out of per-minute rates) */
+	totalPausedFrames: number;
 	isPal: boolean;
 	playedOn: string | null;
 	matchId: string | null;
@@ -108,7 +118,36 @@ export interface GameStatsForDB {
 	winnerIndex: number | null;
 	loserIndex: number | null;
 	gameEndMethod: string | null;
+	/** Whether the replay reaches Game End; unfinished games are left out of stats unless counted */
+	completed: boolean;
+
+	/** STATS_VERSION that computed these stats */
+	statsVersion: number;
+	/** Replay format version from Game Start (e.g. "3.16.0"), null if unknown */
+	replayVersion: string | null;
 
 	// Player stats (array of 2+ players)
 	players: PlayerStatsForDB[];
+
+	/** Combos (conversions), scored as highlights by the backend */
+	combos: ComboForDB[];
+}
+
+/**
+ * A combo (slippi-js conversion) to save to the database.
+ */
+export interface ComboForDB {
+	/** Player who performed the combo */
+	playerIndex: number;
+	/** Player who was hit */
+	opponentIndex: number;
+	startFrame: number;
+	endFrame: number;
+	/** Opponent's percent when the combo started */
+	startPercent: number;
+	endPercent: number;
+	moveCount: number;
+	/** Number of different moves used */
+	distinctMoves: number;
+	didKill: boolean;
 }
@@ -19,25 +19,89 @@ import { toast } from "svelte-sonner";
 
 /**
  * Error structure returned by Tauri commands from Rust backend.
+ * `code` is stable across releases; `message` is English detail for logs.
  */
 interface TauriError {
 	message: string;
 	name: string;
+	code?: string;
+	details?: {
+		stderrTail?: string | null;
+		kind?: string;
+		reason?: string;
+		needed?: number;
+		available?: number;
+		identifier?: string;
+		path?: string;
+		crop?: { x: number; y: number; width: number; height: number };
+		videoWidth?: number;
+		videoHeight?: number;
+	};
 }
 
 /**
- * User-friendly error messages for common Tauri errors
+ * What to tell the user for an error code, and what they can do about it
  */
-const ERROR_MESSAGES: Record<string, string> = {
-	initializationError: "Failed to initialize",
-	recordingFailed: "Recording operation failed",
-	windowNotFound: "Dolphin window not found",
-	unsupportedPlatform: "This feature is not supported on your platform",
-	watchError: "Failed to watch folder",
-	invalidPath: "Invalid file path",
-	permissionError: "Permission denied"
+interface ErrorCopy {
+	message: string;
+	action?: string;
+}
+
+/**
+ * Messages per locale, keyed by the error codes from the Rust backend.
+ * Locales without a translation for a code fall back to English.
+ */
+const ERROR_COPY: Record<string, Record<string, ErrorCopy>> = {
+	en: {
+		file_not_found: { message: "The file couldn't be found", action: "It may have been moved or deleted" },
+		permission_denied: { message: "Permission denied", action: "Check that Buckwheat can access this folder" },
+		disk_full: { message: "Not enough disk space", action: "Free up space or move recordings to another drive in Settings → Recording" },
+		io_failed: { message: "Couldn't read or write a file" },
+		invalid_text: { message: "A file contained unreadable text" },
+		watch_failed: { message: "Failed to watch folder", action: "Check the Slippi replay folder in Settings" },
+		invalid_path: { message: "Invalid file path" },
+		unsupported_platform: { message: "This feature is not supported on your platform" },
+		initialization_failed: { message: "Failed to initialize" },
+		window_not_found: { message: "Dolphin window not found", action: "Start Dolphin, or pick the game window in Settings" },
+		recording_failed: { message: "Recording operation failed" },
+		obs_failed: { message: "OBS didn't respond", action: "Check that OBS is running and its WebSocket server is enabled" },
+		database_migration_failed: { message: "Couldn't update the library database", action: "Restart Buckwheat; if it keeps happening, export diagnostics from Settings" },
+		ffmpeg_failed: { message: "FFmpeg couldn't process the video", action: "The video may be damaged or still being written" },
+		database_busy: { message: "The library is busy", action: "Try again in a moment" },
+		database_corrupt: { message: "The library database is damaged", action: "Export diagnostics from Settings and rebuild the library" },
+		database_conflict: { message: "That conflicts with something already in the library" },
+		database_failed: { message: "The library database reported an error" },
+		not_found: { message: "That recording no longer exists", action: "Refresh the library" },
+		no_audio: { message: "That video has no audio" },
+		crop_out_of_bounds: { message: "The crop doesn't fit in the video", action: "Draw the crop inside the video's frame" },
+		media_unreadable: { message: "That video can't be read", action: "It may be corrupt or still being written" },
+		replay_unreadable: { message: "The replay can't be read", action: "It may be corrupt or from a newer Slippi version" },
+		window_capture_failed: { message: "Couldn't capture the game window", action: "Make sure Dolphin isn't minimized, or record the whole screen instead" },
+		window_gone: { message: "That window has closed", action: "Refresh the window list" },
+		volume_not_mounted: { message: "The drive with this folder isn't connected", action: "Reconnect the drive, or choose another folder in Settings" },
+		path_not_writable: { message: "Buckwheat can't write to this folder", action: "Check its permissions, or choose another folder in Settings" },
+		not_a_directory: { message: "A file is in the way of this folder", action: "Choose another folder in Settings" },
+		unknown_job: { message: "There's no background job by that name", action: "Refresh the job list" }
+	}
 };
 
+function errorCopy(code: string): ErrorCopy | undefined {
+	const locale = typeof navigator !== "undefined" ? navigator.language.split("-")[0] : "en";
+	return ERROR_COPY[locale]?.[code] ?? ERROR_COPY.en[code];
+}
+
+function isTauriError(error: unknown): error is TauriError {
+	return typeof error === "object" && error !== null && "name" in error && "message" in error;
+}
+
+/**
+ * The machine-readable code of a command error, if it has one.
+ * @param error - Error from a Tauri command
+ */
+export function getErrorCode(error: unknown): string | undefined {
+	return isTauriError(error) ? error.code : undefined;
+}
+
 /**
  * Extract a user-friendly message from various error types.
  * @param error - Error from Tauri command, JS Error, or unknown
@@ -45,21 +109,12 @@ const ERROR_MESSAGES: Record<string, string> = {
  */
 function getErrorMessage(error: TauriError | Error | unknown): string {
 	// Handle Tauri error objects
-	if (typeof error === "object" && error !== null && "name" in error && "message" in error) {
-		const tauriError = error as TauriError;
-		
-		// Check if it's a permission error
-		if (tauriError.message.toLowerCase().includes("permission")) {
-			return `Permission Error: ${tauriError.message}`;
+	if (isTauriError(error)) {
+		const copy = error.code ? errorCopy(error.code) : undefined;
+		if (copy) {
+			return `${copy.message}: ${error.message}`;
 		}
-		
-		// Use custom message if available
-		const customMessage = ERROR_MESSAGES[tauriError.name];
-		if (customMessage) {
-			return `${customMessage}: ${tauriError.message}`;
-		}
-		
-		return tauriError.message;
+		return error.message;
 	}
 	
 	// Handle regular Error objects
@@ -71,6 +126,20 @@ function getErrorMessage(error: TauriError | Error | unknown): string {
 	return String(error);
 }
 
+/**
+ * Suggested fix for an error, with the detail the backend sent
+ */
+function getErrorDescription(error: unknown): string | undefined {
+	if (!isTauriError(error) || !error.code) {
+		return undefined;
+	}
+	const copy = errorCopy(error.code);
+	if (!copy) {
+		return undefined;
+	}
+	return [copy.action, error.details?.reason].filter(Boolean).join(". ") || undefined;
+}
+
 /**
  * Handle Tauri errors with user feedback.
  * Logs the error to console and shows a toast notification.
@@ -95,6 +164,7 @@ export function handleTauriError(
 	console.error(fullMessage, error);
 	
 	toast.error(fullMessage, {
+		description: getErrorDescription(error),
 		duration: 5000
 	});
 }
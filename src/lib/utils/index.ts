@@ -37,5 +37,7 @@ export {
 	parseSlippiFileWithCache,
 	clearSlippiCache,
 	removeFromCache,
+	pruneMissingFromCache,
+	getSlippiCacheStats,
 } from './slippi';
 
@@ -15,7 +15,7 @@
  */
 
 import { SlippiGame } from "@slippi/slippi-js";
-import { readFile } from "@tauri-apps/plugin-fs";
+import { exists, readFile } from "@tauri-apps/plugin-fs";
 import type { SlippiMetadata, SlippiPlayer } from "$lib/types/recording";
 import { CharacterId, StageId } from "$lib/types/recording";
 
@@ -38,55 +38,90 @@ export async function parseSlippiFile(slpPath: string): Promise<SlippiMetadata |
 		);
 
 		// Parse with slippi-js
-		const game = new SlippiGame(arrayBuffer);
-		const settings = game.getSettings();
-		const metadata = game.getMetadata();
-
-		if (!settings) {
-			console.warn("No settings found in .slp file:", slpPath);
-			return null;
-		}
-
-		// Extract player information (using snake_case to match Rust backend)
-		const players: SlippiPlayer[] = settings.players
-			.filter((p) => p !== null) // Filter out null players
-			.map((player) => ({
-				character_id: player.characterId as CharacterId,
-				character_color: player.characterColor || 0,
-				player_tag: player.nametag || `Player ${player.playerIndex + 1}`,
-				port: player.port,
-			}));
-
-		// Get all characters played
-		const characters = players.map((p) => p.character_id);
-
-		// Calculate game duration from metadata or frames
-		const lastFrame = metadata?.lastFrame || 0;
-		const gameDuration = lastFrame > 0 ? lastFrame : 0;
-
-		// Determine winner (if game ended normally)
-		const gameEnd = game.getGameEnd();
-		const winnerPort = gameEnd?.gameEndMethod === 2 ? gameEnd.lrasInitiatorIndex : null;
-
-		return {
-			characters,
-			stage: settings.stageId as StageId,
-			players,
-			game_duration: gameDuration,
-			start_time: metadata?.startAt || new Date().toISOString(),
-			is_pal: settings.isPAL || false,
-			winner_port: winnerPort !== null ? winnerPort + 1 : null, // Convert to 1-based port
-			played_on: metadata?.playedOn || null,
-			total_frames: lastFrame,
-		};
+		return buildSlippiMetadata(new SlippiGame(arrayBuffer), slpPath);
 	} catch (error) {
 		console.error("Error parsing .slp file:", slpPath, error);
 		return null;
 	}
 }
 
-/** Cache for parsed .slp files to avoid expensive re-parsing */
-const slippiCache = new Map<string, SlippiMetadata | null>();
+/**
+ * Extract metadata from an already-loaded replay.
+ *
+ * @param game - Game loaded from the .slp file
+ * @param slpPath - Path to the .slp file (for logging)
+ * @returns SlippiMetadata, or null if the replay has no settings
+ */
+export function buildSlippiMetadata(game: SlippiGame, slpPath: string): SlippiMetadata | null {
+	const settings = game.getSettings();
+	const metadata = game.getMetadata();
+
+	if (!settings) {
+		console.warn("No settings found in .slp file:", slpPath);
+		return null;
+	}
+
+	// Extract player information (using snake_case to match Rust backend)
+	const players: SlippiPlayer[] = settings.players
+		.filter((p) => p !== null) // Filter out null players
+		.map((player) => ({
+			character_id: player.characterId as CharacterId,
+			character_color: player.characterColor || 0,
+			player_tag: player.nametag || `Player ${player.playerIndex + 1}`,
+			port: player.port,
+		}));
+
+	// Get all characters played
+	const characters = players.map((p) => p.character_id);
+
+	// Calculate game duration from metadata or frames
+	const lastFrame = metadata?.lastFrame || 0;
+	const gameDuration = lastFrame > 0 ? lastFrame : 0;
+
+	// Determine winner (if game ended normally)
+	const gameEnd = game.getGameEnd();
+	const winnerPort = gameEnd?.gameEndMethod === 2 ? gameEnd.lrasInitiatorIndex : null;
+
+	return {
+		characters,
+		stage: settings.stageId as StageId,
+		players,
+		game_duration: gameDuration,
+		start_time: metadata?.startAt || new Date().toISOString(),
+		is_pal: settings.isPAL || false,
+		winner_port: winnerPort !== null ? winnerPort + 1 : null, // Convert to 1-based port
+		played_on: metadata?.playedOn || null,
+		total_frames: lastFrame,
+	};
+}
+
+/** Most replays the parsing cache holds */
+const CACHE_MAX_ENTRIES = 256;
+/** Roughly how much metadata the parsing cache holds, in bytes */
+const CACHE_MAX_BYTES = 32 * 1024 * 1024;
+
+/**
+ * Cache for parsed .slp files to avoid expensive re-parsing. A Map keeps
+ * insertion order, so re-inserting on every hit makes its first key the least
+ * recently used one.
+ */
+const slippiCache = new Map<string, { metadata: SlippiMetadata | null; bytes: number }>();
+let slippiCacheBytes = 0;
+const slippiCacheCounts = { hits: 0, misses: 0 };
+
+/** One key per file however its path is spelled (separators, repeated slashes) */
+function cacheKey(slpPath: string): string {
+	return slpPath.replace(/\\/g, "/").replace(/\/{2,}/g, "/");
+}
+
+/** Drop least recently used entries until the cache is within its bounds */
+function evictToBounds(): void {
+	for (const [key, entry] of slippiCache) {
+		if (slippiCache.size <= CACHE_MAX_ENTRIES && slippiCacheBytes <= CACHE_MAX_BYTES) break;
+		slippiCache.delete(key);
+		slippiCacheBytes -= entry.bytes;
+	}
+}
 
 /**
  * Parse a .slp file with caching.
@@ -98,21 +133,41 @@ const slippiCache = new Map<string, SlippiMetadata | null>();
 export async function parseSlippiFileWithCache(
 	slpPath: string
 ): Promise<SlippiMetadata | null> {
-	if (slippiCache.has(slpPath)) {
-		return slippiCache.get(slpPath) || null;
+	const key = cacheKey(slpPath);
+	const cached = slippiCache.get(key);
+	if (cached) {
+		slippiCacheCounts.hits++;
+		slippiCache.delete(key);
+		slippiCache.set(key, cached);
+		return cached.metadata;
 	}
 
+	slippiCacheCounts.misses++;
 	const metadata = await parseSlippiFile(slpPath);
-	slippiCache.set(slpPath, metadata);
+	// Strings are UTF-16, two bytes a character
+	const bytes = JSON.stringify(metadata).length * 2;
+	const previous = slippiCache.get(key);
+	if (previous) slippiCacheBytes -= previous.bytes;
+	slippiCache.set(key, { metadata, bytes });
+	slippiCacheBytes += bytes;
+	evictToBounds();
 	return metadata;
 }
 
+/**
+ * Hits, misses and size of the Slippi parsing cache.
+ */
+export function getSlippiCacheStats(): { hits: number; misses: number; entries: number; bytes: number } {
+	return { ...slippiCacheCounts, entries: slippiCache.size, bytes: slippiCacheBytes };
+}
+
 /**
  * Clear all entries from the Slippi parsing cache.
  * Call this when you need to force re-parsing of all files.
  */
 export function clearSlippiCache(): void {
 	slippiCache.clear();
+	slippiCacheBytes = 0;
 }
 
 /**
@@ -121,6 +176,24 @@ export function clearSlippiCache(): void {
  * @param slpPath - Path to remove from cache
  */
 export function removeFromCache(slpPath: string): void {
-	slippiCache.delete(slpPath);
+	const key = cacheKey(slpPath);
+	const entry = slippiCache.get(key);
+	if (!entry) return;
+	slippiCache.delete(key);
+	slippiCacheBytes -= entry.bytes;
 }
 
+/**
+ * Drop cached entries whose file no longer exists.
+ * @returns How many entries were dropped
+ */
+export async function pruneMissingFromCache(): Promise<number> {
+	let pruned = 0;
+	for (const key of [...slippiCache.keys()]) {
+		if (!(await exists(key).catch(() => false))) {
+			removeFromCache(key);
+			pruned++;
+		}
+	}
+	return pruned;
+}
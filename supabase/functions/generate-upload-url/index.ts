@@ -63,7 +63,29 @@ Deno.serve(async (req) => {
       })
     }
 
-    const { fileName, fileSize } = await req.json()
+    const { fileName, fileSize, recordingId } = await req.json()
+
+    // Recording ids are derived from the game, so another device uploading the
+    // same game finds the existing upload instead of creating a second one
+    if (recordingId) {
+      const { data: existing } = await supabase
+        .from('uploads')
+        .select('*')
+        .eq('user_id', user.id)
+        .eq('recording_id', recordingId)
+        .neq('status', 'FAILED')
+        .maybeSingle()
+
+      if (existing) {
+        return new Response(JSON.stringify({ alreadyExists: true, upload: existing }), {
+          status: 200,
+          headers: {
+            'Content-Type': 'application/json',
+            'Access-Control-Allow-Origin': '*',
+          }
+        })
+      }
+    }
     
     if (profile.storage_used + fileSize > profile.storage_limit) {
       return new Response(JSON.stringify({ error: 'Quota exceeded' }), {
@@ -104,6 +126,7 @@ Deno.serve(async (req) => {
         filename: fileName,
         b2_file_name: key,
         file_size: fileSize,
+        recording_id: recordingId ?? null,
         status: 'UPLOADING',
       })
       .select()
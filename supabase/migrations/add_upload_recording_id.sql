@@ -0,0 +1,8 @@
+-- Link uploads to the app's recording id, which is derived from the game
+ALTER TABLE uploads ADD COLUMN IF NOT EXISTS recording_id TEXT;
+
+-- One upload per game per user, whichever device uploads it first
+-- (failed uploads don't count, so they can be retried)
+CREATE UNIQUE INDEX IF NOT EXISTS idx_uploads_user_recording
+  ON uploads(user_id, recording_id)
+  WHERE recording_id IS NOT NULL AND status <> 'FAILED';